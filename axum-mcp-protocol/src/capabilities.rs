@@ -1,8 +1,10 @@
 //! MCP capability definitions and management
 
+use alloc::{string::String, vec};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+
+use crate::Map;
 
 pub use super::messages::{
     ClientCapabilities, LoggingCapability, PromptsCapability, ResourcesCapability,
@@ -60,7 +62,7 @@ impl McpCapabilities {
 impl Default for ClientCapabilities {
     fn default() -> Self {
         Self {
-            experimental: HashMap::new(),
+            experimental: Map::new(),
             sampling: Some(SamplingCapability {}),
         }
     }
@@ -104,7 +106,7 @@ impl ClientCapabilities {
 impl Default for ServerCapabilities {
     fn default() -> Self {
         Self {
-            experimental: HashMap::new(),
+            experimental: Map::new(),
             logging: Some(LoggingCapability {}),
             prompts: Some(PromptsCapability {
                 list_changed: false,
@@ -116,15 +118,15 @@ impl Default for ServerCapabilities {
             tools: Some(ToolsCapability {
                 list_changed: false,
             }),
-            batch: Some(crate::protocol::BatchCapability {
+            batch: Some(crate::BatchCapability {
                 max_batch_size: 50,
                 max_parallel: 5,
                 supports_dependencies: true,
                 supports_progress: true,
                 supported_execution_modes: vec![
-                    crate::protocol::BatchExecutionMode::Parallel,
-                    crate::protocol::BatchExecutionMode::Sequential,
-                    crate::protocol::BatchExecutionMode::Dependency,
+                    crate::BatchExecutionMode::Parallel,
+                    crate::BatchExecutionMode::Sequential,
+                    crate::BatchExecutionMode::Dependency,
                 ],
             }),
         }
@@ -1,8 +1,12 @@
 //! JSON-RPC 2.0 implementation for MCP
 
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::fmt;
 
 /// JSON-RPC 2.0 version string
 pub const JSONRPC_VERSION: &str = "2.0";
@@ -197,7 +201,7 @@ impl fmt::Display for JsonRpcError {
     }
 }
 
-impl std::error::Error for JsonRpcError {}
+impl core::error::Error for JsonRpcError {}
 
 /// Standard JSON-RPC 2.0 error codes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,190 @@
+//! MCP protocol implementation including JSON-RPC 2.0 and MCP-specific message types
+//!
+//! These are the message types `axum-mcp` speaks, split out so clients that
+//! cannot take the server's dependencies, such as firmware or wasm builds,
+//! can reuse them. The crate depends only on `serde` and `serde_json`.
+//!
+//! Without the default `std` feature the crate is `no_std` and needs only
+//! `alloc`. Maps in messages ([`Map`]) are `BTreeMap` either way, so enabling
+//! `std` anywhere in a dependency graph does not change the public types.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod capabilities;
+pub mod jsonrpc;
+pub mod messages;
+
+pub use capabilities::{ClientCapabilities, McpCapabilities, ServerCapabilities, ToolsCapability};
+pub use jsonrpc::{JsonRpcError, JsonRpcErrorCode, JsonRpcRequest, JsonRpcResponse};
+pub use messages::{
+    BatchCapability, BatchExecutionMode, BatchItemResult, BatchParams, BatchProgressNotification,
+    BatchRequest, BatchResult, BatchStats, CancelledNotification, ClientInfo, CompleteResult,
+    CreateMessageResult, EmptyResult, InitializeParams, InitializeResult, LogLevel,
+    LoggingMessageNotification, McpMessage, McpMethod, McpNotification, McpRequest, McpResponse,
+    PingResult, ProgressNotification, PromptsListResult, ResourceUpdatedNotification,
+    ResourcesListParams, ResourcesListResult, ResourcesReadParams, ResourcesReadResult,
+    ResourcesSubscribeParams, ResourcesUnsubscribeParams, ServerInfo, Tool, ToolContent,
+    ToolsCallParams, ToolsCallResult, ToolsListParams, ToolsListResult,
+};
+
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+/// Map used for metadata and capability fields, the same with and without `std`
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// MCP protocol version (server default)
+pub const MCP_PROTOCOL_VERSION: &str = "0.1.0";
+
+/// Supported MCP protocol versions
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[
+    "2024-11-05", // Claude Code primary version
+    "2025-03-26", // Claude Code latest version
+    "0.1.0",      // MCP standard version
+    "1.0.0",      // Future version compatibility
+];
+
+/// Validate MCP protocol version
+pub fn validate_protocol_version(version: &str) -> bool {
+    SUPPORTED_PROTOCOL_VERSIONS.contains(&version)
+}
+
+/// Get the best supported protocol version for negotiation
+pub fn get_protocol_version_for_client(client_version: &str) -> String {
+    if SUPPORTED_PROTOCOL_VERSIONS.contains(&client_version) {
+        client_version.to_string()
+    } else {
+        // For Claude compatibility, prefer Claude's version format if possible
+        if client_version.starts_with("2024") || client_version.starts_with("2025") {
+            "2024-11-05".to_string()
+        } else {
+            MCP_PROTOCOL_VERSION.to_string()
+        }
+    }
+}
+
+/// Standard MCP methods
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StandardMethod {
+    // Lifecycle methods
+    #[serde(rename = "initialize")]
+    Initialize,
+    #[serde(rename = "initialized")]
+    Initialized,
+
+    // Ping/pong for connection health
+    #[serde(rename = "ping")]
+    Ping,
+    #[serde(rename = "pong")]
+    Pong,
+
+    // Tool methods
+    #[serde(rename = "tools/list")]
+    ToolsList,
+    #[serde(rename = "tools/call")]
+    ToolsCall,
+
+    // Batch methods
+    #[serde(rename = "batch")]
+    Batch,
+
+    // Resource methods
+    #[serde(rename = "resources/list")]
+    ResourcesList,
+    #[serde(rename = "resources/read")]
+    ResourcesRead,
+    #[serde(rename = "resources/subscribe")]
+    ResourcesSubscribe,
+    #[serde(rename = "resources/unsubscribe")]
+    ResourcesUnsubscribe,
+
+    // Prompt methods
+    #[serde(rename = "prompts/list")]
+    PromptsList,
+    #[serde(rename = "prompts/get")]
+    PromptsGet,
+
+    // Completion/sampling methods
+    #[serde(rename = "completion/complete")]
+    CompletionComplete,
+    #[serde(rename = "sampling/createMessage")]
+    SamplingCreateMessage,
+
+    // Logging methods
+    #[serde(rename = "logging/setLevel")]
+    LoggingSetLevel,
+
+    // Notification methods
+    #[serde(rename = "notifications/initialized")]
+    NotificationsInitialized,
+    #[serde(rename = "notifications/cancelled")]
+    NotificationsCancelled,
+    #[serde(rename = "notifications/progress")]
+    NotificationsProgress,
+    #[serde(rename = "notifications/message")]
+    NotificationsMessage,
+    #[serde(rename = "notifications/resources/updated")]
+    NotificationsResourcesUpdated,
+    #[serde(rename = "notifications/resources/list_changed")]
+    NotificationsResourcesListChanged,
+    #[serde(rename = "notifications/tools/list_changed")]
+    NotificationsToolsListChanged,
+    #[serde(rename = "notifications/batch_progress")]
+    NotificationsBatchProgress,
+}
+
+impl StandardMethod {
+    /// Wire names of all standard methods
+    pub const NAMES: &'static [&'static str] = &[
+        "initialize",
+        "initialized",
+        "ping",
+        "pong",
+        "tools/list",
+        "tools/call",
+        "batch",
+        "resources/list",
+        "resources/read",
+        "resources/subscribe",
+        "resources/unsubscribe",
+        "prompts/list",
+        "prompts/get",
+        "completion/complete",
+        "sampling/createMessage",
+        "logging/setLevel",
+        "notifications/initialized",
+        "notifications/cancelled",
+        "notifications/progress",
+        "notifications/message",
+        "notifications/resources/updated",
+        "notifications/resources/list_changed",
+        "notifications/tools/list_changed",
+        "notifications/batch_progress",
+    ];
+
+    /// Check if this method requires initialization
+    pub fn requires_initialization(&self) -> bool {
+        !matches!(
+            self,
+            StandardMethod::Initialize | StandardMethod::Initialized
+        )
+    }
+
+    /// Check if this method is a notification (no response expected)
+    pub fn is_notification(&self) -> bool {
+        matches!(
+            self,
+            StandardMethod::Initialized
+                | StandardMethod::NotificationsInitialized
+                | StandardMethod::NotificationsCancelled
+                | StandardMethod::NotificationsProgress
+                | StandardMethod::NotificationsMessage
+                | StandardMethod::NotificationsResourcesUpdated
+                | StandardMethod::NotificationsResourcesListChanged
+                | StandardMethod::NotificationsToolsListChanged
+                | StandardMethod::NotificationsBatchProgress
+        )
+    }
+}
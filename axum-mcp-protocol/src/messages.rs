@@ -1,10 +1,13 @@
 //! MCP-specific message types and protocol definitions
 
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
 
-use super::{JsonRpcRequest, JsonRpcResponse};
+use super::{JsonRpcRequest, JsonRpcResponse, Map};
 
 /// Top-level MCP message type that wraps JSON-RPC messages
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -70,6 +73,14 @@ pub enum McpMethod {
     #[serde(rename = "resources/read")]
     ResourcesRead(ResourcesReadParams),
 
+    /// Subscribe to resource updates
+    #[serde(rename = "resources/subscribe")]
+    ResourcesSubscribe(ResourcesSubscribeParams),
+
+    /// Unsubscribe from resource updates
+    #[serde(rename = "resources/unsubscribe")]
+    ResourcesUnsubscribe(ResourcesUnsubscribeParams),
+
     /// List available prompts
     #[serde(rename = "prompts/list")]
     PromptsList(Option<PromptsListParams>),
@@ -90,10 +101,38 @@ pub enum McpMethod {
     #[serde(rename = "logging/setLevel")]
     LoggingSetLevel(LoggingSetLevelParams),
 
+    /// Initialize complete notification (current spec name)
+    #[serde(rename = "notifications/initialized")]
+    NotificationsInitialized,
+
+    /// Request cancellation notification
+    #[serde(rename = "notifications/cancelled")]
+    NotificationsCancelled(CancelledNotification),
+
     /// Progress notification
     #[serde(rename = "notifications/progress")]
     NotificationsProgress(ProgressNotification),
 
+    /// Log message notification
+    #[serde(rename = "notifications/message")]
+    NotificationsMessage(LoggingMessageNotification),
+
+    /// Resource updated notification
+    #[serde(rename = "notifications/resources/updated")]
+    NotificationsResourcesUpdated(ResourceUpdatedNotification),
+
+    /// Resource list changed notification
+    #[serde(rename = "notifications/resources/list_changed")]
+    NotificationsResourcesListChanged,
+
+    /// Tool list changed notification
+    #[serde(rename = "notifications/tools/list_changed")]
+    NotificationsToolsListChanged,
+
+    /// Prompt list changed notification
+    #[serde(rename = "notifications/prompts/list_changed")]
+    NotificationsPromptsListChanged,
+
     /// Task execution progress notification
     #[serde(rename = "notifications/task_progress")]
     NotificationsTaskProgress(TaskProgressNotification),
@@ -139,6 +178,16 @@ pub struct McpNotification {
     pub method: McpMethod,
 }
 
+impl McpNotification {
+    /// Create a JSON-RPC 2.0 notification for a method
+    pub fn new(method: McpMethod) -> Self {
+        Self {
+            jsonrpc: super::jsonrpc::JSONRPC_VERSION.to_string(),
+            method,
+        }
+    }
+}
+
 // === Initialize Protocol ===
 
 /// Parameters for the initialize method
@@ -182,7 +231,7 @@ pub struct ClientInfo {
 
     /// Additional client metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Server information
@@ -196,9 +245,32 @@ pub struct ServerInfo {
 
     /// Additional server metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
+// === Ping Protocol ===
+
+/// Result of the ping method
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PingResult {
+    /// Status marker (`"pong"`), kept for clients that check it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+impl PingResult {
+    /// The `{"status": "pong"}` result this server answers with
+    pub fn pong() -> Self {
+        Self {
+            status: Some("pong".to_string()),
+        }
+    }
+}
+
+/// Empty result for methods that only acknowledge (e.g. resources/subscribe)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmptyResult {}
+
 // === Tool Protocol ===
 
 /// Parameters for tools/list method
@@ -235,7 +307,7 @@ pub struct Tool {
 
     /// Additional tool metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Parameters for tools/call method
@@ -261,7 +333,7 @@ pub struct ToolsCallResult {
 
     /// Additional metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Content returned by tool execution
@@ -326,7 +398,7 @@ pub struct Resource {
 
     /// Additional metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Resource reference
@@ -341,13 +413,38 @@ pub struct ResourceReference {
 pub struct ResourcesReadParams {
     /// Resource URI to read
     pub uri: String,
+
+    /// Acceptable formats, such as `text/html, text/markdown;q=0.5`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+
+    /// Entity tags of copies the client holds, as in HTTP's `If-None-Match`
+    #[serde(
+        default,
+        rename = "ifNoneMatch",
+        alias = "if_none_match",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub if_none_match: Option<String>,
 }
 
 /// Result of resources/read method
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ResourcesReadResult {
-    /// Resource contents
+    /// Resource contents, empty when not modified
     pub contents: Vec<ResourceContent>,
+
+    /// Whether the resource matched `ifNoneMatch` and its contents were left out
+    #[serde(
+        default,
+        rename = "notModified",
+        skip_serializing_if = "core::ops::Not::not"
+    )]
+    pub not_modified: bool,
+
+    /// Result metadata, such as the entity tag or reductions of oversized content
+    #[serde(default, rename = "_meta", skip_serializing_if = "Map::is_empty")]
+    pub meta: Map<String, Value>,
 }
 
 /// Resource content
@@ -371,6 +468,27 @@ pub enum ResourceContent {
     },
 }
 
+/// Parameters for resources/subscribe method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourcesSubscribeParams {
+    /// URI of the resource to watch
+    pub uri: String,
+}
+
+/// Parameters for resources/unsubscribe method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourcesUnsubscribeParams {
+    /// URI of the resource to stop watching
+    pub uri: String,
+}
+
+/// Resource updated notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUpdatedNotification {
+    /// URI of the resource that changed
+    pub uri: String,
+}
+
 // === Prompt Protocol ===
 
 /// Parameters for prompts/list method
@@ -381,6 +499,55 @@ pub struct PromptsListParams {
     pub cursor: Option<String>,
 }
 
+/// Result of prompts/list method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptsListResult {
+    /// Available prompts
+    pub prompts: Vec<Prompt>,
+
+    /// Cursor for next page
+    #[serde(skip_serializing_if = "Option::is_none", rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+/// Prompt definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prompt {
+    /// Prompt name
+    pub name: String,
+
+    /// Prompt description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Arguments the prompt accepts
+    #[serde(default)]
+    pub arguments: Vec<PromptArgument>,
+
+    /// Additional prompt metadata
+    #[serde(flatten)]
+    pub metadata: Map<String, Value>,
+}
+
+/// Argument accepted by a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptArgument {
+    /// Argument name
+    pub name: String,
+
+    /// Argument description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Whether the argument must be provided
+    #[serde(default)]
+    pub required: bool,
+
+    /// JSON schema for the argument value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
 /// Parameters for prompts/get method
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PromptsGetParams {
@@ -389,7 +556,7 @@ pub struct PromptsGetParams {
 
     /// Prompt arguments
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub arguments: Option<HashMap<String, Value>>,
+    pub arguments: Option<Map<String, Value>>,
 }
 
 // === Completion Protocol ===
@@ -401,9 +568,32 @@ pub struct CompletionParams {
     pub argument: CompletionArgument,
 
     /// Reference to the resource being completed
+    #[serde(rename = "ref")]
     pub ref_: CompletionReference,
 }
 
+/// Result of completion/complete method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompleteResult {
+    /// Completion values
+    pub completion: Completion,
+}
+
+/// Completion values for an argument
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    /// Suggested values (at most 100)
+    pub values: Vec<String>,
+
+    /// Total number of available values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+
+    /// Whether more values exist beyond `values`
+    #[serde(skip_serializing_if = "Option::is_none", rename = "hasMore")]
+    pub has_more: Option<bool>,
+}
+
 /// Completion argument
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CompletionArgument {
@@ -459,7 +649,24 @@ pub struct SamplingParams {
 
     /// Additional metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
+}
+
+/// Result of sampling/createMessage method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateMessageResult {
+    /// Role of the generated message
+    pub role: MessageRole,
+
+    /// Generated content
+    pub content: MessageContent,
+
+    /// Model that generated the message
+    pub model: String,
+
+    /// Why generation stopped (e.g. `endTurn`, `maxTokens`)
+    #[serde(skip_serializing_if = "Option::is_none", rename = "stopReason")]
+    pub stop_reason: Option<String>,
 }
 
 /// Sampling message
@@ -542,7 +749,7 @@ pub struct ModelHint {
 
 /// Include context options
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "camelCase")]
 pub enum IncludeContext {
     None,
     ThisServer,
@@ -572,8 +779,34 @@ pub enum LogLevel {
     Emergency,
 }
 
+/// Log message notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingMessageNotification {
+    /// Severity of the message
+    pub level: LogLevel,
+
+    /// Name of the logger that emitted the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+
+    /// Message payload
+    pub data: Value,
+}
+
 // === Notification Protocol ===
 
+/// Request cancellation notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CancelledNotification {
+    /// ID of the request to cancel
+    #[serde(rename = "requestId")]
+    pub request_id: Value,
+
+    /// Why the request was cancelled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
 /// Progress notification
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ProgressNotification {
@@ -581,12 +814,16 @@ pub struct ProgressNotification {
     #[serde(rename = "progressToken")]
     pub progress_token: Value,
 
-    /// Progress value (0.0 to 1.0)
-    pub progress: f32,
+    /// Progress so far, increasing with every notification
+    pub progress: f64,
 
     /// Total work units
     #[serde(skip_serializing_if = "Option::is_none")]
     pub total: Option<u64>,
+
+    /// Description of the current step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 /// Task execution progress notification
@@ -634,7 +871,7 @@ pub struct TaskProgressNotification {
 pub struct ClientCapabilities {
     /// Experimental capabilities
     #[serde(default)]
-    pub experimental: HashMap<String, Value>,
+    pub experimental: Map<String, Value>,
 
     /// Sampling capability
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -646,7 +883,7 @@ pub struct ClientCapabilities {
 pub struct ServerCapabilities {
     /// Experimental capabilities
     #[serde(default)]
-    pub experimental: HashMap<String, Value>,
+    pub experimental: Map<String, Value>,
 
     /// Logging capability
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -759,7 +996,7 @@ pub struct BatchParams {
 
     /// Additional batch metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Individual request within a batch
@@ -787,9 +1024,13 @@ pub struct BatchRequest {
     #[serde(default)]
     pub priority: i32,
 
+    /// Principal to run this request on behalf of, subject to delegation checks
+    #[serde(skip_serializing_if = "Option::is_none", rename = "onBehalfOf")]
+    pub on_behalf_of: Option<String>,
+
     /// Additional request metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Batch execution mode
@@ -823,7 +1064,7 @@ pub struct BatchResult {
 
     /// Additional result metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Result for an individual request in a batch
@@ -850,7 +1091,7 @@ pub struct BatchItemResult {
 
     /// Additional item metadata
     #[serde(flatten)]
-    pub metadata: HashMap<String, Value>,
+    pub metadata: Map<String, Value>,
 }
 
 /// Batch execution statistics
@@ -911,3 +1152,70 @@ pub struct BatchProgressNotification {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_spec_shaped_messages() {
+        let params: CompletionParams = serde_json::from_value(json!({
+            "ref": {"type": "ref/prompt", "name": "review"},
+            "argument": {"name": "language", "value": "ru"}
+        }))
+        .unwrap();
+        assert_eq!(
+            params.ref_,
+            CompletionReference::Prompt {
+                name: "review".to_string()
+            }
+        );
+
+        let include: IncludeContext = serde_json::from_value(json!("thisServer")).unwrap();
+        assert_eq!(include, IncludeContext::ThisServer);
+
+        let result = CompleteResult {
+            completion: Completion {
+                values: vec!["rust".to_string()],
+                total: Some(1),
+                has_more: Some(false),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(result).unwrap(),
+            json!({"completion": {"values": ["rust"], "total": 1, "hasMore": false}})
+        );
+    }
+
+    #[test]
+    fn test_notification_serialization() {
+        let notification = McpNotification::new(McpMethod::NotificationsMessage(
+            LoggingMessageNotification {
+                level: LogLevel::Warning,
+                logger: Some("db".to_string()),
+                data: json!("slow query"),
+            },
+        ));
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/message",
+                "params": {"level": "warning", "logger": "db", "data": "slow query"}
+            })
+        );
+
+        let cancelled: McpNotification = serde_json::from_value(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/cancelled",
+            "params": {"requestId": 7, "reason": "user aborted"}
+        }))
+        .unwrap();
+        assert!(matches!(
+            cancelled.method,
+            McpMethod::NotificationsCancelled(CancelledNotification { ref reason, .. })
+                if reason.as_deref() == Some("user aborted")
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+//! Allocations and time per SSE event under notification fan-out
+//!
+//! Encodes the same notification for many subscribers, once through
+//! `axum::response::sse::Event` (a JSON string plus the event's own buffer)
+//! and once through one `SseEncoder` per subscriber, which reuses its buffer.
+//!
+//! Run with: cargo bench --bench sse_encoding
+
+use axum::response::sse::Event;
+use axum_mcp::server::SseEncoder;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+/// Counts allocations made through the global allocator
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const SUBSCRIBERS: usize = 1_000;
+const NOTIFICATIONS: usize = 200;
+
+fn measure(name: &str, mut encode: impl FnMut(usize, usize)) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    for notification in 0..NOTIFICATIONS {
+        for subscriber in 0..SUBSCRIBERS {
+            encode(notification, subscriber);
+        }
+    }
+    let elapsed = started.elapsed();
+    let events = (NOTIFICATIONS * SUBSCRIBERS) as f64;
+    println!(
+        "{:<12} {:>8.2} allocations/event {:>8.1} ns/event",
+        name,
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / events,
+        elapsed.as_nanos() as f64 / events,
+    );
+}
+
+fn main() {
+    let notification = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/updated",
+        "params": {"uri": "files://reports/2024/q3.csv", "title": "Quarterly report"}
+    });
+    let ids: Vec<String> = (0..NOTIFICATIONS).map(|i| i.to_string()).collect();
+
+    println!(
+        "{} notifications fanned out to {} subscribers",
+        NOTIFICATIONS, SUBSCRIBERS
+    );
+
+    measure("axum Event", |notification_index, _| {
+        let event = Event::default()
+            .id(&ids[notification_index])
+            .event("notification")
+            .data(serde_json::to_string(&notification).unwrap());
+        drop(black_box(event));
+    });
+
+    let mut encoders: Vec<SseEncoder> = (0..SUBSCRIBERS).map(|_| SseEncoder::new()).collect();
+    measure("SseEncoder", |notification_index, subscriber| {
+        // The transport drops each event once written
+        let event = encoders[subscriber].json_event(
+            Some(&ids[notification_index]),
+            Some("notification"),
+            &notification,
+        );
+        drop(black_box(event));
+    });
+}
@@ -7,38 +7,21 @@
 use axum_mcp::{
     axum_integration::{mcp_routes_with_wrapper, McpServerWrapper},
     prelude::*,
+    security::AnonymousAuth,
     server::{config::McpServerConfig, service::McpServer},
 };
-use std::collections::HashMap;
 use tokio::net::TcpListener;
 
 // Define a simple server state
 #[derive(Clone)]
 struct SimpleServerState {
     tools: InMemoryToolRegistry,
-    auth: SimpleAuth,
-}
-
-// Simple authentication that allows everything
-#[derive(Clone)]
-struct SimpleAuth;
-
-#[async_trait]
-impl McpAuth for SimpleAuth {
-    async fn authenticate(&self, _client_info: &ClientContext) -> McpResult<SecurityContext> {
-        // For this example, all clients get full access
-        Ok(SecurityContext::system())
-    }
-
-    async fn authorize(&self, _context: &SecurityContext, _resource: &str, _action: &str) -> bool {
-        // Allow all operations
-        true
-    }
+    auth: AnonymousAuth,
 }
 
 impl McpServerState for SimpleServerState {
     type ToolRegistry = InMemoryToolRegistry;
-    type AuthManager = SimpleAuth;
+    type AuthManager = AnonymousAuth;
 
     fn tool_registry(&self) -> &Self::ToolRegistry {
         &self.tools
@@ -52,12 +35,13 @@ impl McpServerState for SimpleServerState {
         axum_mcp::protocol::ServerInfo {
             name: "Minimal MCP Server".to_string(),
             version: "1.0.0".to_string(),
-            metadata: std::collections::HashMap::new(),
+            metadata: Default::default(),
         }
     }
 }
 
 // Custom tool registry with a simple echo tool
+#[allow(dead_code)] // Shown for reference; the server below uses InMemoryToolRegistry
 struct EchoToolRegistry;
 
 #[async_trait]
@@ -76,7 +60,7 @@ impl ToolRegistry for EchoToolRegistry {
                 },
                 "required": ["message"]
             }),
-            metadata: HashMap::new(),
+            metadata: Default::default(),
         }])
     }
 
@@ -115,7 +99,7 @@ impl ToolRegistry for EchoToolRegistry {
                             text: format!("Echo: {}", message),
                         }],
                         is_error: false,
-                        metadata: std::collections::HashMap::new(),
+                        metadata: Default::default(),
                     });
                 }
             }
@@ -169,7 +153,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create server state
     let state = SimpleServerState {
         tools,
-        auth: SimpleAuth,
+        // Clients need no credentials and get a rate limited guest context
+        auth: AnonymousAuth::new(),
     };
 
     // Create MCP server
@@ -181,7 +166,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create Axum app with MCP routes
     let app = axum::Router::new()
         .merge(mcp_routes_with_wrapper())
-        .with_state(server_wrapper);
+        .with_state(server_wrapper.clone());
 
     // Start the server
     println!("Starting MCP server on http://0.0.0.0:3000");
@@ -197,6 +182,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("    -d '{{\"jsonrpc\":\"2.0\",\"method\":\"tools/list\",\"id\":1}}'");
 
     let listener = TcpListener::bind("0.0.0.0:3000").await?;
+    server_wrapper
+        .server()
+        .webhooks()
+        .server_started(listener.local_addr()?.to_string());
     axum::serve(listener, app).await?;
 
     Ok(())
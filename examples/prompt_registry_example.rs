@@ -68,7 +68,7 @@ impl McpServerState for AIWorkflowServerState {
         ServerInfo {
             name: "AI Workflow Server".to_string(),
             version: "1.0.0".to_string(),
-            metadata: std::collections::HashMap::new(),
+            metadata: Default::default(),
         }
     }
 }
@@ -0,0 +1,32 @@
+//! Feed arbitrary JSON-RPC requests through `McpServer::handle_request`
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use axum_mcp::{protocol::JsonRpcRequest, security::SecurityContext, server::McpServer};
+use axum_mcp_fuzz::{assert_valid_response, server, FuzzState};
+use libfuzzer_sys::fuzz_target;
+
+fn fixture() -> &'static (tokio::runtime::Runtime, McpServer<FuzzState>) {
+    static FIXTURE: OnceLock<(tokio::runtime::Runtime, McpServer<FuzzState>)> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        (runtime, server())
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(request) = serde_json::from_slice::<JsonRpcRequest>(data) else {
+        return;
+    };
+    let (runtime, server) = fixture();
+
+    let id = request.id.clone();
+    let response = runtime.block_on(server.handle_request(request, SecurityContext::system()));
+    assert_eq!(response.id, id, "response must echo the request ID");
+    assert_valid_response(&response);
+});
@@ -0,0 +1,51 @@
+//! Feed arbitrary bodies to `POST /mcp` through the axum handlers
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use axum::{body::Body, http::Request, Router};
+use axum_mcp::protocol::JsonRpcResponse;
+use axum_mcp_fuzz::{assert_valid_response, router};
+use libfuzzer_sys::fuzz_target;
+use tower::ServiceExt;
+
+fn fixture() -> &'static (tokio::runtime::Runtime, Router) {
+    static FIXTURE: OnceLock<(tokio::runtime::Runtime, Router)> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        (runtime, router())
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (runtime, router) = fixture();
+    let request = Request::post("/mcp")
+        .header("content-type", "application/json")
+        .body(Body::from(data.to_vec()))
+        .unwrap();
+
+    runtime.block_on(async {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        assert!(
+            status.is_success() || status.as_u16() == 400,
+            "unexpected status {}",
+            status
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: JsonRpcResponse =
+            serde_json::from_slice(&body).expect("every answer is a JSON-RPC response");
+        assert_valid_response(&response);
+        if status.as_u16() == 400 {
+            let code = response.error.as_ref().map(|error| error.code);
+            assert!(matches!(code, Some(-32700) | Some(-32600)));
+        }
+    });
+});
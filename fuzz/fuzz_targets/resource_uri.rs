@@ -0,0 +1,42 @@
+//! Feed pathological URIs to URI parsing and `resources/read`
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use axum_mcp::{
+    protocol::JsonRpcRequest,
+    security::SecurityContext,
+    server::{McpServer, UriSchemeConfig},
+};
+use axum_mcp_fuzz::{assert_valid_response, server, FuzzState};
+use libfuzzer_sys::fuzz_target;
+
+fn fixture() -> &'static (tokio::runtime::Runtime, McpServer<FuzzState>) {
+    static FIXTURE: OnceLock<(tokio::runtime::Runtime, McpServer<FuzzState>)> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        (runtime, server())
+    })
+}
+
+fuzz_target!(|uri: &str| {
+    let scheme = UriSchemeConfig::new("fuzz", "Fuzz resources");
+    if let Ok(parsed) = scheme.parse_uri(uri) {
+        assert_eq!(parsed.scheme, "fuzz");
+        let _ = parsed.path_segments();
+        let _ = parsed.query_params();
+    }
+
+    let (runtime, server) = fixture();
+    let request = JsonRpcRequest::new(
+        "resources/read",
+        Some(serde_json::json!({ "uri": uri })),
+        Some(serde_json::json!(1)),
+    );
+    let response = runtime.block_on(server.handle_request(request, SecurityContext::system()));
+    assert_valid_response(&response);
+});
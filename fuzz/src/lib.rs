@@ -0,0 +1,144 @@
+//! Shared fixtures for the axum-mcp fuzz targets
+//!
+//! Every target runs against the same small server: batch enabled, one tool
+//! that echoes its arguments and an in-memory `fuzz://` resource registry.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use axum_mcp::{
+    axum_integration::McpServerWrapper,
+    error::{McpError, McpResult},
+    protocol::{JsonRpcResponse, Tool, ToolContent, ToolsCallResult},
+    security::{ClientContext, McpAuth, SecurityContext},
+    server::{
+        InMemoryResourceRegistry, McpServer, McpServerConfig, McpServerState, McpTool,
+        ResourceRegistry, ToolExecutionContext, ToolRegistry, UriSchemeConfig,
+    },
+};
+
+/// Registry with a single `echo` tool
+#[derive(Clone)]
+pub struct EchoTools;
+
+#[async_trait]
+impl ToolRegistry for EchoTools {
+    async fn list_tools(&self, _context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        Ok(vec![echo_tool().tool])
+    }
+
+    async fn get_tool(&self, name: &str, _context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        Ok((name == "echo").then(echo_tool))
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        if name != "echo" {
+            return Err(McpError::ToolNotFound {
+                name: name.to_string(),
+            });
+        }
+        Ok(ToolsCallResult {
+            content: vec![ToolContent::Text {
+                text: context.arguments.unwrap_or_default().to_string(),
+            }],
+            is_error: false,
+            metadata: HashMap::new(),
+        })
+    }
+
+    async fn can_access_tool(&self, name: &str, _context: &SecurityContext) -> bool {
+        name == "echo"
+    }
+}
+
+fn echo_tool() -> McpTool {
+    McpTool::new(
+        "echo",
+        "Echo the arguments",
+        serde_json::json!({"type": "object"}),
+        "fuzz",
+    )
+    .public()
+}
+
+/// Auth that lets every client in
+#[derive(Clone)]
+pub struct OpenAuth;
+
+#[async_trait]
+impl McpAuth for OpenAuth {
+    async fn authenticate(&self, _client: &ClientContext) -> McpResult<SecurityContext> {
+        Ok(SecurityContext::system())
+    }
+
+    async fn authorize(&self, _context: &SecurityContext, _resource: &str, _action: &str) -> bool {
+        true
+    }
+}
+
+/// Server state used by all fuzz targets
+#[derive(Clone)]
+pub struct FuzzState {
+    resources: std::sync::Arc<InMemoryResourceRegistry>,
+}
+
+impl McpServerState for FuzzState {
+    type ToolRegistry = EchoTools;
+    type AuthManager = OpenAuth;
+
+    fn tool_registry(&self) -> &Self::ToolRegistry {
+        &EchoTools
+    }
+
+    fn auth_manager(&self) -> &Self::AuthManager {
+        &OpenAuth
+    }
+
+    fn resource_registry(&self) -> Option<&dyn ResourceRegistry> {
+        Some(self.resources.as_ref())
+    }
+}
+
+/// Build the server under test
+pub fn server() -> McpServer<FuzzState> {
+    let resources = InMemoryResourceRegistry::new(UriSchemeConfig::new("fuzz", "Fuzz resources"));
+    McpServer::new(
+        McpServerConfig::default().with_batch(16),
+        FuzzState {
+            resources: std::sync::Arc::new(resources),
+        },
+    )
+}
+
+/// Build the axum router under test
+pub fn router() -> axum::Router {
+    McpServerWrapper::new(server()).into_router()
+}
+
+/// Assert that an error response uses a JSON-RPC 2.0 error code
+///
+/// Valid codes are the predefined ones (`-32700`, `-32600` to `-32603`) and
+/// the implementation-defined server error range (`-32000` to `-32099`).
+pub fn assert_valid_response(response: &JsonRpcResponse) {
+    assert_eq!(response.jsonrpc, "2.0");
+    assert!(
+        response.result.is_some() != response.error.is_some(),
+        "response must carry exactly one of result and error: {:?}",
+        response
+    );
+    if let Some(error) = &response.error {
+        let code = error.code;
+        assert!(
+            code == -32700
+                || (-32603..=-32600).contains(&code)
+                || (-32099..=-32000).contains(&code),
+            "unexpected error code {} in {:?}",
+            code,
+            response
+        );
+    }
+}
@@ -0,0 +1,80 @@
+//! `cargo mcp new`: generate a ready-to-run MCP server project
+//!
+//! ```text
+//! cargo mcp new <name> [--transport http|stdio|both] [--auth none|bearer]
+//!                      [--resources] [--prompts] [--path <axum-mcp checkout>]
+//! ```
+//!
+//! Build with: cargo install axum-mcp --features cli --bin cargo-mcp
+
+use axum_mcp::{
+    scaffold::{Scaffold, ScaffoldAuth, ScaffoldTransport},
+    McpError, McpResult,
+};
+
+const USAGE: &str = "\
+usage: cargo mcp new <name> [options]
+
+Options:
+  --transport http|stdio|both   transports to serve (default: http)
+  --auth none|bearer            client authentication (default: none)
+  --resources                   include an example resource registry
+  --prompts                     include an example prompt registry
+  --path <dir>                  depend on a local axum-mcp checkout";
+
+fn usage_error(message: impl std::fmt::Display) -> McpError {
+    McpError::Configuration {
+        message: format!("{}\n\n{}", message, USAGE),
+    }
+}
+
+fn run(args: Vec<String>) -> McpResult<()> {
+    let mut args = args.into_iter().peekable();
+    // Invoked by cargo as `cargo-mcp mcp new ...`
+    if args.peek().is_some_and(|arg| arg == "mcp") {
+        args.next();
+    }
+    if args.next().as_deref() != Some("new") {
+        return Err(usage_error("Expected the 'new' command"));
+    }
+    let name = args
+        .next()
+        .filter(|name| !name.starts_with('-'))
+        .ok_or_else(|| usage_error("Expected a project name"))?;
+
+    let mut scaffold = Scaffold::new(&name);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| usage_error(format!("Expected a value after {}", arg)))
+        };
+        scaffold = match arg.as_str() {
+            "--transport" => scaffold.with_transport(match value()?.as_str() {
+                "http" => ScaffoldTransport::Http,
+                "stdio" => ScaffoldTransport::Stdio,
+                "both" => ScaffoldTransport::Both,
+                other => return Err(usage_error(format!("Unknown transport '{}'", other))),
+            }),
+            "--auth" => scaffold.with_auth(match value()?.as_str() {
+                "none" => ScaffoldAuth::None,
+                "bearer" => ScaffoldAuth::Bearer,
+                other => return Err(usage_error(format!("Unknown authentication '{}'", other))),
+            }),
+            "--path" => scaffold.with_path_dependency(value()?),
+            "--resources" => scaffold.with_resources(true),
+            "--prompts" => scaffold.with_prompts(true),
+            other => return Err(usage_error(format!("Unknown option '{}'", other))),
+        };
+    }
+
+    scaffold.write_to(&name)?;
+    println!("Created {}; run it with: cd {} && cargo run", name, name);
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run(std::env::args().skip(1).collect()) {
+        eprintln!("error: {}", e);
+        std::process::exit(2);
+    }
+}
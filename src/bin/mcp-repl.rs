@@ -0,0 +1,471 @@
+//! Interactive MCP client for poking at a server
+//!
+//! ```text
+//! mcp-repl http://localhost:3000/mcp                 StreamableHTTP
+//! mcp-repl --header "Authorization: Bearer x" URL    with extra headers
+//! mcp-repl --sse http://localhost:3000               legacy HTTP+SSE
+//! mcp-repl -- my-server --stdio                      child process on stdio
+//! ```
+//!
+//! Connects, initializes a session and reads commands from stdin; type `help`
+//! for the list. Tool and prompt arguments can be given inline as JSON, or
+//! edited in `$VISUAL`/`$EDITOR` starting from a skeleton built from the
+//! tool's input schema.
+//!
+//! Build with: cargo build --features cli --bin mcp-repl
+
+use axum_mcp::{
+    protocol::{JsonRpcRequest, JsonRpcResponse},
+    transport::{McpTransport, SseTransport, StdioTransport},
+    McpError, McpResult,
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, io::Write, time::Duration};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Protocol version requested during `initialize`
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// Header carrying the StreamableHTTP session ID
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// How long to wait for a response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+const HELP: &str = "\
+Commands:
+  tools                     list tools
+  resources                 list resources and resource templates
+  prompts                   list prompts
+  call <tool> [json]        call a tool; without arguments, edit them in $EDITOR
+  read <uri>                read a resource
+  prompt <name> [json]      render a prompt; without arguments, edit them in $EDITOR
+  raw <method> [json]       send any request
+  ping                      check the server answers
+  help                      show this list
+  quit                      leave";
+
+/// StreamableHTTP endpoint spoken to with plain POSTs
+struct HttpClient {
+    client: reqwest::Client,
+    url: String,
+    headers: Vec<(String, String)>,
+    session_id: Option<String>,
+}
+
+impl HttpClient {
+    /// POST one message, returning the response to it if it has an ID
+    async fn post(&mut self, message: &JsonRpcRequest) -> McpResult<Option<JsonRpcResponse>> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .header("accept", "application/json, text/event-stream")
+            .json(message);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(session_id) = &self.session_id {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+
+        let response = request.send().await.map_err(network_error)?;
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            self.session_id = Some(session_id.to_string());
+        }
+        let status = response.status();
+        let is_sse = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await.map_err(network_error)?;
+        if message.id.is_none() || body.trim().is_empty() {
+            return Ok(None);
+        }
+        if !is_sse {
+            return match serde_json::from_str(&body) {
+                Ok(response) => Ok(Some(response)),
+                Err(_) => Err(McpError::Network {
+                    message: format!("HTTP {}: {}", status, body.trim()),
+                }),
+            };
+        }
+
+        // Progress and other notifications may come before the response
+        let mut answer = None;
+        for event in body.split("\n\n") {
+            let data: String = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect::<Vec<_>>()
+                .join("\n");
+            let Ok(value) = serde_json::from_str::<Value>(&data) else {
+                continue;
+            };
+            if value
+                .get("id")
+                .is_some_and(|id| message.id.as_ref() == Some(id))
+            {
+                answer = Some(serde_json::from_value(value)?);
+            } else if let Some(method) = value.get("method").and_then(Value::as_str) {
+                eprintln!(
+                    "<- {} {}",
+                    method,
+                    value.get("params").unwrap_or(&Value::Null)
+                );
+            }
+        }
+        Ok(answer)
+    }
+}
+
+/// A connection to the server under test
+enum Connection {
+    Http(HttpClient),
+    Transport(Box<dyn McpTransport>),
+}
+
+/// Client state: the connection and the next request ID
+struct Session {
+    connection: Connection,
+    next_id: u64,
+}
+
+impl Session {
+    /// Send a request and return its result, or the server's error
+    async fn request(&mut self, method: &str, params: Option<Value>) -> McpResult<Value> {
+        self.next_id += 1;
+        let request = JsonRpcRequest::new(method, params, Some(json!(self.next_id)));
+        let response = match &mut self.connection {
+            Connection::Http(http) => http.post(&request).await?.ok_or(McpError::Protocol {
+                message: "The server sent no response".to_string(),
+            })?,
+            Connection::Transport(transport) => {
+                transport.send_and_receive(request, REQUEST_TIMEOUT).await?
+            }
+        };
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(McpError::Protocol {
+                message: format!(
+                    "{} ({}){}",
+                    error.message,
+                    error.code,
+                    error
+                        .data
+                        .map(|data| format!(": {}", data))
+                        .unwrap_or_default()
+                ),
+            }),
+            (result, None) => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// Send a notification
+    async fn notify(&mut self, method: &str) -> McpResult<()> {
+        let notification = JsonRpcRequest::new(method, None, None);
+        match &mut self.connection {
+            Connection::Http(http) => http.post(&notification).await.map(|_| ()),
+            Connection::Transport(transport) => transport.send(notification).await,
+        }
+    }
+}
+
+fn network_error(error: reqwest::Error) -> McpError {
+    McpError::Network {
+        message: error.to_string(),
+    }
+}
+
+/// Connect as described by the command line
+async fn connect(args: Vec<String>) -> McpResult<Connection> {
+    let mut headers = Vec::new();
+    let mut sse = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--header" | "-H" => {
+                let header = args.next().unwrap_or_default();
+                let (name, value) = header.split_once(':').ok_or(McpError::Configuration {
+                    message: format!("Expected 'Name: value', got '{}'", header),
+                })?;
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+            "--sse" => sse = true,
+            "--" => {
+                let command = args.next().ok_or(McpError::Configuration {
+                    message: "Expected a command after --".to_string(),
+                })?;
+                let mut transport =
+                    StdioTransport::new(command, args.collect(), HashMap::new(), None)?;
+                transport.connect().await?;
+                return Ok(Connection::Transport(Box::new(transport)));
+            }
+            url if sse => {
+                let mut transport = SseTransport::new(
+                    url.to_string(),
+                    headers.into_iter().collect(),
+                    None,
+                    REQUEST_TIMEOUT,
+                    true,
+                )?;
+                transport.connect().await?;
+                return Ok(Connection::Transport(Box::new(transport)));
+            }
+            url => {
+                return Ok(Connection::Http(HttpClient {
+                    client: reqwest::Client::new(),
+                    url: url.to_string(),
+                    headers,
+                    session_id: None,
+                }))
+            }
+        }
+    }
+    Err(McpError::Configuration {
+        message: "usage: mcp-repl [--header 'Name: value']... [--sse] <url> | mcp-repl -- <command> [args...]"
+            .to_string(),
+    })
+}
+
+/// Example value for a JSON schema, for editing arguments
+fn skeleton(schema: &Value) -> Value {
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = schema.get("enum").and_then(|e| e.get(0)) {
+        return first.clone();
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => json!(""),
+        Some("integer") | Some("number") => json!(0),
+        Some("boolean") => json!(false),
+        Some("array") => json!([]),
+        Some("null") => Value::Null,
+        _ => Value::Object(
+            schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, schema)| (name.clone(), skeleton(schema)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Let the user edit `value` in their editor
+fn edit(value: &Value) -> McpResult<Value> {
+    let path = std::env::temp_dir().join(format!("mcp-repl-{}.json", std::process::id()));
+    std::fs::write(&path, serde_json::to_string_pretty(value)?)?;
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with arguments, as in `code --wait`
+    let mut words = editor.split_whitespace();
+    let status = std::process::Command::new(words.next().unwrap_or("vi"))
+        .args(words)
+        .arg(&path)
+        .status()?;
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status.success() {
+        return Err(McpError::Validation {
+            message: format!("{} exited with {}", editor, status),
+        });
+    }
+    Ok(serde_json::from_str(&edited?)?)
+}
+
+/// Arguments given inline, or edited starting from `template`
+fn arguments(inline: Option<&str>, template: impl FnOnce() -> Value) -> McpResult<Value> {
+    match inline {
+        Some(json) => Ok(serde_json::from_str(json)?),
+        None => edit(&template()),
+    }
+}
+
+/// Run one command line; returns false to quit
+async fn run(session: &mut Session, line: &str) -> McpResult<bool> {
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let (name, inline) = match rest.split_once(char::is_whitespace) {
+        Some((name, json)) => (name, Some(json.trim())),
+        None => (rest, None),
+    };
+
+    let result = match command {
+        "" => return Ok(true),
+        "quit" | "exit" => return Ok(false),
+        "help" | "?" => {
+            println!("{}", HELP);
+            return Ok(true);
+        }
+        "ping" => session.request("ping", None).await?,
+        "tools" => {
+            let tools = session.request("tools/list", None).await?;
+            for tool in tools["tools"].as_array().into_iter().flatten() {
+                println!(
+                    "{:<32} {}",
+                    tool["name"].as_str().unwrap_or_default(),
+                    tool["description"].as_str().unwrap_or_default()
+                );
+            }
+            return Ok(true);
+        }
+        "resources" => {
+            let resources = session.request("resources/list", None).await?;
+            for resource in resources["resources"].as_array().into_iter().flatten() {
+                println!(
+                    "{:<48} {}",
+                    resource["uri"].as_str().unwrap_or_default(),
+                    resource["name"].as_str().unwrap_or_default()
+                );
+            }
+            if let Ok(templates) = session.request("resources/templates/list", None).await {
+                for template in templates["resourceTemplates"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                {
+                    println!(
+                        "{:<48} {}",
+                        template["uriTemplate"].as_str().unwrap_or_default(),
+                        template["name"].as_str().unwrap_or_default()
+                    );
+                }
+            }
+            return Ok(true);
+        }
+        "prompts" => {
+            let prompts = session.request("prompts/list", None).await?;
+            for prompt in prompts["prompts"].as_array().into_iter().flatten() {
+                println!(
+                    "{:<32} {}",
+                    prompt["name"].as_str().unwrap_or_default(),
+                    prompt["description"].as_str().unwrap_or_default()
+                );
+            }
+            return Ok(true);
+        }
+        "call" if !name.is_empty() => {
+            let schema = match inline {
+                Some(_) => Value::Null,
+                None => {
+                    let tools = session.request("tools/list", None).await?;
+                    tools["tools"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .find(|tool| tool["name"] == name)
+                        .map(|tool| tool["inputSchema"].clone())
+                        .ok_or(McpError::ToolNotFound {
+                            name: name.to_string(),
+                        })?
+                }
+            };
+            let arguments = arguments(inline, || skeleton(&schema))?;
+            session
+                .request(
+                    "tools/call",
+                    Some(json!({"name": name, "arguments": arguments})),
+                )
+                .await?
+        }
+        "read" if !name.is_empty() => {
+            session
+                .request("resources/read", Some(json!({"uri": name})))
+                .await?
+        }
+        "prompt" if !name.is_empty() => {
+            let parameters = match inline {
+                Some(_) => Vec::new(),
+                None => {
+                    let prompts = session.request("prompts/list", None).await?;
+                    prompts["prompts"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .find(|prompt| prompt["name"] == name)
+                        .and_then(|prompt| prompt["arguments"].as_array().cloned())
+                        .unwrap_or_default()
+                }
+            };
+            let arguments = arguments(inline, || {
+                Value::Object(
+                    parameters
+                        .iter()
+                        .filter_map(|p| p["name"].as_str())
+                        .map(|name| (name.to_string(), json!("")))
+                        .collect(),
+                )
+            })?;
+            session
+                .request(
+                    "prompts/get",
+                    Some(json!({"name": name, "arguments": arguments})),
+                )
+                .await?
+        }
+        "raw" if !name.is_empty() => {
+            let params = inline.map(serde_json::from_str).transpose()?;
+            session.request(name, params).await?
+        }
+        _ => {
+            println!("Unknown command '{}'; type 'help' for the list", line);
+            return Ok(true);
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() -> McpResult<()> {
+    let connection = connect(std::env::args().skip(1).collect()).await?;
+    let mut session = Session {
+        connection,
+        next_id: 0,
+    };
+
+    let info = session
+        .request(
+            "initialize",
+            Some(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {"name": "mcp-repl", "version": env!("CARGO_PKG_VERSION")}
+            })),
+        )
+        .await?;
+    session.notify("notifications/initialized").await?;
+    println!(
+        "Connected to {} {} (protocol {}); type 'help' for commands",
+        info["serverInfo"]["name"].as_str().unwrap_or("server"),
+        info["serverInfo"]["version"].as_str().unwrap_or_default(),
+        info["protocolVersion"].as_str().unwrap_or("unknown"),
+    );
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        print!("mcp> ");
+        std::io::stdout().flush()?;
+        let Some(line) = lines.next_line().await? else {
+            break;
+        };
+        match run(&mut session, line.trim()).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! Embedding the server in Python and Node.js
+//!
+//! [`EmbeddedServer`] is the small surface the language bindings wrap:
+//! register tools whose logic is a callback, then run the regular axum
+//! StreamableHTTP endpoint. Everything between the socket and the callback —
+//! session handling, validation, rate limiting, quotas and the protocol
+//! itself — is the same [`McpServer`] a Rust application would use, configured
+//! through [`McpServerConfig::from_env`].
+//!
+//! The bindings themselves live in [`python`] (`python` feature, pyo3) and
+//! [`node`] (`node` feature, napi-rs). Callbacks receive the tool arguments as
+//! JSON and return either a string, a full `tools/call` result object, or any
+//! other JSON value, which is returned as text.
+
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "python")]
+pub mod python;
+
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+};
+use tokio::net::TcpListener;
+use tracing::info;
+
+use crate::{
+    axum_integration::McpServerWrapper,
+    error::{McpError, McpResult},
+    protocol::{ServerInfo, Tool, ToolContent, ToolsCallResult},
+    security::{ClientContext, McpAuth, SecurityContext},
+    server::{
+        McpServer, McpServerConfig, McpServerState, McpTool, ToolExecutionContext, ToolRegistry,
+    },
+    transport::{InMemoryEventStore, SessionManager},
+};
+
+/// Tool logic supplied by the host language
+///
+/// Called with the tool arguments; an `Err` is reported to the client as a
+/// failed tool call.
+pub type ToolCallback =
+    Arc<dyn Fn(Value) -> BoxFuture<'static, Result<Value, String>> + Send + Sync>;
+
+/// Tools backed by [`ToolCallback`]s
+#[derive(Clone, Default)]
+pub struct CallbackToolRegistry {
+    tools: Arc<RwLock<HashMap<String, (McpTool, ToolCallback)>>>,
+}
+
+impl CallbackToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a tool
+    pub fn register(&self, tool: McpTool, callback: ToolCallback) {
+        let name = tool.tool.name.clone();
+        self.tools.write().unwrap().insert(name, (tool, callback));
+    }
+}
+
+#[async_trait]
+impl ToolRegistry for CallbackToolRegistry {
+    async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        let tools = self.tools.read().unwrap();
+        let mut tools: Vec<Tool> = tools
+            .values()
+            .filter(|(tool, _)| context.exposes(tool.exposure()))
+            .map(|(tool, _)| tool.tool.clone())
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tools)
+    }
+
+    async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        Ok(self
+            .tools
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|(tool, _)| tool.clone())
+            .filter(|tool| context.exposes(tool.exposure())))
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        execution_context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        let callback = self
+            .tools
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|(_, callback)| Arc::clone(callback))
+            .ok_or_else(|| McpError::ToolNotFound {
+                name: name.to_string(),
+            })?;
+
+        let arguments = execution_context.arguments.unwrap_or(Value::Null);
+        match callback(arguments).await {
+            Ok(value) => Ok(tool_result(value)),
+            Err(message) => Ok(ToolsCallResult {
+                content: vec![ToolContent::Text { text: message }],
+                is_error: true,
+                metadata: Default::default(),
+            }),
+        }
+    }
+
+    async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
+        self.tools
+            .read()
+            .unwrap()
+            .get(name)
+            .is_some_and(|(tool, _)| context.exposes(tool.exposure()))
+    }
+}
+
+/// Turn a callback's return value into a `tools/call` result
+///
+/// Strings become text content, objects shaped like a `tools/call` result are
+/// passed through, and anything else is returned as JSON text.
+pub fn tool_result(value: Value) -> ToolsCallResult {
+    let text = match value {
+        Value::String(text) => text,
+        Value::Object(ref object) if object.contains_key("content") => {
+            match serde_json::from_value(value.clone()) {
+                Ok(result) => return result,
+                Err(_) => value.to_string(),
+            }
+        }
+        value => value.to_string(),
+    };
+    ToolsCallResult {
+        content: vec![ToolContent::Text { text }],
+        is_error: false,
+        metadata: Default::default(),
+    }
+}
+
+/// Admits every client; access is governed by the server configuration
+#[derive(Clone, Default)]
+pub struct EmbeddedAuth;
+
+#[async_trait]
+impl McpAuth for EmbeddedAuth {
+    async fn authenticate(&self, client_info: &ClientContext) -> McpResult<SecurityContext> {
+        Ok(SecurityContext::authenticated(
+            client_info.clone(),
+            Vec::new(),
+        ))
+    }
+
+    async fn authorize(&self, _context: &SecurityContext, _resource: &str, _action: &str) -> bool {
+        true
+    }
+}
+
+/// Server state of an [`EmbeddedServer`]
+#[derive(Clone)]
+pub struct EmbeddedState {
+    tools: CallbackToolRegistry,
+    info: ServerInfo,
+}
+
+impl McpServerState for EmbeddedState {
+    type ToolRegistry = CallbackToolRegistry;
+    type AuthManager = EmbeddedAuth;
+
+    fn tool_registry(&self) -> &Self::ToolRegistry {
+        &self.tools
+    }
+
+    fn auth_manager(&self) -> &Self::AuthManager {
+        &EmbeddedAuth
+    }
+
+    fn server_info(&self) -> ServerInfo {
+        self.info.clone()
+    }
+}
+
+/// An MCP server whose tools are implemented by the embedding language
+#[derive(Clone)]
+pub struct EmbeddedServer {
+    config: McpServerConfig,
+    tools: CallbackToolRegistry,
+}
+
+impl EmbeddedServer {
+    /// Create a server with `config`
+    pub fn new(config: McpServerConfig) -> Self {
+        Self {
+            config,
+            tools: CallbackToolRegistry::new(),
+        }
+    }
+
+    /// Create a server configured from `MCP_*` environment variables
+    pub fn from_env() -> McpResult<Self> {
+        Ok(Self::new(McpServerConfig::from_env()?))
+    }
+
+    /// Name and version reported to clients
+    pub fn with_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.config.name = name.into();
+        self.config.version = version.into();
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &McpServerConfig {
+        &self.config
+    }
+
+    /// Register a tool implemented by `callback`
+    pub fn register_tool(
+        &self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: Value,
+        callback: ToolCallback,
+    ) {
+        let tool = McpTool::new(name, description, input_schema, "embedded").public();
+        self.tools.register(tool, callback);
+    }
+
+    /// The MCP server serving the registered tools
+    pub fn server(&self) -> McpServer<EmbeddedState> {
+        let state = EmbeddedState {
+            tools: self.tools.clone(),
+            info: ServerInfo {
+                name: self.config.name.clone(),
+                version: self.config.version.clone(),
+                metadata: Default::default(),
+            },
+        };
+        McpServer::new(self.config.clone(), state)
+    }
+
+    /// Router serving the MCP endpoints under the configured base path
+    pub fn router(&self) -> axum::Router {
+        let session = &self.config.session;
+        let events = Arc::new(InMemoryEventStore::new(session.max_events, session.timeout));
+        let sessions = SessionManager::new(events, session.timeout, session.cleanup_interval);
+        McpServerWrapper::with_session_manager(self.server(), sessions).into_router()
+    }
+
+    /// Validate the server and serve it on `address` until `shutdown` resolves
+    ///
+    /// Without an address the configured `host` and `port` are used.
+    pub async fn serve(
+        &self,
+        address: Option<&str>,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> McpResult<()> {
+        self.server().validate().await.into_result()?;
+        let address = address
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}:{}", self.config.host, self.config.port));
+        let listener = TcpListener::bind(&address).await?;
+        info!("Embedded MCP server listening on {}", address);
+
+        axum::serve(listener, self.router())
+            .with_graceful_shutdown(shutdown)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::JsonRpcRequest;
+
+    #[tokio::test]
+    async fn test_callback_tools() {
+        let embedded = EmbeddedServer::new(McpServerConfig::default());
+        embedded.register_tool(
+            "shout",
+            "Upper-case a message",
+            serde_json::json!({"type": "object"}),
+            Arc::new(|arguments: Value| {
+                Box::pin(async move {
+                    match arguments["message"].as_str() {
+                        Some(message) => Ok(Value::String(message.to_uppercase())),
+                        None => Err("message is required".to_string()),
+                    }
+                })
+            }),
+        );
+        let server = embedded.server();
+        assert!(server.validate().await.is_ok());
+
+        let call = |arguments: Value| {
+            let request = JsonRpcRequest::new(
+                "tools/call",
+                Some(serde_json::json!({"name": "shout", "arguments": arguments})),
+                Some(serde_json::json!(1)),
+            );
+            let server = server.clone();
+            async move {
+                let response = server
+                    .handle_request(request, SecurityContext::system())
+                    .await;
+                serde_json::from_value::<ToolsCallResult>(response.result.unwrap()).unwrap()
+            }
+        };
+
+        let result = call(serde_json::json!({"message": "hi"})).await;
+        assert!(!result.is_error);
+        assert!(matches!(&result.content[0], ToolContent::Text { text } if text == "HI"));
+        assert!(call(serde_json::json!({})).await.is_error);
+    }
+
+    #[test]
+    fn test_tool_result() {
+        let passthrough = tool_result(serde_json::json!({
+            "content": [{"type": "text", "text": "done"}],
+            "isError": true
+        }));
+        assert!(passthrough.is_error);
+        let json = tool_result(serde_json::json!({"count": 2}));
+        assert!(matches!(&json.content[0], ToolContent::Text { text } if text == r#"{"count":2}"#));
+    }
+}
@@ -0,0 +1,90 @@
+//! Node.js bindings (napi-rs)
+//!
+//! ```js
+//! const { Server } = require('axum-mcp');
+//!
+//! const server = new Server('notes', '1.0.0');
+//! server.tool('search', 'Search notes', { type: 'object' }, async (args) => {
+//!   return `Found 3 notes about ${args.query}`;
+//! });
+//! await server.run('127.0.0.1:3000');
+//! ```
+//!
+//! Callbacks run on the JavaScript thread and must return a promise, so
+//! declare them `async`; a rejected promise is reported to the client as a
+//! failed tool call. [`Server::run`] resolves on Ctrl-C.
+
+use napi::{
+    bindgen_prelude::Promise,
+    threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction},
+    JsFunction,
+};
+use napi_derive::napi;
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::{EmbeddedServer, ToolCallback};
+
+/// MCP server with tools implemented in JavaScript
+#[napi]
+pub struct Server {
+    inner: EmbeddedServer,
+}
+
+#[napi]
+impl Server {
+    /// Create a server configured from `MCP_*` environment variables
+    #[napi(constructor)]
+    pub fn new(name: Option<String>, version: Option<String>) -> napi::Result<Self> {
+        let mut inner = EmbeddedServer::from_env().map_err(node_error)?;
+        if let Some(name) = name {
+            let version = version.unwrap_or_else(|| inner.config().version.clone());
+            inner = inner.with_info(name, version);
+        }
+        Ok(Self { inner })
+    }
+
+    /// Register `callback(arguments)` as the tool `name`
+    #[napi]
+    pub fn tool(
+        &self,
+        name: String,
+        description: String,
+        input_schema: Value,
+        callback: JsFunction,
+    ) -> napi::Result<()> {
+        let callback: ThreadsafeFunction<Value, ErrorStrategy::Fatal> = callback
+            .create_threadsafe_function(0, |ctx: ThreadSafeCallContext<Value>| {
+                Ok(vec![ctx.env.to_js_value(&ctx.value)?])
+            })?;
+        let callback = Arc::new(callback);
+        let tool_callback: ToolCallback = Arc::new(move |arguments: Value| {
+            let callback = Arc::clone(&callback);
+            Box::pin(async move {
+                let promise = callback
+                    .call_async::<Promise<Value>>(arguments)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                promise.await.map_err(|e| e.to_string())
+            })
+        });
+        self.inner
+            .register_tool(name, description, input_schema, tool_callback);
+        Ok(())
+    }
+
+    /// Serve until interrupted, on `address` or the configured host and port
+    #[napi]
+    pub async fn run(&self, address: Option<String>) -> napi::Result<()> {
+        self.inner
+            .serve(address.as_deref(), async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+            .map_err(node_error)
+    }
+}
+
+fn node_error(error: impl std::fmt::Display) -> napi::Error {
+    napi::Error::from_reason(error.to_string())
+}
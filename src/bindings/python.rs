@@ -0,0 +1,122 @@
+//! Python bindings (pyo3)
+//!
+//! ```python
+//! import axum_mcp
+//!
+//! server = axum_mcp.Server("notes", "1.0.0")
+//!
+//! def search(args):
+//!     return f"Found 3 notes about {args['query']}"
+//!
+//! server.tool("search", "Search notes", {"type": "object"}, search)
+//! server.run("127.0.0.1:3000")
+//! ```
+//!
+//! Callbacks run on a blocking thread holding the GIL; exceptions they raise
+//! are reported to the client as failed tool calls. [`Server::run`] releases
+//! the GIL while serving and returns on Ctrl-C.
+
+use pyo3::{exceptions::PyRuntimeError, prelude::*, types::PyString};
+use serde_json::Value;
+use std::sync::Arc;
+
+use super::{EmbeddedServer, ToolCallback};
+
+/// MCP server with tools implemented in Python
+#[pyclass(name = "Server")]
+pub struct Server {
+    inner: EmbeddedServer,
+}
+
+#[pymethods]
+impl Server {
+    /// Create a server configured from `MCP_*` environment variables
+    #[new]
+    #[pyo3(signature = (name = None, version = None))]
+    fn new(name: Option<String>, version: Option<String>) -> PyResult<Self> {
+        let mut inner = EmbeddedServer::from_env().map_err(runtime_error)?;
+        if let Some(name) = name {
+            let version = version.unwrap_or_else(|| inner.config().version.clone());
+            inner = inner.with_info(name, version);
+        }
+        Ok(Self { inner })
+    }
+
+    /// Register `callback(arguments)` as the tool `name`
+    ///
+    /// `input_schema` is a dict holding the JSON schema of the arguments.
+    fn tool(
+        &self,
+        py: Python<'_>,
+        name: String,
+        description: String,
+        input_schema: &Bound<'_, PyAny>,
+        callback: PyObject,
+    ) -> PyResult<()> {
+        let input_schema = to_json(py, input_schema)?;
+        let callback = Arc::new(callback);
+        let tool_callback: ToolCallback = Arc::new(move |arguments: Value| {
+            let callback = Arc::clone(&callback);
+            Box::pin(async move {
+                tokio::task::spawn_blocking(move || call(&callback, arguments))
+                    .await
+                    .map_err(|e| format!("Tool callback panicked: {}", e))?
+            })
+        });
+        self.inner
+            .register_tool(name, description, input_schema, tool_callback);
+        Ok(())
+    }
+
+    /// Serve until interrupted, on `address` or the configured host and port
+    #[pyo3(signature = (address = None))]
+    fn run(&self, py: Python<'_>, address: Option<String>) -> PyResult<()> {
+        let inner = self.inner.clone();
+        py.allow_threads(move || {
+            let runtime = tokio::runtime::Runtime::new()?;
+            runtime
+                .block_on(inner.serve(address.as_deref(), async {
+                    let _ = tokio::signal::ctrl_c().await;
+                }))
+                .map_err(runtime_error)
+        })
+    }
+}
+
+/// Call a Python tool callback with JSON arguments
+fn call(callback: &PyObject, arguments: Value) -> Result<Value, String> {
+    Python::with_gil(|py| {
+        let arguments = from_json(py, &arguments)?;
+        let result = callback.call1(py, (arguments,))?;
+        let result = result.bind(py);
+        if let Ok(text) = result.downcast::<PyString>() {
+            return Ok(Value::String(text.to_str()?.to_string()));
+        }
+        to_json(py, result)
+    })
+    .map_err(|e: PyErr| e.to_string())
+}
+
+fn from_json<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+fn to_json(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let text: String = py
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&text).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+fn runtime_error(error: impl std::fmt::Display) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+/// The `axum_mcp` Python module
+#[pymodule]
+pub fn axum_mcp(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Server>()?;
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Injectable time source for time-dependent features
+//!
+//! Rate limiters, session expiry, session stores, duplicate request windows,
+//! the request scheduler and tool timeouts read time through a [`Clock`]
+//! instead of calling `Instant::now()` directly, so tests can control it:
+//!
+//! - [`Clock::system`], the default, is real time.
+//! - [`Clock::tokio`] follows tokio's clock, so it stands still under
+//!   `tokio::time::pause()` and moves with `tokio::time::advance()` in tests
+//!   built with tokio's `test-util` feature.
+//! - [`Clock::manual`] only moves when [`Clock::advance`] is called, which
+//!   makes expiry and retry logic testable without sleeping.
+//!
+//! [`Clock::sleep`] and [`Clock::timeout`] wait on the same clock, so
+//! background tasks and timeouts driven by a manual clock also wait for it to
+//! be advanced.
+
+use std::{
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::sync::watch;
+
+/// Time source shared by the time-dependent parts of the server
+#[derive(Clone, Default)]
+pub struct Clock {
+    source: Source,
+}
+
+#[derive(Clone, Default)]
+enum Source {
+    #[default]
+    System,
+    Tokio(Origin),
+    Manual(Arc<Manual>),
+}
+
+/// The same moment on each clock a virtual clock is mapped onto
+#[derive(Clone, Copy)]
+struct Origin {
+    instant: Instant,
+    tokio: tokio::time::Instant,
+    system: SystemTime,
+}
+
+impl Origin {
+    fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            tokio: tokio::time::Instant::now(),
+            system: SystemTime::now(),
+        }
+    }
+}
+
+struct Manual {
+    origin: Origin,
+    elapsed: watch::Sender<Duration>,
+}
+
+impl Clock {
+    /// Real time
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    /// Tokio's clock, which can be paused and advanced in tests
+    pub fn tokio() -> Self {
+        Self {
+            source: Source::Tokio(Origin::now()),
+        }
+    }
+
+    /// A clock that stands still until it is advanced
+    pub fn manual() -> Self {
+        Self {
+            source: Source::Manual(Arc::new(Manual {
+                origin: Origin::now(),
+                elapsed: watch::channel(Duration::ZERO).0,
+            })),
+        }
+    }
+
+    /// Whether this is a manual clock
+    pub fn is_manual(&self) -> bool {
+        matches!(self.source, Source::Manual(_))
+    }
+
+    /// Move a manual clock forward by `duration`
+    ///
+    /// Sleeps and timeouts on the clock that are now due complete. Has no
+    /// effect on clocks that follow real or tokio time.
+    pub fn advance(&self, duration: Duration) {
+        if let Source::Manual(manual) = &self.source {
+            manual.elapsed.send_modify(|elapsed| *elapsed += duration);
+        }
+    }
+
+    /// Current monotonic time
+    pub fn now(&self) -> Instant {
+        match &self.source {
+            Source::System => Instant::now(),
+            Source::Tokio(origin) => origin.instant + origin.tokio.elapsed(),
+            Source::Manual(manual) => manual.origin.instant + *manual.elapsed.borrow(),
+        }
+    }
+
+    /// Current wall-clock time
+    pub fn system_time(&self) -> SystemTime {
+        match &self.source {
+            Source::System => SystemTime::now(),
+            Source::Tokio(origin) => origin.system + origin.tokio.elapsed(),
+            Source::Manual(manual) => manual.origin.system + *manual.elapsed.borrow(),
+        }
+    }
+
+    /// Wait until `duration` has passed on this clock, counted from now
+    pub fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let manual = match &self.source {
+            Source::Manual(manual) => {
+                let elapsed = manual.elapsed.subscribe();
+                let deadline = *elapsed.borrow() + duration;
+                Some((elapsed, deadline))
+            }
+            Source::System | Source::Tokio(_) => None,
+        };
+        let sleep = manual.is_none().then(|| tokio::time::sleep(duration));
+        async move {
+            match (manual, sleep) {
+                (Some((mut elapsed, deadline)), _) => {
+                    // The sender lives as long as the clock, so this only
+                    // fails once nothing can advance it any more
+                    if elapsed
+                        .wait_for(|elapsed| *elapsed >= deadline)
+                        .await
+                        .is_err()
+                    {
+                        std::future::pending::<()>().await;
+                    }
+                }
+                (None, Some(sleep)) => sleep.await,
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Run `future` for at most `duration` on this clock
+    ///
+    /// Returns `None` if the time ran out first.
+    pub async fn timeout<F: Future>(&self, duration: Duration, future: F) -> Option<F::Output> {
+        let deadline = self.sleep(duration);
+        tokio::select! {
+            output = future => Some(output),
+            _ = deadline => None,
+        }
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Source::System => f.write_str("Clock::System"),
+            Source::Tokio(_) => f.write_str("Clock::Tokio"),
+            Source::Manual(manual) => f
+                .debug_tuple("Clock::Manual")
+                .field(&*manual.elapsed.borrow())
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manual_clock() {
+        let clock = Clock::manual();
+        let start = clock.now();
+        let started_at = clock.system_time();
+
+        let sleeping = tokio::spawn(clock.sleep(Duration::from_secs(60)));
+        clock.advance(Duration::from_secs(30));
+        tokio::task::yield_now().await;
+        assert!(!sleeping.is_finished());
+
+        clock.advance(Duration::from_secs(30));
+        sleeping.await.unwrap();
+        assert_eq!(clock.now() - start, Duration::from_secs(60));
+        assert_eq!(
+            clock.system_time().duration_since(started_at).unwrap(),
+            Duration::from_secs(60)
+        );
+
+        let deadline = clock.sleep(Duration::from_secs(1));
+        clock.advance(Duration::from_secs(1));
+        deadline.await;
+    }
+}
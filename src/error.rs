@@ -1,17 +1,62 @@
 //! Error types for MCP operations
 
+#[cfg(feature = "axum")]
 use axum::{
-    http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
+use http::StatusCode;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{collections::BTreeMap, error::Error as StdError, fmt, time::Duration};
 use thiserror::Error;
 
 /// Result type for MCP operations
 pub type McpResult<T> = Result<T, McpError>;
 
+/// A foreign error captured with its causes
+///
+/// Keeps the message of every error in the chain, so an [`McpError`] can
+/// carry the cause of an I/O, serialization or `anyhow` error while staying
+/// cloneable and serializable.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceError {
+    /// Message of this error
+    pub message: String,
+
+    /// The error that caused this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Box<SourceError>>,
+}
+
+impl SourceError {
+    /// Capture `err` and its chain of sources
+    pub fn new(err: &(dyn StdError + 'static)) -> Self {
+        Self {
+            message: err.to_string(),
+            source: err.source().map(|source| Box::new(Self::new(source))),
+        }
+    }
+
+    /// Capture the sources of `err`, if it has any
+    pub fn sources_of(err: &(dyn StdError + 'static)) -> Option<Self> {
+        err.source().map(Self::new)
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for SourceError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn StdError + 'static))
+    }
+}
+
 /// MCP error types
 #[derive(Error, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum McpError {
@@ -35,6 +80,14 @@ pub enum McpError {
     #[error("Tool not found: {name}")]
     ToolNotFound { name: String },
 
+    /// Unknown JSON-RPC method, rejected in strict method mode
+    #[error("Method not found: {method}")]
+    MethodNotFound {
+        method: String,
+        /// Closest known method name, if any is similar
+        suggestion: Option<String>,
+    },
+
     /// Tool execution errors
     #[error("Tool execution failed: {tool}: {message}")]
     ToolExecution { tool: String, message: String },
@@ -74,6 +127,37 @@ pub enum McpError {
         retry_after: Option<u64>,
     },
 
+    /// Server too busy to take on more work
+    #[error("Server overloaded: {reason}")]
+    Overloaded {
+        reason: String,
+        /// Seconds clients should wait before retrying
+        retry_after: u64,
+    },
+
+    /// Item switched into maintenance by an operator
+    #[error("{item} is under maintenance: {message}")]
+    UnderMaintenance {
+        /// The tool, prompt or resource scheme, such as `tool 'search'`
+        item: String,
+        /// Message for clients, set by the operator
+        message: String,
+        /// Seconds until the item is back, when a maintenance window says
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    },
+
+    /// Quota for the current window used up
+    #[error("Quota exceeded for {principal}: {metric}")]
+    QuotaExceeded {
+        principal: String,
+        metric: String,
+        /// Remaining quota per limited metric in the current window
+        remaining: BTreeMap<String, u64>,
+        /// Seconds until the window resets
+        reset_after: u64,
+    },
+
     /// Network errors
     #[error("Network error: {message}")]
     Network { message: String },
@@ -84,11 +168,21 @@ pub enum McpError {
 
     /// I/O errors
     #[error("I/O error: {message}")]
-    Io { message: String },
+    Io {
+        message: String,
+        #[source]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<SourceError>,
+    },
 
     /// Serialization/deserialization errors
     #[error("Serialization error: {message}")]
-    Serialization { message: String },
+    Serialization {
+        message: String,
+        #[source]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<SourceError>,
+    },
 
     /// Connection errors
     #[error("Connection error: {message}")]
@@ -104,16 +198,138 @@ pub enum McpError {
 
     /// Internal server errors
     #[error("Internal error: {message}")]
-    Internal { message: String },
+    Internal {
+        message: String,
+        #[source]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<SourceError>,
+    },
+
+    /// An error with a description of what was being done when it happened
+    ///
+    /// Added by [`McpResultExt::context`]. Status codes and client messages
+    /// are those of the innermost error.
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<McpError>,
+    },
 }
 
 impl McpError {
+    /// Create an internal error without a source
+    pub fn internal(message: impl Into<String>) -> Self {
+        McpError::Internal {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// Wrap this error with a description of what was being done
+    pub fn context(self, context: impl Into<String>) -> Self {
+        McpError::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The innermost [`McpError`], below any added context
+    pub fn root_cause(&self) -> &McpError {
+        match self {
+            McpError::Context { source, .. } => source.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// This error followed by its sources, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        std::iter::successors(Some(self as &(dyn StdError + 'static)), |&err| err.source())
+    }
+
+    /// The messages of the whole chain, joined with `: `, for logs
+    pub fn report(&self) -> String {
+        self.chain()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(": ")
+    }
+
+    /// Stable machine-readable code, such as `tool_not_found`
+    ///
+    /// Sent to clients as `code` in the JSON-RPC error data.
+    pub fn code(&self) -> &'static str {
+        match self {
+            McpError::Transport { .. } => "transport",
+            McpError::Protocol { .. } => "protocol",
+            McpError::Authentication { .. } => "authentication",
+            McpError::Authorization { .. } => "authorization",
+            McpError::ToolNotFound { .. } => "tool_not_found",
+            McpError::MethodNotFound { .. } => "method_not_found",
+            McpError::ToolExecution { .. } => "tool_execution",
+            McpError::ResourceNotFound { .. } => "resource_not_found",
+            McpError::InvalidResource { .. } => "invalid_resource",
+            McpError::ServerTimeout { .. } => "server_timeout",
+            McpError::ClientTimeout { .. } => "client_timeout",
+            McpError::Configuration { .. } => "configuration",
+            McpError::Session { .. } => "session",
+            McpError::RateLimit { .. } => "rate_limited",
+            McpError::RateLimitExceeded { .. } => "rate_limited",
+            McpError::Overloaded { .. } => "overloaded",
+            McpError::UnderMaintenance { .. } => "under_maintenance",
+            McpError::QuotaExceeded { .. } => "quota_exceeded",
+            McpError::Network { .. } => "network",
+            McpError::Validation { .. } => "validation",
+            McpError::Io { .. } => "io",
+            McpError::Serialization { .. } => "serialization",
+            McpError::Connection { .. } => "connection",
+            McpError::ConnectionFailed { .. } => "connection_failed",
+            McpError::ConnectionTimeout { .. } => "connection_timeout",
+            McpError::Internal { .. } => "internal",
+            McpError::Context { source, .. } => source.code(),
+        }
+    }
+
+    /// Whether retrying the same request later may succeed
+    ///
+    /// True for rate limits, quotas, overload, maintenance,
+    /// server timeouts and network failures; everything else is permanent.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.root_cause(),
+            McpError::RateLimit { .. }
+                | McpError::RateLimitExceeded { .. }
+                | McpError::QuotaExceeded { .. }
+                | McpError::Overloaded { .. }
+                | McpError::UnderMaintenance { .. }
+                | McpError::ServerTimeout { .. }
+                | McpError::Network { .. }
+                | McpError::Connection { .. }
+                | McpError::ConnectionFailed { .. }
+                | McpError::ConnectionTimeout { .. }
+        )
+    }
+
+    /// How long clients should wait before retrying, if known
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.root_cause() {
+            McpError::RateLimitExceeded { retry_after, .. }
+            | McpError::UnderMaintenance { retry_after, .. } => {
+                retry_after.map(Duration::from_secs)
+            }
+            McpError::Overloaded { retry_after, .. } => Some(Duration::from_secs(*retry_after)),
+            McpError::QuotaExceeded { reset_after, .. } => Some(Duration::from_secs(*reset_after)),
+            _ => None,
+        }
+    }
+
     /// Get the HTTP status code for this error
     pub fn status_code(&self) -> StatusCode {
         match self {
             McpError::Authentication { .. } => StatusCode::UNAUTHORIZED,
             McpError::Authorization { .. } => StatusCode::FORBIDDEN,
             McpError::ToolNotFound { .. } => StatusCode::NOT_FOUND,
+            McpError::MethodNotFound { .. } => StatusCode::NOT_FOUND,
             McpError::ResourceNotFound { .. } => StatusCode::NOT_FOUND,
             McpError::InvalidResource { .. } => StatusCode::BAD_REQUEST,
             McpError::Validation { .. } => StatusCode::BAD_REQUEST,
@@ -121,6 +337,9 @@ impl McpError {
             McpError::Configuration { .. } => StatusCode::BAD_REQUEST,
             McpError::RateLimit { .. } => StatusCode::TOO_MANY_REQUESTS,
             McpError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            McpError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            McpError::Overloaded { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            McpError::UnderMaintenance { .. } => StatusCode::SERVICE_UNAVAILABLE,
             McpError::Network { .. } => StatusCode::SERVICE_UNAVAILABLE,
             McpError::ServerTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
             McpError::ClientTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
@@ -133,6 +352,7 @@ impl McpError {
             McpError::Io { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             McpError::Serialization { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             McpError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            McpError::Context { source, .. } => source.status_code(),
         }
     }
 
@@ -141,6 +361,7 @@ impl McpError {
         match self {
             McpError::Protocol { .. } => -32600,          // Invalid Request
             McpError::ToolNotFound { .. } => -32601,      // Method not found
+            McpError::MethodNotFound { .. } => -32601,    // Method not found
             McpError::ResourceNotFound { .. } => -32601,  // Method not found (resource not found)
             McpError::InvalidResource { .. } => -32602,   // Invalid params
             McpError::Validation { .. } => -32602,        // Invalid params
@@ -148,8 +369,12 @@ impl McpError {
             McpError::Authorization { .. } => -32000,     // Server error (authz)
             McpError::RateLimit { .. } => -32000,         // Server error (rate limit)
             McpError::RateLimitExceeded { .. } => -32000, // Server error (rate limit)
+            McpError::QuotaExceeded { .. } => -32000,     // Server error (quota)
+            McpError::Overloaded { .. } => -32000,        // Server error (overload)
+            McpError::UnderMaintenance { .. } => -32000,  // Server error (maintenance)
             McpError::ToolExecution { .. } => -32000,     // Server error (execution)
-            _ => -32603,                                  // Internal error
+            McpError::Context { source, .. } => source.error_code(),
+            _ => -32603, // Internal error
         }
     }
 
@@ -159,6 +384,15 @@ impl McpError {
             McpError::Authentication { .. } => "Authentication required".to_string(),
             McpError::Authorization { .. } => "Access denied".to_string(),
             McpError::ToolNotFound { name } => format!("Tool '{}' not found", name),
+            McpError::MethodNotFound { method, suggestion } => match suggestion {
+                Some(suggestion) => {
+                    format!(
+                        "Method '{}' not found, did you mean '{}'?",
+                        method, suggestion
+                    )
+                }
+                None => format!("Method '{}' not found", method),
+            },
             McpError::ResourceNotFound { uri } => format!("Resource '{}' not found", uri),
             McpError::InvalidResource { uri, message } => {
                 format!("Invalid resource '{}': {}", uri, message)
@@ -167,11 +401,59 @@ impl McpError {
             McpError::Protocol { message } => message.clone(),
             McpError::RateLimit { .. } => "Rate limit exceeded".to_string(),
             McpError::RateLimitExceeded { .. } => "Rate limit exceeded".to_string(),
+            McpError::QuotaExceeded { metric, .. } => format!("Quota exceeded: {}", metric),
+            McpError::Overloaded { .. } => "Server overloaded, retry later".to_string(),
+            McpError::UnderMaintenance { item, message, .. } => {
+                format!("{} is temporarily unavailable: {}", item, message)
+            }
             McpError::ServerTimeout { .. } => "Request timeout".to_string(),
             McpError::ClientTimeout { .. } => "Request timeout".to_string(),
+            McpError::Context { source, .. } => source.client_message(),
             _ => "Internal server error".to_string(),
         }
     }
+
+    /// Structured details safe to share with clients, sent as JSON-RPC `data`
+    ///
+    /// Always includes the machine-readable [`code`](Self::code) and whether
+    /// the error is [`transient`](Self::is_transient), plus `retryAfterMs`
+    /// when [`retry_after`](Self::retry_after) is known.
+    pub fn client_data(&self) -> Option<serde_json::Value> {
+        let mut data = match self.root_cause() {
+            McpError::QuotaExceeded {
+                metric,
+                remaining,
+                reset_after,
+                ..
+            } => serde_json::json!({
+                "code": self.code(),
+                "metric": metric,
+                "remaining": remaining,
+                "resetAfter": reset_after,
+            }),
+            McpError::Overloaded { retry_after, .. } => serde_json::json!({
+                "code": self.code(),
+                "retryAfter": retry_after,
+            }),
+            McpError::UnderMaintenance { item, .. } => serde_json::json!({
+                "code": self.code(),
+                "item": item,
+            }),
+            McpError::MethodNotFound {
+                suggestion: Some(suggestion),
+                ..
+            } => serde_json::json!({
+                "code": self.code(),
+                "suggestion": suggestion,
+            }),
+            _ => serde_json::json!({"code": self.code()}),
+        };
+        data["transient"] = self.is_transient().into();
+        if let Some(retry_after) = self.retry_after() {
+            data["retryAfterMs"] = (retry_after.as_millis() as u64).into();
+        }
+        Some(data)
+    }
 }
 
 /// Error response for HTTP endpoints
@@ -182,13 +464,14 @@ pub struct ErrorResponse {
     pub details: Option<serde_json::Value>,
 }
 
+#[cfg(feature = "axum")]
 impl IntoResponse for McpError {
     fn into_response(self) -> Response {
         let status = self.status_code();
         let error_response = ErrorResponse {
             error: self.client_message(),
             code: Some(self.error_code()),
-            details: None,
+            details: self.client_data(),
         };
 
         (status, Json(error_response)).into_response()
@@ -200,6 +483,7 @@ impl From<std::io::Error> for McpError {
     fn from(err: std::io::Error) -> Self {
         McpError::Io {
             message: err.to_string(),
+            source: SourceError::sources_of(&err),
         }
     }
 }
@@ -208,10 +492,12 @@ impl From<serde_json::Error> for McpError {
     fn from(err: serde_json::Error) -> Self {
         McpError::Serialization {
             message: err.to_string(),
+            source: SourceError::sources_of(&err),
         }
     }
 }
 
+#[cfg(feature = "runtime")]
 impl From<url::ParseError> for McpError {
     fn from(err: url::ParseError) -> Self {
         McpError::Configuration {
@@ -220,6 +506,7 @@ impl From<url::ParseError> for McpError {
     }
 }
 
+#[cfg(feature = "runtime")]
 impl From<tokio::time::error::Elapsed> for McpError {
     fn from(_: tokio::time::error::Elapsed) -> Self {
         McpError::ServerTimeout {
@@ -232,16 +519,50 @@ impl From<anyhow::Error> for McpError {
     fn from(err: anyhow::Error) -> Self {
         McpError::Internal {
             message: err.to_string(),
+            source: SourceError::sources_of(err.as_ref()),
         }
     }
 }
 
+/// Adds context to errors on their way up
+///
+/// Implemented for any result whose error converts into [`McpError`]:
+///
+/// ```rust
+/// use axum_mcp::error::{McpResult, McpResultExt};
+///
+/// fn load(path: &str) -> McpResult<String> {
+///     std::fs::read_to_string(path).with_context(|| format!("Failed to load {}", path))
+/// }
+///
+/// let error = load("/nonexistent").unwrap_err();
+/// assert_eq!(error.code(), "io");
+/// assert!(error.report().starts_with("Failed to load /nonexistent: I/O error: "));
+/// ```
+pub trait McpResultExt<T> {
+    /// Wrap the error with a description of what was being done
+    fn context(self, context: impl Into<String>) -> McpResult<T>;
+
+    /// Like [`context`](Self::context), building the description only on error
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> McpResult<T>;
+}
+
+impl<T, E: Into<McpError>> McpResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> McpResult<T> {
+        self.map_err(|err| err.into().context(context))
+    }
+
+    fn with_context<C: Into<String>>(self, context: impl FnOnce() -> C) -> McpResult<T> {
+        self.map_err(|err| err.into().context(context()))
+    }
+}
+
 impl From<McpError> for crate::protocol::JsonRpcError {
     fn from(err: McpError) -> Self {
         crate::protocol::JsonRpcError {
             code: err.error_code(),
             message: err.client_message(),
-            data: None,
+            data: err.client_data(),
         }
     }
 }
@@ -308,4 +629,69 @@ mod tests {
         };
         assert_eq!(tool_error.client_message(), "Tool 'test_tool' not found");
     }
+
+    #[test]
+    fn test_transient_errors_carry_retry_hints() {
+        let overloaded = McpError::Overloaded {
+            reason: "too many calls".to_string(),
+            retry_after: 2,
+        }
+        .context("Failed to charge card");
+        assert!(overloaded.is_transient());
+        assert_eq!(overloaded.retry_after(), Some(Duration::from_secs(2)));
+        let error = crate::protocol::JsonRpcError::from(overloaded);
+        let data = error.data.unwrap();
+        assert_eq!(data["code"], "overloaded");
+        assert_eq!(data["transient"], true);
+        assert_eq!(data["retryAfterMs"], 2000);
+
+        let maintenance = McpError::UnderMaintenance {
+            item: "tool 'deploy'".to_string(),
+            message: "read-only until 10:00 UTC".to_string(),
+            retry_after: Some(90),
+        };
+        let data = maintenance.client_data().unwrap();
+        assert_eq!(data["item"], "tool 'deploy'");
+        assert_eq!(data["retryAfterMs"], 90_000);
+
+        let rate_limited = McpError::RateLimit {
+            message: "slow down".to_string(),
+        };
+        assert!(rate_limited.is_transient());
+        let data = rate_limited.client_data().unwrap();
+        assert_eq!(data["transient"], true);
+        assert!(data.get("retryAfterMs").is_none());
+
+        let not_found = McpError::ToolNotFound {
+            name: "search".to_string(),
+        };
+        assert!(!not_found.is_transient());
+        assert_eq!(not_found.client_data().unwrap()["transient"], false);
+    }
+
+    #[test]
+    fn test_error_context_chain() {
+        let result: Result<(), _> =
+            Err(anyhow::anyhow!("connection reset").context("Failed to fetch index"));
+        let error = result
+            .context("Failed to read resource")
+            .context("Failed to render prompt 'review'")
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Failed to render prompt 'review'");
+        assert_eq!(
+            error.report(),
+            "Failed to render prompt 'review': Failed to read resource: \
+             Internal error: Failed to fetch index: connection reset"
+        );
+        assert_eq!(error.chain().count(), 4);
+        assert_eq!(error.code(), "internal");
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.client_message(), "Internal server error");
+        assert_eq!(error.client_data().unwrap()["code"], "internal");
+        assert!(matches!(error.root_cause(), McpError::Internal { .. }));
+
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(serde_json::from_str::<McpError>(&json).unwrap(), error);
+    }
 }
@@ -0,0 +1,228 @@
+//! Pluggable generation of the IDs the server hands out
+//!
+//! Session IDs, subscription IDs, progress tokens and the IDs of requests
+//! sent to clients are random UUIDv4 by default. Downstream systems often
+//! want IDs that sort by creation time or tell which shard made them, so the
+//! generator is configurable with [`IdStrategy`]:
+//!
+//! - `uuid_v4`: random UUIDs, the default
+//! - `uuid_v7`: time-ordered UUIDs
+//! - `ulid`: time-ordered 26-character ULIDs
+//! - `snowflake`: 64-bit integers of time, node and sequence
+//!
+//! Anything else implements [`IdGenerator`] and is passed to
+//! [`McpServer::with_id_generator`](crate::server::McpServer::with_id_generator).
+//!
+//! Session IDs are bearer credentials: whoever knows one can resume, reattach
+//! to or delete the session. The time-ordered strategies are partly
+//! predictable, so their session IDs keep the sortable ID as a prefix and add
+//! a random UUIDv4 in simple form after a `-`, such as
+//! `01JAF3X9ZQ8W6M2C4N7R5T1V0B-9f1c0b7e6d2a4c3f8e5b1a0d9c8b7a6f` with `ulid`.
+//! Custom generators must also return unguessable values for
+//! [`IdKind::Session`].
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+/// What an ID is generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    /// StreamableHTTP session; must be unguessable, as it authorizes resuming
+    /// and deleting the session
+    Session,
+    /// Request sent to a client
+    Request,
+    /// Progress token of a long-running request
+    Progress,
+    /// Resource subscription
+    Subscription,
+}
+
+/// Source of IDs
+pub trait IdGenerator: Send + Sync {
+    /// A new ID for `kind`, unique among those of the same kind
+    fn generate(&self, kind: IdKind) -> String;
+}
+
+/// Random UUIDv4
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+    fn generate(&self, _kind: IdKind) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// UUIDv7, ordered by creation time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7;
+
+impl IdGenerator for UuidV7 {
+    fn generate(&self, kind: IdKind) -> String {
+        unguessable(kind, Uuid::now_v7().to_string())
+    }
+}
+
+/// ULID: a millisecond timestamp and 80 random bits in Crockford base32
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ulid;
+
+impl IdGenerator for Ulid {
+    fn generate(&self, kind: IdKind) -> String {
+        const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+        let random = u128::from_be_bytes(*Uuid::new_v4().as_bytes()) & ((1 << 80) - 1);
+        let value = ((unix_millis() as u128) << 80) | random;
+        let id = (0..26)
+            .map(|i| ALPHABET[((value >> ((25 - i) * 5)) & 31) as usize] as char)
+            .collect();
+        unguessable(kind, id)
+    }
+}
+
+/// Milliseconds of the Snowflake epoch, 2020-01-01T00:00:00Z
+pub const SNOWFLAKE_EPOCH_MS: u64 = 1_577_836_800_000;
+
+/// Snowflake IDs: 41 bits of milliseconds, 10 of node and 12 of sequence
+///
+/// IDs from one generator increase strictly. When more than 4096 are made in
+/// a millisecond, or the system clock goes back, the generator runs ahead of
+/// the clock instead of waiting.
+#[derive(Debug)]
+pub struct Snowflake {
+    node_id: u16,
+    last: Mutex<(u64, u64)>,
+}
+
+impl Snowflake {
+    /// Largest node ID
+    pub const MAX_NODE_ID: u16 = 1023;
+
+    /// Generator for node `node_id`, of which the low 10 bits are used
+    pub fn new(node_id: u16) -> Self {
+        Self {
+            node_id: node_id & Self::MAX_NODE_ID,
+            last: Mutex::new((0, 0)),
+        }
+    }
+}
+
+impl IdGenerator for Snowflake {
+    fn generate(&self, kind: IdKind) -> String {
+        let now = unix_millis().saturating_sub(SNOWFLAKE_EPOCH_MS);
+        let mut last = self.last.lock().unwrap_or_else(|p| p.into_inner());
+        let (millis, sequence) = match *last {
+            (millis, sequence) if now <= millis && sequence < 4095 => (millis, sequence + 1),
+            (millis, _) if now <= millis => (millis + 1, 0),
+            _ => (now, 0),
+        };
+        *last = (millis, sequence);
+        let id = ((millis << 22) | (u64::from(self.node_id) << 12) | sequence).to_string();
+        unguessable(kind, id)
+    }
+}
+
+/// `id`, followed by a random UUIDv4 for session IDs
+fn unguessable(kind: IdKind, id: String) -> String {
+    if kind == IdKind::Session {
+        format!("{}-{}", id, Uuid::new_v4().simple())
+    } else {
+        id
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Built-in ID generator to use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IdStrategy {
+    /// Random UUIDv4
+    #[default]
+    UuidV4,
+    /// Time-ordered UUIDv7
+    UuidV7,
+    /// Time-ordered ULIDs
+    Ulid,
+    /// Snowflake IDs for node `node_id` (0 to 1023)
+    Snowflake { node_id: u16 },
+}
+
+impl IdStrategy {
+    /// Generator following this strategy
+    pub fn build(&self) -> Ids {
+        match self {
+            Self::UuidV4 => Ids::new(UuidV4),
+            Self::UuidV7 => Ids::new(UuidV7),
+            Self::Ulid => Ids::new(Ulid),
+            Self::Snowflake { node_id } => Ids::new(Snowflake::new(*node_id)),
+        }
+    }
+}
+
+/// Shared handle on an ID generator, UUIDv4 by default
+#[derive(Clone)]
+pub struct Ids(Arc<dyn IdGenerator>);
+
+impl Ids {
+    /// Handle on `generator`
+    pub fn new(generator: impl IdGenerator + 'static) -> Self {
+        Self(Arc::new(generator))
+    }
+
+    /// A new ID for `kind`
+    pub fn generate(&self, kind: IdKind) -> String {
+        self.0.generate(kind)
+    }
+}
+
+impl Default for Ids {
+    fn default() -> Self {
+        Self::new(UuidV4)
+    }
+}
+
+impl fmt::Debug for Ids {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Ids").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_ordered_strategies() {
+        let ulid = Ulid.generate(IdKind::Subscription);
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.chars().all(|c| c.is_ascii_alphanumeric()));
+
+        let uuid: Uuid = UuidV7.generate(IdKind::Progress).parse().unwrap();
+        assert_eq!(uuid.get_version_num(), 7);
+
+        let strategy: IdStrategy =
+            serde_json::from_value(serde_json::json!({"type": "snowflake", "node_id": 5})).unwrap();
+        let ids = strategy.build();
+        let session = ids.generate(IdKind::Session);
+        let (snowflake, random) = session.split_once('-').unwrap();
+        assert_eq!((snowflake.parse::<u64>().unwrap() >> 12) & 1023, 5);
+        let random = Uuid::try_parse(random).unwrap();
+        assert_eq!(random.get_version_num(), 4);
+        let generated: Vec<u64> = (0..10_000)
+            .map(|_| ids.generate(IdKind::Request).parse().unwrap())
+            .collect();
+        assert!(generated.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(generated.iter().all(|id| (id >> 12) & 1023 == 5));
+    }
+}
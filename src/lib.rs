@@ -154,6 +154,7 @@
 //! - [`security`] - Authentication and authorization framework
 //! - [`error`] - Comprehensive error types and handling
 //! - [`axum_integration`] - Axum-specific HTTP handlers and utilities
+//! - [`scaffold`] - Generating new server projects
 //!
 //! ## Transports
 //!
@@ -187,14 +188,28 @@
 //! - Audit logging capabilities
 //! - Rate limiting support
 
+#[cfg(any(feature = "python", feature = "node"))]
+pub mod bindings;
+#[cfg(feature = "runtime")]
+pub mod clock;
 pub mod error;
+#[cfg(feature = "runtime")]
+pub mod ids;
 pub mod protocol;
+pub mod scaffold;
+#[cfg(feature = "server")]
 pub mod security;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "transport")]
 pub mod transport;
 
 // Re-export commonly used types
-pub use error::{McpError, McpResult};
+#[cfg(feature = "runtime")]
+pub use clock::Clock;
+pub use error::{McpError, McpResult, McpResultExt};
+#[cfg(feature = "runtime")]
+pub use ids::{IdGenerator, IdKind, IdStrategy, Ids};
 
 // Re-export protocol types
 pub use protocol::{
@@ -204,6 +219,7 @@ pub use protocol::{
 };
 
 // Re-export server framework
+#[cfg(feature = "server")]
 pub use server::{
     EmbeddedResource, GetPromptRequest, GetPromptResult, InMemoryPromptRegistry,
     InMemoryResourceRegistry, InMemoryToolRegistry, McpServerConfig, McpServerState, McpTool,
@@ -215,16 +231,26 @@ pub use server::{
 };
 
 // Re-export transport types
-pub use transport::{
-    EventStore, InMemoryEventStore, McpEvent, McpTransport, SessionManager, SseTransport,
-    StdioTransport, StreamableHttpTransport, TransportFactory, TransportHealth, TransportType,
-};
+#[cfg(all(feature = "client", feature = "sse"))]
+pub use transport::SseTransport;
+#[cfg(all(feature = "client", feature = "stdio"))]
+pub use transport::StdioTransport;
+#[cfg(feature = "axum")]
+pub use transport::StreamableHttpTransport;
+#[cfg(feature = "client")]
+pub use transport::TransportFactory;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use transport::WasmTransport;
+#[cfg(feature = "server")]
+pub use transport::{EventStore, InMemoryEventStore, McpEvent, SessionManager};
+#[cfg(feature = "transport")]
+pub use transport::{McpTransport, TransportHealth, TransportType};
 
 // Re-export security framework
+#[cfg(feature = "server")]
 pub use security::{ClientContext, ClientPermissions, McpAuth, SecurityContext};
 
-/// Axum integration module
-#[cfg(feature = "handlers")]
+#[cfg(feature = "axum")]
 pub mod axum_integration {
     //! Axum-specific HTTP handlers and utilities
 
@@ -232,9 +258,12 @@ pub mod axum_integration {
     use crate::transport::streamable_http::SessionManager;
     use crate::transport::TransportHealth;
 
+    pub use crate::server::extract::{McpRequestParts, McpState, MCP_SESSION_HEADER};
+    #[cfg(feature = "sse")]
+    pub use crate::server::handler::mcp_messages_handler;
     pub use crate::server::handler::{
-        mcp_delete_handler, mcp_get_handler, mcp_post_handler, mcp_routes, mcp_sse_handler,
-        McpEndpointInfo, McpQueryParams,
+        mcp_delete_handler, mcp_get_handler, mcp_post_handler, mcp_routes,
+        mcp_routes_with_base_path, mcp_sse_handler, McpEndpointInfo, McpQueryParams,
     };
 
     /// Wrapper for McpServer that implements McpHandlerState
@@ -254,7 +283,13 @@ pub mod axum_integration {
         }
 
         /// Create a new wrapper with session management for StreamableHTTP
+        ///
+        /// Events sent to sessions are mirrored to the server's wire tap, and
+        /// session IDs come from the server's ID generator.
         pub fn with_session_manager(server: McpServer<S>, session_manager: SessionManager) -> Self {
+            let session_manager = session_manager
+                .with_wire_tap(server.wire_tap().clone())
+                .with_ids(server.ids().clone());
             Self {
                 server,
                 session_manager: Some(session_manager),
@@ -265,6 +300,24 @@ pub mod axum_integration {
         pub fn server(&self) -> &McpServer<S> {
             &self.server
         }
+
+        /// Build a router serving the MCP endpoints with this wrapper as state
+        ///
+        /// Endpoints are mounted under the configured `base_path`.
+        pub fn into_router(self) -> axum::Router {
+            self.into_router_with(axum::Router::new())
+        }
+
+        /// Build a router serving the MCP endpoints alongside additional routes
+        ///
+        /// The extra routes share this wrapper as state, so their handlers can use
+        /// [`McpState`] and [`McpRequestParts`] to reach the server and session data.
+        pub fn into_router_with(self, routes: axum::Router<Self>) -> axum::Router {
+            let base_path = self.server.config().base_path.clone();
+            crate::server::handler::mcp_routes_with_base_path(&base_path)
+                .merge(routes)
+                .with_state(self)
+        }
     }
 
     impl<S: McpServerState> McpHandlerState for McpServerWrapper<S> {
@@ -284,6 +337,17 @@ pub mod axum_integration {
         }
     }
 
+    /// Mount an MCP server under the given path, returning a stateless router
+    ///
+    /// Several independent servers (with different states and configs) can be
+    /// mounted in one application by merging the returned routers.
+    pub fn mcp_routes_at<S>(path: &str, wrapper: McpServerWrapper<S>) -> axum::Router
+    where
+        S: McpServerState + Clone + Send + Sync + 'static,
+    {
+        crate::server::handler::mcp_routes_with_base_path(path).with_state(wrapper)
+    }
+
     /// Convenience function to create MCP routes with wrapper
     pub fn mcp_routes_with_wrapper<S>() -> axum::Router<McpServerWrapper<S>>
     where
@@ -297,31 +361,86 @@ pub mod axum_integration {
 pub mod prelude {
     //! Commonly used types and traits
 
+    #[cfg(feature = "transport")]
+    pub use crate::transport::{McpTransport, TransportType};
     pub use crate::{
-        error::{McpError, McpResult},
+        error::{McpError, McpResult, McpResultExt},
         protocol::{JsonRpcRequest, JsonRpcResponse, Tool, ToolContent, ToolsCallResult},
+    };
+    #[cfg(feature = "server")]
+    pub use crate::{
         security::{ClientContext, McpAuth, SecurityContext},
         server::{
             InMemoryToolRegistry, McpServerConfig, McpServerState, McpTool, ToolExecutionContext,
             ToolRegistry,
         },
-        transport::{McpTransport, SessionManager, TransportType},
+        transport::SessionManager,
     };
 
+    #[cfg(feature = "transport")]
     pub use async_trait::async_trait;
     pub use serde_json::{json, Value};
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "server"))]
 mod tests {
     use super::*;
     use crate::prelude::*;
 
+    #[derive(Clone)]
+    struct TestState {
+        tools: InMemoryToolRegistry,
+        auth: TestAuth,
+    }
+
+    #[derive(Clone)]
+    struct TestAuth;
+
+    #[async_trait]
+    impl McpAuth for TestAuth {
+        async fn authenticate(&self, _client: &ClientContext) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::system())
+        }
+
+        async fn authorize(
+            &self,
+            _context: &SecurityContext,
+            _resource: &str,
+            _action: &str,
+        ) -> bool {
+            true
+        }
+    }
+
+    impl McpServerState for TestState {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &self.auth
+        }
+    }
+
+    fn test_server() -> server::McpServer<TestState> {
+        server::McpServer::new(
+            McpServerConfig::default(),
+            TestState {
+                tools: InMemoryToolRegistry::new(),
+                auth: TestAuth,
+            },
+        )
+    }
+
     #[test]
     fn test_crate_exports() {
         // Test that all major types are accessible
         let _error: McpError = McpError::Internal {
             message: "test".to_string(),
+            source: None,
         };
         let _protocol_version = MCP_PROTOCOL_VERSION;
         let _supported_versions = SUPPORTED_PROTOCOL_VERSIONS;
@@ -329,52 +448,454 @@ mod tests {
 
     #[tokio::test]
     async fn test_basic_server_creation() {
-        #[derive(Clone)]
-        struct TestState {
-            tools: InMemoryToolRegistry,
+        let config = McpServerConfig::default();
+        let state = TestState {
+            tools: InMemoryToolRegistry::new(),
             auth: TestAuth,
-        }
+        };
 
-        #[derive(Clone)]
-        struct TestAuth;
+        let _config = config;
+        let _state = state;
+        // Basic trait implementation should succeed
+    }
 
-        #[async_trait]
-        impl McpAuth for TestAuth {
-            async fn authenticate(&self, _client: &ClientContext) -> McpResult<SecurityContext> {
-                Ok(SecurityContext::system())
-            }
+    #[tokio::test]
+    async fn test_wrapper_router_with_extractors() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{McpRequestParts, McpServerWrapper, McpState};
+        use tower::ServiceExt;
+
+        async fn status(
+            McpState(server): McpState<TestState>,
+            parts: McpRequestParts,
+        ) -> axum::Json<Value> {
+            axum::Json(json!({
+                "name": server.config().name,
+                "session": parts.session_id,
+                "authenticated": parts.security.is_authenticated(),
+            }))
+        }
 
-            async fn authorize(
-                &self,
-                _context: &SecurityContext,
-                _resource: &str,
-                _action: &str,
-            ) -> bool {
-                true
-            }
+        let app = McpServerWrapper::new(test_server())
+            .into_router_with(axum::Router::new().route("/status", axum::routing::get(status)));
+
+        let response = app
+            .oneshot(
+                Request::get("/status")
+                    .header("mcp-session-id", "session-1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let value: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["name"], "MCP Server");
+        assert_eq!(value["session"], "session-1");
+        assert_eq!(value["authenticated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_multiple_mounts() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use tower::ServiceExt;
+
+        let other = server::McpServer::new(
+            McpServerConfig {
+                name: "Other Server".to_string(),
+                ..Default::default()
+            },
+            TestState {
+                tools: InMemoryToolRegistry::new(),
+                auth: TestAuth,
+            },
+        );
+
+        let app = axum::Router::new()
+            .merge(mcp_routes_at(
+                "/api/v1/mcp",
+                McpServerWrapper::new(test_server()),
+            ))
+            .merge(mcp_routes_at("/other/mcp/", McpServerWrapper::new(other)));
+
+        for (path, name) in [
+            ("/api/v1/mcp", "MCP Server"),
+            ("/other/mcp", "Other Server"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let value: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(value["name"], name);
         }
 
-        impl McpServerState for TestState {
-            type ToolRegistry = InMemoryToolRegistry;
-            type AuthManager = TestAuth;
+        let response = app
+            .oneshot(Request::get("/mcp").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
 
-            fn tool_registry(&self) -> &Self::ToolRegistry {
-                &self.tools
-            }
+    #[cfg(feature = "ui")]
+    #[tokio::test]
+    async fn test_ui_is_served_under_base_path() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use tower::ServiceExt;
+
+        let app = mcp_routes_at("/api/mcp", McpServerWrapper::new(test_server()));
+        let response = app
+            .oneshot(Request::get("/api/mcp/ui").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let page = String::from_utf8(body.to_vec()).unwrap();
+        assert!(page.contains(r#"const ENDPOINT = "/api/mcp";"#));
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document_is_served_under_base_path() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use tower::ServiceExt;
+
+        let mut tools = InMemoryToolRegistry::new();
+        tools.register_tool(
+            McpTool::new(
+                "echo",
+                "Echo a message",
+                serde_json::json!({"type": "object", "properties": {"message": {"type": "string"}}}),
+                "test",
+            )
+            .public(),
+        );
+        tools.register_tool(McpTool::new(
+            "internal",
+            "Needs authentication",
+            serde_json::json!({"type": "object"}),
+            "test",
+        ));
+        let server = server::McpServer::new(
+            McpServerConfig::default(),
+            TestState {
+                tools,
+                auth: TestAuth,
+            },
+        );
+
+        let app = mcp_routes_at("/api/mcp", McpServerWrapper::new(server));
+        let response = app
+            .oneshot(
+                Request::get("/api/mcp/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(document["openapi"], "3.1.0");
+        assert!(document["paths"]["/api/mcp"]["post"].is_object());
+        assert!(document["paths"]["/api/mcp/sse"]["get"].is_object());
+        let tools = document["x-mcp"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0]["name"], "echo");
+        assert_eq!(tools[1]["name"], "internal");
+        assert_eq!(
+            tools[0]["inputSchema"]["properties"]["message"]["type"],
+            "string"
+        );
+        assert!(document["x-mcp"]["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|method| method["name"] == "tools/call"));
+    }
 
-            fn auth_manager(&self) -> &Self::AuthManager {
-                &self.auth
+    #[cfg(feature = "sse")]
+    #[tokio::test]
+    async fn test_legacy_sse_flow() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use futures_util::StreamExt;
+        use tower::ServiceExt;
+
+        let app = mcp_routes_at("/api/mcp", McpServerWrapper::new(test_server()));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/api/mcp/sse").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let mut events = response.into_body().into_data_stream();
+
+        let first = events.next().await.unwrap().unwrap();
+        let first = String::from_utf8(first.to_vec()).unwrap();
+        assert!(first.starts_with("event: endpoint\n"));
+        let endpoint = first
+            .lines()
+            .find_map(|line| line.strip_prefix("data: "))
+            .unwrap()
+            .to_string();
+        assert!(endpoint.starts_with("/api/mcp/messages?session_id="));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post(&endpoint)
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"jsonrpc":"2.0","method":"ping","id":7}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::ACCEPTED);
+
+        let message = events.next().await.unwrap().unwrap();
+        let message = String::from_utf8(message.to_vec()).unwrap();
+        assert!(message.starts_with("event: message\n"));
+        assert!(message.contains("pong"));
+
+        let response = app
+            .oneshot(
+                Request::post("/api/mcp/messages?session_id=unknown")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"jsonrpc":"2.0","method":"ping","id":8}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_session_resumption() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use futures_util::StreamExt;
+        use std::{sync::Arc, time::Duration};
+        use tower::ServiceExt;
+        use transport::InMemoryEventStore;
+
+        let session_manager = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(3600))),
+            Duration::from_secs(300),
+            Duration::from_secs(60),
+        );
+        let app = mcp_routes_at(
+            "/mcp",
+            McpServerWrapper::with_session_manager(test_server(), session_manager.clone()),
+        );
+        let post = |body: &'static str, session_id: Option<&str>| {
+            let mut request = Request::post("/mcp").header("content-type", "application/json");
+            if let Some(session_id) = session_id {
+                request = request.header("mcp-session-id", session_id);
             }
-        }
+            request.body(Body::from(body)).unwrap()
+        };
 
-        let config = McpServerConfig::default();
-        let state = TestState {
-            tools: InMemoryToolRegistry::new(),
-            auth: TestAuth,
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"initialize","id":1,"params":{"protocolVersion":"2025-03-26","capabilities":{}}}"#,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let session_id = response.headers()["mcp-session-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"ping","id":2}"#,
+                Some(&session_id),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let state = session_manager.session_state(&session_id).await.unwrap();
+        assert_eq!(state.protocol_version.as_deref(), Some("2025-03-26"));
+        assert!(state.client_capabilities.is_some());
+        assert!(state.pending_requests.is_empty());
+
+        // Reconnect after missing the ping response
+        let events = session_manager
+            .get_events_since(&session_id, None)
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::get("/mcp")
+                    .header("accept", "text/event-stream")
+                    .header("mcp-session-id", &session_id)
+                    .header("last-event-id", &events[0].id)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        let replayed = response
+            .into_body()
+            .into_data_stream()
+            .next()
+            .await
+            .unwrap()
+            .unwrap();
+        let replayed = String::from_utf8(replayed.to_vec()).unwrap();
+        assert!(replayed.contains(&format!("id: {}", events[1].id)));
+        assert!(replayed.contains("pong"));
+
+        // Unknown and expired sessions must start over with initialize
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"ping","id":3}"#,
+                Some("expired"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+
+        let response = app
+            .oneshot(
+                Request::get("/mcp")
+                    .header("accept", "text/event-stream")
+                    .header("mcp-session-id", "expired")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_session_affinity_headers_and_ownership() {
+        use axum::{body::Body, http::Request};
+        use axum_integration::{mcp_routes_at, McpServerWrapper};
+        use server::SessionAffinityConfig;
+        use std::{sync::Arc, time::Duration};
+        use tower::ServiceExt;
+        use transport::InMemoryEventStore;
+
+        let session_manager = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(3600))),
+            Duration::from_secs(300),
+            Duration::from_secs(60),
+        )
+        .with_node_id("node-a");
+        let config = test_server().config().clone().with_session_affinity(
+            SessionAffinityConfig::default()
+                .with_cookie("mcp_session")
+                .enforce_ownership()
+                .with_peer("node-b", "http://node-b.internal/mcp"),
+        );
+        let server = server::McpServer::new(
+            config,
+            TestState {
+                tools: InMemoryToolRegistry::new(),
+                auth: TestAuth,
+            },
+        );
+        let app = mcp_routes_at(
+            "/mcp",
+            McpServerWrapper::with_session_manager(server, session_manager),
+        );
+        let post = |body: &'static str, session_id: &str| {
+            let mut request = Request::post("/mcp").header("content-type", "application/json");
+            if !session_id.is_empty() {
+                request = request.header("mcp-session-id", session_id);
+            }
+            request.body(Body::from(body)).unwrap()
         };
 
-        let _config = config;
-        let _state = state;
-        // Basic trait implementation should succeed
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"initialize","id":1,"params":{"protocolVersion":"2025-03-26","capabilities":{}}}"#,
+                "",
+            ))
+            .await
+            .unwrap();
+        let session_id = response.headers()["mcp-session-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(session_id.starts_with("node-a."));
+        assert_eq!(
+            response.headers()["set-cookie"],
+            format!(
+                "mcp_session={}; Path=/mcp; HttpOnly; SameSite=Lax",
+                session_id
+            )
+            .as_str()
+        );
+
+        // Later responses echo the session for balancers that learn from them
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"ping","id":2}"#,
+                &session_id,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["mcp-session-id"], session_id.as_str());
+        assert!(response.headers().get("set-cookie").is_none());
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"ping","id":3}"#,
+                "node-b.1234",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::TEMPORARY_REDIRECT
+        );
+        assert_eq!(response.headers()["location"], "http://node-b.internal/mcp");
+        assert_eq!(response.headers()["mcp-session-owner"], "node-b");
+
+        let response = app
+            .oneshot(post(
+                r#"{"jsonrpc":"2.0","method":"ping","id":4}"#,
+                "node-c.1234",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::MISDIRECTED_REQUEST
+        );
     }
 }
@@ -0,0 +1,666 @@
+//! Generating new server projects
+//!
+//! A [`Scaffold`] describes a server project to start from: which transports
+//! it serves, how clients authenticate and whether it offers resources and
+//! prompts besides its example tools. [`Scaffold::files`] renders the
+//! project and [`Scaffold::write_to`] creates it on disk:
+//!
+//! ```rust,no_run
+//! use axum_mcp::scaffold::{Scaffold, ScaffoldAuth, ScaffoldTransport};
+//!
+//! Scaffold::new("weather-server")
+//!     .with_transport(ScaffoldTransport::Both)
+//!     .with_auth(ScaffoldAuth::Bearer)
+//!     .with_prompts(true)
+//!     .write_to("weather-server")
+//!     .unwrap();
+//! ```
+//!
+//! The `cargo-mcp` binary (feature `cli`) does the same as `cargo mcp new`.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::{McpError, McpResult};
+
+/// Environment variable holding the token of generated bearer-token servers
+pub const TOKEN_ENV: &str = "MCP_TOKEN";
+
+/// Address generated HTTP servers listen on
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:3000";
+
+/// Transports a generated server listens on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaffoldTransport {
+    /// StreamableHTTP on [`DEFAULT_ADDRESS`]
+    #[default]
+    Http,
+    /// Newline-delimited JSON-RPC on stdin and stdout
+    Stdio,
+    /// Both of the above
+    Both,
+}
+
+impl ScaffoldTransport {
+    fn http(self) -> bool {
+        matches!(self, ScaffoldTransport::Http | ScaffoldTransport::Both)
+    }
+
+    fn stdio(self) -> bool {
+        matches!(self, ScaffoldTransport::Stdio | ScaffoldTransport::Both)
+    }
+}
+
+/// How clients of a generated server authenticate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaffoldAuth {
+    /// Every client is admitted
+    #[default]
+    None,
+    /// HTTP clients must send the token in [`TOKEN_ENV`] as a bearer token
+    Bearer,
+}
+
+/// A file of a generated project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldFile {
+    /// Path relative to the project directory
+    pub path: PathBuf,
+    /// File contents
+    pub contents: String,
+}
+
+/// Description of a server project to generate
+#[derive(Debug, Clone)]
+pub struct Scaffold {
+    name: String,
+    transport: ScaffoldTransport,
+    auth: ScaffoldAuth,
+    resources: bool,
+    prompts: bool,
+    dependency: String,
+}
+
+impl Scaffold {
+    /// A project for a crate called `name`, serving example tools over HTTP
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            transport: ScaffoldTransport::default(),
+            auth: ScaffoldAuth::default(),
+            resources: false,
+            prompts: false,
+            dependency: format!("version = \"{}\"", crate_version()),
+        }
+    }
+
+    /// Serve the given transports
+    pub fn with_transport(mut self, transport: ScaffoldTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Authenticate clients with `auth`
+    pub fn with_auth(mut self, auth: ScaffoldAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Include an example resource registry
+    pub fn with_resources(mut self, resources: bool) -> Self {
+        self.resources = resources;
+        self
+    }
+
+    /// Include an example prompt registry
+    pub fn with_prompts(mut self, prompts: bool) -> Self {
+        self.prompts = prompts;
+        self
+    }
+
+    /// Depend on a local checkout of this crate instead of the released version
+    pub fn with_path_dependency(mut self, path: impl AsRef<Path>) -> Self {
+        self.dependency = format!("path = {:?}", path.as_ref().display().to_string());
+        self
+    }
+
+    /// Check that the options describe a project that can be generated
+    pub fn validate(&self) -> McpResult<()> {
+        let valid_name = self
+            .name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(McpError::Validation {
+                message: format!(
+                    "'{}' is not a valid crate name; use letters, digits, '-' and '_', starting with a letter",
+                    self.name
+                ),
+            });
+        }
+        if self.auth == ScaffoldAuth::Bearer && !self.transport.http() {
+            return Err(McpError::Configuration {
+                message: "Bearer token authentication needs the HTTP transport".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Render the project's files
+    pub fn files(&self) -> McpResult<Vec<ScaffoldFile>> {
+        self.validate()?;
+        Ok(vec![
+            self.file("Cargo.toml", self.manifest()),
+            self.file("src/main.rs", self.main()),
+            self.file("README.md", self.readme()),
+            self.file(".gitignore", "/target\n".to_string()),
+        ])
+    }
+
+    /// Create the project in `dir`, which must not exist or be empty
+    ///
+    /// Returns the paths of the files written.
+    pub fn write_to(&self, dir: impl AsRef<Path>) -> McpResult<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        let files = self.files()?;
+        if dir
+            .read_dir()
+            .is_ok_and(|mut entries| entries.next().is_some())
+        {
+            return Err(McpError::Configuration {
+                message: format!("{} already exists and is not empty", dir.display()),
+            });
+        }
+
+        let mut written = Vec::with_capacity(files.len());
+        for file in files {
+            let path = dir.join(&file.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, file.contents)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    fn file(&self, path: &str, contents: String) -> ScaffoldFile {
+        ScaffoldFile {
+            path: PathBuf::from(path),
+            contents,
+        }
+    }
+
+    /// URI scheme of the example resources: the crate name, which is a valid
+    /// scheme once underscores are replaced
+    fn scheme(&self) -> String {
+        self.name.to_ascii_lowercase().replace('_', "-")
+    }
+
+    fn manifest(&self) -> String {
+        MANIFEST
+            .replace("{name}", &self.name)
+            .replace("{dependency}", &self.dependency)
+    }
+
+    fn main(&self) -> String {
+        let http = self.transport.http();
+        let bearer = self.auth == ScaffoldAuth::Bearer;
+
+        let mut server_imports = vec!["Listener", "McpServer", "TransportSet"];
+        if self.resources {
+            server_imports.extend([
+                "InMemoryResourceRegistry",
+                "Resource",
+                "ResourceRegistry",
+                "UriSchemeConfig",
+            ]);
+        }
+        if self.prompts {
+            server_imports.extend([
+                "InMemoryPromptRegistry",
+                "PromptParameter",
+                "PromptRegistry",
+            ]);
+        }
+        if bearer {
+            server_imports.push("listeners::AUTHORIZATION_KEY");
+        }
+        // Modules before types, as rustfmt orders them
+        server_imports.sort_unstable_by_key(|import| (!import.contains("::"), *import));
+
+        let mut out = String::new();
+        out.push_str(&MAIN_HEADER.replace("{name}", &self.name));
+        match self.transport {
+            ScaffoldTransport::Http => out.push_str(&format!(
+                "//! Run with `cargo run` and connect clients to http://{}/mcp.\n",
+                DEFAULT_ADDRESS
+            )),
+            ScaffoldTransport::Stdio => out.push_str(
+                "//! Clients start it as a child process and talk to it on stdin and stdout.\n",
+            ),
+            ScaffoldTransport::Both => out.push_str(&format!(
+                "//! Run with `cargo run` and connect clients to http://{}/mcp, or start it\n\
+                 //! as a child process and talk to it on stdin and stdout.\n",
+                DEFAULT_ADDRESS
+            )),
+        }
+        out.push_str(&format!(
+            "\nuse axum_mcp::{{\n    prelude::*,\n    protocol::ServerInfo,\n    server::{},\n}};\nuse std::collections::HashMap;\n",
+            import_list(&server_imports)
+        ));
+        if http {
+            out.push_str(&format!(
+                "\n/// Address the HTTP listener binds to\nconst ADDRESS: &str = \"{}\";\n",
+                DEFAULT_ADDRESS
+            ));
+        }
+        if bearer {
+            out.push_str(&format!(
+                "\n/// Environment variable holding the token HTTP clients must send\nconst TOKEN_ENV: &str = \"{}\";\n",
+                TOKEN_ENV
+            ));
+        }
+        out.push_str(MAIN_TOOLS);
+        out.push_str(MAIN_OPEN_AUTH);
+        if bearer {
+            out.push_str(MAIN_BEARER_AUTH);
+        }
+        if self.resources {
+            out.push_str(&MAIN_RESOURCES.replace("{scheme}", &self.scheme()));
+        }
+        if self.prompts {
+            out.push_str(MAIN_PROMPTS);
+        }
+
+        out.push_str(
+            "\n/// Registries and authentication of the server\n#[derive(Clone)]\nstruct State {\n    tools: Tools,\n    auth: OpenAuth,\n",
+        );
+        if self.resources {
+            out.push_str("    resources: InMemoryResourceRegistry,\n");
+        }
+        if self.prompts {
+            out.push_str("    prompts: InMemoryPromptRegistry,\n");
+        }
+        out.push_str(MAIN_STATE);
+        if self.resources {
+            out.push_str(
+                "\n    fn resource_registry(&self) -> Option<&dyn ResourceRegistry> {\n        Some(&self.resources)\n    }\n",
+            );
+        }
+        if self.prompts {
+            out.push_str(
+                "\n    fn prompt_registry(&self) -> Option<&dyn PromptRegistry> {\n        Some(&self.prompts)\n    }\n",
+            );
+        }
+        out.push_str(MAIN_SERVER_INFO);
+
+        out.push_str(MAIN_START);
+        if self.resources {
+            out.push_str("        resources: resources(),\n");
+        }
+        if self.prompts {
+            out.push_str("        prompts: prompts(),\n");
+        }
+        out.push_str("    };\n\n    let transports = TransportSet::new(McpServer::new(McpServerConfig::default(), state))");
+        if http {
+            out.push_str("\n        .listen(Listener::tcp(\"http\", ADDRESS)");
+            if bearer {
+                out.push_str(".with_auth(TokenAuth::from_env()?)");
+            }
+            out.push(')');
+        }
+        if self.transport.stdio() {
+            out.push_str("\n        .listen(Listener::stdio(\"stdio\"))");
+        }
+        out.push_str(MAIN_SERVE);
+        out
+    }
+
+    fn readme(&self) -> String {
+        let mut out = format!(
+            "# {}\n\nAn MCP server built with [axum-mcp](https://crates.io/crates/axum-mcp).\n\n## Running\n\n```bash\n",
+            self.name
+        );
+        if self.auth == ScaffoldAuth::Bearer {
+            out.push_str(&format!("export {}=change-me\n", TOKEN_ENV));
+        }
+        out.push_str("cargo run\n```\n\n");
+        if self.transport.http() {
+            out.push_str(&format!(
+                "HTTP clients connect to `http://{}/mcp`",
+                DEFAULT_ADDRESS
+            ));
+            if self.auth == ScaffoldAuth::Bearer {
+                out.push_str(&format!(" with `Authorization: Bearer ${}`", TOKEN_ENV));
+            }
+            out.push_str(".\n");
+        }
+        if self.transport.stdio() {
+            out.push_str(&format!(
+                "Clients can also start `target/release/{}` and talk to it on stdin and stdout.\n",
+                self.name
+            ));
+        }
+        out.push_str(
+            "\n## Extending\n\nTools are defined in `tools()` and run in `Tools::execute_tool` in\n`src/main.rs`.",
+        );
+        if self.resources {
+            out.push_str(" Resources are added in `resources()`.");
+        }
+        if self.prompts {
+            out.push_str(" Prompts are added in `prompts()`.");
+        }
+        out.push('\n');
+        out
+    }
+}
+
+/// `{a, b}` on one line if it fits, otherwise wrapped as rustfmt would
+fn import_list(imports: &[&str]) -> String {
+    const MAX_WIDTH: usize = 100;
+    const INDENT: &str = "        ";
+
+    let joined = imports.join(", ");
+    if "    server::{".len() + joined.len() + "},".len() <= MAX_WIDTH {
+        return format!("{{{}}}", joined);
+    }
+    let mut out = String::from("{\n");
+    let mut line = String::new();
+    for import in imports {
+        if !line.is_empty() && INDENT.len() + line.len() + import.len() + 1 > MAX_WIDTH {
+            out.push_str(INDENT);
+            out.push_str(line.trim_end());
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(import);
+        line.push_str(", ");
+    }
+    out.push_str(INDENT);
+    out.push_str(line.trim_end_matches(' '));
+    out.push_str("\n    }");
+    out
+}
+
+/// Version requirement on this crate for generated projects
+fn crate_version() -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut parts = version.split('.');
+    match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => format!("{}.{}", major, minor),
+        _ => version.to_string(),
+    }
+}
+
+const MANIFEST: &str = r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+axum-mcp = { {dependency}, default-features = false, features = ["axum"] }
+async-trait = "0.1"
+serde_json = "1.0"
+tokio = { version = "1.0", features = ["full"] }
+tracing-subscriber = "0.3"
+"#;
+
+const MAIN_HEADER: &str = "//! {name}: an MCP server\n//!\n";
+
+const MAIN_TOOLS: &str = r#"
+/// Tools offered by this server
+fn tools() -> Vec<McpTool> {
+    vec![
+        McpTool::new(
+            "echo",
+            "Echo back a message",
+            json!({
+                "type": "object",
+                "properties": {"message": {"type": "string", "description": "Message to echo"}},
+                "required": ["message"]
+            }),
+            "examples",
+        )
+        .public(),
+        McpTool::new(
+            "add",
+            "Add two numbers",
+            json!({
+                "type": "object",
+                "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+                "required": ["a", "b"]
+            }),
+            "examples",
+        )
+        .public(),
+    ]
+}
+
+/// Runs the tools returned by [`tools`]
+#[derive(Clone)]
+struct Tools;
+
+#[async_trait]
+impl ToolRegistry for Tools {
+    async fn list_tools(&self, _context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        Ok(tools().into_iter().map(|tool| tool.tool).collect())
+    }
+
+    async fn get_tool(&self, name: &str, _context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        Ok(tools().into_iter().find(|tool| tool.tool.name == name))
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        let arguments = context.arguments.unwrap_or_default();
+        let text = match name {
+            "echo" => arguments["message"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            "add" => {
+                let a = arguments["a"].as_f64().unwrap_or_default();
+                let b = arguments["b"].as_f64().unwrap_or_default();
+                (a + b).to_string()
+            }
+            _ => {
+                return Err(McpError::ToolNotFound {
+                    name: name.to_string(),
+                })
+            }
+        };
+        Ok(ToolsCallResult {
+            content: vec![ToolContent::Text { text }],
+            is_error: false,
+            metadata: HashMap::new(),
+        })
+    }
+
+    async fn can_access_tool(&self, name: &str, _context: &SecurityContext) -> bool {
+        tools().iter().any(|tool| tool.tool.name == name)
+    }
+}
+"#;
+
+const MAIN_OPEN_AUTH: &str = r#"
+/// Admits every client
+#[derive(Clone)]
+struct OpenAuth;
+
+#[async_trait]
+impl McpAuth for OpenAuth {
+    async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+        Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+    }
+
+    async fn authorize(&self, _context: &SecurityContext, _resource: &str, _action: &str) -> bool {
+        true
+    }
+}
+"#;
+
+const MAIN_BEARER_AUTH: &str = r#"
+/// Admits HTTP clients sending the expected bearer token
+struct TokenAuth {
+    expected: String,
+}
+
+impl TokenAuth {
+    fn from_env() -> McpResult<Self> {
+        let token = std::env::var(TOKEN_ENV).map_err(|_| McpError::Configuration {
+            message: format!("Set {} to the token clients must send", TOKEN_ENV),
+        })?;
+        Ok(Self {
+            expected: format!("Bearer {}", token),
+        })
+    }
+}
+
+#[async_trait]
+impl McpAuth for TokenAuth {
+    async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+        match client.metadata.get(AUTHORIZATION_KEY) {
+            Some(authorization) if *authorization == self.expected => {
+                Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+            }
+            _ => Err(McpError::Authentication {
+                message: "Missing or invalid bearer token".to_string(),
+            }),
+        }
+    }
+
+    async fn authorize(&self, _context: &SecurityContext, _resource: &str, _action: &str) -> bool {
+        true
+    }
+}
+"#;
+
+const MAIN_RESOURCES: &str = r#"
+/// Resources offered by this server
+fn resources() -> InMemoryResourceRegistry {
+    let mut resources =
+        InMemoryResourceRegistry::new(UriSchemeConfig::new("{scheme}", "Documents of this server"));
+    resources.add_resource(Resource::from_bytes(
+        "{scheme}://docs/readme",
+        "README",
+        b"Replace this with your own resources.\n",
+        Some("text/markdown"),
+    ));
+    resources
+}
+"#;
+
+const MAIN_PROMPTS: &str = r#"
+/// Prompts offered by this server
+fn prompts() -> InMemoryPromptRegistry {
+    let mut prompts = InMemoryPromptRegistry::new();
+    prompts.add_workflow_prompt(
+        "summarize",
+        "Summarize a text",
+        "You write short, accurate summaries.",
+        "Summarize the following text:\n\n{{text}}",
+        vec![PromptParameter {
+            name: "text".to_string(),
+            description: "Text to summarize".to_string(),
+            required: true,
+            schema: None,
+            default: None,
+        }],
+    );
+    prompts
+}
+"#;
+
+const MAIN_STATE: &str = r#"}
+
+impl McpServerState for State {
+    type ToolRegistry = Tools;
+    type AuthManager = OpenAuth;
+
+    fn tool_registry(&self) -> &Self::ToolRegistry {
+        &self.tools
+    }
+
+    fn auth_manager(&self) -> &Self::AuthManager {
+        &self.auth
+    }
+"#;
+
+const MAIN_SERVER_INFO: &str = r#"
+    fn server_info(&self) -> ServerInfo {
+        ServerInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+"#;
+
+const MAIN_START: &str = r#"
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Logs go to stderr, keeping stdout free for the stdio transport
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .init();
+
+    let state = State {
+        tools: Tools,
+        auth: OpenAuth,
+"#;
+
+const MAIN_SERVE: &str = r#";
+    transports
+        .serve(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_files() {
+        let files = Scaffold::new("weather_server")
+            .with_transport(ScaffoldTransport::Both)
+            .with_auth(ScaffoldAuth::Bearer)
+            .with_resources(true)
+            .with_prompts(true)
+            .files()
+            .unwrap();
+        let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            ["Cargo.toml", "src/main.rs", "README.md", ".gitignore"].map(PathBuf::from)
+        );
+
+        let main = &files[1].contents;
+        assert!(main.contains(".with_auth(TokenAuth::from_env()?)"));
+        assert!(main.contains("Listener::stdio(\"stdio\")"));
+        assert!(main.contains("\"weather-server://docs/readme\""));
+        assert!(main.contains("fn prompt_registry"));
+        assert!(files[0].contents.contains("name = \"weather_server\""));
+
+        let stdio_bearer = Scaffold::new("local")
+            .with_transport(ScaffoldTransport::Stdio)
+            .with_auth(ScaffoldAuth::Bearer);
+        assert!(stdio_bearer.files().is_err());
+        assert!(Scaffold::new("1st server").files().is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Guest access without credentials
+//!
+//! Demo and public servers often let anyone in. Rather than handing every
+//! client a [`SecurityContext::system`] context, [`AnonymousAuth`] gives them
+//! a [`SecurityContext::guest`] context:
+//!
+//! - anonymous, so tools requiring authentication stay hidden,
+//! - limited to the `public` exposure profile, or those of
+//!   [`AnonymousConfig::profiles`],
+//! - rate limited to [`AnonymousConfig::requests_per_minute`] per remote IP
+//!   address, whatever the port of the connection,
+//! - only authorized to `list` and `read`.
+//!
+//! Use it as a listener's auth, or with
+//! [`Listener::with_guest_access`](crate::server::Listener::with_guest_access)
+//! to admit clients without an `Authorization` header as guests next to the
+//! listener's own auth.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{
+    clock::Clock,
+    error::McpResult,
+    security::{
+        auth::{EXPOSURE_KEY, LIST_ACTION},
+        fingerprint::REMOTE_IP_KEY,
+        ClientContext, McpAuth, RateLimitConfig, RateLimiter, SecurityContext,
+    },
+    server::exposure::PUBLIC,
+};
+
+/// Guest access settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnonymousConfig {
+    /// Requests each guest may send per minute
+    pub requests_per_minute: u32,
+
+    /// Exposure profiles guests see
+    pub profiles: Vec<String>,
+}
+
+impl Default for AnonymousConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 30,
+            profiles: vec![PUBLIC.to_string()],
+        }
+    }
+}
+
+/// An [`McpAuth`] admitting every client as a rate limited guest
+///
+/// Clones share the rate limits.
+#[derive(Clone)]
+pub struct AnonymousAuth {
+    config: AnonymousConfig,
+    limiter: Arc<RateLimiter>,
+}
+
+impl AnonymousAuth {
+    /// Admit guests with the default settings
+    pub fn new() -> Self {
+        Self::with_config(AnonymousConfig::default())
+    }
+
+    /// Admit guests with the given settings
+    pub fn with_config(config: AnonymousConfig) -> Self {
+        let limiter = RateLimiter::new(RateLimitConfig::per_minute(config.requests_per_minute));
+        Self {
+            config,
+            limiter: Arc::new(limiter),
+        }
+    }
+
+    /// Measure rate limit windows on `clock` instead of real time
+    pub fn with_clock(self, clock: Clock) -> Self {
+        let limit = RateLimitConfig::per_minute(self.config.requests_per_minute);
+        Self {
+            limiter: Arc::new(RateLimiter::new(limit).with_clock(clock)),
+            ..self
+        }
+    }
+
+    /// Settings of this auth
+    pub fn config(&self) -> &AnonymousConfig {
+        &self.config
+    }
+}
+
+impl Default for AnonymousAuth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl McpAuth for AnonymousAuth {
+    async fn authenticate(&self, client_info: &ClientContext) -> McpResult<SecurityContext> {
+        let key = client_info
+            .metadata
+            .get(REMOTE_IP_KEY)
+            .or(client_info.session_id.as_ref())
+            .map_or("anonymous", String::as_str);
+        self.limiter.check_rate_limit(key).await?;
+
+        let mut context = SecurityContext::guest(client_info.clone());
+        context
+            .client
+            .metadata
+            .insert(EXPOSURE_KEY.to_string(), self.config.profiles.join(","));
+        Ok(context)
+    }
+
+    async fn authorize(&self, context: &SecurityContext, _resource: &str, action: &str) -> bool {
+        !context.is_guest() || action == LIST_ACTION || action == "read"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{security::fingerprint::REMOTE_ADDR_KEY, server::exposure::INTERNAL};
+
+    #[tokio::test]
+    async fn test_guests_are_restricted() {
+        let auth = AnonymousAuth::with_config(AnonymousConfig {
+            requests_per_minute: 2,
+            ..Default::default()
+        })
+        .with_clock(Clock::manual());
+        let mut client = ClientContext {
+            client_id: Some("admin".to_string()),
+            ..Default::default()
+        };
+        let connect = |client: &mut ClientContext, port: u16| {
+            client
+                .metadata
+                .insert(REMOTE_ADDR_KEY.to_string(), format!("203.0.113.7:{}", port));
+            client
+                .metadata
+                .insert(REMOTE_IP_KEY.to_string(), "203.0.113.7".to_string());
+        };
+        connect(&mut client, 5000);
+
+        let context = auth.authenticate(&client).await.unwrap();
+        assert!(context.is_guest() && context.is_anonymous() && !context.is_system());
+        assert_eq!(context.principal(), "anonymous");
+        assert!(context.exposes(PUBLIC));
+        assert!(!context.exposes(INTERNAL));
+        assert!(auth.authorize(&context, "tool:search", LIST_ACTION).await);
+        assert!(!auth.authorize(&context, "tool:search", "write").await);
+
+        // New connections from the same address share the limit
+        connect(&mut client, 5001);
+        assert!(auth.authenticate(&client).await.is_ok());
+        connect(&mut client, 5002);
+        assert!(auth.authenticate(&client).await.is_err());
+    }
+}
@@ -5,8 +5,11 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::permissions::ClientPermissions;
-use crate::error::{McpError, McpResult};
+use super::{fingerprint::ClientFingerprint, permissions::ClientPermissions};
+use crate::{
+    error::{McpError, McpResult},
+    server::locale::ClientLocale,
+};
 
 /// Authentication result
 pub type AuthResult<T> = Result<T, AuthError>;
@@ -107,6 +110,9 @@ pub struct ClientContext {
 
     /// Additional client metadata
     pub metadata: HashMap<String, String>,
+
+    /// Client implementation fingerprint captured during `initialize`
+    pub fingerprint: Option<ClientFingerprint>,
 }
 
 impl Default for ClientContext {
@@ -116,10 +122,49 @@ impl Default for ClientContext {
             client_id: None,
             session_id: None,
             metadata: HashMap::new(),
+            fingerprint: None,
         }
     }
 }
 
+/// Client metadata key for the comma separated exposure profiles a client sees
+pub const EXPOSURE_KEY: &str = "exposure";
+
+/// Capability of guest contexts, see [`SecurityContext::guest`]
+pub const GUEST_CAPABILITY: &str = "guest";
+
+/// Capability of service-account contexts, see [`SecurityContext::service_account`]
+pub const SERVICE_ACCOUNT_CAPABILITY: &str = "service_account";
+
+/// Capability allowing a context to [`impersonate`](SecurityContext::impersonate)
+pub const IMPERSONATE_CAPABILITY: &str = "impersonate";
+
+/// Capability letting a context list every tool, resource and prompt
+///
+/// Held by the server's internal service accounts. Registries that hide items
+/// from non-system contexts should list them to contexts with
+/// [`can_list_all`](SecurityContext::can_list_all).
+pub const LIST_ALL_CAPABILITY: &str = "list_all";
+
+/// Capability letting a context read every resource, see
+/// [`can_read_all`](SecurityContext::can_read_all)
+pub const READ_ALL_CAPABILITY: &str = "read_all";
+
+/// Client metadata key for the principal impersonating a context
+pub const IMPERSONATED_BY_KEY: &str = "impersonated_by";
+
+/// Prefix of the resources authorized per tool, followed by the tool's name
+pub const TOOL_RESOURCE_PREFIX: &str = "tool:";
+
+/// Action authorized per tool when `tools/list` is filtered by authorization
+pub const LIST_ACTION: &str = "list";
+
+/// Client metadata key for the principal that delegated a context
+pub const DELEGATED_BY_KEY: &str = "delegated_by";
+
+/// Client metadata key for the service account running a context's work
+pub const EXECUTED_BY_KEY: &str = "executed_by";
+
 /// Security context for authenticated requests
 #[derive(Debug, Clone)]
 pub struct SecurityContext {
@@ -162,6 +207,47 @@ impl SecurityContext {
         }
     }
 
+    /// Create a guest context for a client without credentials
+    ///
+    /// Guests are anonymous, so tools requiring authentication stay hidden,
+    /// but may use the server without a prior `initialize`. Any client ID the
+    /// client claimed is dropped.
+    pub fn guest(client: ClientContext) -> Self {
+        Self {
+            client: ClientContext {
+                client_id: None,
+                ..client
+            },
+            permissions: ClientPermissions::guest(),
+            authenticated: false,
+            authenticated_at: None,
+            capabilities: vec![GUEST_CAPABILITY.to_string(), "initialized".to_string()],
+        }
+    }
+
+    /// Create a context for work the server does on its own, such as a
+    /// scheduled job, attributed to the service account `name`
+    ///
+    /// Service accounts are authenticated but have default permissions, not
+    /// system privileges; add [`IMPERSONATE_CAPABILITY`] to let one act for
+    /// users.
+    pub fn service_account(name: impl Into<String>) -> Self {
+        Self {
+            client: ClientContext {
+                user_agent: "service-account".to_string(),
+                client_id: Some(name.into()),
+                ..Default::default()
+            },
+            permissions: ClientPermissions::default(),
+            authenticated: true,
+            authenticated_at: Some(Utc::now()),
+            capabilities: vec![
+                SERVICE_ACCOUNT_CAPABILITY.to_string(),
+                "initialized".to_string(),
+            ],
+        }
+    }
+
     /// Create an authenticated security context
     pub fn authenticated(client: ClientContext, capabilities: Vec<String>) -> Self {
         Self {
@@ -178,6 +264,11 @@ impl SecurityContext {
         !self.authenticated
     }
 
+    /// Check if the context is a guest context
+    pub fn is_guest(&self) -> bool {
+        self.has_capability(GUEST_CAPABILITY)
+    }
+
     /// Check if the context is authenticated
     pub fn is_authenticated(&self) -> bool {
         self.authenticated
@@ -188,6 +279,136 @@ impl SecurityContext {
         self.capabilities.contains(&"system".to_string())
     }
 
+    /// Identifier of the principal acting through this context
+    ///
+    /// The client ID when known, `system` for system contexts, the session for
+    /// unidentified session clients and `anonymous` otherwise.
+    pub fn principal(&self) -> String {
+        if let Some(client_id) = &self.client.client_id {
+            client_id.clone()
+        } else if self.is_system() {
+            "system".to_string()
+        } else if let Some(session_id) = &self.client.session_id {
+            format!("session:{}", session_id)
+        } else {
+            "anonymous".to_string()
+        }
+    }
+
+    /// Context for acting on behalf of `principal`
+    ///
+    /// The delegated context keeps this context's session and permissions,
+    /// but is attributed to `principal` and records this context's principal
+    /// as the delegator. It loses system privileges.
+    pub fn on_behalf_of(&self, principal: impl Into<String>) -> Self {
+        let mut context = self.clone();
+        context
+            .client
+            .metadata
+            .insert(DELEGATED_BY_KEY.to_string(), self.principal());
+        context.client.client_id = Some(principal.into());
+        context
+            .capabilities
+            .retain(|capability| capability != "system");
+        context
+    }
+
+    /// Context for running as `principal`, for system contexts and those with
+    /// [`IMPERSONATE_CAPABILITY`]
+    ///
+    /// Unlike [`on_behalf_of`](Self::on_behalf_of), which is checked with the
+    /// auth's [`authorize_delegation`](McpAuth::authorize_delegation), the
+    /// right to impersonate is carried by the context itself. The
+    /// impersonated context keeps this context's session but none of its
+    /// rights: it has default permissions and no capabilities besides
+    /// `initialized`, so it can neither impersonate further nor act as
+    /// system. It records this context's principal as the impersonator.
+    pub fn impersonate(&self, principal: impl Into<String>) -> McpResult<Self> {
+        let principal = principal.into();
+        if !self.is_system() && !self.has_capability(IMPERSONATE_CAPABILITY) {
+            return Err(McpError::Authorization {
+                message: format!("{} may not impersonate {}", self.principal(), principal),
+            });
+        }
+        // Only the connection carries over, none of this context's rights
+        let mut client = self.client.clone();
+        client
+            .metadata
+            .insert(IMPERSONATED_BY_KEY.to_string(), self.principal());
+        client.client_id = Some(principal);
+        Ok(Self::authenticated(client, vec!["initialized".to_string()]))
+    }
+
+    /// Principal impersonating this context, if any
+    pub fn impersonator(&self) -> Option<&str> {
+        self.client
+            .metadata
+            .get(IMPERSONATED_BY_KEY)
+            .map(String::as_str)
+    }
+
+    /// Context for work the service account `name` runs for this context
+    ///
+    /// Unlike [`impersonate`](Self::impersonate), the context keeps its
+    /// principal, permissions and capabilities; it only records `name` as the
+    /// executor, so the work is attributed to both.
+    pub fn executed_by(&self, name: impl Into<String>) -> Self {
+        let mut context = self.clone();
+        context
+            .client
+            .metadata
+            .insert(EXECUTED_BY_KEY.to_string(), name.into());
+        context
+    }
+
+    /// Service account running this context's work, if any
+    pub fn executor(&self) -> Option<&str> {
+        self.client
+            .metadata
+            .get(EXECUTED_BY_KEY)
+            .map(String::as_str)
+    }
+
+    /// Check if the context is a service-account context
+    pub fn is_service_account(&self) -> bool {
+        self.has_capability(SERVICE_ACCOUNT_CAPABILITY)
+    }
+
+    /// Whether registries should list everything to this context: system
+    /// contexts and contexts with [`LIST_ALL_CAPABILITY`]
+    pub fn can_list_all(&self) -> bool {
+        self.is_system() || self.has_capability(LIST_ALL_CAPABILITY)
+    }
+
+    /// Whether registries should let this context read every resource: system
+    /// contexts and contexts with [`READ_ALL_CAPABILITY`]
+    pub fn can_read_all(&self) -> bool {
+        self.is_system() || self.has_capability(READ_ALL_CAPABILITY)
+    }
+
+    /// Principal that delegated this context, if any
+    pub fn delegator(&self) -> Option<&str> {
+        self.client
+            .metadata
+            .get(DELEGATED_BY_KEY)
+            .map(String::as_str)
+    }
+
+    /// Whether items with exposure `profile` are visible to this client
+    ///
+    /// Without recorded profiles every item is visible.
+    pub fn exposes(&self, profile: &str) -> bool {
+        match self.client.metadata.get(EXPOSURE_KEY) {
+            Some(profiles) => profiles.split(',').any(|exposed| exposed == profile),
+            None => true,
+        }
+    }
+
+    /// Locale and time zone the client sent with `initialize`, if any
+    pub fn locale(&self) -> Option<&ClientLocale> {
+        self.client.fingerprint.as_ref()?.locale.as_ref()
+    }
+
     /// Check if the context has a specific capability
     pub fn has_capability(&self, capability: &str) -> bool {
         self.capabilities.contains(&capability.to_string())
@@ -210,6 +431,41 @@ pub trait McpAuth: Send + Sync {
 
     /// Authorize an action for a security context
     async fn authorize(&self, context: &SecurityContext, resource: &str, action: &str) -> bool;
+
+    /// Authorize several `(resource, action)` pairs at once, in order
+    ///
+    /// Auths backed by a remote policy service can override this to answer
+    /// in one round trip; by default each pair is passed to
+    /// [`authorize`](Self::authorize).
+    async fn authorize_many(
+        &self,
+        context: &SecurityContext,
+        checks: &[(&str, &str)],
+    ) -> Vec<bool> {
+        let mut decisions = Vec::with_capacity(checks.len());
+        for (resource, action) in checks {
+            decisions.push(self.authorize(context, resource, action).await);
+        }
+        decisions
+    }
+
+    /// Whether `context` may act on behalf of `principal`
+    ///
+    /// Checked for batch items with `onBehalfOf`, e.g. a gateway fanning out
+    /// work for its end users. Delegation is denied by default.
+    async fn authorize_delegation(&self, context: &SecurityContext, principal: &str) -> bool {
+        let _ = (context, principal);
+        false
+    }
+
+    /// Largest request body `authenticate` needs to see, if it needs it at all
+    ///
+    /// Listeners then buffer HTTP request bodies up to this size and pass them
+    /// in the client metadata under
+    /// [`REQUEST_BODY_KEY`](crate::security::signature::REQUEST_BODY_KEY).
+    fn request_body_limit(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// MCP authentication manager
@@ -295,6 +551,7 @@ impl McpAuthManager {
             client_id: Some(key_info.name.clone()),
             session_id: Some(uuid::Uuid::new_v4().to_string()),
             metadata: HashMap::new(),
+            fingerprint: None,
         };
 
         let security_context = SecurityContext {
@@ -421,6 +678,33 @@ mod tests {
         assert!(session.is_none());
     }
 
+    #[test]
+    fn test_service_accounts_impersonate_with_capability() {
+        let mut job = SecurityContext::service_account("nightly-report");
+        assert_eq!(job.principal(), "nightly-report");
+        assert!(job.is_service_account() && !job.is_system());
+        assert!(job.impersonate("alice").is_err());
+
+        job.add_capability(IMPERSONATE_CAPABILITY);
+        let alice = job.impersonate("alice").unwrap();
+        assert_eq!(alice.principal(), "alice");
+        assert_eq!(alice.impersonator(), Some("nightly-report"));
+        assert!(!alice.is_service_account());
+        assert!(alice.impersonate("bob").is_err());
+
+        // System privileges, permissions and roles do not carry over
+        let mut admin = SecurityContext::system();
+        admin.add_capability("admin");
+        let alice = admin.impersonate("alice").unwrap();
+        assert!(!alice.is_system() && !alice.has_capability("admin"));
+        assert!(!alice.permissions.can_read_logs);
+        assert!(alice.permissions.allowed_task_patterns.is_empty());
+
+        let batch = alice.executed_by("batch-executor");
+        assert_eq!(batch.principal(), "alice");
+        assert_eq!(batch.executor(), Some("batch-executor"));
+    }
+
     #[tokio::test]
     async fn test_no_auth() {
         let auth_manager = McpAuthManager::new(McpAuthConfig::None);
@@ -0,0 +1,406 @@
+//! Caching of authorization decisions
+//!
+//! [`McpAuth::authorize`] may ask a remote policy service, which adds latency
+//! to every check and, when `tools/list` is filtered by authorization, one
+//! check per tool. [`CachedAuth`] wraps another [`McpAuth`] and remembers its
+//! decisions by principal, resource and action for
+//! [`AuthorizationCacheConfig::ttl`]. Decisions are also keyed on the
+//! standing of the context: whether it is authenticated and its capabilities,
+//! which mark system, guest and service-account contexts, so a context never
+//! gets a decision made for another kind of context with the same principal. [`McpAuth::authorize_many`] is answered
+//! from the cache where possible and forwards only the misses, in one call.
+//!
+//! Anonymous contexts share their principal, and delegated and impersonated
+//! contexts carry another principal's ID for the principal acting through
+//! them, so their decisions are neither read from nor written to the cache.
+//!
+//! When policies change, drop stale decisions through the
+//! [`AuthorizationCache`] handle from [`CachedAuth::cache`], per principal,
+//! per resource, or all at once.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::McpResult,
+    security::{ClientContext, McpAuth, SecurityContext},
+};
+
+/// Authorization cache settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthorizationCacheConfig {
+    /// How long a decision is reused; zero disables caching
+    #[serde(with = "humantime_serde")]
+    pub ttl: Duration,
+
+    /// Most decisions remembered; expired ones are evicted first, then all
+    pub max_entries: usize,
+}
+
+impl Default for AuthorizationCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            max_entries: 100_000,
+        }
+    }
+}
+
+/// Principal, standing, resource and action of a decision
+type DecisionKey = (String, String, String, String);
+
+/// Cached authorization decisions, shared with the [`CachedAuth`] using them
+///
+/// Cloning is cheap; clones share the decisions.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorizationCache {
+    decisions: Arc<RwLock<HashMap<DecisionKey, (bool, Instant)>>>,
+    config: AuthorizationCacheConfig,
+    clock: Clock,
+}
+
+impl AuthorizationCache {
+    /// An empty cache with the given settings
+    pub fn new(config: AuthorizationCacheConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Expire decisions on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The unexpired decision for `context` doing `action` on `resource`
+    pub fn get(&self, context: &SecurityContext, resource: &str, action: &str) -> Option<bool> {
+        let key = decision_key(context, resource, action);
+        let decisions = self.decisions.read().unwrap();
+        match decisions.get(&key) {
+            Some((allowed, expires)) if *expires > self.clock.now() => Some(*allowed),
+            _ => None,
+        }
+    }
+
+    /// Remember a decision
+    pub fn insert(&self, context: &SecurityContext, resource: &str, action: &str, allowed: bool) {
+        if self.config.ttl.is_zero() || self.config.max_entries == 0 {
+            return;
+        }
+        let now = self.clock.now();
+        let mut decisions = self.decisions.write().unwrap();
+        if decisions.len() >= self.config.max_entries {
+            decisions.retain(|_, (_, expires)| *expires > now);
+            if decisions.len() >= self.config.max_entries {
+                decisions.clear();
+            }
+        }
+        decisions.insert(
+            decision_key(context, resource, action),
+            (allowed, now + self.config.ttl),
+        );
+    }
+
+    /// Drop the decisions about `principal`, e.g. after changing its roles
+    pub fn invalidate_principal(&self, principal: &str) {
+        let mut decisions = self.decisions.write().unwrap();
+        decisions.retain(|(p, _, _, _), _| p != principal);
+        debug!("Authorization decisions for {} invalidated", principal);
+    }
+
+    /// Drop the decisions about `resource`, e.g. after changing its policy
+    pub fn invalidate_resource(&self, resource: &str) {
+        let mut decisions = self.decisions.write().unwrap();
+        decisions.retain(|(_, _, r, _), _| r != resource);
+        debug!("Authorization decisions on {} invalidated", resource);
+    }
+
+    /// Drop all decisions
+    pub fn clear(&self) {
+        self.decisions.write().unwrap().clear();
+    }
+
+    /// Number of decisions remembered, including expired ones
+    pub fn len(&self) -> usize {
+        self.decisions.read().unwrap().len()
+    }
+
+    /// Whether no decisions are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An [`McpAuth`] that caches the authorization decisions of another
+///
+/// Clones share the cache.
+#[derive(Clone)]
+pub struct CachedAuth<A> {
+    inner: A,
+    cache: AuthorizationCache,
+}
+
+impl<A: McpAuth> CachedAuth<A> {
+    /// Cache the decisions of `inner` with the default settings
+    pub fn new(inner: A) -> Self {
+        Self {
+            inner,
+            cache: AuthorizationCache::default(),
+        }
+    }
+
+    /// Use the given settings, dropping decisions cached so far
+    pub fn with_config(mut self, config: AuthorizationCacheConfig) -> Self {
+        self.cache = AuthorizationCache::new(config).with_clock(self.cache.clock.clone());
+        self
+    }
+
+    /// Expire decisions on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.cache = self.cache.with_clock(clock);
+        self
+    }
+
+    /// The cache, for invalidating decisions when policies change
+    pub fn cache(&self) -> &AuthorizationCache {
+        &self.cache
+    }
+
+    /// The wrapped auth
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<A: McpAuth> McpAuth for CachedAuth<A> {
+    async fn authenticate(&self, client_info: &ClientContext) -> McpResult<SecurityContext> {
+        self.inner.authenticate(client_info).await
+    }
+
+    async fn authorize(&self, context: &SecurityContext, resource: &str, action: &str) -> bool {
+        if !cacheable(context) {
+            return self.inner.authorize(context, resource, action).await;
+        }
+        if let Some(allowed) = self.cache.get(context, resource, action) {
+            return allowed;
+        }
+        let allowed = self.inner.authorize(context, resource, action).await;
+        self.cache.insert(context, resource, action, allowed);
+        allowed
+    }
+
+    async fn authorize_many(
+        &self,
+        context: &SecurityContext,
+        checks: &[(&str, &str)],
+    ) -> Vec<bool> {
+        if !cacheable(context) {
+            return self.inner.authorize_many(context, checks).await;
+        }
+        let mut decisions: Vec<Option<bool>> = checks
+            .iter()
+            .map(|(resource, action)| self.cache.get(context, resource, action))
+            .collect();
+        let misses: Vec<(&str, &str)> = checks
+            .iter()
+            .zip(&decisions)
+            .filter(|(_, decision)| decision.is_none())
+            .map(|(check, _)| *check)
+            .collect();
+        if !misses.is_empty() {
+            let mut answers = self
+                .inner
+                .authorize_many(context, &misses)
+                .await
+                .into_iter();
+            for ((resource, action), decision) in checks.iter().zip(&mut decisions) {
+                if decision.is_none() {
+                    // A short answer from the inner auth denies, uncached, the rest
+                    let allowed = answers.next();
+                    if let Some(allowed) = allowed {
+                        self.cache.insert(context, resource, action, allowed);
+                    }
+                    *decision = Some(allowed.unwrap_or(false));
+                }
+            }
+        }
+        decisions.into_iter().map(|d| d.unwrap_or(false)).collect()
+    }
+
+    async fn authorize_delegation(&self, context: &SecurityContext, principal: &str) -> bool {
+        self.inner.authorize_delegation(context, principal).await
+    }
+
+    fn request_body_limit(&self) -> Option<usize> {
+        self.inner.request_body_limit()
+    }
+}
+
+/// Whether decisions for `context` belong to its principal
+///
+/// Anonymous contexts, guests among them, share the `anonymous` principal,
+/// and delegated and impersonated contexts are attributed to another
+/// principal than the one acting.
+fn cacheable(context: &SecurityContext) -> bool {
+    context.is_authenticated() && context.delegator().is_none() && context.impersonator().is_none()
+}
+
+fn decision_key(context: &SecurityContext, resource: &str, action: &str) -> DecisionKey {
+    (
+        context.principal(),
+        standing(context),
+        resource.to_string(),
+        action.to_string(),
+    )
+}
+
+/// Authentication state and sorted capabilities of `context`
+///
+/// Capabilities include `system`, `guest` and `service_account`, so system,
+/// guest and service-account contexts of the same principal stand apart.
+fn standing(context: &SecurityContext) -> String {
+    let mut capabilities: Vec<&str> = context.capabilities.iter().map(String::as_str).collect();
+    capabilities.sort_unstable();
+    capabilities.dedup();
+    format!(
+        "{}:{}",
+        if context.is_authenticated() {
+            "authenticated"
+        } else {
+            "anonymous"
+        },
+        capabilities.join(",")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Allows reads only, counting the checks it answers
+    #[derive(Default)]
+    struct PolicyService {
+        checks: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl McpAuth for PolicyService {
+        async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+        }
+
+        async fn authorize(&self, _: &SecurityContext, _: &str, action: &str) -> bool {
+            self.checks.fetch_add(1, Ordering::SeqCst);
+            action == "read"
+        }
+    }
+
+    /// Allows an action to contexts with a capability of the same name
+    struct CapabilityPolicy;
+
+    #[async_trait]
+    impl McpAuth for CapabilityPolicy {
+        async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+        }
+
+        async fn authorize(&self, context: &SecurityContext, _: &str, action: &str) -> bool {
+            context.has_capability(action)
+        }
+    }
+
+    fn user(id: &str) -> SecurityContext {
+        let client = ClientContext {
+            client_id: Some(id.to_string()),
+            ..Default::default()
+        };
+        SecurityContext::authenticated(client, Vec::new())
+    }
+
+    #[tokio::test]
+    async fn test_decisions_are_cached_until_invalidated() {
+        let clock = Clock::manual();
+        let auth = CachedAuth::new(PolicyService::default()).with_clock(clock.clone());
+        let alice = user("alice");
+
+        assert!(auth.authorize(&alice, "tool:search", "read").await);
+        let decisions = auth
+            .authorize_many(&alice, &[("tool:search", "read"), ("tool:search", "write")])
+            .await;
+        assert_eq!(decisions, vec![true, false]);
+        // Only the miss was forwarded
+        assert_eq!(auth.inner().checks.load(Ordering::SeqCst), 2);
+
+        // Other principals are checked separately
+        assert!(auth.authorize(&user("bob"), "tool:search", "read").await);
+        assert_eq!(auth.inner().checks.load(Ordering::SeqCst), 3);
+
+        auth.cache().invalidate_principal("alice");
+        assert!(auth.authorize(&alice, "tool:search", "read").await);
+        assert_eq!(auth.inner().checks.load(Ordering::SeqCst), 4);
+
+        auth.cache().invalidate_resource("tool:search");
+        assert!(auth.cache().is_empty());
+
+        assert!(auth.authorize(&alice, "tool:search", "read").await);
+        clock.advance(Duration::from_secs(61));
+        assert!(auth.authorize(&alice, "tool:search", "read").await);
+        assert_eq!(auth.inner().checks.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_delegated_contexts_bypass_the_cache() {
+        let auth = CachedAuth::new(CapabilityPolicy);
+        let alice = user("alice");
+        let mut editor = user("bob");
+        editor.add_capability("write");
+        let as_alice = editor.on_behalf_of("alice");
+        let mut admin = SecurityContext::system();
+        admin.add_capability("write");
+        let impersonated = admin.impersonate("alice").unwrap();
+
+        assert!(!auth.authorize(&alice, "doc", "write").await);
+        assert!(auth.authorize(&as_alice, "doc", "write").await);
+        assert_eq!(
+            auth.authorize_many(&as_alice, &[("doc", "write")]).await,
+            vec![true]
+        );
+        // Impersonating alice gives alice's rights, not the impersonator's
+        assert!(!auth.authorize(&impersonated, "doc", "write").await);
+        assert!(!auth.authorize(&alice, "doc", "write").await);
+        assert_eq!(auth.cache().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_decisions_are_kept_per_standing() {
+        let auth = CachedAuth::new(CapabilityPolicy);
+        let alice = user("alice");
+        let mut service = SecurityContext::service_account("alice");
+        service.add_capability("write");
+        let mut guest = SecurityContext::guest(ClientContext::default());
+        guest.add_capability("write");
+
+        assert!(auth.authorize(&service, "doc", "write").await);
+        assert!(!auth.authorize(&alice, "doc", "write").await);
+        assert!(auth.authorize(&guest, "doc", "write").await);
+        assert!(
+            !auth
+                .authorize(&SecurityContext::anonymous(), "doc", "write")
+                .await
+        );
+        // Anonymous contexts are not cached
+        assert_eq!(auth.cache().len(), 2);
+    }
+}
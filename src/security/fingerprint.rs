@@ -0,0 +1,315 @@
+//! Client implementation fingerprinting and user-agent policy
+//!
+//! The `clientInfo` sent with `initialize` is normalized into a
+//! [`ClientFingerprint`] together with transport-level details (transport,
+//! remote address, user agent). A [`ClientPolicy`] can then reject clients by
+//! name, minimum version or user agent, and apply per-client-type rate limits.
+
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::auth::ClientContext;
+use crate::{
+    error::{McpError, McpResult},
+    protocol::InitializeParams,
+    server::locale::ClientLocale,
+};
+
+/// Client metadata key for the transport a request arrived on
+pub const TRANSPORT_KEY: &str = "transport";
+
+/// Client metadata key for the remote address of the connection
+pub const REMOTE_ADDR_KEY: &str = "remote_addr";
+
+/// Client metadata key for the IP address of the connection, without the
+/// ephemeral port, for limits that must hold across connections
+pub const REMOTE_IP_KEY: &str = "remote_ip";
+
+/// Client type used when a client did not send `clientInfo`
+pub const UNKNOWN_CLIENT: &str = "unknown";
+
+/// Normalized description of a client implementation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientFingerprint {
+    /// Normalized client name (lowercase, whitespace replaced by `-`)
+    pub name: Option<String>,
+
+    /// Normalized client version (without a leading `v`)
+    pub version: Option<String>,
+
+    /// Transport the client connected over (e.g. `streamable_http`, `unix`)
+    pub transport: Option<String>,
+
+    /// Remote address of the connection, if known
+    pub remote_addr: Option<String>,
+
+    /// User agent reported by the HTTP client
+    pub user_agent: String,
+
+    /// Locale and time zone the client sent in `clientInfo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<ClientLocale>,
+}
+
+impl ClientFingerprint {
+    /// Build a fingerprint from `initialize` parameters and the request's client context
+    pub fn from_initialize(params: &InitializeParams, client: &ClientContext) -> Self {
+        let (name, version) = match &params.client_info {
+            Some(info) => (normalize_name(&info.name), normalize_version(&info.version)),
+            None => (None, None),
+        };
+
+        Self {
+            name,
+            version,
+            transport: client.metadata.get(TRANSPORT_KEY).cloned(),
+            remote_addr: client.metadata.get(REMOTE_ADDR_KEY).cloned(),
+            user_agent: client.user_agent.clone(),
+            locale: ClientLocale::from_initialize(params),
+        }
+    }
+
+    /// Client type used for policy lookups (the normalized name or `unknown`)
+    pub fn client_type(&self) -> &str {
+        self.name.as_deref().unwrap_or(UNKNOWN_CLIENT)
+    }
+}
+
+/// Policy applied to clients when they initialize
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientPolicy {
+    /// Reject clients that do not send `clientInfo`
+    #[serde(default)]
+    pub require_client_info: bool,
+
+    /// Client names that are never allowed to connect
+    #[serde(default)]
+    pub blocked_clients: Vec<String>,
+
+    /// Minimum version per client name (e.g. `{"claude-desktop": "0.7.0"}`)
+    #[serde(default)]
+    pub min_versions: HashMap<String, String>,
+
+    /// Case-insensitive substrings that reject a user agent
+    #[serde(default)]
+    pub blocked_user_agents: Vec<String>,
+
+    /// Requests per minute allowed per client, keyed by client type
+    #[serde(default)]
+    pub rate_limits_per_minute: HashMap<String, u32>,
+}
+
+impl ClientPolicy {
+    /// Reject clients that do not send `clientInfo`
+    pub fn require_client_info(mut self) -> Self {
+        self.require_client_info = true;
+        self
+    }
+
+    /// Block a client by name
+    pub fn block_client(mut self, name: &str) -> Self {
+        if let Some(name) = normalize_name(name) {
+            self.blocked_clients.push(name);
+        }
+        self
+    }
+
+    /// Require at least `version` for clients called `name`
+    pub fn with_min_version(mut self, name: &str, version: &str) -> Self {
+        if let (Some(name), Some(version)) = (normalize_name(name), normalize_version(version)) {
+            self.min_versions.insert(name, version);
+        }
+        self
+    }
+
+    /// Block user agents containing `pattern`
+    pub fn block_user_agent(mut self, pattern: impl Into<String>) -> Self {
+        self.blocked_user_agents.push(pattern.into());
+        self
+    }
+
+    /// Limit clients of the given type to `requests_per_minute`
+    pub fn with_rate_limit(mut self, client_type: &str, requests_per_minute: u32) -> Self {
+        let client_type = normalize_name(client_type).unwrap_or_else(|| UNKNOWN_CLIENT.into());
+        self.rate_limits_per_minute
+            .insert(client_type, requests_per_minute);
+        self
+    }
+
+    /// Check a fingerprint against the policy
+    pub fn check(&self, fingerprint: &ClientFingerprint) -> McpResult<()> {
+        let Some(name) = fingerprint.name.as_deref() else {
+            if self.require_client_info {
+                return Err(rejected("clientInfo is required"));
+            }
+            return self.check_user_agent(&fingerprint.user_agent);
+        };
+
+        if self
+            .blocked_clients
+            .iter()
+            .any(|blocked| normalize_name(blocked).as_deref() == Some(name))
+        {
+            return Err(rejected(&format!("client '{}' is not allowed", name)));
+        }
+
+        if let Some(min_version) = self.min_versions.get(name) {
+            let too_old = match fingerprint.version.as_deref() {
+                Some(version) => compare_versions(version, min_version) == Ordering::Less,
+                None => true,
+            };
+            if too_old {
+                return Err(rejected(&format!(
+                    "client '{}' must be version {} or newer",
+                    name, min_version
+                )));
+            }
+        }
+
+        self.check_user_agent(&fingerprint.user_agent)
+    }
+
+    fn check_user_agent(&self, user_agent: &str) -> McpResult<()> {
+        let user_agent = user_agent.to_lowercase();
+        match self
+            .blocked_user_agents
+            .iter()
+            .find(|pattern| user_agent.contains(&pattern.to_lowercase()))
+        {
+            Some(pattern) => Err(rejected(&format!("user agent matches '{}'", pattern))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn rejected(reason: &str) -> McpError {
+    McpError::Authorization {
+        message: format!("Client rejected by policy: {}", reason),
+    }
+}
+
+/// Normalize a client name for comparison
+pub fn normalize_name(name: &str) -> Option<String> {
+    let normalized = name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+/// Normalize a client version for comparison
+pub fn normalize_version(version: &str) -> Option<String> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix('v')
+        .or_else(|| version.strip_prefix('V'))
+        .unwrap_or(version);
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Compare dotted version strings numerically (`1.10.0` > `1.9.3`)
+///
+/// Pre-release and build suffixes are ignored; missing components count as zero.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn components(version: &str) -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    let (a, b) = (components(a), components(b));
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{messages::ClientInfo, ClientCapabilities};
+
+    fn fingerprint(name: &str, version: &str) -> ClientFingerprint {
+        let params = InitializeParams {
+            protocol_version: "2025-03-26".to_string(),
+            capabilities: ClientCapabilities::default(),
+            client_info: Some(ClientInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                metadata: Default::default(),
+            }),
+        };
+        let mut client = ClientContext::default();
+        client
+            .metadata
+            .insert(TRANSPORT_KEY.to_string(), "streamable_http".to_string());
+        ClientFingerprint::from_initialize(&params, &client)
+    }
+
+    #[test]
+    fn test_fingerprint_normalization() {
+        let fp = fingerprint("  Claude   Desktop ", "v0.7.1");
+        assert_eq!(fp.name.as_deref(), Some("claude-desktop"));
+        assert_eq!(fp.version.as_deref(), Some("0.7.1"));
+        assert_eq!(fp.transport.as_deref(), Some("streamable_http"));
+        assert_eq!(fp.client_type(), "claude-desktop");
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.7.0-beta", "0.7.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_policy_checks() {
+        let policy = ClientPolicy::default()
+            .block_client("Bad Client")
+            .with_min_version("Claude Desktop", "0.7.0")
+            .block_user_agent("curl/");
+
+        assert!(policy
+            .check(&fingerprint("claude-desktop", "0.7.1"))
+            .is_ok());
+        assert!(policy
+            .check(&fingerprint("claude-desktop", "0.6.9"))
+            .is_err());
+        assert!(policy.check(&fingerprint("bad-client", "1.0")).is_err());
+
+        let mut curl = fingerprint("other", "1.0");
+        curl.user_agent = "curl/8.0".to_string();
+        assert!(matches!(
+            policy.check(&curl),
+            Err(McpError::Authorization { .. })
+        ));
+
+        let anonymous = ClientFingerprint::from_initialize(
+            &InitializeParams {
+                protocol_version: "2025-03-26".to_string(),
+                capabilities: ClientCapabilities::default(),
+                client_info: None,
+            },
+            &ClientContext::default(),
+        );
+        assert!(policy.check(&anonymous).is_ok());
+        assert!(policy.require_client_info().check(&anonymous).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Method-level authorization matrix
+//!
+//! Maps JSON-RPC methods to the capabilities (roles) a context needs to call
+//! them, checked before any handler runs:
+//!
+//! ```yaml
+//! security:
+//!   method_authorization:
+//!     tools/call: [tool_user]
+//!     resources/read: [reader, tool_user]
+//!     "admin/*": [operator]
+//! ```
+//!
+//! A context needs one of the listed capabilities. Patterns ending in `*`
+//! match by prefix; the exact method wins over patterns, and longer patterns
+//! over shorter ones. Methods no rule matches are left to the registries'
+//! own access checks, and system contexts are never restricted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// Capabilities required per method pattern
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MethodAuthorization {
+    rules: BTreeMap<String, Vec<String>>,
+}
+
+impl MethodAuthorization {
+    /// Matrix without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require one of `capabilities` for methods matching `pattern`
+    pub fn require<I, C>(mut self, pattern: impl Into<String>, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = C>,
+        C: Into<String>,
+    {
+        self.rules.insert(
+            pattern.into(),
+            capabilities.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Method patterns with rules
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.rules.keys().map(String::as_str)
+    }
+
+    /// Capabilities of the rule for `method`, if one matches
+    pub fn required_for(&self, method: &str) -> Option<&[String]> {
+        if let Some(capabilities) = self.rules.get(method) {
+            return Some(capabilities);
+        }
+        self.rules
+            .iter()
+            .filter_map(|(pattern, capabilities)| {
+                let prefix = pattern.strip_suffix('*')?;
+                method
+                    .starts_with(prefix)
+                    .then_some((prefix.len(), capabilities))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, capabilities)| capabilities.as_slice())
+    }
+
+    /// Check that `context` may call `method`
+    pub fn check(&self, method: &str, context: &SecurityContext) -> McpResult<()> {
+        let Some(required) = self.required_for(method) else {
+            return Ok(());
+        };
+        if context.is_system() || required.iter().any(|c| context.has_capability(c)) {
+            return Ok(());
+        }
+        Err(McpError::Authorization {
+            message: format!(
+                "Method '{}' requires one of: {}",
+                method,
+                required.join(", ")
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_rule_applies() {
+        let matrix: MethodAuthorization = serde_json::from_value(serde_json::json!({
+            "tools/call": ["tool_user"],
+            "admin/*": ["operator"],
+            "admin/quotas/*": ["billing"],
+        }))
+        .unwrap();
+
+        let mut context = SecurityContext::anonymous();
+        context.add_capability("tool_user");
+        assert!(matrix.check("tools/call", &context).is_ok());
+        assert!(matrix.check("tools/list", &context).is_ok());
+        let error = matrix.check("admin/wire_tap", &context).unwrap_err();
+        assert!(error.to_string().contains("operator"));
+
+        context.add_capability("operator");
+        assert!(matrix.check("admin/wire_tap", &context).is_ok());
+        assert!(matrix.check("admin/quotas/reset", &context).is_err());
+        assert!(matrix
+            .check("admin/quotas/reset", &SecurityContext::system())
+            .is_ok());
+    }
+}
@@ -1,14 +1,25 @@
 //! Security and authentication for MCP connections
 
+pub mod anonymous;
 pub mod auth;
+pub mod authz_cache;
+pub mod fingerprint;
+pub mod method_authz;
 pub mod permissions;
 pub mod rate_limit;
+pub mod signature;
 
+pub use anonymous::{AnonymousAuth, AnonymousConfig};
 pub use auth::{
     AuthResult, ClientContext, McpAuth, McpAuthConfig, McpAuthManager, SecurityContext,
+    IMPERSONATE_CAPABILITY, LIST_ALL_CAPABILITY, READ_ALL_CAPABILITY,
 };
+pub use authz_cache::{AuthorizationCache, AuthorizationCacheConfig, CachedAuth};
+pub use fingerprint::{ClientFingerprint, ClientPolicy};
+pub use method_authz::MethodAuthorization;
 pub use permissions::{ClientPermissions, PermissionChecker, RateLimits, ResourceQuotas};
 pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use signature::{SignedRequestAuth, SignedRequestConfig};
 
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -55,6 +55,18 @@ impl ClientPermissions {
         }
     }
 
+    /// Create permissions for guests without credentials
+    pub fn guest() -> Self {
+        Self {
+            can_execute_tasks: true,
+            can_read_logs: false,
+            can_read_traces: false,
+            allowed_task_patterns: vec!["*".to_string()],
+            rate_limits: RateLimits::strict(),
+            resource_quotas: ResourceQuotas::default(),
+        }
+    }
+
     /// Create task execution permissions for specific patterns
     pub fn task_execution(patterns: Vec<String>) -> Self {
         Self {
@@ -358,8 +370,10 @@ mod tests {
 
     #[test]
     fn test_log_request_validation() {
-        let mut permissions = ClientPermissions::default();
-        permissions.can_read_logs = true;
+        let mut permissions = ClientPermissions {
+            can_read_logs: true,
+            ..Default::default()
+        };
         permissions.resource_quotas.max_log_entries_per_request = Some(100);
 
         // Should allow up to the limit
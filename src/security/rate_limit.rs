@@ -5,7 +5,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
-use crate::{McpError, McpResult};
+use crate::{clock::Clock, McpError, McpResult};
 
 /// Rate limiter configuration
 #[derive(Debug, Clone)]
@@ -62,10 +62,10 @@ struct RateLimiterState {
 }
 
 impl RateLimiterState {
-    fn new() -> Self {
+    fn new(now: Instant) -> Self {
         Self {
             requests: Vec::new(),
-            window_start: Instant::now(),
+            window_start: now,
             total_requests: 0,
         }
     }
@@ -78,6 +78,9 @@ pub struct RateLimiter {
 
     /// State per client/key
     states: Arc<RwLock<HashMap<String, RateLimiterState>>>,
+
+    /// Time source for windows
+    clock: Clock,
 }
 
 impl RateLimiter {
@@ -86,9 +89,16 @@ impl RateLimiter {
         Self {
             config,
             states: Arc::new(RwLock::new(HashMap::new())),
+            clock: Clock::default(),
         }
     }
 
+    /// Measure windows on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Check if a request is allowed for the given key
     pub async fn check_rate_limit(&self, key: &str) -> McpResult<()> {
         self.check_rate_limit_with_count(key, 1).await
@@ -96,12 +106,12 @@ impl RateLimiter {
 
     /// Check rate limit with a specific request count
     pub async fn check_rate_limit_with_count(&self, key: &str, count: u32) -> McpResult<()> {
-        let now = Instant::now();
+        let now = self.clock.now();
         let mut states = self.states.write().await;
 
         let state = states
             .entry(key.to_string())
-            .or_insert_with(RateLimiterState::new);
+            .or_insert_with(|| RateLimiterState::new(now));
 
         if self.config.sliding_window {
             self.check_sliding_window(state, now, count)
@@ -211,7 +221,7 @@ impl RateLimiter {
 
     /// Get current rate limit status for a key
     pub async fn get_status(&self, key: &str) -> RateLimitStatus {
-        let now = Instant::now();
+        let now = self.clock.now();
         let states = self.states.read().await;
 
         if let Some(state) = states.get(key) {
@@ -275,7 +285,7 @@ impl RateLimiter {
 
     /// Clean up old state entries
     pub async fn cleanup(&self, max_age: Duration) {
-        let now = Instant::now();
+        let now = self.clock.now();
         let mut states = self.states.write().await;
 
         states.retain(|_, state| {
@@ -329,6 +339,7 @@ impl RateLimitStatus {
 /// Multi-tier rate limiter for different operation types
 pub struct MultiTierRateLimiter {
     limiters: HashMap<String, RateLimiter>,
+    clock: Clock,
 }
 
 impl MultiTierRateLimiter {
@@ -336,13 +347,22 @@ impl MultiTierRateLimiter {
     pub fn new() -> Self {
         Self {
             limiters: HashMap::new(),
+            clock: Clock::default(),
         }
     }
 
+    /// Measure windows on `clock`, for limiters added afterwards
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Add a rate limiter for a specific operation type
     pub fn add_limiter(&mut self, operation: impl Into<String>, config: RateLimitConfig) {
-        self.limiters
-            .insert(operation.into(), RateLimiter::new(config));
+        self.limiters.insert(
+            operation.into(),
+            RateLimiter::new(config).with_clock(self.clock.clone()),
+        );
     }
 
     /// Check rate limit for a specific operation and client
@@ -385,12 +405,12 @@ impl Default for MultiTierRateLimiter {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::time::sleep;
 
     #[tokio::test]
     async fn test_sliding_window_rate_limiter() {
+        let clock = Clock::manual();
         let config = RateLimitConfig::new(3, Duration::from_millis(100));
-        let limiter = RateLimiter::new(config);
+        let limiter = RateLimiter::new(config).with_clock(clock.clone());
 
         // First 3 requests should pass
         assert!(limiter.check_rate_limit("client1").await.is_ok());
@@ -400,8 +420,8 @@ mod tests {
         // 4th request should fail
         assert!(limiter.check_rate_limit("client1").await.is_err());
 
-        // Wait for window to slide
-        sleep(Duration::from_millis(110)).await;
+        // Let the window slide
+        clock.advance(Duration::from_millis(110));
 
         // Should allow requests again
         assert!(limiter.check_rate_limit("client1").await.is_ok());
@@ -409,9 +429,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_fixed_window_rate_limiter() {
+        let clock = Clock::manual();
         let mut config = RateLimitConfig::new(2, Duration::from_millis(100));
         config.sliding_window = false;
-        let limiter = RateLimiter::new(config);
+        let limiter = RateLimiter::new(config).with_clock(clock.clone());
 
         // First 2 requests should pass
         assert!(limiter.check_rate_limit("client1").await.is_ok());
@@ -420,8 +441,8 @@ mod tests {
         // 3rd request should fail
         assert!(limiter.check_rate_limit("client1").await.is_err());
 
-        // Wait for window to reset
-        sleep(Duration::from_millis(110)).await;
+        // Let the window reset
+        clock.advance(Duration::from_millis(110));
 
         // Should allow requests again
         assert!(limiter.check_rate_limit("client1").await.is_ok());
@@ -0,0 +1,343 @@
+//! Signed requests with replay protection
+//!
+//! Servers exposed to third parties, like webhook receivers, can require each
+//! HTTP request to be signed with a shared secret. [`SignedRequestAuth`] wraps
+//! another [`McpAuth`] and, before handing over to it, checks three headers:
+//!
+//! - `X-MCP-Timestamp`: Unix time in seconds when the request was signed, which
+//!   must be within [`SignedRequestConfig::clock_skew`] of the server's clock,
+//! - `X-MCP-Nonce`: a value never used before within that window,
+//! - `X-MCP-Signature`: the hex HMAC-SHA256 of `{timestamp}.{nonce}.{body}`,
+//!   optionally prefixed with `sha256=`.
+//!
+//! Seen nonces are remembered for twice the clock skew, after which their
+//! timestamps are rejected anyway, and at most
+//! [`replay_cache_size`](SignedRequestConfig::replay_cache_size) at a time.
+//! Use it as the auth of a [`Listener`](crate::server::Listener), which passes
+//! the headers and the body in the client metadata.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::{ClientContext, McpAuth, SecurityContext},
+    server::webhooks::sign,
+};
+
+/// Header carrying the time a request was signed
+pub const TIMESTAMP_HEADER: &str = "x-mcp-timestamp";
+
+/// Header carrying the single-use nonce of a request
+pub const NONCE_HEADER: &str = "x-mcp-nonce";
+
+/// Header carrying the signature of a request
+pub const SIGNATURE_HEADER: &str = "x-mcp-signature";
+
+/// Client metadata key for the [`TIMESTAMP_HEADER`]
+pub const TIMESTAMP_KEY: &str = "signature_timestamp";
+
+/// Client metadata key for the [`NONCE_HEADER`]
+pub const NONCE_KEY: &str = "signature_nonce";
+
+/// Client metadata key for the [`SIGNATURE_HEADER`]
+pub const SIGNATURE_KEY: &str = "signature";
+
+/// Client metadata key for the request body, for auths that ask for it
+pub const REQUEST_BODY_KEY: &str = "request_body";
+
+/// Signed request settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignedRequestConfig {
+    /// How far a request's timestamp may be from the server's clock
+    #[serde(with = "humantime_serde")]
+    pub clock_skew: Duration,
+
+    /// Most nonces remembered; new requests are rejected while it is full
+    pub replay_cache_size: usize,
+
+    /// Largest request body that is buffered and verified
+    pub max_body_bytes: usize,
+}
+
+impl Default for SignedRequestConfig {
+    fn default() -> Self {
+        Self {
+            clock_skew: Duration::from_secs(300),
+            replay_cache_size: 100_000,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+/// An [`McpAuth`] that requires signed, single-use requests before delegating
+pub struct SignedRequestAuth<A> {
+    inner: A,
+    secret: Vec<u8>,
+    config: SignedRequestConfig,
+    nonces: Mutex<Nonces>,
+    clock: Clock,
+}
+
+/// Seen nonces, queued in the order they expire
+///
+/// Every nonce lives for the same time, so the queue is ordered by expiry and
+/// forgetting expired nonces only looks at its front.
+#[derive(Debug, Default)]
+struct Nonces {
+    seen: HashSet<String>,
+    expiries: VecDeque<(Instant, String)>,
+}
+
+impl Nonces {
+    /// Forget the nonces that expired by `now`
+    fn expire(&mut self, now: Instant) {
+        while self
+            .expiries
+            .front()
+            .is_some_and(|(expires, _)| *expires <= now)
+        {
+            if let Some((_, nonce)) = self.expiries.pop_front() {
+                self.seen.remove(&nonce);
+            }
+        }
+    }
+
+    /// Remember `nonce` until `expires`
+    fn insert(&mut self, nonce: &str, expires: Instant) {
+        if self.seen.insert(nonce.to_string()) {
+            self.expiries.push_back((expires, nonce.to_string()));
+        }
+    }
+}
+
+impl<A: McpAuth> SignedRequestAuth<A> {
+    /// Require requests signed with `secret`, then authenticate with `inner`
+    pub fn new(inner: A, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            inner,
+            secret: secret.into(),
+            config: SignedRequestConfig::default(),
+            nonces: Mutex::new(Nonces::default()),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Use the given settings
+    pub fn with_config(mut self, config: SignedRequestConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Check timestamps and expire nonces on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Check the signature of a request and remember its nonce
+    pub fn verify(&self, client: &ClientContext) -> McpResult<()> {
+        let field = |key: &str| {
+            client
+                .metadata
+                .get(key)
+                .ok_or_else(|| rejected(format!("missing {}", key)))
+        };
+        let timestamp = field(TIMESTAMP_KEY)?;
+        let nonce = field(NONCE_KEY)?;
+        let signature = field(SIGNATURE_KEY)?;
+        let body = field(REQUEST_BODY_KEY)?;
+
+        let signed_at = timestamp
+            .parse::<u64>()
+            .map_err(|_| rejected("invalid timestamp".to_string()))?;
+        let now = self
+            .clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if signed_at.abs_diff(now) > self.config.clock_skew.as_secs() {
+            return Err(rejected(
+                "timestamp outside the allowed clock skew".to_string(),
+            ));
+        }
+
+        let message = format!("{}.{}.{}", timestamp, nonce, body);
+        let expected = sign(&self.secret, message.as_bytes());
+        let signature = signature.strip_prefix("sha256=").unwrap_or(signature);
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(rejected("invalid signature".to_string()));
+        }
+
+        // Only correctly signed requests use up nonces
+        let now = self.clock.now();
+        let mut nonces = self
+            .nonces
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        nonces.expire(now);
+        if nonces.seen.contains(nonce.as_str()) {
+            return Err(rejected("nonce already used".to_string()));
+        }
+        if nonces.seen.len() >= self.config.replay_cache_size {
+            return Err(rejected("too many recent requests".to_string()));
+        }
+        nonces.insert(nonce, now + 2 * self.config.clock_skew);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<A: McpAuth> McpAuth for SignedRequestAuth<A> {
+    async fn authenticate(&self, client_info: &ClientContext) -> McpResult<SecurityContext> {
+        self.verify(client_info)?;
+        self.inner.authenticate(client_info).await
+    }
+
+    async fn authorize(&self, context: &SecurityContext, resource: &str, action: &str) -> bool {
+        self.inner.authorize(context, resource, action).await
+    }
+
+    async fn authorize_delegation(&self, context: &SecurityContext, principal: &str) -> bool {
+        self.inner.authorize_delegation(context, principal).await
+    }
+
+    fn request_body_limit(&self) -> Option<usize> {
+        Some(self.config.max_body_bytes)
+    }
+}
+
+fn rejected(reason: String) -> McpError {
+    McpError::Authentication {
+        message: format!("Request signature rejected: {}", reason),
+    }
+}
+
+/// Compare without revealing how many leading bytes match
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct OpenAuth;
+
+    #[async_trait]
+    impl McpAuth for OpenAuth {
+        async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+        }
+
+        async fn authorize(&self, _: &SecurityContext, _: &str, _: &str) -> bool {
+            true
+        }
+    }
+
+    fn signed(timestamp: u64, nonce: &str, body: &str, secret: &[u8]) -> ClientContext {
+        let mut client = ClientContext::default();
+        let message = format!("{}.{}.{}", timestamp, nonce, body);
+        for (key, value) in [
+            (TIMESTAMP_KEY, timestamp.to_string()),
+            (NONCE_KEY, nonce.to_string()),
+            (
+                SIGNATURE_KEY,
+                format!("sha256={}", sign(secret, message.as_bytes())),
+            ),
+            (REQUEST_BODY_KEY, body.to_string()),
+        ] {
+            client.metadata.insert(key.to_string(), value);
+        }
+        client
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests_are_single_use() {
+        let clock = Clock::manual();
+        let auth = SignedRequestAuth::new(OpenAuth, "s3cret").with_clock(clock.clone());
+        let now = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#;
+
+        assert!(auth
+            .authenticate(&signed(now, "a", body, b"s3cret"))
+            .await
+            .is_ok());
+        // Replayed, signed with another secret, or too old
+        assert!(auth
+            .authenticate(&signed(now, "a", body, b"s3cret"))
+            .await
+            .is_err());
+        assert!(auth
+            .authenticate(&signed(now, "b", body, b"guess"))
+            .await
+            .is_err());
+        assert!(auth
+            .authenticate(&signed(now - 301, "c", body, b"s3cret"))
+            .await
+            .is_err());
+        let mut tampered = signed(now, "d", body, b"s3cret");
+        tampered
+            .metadata
+            .insert(REQUEST_BODY_KEY.to_string(), "{}".to_string());
+        assert!(auth.authenticate(&tampered).await.is_err());
+        assert!(auth.authenticate(&ClientContext::default()).await.is_err());
+
+        // Forgotten once its timestamp could no longer be accepted
+        clock.advance(Duration::from_secs(601));
+        assert!(auth
+            .authenticate(&signed(now + 601, "e", body, b"s3cret"))
+            .await
+            .is_ok());
+        let nonces = auth.nonces.lock().unwrap();
+        assert_eq!((nonces.seen.len(), nonces.expiries.len()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_nonce_cache_is_bounded() {
+        let clock = Clock::manual();
+        let auth = SignedRequestAuth::new(OpenAuth, "s3cret")
+            .with_config(SignedRequestConfig {
+                replay_cache_size: 2,
+                ..Default::default()
+            })
+            .with_clock(clock.clone());
+        let now = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let body = "{}";
+
+        for nonce in ["a", "b"] {
+            assert!(auth
+                .authenticate(&signed(now, nonce, body, b"s3cret"))
+                .await
+                .is_ok());
+        }
+        let error = auth
+            .authenticate(&signed(now, "c", body, b"s3cret"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("too many recent requests"));
+
+        clock.advance(Duration::from_secs(601));
+        assert!(auth
+            .authenticate(&signed(now + 601, "c", body, b"s3cret"))
+            .await
+            .is_ok());
+        assert_eq!(auth.nonces.lock().unwrap().expiries.len(), 1);
+    }
+}
@@ -0,0 +1,360 @@
+//! Admission control under overload
+//!
+//! When the server is under stress, queueing more expensive work only makes
+//! every request slower. With admission control enabled, each expensive
+//! request (see [`AdmissionConfig::expensive_methods`]) is checked against
+//! three signals before it runs:
+//!
+//! - the number of expensive requests already in flight
+//! - queue latency: how long the oldest request waiting in the
+//!   [`RequestScheduler`] has been queued
+//! - the resident memory of the process
+//!
+//! If any of them is over its limit the request fails straight away with
+//! [`McpError::Overloaded`], which tells clients to retry later. Cheap methods
+//! such as `ping`, `initialize` and listings are always admitted, and the
+//! health endpoint does not pass through admission at all, so the server stays
+//! observable while it sheds load.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    server::scheduler::RequestScheduler,
+};
+
+/// Reads the resident memory of the process in bytes
+pub type MemoryProbe = Arc<dyn Fn() -> Option<u64> + Send + Sync>;
+
+/// Admission control configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdmissionConfig {
+    /// Whether expensive requests are checked before they run
+    pub enabled: bool,
+
+    /// Methods that are checked; all others are always admitted
+    pub expensive_methods: Vec<String>,
+
+    /// Expensive requests running or queued at the same time
+    pub max_in_flight: usize,
+
+    /// Reject while a scheduled request has waited longer than this
+    #[serde(with = "humantime_serde")]
+    pub max_queue_latency: Option<Duration>,
+
+    /// Reject while the resident memory of the process is above this
+    pub max_memory_bytes: Option<u64>,
+
+    /// How often resident memory is sampled
+    #[serde(with = "humantime_serde")]
+    pub memory_sample_interval: Duration,
+
+    /// Seconds clients are told to wait before retrying
+    pub retry_after: u64,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            expensive_methods: ["tools/call", "batch", "resources/read", "prompts/get"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            max_in_flight: 256,
+            max_queue_latency: Some(Duration::from_secs(5)),
+            max_memory_bytes: None,
+            memory_sample_interval: Duration::from_secs(1),
+            retry_after: 5,
+        }
+    }
+}
+
+impl AdmissionConfig {
+    /// Check expensive requests with the default limits
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Reject when `max_in_flight` expensive requests are already running
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Reject while scheduled requests wait longer than `latency`
+    pub fn with_max_queue_latency(mut self, latency: Duration) -> Self {
+        self.max_queue_latency = Some(latency);
+        self
+    }
+
+    /// Reject while resident memory is above `bytes`
+    pub fn with_max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+}
+
+/// Current load as seen by admission control
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionStats {
+    /// Expensive requests currently admitted
+    pub in_flight: usize,
+
+    /// Wait of the oldest scheduled request, in milliseconds
+    pub queue_latency_ms: u64,
+
+    /// Last sampled resident memory, if known
+    pub memory_bytes: Option<u64>,
+
+    /// Requests rejected since startup
+    pub rejected: u64,
+}
+
+tokio::task_local! {
+    /// Set while an admitted request runs, so the items of a batch are not
+    /// counted on top of the batch itself
+    static ADMITTED: ();
+}
+
+#[derive(Debug, Default)]
+struct MemorySample {
+    taken_at: Option<Instant>,
+    bytes: Option<u64>,
+}
+
+/// Rejects expensive requests while the server is overloaded
+#[derive(Clone)]
+pub struct AdmissionController {
+    config: AdmissionConfig,
+    scheduler: RequestScheduler,
+    memory_probe: MemoryProbe,
+    memory: Arc<Mutex<MemorySample>>,
+    in_flight: Arc<AtomicUsize>,
+    rejected: Arc<AtomicU64>,
+    clock: Clock,
+}
+
+impl std::fmt::Debug for AdmissionController {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdmissionController")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// An admitted expensive request; dropping it frees its place
+#[derive(Debug)]
+struct AdmissionGuard {
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for AdmissionGuard {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl AdmissionController {
+    /// Check requests as configured, reading queue latency from `scheduler`
+    pub fn new(config: AdmissionConfig, scheduler: RequestScheduler) -> Self {
+        Self {
+            config,
+            scheduler,
+            memory_probe: Arc::new(resident_memory),
+            memory: Arc::new(Mutex::new(MemorySample::default())),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            rejected: Arc::new(AtomicU64::new(0)),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Measure queue latency and sample age on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.scheduler = self.scheduler.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Read resident memory with `probe` instead of from the operating system
+    pub fn with_memory_probe(mut self, probe: MemoryProbe) -> Self {
+        self.memory_probe = probe;
+        self.memory = Arc::new(Mutex::new(MemorySample::default()));
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &AdmissionConfig {
+        &self.config
+    }
+
+    /// Whether requests for `method` are checked
+    pub fn is_expensive(&self, method: &str) -> bool {
+        self.config.enabled && self.config.expensive_methods.iter().any(|m| m == method)
+    }
+
+    /// Run `task` unless the server is too loaded to take on `method`
+    ///
+    /// Requests made from within an admitted request, such as the items of a
+    /// batch, are not checked again.
+    pub async fn run<F: Future>(&self, method: &str, task: F) -> McpResult<F::Output> {
+        if !self.is_expensive(method) || ADMITTED.try_with(|_| ()).is_ok() {
+            return Ok(task.await);
+        }
+        let _guard = self.admit(method)?;
+        Ok(ADMITTED.scope((), task).await)
+    }
+
+    /// Current load and rejection count
+    pub fn stats(&self) -> AdmissionStats {
+        AdmissionStats {
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            queue_latency_ms: self.scheduler.queue_latency().as_millis() as u64,
+            memory_bytes: self.memory.lock().unwrap().bytes,
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+
+    fn admit(&self, method: &str) -> McpResult<AdmissionGuard> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = AdmissionGuard {
+            in_flight: Arc::clone(&self.in_flight),
+        };
+        if let Some(reason) = self.overload_reason(in_flight) {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            debug!("Rejecting {}: {}", method, reason);
+            return Err(McpError::Overloaded {
+                reason,
+                retry_after: self.config.retry_after,
+            });
+        }
+        Ok(guard)
+    }
+
+    fn overload_reason(&self, in_flight: usize) -> Option<String> {
+        if in_flight >= self.config.max_in_flight {
+            return Some(format!("{} requests in flight", in_flight));
+        }
+        if let Some(max) = self.config.max_queue_latency {
+            let latency = self.scheduler.queue_latency();
+            if latency > max {
+                return Some(format!("queue latency {}ms", latency.as_millis()));
+            }
+        }
+        if let Some(max) = self.config.max_memory_bytes {
+            if let Some(bytes) = self.sample_memory().filter(|bytes| *bytes > max) {
+                return Some(format!("resident memory {} bytes", bytes));
+            }
+        }
+        None
+    }
+
+    fn sample_memory(&self) -> Option<u64> {
+        let mut sample = self.memory.lock().unwrap();
+        let now = self.clock.now();
+        let stale = sample.taken_at.is_none_or(|taken_at| {
+            now.saturating_duration_since(taken_at) >= self.config.memory_sample_interval
+        });
+        if stale {
+            sample.bytes = (self.memory_probe)();
+            sample.taken_at = Some(now);
+        }
+        sample.bytes
+    }
+}
+
+/// Resident memory of the current process, where the platform reports it
+pub fn resident_memory() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let kilobytes = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kilobytes * 1024)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::scheduler::SchedulerConfig;
+
+    #[tokio::test]
+    async fn test_admission_rejects_when_overloaded() {
+        let memory = Arc::new(AtomicU64::new(100));
+        let controller = AdmissionController::new(
+            AdmissionConfig {
+                memory_sample_interval: Duration::ZERO,
+                ..AdmissionConfig::enabled()
+                    .with_max_in_flight(1)
+                    .with_max_memory(1000)
+            },
+            RequestScheduler::new(SchedulerConfig::default()),
+        )
+        .with_memory_probe({
+            let memory = Arc::clone(&memory);
+            Arc::new(move || Some(memory.load(Ordering::SeqCst)))
+        });
+
+        // Nested requests share the place of the outer one
+        let nested = controller
+            .run("batch", async {
+                assert_eq!(controller.stats().in_flight, 1);
+                let item = controller.run("tools/call", async {}).await;
+                let other = tokio::spawn({
+                    let controller = controller.clone();
+                    async move { controller.run("tools/call", async {}).await }
+                });
+                (item, other.await.unwrap())
+            })
+            .await
+            .unwrap();
+        assert!(nested.0.is_ok());
+        let error = nested.1.unwrap_err();
+        assert_eq!(error.code(), "overloaded");
+        assert_eq!(error.client_message(), "Server overloaded, retry later");
+        assert_eq!(controller.stats().in_flight, 0);
+
+        memory.store(2000, Ordering::SeqCst);
+        assert!(controller.run("tools/call", async {}).await.is_err());
+        assert!(controller.run("ping", async {}).await.is_ok());
+        memory.store(500, Ordering::SeqCst);
+        assert!(controller.run("tools/call", async {}).await.is_ok());
+
+        let stats = controller.stats();
+        assert_eq!(stats.rejected, 2);
+        assert_eq!(stats.memory_bytes, Some(500));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resident_memory() {
+        assert!(resident_memory().is_some_and(|bytes| bytes > 0));
+    }
+}
@@ -0,0 +1,281 @@
+//! Session affinity for clustered deployments
+//!
+//! Load balancers keep a StreamableHTTP session on one replica by routing on
+//! its ID. [`SessionAffinityConfig`] controls what the server exposes for that:
+//!
+//! - The `Mcp-Session-Id` header is echoed on every response for a session,
+//!   so balancers that learn affinity from responses see it.
+//! - Optionally, a cookie carrying the session ID is set when a session is
+//!   issued, for balancers with cookie-based stickiness.
+//!
+//! For balancers that hash requests, [`affinity_key`] extracts the session ID
+//! from a request and [`HashRing`] maps keys onto nodes with consistent
+//! hashing, so adding or removing a node moves few sessions.
+//!
+//! A [`SessionManager`](crate::transport::SessionManager) given a node ID with
+//! [`with_node_id`](crate::transport::SessionManager::with_node_id) issues
+//! session IDs of the form `{node_id}.{uuid}`. With `enforce_ownership`, the
+//! server rejects requests for sessions issued by another node: with
+//! `307 Temporary Redirect` to the owner when its URL is listed in `peers`,
+//! otherwise with `421 Misdirected Request`. Both name the owner in the
+//! `Mcp-Session-Owner` header.
+
+#[cfg(feature = "axum")]
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::{header, HeaderMap, HeaderValue, Request};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "axum")]
+use crate::protocol::{JsonRpcError, JsonRpcResponse};
+use crate::transport::streamable_http::MCP_SESSION_HEADER;
+
+/// Header naming the node that owns a session
+pub const SESSION_OWNER_HEADER: &str = "mcp-session-owner";
+
+/// Separator between the node ID and the rest of a session ID
+pub const NODE_SEPARATOR: char = '.';
+
+/// Session affinity configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionAffinityConfig {
+    /// Echo `Mcp-Session-Id` on every response for a session, not only when
+    /// it is issued
+    pub echo_session_header: bool,
+
+    /// Name of a cookie set to the session ID when a session is issued
+    pub cookie: Option<String>,
+
+    /// Reject requests for sessions issued by other nodes
+    pub enforce_ownership: bool,
+
+    /// Base URLs of other nodes by node ID, used as redirect targets
+    pub peers: HashMap<String, String>,
+}
+
+impl Default for SessionAffinityConfig {
+    fn default() -> Self {
+        Self {
+            echo_session_header: true,
+            cookie: None,
+            enforce_ownership: false,
+            peers: HashMap::new(),
+        }
+    }
+}
+
+impl SessionAffinityConfig {
+    /// Set a cookie named `name` to the session ID when a session is issued
+    pub fn with_cookie(mut self, name: impl Into<String>) -> Self {
+        self.cookie = Some(name.into());
+        self
+    }
+
+    /// Reject requests for sessions issued by other nodes
+    pub fn enforce_ownership(mut self) -> Self {
+        self.enforce_ownership = true;
+        self
+    }
+
+    /// Redirect requests for sessions of `node_id` to `url`
+    pub fn with_peer(mut self, node_id: impl Into<String>, url: impl Into<String>) -> Self {
+        self.peers.insert(node_id.into(), url.into());
+        self
+    }
+
+    /// `Set-Cookie` value for a newly issued session, if a cookie is configured
+    pub fn session_cookie(&self, session_id: &str, path: &str) -> Option<HeaderValue> {
+        let name = self.cookie.as_ref()?;
+        HeaderValue::from_str(&format!(
+            "{}={}; Path={}; HttpOnly; SameSite=Lax",
+            name, session_id, path
+        ))
+        .ok()
+    }
+
+    /// Response turning away a request for a session owned by `owner`
+    #[cfg(feature = "axum")]
+    pub fn misdirected_response(&self, owner: &str, id: Option<serde_json::Value>) -> Response {
+        let error = JsonRpcError::new(
+            -32000,
+            "Session is owned by another node",
+            Some(serde_json::json!({ "owner": owner })),
+        );
+        let mut response = Json(JsonRpcResponse::error(error, id)).into_response();
+        match self
+            .peers
+            .get(owner)
+            .and_then(|url| HeaderValue::from_str(url).ok())
+        {
+            Some(location) => {
+                *response.status_mut() = http::StatusCode::TEMPORARY_REDIRECT;
+                response.headers_mut().insert(header::LOCATION, location);
+            }
+            None => *response.status_mut() = http::StatusCode::MISDIRECTED_REQUEST,
+        }
+        if let Ok(owner) = HeaderValue::from_str(owner) {
+            response.headers_mut().insert(SESSION_OWNER_HEADER, owner);
+        }
+        response
+    }
+}
+
+/// Node that issued a session, for IDs of the form `{node_id}.{uuid}`
+pub fn session_owner(session_id: &str) -> Option<&str> {
+    session_id
+        .split_once(NODE_SEPARATOR)
+        .map(|(node_id, _)| node_id)
+        .filter(|node_id| !node_id.is_empty())
+}
+
+/// Session ID to route a request on
+///
+/// Taken from the `Mcp-Session-Id` header, the `session_id` query parameter,
+/// or the cookie named `cookie`, in that order.
+pub fn affinity_key<B>(request: &Request<B>, cookie: Option<&str>) -> Option<String> {
+    if let Some(session_id) = header_value(request.headers(), MCP_SESSION_HEADER) {
+        return Some(session_id.to_string());
+    }
+    if let Some(session_id) = request.uri().query().and_then(|query| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "session_id")
+            .map(|(_, value)| value.into_owned())
+    }) {
+        return Some(session_id);
+    }
+    let cookie = cookie?;
+    request
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == cookie)
+        .map(|(_, value)| value.to_string())
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+}
+
+/// Consistent hash ring mapping affinity keys onto nodes
+#[derive(Debug, Clone, Default)]
+pub struct HashRing {
+    ring: BTreeMap<u64, String>,
+    virtual_nodes: usize,
+}
+
+impl HashRing {
+    /// Place each node on the ring `virtual_nodes` times
+    ///
+    /// More virtual nodes spread keys more evenly.
+    pub fn new<I, N>(nodes: I, virtual_nodes: usize) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        let mut ring = Self {
+            ring: BTreeMap::new(),
+            virtual_nodes: virtual_nodes.max(1),
+        };
+        for node in nodes {
+            ring.add(node);
+        }
+        ring
+    }
+
+    /// Add a node
+    pub fn add(&mut self, node: impl Into<String>) {
+        let node = node.into();
+        for replica in 0..self.virtual_nodes {
+            self.ring
+                .insert(hash(&format!("{}#{}", node, replica)), node.clone());
+        }
+    }
+
+    /// Remove a node; its keys move to the following nodes
+    pub fn remove(&mut self, node: &str) {
+        self.ring.retain(|_, owner| owner != node);
+    }
+
+    /// Node responsible for `key`
+    pub fn node_for(&self, key: &str) -> Option<&str> {
+        let point = hash(key);
+        self.ring
+            .range(point..)
+            .next()
+            .or_else(|| self.ring.iter().next())
+            .map(|(_, node)| node.as_str())
+    }
+}
+
+fn hash(key: &str) -> u64 {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affinity_key_sources() {
+        let request = Request::builder()
+            .uri("/mcp?session_id=from-query")
+            .header("cookie", "theme=dark; mcp_session=from-cookie")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            affinity_key(&request, Some("mcp_session")).as_deref(),
+            Some("from-query")
+        );
+
+        let request = Request::builder()
+            .uri("/mcp")
+            .header("cookie", "theme=dark; mcp_session=from-cookie")
+            .body(())
+            .unwrap();
+        assert_eq!(
+            affinity_key(&request, Some("mcp_session")).as_deref(),
+            Some("from-cookie")
+        );
+        assert_eq!(affinity_key(&request, None), None);
+
+        assert_eq!(session_owner("node-a.1234"), Some("node-a"));
+        assert_eq!(session_owner("1234-5678"), None);
+    }
+
+    #[test]
+    fn test_hash_ring_moves_few_keys() {
+        let keys: Vec<String> = (0..1000).map(|i| format!("session-{}", i)).collect();
+        let mut ring = HashRing::new(["a", "b", "c"], 64);
+        let before: Vec<String> = keys
+            .iter()
+            .map(|key| ring.node_for(key).unwrap().to_string())
+            .collect();
+        assert!(["a", "b", "c"]
+            .iter()
+            .all(|node| before.iter().any(|owner| owner == node)));
+
+        ring.remove("c");
+        let moved = keys
+            .iter()
+            .zip(&before)
+            .filter(|(key, owner)| ring.node_for(key) != Some(owner.as_str()))
+            .count();
+        // Only the keys that were on `c` move
+        assert_eq!(moved, before.iter().filter(|owner| *owner == "c").count());
+        assert_eq!(HashRing::default().node_for("x"), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Method aliases and deprecation shims
+//!
+//! [`MethodAliasConfig`] maps extra method names onto the methods that handle
+//! them, so clients using a legacy custom method, or another spelling of a
+//! standard one, keep working after a rename. Aliases are resolved once,
+//! before authorization and routing; they do not chain.
+//!
+//! Calls through a deprecated alias still succeed, but are logged and, for
+//! StreamableHTTP sessions, answered with a `notifications/message` warning
+//! naming the replacement, so client developers notice before the alias is
+//! removed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::protocol::{JsonRpcRequest, LogLevel, LoggingMessageNotification};
+
+/// Logger named in deprecation warnings sent to clients
+pub const DEPRECATION_LOGGER: &str = "axum-mcp.deprecation";
+
+/// A method name accepted in place of another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MethodAlias {
+    /// Method the alias is handled as
+    pub target: String,
+
+    /// Warn clients that call the alias to move to `target`
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+/// Method alias table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MethodAliasConfig {
+    /// Aliases by the name clients send
+    pub aliases: HashMap<String, MethodAlias>,
+}
+
+impl MethodAliasConfig {
+    /// Handle `alias` as `target`
+    pub fn with_alias(mut self, alias: impl Into<String>, target: impl Into<String>) -> Self {
+        self.aliases.insert(
+            alias.into(),
+            MethodAlias {
+                target: target.into(),
+                deprecated: false,
+            },
+        );
+        self
+    }
+
+    /// Handle `old` as `new`, warning clients that still call `old`
+    pub fn with_deprecated(mut self, old: impl Into<String>, new: impl Into<String>) -> Self {
+        self.aliases.insert(
+            old.into(),
+            MethodAlias {
+                target: new.into(),
+                deprecated: true,
+            },
+        );
+        self
+    }
+
+    /// The alias entry for `method`, if it is an alias
+    pub fn get(&self, method: &str) -> Option<&MethodAlias> {
+        self.aliases.get(method)
+    }
+
+    /// Rename an aliased request to its target method
+    ///
+    /// Returns the alias the request was sent under, if it was rewritten.
+    pub fn resolve(&self, request: &mut JsonRpcRequest) -> Option<String> {
+        let alias = self.get(&request.method)?;
+        let target = alias.target.clone();
+        Some(std::mem::replace(&mut request.method, target))
+    }
+
+    /// Warning for a call to `method`, if it is a deprecated alias
+    pub fn deprecation_warning(&self, method: &str) -> Option<LoggingMessageNotification> {
+        let alias = self.get(method).filter(|alias| alias.deprecated)?;
+        Some(LoggingMessageNotification {
+            level: LogLevel::Warning,
+            logger: Some(DEPRECATION_LOGGER.to_string()),
+            data: serde_json::json!({
+                "message": format!(
+                    "Method '{}' is deprecated, use '{}' instead",
+                    method, alias.target
+                ),
+                "method": method,
+                "replacement": alias.target,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_and_warn() {
+        let aliases = MethodAliasConfig::default()
+            .with_alias("tool/call", "tools/call")
+            .with_deprecated("reports/make", "reports/generate");
+
+        let mut request = JsonRpcRequest::new("tool/call", None, Some(serde_json::json!(1)));
+        assert_eq!(aliases.resolve(&mut request).as_deref(), Some("tool/call"));
+        assert_eq!(request.method, "tools/call");
+        assert_eq!(aliases.resolve(&mut request), None);
+
+        assert!(aliases.deprecation_warning("tool/call").is_none());
+        let warning = aliases.deprecation_warning("reports/make").unwrap();
+        assert_eq!(warning.level, LogLevel::Warning);
+        assert_eq!(warning.data["replacement"], "reports/generate");
+    }
+}
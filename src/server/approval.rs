@@ -0,0 +1,358 @@
+//! Human approval for sensitive tool calls
+//!
+//! Tools registered with [`McpTool::require_approval`](crate::server::McpTool::require_approval)
+//! are not executed straight away. The server files an [`ApprovalRequest`],
+//! posts it to the configured webhook, and holds the call until an operator
+//! decides or the approval times out. Pending requests can be listed and
+//! decided through [`ApprovalManager`] or the `admin/approvals/list` and
+//! `admin/approvals/decide` methods, which are restricted to system contexts.
+//!
+//! Clients that sent a progress token are told about the pending approval and
+//! its outcome through progress notifications.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// Approval workflow configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    /// How long a call waits for a decision before it is denied
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// URL that new approval requests are POSTed to as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+impl Default for ApprovalConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(300),
+            webhook_url: None,
+        }
+    }
+}
+
+impl ApprovalConfig {
+    /// Post approval requests to a webhook
+    pub fn with_webhook(mut self, url: impl Into<String>) -> Self {
+        self.webhook_url = Some(url.into());
+        self
+    }
+
+    /// Set how long calls wait for a decision
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// A tool call waiting for a decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRequest {
+    /// Approval identifier, used to decide the request
+    pub id: String,
+
+    /// Name of the tool to be called
+    pub tool: String,
+
+    /// Principal making the call
+    pub principal: String,
+
+    /// Session the call was made in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Arguments the tool will be called with
+    pub arguments: serde_json::Value,
+
+    /// When the approval was requested
+    pub requested_at: DateTime<Utc>,
+
+    /// When the call is denied if nobody decides
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Decision on an approval request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    /// Run the tool
+    Approved,
+    /// Refuse the call
+    Denied {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+}
+
+/// Params of `admin/approvals/decide`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalDecisionParams {
+    /// Approval identifier
+    pub id: String,
+
+    /// The decision
+    #[serde(flatten)]
+    pub decision: ApprovalDecision,
+}
+
+struct PendingApproval {
+    request: ApprovalRequest,
+    decision: oneshot::Sender<ApprovalDecision>,
+}
+
+/// Pending approval requests of a server
+#[derive(Clone)]
+pub struct ApprovalManager {
+    pending: Arc<Mutex<HashMap<String, PendingApproval>>>,
+    config: ApprovalConfig,
+    http: reqwest::Client,
+    clock: Clock,
+}
+
+impl std::fmt::Debug for ApprovalManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalManager")
+            .field("config", &self.config)
+            .field("pending", &self.pending_count())
+            .finish()
+    }
+}
+
+/// Removes a pending approval when the waiting call goes away
+struct PendingGuard<'a> {
+    manager: &'a ApprovalManager,
+    id: String,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.manager.take_pending(&self.id);
+    }
+}
+
+impl ApprovalManager {
+    /// Create a manager with the given configuration
+    pub fn new(config: ApprovalConfig) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            config,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Time approvals out on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Approval configuration
+    pub fn config(&self) -> &ApprovalConfig {
+        &self.config
+    }
+
+    /// Build an approval request for a call
+    pub fn new_request(
+        &self,
+        tool: impl Into<String>,
+        context: &SecurityContext,
+        arguments: serde_json::Value,
+    ) -> ApprovalRequest {
+        let requested_at = DateTime::<Utc>::from(self.clock.system_time());
+        let timeout =
+            chrono::Duration::from_std(self.config.timeout).unwrap_or(chrono::Duration::MAX);
+        ApprovalRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.into(),
+            principal: context.principal(),
+            session_id: context.client.session_id.clone(),
+            arguments,
+            requested_at,
+            expires_at: requested_at
+                .checked_add_signed(timeout)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+
+    /// File a request and wait for its decision
+    ///
+    /// Returns once the call is approved. Denied and timed out calls fail
+    /// with an authorization error. Dropping the future withdraws the request.
+    pub async fn await_decision(&self, request: ApprovalRequest) -> McpResult<()> {
+        let (decision_tx, decision_rx) = oneshot::channel();
+        let id = request.id.clone();
+        let tool = request.tool.clone();
+        self.lock().insert(
+            id.clone(),
+            PendingApproval {
+                request: request.clone(),
+                decision: decision_tx,
+            },
+        );
+        let _guard = PendingGuard { manager: self, id };
+
+        info!(
+            "Tool call {} by {} awaits approval {}",
+            request.tool, request.principal, request.id
+        );
+        self.notify_webhook(&request).await;
+
+        match self.clock.timeout(self.config.timeout, decision_rx).await {
+            Some(Ok(ApprovalDecision::Approved)) => Ok(()),
+            Some(Ok(ApprovalDecision::Denied { reason })) => Err(McpError::Authorization {
+                message: match reason {
+                    Some(reason) => format!("Call to {} was denied: {}", tool, reason),
+                    None => format!("Call to {} was denied", tool),
+                },
+            }),
+            Some(Err(_)) => Err(McpError::Internal {
+                message: format!("Approval for {} was abandoned", tool),
+                source: None,
+            }),
+            None => Err(McpError::Authorization {
+                message: format!(
+                    "Call to {} was not approved within {:?}",
+                    tool, self.config.timeout
+                ),
+            }),
+        }
+    }
+
+    /// Decide a pending request
+    pub fn decide(&self, id: &str, decision: ApprovalDecision) -> McpResult<ApprovalRequest> {
+        let pending = self.take_pending(id).ok_or_else(|| McpError::Validation {
+            message: format!("Unknown or expired approval request: {}", id),
+        })?;
+        debug!("Approval {} decided: {:?}", id, decision);
+        // The call may have gone away since; the decision is moot then
+        let _ = pending.decision.send(decision);
+        Ok(pending.request)
+    }
+
+    /// Pending requests, oldest first
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        let mut requests: Vec<_> = self
+            .lock()
+            .values()
+            .map(|pending| pending.request.clone())
+            .collect();
+        requests.sort_by_key(|request| request.requested_at);
+        requests
+    }
+
+    /// Number of pending requests
+    pub fn pending_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn take_pending(&self, id: &str) -> Option<PendingApproval> {
+        self.lock().remove(id)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, PendingApproval>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn notify_webhook(&self, request: &ApprovalRequest) {
+        let Some(url) = &self.config.webhook_url else {
+            return;
+        };
+        let result = self
+            .http
+            .post(url)
+            .json(request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            // The request stays pending and can still be decided via the admin API
+            warn!("Failed to post approval request {}: {}", request.id, e);
+        }
+    }
+}
+
+impl Default for ApprovalManager {
+    fn default() -> Self {
+        Self::new(ApprovalConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_approval_decisions() {
+        let manager = ApprovalManager::default();
+        let context = SecurityContext::system();
+
+        let request = manager.new_request("deploy", &context, serde_json::json!({"env": "prod"}));
+        let id = request.id.clone();
+        let waiting = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.await_decision(request).await }
+        });
+        while manager.pending_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(manager.pending()[0].principal, "system");
+        manager.decide(&id, ApprovalDecision::Approved).unwrap();
+        assert!(waiting.await.unwrap().is_ok());
+        assert!(manager.decide(&id, ApprovalDecision::Approved).is_err());
+
+        let request = manager.new_request("deploy", &context, serde_json::Value::Null);
+        let id = request.id.clone();
+        let waiting = tokio::spawn({
+            let manager = manager.clone();
+            async move { manager.await_decision(request).await }
+        });
+        while manager.pending_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        manager
+            .decide(
+                &id,
+                ApprovalDecision::Denied {
+                    reason: Some("not today".to_string()),
+                },
+            )
+            .unwrap();
+        let error = waiting.await.unwrap().unwrap_err();
+        assert!(error.to_string().contains("not today"));
+    }
+
+    #[tokio::test]
+    async fn test_approval_timeout() {
+        let manager =
+            ApprovalManager::new(ApprovalConfig::default().with_timeout(Duration::from_millis(10)));
+        let request = manager.new_request(
+            "deploy",
+            &SecurityContext::system(),
+            serde_json::Value::Null,
+        );
+
+        assert!(manager.await_decision(request).await.is_err());
+        assert_eq!(manager.pending_count(), 0);
+    }
+}
@@ -0,0 +1,620 @@
+//! Resources inside zip and tar archives
+//!
+//! [`ArchiveResourceRegistry`] wraps another registry and serves the files
+//! inside the archives it holds. Reading `files://bundle.zip!/data/rows.csv`
+//! fetches `files://bundle.zip` from the wrapped registry, extracts
+//! `data/rows.csv` and returns it with its detected MIME type. Archives and
+//! extracted files are cached up to a byte budget, and the cache can be
+//! registered with a server's [`MemoryAccountant`] so it shrinks under memory
+//! pressure.
+//!
+//! Zip (stored or deflated entries), tar and gzip-compressed tar archives are
+//! supported, decoded with the `zip`, `tar` and `flate2` crates. Extracted
+//! files and archive listings are limited in size and entry count. The
+//! entries of archives added with [`mount`](ArchiveResourceRegistry::mount)
+//! are listed as resource templates.
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{Cursor, Read},
+    sync::{Arc, Mutex},
+};
+use zip::ZipArchive;
+
+use crate::{
+    error::{McpError, McpResult},
+    security::SecurityContext,
+    server::{
+        memory::{MemoryAccountant, MemoryCache},
+        resource::{
+            Resource, ResourceContent, ResourceRegistry, ResourceSubscription, ResourceTemplate,
+            UriSchemeConfig, VersionedResourceRegistry,
+        },
+    },
+};
+
+/// Separates the archive URI from the path inside it
+pub const ARCHIVE_SEPARATOR: &str = "!/";
+
+/// Default largest size of a single extracted file
+pub const DEFAULT_MAX_ENTRY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Default largest number of entries in an archive
+pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// Default byte budget of the archive and file cache
+pub const DEFAULT_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// Split `scheme://bundle.zip!/inner/path` into the archive URI and inner path
+pub fn split_archive_uri(uri: &str) -> Option<(&str, &str)> {
+    uri.split_once(ARCHIVE_SEPARATOR)
+}
+
+/// Registry serving the files inside archives held by another registry
+pub struct ArchiveResourceRegistry {
+    inner: Box<dyn ResourceRegistry>,
+    mounts: Vec<String>,
+    max_entry_bytes: usize,
+    max_entries: usize,
+    cache: Arc<Mutex<ByteCache>>,
+}
+
+impl ArchiveResourceRegistry {
+    /// Serve archives held by `inner`
+    pub fn new(inner: Box<dyn ResourceRegistry>) -> Self {
+        Self {
+            inner,
+            mounts: Vec::new(),
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            cache: Arc::new(Mutex::new(ByteCache::new(DEFAULT_CACHE_BYTES))),
+        }
+    }
+
+    /// List the entries of the archive at `uri` as resource templates
+    pub fn mount(mut self, uri: impl Into<String>) -> Self {
+        self.mounts.push(uri.into());
+        self
+    }
+
+    /// Refuse to extract files, or decompress gzip archives, larger than `bytes`
+    pub fn with_max_entry_bytes(mut self, bytes: usize) -> Self {
+        self.max_entry_bytes = bytes;
+        self
+    }
+
+    /// Refuse to list archives of more than `entries` entries
+    pub fn with_max_entries(mut self, entries: usize) -> Self {
+        self.max_entries = entries;
+        self
+    }
+
+    /// Cache archives and extracted files up to `bytes`
+    pub fn with_cache_bytes(self, bytes: usize) -> Self {
+        self.lock().capacity = bytes;
+        self
+    }
+
+    /// Count the cache in `memory` and let it evict under memory pressure
+    pub fn with_memory_accountant(self, memory: &MemoryAccountant) -> Self {
+        let cache: Arc<dyn MemoryCache> = self.cache.clone();
+        memory.register_cache(&cache);
+        self
+    }
+
+    /// Drop the cached archive at `uri` and the files extracted from it
+    pub fn invalidate(&self, uri: &str) {
+        let prefix = format!("{}{}", uri, ARCHIVE_SEPARATOR);
+        self.lock()
+            .retain(|key| key != uri && !key.starts_with(&prefix));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ByteCache> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Contents of the archive at `uri`, with gzip compression removed
+    async fn archive(&self, uri: &str, context: &SecurityContext) -> McpResult<Arc<Vec<u8>>> {
+        if let Some(bytes) = self.lock().get(uri) {
+            return Ok(bytes);
+        }
+        let resource = self.inner.get_resource(uri, context).await?;
+        let bytes = match resource.content {
+            ResourceContent::Text { text } => text.into_bytes(),
+            ResourceContent::Blob { blob, .. } => {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD
+                    .decode(blob)
+                    .map_err(|e| invalid(uri, format!("Invalid base64 content: {}", e)))?
+            }
+        };
+        let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+            gunzip(&bytes, self.max_entry_bytes).map_err(|message| invalid(uri, message))?
+        } else {
+            bytes
+        };
+        let bytes = Arc::new(bytes);
+        self.lock().insert(uri.to_string(), Arc::clone(&bytes));
+        Ok(bytes)
+    }
+
+    /// Extract the file at `uri`, which names an archive and a path inside it
+    async fn extract(&self, uri: &str, context: &SecurityContext) -> McpResult<Arc<Vec<u8>>> {
+        if let Some(bytes) = self.lock().get(uri) {
+            return Ok(bytes);
+        }
+        let (archive_uri, path) = split_archive_uri(uri).ok_or_else(|| {
+            invalid(
+                uri,
+                format!("Missing '{}' after the archive", ARCHIVE_SEPARATOR),
+            )
+        })?;
+        let archive = self.archive(archive_uri, context).await?;
+        let entries = list_entries(&archive, self.max_entries)
+            .map_err(|message| invalid(archive_uri, message))?;
+        let entry = entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: uri.to_string(),
+            })?;
+        if entry.size > self.max_entry_bytes {
+            return Err(invalid(
+                uri,
+                format!(
+                    "Entry of {} bytes exceeds the limit of {} bytes",
+                    entry.size, self.max_entry_bytes
+                ),
+            ));
+        }
+        let bytes = Arc::new(
+            entry
+                .read(&archive, self.max_entry_bytes)
+                .map_err(|message| invalid(uri, message))?,
+        );
+        self.lock().insert(uri.to_string(), Arc::clone(&bytes));
+        Ok(bytes)
+    }
+}
+
+#[async_trait]
+impl ResourceRegistry for ArchiveResourceRegistry {
+    fn uri_scheme(&self) -> &UriSchemeConfig {
+        self.inner.uri_scheme()
+    }
+
+    async fn list_resource_templates(
+        &self,
+        context: &SecurityContext,
+    ) -> McpResult<Vec<ResourceTemplate>> {
+        let mut templates = self.inner.list_resource_templates(context).await?;
+        for mount in &self.mounts {
+            let entries = match self.archive(mount, context).await.and_then(|archive| {
+                list_entries(&archive, self.max_entries).map_err(|message| invalid(mount, message))
+            }) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("Skipping archive {}: {}", mount, e);
+                    continue;
+                }
+            };
+            templates.extend(entries.into_iter().map(|entry| {
+                ResourceTemplate {
+                    uri_template: format!("{}{}{}", mount, ARCHIVE_SEPARATOR, entry.path),
+                    name: entry.path.clone(),
+                    description: Some(format!("{} in {}", entry.path, mount)),
+                    mime_type: mime_guess::from_path(&entry.path)
+                        .first_raw()
+                        .map(str::to_string),
+                    metadata: HashMap::from([("size".to_string(), entry.size.into())]),
+                }
+            }));
+        }
+        Ok(templates)
+    }
+
+    async fn get_resource(&self, uri: &str, context: &SecurityContext) -> McpResult<Resource> {
+        let Some((_, path)) = split_archive_uri(uri) else {
+            return self.inner.get_resource(uri, context).await;
+        };
+        let bytes = self.extract(uri, context).await?;
+        Ok(Resource::from_bytes(uri, path, &bytes, None))
+    }
+
+    async fn resource_exists(&self, uri: &str, context: &SecurityContext) -> McpResult<bool> {
+        match split_archive_uri(uri) {
+            Some((archive_uri, path)) => {
+                let archive = self.archive(archive_uri, context).await?;
+                let entries = list_entries(&archive, self.max_entries)
+                    .map_err(|message| invalid(archive_uri, message))?;
+                Ok(entries.iter().any(|entry| entry.path == path))
+            }
+            None => self.inner.resource_exists(uri, context).await,
+        }
+    }
+
+    async fn subscribe_to_resource(
+        &self,
+        uri: &str,
+        context: &SecurityContext,
+    ) -> McpResult<ResourceSubscription> {
+        // Files change with their archive
+        let archive_uri = split_archive_uri(uri).map_or(uri, |(archive_uri, _)| archive_uri);
+        self.inner.subscribe_to_resource(archive_uri, context).await
+    }
+
+    async fn unsubscribe_from_resource(
+        &self,
+        subscription_id: &str,
+        context: &SecurityContext,
+    ) -> McpResult<()> {
+        self.inner
+            .unsubscribe_from_resource(subscription_id, context)
+            .await
+    }
+
+    fn can_handle_uri(&self, uri: &str) -> bool {
+        let archive_uri = split_archive_uri(uri).map_or(uri, |(archive_uri, _)| archive_uri);
+        self.inner.can_handle_uri(archive_uri)
+    }
+
+    fn as_versioned(&self) -> Option<&dyn VersionedResourceRegistry> {
+        self.inner.as_versioned()
+    }
+
+    fn invalidate(&self, uri: &str) {
+        let archive_uri = split_archive_uri(uri).map_or(uri, |(archive_uri, _)| archive_uri);
+        ArchiveResourceRegistry::invalidate(self, archive_uri);
+        self.inner.invalidate(archive_uri);
+    }
+}
+
+fn invalid(uri: &str, message: impl Into<String>) -> McpError {
+    McpError::InvalidResource {
+        uri: uri.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Byte-bounded cache evicting the oldest entries first
+struct ByteCache {
+    entries: HashMap<String, Arc<Vec<u8>>>,
+    order: VecDeque<String>,
+    bytes: usize,
+    capacity: usize,
+}
+
+impl ByteCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            bytes: 0,
+            capacity,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Arc<Vec<u8>>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: String, value: Arc<Vec<u8>>) {
+        if value.len() > self.capacity {
+            return;
+        }
+        if let Some(old) = self.entries.remove(&key) {
+            self.bytes -= old.len();
+            self.order.retain(|k| *k != key);
+        }
+        while self.bytes + value.len() > self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.len();
+            }
+        }
+        self.bytes += value.len();
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+    }
+
+    /// Evict the oldest entries until at least `bytes` are freed
+    fn evict(&mut self, bytes: usize) -> usize {
+        let mut freed = 0;
+        while freed < bytes {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.len();
+                freed += evicted.len();
+            }
+        }
+        freed
+    }
+
+    fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        let entries = &mut self.entries;
+        let bytes = &mut self.bytes;
+        self.order.retain(|key| {
+            if keep(key) {
+                return true;
+            }
+            if let Some(removed) = entries.remove(key) {
+                *bytes -= removed.len();
+            }
+            false
+        });
+    }
+}
+
+impl MemoryCache for Mutex<ByteCache> {
+    fn cached_bytes(&self) -> u64 {
+        self.lock().unwrap_or_else(|e| e.into_inner()).bytes as u64
+    }
+
+    fn evict(&self, bytes: u64) -> u64 {
+        self.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .evict(bytes as usize) as u64
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZIP_LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+const ZIP_END_OF_DIRECTORY: &[u8] = b"PK\x05\x06";
+
+/// Where the data of an entry is found
+#[derive(Debug, Clone, Copy)]
+enum Location {
+    /// Index in the zip central directory
+    Zip(usize),
+    /// Offset in a tar archive, which stores files uncompressed
+    Tar(usize),
+}
+
+/// A file inside an archive
+#[derive(Debug, Clone)]
+pub(crate) struct ArchiveEntry {
+    pub(crate) path: String,
+    size: usize,
+    location: Location,
+}
+
+impl ArchiveEntry {
+    /// Extract the entry from `archive`, refusing more than `max_bytes`
+    pub(crate) fn read(&self, archive: &[u8], max_bytes: usize) -> Result<Vec<u8>, String> {
+        let bytes = match self.location {
+            Location::Zip(index) => {
+                let mut zip = ZipArchive::new(Cursor::new(archive)).map_err(|e| e.to_string())?;
+                let file = zip.by_index(index).map_err(|e| e.to_string())?;
+                read_limited(file, max_bytes)?
+            }
+            Location::Tar(offset) => archive
+                .get(offset..offset + self.size)
+                .ok_or("Archive is truncated")?
+                .to_vec(),
+        };
+        if bytes.len() != self.size {
+            return Err(format!(
+                "Expected {} bytes, extracted {}",
+                self.size,
+                bytes.len()
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+/// Files in a zip or tar archive, of at most `max_entries` entries
+pub(crate) fn list_entries(
+    archive: &[u8],
+    max_entries: usize,
+) -> Result<Vec<ArchiveEntry>, String> {
+    if archive.starts_with(ZIP_LOCAL_HEADER) || archive.starts_with(ZIP_END_OF_DIRECTORY) {
+        zip_entries(archive, max_entries)
+    } else if archive.get(257..262) == Some(b"ustar") {
+        tar_entries(archive, max_entries)
+    } else {
+        Err("Not a zip or tar archive".to_string())
+    }
+}
+
+fn zip_entries(archive: &[u8], max_entries: usize) -> Result<Vec<ArchiveEntry>, String> {
+    let mut zip = ZipArchive::new(Cursor::new(archive)).map_err(|e| e.to_string())?;
+    if zip.len() > max_entries {
+        return Err(too_many_entries(max_entries));
+    }
+    let mut entries = Vec::with_capacity(zip.len());
+    for index in 0..zip.len() {
+        let file = zip.by_index_raw(index).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        entries.push(ArchiveEntry {
+            path: file.name().to_string(),
+            size: entry_size(file.size())?,
+            location: Location::Zip(index),
+        });
+    }
+    Ok(entries)
+}
+
+fn tar_entries(archive: &[u8], max_entries: usize) -> Result<Vec<ArchiveEntry>, String> {
+    let mut tar = tar::Archive::new(archive);
+    let mut entries = Vec::new();
+    for (index, entry) in tar.entries().map_err(|e| e.to_string())?.enumerate() {
+        if index == max_entries {
+            return Err(too_many_entries(max_entries));
+        }
+        let entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|e| e.to_string())?;
+        entries.push(ArchiveEntry {
+            path: path.to_string_lossy().trim_start_matches("./").to_string(),
+            size: entry_size(entry.size())?,
+            location: Location::Tar(entry_size(entry.raw_file_position())?),
+        });
+    }
+    Ok(entries)
+}
+
+fn entry_size(size: u64) -> Result<usize, String> {
+    usize::try_from(size).map_err(|_| format!("Entry of {} bytes is too large", size))
+}
+
+fn too_many_entries(max_entries: usize) -> String {
+    format!("Archive has more than {} entries", max_entries)
+}
+
+/// Decompress a gzip member
+fn gunzip(bytes: &[u8], max_bytes: usize) -> Result<Vec<u8>, String> {
+    read_limited(GzDecoder::new(bytes), max_bytes)
+}
+
+/// Read `reader` to the end, refusing more than `max_bytes`
+fn read_limited(reader: impl Read, max_bytes: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    reader
+        .take(max_bytes as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() > max_bytes {
+        return Err(format!("Extracted data exceeds {} bytes", max_bytes));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::resource::InMemoryResourceRegistry;
+    use base64::Engine;
+
+    /// `data/rows.csv` (deflated, 39 rows) and `README.txt` (stored)
+    const BUNDLE_ZIP: &str = "UEsDBBQAAAAAAAAAIQAAAAAAAAAAAAAAAAAFAAAAZGF0YS9QSwMEFAAAAAgAVKNQXUvjivudAAAAfAEAAA0AAABkYXRhL3Jvd3MuY3N2LdAxTsRAEETR/J+CA2zgqrJn7OMgsQEBIJFwfVZTjir7r9WfH4/v968nevz+/L0JrzVZG/a1O8fag7F2MNdOzrUn19oLbQ1t6E4KNSqjZhXUsHbUtA7UuAZqXhMV0IlK6MI1vOEaft19H25cw8E1vOMaPnAND1zDE9fwiWv4IjWykRoRqZHXd+73hNTITmrkIDUySI1MUiMnqZGLf1BLAwQUAAAAAABUo1BdKPv9WA8AAAAPAAAACgAAAFJFQURNRS50eHRCdW5kbGUgb2Ygcm93cwpQSwECFAMUAAAAAAAAACEAAAAAAAAAAAAAAAAABQAAAAAAAAAAAAAAgAEAAAAAZGF0YS9QSwECFAMUAAAACABUo1BdS+OK+50AAAB8AQAADQAAAAAAAAAAAAAAgAEjAAAAZGF0YS9yb3dzLmNzdlBLAQIUAxQAAAAAAFSjUF0o+/1YDwAAAA8AAAAKAAAAAAAAAAAAAACAAesAAABSRUFETUUudHh0UEsFBgAAAAADAAMApgAAACIBAAAAAA==";
+
+    /// `dist/manifest.json` in a gzip-compressed tar
+    const DIST_TAR_GZ: &str = "H4sIAIGI0moC/+3NQQrCMBSE4bf2FCUHqC8SsuhtUlohoik0yUq8e6MboWsRxP/bzDCbmWIux1tI8Tzn0l/ykuTjtPHOvbLZp6r17/7crXXqpVP5gppLWNu9/Ke7GWu8Tmbo3OlxEAAAAAAAAAAAAAAAAADAj9gAhHvBPgAoAAA=";
+
+    fn registry() -> ArchiveResourceRegistry {
+        let mut files =
+            InMemoryResourceRegistry::new(UriSchemeConfig::new("files", "Build artifacts"));
+        for (uri, archive) in [
+            ("files://bundle.zip", BUNDLE_ZIP),
+            ("files://dist.tar.gz", DIST_TAR_GZ),
+        ] {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(archive)
+                .unwrap();
+            files.add_resource(Resource::from_bytes(uri, uri, &bytes, None));
+        }
+        ArchiveResourceRegistry::new(Box::new(files)).mount("files://bundle.zip")
+    }
+
+    fn text(resource: &Resource) -> &str {
+        match &resource.content {
+            ResourceContent::Text { text } => text,
+            ResourceContent::Blob { .. } => panic!("expected text"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_entries_are_served_and_listed() {
+        let registry = registry();
+        let context = SecurityContext::system();
+
+        let rows = registry
+            .get_resource("files://bundle.zip!/data/rows.csv", &context)
+            .await
+            .unwrap();
+        assert_eq!(rows.mime_type.as_deref(), Some("text/csv"));
+        assert_eq!(text(&rows).lines().count(), 40);
+        assert!(text(&rows).ends_with("39,row 39\n"));
+
+        let readme = registry
+            .get_resource("files://bundle.zip!/README.txt", &context)
+            .await
+            .unwrap();
+        assert_eq!(text(&readme), "Bundle of rows\n");
+
+        let manifest = registry
+            .get_resource("files://dist.tar.gz!/dist/manifest.json", &context)
+            .await
+            .unwrap();
+        assert_eq!(manifest.mime_type.as_deref(), Some("application/json"));
+        assert_eq!(text(&manifest), "{\"build\": 42}\n");
+
+        assert!(matches!(
+            registry
+                .get_resource("files://bundle.zip!/missing.txt", &context)
+                .await,
+            Err(McpError::ResourceNotFound { .. })
+        ));
+        assert!(registry.can_handle_uri("files://bundle.zip!/README.txt"));
+
+        let templates = registry.list_resource_templates(&context).await.unwrap();
+        let uris: Vec<&str> = templates.iter().map(|t| t.uri_template.as_str()).collect();
+        assert_eq!(
+            uris,
+            [
+                "files://bundle.zip!/data/rows.csv",
+                "files://bundle.zip!/README.txt"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extraction_is_limited_and_cached() {
+        let context = SecurityContext::system();
+        let registry = registry().with_max_entry_bytes(64);
+        assert!(matches!(
+            registry
+                .get_resource("files://bundle.zip!/data/rows.csv", &context)
+                .await,
+            Err(McpError::InvalidResource { .. })
+        ));
+
+        let registry = registry
+            .with_max_entry_bytes(DEFAULT_MAX_ENTRY_BYTES)
+            .with_max_entries(2);
+        assert!(matches!(
+            registry
+                .get_resource("files://bundle.zip!/README.txt", &context)
+                .await,
+            Err(McpError::InvalidResource { .. })
+        ));
+        registry.invalidate("files://bundle.zip");
+
+        let registry = registry.with_max_entries(DEFAULT_MAX_ENTRIES);
+        registry
+            .get_resource("files://bundle.zip!/README.txt", &context)
+            .await
+            .unwrap();
+        assert!(registry.lock().get("files://bundle.zip").is_some());
+        assert!(registry
+            .lock()
+            .get("files://bundle.zip!/README.txt")
+            .is_some());
+
+        registry.invalidate("files://bundle.zip");
+        assert!(registry.lock().entries.is_empty());
+        assert_eq!(registry.lock().bytes, 0);
+
+        // Memory pressure empties the cache
+        let memory = MemoryAccountant::new(crate::server::MemoryConfig::enabled(0));
+        let registry = registry.with_memory_accountant(&memory);
+        registry
+            .get_resource("files://bundle.zip!/README.txt", &context)
+            .await
+            .unwrap();
+        assert!(memory.usage().cache_bytes > 0);
+        memory.check("resources/read").unwrap();
+        assert_eq!(memory.usage().cache_bytes, 0);
+    }
+}
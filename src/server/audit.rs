@@ -0,0 +1,477 @@
+//! Tool execution audit trail
+//!
+//! When auditing is enabled the server records every `tools/call` and exposes
+//! the recent records as resources under the built-in `mcp-audit` scheme:
+//!
+//! - `mcp-audit://executions` lists the retained executions, newest first
+//! - `mcp-audit://executions/{id}` returns a single execution
+//!
+//! Records hold who called which tool, a hash of the arguments (never the
+//! arguments themselves), how long the call took and how it ended. Principals
+//! that may read traces see every record; everyone else only sees their own.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::ToolsCallResult,
+    security::SecurityContext,
+    server::resource::{
+        Resource, ResourceContent, ResourceRegistry, ResourceSubscription, ResourceTemplate,
+        UriSchemeConfig,
+    },
+};
+
+/// URI scheme of the built-in audit resources
+pub const AUDIT_SCHEME: &str = "mcp-audit";
+
+/// How many executions the audit trail keeps, and for how long
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRetention {
+    /// Maximum number of executions kept; the oldest are dropped first
+    pub max_records: usize,
+
+    /// Maximum age of a kept execution
+    #[serde(with = "humantime_serde")]
+    pub max_age: Duration,
+}
+
+impl Default for AuditRetention {
+    fn default() -> Self {
+        Self {
+            max_records: 1000,
+            max_age: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// How a tool execution ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExecutionOutcome {
+    /// The tool returned a result
+    Success,
+    /// The tool returned a result flagged `isError`
+    ToolError,
+    /// The call failed before producing a result
+    Failed { error: String },
+}
+
+impl ExecutionOutcome {
+    /// Outcome of a `tools/call` result
+    pub fn from_result(result: &McpResult<ToolsCallResult>) -> Self {
+        match result {
+            Ok(result) if result.is_error => Self::ToolError,
+            Ok(_) => Self::Success,
+            Err(error) => Self::Failed {
+                error: error.client_message(),
+            },
+        }
+    }
+}
+
+/// A single recorded tool execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolExecutionRecord {
+    /// Execution identifier, used in the resource URI
+    pub id: String,
+
+    /// Name of the called tool
+    pub tool: String,
+
+    /// Principal that made the call (see [`SecurityContext::principal`])
+    pub principal: String,
+
+    /// Session the call was made in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Principal that made the call on behalf of `principal`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegated_by: Option<String>,
+
+    /// Principal that impersonated `principal` to make the call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<String>,
+
+    /// Service account that ran the call for `principal`, such as the batch
+    /// executor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub executed_by: Option<String>,
+
+    /// Hex encoded SHA-256 of the JSON arguments
+    pub arguments_hash: String,
+
+    /// When the execution started
+    pub started_at: DateTime<Utc>,
+
+    /// How long the execution took in milliseconds
+    pub duration_ms: u64,
+
+    /// How the execution ended
+    pub outcome: ExecutionOutcome,
+}
+
+impl ToolExecutionRecord {
+    /// Create a record for a finished execution
+    pub fn new(
+        tool: impl Into<String>,
+        context: &SecurityContext,
+        arguments: &serde_json::Value,
+        started_at: DateTime<Utc>,
+        duration: Duration,
+        outcome: ExecutionOutcome,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.into(),
+            principal: context.principal(),
+            session_id: context.client.session_id.clone(),
+            delegated_by: context.delegator().map(str::to_string),
+            impersonated_by: context.impersonator().map(str::to_string),
+            executed_by: context.executor().map(str::to_string),
+            arguments_hash: hash_arguments(arguments),
+            started_at,
+            duration_ms: duration.as_millis() as u64,
+            outcome,
+        }
+    }
+
+    /// URI of the resource exposing this record
+    pub fn uri(&self) -> String {
+        format!("{}://executions/{}", AUDIT_SCHEME, self.id)
+    }
+}
+
+/// Hex encoded SHA-256 of a JSON value
+///
+/// Object keys serialize in sorted order, so equal arguments hash equally.
+pub fn hash_arguments(arguments: &serde_json::Value) -> String {
+    let digest = Sha256::digest(arguments.to_string().as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Recent tool executions, exposed as `mcp-audit://` resources
+#[derive(Debug, Clone)]
+pub struct ExecutionAuditTrail {
+    records: Arc<RwLock<VecDeque<ToolExecutionRecord>>>,
+    retention: AuditRetention,
+    scheme: Arc<UriSchemeConfig>,
+}
+
+impl ExecutionAuditTrail {
+    /// Create an empty trail with the given retention
+    pub fn new(retention: AuditRetention) -> Self {
+        Self {
+            records: Arc::new(RwLock::new(VecDeque::new())),
+            retention,
+            scheme: Arc::new(
+                UriSchemeConfig::new(AUDIT_SCHEME, "Tool execution audit trail")
+                    .with_types(vec!["execution".to_string()]),
+            ),
+        }
+    }
+
+    /// Retention applied to the trail
+    pub fn retention(&self) -> &AuditRetention {
+        &self.retention
+    }
+
+    /// Record an execution, dropping records outside the retention
+    pub async fn record(&self, record: ToolExecutionRecord) {
+        let mut records = self.records.write().await;
+        records.push_back(record);
+        self.prune(&mut records);
+    }
+
+    /// Get a retained execution by ID
+    pub async fn get(&self, id: &str) -> Option<ToolExecutionRecord> {
+        let mut records = self.records.write().await;
+        self.prune(&mut records);
+        records.iter().find(|record| record.id == id).cloned()
+    }
+
+    /// Retained executions visible to `context`, newest first
+    pub async fn recent(&self, context: &SecurityContext) -> Vec<ToolExecutionRecord> {
+        let mut records = self.records.write().await;
+        self.prune(&mut records);
+        records
+            .iter()
+            .rev()
+            .filter(|record| can_see(context, record))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of retained executions
+    pub async fn len(&self) -> usize {
+        self.records.read().await.len()
+    }
+
+    /// Whether no executions are retained
+    pub async fn is_empty(&self) -> bool {
+        self.records.read().await.is_empty()
+    }
+
+    fn prune(&self, records: &mut VecDeque<ToolExecutionRecord>) {
+        while records.len() > self.retention.max_records {
+            records.pop_front();
+        }
+
+        // Records are appended when calls finish, so they are only roughly
+        // ordered by start time
+        let cutoff = chrono::Duration::from_std(self.retention.max_age)
+            .ok()
+            .and_then(|max_age| Utc::now().checked_sub_signed(max_age));
+        if let Some(cutoff) = cutoff {
+            records.retain(|record| record.started_at >= cutoff);
+        }
+    }
+
+    fn resource_id<'a>(&self, uri: &'a str) -> McpResult<Option<&'a str>> {
+        let path = uri
+            .strip_prefix(AUDIT_SCHEME)
+            .and_then(|rest| rest.strip_prefix("://executions"))
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: uri.to_string(),
+            })?;
+
+        match path.trim_end_matches('/') {
+            "" => Ok(None),
+            id => id
+                .strip_prefix('/')
+                .filter(|id| !id.is_empty() && !id.contains('/'))
+                .map(Some)
+                .ok_or_else(|| McpError::ResourceNotFound {
+                    uri: uri.to_string(),
+                }),
+        }
+    }
+}
+
+impl Default for ExecutionAuditTrail {
+    fn default() -> Self {
+        Self::new(AuditRetention::default())
+    }
+}
+
+/// Trace readers and system contexts see all executions, others their own
+/// and those they delegated or impersonated
+fn can_see(context: &SecurityContext, record: &ToolExecutionRecord) -> bool {
+    let principal = context.principal();
+    context.is_system()
+        || context.permissions.can_read_traces
+        || record.principal == principal
+        || record.delegated_by.as_ref() == Some(&principal)
+        || record.impersonated_by.as_ref() == Some(&principal)
+}
+
+fn json_resource(
+    uri: String,
+    name: String,
+    description: &str,
+    value: impl Serialize,
+) -> McpResult<Resource> {
+    Ok(Resource {
+        uri,
+        name,
+        description: Some(description.to_string()),
+        mime_type: Some("application/json".to_string()),
+        content: ResourceContent::Text {
+            text: serde_json::to_string_pretty(&value)?,
+        },
+        metadata: HashMap::new(),
+    })
+}
+
+#[async_trait]
+impl ResourceRegistry for ExecutionAuditTrail {
+    fn uri_scheme(&self) -> &UriSchemeConfig {
+        &self.scheme
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _context: &SecurityContext,
+    ) -> McpResult<Vec<ResourceTemplate>> {
+        let template = |uri_template: String, name: &str, description: &str| ResourceTemplate {
+            uri_template,
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            mime_type: Some("application/json".to_string()),
+            metadata: HashMap::new(),
+        };
+
+        Ok(vec![
+            template(
+                format!("{}://executions", AUDIT_SCHEME),
+                "Recent tool executions",
+                "Tool executions retained by the audit trail, newest first",
+            ),
+            template(
+                format!("{}://executions/{{id}}", AUDIT_SCHEME),
+                "Tool execution",
+                "A single tool execution: caller, arguments hash, duration and outcome",
+            ),
+        ])
+    }
+
+    async fn get_resource(&self, uri: &str, context: &SecurityContext) -> McpResult<Resource> {
+        match self.resource_id(uri)? {
+            None => {
+                let records = self.recent(context).await;
+                json_resource(
+                    uri.to_string(),
+                    "Recent tool executions".to_string(),
+                    "Tool executions retained by the audit trail, newest first",
+                    serde_json::json!({ "executions": records }),
+                )
+            }
+            Some(id) => {
+                let record = self
+                    .get(id)
+                    .await
+                    .filter(|record| can_see(context, record))
+                    .ok_or_else(|| McpError::ResourceNotFound {
+                        uri: uri.to_string(),
+                    })?;
+                json_resource(
+                    uri.to_string(),
+                    format!("Execution of {}", record.tool),
+                    "A single tool execution: caller, arguments hash, duration and outcome",
+                    record,
+                )
+            }
+        }
+    }
+
+    async fn resource_exists(&self, uri: &str, context: &SecurityContext) -> McpResult<bool> {
+        match self.resource_id(uri) {
+            Ok(None) => Ok(true),
+            Ok(Some(id)) => Ok(self
+                .get(id)
+                .await
+                .is_some_and(|record| can_see(context, &record))),
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn subscribe_to_resource(
+        &self,
+        uri: &str,
+        _context: &SecurityContext,
+    ) -> McpResult<ResourceSubscription> {
+        Err(McpError::InvalidResource {
+            uri: uri.to_string(),
+            message: "Audit resources do not support subscriptions".to_string(),
+        })
+    }
+
+    async fn unsubscribe_from_resource(
+        &self,
+        _subscription_id: &str,
+        _context: &SecurityContext,
+    ) -> McpResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::ClientContext;
+
+    fn client(id: &str) -> SecurityContext {
+        SecurityContext::authenticated(
+            ClientContext {
+                client_id: Some(id.to_string()),
+                ..Default::default()
+            },
+            Vec::new(),
+        )
+    }
+
+    fn record(context: &SecurityContext, tool: &str) -> ToolExecutionRecord {
+        ToolExecutionRecord::new(
+            tool,
+            context,
+            &serde_json::json!({"b": 2, "a": 1}),
+            Utc::now(),
+            Duration::from_millis(5),
+            ExecutionOutcome::Success,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_audit_resources() {
+        let trail = ExecutionAuditTrail::default();
+        let alice = client("alice");
+        let bob = client("bob");
+
+        let alice_record = record(&alice, "search");
+        trail.record(alice_record.clone()).await;
+        trail.record(record(&bob, "deploy")).await;
+
+        let listing = trail
+            .get_resource("mcp-audit://executions", &SecurityContext::system())
+            .await
+            .unwrap();
+        let ResourceContent::Text { text } = listing.content else {
+            panic!("expected text content");
+        };
+        let listing: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(listing["executions"][0]["tool"], "deploy");
+        assert_eq!(listing["executions"][1]["principal"], "alice");
+        assert_eq!(
+            listing["executions"][1]["argumentsHash"],
+            hash_arguments(&serde_json::json!({"a": 1, "b": 2}))
+        );
+
+        // Other principals cannot see alice's execution
+        assert_eq!(trail.recent(&bob).await.len(), 1);
+        assert!(trail.get_resource(&alice_record.uri(), &bob).await.is_err());
+        assert!(trail
+            .get_resource(&alice_record.uri(), &alice)
+            .await
+            .is_ok());
+        assert!(trail
+            .get_resource("mcp-audit://executions/a/b", &alice)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_audit_retention() {
+        let trail = ExecutionAuditTrail::new(AuditRetention {
+            max_records: 2,
+            max_age: Duration::from_secs(60),
+        });
+        let context = SecurityContext::system();
+
+        let mut stale = record(&context, "old");
+        stale.started_at = Utc::now() - chrono::Duration::minutes(5);
+        trail.record(stale).await;
+        assert!(trail.is_empty().await);
+
+        for tool in ["one", "two", "three"] {
+            trail.record(record(&context, tool)).await;
+        }
+        let tools: Vec<_> = trail
+            .recent(&context)
+            .await
+            .into_iter()
+            .map(|record| record.tool)
+            .collect();
+        assert_eq!(tools, vec!["three", "two"]);
+    }
+}
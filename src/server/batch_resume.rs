@@ -0,0 +1,229 @@
+//! Resumable batches keyed by correlation token
+//!
+//! A batch sent with a `correlationToken` records each item that succeeds.
+//! If the connection drops before the batch is answered, the client can send
+//! the same batch again with the same token: items that already succeeded
+//! are answered from the record, marked with [`RESUMED_META`], and only the
+//! rest are run. Failed items are run again, since their errors are often
+//! transient.
+//!
+//! A resumed batch that arrives while the original is still running waits
+//! for it, so no item runs twice. Records belong to the principal that sent
+//! the batch and are kept for [`BatchResumeConfig::retention`] after the
+//! batch was last sent.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::OwnedMutexGuard;
+use tracing::debug;
+
+use crate::{clock::Clock, protocol::BatchItemResult, security::SecurityContext};
+
+/// Item metadata key marking results answered from an earlier run
+pub const RESUMED_META: &str = "resumed";
+
+/// Resumable batch configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchResumeConfig {
+    /// Whether batches with a correlation token are recorded
+    pub enabled: bool,
+
+    /// How long a batch's record is kept after the batch was last sent
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+
+    /// Batches recorded at once; the least recently used are dropped first
+    pub max_batches: usize,
+}
+
+impl Default for BatchResumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention: Duration::from_secs(600),
+            max_batches: 1000,
+        }
+    }
+}
+
+impl BatchResumeConfig {
+    /// Keep records for `retention` after the batch was last sent
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+}
+
+/// Records keyed by principal and correlation token, with when each was last sent
+type Records = HashMap<(String, String), (Record, Instant)>;
+
+#[derive(Debug, Default)]
+struct Record {
+    /// Held while a batch with this token runs
+    running: Arc<tokio::sync::Mutex<()>>,
+    results: Arc<Mutex<HashMap<String, BatchItemResult>>>,
+}
+
+/// Recorded progress of one batch, held while it runs
+#[derive(Debug)]
+pub struct BatchProgress {
+    results: Arc<Mutex<HashMap<String, BatchItemResult>>>,
+    _running: OwnedMutexGuard<()>,
+}
+
+impl BatchProgress {
+    /// Result of an item that succeeded in an earlier run
+    pub fn completed(&self, id: &str) -> Option<BatchItemResult> {
+        let mut result = self.results.lock().unwrap().get(id).cloned()?;
+        result
+            .metadata
+            .insert(RESUMED_META.to_string(), serde_json::Value::Bool(true));
+        Some(result)
+    }
+
+    /// Record an item's result; only successes are kept
+    pub fn record(&self, result: &BatchItemResult) {
+        if result.error.is_none() && !result.skipped {
+            self.results
+                .lock()
+                .unwrap()
+                .insert(result.id.clone(), result.clone());
+        }
+    }
+
+    /// Number of items recorded
+    pub fn len(&self) -> usize {
+        self.results.lock().unwrap().len()
+    }
+
+    /// Whether no item has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Recorded batches of a server
+#[derive(Debug, Clone)]
+pub struct BatchJournal {
+    config: BatchResumeConfig,
+    records: Arc<Mutex<Records>>,
+    clock: Clock,
+}
+
+impl BatchJournal {
+    /// Record batches as configured
+    pub fn new(config: BatchResumeConfig) -> Self {
+        Self {
+            config,
+            records: Arc::new(Mutex::new(HashMap::new())),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Measure retention on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Start or resume the batch with `token` of the caller
+    ///
+    /// Waits while another batch with the same token runs. Returns `None`
+    /// when recording is disabled.
+    pub async fn begin(&self, context: &SecurityContext, token: &str) -> Option<BatchProgress> {
+        if !self.config.enabled {
+            return None;
+        }
+        let (running, results) = {
+            let now = self.clock.now();
+            let mut records = self.records.lock().unwrap();
+            records
+                .retain(|_, (_, last_used)| now.duration_since(*last_used) < self.config.retention);
+            let key = (context.principal(), token.to_string());
+            if !records.contains_key(&key) && records.len() >= self.config.max_batches {
+                let oldest = records
+                    .iter()
+                    .min_by_key(|(_, (_, last_used))| *last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    records.remove(&oldest);
+                }
+            }
+            let (record, last_used) = records
+                .entry(key)
+                .or_insert_with(|| (Record::default(), now));
+            *last_used = now;
+            (Arc::clone(&record.running), Arc::clone(&record.results))
+        };
+
+        let running = running.lock_owned().await;
+        let progress = BatchProgress {
+            results,
+            _running: running,
+        };
+        if !progress.is_empty() {
+            debug!(
+                "Resuming batch {} with {} completed items",
+                token,
+                progress.len()
+            );
+        }
+        Some(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, ok: bool) -> BatchItemResult {
+        BatchItemResult {
+            id: id.to_string(),
+            result: ok.then(|| serde_json::json!({"ok": true})),
+            error: (!ok).then(|| crate::protocol::JsonRpcError {
+                code: -32603,
+                message: "failed".to_string(),
+                data: None,
+            }),
+            execution_time_ms: 0,
+            skipped: false,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_resumption() {
+        let clock = Clock::manual();
+        let journal = BatchJournal::new(BatchResumeConfig::default()).with_clock(clock.clone());
+        let context = SecurityContext::system();
+
+        let progress = journal.begin(&context, "nightly").await.unwrap();
+        progress.record(&item("a", true));
+        progress.record(&item("b", false));
+
+        // A resumed batch waits for the running one
+        let resumed = tokio::spawn({
+            let journal = journal.clone();
+            let context = context.clone();
+            async move { journal.begin(&context, "nightly").await.unwrap() }
+        });
+        tokio::task::yield_now().await;
+        assert!(!resumed.is_finished());
+        drop(progress);
+
+        let progress = resumed.await.unwrap();
+        let a = progress.completed("a").unwrap();
+        assert_eq!(a.metadata[RESUMED_META], true);
+        assert!(progress.completed("b").is_none());
+        drop(progress);
+
+        clock.advance(Duration::from_secs(600));
+        let progress = journal.begin(&context, "nightly").await.unwrap();
+        assert!(progress.is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+//! Dependency ordering and cross-item templating for batches
+//!
+//! In `dependency` and `priority_dependency` mode, batch items run in an order
+//! that respects their `dependencies`, and string parameters may reference the
+//! results of earlier items:
+//!
+//! ```json
+//! {"id": "read", "method": "resources/read",
+//!  "params": {"uri": "${items.search.result.results.0.uri}"}}
+//! ```
+//!
+//! A string that is exactly one placeholder is replaced by the referenced JSON
+//! value; placeholders inside longer strings are interpolated, with non-string
+//! values written as JSON. An item referenced by a placeholder is an implicit
+//! dependency.
+
+use serde_json::Value;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::BatchRequest,
+};
+
+const OPEN: &str = "${";
+const PREFIX: &str = "items.";
+
+/// A parsed `${items.<id>.result.<path>}` placeholder
+struct Placeholder<'a> {
+    id: &'a str,
+    path: Vec<&'a str>,
+}
+
+impl<'a> Placeholder<'a> {
+    fn parse(expression: &'a str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid placeholder '${{{}}}'; expected ${{items.<id>.result.<path>}}",
+                expression
+            )
+        };
+        let rest = expression.strip_prefix(PREFIX).ok_or_else(invalid)?;
+        let mut segments = rest.split('.');
+        let id = segments
+            .next()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(invalid)?;
+        if segments.next() != Some("result") {
+            return Err(invalid());
+        }
+        Ok(Self {
+            id,
+            path: segments.collect(),
+        })
+    }
+
+    fn resolve(&self, results: &HashMap<String, Value>) -> Result<Value, String> {
+        let mut value = results
+            .get(self.id)
+            .ok_or_else(|| format!("Item '{}' has no result", self.id))?;
+        for segment in &self.path {
+            value = match value {
+                Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                Value::Object(fields) => fields.get(*segment),
+                _ => None,
+            }
+            .ok_or_else(|| {
+                format!(
+                    "Result of item '{}' has no '{}'",
+                    self.id,
+                    self.path.join(".")
+                )
+            })?;
+        }
+        Ok(value.clone())
+    }
+}
+
+/// Call `visit` with the expression of each placeholder in `text`
+fn placeholders<'a>(text: &'a str, mut visit: impl FnMut(&'a str)) {
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        let after = &rest[start + OPEN.len()..];
+        let Some(end) = after.find('}') else {
+            return;
+        };
+        visit(&after[..end]);
+        rest = &after[end + 1..];
+    }
+}
+
+/// IDs of the items referenced by placeholders in `params`
+pub fn references(params: &Value) -> Vec<String> {
+    let mut ids = Vec::new();
+    match params {
+        Value::String(text) => placeholders(text, |expression| {
+            if let Ok(placeholder) = Placeholder::parse(expression) {
+                ids.push(placeholder.id.to_string());
+            }
+        }),
+        Value::Array(items) => items.iter().for_each(|item| ids.extend(references(item))),
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|field| ids.extend(references(field))),
+        _ => {}
+    }
+    ids
+}
+
+/// Substitute placeholders in `params` with values from `results`
+///
+/// `results` maps item IDs to the results of items that succeeded.
+pub fn render(params: &Value, results: &HashMap<String, Value>) -> Result<Value, String> {
+    Ok(match params {
+        Value::String(text) => {
+            let whole = text
+                .strip_prefix(OPEN)
+                .and_then(|rest| rest.strip_suffix('}'))
+                .filter(|expression| !expression.contains('}'));
+            if let Some(expression) = whole {
+                return Placeholder::parse(expression)?.resolve(results);
+            }
+
+            let mut rendered = String::with_capacity(text.len());
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find(OPEN) {
+                let after = &rest[start + OPEN.len()..];
+                let Some(end) = after.find('}') else {
+                    break;
+                };
+                rendered.push_str(&rest[..start]);
+                match Placeholder::parse(&after[..end])?.resolve(results)? {
+                    Value::String(value) => rendered.push_str(&value),
+                    value => rendered.push_str(&value.to_string()),
+                }
+                rest = &after[end + 1..];
+            }
+            rendered.push_str(rest);
+            Value::String(rendered)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render(item, results))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, field)| Ok((key.clone(), render(field, results)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Dependencies of `item`, declared and referenced by placeholders
+pub fn dependencies(item: &BatchRequest) -> Vec<String> {
+    let mut ids = item.dependencies.clone();
+    if let Some(params) = &item.params {
+        for id in references(params) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// Indices of `items` in an order where every item follows its dependencies
+///
+/// Among items that are ready, higher `priority` runs first when
+/// `by_priority` is set; otherwise, and on ties, batch order is kept.
+pub fn dependency_order(items: &[BatchRequest], by_priority: bool) -> McpResult<Vec<usize>> {
+    let index: HashMap<&str, usize> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (item.id.as_str(), i))
+        .collect();
+    if index.len() != items.len() {
+        return Err(McpError::Validation {
+            message: "Batch item IDs must be unique".to_string(),
+        });
+    }
+
+    let mut waiting = Vec::with_capacity(items.len());
+    for item in items {
+        let mut ids = HashSet::new();
+        for id in dependencies(item) {
+            let dependency = *index.get(id.as_str()).ok_or_else(|| McpError::Validation {
+                message: format!("Batch item '{}' depends on unknown item '{}'", item.id, id),
+            })?;
+            ids.insert(dependency);
+        }
+        waiting.push(ids);
+    }
+
+    let mut order = Vec::with_capacity(items.len());
+    let mut done = vec![false; items.len()];
+    while order.len() < items.len() {
+        let next = (0..items.len())
+            .filter(|&i| !done[i] && waiting[i].iter().all(|&d| done[d]))
+            .min_by_key(|&i| (Reverse(if by_priority { items[i].priority } else { 0 }), i))
+            .ok_or_else(|| McpError::Validation {
+                message: "Batch item dependencies form a cycle".to_string(),
+            })?;
+        done[next] = true;
+        order.push(next);
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(id: &str, priority: i32, params: Value) -> BatchRequest {
+        BatchRequest {
+            id: id.to_string(),
+            method: "tools/call".to_string(),
+            params: Some(params),
+            dependencies: Vec::new(),
+            timeout_ms: None,
+            priority,
+            on_behalf_of: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_render_placeholders() {
+        let results = HashMap::from([(
+            "search".to_string(),
+            json!({"results": [{"uri": "file:///a.md", "score": 2}]}),
+        )]);
+        let params = json!({
+            "uri": "${items.search.result.results.0.uri}",
+            "score": "${items.search.result.results.0.score}",
+            "note": "best: ${items.search.result.results.0.uri} (${items.search.result.results.0.score})"
+        });
+
+        assert_eq!(
+            render(&params, &results).unwrap(),
+            json!({"uri": "file:///a.md", "score": 2, "note": "best: file:///a.md (2)"})
+        );
+        assert!(render(&json!("${items.other.result}"), &results).is_err());
+        assert!(render(&json!("${items.search.result.missing}"), &results).is_err());
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let items = vec![
+            item("read", 0, json!({"uri": "${items.search.result.uri}"})),
+            item("search", 0, json!({})),
+            item("urgent", 5, json!({})),
+        ];
+        assert_eq!(dependency_order(&items, false).unwrap(), vec![1, 0, 2]);
+        assert_eq!(dependency_order(&items, true).unwrap(), vec![2, 1, 0]);
+
+        let cycle = vec![
+            item("a", 0, json!("${items.b.result}")),
+            item("b", 0, json!("${items.a.result}")),
+        ];
+        assert!(dependency_order(&cycle, false).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Build and runtime metadata in the server info
+//!
+//! "Which deployment am I talking to?" is the first question when debugging a
+//! client against several servers. The server adds a `build` object to the
+//! `serverInfo` of the `initialize` result, and answers `server/info` with the
+//! same server info at any time:
+//!
+//! ```json
+//! {"name": "notes", "version": "1.4.0", "build": {
+//!     "crateVersion": "0.1.0", "gitHash": "3f9c2ab", "features": ["axum", "server"],
+//!     "protocolVersions": ["2024-11-05", "2025-03-26"],
+//!     "startedAt": "2026-10-17T08:00:00Z", "uptimeSeconds": 5400}}
+//! ```
+//!
+//! The git hash comes from [`BuildInfoConfig::git_hash`], or the
+//! [`GIT_HASH_ENV`] environment variable at run time or, failing that, when
+//! the crate was compiled.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+use crate::protocol::{ServerInfo, SUPPORTED_PROTOCOL_VERSIONS};
+
+/// Returns the server info, including the build metadata
+pub const SERVER_INFO: &str = "server/info";
+
+/// `serverInfo` field holding the build metadata
+pub const BUILD_INFO_KEY: &str = "build";
+
+/// Environment variable holding the git hash of the deployment
+pub const GIT_HASH_ENV: &str = "MCP_GIT_HASH";
+
+/// Cargo features of this crate that can be reported
+const FEATURES: &[(&str, bool)] = &[
+    ("axum", cfg!(feature = "axum")),
+    ("chaos", cfg!(feature = "chaos")),
+    ("cli", cfg!(feature = "cli")),
+    ("client", cfg!(feature = "client")),
+    ("documents", cfg!(feature = "documents")),
+    ("http2", cfg!(feature = "http2")),
+    ("isolation", cfg!(feature = "isolation")),
+    ("metrics", cfg!(feature = "metrics")),
+    ("nats", cfg!(feature = "nats")),
+    ("node", cfg!(feature = "node")),
+    ("prompts", cfg!(feature = "prompts")),
+    ("python", cfg!(feature = "python")),
+    ("redis", cfg!(feature = "redis")),
+    ("resources", cfg!(feature = "resources")),
+    ("runtime", cfg!(feature = "runtime")),
+    ("sandbox", cfg!(feature = "sandbox")),
+    ("server", cfg!(feature = "server")),
+    ("simd-json", cfg!(feature = "simd-json")),
+    ("sse", cfg!(feature = "sse")),
+    ("stdio", cfg!(feature = "stdio")),
+    ("tiktoken", cfg!(feature = "tiktoken")),
+    ("timezones", cfg!(feature = "timezones")),
+    ("tls", cfg!(feature = "tls")),
+    ("transport", cfg!(feature = "transport")),
+    ("ui", cfg!(feature = "ui")),
+    ("vault", cfg!(feature = "vault")),
+    ("wasm", cfg!(feature = "wasm")),
+    ("ws", cfg!(feature = "ws")),
+];
+
+/// Build metadata settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BuildInfoConfig {
+    /// Whether `serverInfo` carries the build metadata
+    pub enabled: bool,
+
+    /// Git hash of the deployment, overriding [`GIT_HASH_ENV`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_hash: Option<String>,
+}
+
+impl Default for BuildInfoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            git_hash: None,
+        }
+    }
+}
+
+/// Build and runtime metadata of a running server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    /// Version of this crate
+    pub crate_version: String,
+
+    /// Git hash of the deployment, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_hash: Option<String>,
+
+    /// Enabled Cargo features of this crate
+    pub features: Vec<String>,
+
+    /// Protocol versions the server speaks
+    pub protocol_versions: Vec<String>,
+
+    /// When the server started
+    pub started_at: DateTime<Utc>,
+
+    /// Seconds since the server started
+    pub uptime_seconds: u64,
+}
+
+impl BuildInfo {
+    /// Metadata of a server started at `started_at`, as of `now`
+    pub fn collect(config: &BuildInfoConfig, started_at: SystemTime, now: SystemTime) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: config.git_hash.clone().or_else(git_hash),
+            features: enabled_features().into_iter().map(str::to_string).collect(),
+            protocol_versions: SUPPORTED_PROTOCOL_VERSIONS
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            started_at: started_at.into(),
+            uptime_seconds: now.duration_since(started_at).unwrap_or_default().as_secs(),
+        }
+    }
+
+    /// Add this metadata to `info`, unless it already has a `build` field
+    pub fn insert_into(&self, info: &mut ServerInfo) {
+        if !info.metadata.contains_key(BUILD_INFO_KEY) {
+            info.metadata.insert(
+                BUILD_INFO_KEY.to_string(),
+                serde_json::to_value(self).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+/// Enabled Cargo features of this crate
+pub fn enabled_features() -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Git hash from [`GIT_HASH_ENV`] at run time, or when the crate was compiled
+pub fn git_hash() -> Option<String> {
+    std::env::var(GIT_HASH_ENV)
+        .ok()
+        .or_else(|| option_env!("MCP_GIT_HASH").map(str::to_string))
+        .filter(|hash| !hash.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_build_info() {
+        let started_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_800_000_000);
+        let config = BuildInfoConfig {
+            git_hash: Some("3f9c2ab".to_string()),
+            ..Default::default()
+        };
+        let build = BuildInfo::collect(&config, started_at, started_at + Duration::from_secs(90));
+        assert_eq!(build.git_hash.as_deref(), Some("3f9c2ab"));
+        assert_eq!(build.uptime_seconds, 90);
+        assert!(build.features.iter().any(|f| f == "server"));
+        assert_eq!(
+            build.protocol_versions.len(),
+            SUPPORTED_PROTOCOL_VERSIONS.len()
+        );
+
+        let mut info = ServerInfo {
+            name: "notes".to_string(),
+            version: "1.4.0".to_string(),
+            metadata: Default::default(),
+        };
+        build.insert_into(&mut info);
+        let value = serde_json::to_value(&info).unwrap();
+        assert_eq!(value["build"]["gitHash"], "3f9c2ab");
+        assert_eq!(value["build"]["crateVersion"], env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,354 @@
+//! Fault injection for resilience testing
+//!
+//! [`inject_faults`] wraps a router in middleware that makes the server
+//! misbehave the way real deployments eventually do, according to a
+//! [`ChaosProfile`]:
+//!
+//! - added latency before each request is handled
+//! - requests failing with a server error, an overload or a timeout
+//! - notifications on SSE streams silently dropped
+//! - SSE streams read slowly, as by a client on a poor connection, so the
+//!   server sees backpressure
+//!
+//! Use it in test suites to check that clients retry, time out, resync and
+//! tolerate missing notifications. It is only built with the `chaos` feature,
+//! which should never be enabled in production builds.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::debug;
+
+use crate::error::McpError;
+
+/// Kind of failure injected in place of a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChaosFault {
+    /// 500 Internal Server Error
+    Internal,
+    /// 503 Service Unavailable, asking the client to retry
+    Overloaded,
+    /// 408 Request Timeout
+    Timeout,
+}
+
+impl ChaosFault {
+    fn into_error(self) -> McpError {
+        match self {
+            ChaosFault::Internal => McpError::Internal {
+                message: "Injected fault".to_string(),
+                source: None,
+            },
+            ChaosFault::Overloaded => McpError::Overloaded {
+                reason: "injected fault".to_string(),
+                retry_after: 1,
+            },
+            ChaosFault::Timeout => McpError::ServerTimeout {
+                timeout: Duration::ZERO,
+            },
+        }
+    }
+}
+
+/// Which failures to inject, and how often
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChaosProfile {
+    /// Least latency added before a request is handled
+    #[serde(with = "humantime_serde")]
+    pub min_latency: Duration,
+
+    /// Most latency added before a request is handled
+    #[serde(with = "humantime_serde")]
+    pub max_latency: Duration,
+
+    /// Share of requests, from 0 to 1, answered with a fault
+    pub error_rate: f64,
+
+    /// Faults to choose from for failed requests
+    pub faults: Vec<ChaosFault>,
+
+    /// Share of SSE notifications, from 0 to 1, that are dropped
+    pub notification_drop_rate: f64,
+
+    /// Delay before each SSE event is passed on, simulating a slow consumer
+    #[serde(with = "humantime_serde")]
+    pub sse_event_delay: Option<Duration>,
+
+    /// Seed for reproducible runs; random if unset
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosProfile {
+    fn default() -> Self {
+        Self {
+            min_latency: Duration::ZERO,
+            max_latency: Duration::ZERO,
+            error_rate: 0.0,
+            faults: vec![
+                ChaosFault::Internal,
+                ChaosFault::Overloaded,
+                ChaosFault::Timeout,
+            ],
+            notification_drop_rate: 0.0,
+            sse_event_delay: None,
+            seed: None,
+        }
+    }
+}
+
+impl ChaosProfile {
+    /// Add between `min` and `max` latency to each request
+    pub fn with_latency(mut self, min: Duration, max: Duration) -> Self {
+        self.min_latency = min;
+        self.max_latency = max.max(min);
+        self
+    }
+
+    /// Fail a share `rate` of requests
+    pub fn with_error_rate(mut self, rate: f64) -> Self {
+        self.error_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Fail requests only with `faults`
+    pub fn with_faults(mut self, faults: impl IntoIterator<Item = ChaosFault>) -> Self {
+        self.faults = faults.into_iter().collect();
+        self
+    }
+
+    /// Drop a share `rate` of SSE notifications
+    pub fn with_notification_drop_rate(mut self, rate: f64) -> Self {
+        self.notification_drop_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Read SSE streams one event per `delay`
+    pub fn with_slow_consumer(mut self, delay: Duration) -> Self {
+        self.sse_event_delay = Some(delay);
+        self
+    }
+
+    /// Make the injected faults reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Profile and random state shared by the middleware
+struct Chaos {
+    profile: ChaosProfile,
+    state: AtomicU64,
+}
+
+impl Chaos {
+    fn new(profile: ChaosProfile) -> Self {
+        let seed = profile.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Self {
+            profile,
+            state: AtomicU64::new(seed),
+        }
+    }
+
+    /// Uniform random number in `[0, 1)` (splitmix64)
+    fn next(&self) -> f64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&self, rate: f64) -> bool {
+        rate > 0.0 && self.next() < rate
+    }
+
+    fn latency(&self) -> Duration {
+        let ChaosProfile {
+            min_latency,
+            max_latency,
+            ..
+        } = self.profile;
+        min_latency + (max_latency.saturating_sub(min_latency)).mul_f64(self.next())
+    }
+
+    fn fault(&self) -> Option<ChaosFault> {
+        let faults = &self.profile.faults;
+        if faults.is_empty() || !self.chance(self.profile.error_rate) {
+            return None;
+        }
+        let index = (self.next() * faults.len() as f64) as usize;
+        Some(faults[index.min(faults.len() - 1)])
+    }
+}
+
+/// Inject the failures described by `profile` into every request to `router`
+pub fn inject_faults(router: Router, profile: ChaosProfile) -> Router {
+    router.layer(axum::middleware::from_fn_with_state(
+        Arc::new(Chaos::new(profile)),
+        chaos_middleware,
+    ))
+}
+
+async fn chaos_middleware(
+    State(chaos): State<Arc<Chaos>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let latency = chaos.latency();
+    if !latency.is_zero() {
+        tokio::time::sleep(latency).await;
+    }
+    if let Some(fault) = chaos.fault() {
+        debug!(
+            "Injecting {:?} fault into {} {}",
+            fault,
+            request.method(),
+            request.uri()
+        );
+        return fault.into_error().into_response();
+    }
+
+    let response = next.run(request).await;
+    let is_sse = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if !is_sse
+        || (chaos.profile.notification_drop_rate <= 0.0 && chaos.profile.sse_event_delay.is_none())
+    {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut chunks = body.into_data_stream();
+    let events = async_stream::stream! {
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(e);
+                    break;
+                }
+            };
+            let kept = filter_events(&chaos, &chunk);
+            if kept.is_empty() {
+                continue;
+            }
+            if let Some(delay) = chaos.profile.sse_event_delay {
+                tokio::time::sleep(delay).await;
+            }
+            yield Ok(Bytes::from(kept));
+        }
+    };
+    Response::from_parts(parts, Body::from_stream(events))
+}
+
+/// Drop notification events from a chunk of an SSE stream
+fn filter_events(chaos: &Chaos, chunk: &[u8]) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(chunk) else {
+        return chunk.to_vec();
+    };
+    let mut kept = String::with_capacity(text.len());
+    for event in text.split_inclusive("\n\n") {
+        if is_notification(event) && chaos.chance(chaos.profile.notification_drop_rate) {
+            debug!("Dropping SSE notification");
+            continue;
+        }
+        kept.push_str(event);
+    }
+    kept.into_bytes()
+}
+
+/// Whether an SSE event carries a JSON-RPC notification
+fn is_notification(event: &str) -> bool {
+    let data: String = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect::<Vec<_>>()
+        .join("\n");
+    serde_json::from_str::<serde_json::Value>(&data).is_ok_and(|message| {
+        message.get("method").is_some() && message.get("id").is_none_or(|id| id.is_null())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        response::sse::{Event, Sse},
+        routing::get,
+    };
+    use std::convert::Infallible;
+    use tower::ServiceExt;
+
+    async fn events() -> Sse<impl futures_util::Stream<Item = Result<Event, Infallible>>> {
+        Sse::new(futures_util::stream::iter([
+            Ok(Event::default().data(r#"{"jsonrpc":"2.0","method":"notifications/progress"}"#)),
+            Ok(Event::default().data(r#"{"jsonrpc":"2.0","id":1,"result":{}}"#)),
+        ]))
+    }
+
+    async fn body(router: Router) -> (u16, String) {
+        let response = router
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_injected_faults() {
+        let router = Router::new().route("/", get(events));
+
+        let (status, sse) = body(inject_faults(
+            router.clone(),
+            ChaosProfile::default()
+                .with_notification_drop_rate(1.0)
+                .with_seed(7),
+        ))
+        .await;
+        assert_eq!(status, 200);
+        assert!(!sse.contains("notifications/progress"));
+        assert!(sse.contains(r#""id":1"#));
+
+        let (status, _) = body(inject_faults(
+            router,
+            ChaosProfile::default()
+                .with_error_rate(1.0)
+                .with_faults([ChaosFault::Overloaded]),
+        ))
+        .await;
+        assert_eq!(status, 503);
+    }
+}
@@ -0,0 +1,240 @@
+//! Per-principal limits on concurrent tool executions
+//!
+//! The scheduler bounds how many heavy requests the whole server runs at once;
+//! these limits keep one principal from taking all of that. Each principal
+//! (see [`SecurityContext::principal`]) gets a semaphore with
+//! [`PrincipalConcurrencyConfig::max_concurrent`] permits, or the limit of its
+//! role (the [`ROLE_LABEL`] entry of its client metadata). A tool call waits
+//! for a permit for at most [`PrincipalConcurrencyConfig::max_wait`] and then
+//! fails with [`McpError::RateLimitExceeded`]. System contexts are not limited.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::SecurityContext,
+    server::scheduler::ROLE_LABEL,
+};
+
+/// Per-principal concurrency configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrincipalConcurrencyConfig {
+    /// Whether tool executions are limited per principal
+    pub enabled: bool,
+
+    /// Tool executions a principal may run at the same time
+    pub max_concurrent: usize,
+
+    /// Limits for principals with the given role, overriding `max_concurrent`
+    pub role_limits: HashMap<String, usize>,
+
+    /// How long a call waits for one of its principal's slots
+    #[serde(with = "humantime_serde")]
+    pub max_wait: Duration,
+
+    /// Seconds clients are told to wait before retrying a rejected call
+    pub retry_after: u64,
+}
+
+impl Default for PrincipalConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: 4,
+            role_limits: HashMap::new(),
+            max_wait: Duration::from_secs(30),
+            retry_after: 1,
+        }
+    }
+}
+
+impl PrincipalConcurrencyConfig {
+    /// Let each principal run at most `max_concurrent` tool executions
+    pub fn enabled(max_concurrent: usize) -> Self {
+        Self {
+            enabled: true,
+            max_concurrent,
+            ..Default::default()
+        }
+    }
+
+    /// Let principals with `role` run `max_concurrent` tool executions
+    pub fn with_role_limit(mut self, role: impl Into<String>, max_concurrent: usize) -> Self {
+        self.role_limits.insert(role.into(), max_concurrent);
+        self
+    }
+
+    /// Wait at most `max_wait` for a slot
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+}
+
+/// Each principal's semaphore and the limit it was created with
+type Semaphores = HashMap<String, (Arc<Semaphore>, usize)>;
+
+/// Slot held while a tool runs; released on drop
+#[derive(Debug)]
+pub struct PrincipalPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Keyed semaphores limiting concurrent tool executions per principal
+#[derive(Debug, Clone)]
+pub struct PrincipalLimits {
+    config: PrincipalConcurrencyConfig,
+    semaphores: Arc<Mutex<Semaphores>>,
+    clock: Clock,
+}
+
+impl PrincipalLimits {
+    /// Limit executions as configured
+    pub fn new(config: PrincipalConcurrencyConfig) -> Self {
+        Self {
+            config,
+            semaphores: Arc::new(Mutex::new(HashMap::new())),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Time waits on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Configured limits
+    pub fn config(&self) -> &PrincipalConcurrencyConfig {
+        &self.config
+    }
+
+    /// Concurrent executions allowed for `context`, if limited
+    pub fn limit(&self, context: &SecurityContext) -> Option<usize> {
+        if !self.config.enabled || context.is_system() {
+            return None;
+        }
+        let role_limit = context
+            .client
+            .metadata
+            .get(ROLE_LABEL)
+            .and_then(|role| self.config.role_limits.get(role));
+        Some(*role_limit.unwrap_or(&self.config.max_concurrent))
+    }
+
+    /// Executions of `principal` currently running
+    pub fn running(&self, principal: &str) -> usize {
+        let semaphores = self.semaphores.lock().unwrap();
+        semaphores.get(principal).map_or(0, |(semaphore, limit)| {
+            limit.saturating_sub(semaphore.available_permits())
+        })
+    }
+
+    /// Wait for one of the caller's slots
+    ///
+    /// Returns `None` when the caller is not limited.
+    pub async fn acquire(&self, context: &SecurityContext) -> McpResult<Option<PrincipalPermit>> {
+        let Some(limit) = self.limit(context) else {
+            return Ok(None);
+        };
+        let principal = context.principal();
+        let semaphore = {
+            let mut semaphores = self.semaphores.lock().unwrap();
+            // Only the map holds semaphores nobody runs on or waits for
+            semaphores.retain(|_, (semaphore, _)| Arc::strong_count(semaphore) > 1);
+            let (semaphore, _) = semaphores
+                .entry(principal.clone())
+                .or_insert_with(|| (Arc::new(Semaphore::new(limit)), limit));
+            Arc::clone(semaphore)
+        };
+
+        let permit = match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                debug!("{} is at its limit of {} executions", principal, limit);
+                self.clock
+                    .timeout(self.config.max_wait, semaphore.acquire_owned())
+                    .await
+                    .and_then(Result::ok)
+                    .ok_or_else(|| McpError::RateLimitExceeded {
+                        message: format!("{} already runs {} tool executions", principal, limit),
+                        retry_after: Some(self.config.retry_after),
+                    })?
+            }
+        };
+        Ok(Some(PrincipalPermit { _permit: permit }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::ClientContext;
+
+    fn client(id: &str, role: Option<&str>) -> SecurityContext {
+        let mut client = ClientContext {
+            client_id: Some(id.to_string()),
+            ..Default::default()
+        };
+        if let Some(role) = role {
+            client
+                .metadata
+                .insert(ROLE_LABEL.to_string(), role.to_string());
+        }
+        SecurityContext::authenticated(client, Vec::new())
+    }
+
+    #[tokio::test]
+    async fn test_principal_limits() {
+        let clock = Clock::manual();
+        let limits = PrincipalLimits::new(
+            PrincipalConcurrencyConfig::enabled(1)
+                .with_role_limit("indexer", 2)
+                .with_max_wait(Duration::from_secs(5)),
+        )
+        .with_clock(clock.clone());
+        let alice = client("alice", None);
+
+        let held = limits.acquire(&alice).await.unwrap().unwrap();
+        assert_eq!(limits.running("alice"), 1);
+
+        // Other principals keep their own slots
+        assert!(limits
+            .acquire(&client("bob", None))
+            .await
+            .unwrap()
+            .is_some());
+        let indexer = client("crawler", Some("indexer"));
+        let _first = limits.acquire(&indexer).await.unwrap();
+        let _second = limits.acquire(&indexer).await.unwrap();
+        assert!(limits
+            .acquire(&SecurityContext::system())
+            .await
+            .unwrap()
+            .is_none());
+
+        let waiting = tokio::spawn({
+            let limits = limits.clone();
+            let alice = alice.clone();
+            async move { limits.acquire(&alice).await.map(|permit| permit.is_some()) }
+        });
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(5));
+        assert!(matches!(
+            waiting.await.unwrap(),
+            Err(McpError::RateLimitExceeded { .. })
+        ));
+
+        drop(held);
+        assert!(limits.acquire(&alice).await.unwrap().is_some());
+    }
+}
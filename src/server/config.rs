@@ -1,7 +1,50 @@
 //! MCP server configuration
 
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use crate::{
+    error::{McpError, McpResult},
+    ids::IdStrategy,
+    security::{ClientPolicy, MethodAuthorization},
+    server::{
+        admission::AdmissionConfig,
+        affinity::SessionAffinityConfig,
+        aliases::MethodAliasConfig,
+        approval::ApprovalConfig,
+        audit::AuditRetention,
+        batch_resume::BatchResumeConfig,
+        build_info::BuildInfoConfig,
+        concurrency::PrincipalConcurrencyConfig,
+        duplicates::DuplicateRequestConfig,
+        exposure::ExposureConfig,
+        formatters::FormatterConfig,
+        http_status::HttpStatusConfig,
+        inspector::InspectorConfig,
+        maintenance::MaintenanceConfig,
+        memory::MemoryConfig,
+        pagination::PaginationConfig,
+        prompt_stream::PromptStreamConfig,
+        quota::QuotaConfig,
+        read_many::ReadManyConfig,
+        scheduler::SchedulerConfig,
+        scrub::ScrubConfig,
+        secrets::SecretsConfig,
+        serialization::SerializationConfig,
+        smoke::SmokeToolsConfig,
+        tasks::TaskConfig,
+        tokens::TokenConfig,
+        toolsets::ToolsetConfig,
+        webhooks::{WebhookConfig, WebhooksConfig},
+        wire_tap::WireTapConfig,
+    },
+};
+
+/// Prefix shared by all configuration environment variables
+pub const ENV_PREFIX: &str = "MCP_";
+
+/// Environment variable naming a JSON configuration file to load
+pub const ENV_CONFIG_FILE: &str = "MCP_CONFIG_FILE";
 
 /// MCP server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +61,10 @@ pub struct McpServerConfig {
     /// Port to bind to
     pub port: u16,
 
+    /// Base path the MCP endpoints are mounted under
+    #[serde(default = "default_base_path")]
+    pub base_path: String,
+
     /// Maximum number of concurrent connections
     pub max_connections: usize,
 
@@ -27,6 +74,11 @@ pub struct McpServerConfig {
     /// Request timeout
     pub request_timeout: Duration,
 
+    /// How long a `tools/call` may run before a client accepting
+    /// `text/event-stream` is switched to a streamed SSE response
+    #[serde(default = "default_sse_response_threshold")]
+    pub sse_response_threshold: Option<Duration>,
+
     /// Whether to enable batch operations
     pub enable_batch: bool,
 
@@ -39,6 +91,139 @@ pub struct McpServerConfig {
     /// Security configuration
     pub security: SecurityConfig,
 
+    /// TLS configuration (requires the `tls` feature to serve)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+
+    /// Unix domain socket path to listen on instead of TCP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<PathBuf>,
+
+    /// Approval workflow for tools that require approval
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+
+    /// Outbound webhooks for server events
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+
+    /// Capture of recent JSON-RPC exchanges for debugging
+    #[serde(default)]
+    pub inspector: InspectorConfig,
+
+    /// Wire-level tap of the frames of selected sessions
+    #[serde(default)]
+    pub wire_tap: WireTapConfig,
+
+    /// Built-in tools for smoke testing a new server
+    #[serde(default)]
+    pub smoke_tools: SmokeToolsConfig,
+
+    /// Chunking of streamed `prompts/get` responses
+    #[serde(default)]
+    pub prompt_stream: PromptStreamConfig,
+
+    /// Generator of session, subscription, progress and request IDs
+    ///
+    /// Time-ordered strategies append a random UUIDv4 to session IDs, which
+    /// are bearer credentials and must not be guessable.
+    #[serde(default)]
+    pub ids: IdStrategy,
+
+    /// Session affinity for load-balanced deployments
+    #[serde(default)]
+    pub affinity: SessionAffinityConfig,
+
+    /// HTTP statuses of failed JSON-RPC requests
+    #[serde(default)]
+    pub http_status: HttpStatusConfig,
+
+    /// Token estimates and budgets for prompt and tool results
+    #[serde(default)]
+    pub tokens: TokenConfig,
+
+    /// Handling of requests that reuse a recent request ID
+    #[serde(default)]
+    pub duplicates: DuplicateRequestConfig,
+
+    /// Priority scheduling of heavy requests
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+
+    /// Rejection of expensive requests while the server is overloaded
+    #[serde(default)]
+    pub admission: AdmissionConfig,
+
+    /// Reject methods that are neither standard, built in, nor listed by
+    /// [`McpServerState::custom_methods`](crate::server::McpServerState::custom_methods)
+    #[serde(default)]
+    pub strict_methods: bool,
+
+    /// Extra method names and the methods they are handled as
+    #[serde(default)]
+    pub aliases: MethodAliasConfig,
+
+    /// Exposure profiles served by each listener
+    #[serde(default)]
+    pub exposure: ExposureConfig,
+
+    /// Experimental `tasks/*` methods for running tool calls in the background
+    #[serde(default)]
+    pub tasks: TaskConfig,
+
+    /// Named groups of tools that sessions enable and disable
+    #[serde(default)]
+    pub toolsets: ToolsetConfig,
+
+    /// Resumption of interrupted batches sent with a correlation token
+    #[serde(default)]
+    pub batch_resume: BatchResumeConfig,
+
+    /// Limits on concurrent tool executions per principal
+    #[serde(default)]
+    pub principal_concurrency: PrincipalConcurrencyConfig,
+
+    /// Memory accounting and its soft limit
+    #[serde(default)]
+    pub memory: MemoryConfig,
+
+    /// Encodings of large integers, non-finite floats and binary data in tool results
+    #[serde(default)]
+    pub serialization: SerializationConfig,
+
+    /// Caching of secrets looked up by tools
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    /// Scrubbing of personal data from audit records, captures and tool results
+    #[serde(default)]
+    pub scrub: ScrubConfig,
+
+    /// Scheduled windows in which the server is read-only
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+
+    /// List only the tools [`McpAuth::authorize_many`](crate::security::McpAuth::authorize_many)
+    /// allows with the `list` action on `tool:{name}`
+    #[serde(default)]
+    pub authorize_tool_listing: bool,
+
+    /// Build and runtime metadata in the server info
+    #[serde(default)]
+    pub build_info: BuildInfoConfig,
+
+    /// Page sizes of `tools/list`, `prompts/list` and `resources/list`
+    #[serde(default)]
+    pub pagination: PaginationConfig,
+
+    /// Limits of `resources/read_many`
+    #[serde(default)]
+    pub read_many: ReadManyConfig,
+
+    /// Formatters run on tool results, globally and per tool
+    #[serde(default)]
+    pub formatters: FormatterConfig,
+
     /// Additional server metadata
     pub metadata: HashMap<String, serde_json::Value>,
 }
@@ -50,13 +235,47 @@ impl Default for McpServerConfig {
             version: "0.1.0".to_string(),
             host: "127.0.0.1".to_string(),
             port: 8080,
+            base_path: default_base_path(),
             max_connections: 1000,
             connection_timeout: Duration::from_secs(30),
             request_timeout: Duration::from_secs(60),
+            sse_response_threshold: default_sse_response_threshold(),
             enable_batch: true,
             max_batch_size: 100,
             session: SessionConfig::default(),
             security: SecurityConfig::default(),
+            tls: None,
+            unix_socket: None,
+            approval: ApprovalConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            inspector: InspectorConfig::default(),
+            wire_tap: WireTapConfig::default(),
+            smoke_tools: SmokeToolsConfig::default(),
+            prompt_stream: PromptStreamConfig::default(),
+            ids: IdStrategy::default(),
+            affinity: SessionAffinityConfig::default(),
+            http_status: HttpStatusConfig::default(),
+            tokens: TokenConfig::default(),
+            duplicates: DuplicateRequestConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            admission: AdmissionConfig::default(),
+            strict_methods: false,
+            aliases: MethodAliasConfig::default(),
+            exposure: ExposureConfig::default(),
+            tasks: TaskConfig::default(),
+            toolsets: ToolsetConfig::default(),
+            batch_resume: BatchResumeConfig::default(),
+            principal_concurrency: PrincipalConcurrencyConfig::default(),
+            memory: MemoryConfig::default(),
+            serialization: SerializationConfig::default(),
+            secrets: SecretsConfig::default(),
+            scrub: ScrubConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            authorize_tool_listing: false,
+            build_info: BuildInfoConfig::default(),
+            pagination: PaginationConfig::default(),
+            read_many: ReadManyConfig::default(),
+            formatters: FormatterConfig::default(),
             metadata: HashMap::new(),
         }
     }
@@ -95,6 +314,236 @@ impl McpServerConfig {
         self
     }
 
+    /// Set when long tool calls switch to streamed SSE responses (`None` disables)
+    pub fn with_sse_response_threshold(mut self, threshold: Option<Duration>) -> Self {
+        self.sse_response_threshold = threshold;
+        self
+    }
+
+    /// Mount the MCP endpoints under a custom base path
+    pub fn with_base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Enable TLS using PEM certificate and key files
+    pub fn with_tls(mut self, cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        self.tls = Some(TlsConfig::new(cert_path, key_path));
+        self
+    }
+
+    /// Listen on a Unix domain socket instead of TCP
+    pub fn with_unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Apply a client fingerprint policy to `initialize` requests
+    pub fn with_client_policy(mut self, policy: ClientPolicy) -> Self {
+        self.security.client_policy = policy;
+        self
+    }
+
+    /// Require capabilities per JSON-RPC method before handlers run
+    pub fn with_method_authorization(mut self, matrix: MethodAuthorization) -> Self {
+        self.security.method_authorization = matrix;
+        self
+    }
+
+    /// Record tool executions and expose them under `mcp-audit://executions`
+    pub fn with_audit_trail(mut self, retention: AuditRetention) -> Self {
+        self.security.enable_audit = true;
+        self.security.audit_retention = retention;
+        self
+    }
+
+    /// Meter usage per principal and enforce quota limits
+    pub fn with_quota(mut self, quota: QuotaConfig) -> Self {
+        self.security.quota = quota;
+        self
+    }
+
+    /// Configure how calls to tools that require approval are held
+    pub fn with_approval(mut self, approval: ApprovalConfig) -> Self {
+        self.approval = approval;
+        self
+    }
+
+    /// Deliver server events to a webhook endpoint
+    pub fn with_webhook(mut self, webhook: WebhookConfig) -> Self {
+        self.webhooks.endpoints.push(webhook);
+        self
+    }
+
+    /// Capture recent JSON-RPC exchanges for `admin/inspector/list`
+    pub fn with_inspector(mut self, inspector: InspectorConfig) -> Self {
+        self.inspector = inspector;
+        self
+    }
+
+    /// Mirror the frames of tapped sessions as configured by `wire_tap`
+    pub fn with_wire_tap(mut self, wire_tap: WireTapConfig) -> Self {
+        self.wire_tap = wire_tap;
+        self
+    }
+
+    /// List and answer the built-in `mcp.*` smoke-test tools
+    pub fn with_smoke_tools(mut self, smoke_tools: SmokeToolsConfig) -> Self {
+        self.smoke_tools = smoke_tools;
+        self
+    }
+
+    /// Chunk streamed `prompts/get` responses as configured
+    pub fn with_prompt_stream(mut self, prompt_stream: PromptStreamConfig) -> Self {
+        self.prompt_stream = prompt_stream;
+        self
+    }
+
+    /// Generate session, subscription, progress and request IDs following `ids`
+    pub fn with_ids(mut self, ids: IdStrategy) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Expose and enforce session affinity for load balancers
+    pub fn with_session_affinity(mut self, affinity: SessionAffinityConfig) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
+    /// Answer failed JSON-RPC requests with HTTP statuses as configured
+    pub fn with_http_status(mut self, http_status: HttpStatusConfig) -> Self {
+        self.http_status = http_status;
+        self
+    }
+
+    /// Annotate results with token estimates and apply token budgets
+    pub fn with_tokens(mut self, tokens: TokenConfig) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    /// Detect retried requests that reuse a recent request ID
+    pub fn with_duplicate_requests(mut self, duplicates: DuplicateRequestConfig) -> Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Queue heavy requests by priority and shed load when the queue is full
+    pub fn with_scheduler(mut self, scheduler: SchedulerConfig) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Reject expensive requests while the server is overloaded
+    pub fn with_admission(mut self, admission: AdmissionConfig) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Answer unknown methods with "method not found" instead of passing them
+    /// to the custom method handler
+    pub fn with_strict_methods(mut self) -> Self {
+        self.strict_methods = true;
+        self
+    }
+
+    /// Accept the method names in `aliases` in place of their targets
+    pub fn with_method_aliases(mut self, aliases: MethodAliasConfig) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Only show each listener the tools, prompts and resources of its profiles
+    pub fn with_exposure(mut self, exposure: ExposureConfig) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Serve the experimental `tasks/*` methods
+    pub fn with_tasks(mut self, tasks: TaskConfig) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Group tools into toolsets that sessions enable and disable
+    pub fn with_toolsets(mut self, toolsets: ToolsetConfig) -> Self {
+        self.toolsets = toolsets;
+        self
+    }
+
+    /// Record batches sent with a correlation token so they can be resumed
+    pub fn with_batch_resume(mut self, batch_resume: BatchResumeConfig) -> Self {
+        self.batch_resume = batch_resume;
+        self
+    }
+
+    /// Limit how many tool executions each principal runs at once
+    pub fn with_principal_concurrency(mut self, concurrency: PrincipalConcurrencyConfig) -> Self {
+        self.principal_concurrency = concurrency;
+        self
+    }
+
+    /// Account memory and shed load over a soft limit
+    pub fn with_memory(mut self, memory: MemoryConfig) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Encode large integers, non-finite floats and binary data in tool results
+    pub fn with_serialization(mut self, serialization: SerializationConfig) -> Self {
+        self.serialization = serialization;
+        self
+    }
+
+    /// Cache secrets looked up by tools as configured
+    pub fn with_secrets(mut self, secrets: SecretsConfig) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Scrub personal data from audit records, captures and tool results
+    pub fn with_scrubbing(mut self, scrub: ScrubConfig) -> Self {
+        self.scrub = scrub;
+        self
+    }
+
+    /// Make the server read-only during scheduled maintenance windows
+    pub fn with_maintenance(mut self, maintenance: MaintenanceConfig) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Report `git_hash` as the deployment's git hash in the server info
+    pub fn with_git_hash(mut self, git_hash: impl Into<String>) -> Self {
+        self.build_info.git_hash = Some(git_hash.into());
+        self
+    }
+
+    /// Return listings in pages of at most `page_size` entries
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.pagination.page_size = Some(page_size);
+        self
+    }
+
+    /// Limit `resources/read_many` requests
+    pub fn with_read_many(mut self, read_many: ReadManyConfig) -> Self {
+        self.read_many = read_many;
+        self
+    }
+
+    /// Run the result formatters `formatters` names
+    pub fn with_formatters(mut self, formatters: FormatterConfig) -> Self {
+        self.formatters = formatters;
+        self
+    }
+
+    /// List only the tools clients are authorized to list
+    pub fn with_tool_listing_authorization(mut self) -> Self {
+        self.authorize_tool_listing = true;
+        self
+    }
+
     /// Add custom metadata
     pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
         self.metadata.insert(key.into(), value);
@@ -102,6 +551,245 @@ impl McpServerConfig {
     }
 }
 
+/// Loading configuration from files and the environment
+///
+/// Sources are layered as defaults < file < environment < code: a file only
+/// needs to contain the fields it changes, environment variables override the
+/// file, and builder methods called on the result override both.
+///
+/// | Variable | Field |
+/// |----------|-------|
+/// | `MCP_CONFIG_FILE` | JSON file loaded before the other variables |
+/// | `MCP_NAME`, `MCP_VERSION` | `name`, `version` |
+/// | `MCP_HOST`, `MCP_PORT`, `MCP_BASE_PATH` | `host`, `port`, `base_path` |
+/// | `MCP_MAX_CONNECTIONS` | `max_connections` |
+/// | `MCP_CONNECTION_TIMEOUT`, `MCP_REQUEST_TIMEOUT` | timeouts, e.g. `30s`, `2m` |
+/// | `MCP_ENABLE_BATCH`, `MCP_MAX_BATCH_SIZE` | batch settings |
+/// | `MCP_STRICT_METHODS` | `strict_methods` |
+/// | `MCP_SESSION_TIMEOUT` | `session.timeout` |
+/// | `MCP_AUTH_MODE` | `none` or `required` (`security.require_auth`) |
+/// | `MCP_CORS_ORIGINS` | comma separated origins, `*` for any |
+/// | `MCP_RATE_LIMIT` | requests per `security.rate_limit.window`, `0` disables |
+/// | `MCP_UNIX_SOCKET` | `unix_socket` |
+/// | `MCP_TLS_CERT`, `MCP_TLS_KEY` | `tls` (both required) |
+impl McpServerConfig {
+    /// Load configuration from defaults, `MCP_CONFIG_FILE` and `MCP_*` variables
+    pub fn from_env() -> McpResult<Self> {
+        let config = match std::env::var_os(ENV_CONFIG_FILE) {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.merge_env(std::env::vars())
+    }
+
+    /// Load configuration from a JSON file layered over the defaults
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> McpResult<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|e| McpError::Configuration {
+            message: format!("Cannot read config file {}: {}", path.display(), e),
+        })?;
+        let overrides: serde_json::Value =
+            serde_json::from_str(&contents).map_err(|e| McpError::Configuration {
+                message: format!("Invalid config file {}: {}", path.display(), e),
+            })?;
+        Self::default().merge_json(overrides)
+    }
+
+    /// Overlay a partial JSON document on this configuration
+    pub fn merge_json(self, overrides: serde_json::Value) -> McpResult<Self> {
+        let mut merged = serde_json::to_value(&self)?;
+        merge_values(&mut merged, overrides);
+        serde_json::from_value(merged).map_err(|e| McpError::Configuration {
+            message: format!("Invalid configuration: {}", e),
+        })
+    }
+
+    /// Overlay `MCP_*` variables from the given key/value pairs
+    ///
+    /// Variables without the `MCP_` prefix are ignored.
+    pub fn merge_env<I, K, V>(mut self, vars: I) -> McpResult<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let vars: HashMap<String, String> = vars
+            .into_iter()
+            .filter(|(key, _)| key.as_ref().starts_with(ENV_PREFIX))
+            .map(|(key, value)| (key.as_ref().to_string(), value.as_ref().trim().to_string()))
+            .collect();
+        let get = |name: &str| vars.get(name).filter(|v| !v.is_empty());
+
+        if let Some(v) = get("MCP_NAME") {
+            self.name = v.clone();
+        }
+        if let Some(v) = get("MCP_VERSION") {
+            self.version = v.clone();
+        }
+        if let Some(v) = get("MCP_HOST") {
+            self.host = v.clone();
+        }
+        if let Some(v) = get("MCP_PORT") {
+            self.port = parse_env("MCP_PORT", v)?;
+        }
+        if let Some(v) = get("MCP_BASE_PATH") {
+            self.base_path = v.clone();
+        }
+        if let Some(v) = get("MCP_MAX_CONNECTIONS") {
+            self.max_connections = parse_env("MCP_MAX_CONNECTIONS", v)?;
+        }
+        if let Some(v) = get("MCP_CONNECTION_TIMEOUT") {
+            self.connection_timeout = parse_env_duration("MCP_CONNECTION_TIMEOUT", v)?;
+        }
+        if let Some(v) = get("MCP_REQUEST_TIMEOUT") {
+            self.request_timeout = parse_env_duration("MCP_REQUEST_TIMEOUT", v)?;
+        }
+        if let Some(v) = get("MCP_ENABLE_BATCH") {
+            self.enable_batch = parse_env_bool("MCP_ENABLE_BATCH", v)?;
+        }
+        if let Some(v) = get("MCP_MAX_BATCH_SIZE") {
+            self.max_batch_size = parse_env("MCP_MAX_BATCH_SIZE", v)?;
+        }
+        if let Some(v) = get("MCP_STRICT_METHODS") {
+            self.strict_methods = parse_env_bool("MCP_STRICT_METHODS", v)?;
+        }
+        if let Some(v) = get("MCP_SESSION_TIMEOUT") {
+            self.session.timeout = parse_env_duration("MCP_SESSION_TIMEOUT", v)?;
+        }
+        if let Some(v) = get("MCP_AUTH_MODE") {
+            self.security.require_auth = match v.to_lowercase().as_str() {
+                "none" | "disabled" => false,
+                "required" | "enabled" => true,
+                _ => return Err(invalid_env("MCP_AUTH_MODE", v, "expected none or required")),
+            };
+        }
+        if let Some(v) = get("MCP_CORS_ORIGINS") {
+            self.security.cors.enabled = true;
+            self.security.cors.allowed_origins = v
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(v) = get("MCP_RATE_LIMIT") {
+            let max_requests: u32 = parse_env("MCP_RATE_LIMIT", v)?;
+            self.security.rate_limit.enabled = max_requests > 0;
+            self.security.rate_limit.max_requests = max_requests;
+        }
+        if let Some(v) = get("MCP_UNIX_SOCKET") {
+            self.unix_socket = Some(PathBuf::from(v));
+        }
+        match (get("MCP_TLS_CERT"), get("MCP_TLS_KEY")) {
+            (Some(cert), Some(key)) => self.tls = Some(TlsConfig::new(cert, key)),
+            (None, None) => {}
+            _ => {
+                return Err(McpError::Configuration {
+                    message: "MCP_TLS_CERT and MCP_TLS_KEY must be set together".to_string(),
+                })
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Recursively overlay `overrides` onto `base`, replacing non-object values
+fn merge_values(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn invalid_env(name: &str, value: &str, reason: impl std::fmt::Display) -> McpError {
+    McpError::Configuration {
+        message: format!("Invalid value '{}' for {}: {}", value, name, reason),
+    }
+}
+
+fn parse_env<T>(name: &str, value: &str) -> McpResult<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| invalid_env(name, value, e))
+}
+
+fn parse_env_bool(name: &str, value: &str) -> McpResult<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(invalid_env(name, value, "expected a boolean")),
+    }
+}
+
+fn parse_env_duration(name: &str, value: &str) -> McpResult<Duration> {
+    // Bare numbers are seconds; otherwise accept humantime strings like "30s"
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    humantime_serde::re::humantime::parse_duration(value).map_err(|e| invalid_env(name, value, e))
+}
+
+/// Default base path for MCP endpoints
+pub const DEFAULT_BASE_PATH: &str = "/mcp";
+
+fn default_sse_response_threshold() -> Option<Duration> {
+    Some(Duration::from_secs(5))
+}
+
+fn default_base_path() -> String {
+    DEFAULT_BASE_PATH.to_string()
+}
+
+/// TLS termination configuration
+///
+/// Certificates are read from PEM files on startup. Certificates issued by an
+/// ACME client (e.g. certbot) can be used by pointing at the renewed files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Path to the PEM encoded certificate chain
+    pub cert_path: PathBuf,
+
+    /// Path to the PEM encoded private key
+    pub key_path: PathBuf,
+
+    /// Whether to advertise HTTP/2 via ALPN
+    #[serde(default = "default_true")]
+    pub enable_http2: bool,
+}
+
+impl TlsConfig {
+    /// Create a TLS configuration from certificate and key paths
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            enable_http2: true,
+        }
+    }
+
+    /// Disable HTTP/2 negotiation (HTTP/1.1 only)
+    pub fn http1_only(mut self) -> Self {
+        self.enable_http2 = false;
+        self
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Session configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionConfig {
@@ -141,8 +829,24 @@ pub struct SecurityConfig {
     /// CORS configuration
     pub cors: CorsConfig,
 
-    /// Whether to enable audit logging
+    /// Whether to enable audit logging and the `mcp-audit://` execution trail
     pub enable_audit: bool,
+
+    /// Retention of the tool execution audit trail
+    #[serde(default)]
+    pub audit_retention: AuditRetention,
+
+    /// Per-principal quotas
+    #[serde(default)]
+    pub quota: QuotaConfig,
+
+    /// Client fingerprint policy applied on `initialize`
+    #[serde(default)]
+    pub client_policy: ClientPolicy,
+
+    /// Capabilities required per JSON-RPC method
+    #[serde(default)]
+    pub method_authorization: MethodAuthorization,
 }
 
 /// Rate limiting configuration
@@ -213,6 +917,7 @@ mod tests {
         assert_eq!(config.name, "MCP Server");
         assert_eq!(config.host, "127.0.0.1");
         assert_eq!(config.port, 8080);
+        assert_eq!(config.base_path, "/mcp");
         assert!(config.enable_batch);
         assert_eq!(config.max_batch_size, 100);
     }
@@ -236,4 +941,62 @@ mod tests {
         assert_eq!(config.connection_timeout, Duration::from_secs(15));
         assert!(config.metadata.contains_key("custom"));
     }
+
+    #[test]
+    fn test_merge_env() {
+        let config = McpServerConfig::default()
+            .merge_env([
+                ("MCP_PORT", "9000"),
+                ("MCP_REQUEST_TIMEOUT", "2m"),
+                ("MCP_CONNECTION_TIMEOUT", "5"),
+                ("MCP_ENABLE_BATCH", "false"),
+                ("MCP_AUTH_MODE", "required"),
+                ("MCP_CORS_ORIGINS", "https://a.example, https://b.example"),
+                ("HOME", "/ignored"),
+            ])
+            .unwrap();
+
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.request_timeout, Duration::from_secs(120));
+        assert_eq!(config.connection_timeout, Duration::from_secs(5));
+        assert!(!config.enable_batch);
+        assert!(config.security.require_auth);
+        assert_eq!(
+            config.security.cors.allowed_origins,
+            vec!["https://a.example", "https://b.example"]
+        );
+
+        assert!(McpServerConfig::default()
+            .merge_env([("MCP_PORT", "not-a-port")])
+            .is_err());
+        assert!(McpServerConfig::default()
+            .merge_env([("MCP_TLS_CERT", "cert.pem")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let path =
+            std::env::temp_dir().join(format!("axum-mcp-config-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"name": "From File", "port": 7000, "security": {"enable_audit": true}}"#,
+        )
+        .unwrap();
+
+        // defaults < file < env < code
+        let config = McpServerConfig::from_file(&path)
+            .unwrap()
+            .merge_env([("MCP_PORT", "7100")])
+            .unwrap()
+            .with_batch(10);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.name, "From File");
+        assert_eq!(config.port, 7100);
+        assert!(config.security.enable_audit);
+        assert!(!config.security.require_auth);
+        assert_eq!(config.max_batch_size, 10);
+        assert_eq!(config.host, "127.0.0.1");
+    }
 }
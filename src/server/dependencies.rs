@@ -0,0 +1,226 @@
+//! Resources tools read and write
+//!
+//! Tools declare the resource URI patterns they read and write with
+//! [`McpTool::reads_resource`](crate::server::McpTool::reads_resource) and
+//! [`McpTool::writes_resource`](crate::server::McpTool::writes_resource),
+//! which clients see under `resources` in the tool's metadata:
+//!
+//! ```json
+//! {"name": "save_note", "resources": {"reads": ["notes://index"], "writes": ["notes://{id}"]}}
+//! ```
+//!
+//! Patterns may use `{argument}` placeholders, filled from the call's
+//! arguments, and `*` wildcards. After a successful call the server
+//! invalidates the cached copies of every resource the tool writes and
+//! announces them with `notifications/resources/updated`; wildcards are
+//! matched against the listed resources. `admin/dependencies` returns the
+//! graph of tools and the resources they touch.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::protocol::Tool;
+
+/// Tool metadata key of the declared resource dependencies
+pub const TOOL_RESOURCES_META: &str = "resources";
+
+/// Returns the graph of tools and the resources they read and write
+pub const ADMIN_DEPENDENCIES: &str = "admin/dependencies";
+
+/// Resource URI patterns a tool reads and writes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResourceDependencies {
+    /// Patterns of the resources the tool reads
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reads: Vec<String>,
+
+    /// Patterns of the resources the tool writes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writes: Vec<String>,
+}
+
+impl ResourceDependencies {
+    /// Dependencies declared in the metadata of `tool`
+    pub fn of(tool: &Tool) -> Self {
+        tool.metadata
+            .get(TOOL_RESOURCES_META)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether no dependencies are declared
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty() && self.writes.is_empty()
+    }
+}
+
+/// How a tool touches a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    /// The tool reads the resource
+    Read,
+    /// The tool writes the resource
+    Write,
+}
+
+/// A tool reading or writing the resources matching a pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// Tool name
+    pub tool: String,
+
+    /// URI pattern of the resources
+    pub resource: String,
+
+    /// Whether the tool reads or writes them
+    pub access: Access,
+}
+
+/// Tools and the resources they touch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    /// One edge per declared pattern, by tool name
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    /// Graph of the dependencies declared by `tools`
+    pub fn of<'a>(tools: impl IntoIterator<Item = &'a Tool>) -> Self {
+        let mut edges = Vec::new();
+        for tool in tools {
+            let dependencies = ResourceDependencies::of(tool);
+            for (patterns, access) in [
+                (dependencies.reads, Access::Read),
+                (dependencies.writes, Access::Write),
+            ] {
+                edges.extend(patterns.into_iter().map(|resource| DependencyEdge {
+                    tool: tool.name.clone(),
+                    resource,
+                    access,
+                }));
+            }
+        }
+        edges.sort_by(|a, b| (&a.tool, &a.resource).cmp(&(&b.tool, &b.resource)));
+        Self { edges }
+    }
+
+    /// The graph in Graphviz DOT: tools as boxes, resources as ellipses,
+    /// edges pointing the way data flows
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for edge in &self.edges {
+            let (from, to) = match edge.access {
+                Access::Read => (&edge.resource, &edge.tool),
+                Access::Write => (&edge.tool, &edge.resource),
+            };
+            dot.push_str(&format!("  {:?} -> {:?};\n", from, to));
+        }
+        let mut tools: Vec<&str> = self.edges.iter().map(|e| e.tool.as_str()).collect();
+        tools.sort_unstable();
+        tools.dedup();
+        for tool in tools {
+            dot.push_str(&format!("  {:?} [shape=box];\n", tool));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// `pattern` with its `{argument}` placeholders filled from `arguments`
+///
+/// Placeholders without a string, number or boolean argument become `*`.
+pub fn resolve(pattern: &str, arguments: &Value) -> String {
+    let mut resolved = String::with_capacity(pattern.len());
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        resolved.push_str(&rest[..start]);
+        let name = &rest[start + 1..start + end];
+        match arguments.get(name) {
+            Some(Value::String(value)) => resolved.push_str(value),
+            Some(value @ (Value::Number(_) | Value::Bool(_))) => {
+                resolved.push_str(&value.to_string())
+            }
+            _ => resolved.push('*'),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+/// Whether `uri` matches `pattern`, in which `*` stands for any characters
+pub fn matches(pattern: &str, uri: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = uri.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_patterns() {
+        let arguments = json!({"folder": "work", "id": 42});
+        assert_eq!(
+            resolve("notes://{folder}/{id}", &arguments),
+            "notes://work/42"
+        );
+        assert_eq!(
+            resolve("notes://{missing}/index", &arguments),
+            "notes://*/index"
+        );
+
+        assert!(matches("notes://*/index", "notes://work/index"));
+        assert!(matches("notes://*", "notes://work/a/b"));
+        assert!(!matches("notes://*/index", "notes://work/42"));
+        assert!(matches("notes://a*b*c", "notes://abc"));
+        assert!(!matches("notes://ab*b", "notes://ab"));
+        assert!(matches("notes://index", "notes://index"));
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let tool = |name: &str, resources: Value| Tool {
+            name: name.to_string(),
+            description: String::new(),
+            input_schema: json!({}),
+            metadata: [(TOOL_RESOURCES_META.to_string(), resources)]
+                .into_iter()
+                .collect(),
+        };
+        let tools = [
+            tool(
+                "save",
+                json!({"reads": ["notes://index"], "writes": ["notes://{id}"]}),
+            ),
+            tool("search", json!({"reads": ["notes://*"]})),
+        ];
+        let graph = DependencyGraph::of(&tools);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.edges[1].resource, "notes://{id}");
+        assert_eq!(graph.edges[1].access, Access::Write);
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"save\" -> \"notes://{id}\";"));
+        assert!(dot.contains("\"notes://*\" -> \"search\";"));
+    }
+}
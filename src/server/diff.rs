@@ -0,0 +1,269 @@
+//! Unified diffs between resource versions
+//!
+//! The `resources/diff` method compares two versions of a text resource, or a
+//! resource and content the client provides, and returns a unified diff.
+//! Versions are looked up through
+//! [`VersionedResourceRegistry`](crate::server::resource::VersionedResourceRegistry);
+//! a side without a version is the resource's current content.
+//!
+//! ```json
+//! {"method": "resources/diff", "params": {"uri": "file:///src/main.rs", "fromVersion": 3}}
+//! {"method": "resources/diff", "params": {"uri": "file:///src/main.rs", "content": "..."}}
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Compares two versions of a resource, or a resource and provided content
+pub const RESOURCES_DIFF: &str = "resources/diff";
+
+/// Unchanged lines shown around each change by default
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Params of the `resources/diff` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesDiffParams {
+    /// Resource to compare
+    pub uri: String,
+
+    /// Version to compare from, the current content if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_version: Option<u64>,
+
+    /// Version to compare to, the current content if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_version: Option<u64>,
+
+    /// Content to compare to instead of a version of the resource
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Unchanged lines shown around each change
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<usize>,
+}
+
+/// Result of the `resources/diff` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesDiffResult {
+    /// Compared resource
+    pub uri: String,
+
+    /// Unified diff, empty if both sides are equal
+    pub diff: String,
+
+    /// Number of added lines
+    pub additions: usize,
+
+    /// Number of removed lines
+    pub deletions: usize,
+}
+
+/// Line-level difference between two texts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextDiff {
+    /// Unified diff, empty if the texts are equal
+    pub unified: String,
+
+    /// Number of added lines
+    pub additions: usize,
+
+    /// Number of removed lines
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Diff `old` against `new`, labelling the sides in the file headers
+pub fn unified_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    context_lines: usize,
+) -> TextDiff {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    // Positions in both texts before each edit
+    let mut positions = Vec::with_capacity(edits.len() + 1);
+    let (mut old_position, mut new_position) = (0, 0);
+    for edit in &edits {
+        positions.push((old_position, new_position));
+        match edit {
+            Edit::Equal(..) => {
+                old_position += 1;
+                new_position += 1;
+            }
+            Edit::Delete(_) => old_position += 1,
+            Edit::Insert(_) => new_position += 1,
+        }
+    }
+    positions.push((old_position, new_position));
+
+    // Changes closer than twice the context share a hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (index, _) in edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(..)))
+    {
+        let start = index.saturating_sub(context_lines);
+        let end = (index + 1 + context_lines).min(edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = TextDiff {
+        unified: String::new(),
+        additions: 0,
+        deletions: 0,
+    };
+    if hunks.is_empty() {
+        return diff;
+    }
+    diff.unified = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        diff.unified.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_end - old_start),
+            hunk_range(new_start, new_end - new_start)
+        ));
+        for edit in &edits[start..end] {
+            let (prefix, line) = match *edit {
+                Edit::Equal(old_index, _) => (' ', old_lines[old_index]),
+                Edit::Delete(old_index) => {
+                    diff.deletions += 1;
+                    ('-', old_lines[old_index])
+                }
+                Edit::Insert(new_index) => {
+                    diff.additions += 1;
+                    ('+', new_lines[new_index])
+                }
+            };
+            diff.unified.push(prefix);
+            diff.unified.push_str(line);
+            if !line.ends_with('\n') {
+                diff.unified.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    diff
+}
+
+/// `start,count` of a hunk header, with `start` one-based
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// Shortest edit script turning `old` into `new` (Myers' algorithm)
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    let offset = max + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // Furthest x reached on each diagonal k = x - y, kept for every depth
+    let mut furthest = vec![0isize; 2 * max as usize + 3];
+    let mut trace = Vec::new();
+    'search: for depth in 0..=max {
+        trace.push(furthest.clone());
+        for k in (-depth..=depth).step_by(2) {
+            let mut x =
+                if k == -depth || (k != depth && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+                    furthest[index(k + 1)]
+                } else {
+                    furthest[index(k - 1)] + 1
+                };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            furthest[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back from the end through the depths
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (depth, furthest) in trace.iter().enumerate().rev() {
+        let depth = depth as isize;
+        let k = x - y;
+        let previous_k =
+            if k == -depth || (k != depth && furthest[index(k - 1)] < furthest[index(k + 1)]) {
+                k + 1
+            } else {
+                k - 1
+            };
+        let previous_x = furthest[index(previous_k)];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal(x as usize, y as usize));
+        }
+        if depth > 0 {
+            if x == previous_x {
+                y -= 1;
+                edits.push(Edit::Insert(y as usize));
+            } else {
+                x -= 1;
+                edits.push(Edit::Delete(x as usize));
+            }
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+        let diff = unified_diff(old, new, "doc@1", "doc@2", 1);
+        assert_eq!(
+            diff.unified,
+            "--- doc@1\n+++ doc@2\n\
+             @@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n\
+             @@ -10 +10,2 @@\n j\n+k\n\\ No newline at end of file\n"
+        );
+        assert_eq!((diff.additions, diff.deletions), (2, 1));
+
+        // Changes within twice the context share a hunk
+        let diff = unified_diff(old, "a\nB\nc\nd\ne\nF\ng\nh\ni\nj\n", "a", "b", 1);
+        assert_eq!(diff.unified.matches("@@ -").count(), 2);
+        let diff = unified_diff(old, "a\nB\nc\nd\nE\nf\ng\nh\ni\nj\n", "a", "b", 1);
+        assert_eq!(diff.unified.matches("@@ -").count(), 1);
+
+        assert_eq!(unified_diff(old, old, "a", "b", 3).unified, "");
+        assert_eq!(
+            unified_diff("", "x\n", "a", "b", 3).unified,
+            "--- a\n+++ b\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
+}
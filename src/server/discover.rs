@@ -0,0 +1,283 @@
+//! Self-describing method catalog
+//!
+//! `rpc.discover` returns an [OpenRPC](https://spec.open-rpc.org) document
+//! listing every JSON-RPC method the server answers: the standard MCP methods
+//! it implements, its built-in extensions and the custom methods of the state,
+//! with JSON schemas of their params and results where they are known. Generic
+//! client tooling and contract tests can work from it without knowing the
+//! server in advance.
+//!
+//! Params are passed by name, so each property of a method's params schema
+//! becomes one OpenRPC param. Admin methods are marked with
+//! `x-requires-system-context`, and method aliases are listed under
+//! `x-aliases` of the method they are handled as. The same catalog is served
+//! under `x-mcp.methods` of the [OpenAPI document](crate::server::openapi).
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::server::aliases::MethodAliasConfig;
+
+/// Returns the OpenRPC document of the server's methods
+pub const RPC_DISCOVER: &str = "rpc.discover";
+
+/// OpenRPC version of the discovery document
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// A JSON-RPC method the server answers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodDescription {
+    /// Method name
+    pub name: String,
+
+    /// One-line description
+    pub summary: String,
+
+    /// JSON schema of the params object, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+
+    /// JSON schema of the result, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    /// Whether the method is a notification, answered without a result
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notification: bool,
+
+    /// Whether only system contexts may call the method
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub requires_system_context: bool,
+}
+
+impl MethodDescription {
+    /// A method without known schemas
+    pub fn new(name: impl Into<String>, summary: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            summary: summary.into(),
+            params: None,
+            result: None,
+            notification: false,
+            requires_system_context: false,
+        }
+    }
+
+    /// Describe the params object with the JSON schema `schema`
+    pub fn with_params(mut self, schema: Value) -> Self {
+        self.params = Some(schema);
+        self
+    }
+
+    /// Describe the result with the JSON schema `schema`
+    pub fn with_result(mut self, schema: Value) -> Self {
+        self.result = Some(schema);
+        self
+    }
+
+    /// Mark the method as a notification
+    pub fn notification(mut self) -> Self {
+        self.notification = true;
+        self
+    }
+
+    /// Mark the method as callable by system contexts only
+    pub fn system_only(mut self) -> Self {
+        self.requires_system_context = true;
+        self
+    }
+
+    /// This method as an OpenRPC method object, with the `aliases` it is
+    /// also called by
+    pub fn to_openrpc(&self, aliases: &[&str]) -> Value {
+        let required: Vec<&str> = self
+            .params
+            .as_ref()
+            .and_then(|schema| schema["required"].as_array())
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let params: Vec<Value> = self
+            .params
+            .as_ref()
+            .and_then(|schema| schema["properties"].as_object())
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, schema)| {
+                        json!({
+                            "name": name,
+                            "required": required.contains(&name.as_str()),
+                            "schema": schema,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut method = json!({
+            "name": self.name,
+            "summary": self.summary,
+            "paramStructure": "by-name",
+            "params": params,
+        });
+        if !self.notification {
+            method["result"] = json!({
+                "name": "result",
+                "schema": self.result.clone().unwrap_or_else(|| json!({})),
+            });
+        }
+        if self.requires_system_context {
+            method["x-requires-system-context"] = Value::Bool(true);
+        }
+        if !aliases.is_empty() {
+            method["x-aliases"] = json!(aliases);
+        }
+        method
+    }
+}
+
+/// OpenRPC document of `methods`, for a server named `name` at `version`
+pub fn openrpc_document(
+    name: &str,
+    version: &str,
+    methods: &[MethodDescription],
+    aliases: &MethodAliasConfig,
+) -> Value {
+    let methods: Vec<Value> = methods
+        .iter()
+        .map(|method| {
+            let mut names: Vec<&str> = aliases
+                .aliases
+                .iter()
+                .filter(|(_, alias)| alias.target == method.name)
+                .map(|(alias, _)| alias.as_str())
+                .collect();
+            names.sort_unstable();
+            method.to_openrpc(&names)
+        })
+        .collect();
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {"title": name, "version": version},
+        "methods": methods,
+    })
+}
+
+/// Object schema with the given properties, of which `required` are required
+pub(crate) fn object_schema(properties: Value, required: &[&str]) -> Value {
+    json!({"type": "object", "properties": properties, "required": required})
+}
+
+/// Descriptions of the standard MCP methods, by name
+pub(crate) fn standard_method(name: &str) -> MethodDescription {
+    let string = json!({"type": "string"});
+    let object = json!({"type": "object"});
+    let array = json!({"type": "array", "items": {"type": "object"}});
+    match name {
+        "initialize" => MethodDescription::new(name, "Negotiate protocol version and capabilities")
+            .with_params(object_schema(
+                json!({
+                    "protocolVersion": string,
+                    "capabilities": object,
+                    "clientInfo": object_schema(
+                        json!({"name": string, "version": string}),
+                        &["name", "version"],
+                    ),
+                }),
+                &["protocolVersion", "capabilities"],
+            ))
+            .with_result(object_schema(
+                json!({
+                    "protocolVersion": string,
+                    "capabilities": object,
+                    "serverInfo": object_schema(
+                        json!({"name": string, "version": string}),
+                        &["name", "version"],
+                    ),
+                }),
+                &["protocolVersion", "capabilities", "serverInfo"],
+            )),
+        "initialized" | "notifications/initialized" => {
+            MethodDescription::new(name, "Client finished initialization").notification()
+        }
+        "ping" => MethodDescription::new(name, "Health check").with_result(object),
+        "tools/list" => MethodDescription::new(name, "List available tools")
+            .with_params(object_schema(json!({"cursor": string}), &[]))
+            .with_result(object_schema(
+                json!({"tools": array, "nextCursor": string}),
+                &["tools"],
+            )),
+        "tools/call" => {
+            MethodDescription::new(name, "Call a tool with arguments matching its inputSchema")
+                .with_params(object_schema(
+                    json!({"name": string, "arguments": object}),
+                    &["name"],
+                ))
+                .with_result(object_schema(
+                    json!({"content": array, "isError": {"type": "boolean"}}),
+                    &["content"],
+                ))
+        }
+        "batch" => MethodDescription::new(name, "Run several requests in one call")
+            .with_params(object_schema(
+                json!({"requests": array, "executionMode": string}),
+                &["requests"],
+            ))
+            .with_result(object_schema(json!({"results": array}), &["results"])),
+        "resources/list" => MethodDescription::new(name, "List resource templates")
+            .with_params(object_schema(json!({"cursor": string}), &[]))
+            .with_result(object_schema(
+                json!({"resources": array, "nextCursor": string}),
+                &["resources"],
+            )),
+        "resources/read" => MethodDescription::new(name, "Read a resource by URI")
+            .with_params(object_schema(json!({"uri": string}), &["uri"]))
+            .with_result(object_schema(json!({"contents": array}), &["contents"])),
+        "prompts/list" => MethodDescription::new(name, "List prompts")
+            .with_params(object_schema(json!({"cursor": string}), &[]))
+            .with_result(object_schema(
+                json!({"prompts": array, "nextCursor": string}),
+                &["prompts"],
+            )),
+        "prompts/get" => MethodDescription::new(name, "Render a prompt with arguments")
+            .with_params(object_schema(
+                json!({"name": string, "arguments": object}),
+                &["name"],
+            ))
+            .with_result(object_schema(
+                json!({"description": string, "messages": array}),
+                &["messages"],
+            )),
+        _ => MethodDescription::new(name, "Standard MCP method"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openrpc_document() {
+        let methods = vec![
+            standard_method("tools/call"),
+            standard_method("notifications/initialized"),
+            MethodDescription::new("admin/quotas/reset", "Restore a principal's quota")
+                .system_only(),
+        ];
+        let aliases = MethodAliasConfig::default().with_deprecated("tool/run", "tools/call");
+        let document = openrpc_document("notes", "1.4.0", &methods, &aliases);
+
+        assert_eq!(document["openrpc"], OPENRPC_VERSION);
+        assert_eq!(document["info"]["title"], "notes");
+        let call = &document["methods"][0];
+        assert_eq!(call["params"][0]["name"], "arguments");
+        assert_eq!(call["params"][0]["required"], false);
+        assert_eq!(call["params"][1]["name"], "name");
+        assert_eq!(call["params"][1]["required"], true);
+        assert_eq!(call["result"]["schema"]["required"][0], "content");
+        assert_eq!(call["x-aliases"][0], "tool/run");
+        assert!(document["methods"][1].get("result").is_none());
+        assert_eq!(document["methods"][2]["x-requires-system-context"], true);
+    }
+}
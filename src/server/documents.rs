@@ -0,0 +1,458 @@
+//! Text extraction from PDF and DOCX documents
+//!
+//! With the `documents` feature, `resources/read` returns the plain text of
+//! PDF and DOCX resources requested as `text/plain`, for example with
+//! `files://report.pdf?format=txt`.
+//!
+//! Extraction is best effort. PDFs are parsed with `lopdf` and the text
+//! operators of each page's content streams are read in order; text in fonts
+//! without a standard encoding may come out garbled, and scanned pages yield
+//! nothing. DOCX documents are unpacked with the `zip` crate, and the text of
+//! the main document part is read paragraph by paragraph with `quick-xml`.
+//!
+//! Both formats get the same limits as [archives](crate::server::archive):
+//! documents and each stream or part decompressed from them are capped at
+//! [`DEFAULT_MAX_ENTRY_BYTES`]. lopdf inflates streams without a limit, so
+//! every stream of a PDF is inflated with the cap before it is loaded, and
+//! encrypted or LZW compressed PDFs, whose streams can't be checked that way,
+//! are refused. Extraction runs on the blocking thread pool.
+
+use async_trait::async_trait;
+use encoding_rs::{UTF_16BE, WINDOWS_1252};
+use flate2::read::ZlibDecoder;
+use lopdf::{
+    content::{Content, Operation},
+    Dictionary, Document, Encoding, Object, Stream,
+};
+use quick_xml::{events::Event, Reader};
+use std::{borrow::Cow, collections::BTreeMap, io::Read};
+
+use crate::{
+    error::{McpError, McpResult},
+    server::{
+        archive::{list_entries, DEFAULT_MAX_ENTRIES, DEFAULT_MAX_ENTRY_BYTES},
+        transform::ResourceTransformer,
+    },
+};
+
+/// Layers of compression followed per PDF stream; lopdf applies a stream's
+/// filters in turn, so a stream still compressed after these is refused
+const MAX_STREAM_LAYERS: usize = 8;
+
+/// MIME type of DOCX documents
+pub const DOCX_MIME_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+/// Extracts the text of PDF documents
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PdfTextExtractor;
+
+#[async_trait]
+impl ResourceTransformer for PdfTextExtractor {
+    fn source(&self) -> &str {
+        "application/pdf"
+    }
+
+    fn target(&self) -> &str {
+        "text/plain"
+    }
+
+    async fn transform(&self, uri: &str, text: &str) -> McpResult<String> {
+        self.transform_bytes(uri, text.as_bytes()).await
+    }
+
+    async fn transform_bytes(&self, uri: &str, bytes: &[u8]) -> McpResult<String> {
+        extract(uri, bytes, |bytes| pdf_text(bytes, DEFAULT_MAX_ENTRY_BYTES)).await
+    }
+}
+
+/// Extracts the text of DOCX documents
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DocxTextExtractor;
+
+#[async_trait]
+impl ResourceTransformer for DocxTextExtractor {
+    fn source(&self) -> &str {
+        DOCX_MIME_TYPE
+    }
+
+    fn target(&self) -> &str {
+        "text/plain"
+    }
+
+    async fn transform(&self, uri: &str, text: &str) -> McpResult<String> {
+        self.transform_bytes(uri, text.as_bytes()).await
+    }
+
+    async fn transform_bytes(&self, uri: &str, bytes: &[u8]) -> McpResult<String> {
+        extract(uri, bytes, docx_text).await
+    }
+}
+
+/// Run `extract` on the blocking thread pool, documents being parsed and
+/// decompressed synchronously
+async fn extract(
+    uri: &str,
+    bytes: &[u8],
+    extract: fn(&[u8]) -> Result<String, String>,
+) -> McpResult<String> {
+    let bytes = bytes.to_vec();
+    tokio::task::spawn_blocking(move || extract(&bytes))
+        .await
+        .map_err(|e| McpError::internal(format!("Text extraction failed: {}", e)))?
+        .map_err(|message| unreadable(uri, message))
+}
+
+fn unreadable(uri: &str, message: impl Into<String>) -> McpError {
+    McpError::InvalidResource {
+        uri: uri.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Text of the paragraphs in `word/document.xml`
+fn docx_text(bytes: &[u8]) -> Result<String, String> {
+    let document = list_entries(bytes, DEFAULT_MAX_ENTRIES)?
+        .into_iter()
+        .find(|entry| entry.path == "word/document.xml")
+        .ok_or("Not a DOCX document")?
+        .read(bytes, DEFAULT_MAX_ENTRY_BYTES)?;
+
+    let mut reader = Reader::from_reader(document.as_slice());
+    let mut text = String::new();
+    let mut in_text = false;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(tag) if tag.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(tag) => match tag.name().as_ref() {
+                b"w:t" => in_text = false,
+                b"w:p" => text.push('\n'),
+                _ => {}
+            },
+            Event::Start(tag) | Event::Empty(tag) => match tag.name().as_ref() {
+                b"w:tab" => text.push('\t'),
+                b"w:br" | b"w:cr" => text.push('\n'),
+                _ => {}
+            },
+            Event::Text(run) if in_text => {
+                text.push_str(&run.unescape().map_err(|e| e.to_string())?);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(text.trim_end().to_string() + "\n")
+}
+
+/// Text shown by the content streams of each page of a PDF, refusing
+/// documents or streams of more than `max_bytes`
+fn pdf_text(bytes: &[u8], max_bytes: usize) -> Result<String, String> {
+    if bytes.len() > max_bytes {
+        return Err(format!("Document exceeds {} bytes", max_bytes));
+    }
+    check_streams(bytes, max_bytes)?;
+    let document = Document::load_mem(bytes).map_err(|e| format!("Not a PDF document: {}", e))?;
+
+    let mut text = String::new();
+    for page in document.get_pages().into_values() {
+        let fonts = document.get_page_fonts(page).unwrap_or_default();
+        let encodings: BTreeMap<&[u8], Encoding> = fonts
+            .iter()
+            .filter_map(|(name, font)| {
+                Some((name.as_slice(), font.get_font_encoding(&document).ok()?))
+            })
+            .collect();
+        let content = document
+            .get_page_content(page)
+            .and_then(|content| Content::decode(&content))
+            .map_err(|e| e.to_string())?;
+        show_text(&content.operations, &encodings, &mut text);
+    }
+    Ok(text.trim_end().to_string() + "\n")
+}
+
+/// Refuse PDFs with a stream that inflates to more than `max_bytes`
+///
+/// Streams are found by their `stream` and `endstream` keywords rather than
+/// parsed, and each is decoded for as long as it looks deflated or ASCII85
+/// encoded, which covers every filter chain lopdf decompresses.
+fn check_streams(pdf: &[u8], max_bytes: usize) -> Result<(), String> {
+    if contains(pdf, b"/Encrypt") {
+        return Err("Encrypted PDFs are not supported".to_string());
+    }
+    if contains(pdf, b"/LZWDecode") {
+        return Err("LZW compressed PDFs are not supported".to_string());
+    }
+
+    let mut rest = pdf;
+    while let Some(start) = find(rest, b"stream") {
+        let keyword_end = start + b"stream".len();
+        let is_endstream = rest[..start].ends_with(b"end");
+        let after = &rest[keyword_end..];
+        let Some(data) = after
+            .strip_prefix(b"\r\n")
+            .or_else(|| after.strip_prefix(b"\n"))
+            .filter(|_| !is_endstream)
+        else {
+            rest = after;
+            continue;
+        };
+        let end = find(data, b"endstream").unwrap_or(data.len());
+        check_stream(&data[..end], max_bytes)?;
+        rest = &data[end..];
+    }
+    Ok(())
+}
+
+fn check_stream(data: &[u8], max_bytes: usize) -> Result<(), String> {
+    let mut layer = Cow::Borrowed(data);
+    for _ in 0..MAX_STREAM_LAYERS {
+        let decoded = if is_zlib(&layer) {
+            // lopdf keeps what inflated before an error, so do the same
+            let mut decoded = Vec::new();
+            let _ = ZlibDecoder::new(layer.as_ref())
+                .take(max_bytes as u64 + 1)
+                .read_to_end(&mut decoded);
+            decoded
+        } else if is_ascii85(&layer) {
+            let dictionary = Dictionary::from_iter([("Filter", Object::from("ASCII85Decode"))]);
+            Stream::new(dictionary, layer.into_owned())
+                .decompressed_content()
+                .map_err(|e| e.to_string())?
+        } else {
+            return Ok(());
+        };
+        if decoded.len() > max_bytes {
+            return Err(format!(
+                "PDF stream inflates to more than {} bytes",
+                max_bytes
+            ));
+        }
+        layer = Cow::Owned(decoded);
+    }
+    if is_zlib(&layer) || is_ascii85(&layer) {
+        return Err("PDF stream is compressed too many times".to_string());
+    }
+    Ok(())
+}
+
+/// Whether `data` starts with a zlib header
+fn is_zlib(data: &[u8]) -> bool {
+    matches!(data, [cmf, flg, ..] if cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0)
+}
+
+/// Whether `data` is ASCII85 text closed by `~>`
+fn is_ascii85(data: &[u8]) -> bool {
+    let data = data.trim_ascii();
+    data.ends_with(b"~>")
+        && data[..data.len() - 2]
+            .iter()
+            .all(|&byte| matches!(byte, b'!'..=b'u' | b'z') || byte.is_ascii_whitespace())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    find(haystack, needle).is_some()
+}
+
+/// Append the text shown by the text operators of a page
+fn show_text(operations: &[Operation], encodings: &BTreeMap<&[u8], Encoding>, text: &mut String) {
+    let mut encoding = None;
+    let mut line_y = None;
+    for operation in operations {
+        let operands = operation.operands.as_slice();
+        match operation.operator.as_str() {
+            "Tf" => {
+                encoding = operands
+                    .first()
+                    .and_then(|font| font.as_name().ok())
+                    .and_then(|font| encodings.get(font));
+            }
+            operator @ ("Tj" | "'" | "\"") => {
+                if operator != "Tj" {
+                    new_line(text);
+                }
+                if let Some(Object::String(string, _)) = operands.last() {
+                    text.push_str(&decode_string(string, encoding));
+                }
+            }
+            "TJ" => {
+                let Some(Object::Array(items)) = operands.last() else {
+                    continue;
+                };
+                for item in items {
+                    match item {
+                        Object::String(string, _) => {
+                            text.push_str(&decode_string(string, encoding))
+                        }
+                        // Large negative offsets separate words
+                        offset
+                            if offset.as_float().is_ok_and(|offset| offset < -200.0)
+                                && !text.ends_with(char::is_whitespace) =>
+                        {
+                            text.push(' ')
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            "T*" => new_line(text),
+            "Td" | "TD" => {
+                if let [.., _, y] = operands {
+                    if y.as_float().is_ok_and(|y| y != 0.0) {
+                        new_line(text);
+                    } else if !text.ends_with(char::is_whitespace) && !text.is_empty() {
+                        text.push(' ');
+                    }
+                }
+            }
+            "Tm" => {
+                if let Some(Ok(y)) = operands.last().map(Object::as_float) {
+                    if line_y.is_some_and(|line_y| line_y != y) {
+                        new_line(text);
+                    }
+                    line_y = Some(y);
+                }
+            }
+            _ => {}
+        }
+    }
+    new_line(text);
+}
+
+fn new_line(text: &mut String) {
+    let trimmed = text.trim_end_matches(' ').len();
+    text.truncate(trimmed);
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+/// Text of a PDF string: UTF-16 with a byte order mark, otherwise in the
+/// font's encoding, or single bytes when the font has none
+fn decode_string(bytes: &[u8], encoding: Option<&Encoding>) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+        return UTF_16BE.decode_without_bom_handling(utf16).0.into_owned();
+    }
+    encoding
+        .and_then(|encoding| Document::decode_text(encoding, bytes).ok())
+        .unwrap_or_else(|| {
+            WINDOWS_1252
+                .decode_without_bom_handling(bytes)
+                .0
+                .into_owned()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::transform::ResourceTransformers;
+    use base64::Engine;
+
+    /// One page drawn by a deflated and a plain content stream, in Helvetica
+    const REPORT_PDF: &str = "JVBERi0xLjQKMSAwIG9iago8PCAvVHlwZSAvQ2F0YWxvZyAvUGFnZXMgMiAwIFIgPj4KZW5kb2JqCjIgMCBvYmoKPDwgL1R5cGUgL1BhZ2VzIC9LaWRzIFszIDAgUl0gL0NvdW50IDEgPj4KZW5kb2JqCjMgMCBvYmoKPDwgL1R5cGUgL1BhZ2UgL1BhcmVudCAyIDAgUiAvUmVzb3VyY2VzIDw8IC9Gb250IDw8IC9GMSA2IDAgUiA+PiA+PiAvQ29udGVudHMgWzQgMCBSIDUgMCBSXSA+PgplbmRvYmoKNCAwIG9iago8PCAvTGVuZ3RoIDk4IC9GaWx0ZXIgL0ZsYXRlRGVjb2RlID4+CnN0cmVhbQp4nHMKUdB3M1QwNFIISVMwNwIiA4WQFAWNwNLEopLUopxKhRiNQOMYTYWi1IL8ohJNhZAsBQMFXUMTkKpojaDUMk1DA43UvNJUTQVdYwMDBY30otRyzViFEC8F1xAAoHYZYQplbmRzdHJlYW0KZW5kb2JqCjUgMCBvYmoKPDwgL0xlbmd0aCA3MiA+PgpzdHJlYW0KQlQgL0YxIDEyIFRmIDcyIDcwMCBUZCA8RkVGRjAwNjMwMDYxMDA2NjAwRTk+IFRqIFQqIChDYWZcMzUxIG9wZW4pIFRqIEVUCmVuZHN0cmVhbQplbmRvYmoKNiAwIG9iago8PCAvVHlwZSAvRm9udCAvU3VidHlwZSAvVHlwZTEgL0Jhc2VGb250IC9IZWx2ZXRpY2EgL0VuY29kaW5nIC9XaW5BbnNpRW5jb2RpbmcgPj4KZW5kb2JqCnhyZWYKMCA3CjAwMDAwMDAwMDAgNjU1MzUgZiAKMDAwMDAwMDAwOSAwMDAwMCBuIAowMDAwMDAwMDU4IDAwMDAwIG4gCjAwMDAwMDAxMTUgMDAwMDAgbiAKMDAwMDAwMDIyNSAwMDAwMCBuIAowMDAwMDAwMzk0IDAwMDAwIG4gCjAwMDAwMDA1MTYgMDAwMDAgbiAKdHJhaWxlcgo8PCAvU2l6ZSA3IC9Sb290IDEgMCBSID4+CnN0YXJ0eHJlZgo2MTMKJSVFT0YK";
+
+    /// Two paragraphs, the second split over runs with a tab
+    const NOTES_DOCX: &str = "UEsDBBQAAAAIANGkUF3uR1hmHwAAAB0AAAATAAAAW0NvbnRlbnRfVHlwZXNdLnhtbLOxr8jNUShLLSrOzM+zVTLUM1Cyt7MJqSxILda3AwBQSwMEFAAAAAgA0aRQXYyJWUKoAAAAEwEAABEAAAB3b3JkL2RvY3VtZW50LnhtbG2Puw7CMAxFfyXywAaBDZWmlRjY2OAD0saqKrWOlaSvvyfhKRDLsXQTnWvn5dx3YkTnW0sKdpstCKTampYaBdfLab0H4YMmoztLqGBBD2WRT5mx9dAjBREF5LNJwQwpr6xZ0uQElxCKM2KIQkE2oM9lihLdnfzzOwkzz7qObezQoxsRiuNgGgxipXs+iC/Fu4U7Tc+XCF3JR6yZnR3R/OmVr3Xl557iBlBLAQIUAxQAAAAIANGkUF3uR1hmHwAAAB0AAAATAAAAAAAAAAAAAACAAQAAAABbQ29udGVudF9UeXBlc10ueG1sUEsBAhQDFAAAAAgA0aRQXYyJWUKoAAAAEwEAABEAAAAAAAAAAAAAAIABUAAAAHdvcmQvZG9jdW1lbnQueG1sUEsFBgAAAAACAAIAgAAAACcBAAAAAA==";
+
+    fn decode(document: &str) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD
+            .decode(document)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_document_text_is_extracted() {
+        assert_eq!(
+            PdfTextExtractor
+                .transform_bytes("files://report.pdf", &decode(REPORT_PDF))
+                .await
+                .unwrap(),
+            "Quarterly (Q3) report\nRevenue grew\ncafé\nCafé open\n"
+        );
+        assert_eq!(
+            DocxTextExtractor
+                .transform_bytes("files://notes.docx", &decode(NOTES_DOCX))
+                .await
+                .unwrap(),
+            "Meeting notes\nBudget & plan\tapproved\n"
+        );
+        assert!(PdfTextExtractor
+            .transform_bytes("files://notes.docx", &decode(NOTES_DOCX))
+            .await
+            .is_err());
+
+        assert!(pdf_text(&decode(REPORT_PDF), 1024).is_ok());
+        assert!(pdf_text(&decode(REPORT_PDF), 100)
+            .unwrap_err()
+            .contains("exceeds 100 bytes"));
+
+        let transformers = ResourceTransformers::default();
+        let extractor = transformers
+            .select("files://report.pdf", "application/pdf", "txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(extractor.target(), "text/plain");
+    }
+
+    #[test]
+    fn test_pdf_streams_are_inflated_with_a_limit() {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut encoder, &[b' '; 4096]).unwrap();
+        let bomb = encoder.finish().unwrap();
+
+        // Deflated twice, and ASCII85 encoded on top
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut encoder, &bomb).unwrap();
+        let nested = encoder.finish().unwrap();
+        let ascii85 = ascii85(&nested);
+
+        for (filter, data) in [
+            ("/FlateDecode", bomb.clone()),
+            ("[/FlateDecode /FlateDecode]", nested),
+            ("[/ASCII85Decode /FlateDecode /FlateDecode]", ascii85),
+        ] {
+            let mut pdf = format!(
+                "%PDF-1.4\n1 0 obj\n<< /Length {} /Filter {} >>\nstream\n",
+                data.len(),
+                filter
+            )
+            .into_bytes();
+            pdf.extend_from_slice(&data);
+            pdf.extend_from_slice(b"\nendstream\nendobj\n");
+
+            assert!(check_streams(&pdf, 8192).is_ok(), "{}", filter);
+            assert!(
+                pdf_text(&pdf, 1024)
+                    .unwrap_err()
+                    .contains("more than 1024 bytes"),
+                "{}",
+                filter
+            );
+        }
+    }
+
+    /// ASCII85 encoding with the `~>` end marker
+    fn ascii85(data: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        for chunk in data.chunks(4) {
+            let mut group = [0u8; 4];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let mut value = u32::from_be_bytes(group);
+            let mut digits = [0u8; 5];
+            for digit in digits.iter_mut().rev() {
+                *digit = b'!' + (value % 85) as u8;
+                value /= 85;
+            }
+            encoded.extend_from_slice(&digits[..chunk.len() + 1]);
+        }
+        encoded.extend_from_slice(b"~>");
+        encoded
+    }
+}
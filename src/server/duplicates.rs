@@ -0,0 +1,440 @@
+//! Detection of retried JSON-RPC requests
+//!
+//! A client that times out waiting for a response may send the same request
+//! again with the same ID. For side-effecting tools that means running twice.
+//! [`DuplicateRequests`] remembers recent request IDs per session and,
+//! depending on the [`DuplicateRequestPolicy`], answers a repeated request
+//! with the original response, rejects it, or runs it again.
+//!
+//! Only requests in a session are tracked: separate connections sharing a
+//! client ID each number their requests from the start, so their IDs are not
+//! comparable. The IDs of a session are forgotten when it is deleted.
+//!
+//! A duplicate that arrives while the original is still running waits for it
+//! under [`DuplicateRequestPolicy::Replay`]. An ID reused for a different
+//! method or different parameters is never replayed; it is rejected.
+//!
+//! Each request expires the IDs of its own session only. Idle sessions are
+//! swept at most once per window.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::McpError,
+    protocol::{JsonRpcRequest, JsonRpcResponse},
+    security::SecurityContext,
+};
+
+/// What to do with a request whose ID was seen recently in the same session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateRequestPolicy {
+    /// Run it again; IDs are not tracked
+    #[default]
+    Execute,
+    /// Answer with the response of the original request
+    Replay,
+    /// Answer with an Invalid Request error
+    Reject,
+}
+
+/// Duplicate request detection configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateRequestConfig {
+    /// How duplicates are handled
+    pub policy: DuplicateRequestPolicy,
+
+    /// How long a request ID is remembered after its response was sent
+    #[serde(with = "humantime_serde")]
+    pub window: Duration,
+
+    /// Request IDs remembered per session; the oldest are forgotten first
+    pub max_tracked: usize,
+}
+
+impl Default for DuplicateRequestConfig {
+    fn default() -> Self {
+        Self {
+            policy: DuplicateRequestPolicy::Execute,
+            window: Duration::from_secs(300),
+            max_tracked: 1000,
+        }
+    }
+}
+
+impl DuplicateRequestConfig {
+    /// Answer retried requests with the original response
+    pub fn replay() -> Self {
+        Self {
+            policy: DuplicateRequestPolicy::Replay,
+            ..Default::default()
+        }
+    }
+
+    /// Reject retried requests
+    pub fn reject() -> Self {
+        Self {
+            policy: DuplicateRequestPolicy::Reject,
+            ..Default::default()
+        }
+    }
+
+    /// Remember request IDs for `window` after their response
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Tracked {
+    method: String,
+    params: Option<Value>,
+    response: watch::Receiver<Option<JsonRpcResponse>>,
+    completed_at: Option<Instant>,
+}
+
+impl Tracked {
+    /// Whether the original request was dropped before it answered
+    fn is_abandoned(&self) -> bool {
+        self.completed_at.is_none() && self.response.has_changed().is_err()
+    }
+}
+
+#[derive(Debug)]
+struct SessionRequests {
+    requests: HashMap<String, Tracked>,
+    order: VecDeque<String>,
+    last_used: Instant,
+}
+
+impl SessionRequests {
+    /// Forget requests that were abandoned or answered more than `window` ago
+    fn expire(&mut self, now: Instant, window: Duration) {
+        self.requests.retain(|_, tracked| {
+            !tracked.is_abandoned()
+                && tracked
+                    .completed_at
+                    .is_none_or(|completed_at| now.duration_since(completed_at) < window)
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sessions {
+    sessions: HashMap<String, SessionRequests>,
+    swept_at: Option<Instant>,
+}
+
+enum Admission {
+    Run(watch::Sender<Option<JsonRpcResponse>>),
+    Wait(watch::Receiver<Option<JsonRpcResponse>>),
+    Answer(JsonRpcResponse),
+}
+
+/// Recently seen request IDs per session
+#[derive(Debug, Clone)]
+pub struct DuplicateRequests {
+    config: DuplicateRequestConfig,
+    sessions: Arc<Mutex<Sessions>>,
+    clock: Clock,
+}
+
+impl DuplicateRequests {
+    /// Track request IDs as configured
+    pub fn new(config: DuplicateRequestConfig) -> Self {
+        Self {
+            config,
+            sessions: Arc::new(Mutex::new(Sessions::default())),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Measure the window on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &DuplicateRequestConfig {
+        &self.config
+    }
+
+    /// Answer `request` with `handler` unless it duplicates a recent request
+    ///
+    /// Requests without an ID (notifications) or outside a session are always
+    /// handled.
+    pub async fn handle<F, Fut>(
+        &self,
+        request: JsonRpcRequest,
+        context: SecurityContext,
+        handler: F,
+    ) -> JsonRpcResponse
+    where
+        F: FnOnce(JsonRpcRequest, SecurityContext) -> Fut,
+        Fut: Future<Output = JsonRpcResponse>,
+    {
+        let scope = context.client.session_id.clone();
+        let (Some(scope), Some(id)) = (scope, request.id.as_ref()) else {
+            return handler(request, context).await;
+        };
+        if self.config.policy == DuplicateRequestPolicy::Execute {
+            return handler(request, context).await;
+        }
+
+        // IDs are compared as JSON so that 1 and "1" stay distinct
+        let key = id.to_string();
+        match self.admit(&scope, &key, &request) {
+            Admission::Answer(response) => response,
+            Admission::Wait(mut receiver) => {
+                debug!("Waiting for the original of duplicate request {}", key);
+                let answered = receiver
+                    .wait_for(Option::is_some)
+                    .await
+                    .ok()
+                    .and_then(|response| response.clone());
+                match answered {
+                    Some(response) => response,
+                    // The original was dropped before it answered
+                    None => handler(request, context).await,
+                }
+            }
+            Admission::Run(sender) => {
+                let response = handler(request, context).await;
+                self.complete(&scope, &key, sender, &response);
+                response
+            }
+        }
+    }
+
+    /// Forget the request IDs of a session, once it is deleted
+    pub fn remove(&self, session_id: &str) {
+        self.sessions.lock().unwrap().sessions.remove(session_id);
+    }
+
+    /// Number of request IDs remembered across all sessions
+    pub fn len(&self) -> usize {
+        self.sessions
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .map(|session| session.requests.len())
+            .sum()
+    }
+
+    /// Whether no request IDs are remembered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn admit(&self, scope: &str, key: &str, request: &JsonRpcRequest) -> Admission {
+        let now = self.clock.now();
+        let window = self.config.window;
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions
+            .swept_at
+            .is_none_or(|swept_at| now.duration_since(swept_at) >= window)
+        {
+            sessions.swept_at = Some(now);
+            sessions.sessions.retain(|_, session| {
+                session.expire(now, window);
+                !session.requests.is_empty() || now.duration_since(session.last_used) < window
+            });
+        }
+
+        let session = sessions
+            .sessions
+            .entry(scope.to_string())
+            .or_insert_with(|| SessionRequests {
+                requests: HashMap::new(),
+                order: VecDeque::new(),
+                last_used: now,
+            });
+        session.last_used = now;
+        session.expire(now, window);
+
+        if let Some(tracked) = session.requests.get(key) {
+            debug!("Duplicate request {} in {}", key, scope);
+            if tracked.method != request.method || tracked.params != request.params {
+                return Admission::Answer(reject(
+                    request,
+                    "Request ID was already used for a different request",
+                ));
+            }
+            if self.config.policy == DuplicateRequestPolicy::Reject {
+                return Admission::Answer(reject(request, "Duplicate request ID"));
+            }
+            let answered = tracked.response.borrow().clone();
+            return match answered {
+                Some(mut response) => {
+                    response.id = request.id.clone();
+                    Admission::Answer(response)
+                }
+                None => Admission::Wait(tracked.response.clone()),
+            };
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        session.requests.insert(
+            key.to_string(),
+            Tracked {
+                method: request.method.clone(),
+                params: request.params.clone(),
+                response: receiver,
+                completed_at: None,
+            },
+        );
+        session.order.push_back(key.to_string());
+        while session.requests.len() > self.config.max_tracked {
+            match session.order.pop_front() {
+                Some(oldest) => {
+                    session.requests.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        session
+            .order
+            .retain(|key| session.requests.contains_key(key));
+        Admission::Run(sender)
+    }
+
+    fn complete(
+        &self,
+        scope: &str,
+        key: &str,
+        sender: watch::Sender<Option<JsonRpcResponse>>,
+        response: &JsonRpcResponse,
+    ) {
+        sender.send_replace(Some(response.clone()));
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(tracked) = sessions
+            .sessions
+            .get_mut(scope)
+            .and_then(|session| session.requests.get_mut(key))
+        {
+            tracked.completed_at = Some(self.clock.now());
+        }
+    }
+}
+
+fn reject(request: &JsonRpcRequest, message: &str) -> JsonRpcResponse {
+    let error = McpError::Protocol {
+        message: message.to_string(),
+    };
+    JsonRpcResponse::error(error.into(), request.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn session(id: &str) -> SecurityContext {
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some(id.to_string());
+        context
+    }
+
+    fn call(id: i64, name: &str) -> JsonRpcRequest {
+        JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": name})),
+            Some(serde_json::json!(id)),
+        )
+    }
+
+    async fn send(
+        duplicates: &DuplicateRequests,
+        calls: &AtomicUsize,
+        request: JsonRpcRequest,
+        context: SecurityContext,
+    ) -> JsonRpcResponse {
+        duplicates
+            .handle(request, context, |request, _| async move {
+                let count = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                JsonRpcResponse::success(serde_json::json!(count), request.id)
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_replay_duplicates() {
+        let duplicates = DuplicateRequests::new(DuplicateRequestConfig::replay());
+        let calls = AtomicUsize::new(0);
+
+        // A retry sent while the original is still running waits for it
+        let (first, retry) = tokio::join!(
+            send(&duplicates, &calls, call(1, "charge"), session("a")),
+            send(&duplicates, &calls, call(1, "charge"), session("a")),
+        );
+        assert_eq!(first.result, Some(serde_json::json!(1)));
+        assert_eq!(retry, first);
+        let later = send(&duplicates, &calls, call(1, "charge"), session("a")).await;
+        assert_eq!(later, first);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Other sessions and other IDs are independent
+        send(&duplicates, &calls, call(1, "charge"), session("b")).await;
+        send(&duplicates, &calls, call(2, "charge"), session("a")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let reused = send(&duplicates, &calls, call(1, "refund"), session("a")).await;
+        assert_eq!(reused.error.unwrap().code, -32600);
+
+        duplicates.remove("a");
+        send(&duplicates, &calls, call(1, "charge"), session("a")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_requests_outside_sessions_are_not_tracked() {
+        let duplicates = DuplicateRequests::new(DuplicateRequestConfig::reject());
+        let calls = AtomicUsize::new(0);
+        let mut connection = SecurityContext::system();
+        connection.client.client_id = Some("shared-key".to_string());
+
+        send(&duplicates, &calls, call(1, "charge"), connection.clone()).await;
+        let other = send(&duplicates, &calls, call(1, "charge"), connection).await;
+        assert!(other.error.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(duplicates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reject_and_expire_duplicates() {
+        let clock = Clock::manual();
+        let duplicates = DuplicateRequests::new(
+            DuplicateRequestConfig::reject().with_window(Duration::from_millis(50)),
+        )
+        .with_clock(clock.clone());
+        let calls = AtomicUsize::new(0);
+
+        send(&duplicates, &calls, call(1, "charge"), session("a")).await;
+        let retry = send(&duplicates, &calls, call(1, "charge"), session("a")).await;
+        assert_eq!(retry.error.unwrap().message, "Duplicate request ID");
+        send(&duplicates, &calls, call(1, "charge"), session("b")).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        clock.advance(Duration::from_millis(60));
+        let retry = send(&duplicates, &calls, call(1, "charge"), session("a")).await;
+        assert!(retry.error.is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        // The idle session was swept along the way
+        assert_eq!(duplicates.len(), 1);
+    }
+}
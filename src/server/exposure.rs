@@ -0,0 +1,205 @@
+//! Tool exposure profiles
+//!
+//! Tools, prompts and resources can be assigned a named exposure profile,
+//! such as [`PUBLIC`], [`INTERNAL`] or [`ADMIN`], and each listener exposes a
+//! chosen set of profiles: a Unix domain socket for operators might expose
+//! all of them while the public HTTPS listener only exposes `public`. Items
+//! without a profile are `public`.
+//!
+//! Listeners are identified by name. A router can be given one with
+//! [`name_listener`]; otherwise the transport recorded in the client metadata
+//! is used (`unix`, `streamable_http`, `http`, `sse`, `nats`). The
+//! [`ExposureConfig`] maps listener names to profiles, and the server records
+//! the profiles of the listener a request arrived on in its
+//! [`SecurityContext`], where the registries check them: items outside the
+//! listener's profiles are left out of listings and reported as not found.
+//!
+//! Exposure is off, and every item visible, until a listener is configured.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{
+    security::{auth::EXPOSURE_KEY, fingerprint::TRANSPORT_KEY, SecurityContext},
+    server::{prompt::Prompt, registry::McpTool, resource::Resource},
+};
+
+/// Profile of items meant for any client
+pub const PUBLIC: &str = "public";
+
+/// Profile of items meant for trusted networks
+pub const INTERNAL: &str = "internal";
+
+/// Profile of items meant for operators
+pub const ADMIN: &str = "admin";
+
+/// Metadata key holding the exposure profile of a tool, prompt or resource
+pub const EXPOSURE_META: &str = "exposure";
+
+/// Client metadata key for the name of the listener a request arrived on
+pub const LISTENER_KEY: &str = "listener";
+
+/// Which exposure profiles each listener serves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExposureConfig {
+    /// Profiles exposed by each listener, by listener name
+    pub listeners: HashMap<String, Vec<String>>,
+
+    /// Profiles exposed by listeners not named in `listeners`
+    pub default_profiles: Vec<String>,
+}
+
+impl Default for ExposureConfig {
+    fn default() -> Self {
+        Self {
+            listeners: HashMap::new(),
+            default_profiles: vec![PUBLIC.to_string()],
+        }
+    }
+}
+
+impl ExposureConfig {
+    /// Expose `profiles` on the listener named `listener`
+    pub fn with_listener<I, P>(mut self, listener: impl Into<String>, profiles: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.listeners.insert(
+            listener.into(),
+            profiles.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Whether any listener is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.listeners.is_empty()
+    }
+
+    /// Profiles exposed by the listener named `listener`
+    pub fn profiles_for(&self, listener: &str) -> &[String] {
+        self.listeners
+            .get(listener)
+            .unwrap_or(&self.default_profiles)
+    }
+
+    /// Record the profiles of the request's listener in `context`
+    ///
+    /// Contexts that already carry profiles, and system contexts, are left
+    /// unchanged.
+    pub fn apply(&self, context: &mut SecurityContext) {
+        if !self.is_enabled()
+            || context.is_system()
+            || context.client.metadata.contains_key(EXPOSURE_KEY)
+        {
+            return;
+        }
+        let metadata = &context.client.metadata;
+        let listener = metadata
+            .get(LISTENER_KEY)
+            .or_else(|| metadata.get(TRANSPORT_KEY))
+            .map(String::as_str)
+            .unwrap_or_default();
+        let profiles = self.profiles_for(listener).join(",");
+        context
+            .client
+            .metadata
+            .insert(EXPOSURE_KEY.to_string(), profiles);
+    }
+}
+
+/// Exposure profile recorded in item metadata, `public` if none is
+pub fn profile_of<'a>(metadata: impl IntoIterator<Item = (&'a String, &'a Value)>) -> &'a str {
+    metadata
+        .into_iter()
+        .find(|(key, _)| *key == EXPOSURE_META)
+        .and_then(|(_, profile)| profile.as_str())
+        .unwrap_or(PUBLIC)
+}
+
+/// Name of the listener a router is served on, set by [`name_listener`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerName(pub String);
+
+/// Name the listener `router` is served on, for [`ExposureConfig`]
+#[cfg(feature = "axum")]
+pub fn name_listener(router: axum::Router, name: impl Into<String>) -> axum::Router {
+    router.layer(axum::Extension(ListenerName(name.into())))
+}
+
+impl McpTool {
+    /// Only expose this tool on listeners serving `profile`
+    pub fn with_exposure(mut self, profile: impl Into<String>) -> Self {
+        self.tool
+            .metadata
+            .insert(EXPOSURE_META.to_string(), Value::String(profile.into()));
+        self
+    }
+
+    /// Exposure profile of this tool
+    pub fn exposure(&self) -> &str {
+        profile_of(&self.tool.metadata)
+    }
+}
+
+impl Prompt {
+    /// Only expose this prompt on listeners serving `profile`
+    pub fn with_exposure(mut self, profile: impl Into<String>) -> Self {
+        self.metadata
+            .insert(EXPOSURE_META.to_string(), Value::String(profile.into()));
+        self
+    }
+}
+
+impl Resource {
+    /// Only expose this resource on listeners serving `profile`
+    pub fn with_exposure(mut self, profile: impl Into<String>) -> Self {
+        self.metadata
+            .insert(EXPOSURE_META.to_string(), Value::String(profile.into()));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::ClientContext;
+
+    fn context(transport: &str) -> SecurityContext {
+        let mut client = ClientContext::default();
+        client
+            .metadata
+            .insert(TRANSPORT_KEY.to_string(), transport.to_string());
+        SecurityContext::authenticated(client, Vec::new())
+    }
+
+    #[test]
+    fn test_profiles_per_listener() {
+        let exposure = ExposureConfig::default().with_listener("unix", [PUBLIC, INTERNAL, ADMIN]);
+
+        let mut local = context("unix");
+        exposure.apply(&mut local);
+        assert!(local.exposes(ADMIN));
+
+        let mut remote = context("streamable_http");
+        exposure.apply(&mut remote);
+        assert!(remote.exposes(PUBLIC));
+        assert!(!remote.exposes(INTERNAL));
+
+        // Named listeners take precedence over the transport
+        let mut named = context("streamable_http");
+        named
+            .client
+            .metadata
+            .insert(LISTENER_KEY.to_string(), "unix".to_string());
+        exposure.apply(&mut named);
+        assert!(named.exposes(INTERNAL));
+
+        let mut unconfigured = context("streamable_http");
+        ExposureConfig::default().apply(&mut unconfigured);
+        assert!(unconfigured.exposes(ADMIN));
+    }
+}
@@ -0,0 +1,118 @@
+//! Axum extractors for sharing MCP server state with regular HTTP handlers
+//!
+//! These extractors let applications mount ordinary REST endpoints next to the
+//! MCP endpoints and reuse the same server state, session and security data.
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+use std::{convert::Infallible, ops::Deref};
+
+use crate::{
+    security::SecurityContext,
+    server::{
+        handler::{request_security_context, McpHandlerState},
+        service::McpServer,
+        McpServerState,
+    },
+};
+
+pub use crate::transport::streamable_http::MCP_SESSION_HEADER;
+
+/// Extractor that provides the MCP server behind the router state
+///
+/// Works with any router state implementing [`McpHandlerState`], so REST
+/// handlers can reach the same registries and configuration as MCP requests.
+#[derive(Clone)]
+pub struct McpState<S: McpServerState>(pub McpServer<S>);
+
+impl<S: McpServerState> Deref for McpState<S> {
+    type Target = McpServer<S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T, S> FromRequestParts<T> for McpState<S>
+where
+    T: McpHandlerState<ServerState = S>,
+    S: McpServerState,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &T) -> Result<Self, Self::Rejection> {
+        Ok(McpState(state.mcp_server().clone()))
+    }
+}
+
+/// Per-request MCP data derived from the HTTP request
+#[derive(Debug, Clone)]
+pub struct McpRequestParts {
+    /// Security context built by [`request_security_context`]
+    pub security: SecurityContext,
+
+    /// Session ID from the `mcp-session-id` header or `session_id` query parameter
+    pub session_id: Option<String>,
+
+    /// Whether the session is known to the session manager
+    pub session_active: bool,
+}
+
+impl<T> FromRequestParts<T> for McpRequestParts
+where
+    T: McpHandlerState,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &T) -> Result<Self, Self::Rejection> {
+        let mut security = request_security_context(state, &parts.headers, &parts.extensions);
+
+        let session_id = parts
+            .headers
+            .get(MCP_SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .or_else(|| session_id_from_query(parts.uri.query()));
+
+        let session_active = match (&session_id, state.session_manager()) {
+            (Some(id), Some(manager)) => manager.get_session(id).await.is_some(),
+            _ => false,
+        };
+
+        if security.client.session_id.is_none() {
+            security.client.session_id = session_id.clone();
+        }
+
+        Ok(Self {
+            security,
+            session_id,
+            session_active,
+        })
+    }
+}
+
+/// Extract the `session_id` parameter from a raw query string
+fn session_id_from_query(query: Option<&str>) -> Option<String> {
+    query?.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == "session_id").then(|| {
+            urlencoding::decode(value)
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| value.to_string())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_id_from_query() {
+        assert_eq!(
+            session_id_from_query(Some("transport=sse&session_id=abc%2D1")),
+            Some("abc-1".to_string())
+        );
+        assert_eq!(session_id_from_query(Some("transport=sse")), None);
+        assert_eq!(session_id_from_query(None), None);
+    }
+}
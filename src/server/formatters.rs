@@ -0,0 +1,347 @@
+//! Post-processing of tool results
+//!
+//! A [`ResultFormatter`] rewrites a tool result before it is sent, such as
+//! rendering JSON rows as a markdown table for clients that only show text.
+//! Formatters are registered on the server by name and chained in the order
+//! [`FormatterConfig`] lists them: `global` for every tool, or the chain in
+//! `tools` for the tools it names, which replaces the global one.
+//!
+//! ```json
+//! {"formatters": {"global": ["pretty-json"], "tools": {"sales_report": ["markdown-table", "citations"]}}}
+//! ```
+//!
+//! Built in are [`MarkdownTables`] (`markdown-table`), [`PrettyJson`]
+//! (`pretty-json`) and [`Citations`] (`citations`). Results are formatted
+//! after serialization and before scrubbing and token budgets.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracing::debug;
+
+use crate::{
+    error::McpResult,
+    protocol::{ToolContent, ToolsCallResult},
+    security::SecurityContext,
+};
+
+/// Result metadata key listing the resources a result cites
+pub const CITATIONS_META: &str = "citations";
+
+/// Which formatters run on tool results
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatterConfig {
+    /// Formatters applied to the results of every tool, in order
+    pub global: Vec<String>,
+
+    /// Formatters applied to the results of the named tools instead
+    pub tools: BTreeMap<String, Vec<String>>,
+}
+
+impl FormatterConfig {
+    /// Apply `formatters` to the results of every tool
+    pub fn with_global(mut self, formatters: &[&str]) -> Self {
+        self.global = formatters.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    /// Apply `formatters` to the results of `tool` instead of the global ones
+    pub fn with_tool(mut self, tool: impl Into<String>, formatters: &[&str]) -> Self {
+        self.tools.insert(
+            tool.into(),
+            formatters.iter().map(|name| name.to_string()).collect(),
+        );
+        self
+    }
+
+    /// Names of the formatters for the results of `tool`
+    pub fn chain_for(&self, tool: &str) -> &[String] {
+        self.tools.get(tool).unwrap_or(&self.global)
+    }
+}
+
+/// Rewrites tool results before they are sent
+#[async_trait]
+pub trait ResultFormatter: Send + Sync {
+    /// Name the formatter is configured by
+    fn name(&self) -> &str;
+
+    /// Rewrite the `result` of `tool`, called by `context`
+    async fn format(
+        &self,
+        tool: &str,
+        result: &mut ToolsCallResult,
+        context: &SecurityContext,
+    ) -> McpResult<()>;
+}
+
+/// Formatters available to tool results, by name
+#[derive(Clone)]
+pub struct ResultFormatters {
+    formatters: HashMap<String, Arc<dyn ResultFormatter>>,
+    config: FormatterConfig,
+}
+
+impl ResultFormatters {
+    /// The built-in formatters, chained as `config` says
+    pub fn new(config: FormatterConfig) -> Self {
+        Self {
+            formatters: HashMap::new(),
+            config,
+        }
+        .with_formatter(Arc::new(MarkdownTables))
+        .with_formatter(Arc::new(PrettyJson))
+        .with_formatter(Arc::new(Citations))
+    }
+
+    /// Register a formatter, replacing one of the same name
+    pub fn with_formatter(mut self, formatter: Arc<dyn ResultFormatter>) -> Self {
+        self.formatters
+            .insert(formatter.name().to_string(), formatter);
+        self
+    }
+
+    /// Configured formatter names that no formatter is registered under
+    pub fn unknown(&self) -> Vec<&str> {
+        let mut unknown: Vec<&str> = self
+            .config
+            .global
+            .iter()
+            .chain(self.config.tools.values().flatten())
+            .map(String::as_str)
+            .filter(|name| !self.formatters.contains_key(*name))
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        unknown
+    }
+
+    /// Run the chain of `tool` on its `result`
+    ///
+    /// Unknown formatters are skipped, and a formatter that fails leaves the
+    /// result as the previous ones made it.
+    pub async fn apply(&self, tool: &str, result: &mut ToolsCallResult, context: &SecurityContext) {
+        for name in self.config.chain_for(tool) {
+            let Some(formatter) = self.formatters.get(name) else {
+                debug!("Unknown result formatter '{}'", name);
+                continue;
+            };
+            let mut formatted = result.clone();
+            match formatter.format(tool, &mut formatted, context).await {
+                Ok(()) => *result = formatted,
+                Err(error) => debug!("Formatter '{}' failed on {}: {}", name, tool, error),
+            }
+        }
+    }
+}
+
+impl Default for ResultFormatters {
+    fn default() -> Self {
+        Self::new(FormatterConfig::default())
+    }
+}
+
+/// Text content that is JSON, with its parsed value
+fn json_texts(result: &mut ToolsCallResult) -> impl Iterator<Item = (&mut String, Value)> {
+    result
+        .content
+        .iter_mut()
+        .filter_map(|content| match content {
+            ToolContent::Text { text } => {
+                let value = serde_json::from_str::<Value>(text).ok()?;
+                Some((text, value))
+            }
+            _ => None,
+        })
+}
+
+/// Renders JSON arrays of objects as markdown tables, one row per object
+///
+/// Columns are the keys of all rows, in the order they first appear.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownTables;
+
+#[async_trait]
+impl ResultFormatter for MarkdownTables {
+    fn name(&self) -> &str {
+        "markdown-table"
+    }
+
+    async fn format(
+        &self,
+        _tool: &str,
+        result: &mut ToolsCallResult,
+        _context: &SecurityContext,
+    ) -> McpResult<()> {
+        for (text, value) in json_texts(result) {
+            if let Some(table) = markdown_table(&value) {
+                *text = table;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `rows` as a markdown table, if it is a non-empty array of objects
+pub fn markdown_table(rows: &Value) -> Option<String> {
+    let rows = rows.as_array().filter(|rows| !rows.is_empty())?;
+    let mut columns: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.as_object()?.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+    let cell = |value: Option<&Value>| match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+    let escape = |text: String| text.replace('|', "\\|").replace('\n', " ");
+
+    let mut table = format!("| {} |\n", columns.join(" | "));
+    table.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for row in rows {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| escape(cell(row.get(*column))))
+            .collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    Some(table)
+}
+
+/// Pretty-prints text content that is JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrettyJson;
+
+#[async_trait]
+impl ResultFormatter for PrettyJson {
+    fn name(&self) -> &str {
+        "pretty-json"
+    }
+
+    async fn format(
+        &self,
+        _tool: &str,
+        result: &mut ToolsCallResult,
+        _context: &SecurityContext,
+    ) -> McpResult<()> {
+        for (text, value) in json_texts(result) {
+            if value.is_object() || value.is_array() {
+                *text = serde_json::to_string_pretty(&value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cites the resources a result embeds or links
+///
+/// Appends a numbered list of their URIs as text content and lists them
+/// under [`CITATIONS_META`] in the result metadata.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Citations;
+
+#[async_trait]
+impl ResultFormatter for Citations {
+    fn name(&self) -> &str {
+        "citations"
+    }
+
+    async fn format(
+        &self,
+        _tool: &str,
+        result: &mut ToolsCallResult,
+        _context: &SecurityContext,
+    ) -> McpResult<()> {
+        let mut uris: Vec<&str> = Vec::new();
+        for content in &result.content {
+            if let ToolContent::Resource { resource, .. } = content {
+                if !uris.contains(&resource.uri.as_str()) {
+                    uris.push(&resource.uri);
+                }
+            }
+        }
+        if uris.is_empty() {
+            return Ok(());
+        }
+
+        let mut sources = String::from("Sources:");
+        for (index, uri) in uris.iter().enumerate() {
+            sources.push_str(&format!("\n[{}] {}", index + 1, uri));
+        }
+        let citations: Vec<Value> = uris
+            .iter()
+            .enumerate()
+            .map(|(index, uri)| serde_json::json!({"index": index + 1, "uri": uri}))
+            .collect();
+        result
+            .metadata
+            .insert(CITATIONS_META.to_string(), Value::Array(citations));
+        result.content.push(ToolContent::Text { text: sources });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::messages::ResourceReference;
+
+    fn result(content: Vec<ToolContent>) -> ToolsCallResult {
+        ToolsCallResult {
+            content,
+            is_error: false,
+            metadata: Default::default(),
+        }
+    }
+
+    fn text(text: &str) -> ToolContent {
+        ToolContent::Text {
+            text: text.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_formatter_chains() {
+        let formatters = ResultFormatters::new(
+            FormatterConfig::default()
+                .with_global(&["pretty-json"])
+                .with_tool("sales", &["markdown-table", "citations", "shout"]),
+        );
+        assert_eq!(formatters.unknown(), vec!["shout"]);
+        let context = SecurityContext::system();
+
+        let mut sales = result(vec![
+            text(r#"[{"month": "May", "total": 12}, {"month": "June", "note": "a|b"}]"#),
+            ToolContent::Resource {
+                resource: ResourceReference {
+                    uri: "db://sales/2026".to_string(),
+                },
+                text: None,
+                blob: None,
+            },
+        ]);
+        formatters.apply("sales", &mut sales, &context).await;
+        assert_eq!(
+            sales.content[0],
+            text("| month | total | note |\n| --- | --- | --- |\n| May | 12 |  |\n| June |  | a\\|b |\n")
+        );
+        assert_eq!(sales.content[2], text("Sources:\n[1] db://sales/2026"));
+        assert_eq!(sales.metadata[CITATIONS_META][0]["uri"], "db://sales/2026");
+
+        let mut other = result(vec![text(r#"{"ok":true}"#), text("plain")]);
+        formatters.apply("other", &mut other, &context).await;
+        assert_eq!(
+            other.content,
+            vec![text("{\n  \"ok\": true\n}"), text("plain")]
+        );
+    }
+}
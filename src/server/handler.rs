@@ -1,8 +1,9 @@
 //! Axum HTTP handlers for MCP endpoints
 
 use axum::{
-    extract::{Query, State},
-    http::{HeaderMap, StatusCode},
+    body::Bytes,
+    extract::{ConnectInfo, OriginalUri, Query, State},
+    http::{header, Extensions, HeaderMap, HeaderValue, StatusCode},
     response::{
         sse::{Event, Sse},
         IntoResponse, Response,
@@ -11,21 +12,49 @@ use axum::{
 };
 use futures_util::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::Duration,
+};
+use tokio::sync::{broadcast::error::RecvError, oneshot};
 use tokio_stream::wrappers::BroadcastStream;
+#[cfg(feature = "sse")]
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::{debug, error, info, warn};
-use uuid::Uuid;
+
+use super::{extract::MCP_SESSION_HEADER, progress::ProgressUpdate};
 
 use crate::{
-    protocol::JsonRpcRequest,
-    security::{ClientContext, SecurityContext},
-    server::{service::McpServer, McpServerState},
+    ids::{IdKind, Ids},
+    protocol::{
+        InitializeParams, JsonRpcError, JsonRpcRequest, JsonRpcResponse, McpMethod,
+        McpNotification, ProgressNotification,
+    },
+    security::{
+        fingerprint::{REMOTE_ADDR_KEY, REMOTE_IP_KEY, TRANSPORT_KEY},
+        ClientContext, ClientFingerprint, SecurityContext,
+    },
+    server::{
+        affinity::session_owner,
+        exposure::{ListenerName, LISTENER_KEY},
+        json_body::parse_json_body,
+        notification_filter::{NotificationCategory, NotificationFilter, NOTIFICATIONS_FILTER},
+        prompt_stream::STREAM_META,
+        service::McpServer,
+        sse_encoder::{sse_response, SseEncoder},
+        webhooks::WebhookEventKind,
+        McpServerState,
+    },
     transport::{
-        streamable_http::{McpEvent, SessionManager},
+        outbound::client_response_value,
+        streamable_http::{McpEvent, SessionManager, SESSION_CLOSED_EVENT},
         TransportHealth,
     },
 };
 
+pub use super::config::DEFAULT_BASE_PATH;
+
 /// Handler state for MCP endpoints
 pub trait McpHandlerState: Send + Sync + Clone + 'static {
     /// Server state implementation
@@ -55,6 +84,7 @@ pub trait McpHandlerState: Send + Sync + Clone + 'static {
             client_id: None,
             session_id: None,
             metadata: HashMap::new(),
+            fingerprint: None,
         };
 
         // For now, create a basic authenticated context
@@ -63,10 +93,62 @@ pub trait McpHandlerState: Send + Sync + Clone + 'static {
     }
 }
 
+/// Build the security context for a request
+///
+/// Starts from a [`SecurityContext`] left in the request extensions by
+/// middleware, such as the per-listener authentication of a
+/// [`TransportSet`](crate::server::TransportSet), or else from
+/// [`McpHandlerState::create_security_context`], and adds transport-level
+/// information recorded on the request, such as Unix domain socket peer
+/// credentials.
+pub fn request_security_context<S>(
+    state: &S,
+    headers: &HeaderMap,
+    extensions: &Extensions,
+) -> SecurityContext
+where
+    S: McpHandlerState,
+{
+    let mut security_context = match extensions.get::<SecurityContext>() {
+        Some(context) => context.clone(),
+        None => state.create_security_context(headers),
+    };
+
+    if let Some(ConnectInfo(addr)) = extensions.get::<ConnectInfo<SocketAddr>>() {
+        security_context
+            .client
+            .metadata
+            .insert(REMOTE_ADDR_KEY.to_string(), addr.to_string());
+        security_context
+            .client
+            .metadata
+            .insert(REMOTE_IP_KEY.to_string(), addr.ip().to_string());
+    }
+
+    if let Some(ListenerName(listener)) = extensions.get::<ListenerName>() {
+        security_context
+            .client
+            .metadata
+            .insert(LISTENER_KEY.to_string(), listener.clone());
+    }
+
+    #[cfg(unix)]
+    if let Some(peer) = crate::server::uds::PeerCredentials::from_extensions(extensions) {
+        peer.apply_to(&mut security_context.client);
+        security_context
+            .client
+            .metadata
+            .insert(TRANSPORT_KEY.to_string(), "unix".to_string());
+    }
+
+    security_context
+}
+
 /// Query parameters for MCP endpoints
 #[derive(Debug, Deserialize)]
 pub struct McpQueryParams {
-    /// Session ID for resumable sessions
+    /// Session ID for resumable sessions (legacy clients send `sessionId`)
+    #[serde(alias = "sessionId")]
     pub session_id: Option<String>,
     /// Last event ID for SSE resumption
     pub last_event_id: Option<String>,
@@ -136,11 +218,18 @@ pub async fn mcp_post_handler<S>(
     State(state): State<S>,
     Query(params): Query<McpQueryParams>,
     headers: HeaderMap,
-    Json(request): Json<JsonRpcRequest>,
+    body: Bytes,
 ) -> impl IntoResponse
 where
     S: McpHandlerState,
 {
+    let request = match parse_post_body(&body) {
+        Ok(PostBody::Request(request)) => request,
+        Ok(PostBody::Response(response)) => {
+            return accept_client_response(&state, &params, &headers, response)
+        }
+        Err(response) => return invalid_body_response(*response),
+    };
     debug!(
         "MCP POST request: {} (id: {:?})",
         request.method, request.id
@@ -149,11 +238,564 @@ where
     // Create security context from headers
     let security_context = state.create_security_context(&headers);
 
-    // Handle the request
-    let response: crate::protocol::JsonRpcResponse = state
+    handle_post(state, params, security_context, request, &headers).await
+}
+
+/// A decoded POST body
+enum PostBody {
+    /// A request to handle
+    Request(JsonRpcRequest),
+    /// A client's answer to a server-to-client request
+    Response(JsonRpcResponse),
+}
+
+/// Decode a POST body that is either a JSON-RPC request or a client's response
+fn parse_post_body(body: &[u8]) -> Result<PostBody, Box<JsonRpcResponse>> {
+    let value = decode_body(body)?;
+    if let Some(response) = client_response_value(&value) {
+        return Ok(PostBody::Response(response));
+    }
+    request_from_value(value).map(PostBody::Request)
+}
+
+/// Decode a JSON-RPC request body
+///
+/// Bodies that are not JSON yield a parse error (`-32700`), JSON that is not
+/// a single request object an invalid request error (`-32600`).
+#[cfg(feature = "sse")]
+fn parse_request_body(body: &[u8]) -> Result<JsonRpcRequest, Box<JsonRpcResponse>> {
+    request_from_value(decode_body(body)?)
+}
+
+/// Parse a body as JSON, see [`parse_json_body`]
+fn decode_body(body: &[u8]) -> Result<serde_json::Value, Box<JsonRpcResponse>> {
+    parse_json_body(body).map_err(|e| {
+        let error = JsonRpcError::parse_error(Some(serde_json::json!(e.to_string())));
+        Box::new(JsonRpcResponse::error(error, None))
+    })
+}
+
+fn request_from_value(value: serde_json::Value) -> Result<JsonRpcRequest, Box<JsonRpcResponse>> {
+    let id = value
+        .get("id")
+        .filter(|id| id.is_string() || id.is_number())
+        .cloned();
+    serde_json::from_value(value).map_err(|e| {
+        let error = JsonRpcError::invalid_request(Some(serde_json::json!(e.to_string())));
+        Box::new(JsonRpcResponse::error(error, id))
+    })
+}
+
+/// Hand a client's answer to a server-to-client request to whoever awaits it
+///
+/// Answered with `202 Accepted`, also for responses nobody waits for anymore,
+/// such as late answers to requests that timed out.
+fn accept_client_response<S>(
+    state: &S,
+    params: &McpQueryParams,
+    headers: &HeaderMap,
+    response: JsonRpcResponse,
+) -> Response
+where
+    S: McpHandlerState,
+{
+    let session_id = params
+        .session_id
+        .clone()
+        .or_else(|| session_id_header(headers));
+    let (Some(session_manager), Some(session_id)) = (state.session_manager(), session_id) else {
+        let error = JsonRpcError::invalid_request(Some(serde_json::json!(
+            "Responses are only accepted within a session"
+        )));
+        return invalid_body_response(JsonRpcResponse::error(error, response.id));
+    };
+    if !session_manager.complete_client_request(&session_id, response.clone()) {
+        debug!(
+            "No pending request {:?} for session {}",
+            response.id, session_id
+        );
+    }
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// `400 Bad Request` carrying the JSON-RPC error for an undecodable body
+fn invalid_body_response(response: JsonRpcResponse) -> Response {
+    (StatusCode::BAD_REQUEST, Json(response)).into_response()
+}
+
+/// Answer a JSON-RPC POST, issuing and resuming StreamableHTTP sessions
+///
+/// With a session manager, a successful `initialize` without a session ID
+/// creates a session and returns its ID in the `Mcp-Session-Id` header. Later
+/// requests carrying that ID resume the session; unknown or expired sessions
+/// get `404 Not Found` so the client knows to initialize again.
+async fn handle_post<S>(
+    state: S,
+    mut params: McpQueryParams,
+    security_context: SecurityContext,
+    request: JsonRpcRequest,
+    headers: &HeaderMap,
+) -> Response
+where
+    S: McpHandlerState,
+{
+    if params.session_id.is_none() {
+        params.session_id = session_id_header(headers);
+    }
+
+    let mut issued_session = None;
+    if let Some(session_manager) = state.session_manager() {
+        match &params.session_id {
+            Some(session_id) => {
+                if let Some(response) = misdirected(&state, session_id, request.id.clone()) {
+                    return response;
+                }
+                if let Err(e) = session_manager.resume_session(session_id).await {
+                    debug!("Rejecting request: {}", e);
+                    return session_not_found_response(request.id);
+                }
+            }
+            None if request.method == "initialize" => {
+                match session_manager.create_session().await {
+                    Ok(session_id) => {
+                        state.mcp_server().webhooks().emit(
+                            WebhookEventKind::SessionOpened,
+                            serde_json::json!({ "sessionId": session_id }),
+                        );
+                        params.session_id = Some(session_id.clone());
+                        issued_session = Some(session_id);
+                    }
+                    Err(e) => {
+                        error!("Failed to create session: {}", e);
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "Failed to create session",
+                        )
+                            .into_response();
+                    }
+                }
+            }
+            None => {}
+        }
+    }
+
+    let affinity = state.mcp_server().config().affinity.clone();
+    let cookie_path = normalize_base_path(&state.mcp_server().config().base_path);
+    let session_id = params
+        .session_id
+        .clone()
+        .filter(|_| issued_session.is_some() || affinity.echo_session_header);
+    let accepts_sse = accepts_event_stream(headers);
+    let mut response = respond_post(state, params, security_context, request, accepts_sse).await;
+    if let Some(session_id) = session_id {
+        if let Ok(value) = HeaderValue::from_str(&session_id) {
+            response.headers_mut().insert(MCP_SESSION_HEADER, value);
+        }
+    }
+    if let Some(cookie) =
+        issued_session.and_then(|session_id| affinity.session_cookie(&session_id, &cookie_path))
+    {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+/// Response turning away a request for a session another node owns
+///
+/// Only when the affinity config enforces ownership and the session manager
+/// has a node ID.
+fn misdirected<S>(state: &S, session_id: &str, id: Option<serde_json::Value>) -> Option<Response>
+where
+    S: McpHandlerState,
+{
+    let affinity = &state.mcp_server().config().affinity;
+    if !affinity.enforce_ownership {
+        return None;
+    }
+    let node_id = state.session_manager()?.node_id()?;
+    let owner = session_owner(session_id)?;
+    (owner != node_id).then(|| {
+        debug!("Session {} is owned by node {}", session_id, owner);
+        affinity.misdirected_response(owner, id)
+    })
+}
+
+/// `404 Not Found` with a JSON-RPC error for an unknown or expired session
+fn session_not_found_response(id: Option<serde_json::Value>) -> Response {
+    let error = JsonRpcError::new(-32000, "Session not found", None);
+    (
+        StatusCode::NOT_FOUND,
+        Json(JsonRpcResponse::error(error, id)),
+    )
+        .into_response()
+}
+
+/// Session ID sent in the `Mcp-Session-Id` header
+fn session_id_header(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(MCP_SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+/// Answer a JSON-RPC POST, streaming long tool calls as SSE when the client accepts it
+///
+/// A `tools/call` that is still running after
+/// [`McpServerConfig::sse_response_threshold`](crate::server::McpServerConfig::sse_response_threshold)
+/// switches the response to `text/event-stream`: progress updates are sent as
+/// `notifications/progress` messages, followed by the final response.
+async fn respond_post<S>(
+    state: S,
+    params: McpQueryParams,
+    security_context: SecurityContext,
+    mut request: JsonRpcRequest,
+    accepts_sse: bool,
+) -> Response
+where
+    S: McpHandlerState,
+{
+    if accepts_sse && request.method == "prompts/get" && asks_for_stream(&request) {
+        return stream_prompt(state, params, security_context, request);
+    }
+
+    let threshold = state.mcp_server().config().sse_response_threshold;
+    let Some(threshold) = threshold.filter(|_| accepts_sse && request.method == "tools/call")
+    else {
+        let http_status = state.mcp_server().config().http_status.clone();
+        return http_status.respond(dispatch_post(state, params, security_context, request).await);
+    };
+
+    let http_status = state.mcp_server().config().http_status.clone();
+    let progress_token = ensure_progress_token(&mut request, state.mcp_server().ids());
+    let wants_progress = match (state.session_manager(), &params.session_id) {
+        (Some(session_manager), Some(session_id)) => session_manager
+            .session_state(session_id)
+            .await
+            .is_none_or(|session_state| {
+                session_state
+                    .notification_filter
+                    .allows(Some(NotificationCategory::Progress))
+            }),
+        _ => true,
+    };
+    let mut progress = state.mcp_server().progress_reporter().subscribe();
+    let (response_tx, mut response_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let response = dispatch_post(state, params, security_context, request).await;
+        let _ = response_tx.send(response);
+    });
+
+    match tokio::time::timeout(threshold, &mut response_rx).await {
+        Ok(Ok(response)) => return http_status.respond(response),
+        Ok(Err(_)) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Request handler failed").into_response()
+        }
+        Err(_) => debug!(
+            "tools/call still running after {:?}, streaming response",
+            threshold
+        ),
+    }
+
+    let token = progress_token
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| progress_token.to_string());
+    let events = async_stream::stream! {
+        loop {
+            tokio::select! {
+                update = progress.recv() => match update {
+                    Ok(update) if wants_progress && update.operation_id == token => {
+                        yield Ok::<Event, std::convert::Infallible>(
+                            progress_notification_event(&progress_token, &update),
+                        );
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => {
+                        if let Ok(response) = (&mut response_rx).await {
+                            yield Ok(message_event(&response));
+                        }
+                        break;
+                    }
+                },
+                response = &mut response_rx => {
+                    if let Ok(response) = response {
+                        yield Ok(message_event(&response));
+                    }
+                    break;
+                }
+            }
+        }
+    };
+
+    Sse::new(events).into_response()
+}
+
+/// Whether a `prompts/get` asks for its messages to be streamed
+fn asks_for_stream(request: &JsonRpcRequest) -> bool {
+    request
+        .params
+        .as_ref()
+        .and_then(|params| params.get("_meta")?.get(STREAM_META)?.as_bool())
+        .unwrap_or(false)
+}
+
+/// Answer a `prompts/get` with the chunks of its messages, then its result
+fn stream_prompt<S>(
+    state: S,
+    params: McpQueryParams,
+    security_context: SecurityContext,
+    mut request: JsonRpcRequest,
+) -> Response
+where
+    S: McpHandlerState,
+{
+    let streams = state.mcp_server().prompt_streams().clone();
+    let (stream_id, mut chunks) = streams.open();
+    if let Some(meta) = request
+        .params
+        .as_mut()
+        .and_then(|params| params.get_mut("_meta"))
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        meta.insert(STREAM_META.to_string(), stream_id.clone().into());
+    }
+    let request_id = request.id.clone().unwrap_or_default();
+
+    let (response_tx, mut response_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let response = dispatch_post(state, params, security_context, request).await;
+        streams.close(&stream_id);
+        let _ = response_tx.send(response);
+    });
+
+    let events = async_stream::stream! {
+        loop {
+            tokio::select! {
+                biased;
+                Some(chunk) = chunks.recv() => {
+                    yield Ok::<Event, std::convert::Infallible>(
+                        message_event(&chunk.notification(&request_id)),
+                    );
+                }
+                response = &mut response_rx => {
+                    while let Ok(chunk) = chunks.try_recv() {
+                        yield Ok(message_event(&chunk.notification(&request_id)));
+                    }
+                    if let Ok(response) = response {
+                        yield Ok(message_event(&response));
+                    }
+                    break;
+                }
+            }
+        }
+    };
+
+    Sse::new(events).into_response()
+}
+
+/// Make sure a request carries `_meta.progressToken`, returning its value
+fn ensure_progress_token(request: &mut JsonRpcRequest, ids: &Ids) -> serde_json::Value {
+    if let Some(token) = request
+        .params
+        .as_ref()
+        .and_then(|params| params.get("_meta")?.get("progressToken"))
+    {
+        return token.clone();
+    }
+
+    let token = serde_json::Value::String(ids.generate(IdKind::Progress));
+    if let Some(serde_json::Value::Object(params)) = &mut request.params {
+        let meta = params
+            .entry("_meta")
+            .or_insert_with(|| serde_json::json!({}));
+        if let serde_json::Value::Object(meta) = meta {
+            meta.insert("progressToken".to_string(), token.clone());
+        }
+    }
+    token
+}
+
+/// SSE `message` event carrying a JSON-RPC message
+fn message_event(message: &impl Serialize) -> Event {
+    Event::default()
+        .event("message")
+        .data(serde_json::to_string(message).unwrap_or_default())
+}
+
+/// SSE event carrying a `notifications/progress` message for a progress update
+fn progress_notification_event(token: &serde_json::Value, update: &ProgressUpdate) -> Event {
+    let notification = ProgressNotification {
+        progress_token: token.clone(),
+        progress: update.current as f64,
+        total: (update.total > 0).then_some(update.total as u64),
+        message: Some(update.message.clone()),
+    };
+    message_event(&McpNotification::new(McpMethod::NotificationsProgress(
+        notification,
+    )))
+}
+
+/// Dispatch a JSON-RPC request with an already built security context
+async fn dispatch_post<S>(
+    state: S,
+    params: McpQueryParams,
+    mut security_context: SecurityContext,
+    request: JsonRpcRequest,
+) -> JsonRpcResponse
+where
+    S: McpHandlerState,
+{
+    let transport = match (&params.session_id, state.session_manager()) {
+        (Some(_), Some(_)) => "streamable_http",
+        _ => "http",
+    };
+    security_context
+        .client
+        .metadata
+        .entry(TRANSPORT_KEY.to_string())
+        .or_insert_with(|| transport.to_string());
+
+    // Attach the fingerprint captured when this session initialized
+    if let (Some(session_manager), Some(session_id)) = (state.session_manager(), &params.session_id)
+    {
+        security_context
+            .client
+            .session_id
+            .get_or_insert_with(|| session_id.clone());
+        if security_context.client.fingerprint.is_none() {
+            security_context.client.fingerprint =
+                session_manager.client_fingerprint(session_id).await;
+        }
+    }
+
+    let init_params = (request.method == "initialize")
+        .then(|| request.params.clone())
+        .flatten()
+        .and_then(|params| serde_json::from_value::<InitializeParams>(params).ok());
+    let client = security_context.client.clone();
+    let subscription = match request.method.as_str() {
+        "resources/subscribe" => Some(true),
+        "resources/unsubscribe" => Some(false),
+        _ => None,
+    }
+    .zip(
+        request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("uri")?.as_str())
+            .map(str::to_string),
+    );
+    let filters_notifications = request.method == NOTIFICATIONS_FILTER;
+
+    // Track the request as pending so a resumed session knows it is still running
+    let session = state.session_manager().zip(params.session_id.as_deref());
+    let pending_id = request.id_as_string();
+    if let (Some((session_manager, session_id)), Some(request_id)) = (session, &pending_id) {
+        let method = request.method.clone();
+        let _ = session_manager
+            .update_session_state(session_id, |session_state| {
+                session_state
+                    .pending_requests
+                    .insert(request_id.clone(), method);
+            })
+            .await;
+    }
+
+    // Handle the request, cancelling it if the session is terminated meanwhile
+    let request_id = request.id.clone();
+    let closed = match session {
+        Some((session_manager, session_id)) => session_manager
+            .get_session(session_id)
+            .await
+            .map(|session| session.closed()),
+        None => None,
+    };
+    let deprecation = state
         .mcp_server()
-        .handle_request(request, security_context)
-        .await;
+        .config()
+        .aliases
+        .deprecation_warning(&request.method);
+    let handled = state.mcp_server().handle_request(request, security_context);
+    let response = match closed {
+        Some(closed) => tokio::select! {
+            response = handled => response,
+            _ = closed => {
+                debug!("Cancelled request {:?}: session terminated", request_id);
+                let error = JsonRpcError::new(-32000, "Session terminated", None);
+                return JsonRpcResponse::error(error, request_id);
+            }
+        },
+        None => handled.await,
+    };
+
+    // Point clients still calling a deprecated alias at its replacement
+    if let (Some((session_manager, session_id)), Some(warning)) = (session, deprecation) {
+        let params = serde_json::to_value(warning).ok();
+        if let Err(e) = session_manager
+            .client(session_id)
+            .notify("notifications/message", params)
+            .await
+        {
+            debug!("Not sending deprecation warning: {}", e);
+        }
+    }
+
+    // Record what the request negotiated so it survives reconnects
+    if let Some((session_manager, session_id)) = session {
+        let protocol_version = response
+            .result
+            .as_ref()
+            .and_then(|result| result.get("protocolVersion")?.as_str())
+            .map(str::to_string);
+        let succeeded = response.error.is_none();
+        let notification_filter = filters_notifications
+            .then(|| response.result.clone())
+            .flatten()
+            .and_then(|result| serde_json::from_value::<NotificationFilter>(result).ok());
+        let _ = session_manager
+            .update_session_state(session_id, |session_state| {
+                if let Some(request_id) = &pending_id {
+                    session_state.pending_requests.remove(request_id);
+                }
+                if !succeeded {
+                    return;
+                }
+                if let Some(init_params) = &init_params {
+                    session_state.protocol_version = protocol_version;
+                    session_state.client_capabilities = Some(init_params.capabilities.clone());
+                    session_state.notification_filter =
+                        NotificationFilter::from_capabilities(&init_params.capabilities);
+                }
+                if let Some(notification_filter) = notification_filter {
+                    session_state.notification_filter = notification_filter;
+                }
+                match subscription {
+                    Some((true, uri)) => {
+                        session_state.subscriptions.insert(uri);
+                    }
+                    Some((false, uri)) => {
+                        session_state.subscriptions.remove(&uri);
+                    }
+                    None => {}
+                }
+            })
+            .await;
+    }
+
+    // Remember who initialized the session for later requests
+    if let (Some(session_manager), Some(session_id), Some(init_params)) =
+        (state.session_manager(), &params.session_id, init_params)
+    {
+        if response.error.is_none() {
+            let fingerprint = ClientFingerprint::from_initialize(&init_params, &client);
+            if let Err(e) = session_manager
+                .set_client_fingerprint(session_id, fingerprint)
+                .await
+            {
+                debug!("Not recording client fingerprint: {}", e);
+            }
+        }
+    }
 
     // For StreamableHTTP transport, store the response as an event
     if let Some(session_manager) = state.session_manager() {
@@ -170,39 +812,179 @@ where
         }
     }
 
-    Json(response).into_response()
+    response
 }
 
 /// Handle SSE endpoint for streaming responses
+///
+/// Clients presenting a StreamableHTTP session (via `session_id` or
+/// `mcp-session-id`) resume the session's event stream, or get `404 Not
+/// Found` if it is unknown or expired. Any other client is treated as a legacy HTTP+SSE client: it receives an
+/// `endpoint` event naming the message URL and its responses as `message`
+/// events.
 pub async fn mcp_sse_handler<S>(
     State(state): State<S>,
     Query(params): Query<McpQueryParams>,
     headers: HeaderMap,
 ) -> impl IntoResponse
+where
+    S: McpHandlerState,
+{
+    let messages_path = messages_path_for(&state.mcp_server().config().base_path);
+    handle_sse(state, params, headers, messages_path).await
+}
+
+/// Choose between the StreamableHTTP and legacy HTTP+SSE styles
+async fn handle_sse<S>(
+    state: S,
+    mut params: McpQueryParams,
+    headers: HeaderMap,
+    messages_path: String,
+) -> Response
 where
     S: McpHandlerState,
 {
     debug!("MCP SSE request with params: {:?}", params);
 
-    // Detect if this is Claude Desktop by checking user-agent
-    let user_agent = headers
-        .get("user-agent")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
+    if params.session_id.is_none() {
+        params.session_id = session_id_header(&headers);
+    }
+    if params.last_event_id.is_none() {
+        params.last_event_id = headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+    }
 
-    let is_claude_desktop = user_agent.contains("Claude");
+    let is_streamable = state.session_manager().is_some() && params.session_id.is_some();
 
-    if is_claude_desktop {
-        info!("Claude Desktop client detected, using StreamableHTTP transport");
+    if params.transport.as_deref() == Some("progress") {
+        handle_progress_sse(state)
+    } else if is_streamable {
+        info!("StreamableHTTP client detected, resuming session stream");
         handle_streamable_http_sse(state, params, headers).await
     } else {
-        info!("Standard SSE client detected");
-        handle_standard_sse(state, params, headers).await
+        #[cfg(feature = "sse")]
+        {
+            info!("Legacy HTTP+SSE client detected");
+            handle_legacy_sse(state, messages_path)
+        }
+        #[cfg(not(feature = "sse"))]
+        {
+            let _ = messages_path;
+            (
+                StatusCode::BAD_REQUEST,
+                "Legacy HTTP+SSE requires the `sse` feature",
+            )
+                .into_response()
+        }
     }
 }
 
-/// Handle standard SSE streaming
-async fn handle_standard_sse<S>(state: S, _params: McpQueryParams, _headers: HeaderMap) -> Response
+/// Message endpoint path paired with an SSE endpoint under `base_path`
+fn messages_path_for(base_path: &str) -> String {
+    let base_path = normalize_base_path(base_path);
+    let base_path = base_path.strip_suffix("/sse").unwrap_or(&base_path);
+    format!("{}/messages", base_path.trim_end_matches('/'))
+}
+
+/// Handle a legacy HTTP+SSE stream: announce the message endpoint, then relay responses
+#[cfg(feature = "sse")]
+fn handle_legacy_sse<S>(state: S, messages_path: String) -> Response
+where
+    S: McpHandlerState,
+{
+    let (session, receiver) = state.mcp_server().legacy_sessions().open();
+    let endpoint = format!("{}?session_id={}", messages_path, session.session_id());
+
+    let mut encoder = SseEncoder::new();
+    let endpoint_event = encoder.event(None, Some("endpoint"), &endpoint);
+    let messages = UnboundedReceiverStream::new(receiver).map(move |response| {
+        // Keep the session registered for as long as the stream is alive
+        let _session = &session;
+        encoder.json_event(None, Some("message"), &response)
+    });
+
+    sse_response(
+        stream::once(std::future::ready(endpoint_event)).chain(messages),
+        Duration::from_secs(30),
+    )
+}
+
+/// Handle POST requests to the legacy HTTP+SSE message endpoint
+///
+/// The request is accepted immediately and its response is delivered on the
+/// SSE stream that issued the session ID.
+#[cfg(feature = "sse")]
+pub async fn mcp_messages_handler<S>(
+    State(state): State<S>,
+    Query(params): Query<McpQueryParams>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse
+where
+    S: McpHandlerState,
+{
+    let request = match parse_request_body(&body) {
+        Ok(request) => request,
+        Err(response) => return invalid_body_response(*response),
+    };
+    handle_legacy_message(state, params, headers, Extensions::new(), request).await
+}
+
+#[cfg(feature = "sse")]
+async fn handle_legacy_message<S>(
+    state: S,
+    params: McpQueryParams,
+    headers: HeaderMap,
+    extensions: Extensions,
+    request: JsonRpcRequest,
+) -> Response
+where
+    S: McpHandlerState,
+{
+    let Some(session_id) = params.session_id else {
+        return (StatusCode::BAD_REQUEST, "Session ID required").into_response();
+    };
+    if !state.mcp_server().legacy_sessions().contains(&session_id) {
+        return (StatusCode::NOT_FOUND, "Session not found").into_response();
+    }
+
+    debug!(
+        "Legacy SSE message for session {}: {} (id: {:?})",
+        session_id, request.method, request.id
+    );
+
+    let mut security_context = request_security_context(&state, &headers, &extensions);
+    security_context.client.session_id = Some(session_id.clone());
+    security_context
+        .client
+        .metadata
+        .insert(TRANSPORT_KEY.to_string(), "sse".to_string());
+
+    tokio::spawn(async move {
+        let is_notification = request.id.is_none();
+        let response = state
+            .mcp_server()
+            .handle_request(request, security_context)
+            .await;
+
+        if !is_notification {
+            if let Err(e) = state
+                .mcp_server()
+                .legacy_sessions()
+                .send(&session_id, response)
+            {
+                warn!("Failed to deliver legacy SSE response: {}", e);
+            }
+        }
+    });
+
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// Handle SSE streaming of progress updates (`?transport=progress`)
+fn handle_progress_sse<S>(state: S) -> Response
 where
     S: McpHandlerState,
 {
@@ -210,33 +992,18 @@ where
     let progress_receiver = state.mcp_server().progress_reporter().subscribe();
     let progress_stream = BroadcastStream::new(progress_receiver);
 
-    let event_stream = progress_stream.map(|result| match result {
+    let mut encoder = SseEncoder::new();
+    let event_stream = progress_stream.map(move |result| match result {
         Ok(progress) => {
-            let data = serde_json::to_string(&progress).unwrap_or_default();
-            Ok::<Event, std::convert::Infallible>(
-                Event::default()
-                    .event("progress")
-                    .data(data)
-                    .id(progress.operation_id),
-            )
+            encoder.json_event(Some(&progress.operation_id), Some("progress"), &progress)
         }
         Err(e) => {
             error!("Progress stream error: {}", e);
-            Ok::<Event, std::convert::Infallible>(
-                Event::default()
-                    .event("error")
-                    .data(format!("Stream error: {}", e)),
-            )
+            encoder.event(None, Some("error"), &format!("Stream error: {}", e))
         }
     });
 
-    Sse::new(event_stream)
-        .keep_alive(
-            axum::response::sse::KeepAlive::new()
-                .interval(Duration::from_secs(30))
-                .text("keep-alive"),
-        )
-        .into_response()
+    sse_response(event_stream, Duration::from_secs(30))
 }
 
 /// Handle StreamableHTTP SSE for Claude Desktop
@@ -260,12 +1027,21 @@ where
         }
     };
 
-    // Get or create session
-    let session_id = params
-        .session_id
-        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let Some(session_id) = params.session_id else {
+        return (StatusCode::BAD_REQUEST, "Session ID required").into_response();
+    };
+    if let Some(response) = misdirected(&state, &session_id, None) {
+        return response;
+    }
+    if let Err(e) = session_manager.resume_session(&session_id).await {
+        debug!("Rejecting SSE stream: {}", e);
+        return session_not_found_response(None);
+    }
 
-    // Get events since last event ID
+    // Subscribe before reading the buffer so nothing stored in between is lost
+    let session_stream = session_manager.subscribe_to_session(&session_id).await;
+
+    // Replay events the client missed since `Last-Event-ID`
     let events = if let Some(last_event_id) = &params.last_event_id {
         match session_manager
             .get_events_since(&session_id, Some(last_event_id))
@@ -285,72 +1061,77 @@ where
         // No last event ID, start fresh
         Vec::new()
     };
+    let replayed: HashSet<String> = events.iter().map(|event| event.id.clone()).collect();
 
-    // Create event stream from stored events and new events
-    let stored_events = stream::iter(events.into_iter().map(|event| {
-        Ok::<Event, std::convert::Infallible>(
-            Event::default()
-                .id(event.id)
-                .event(event.event_type)
-                .data(serde_json::to_string(&event.data).unwrap_or_default()),
-        )
-    }));
+    let stored_events = stream::iter(events);
+    let new_events = session_stream
+        .filter(move |event| std::future::ready(!replayed.contains(&event.id)))
+        .scan(false, |closed, event| {
+            // Deliver the session-closed event, then end the stream
+            if *closed {
+                return std::future::ready(None);
+            }
+            *closed = event.event_type == SESSION_CLOSED_EVENT;
+            std::future::ready(Some(event))
+        });
 
-    // Subscribe to new events for this session
-    let session_stream = session_manager.subscribe_to_session(&session_id).await;
-    let new_events = session_stream.map(|event| {
-        Ok::<Event, std::convert::Infallible>(
-            Event::default()
-                .id(event.id)
-                .event(event.event_type)
-                .data(serde_json::to_string(&event.data).unwrap_or_default()),
-        )
+    // Combine stored and new events, resumable by their IDs
+    let mut encoder = SseEncoder::new();
+    let combined_stream = stored_events.chain(new_events).map(move |event| {
+        encoder.json_event(Some(&event.id), Some(&event.event_type), &event.data)
     });
 
-    // Combine stored and new events
-    let combined_stream = stored_events.chain(new_events);
-
-    Sse::new(combined_stream)
-        .keep_alive(
-            axum::response::sse::KeepAlive::new()
-                .interval(Duration::from_secs(15))
-                .text("keep-alive"),
-        )
-        .into_response()
+    sse_response(combined_stream, Duration::from_secs(15))
 }
 
-/// Handle DELETE requests to MCP endpoint (session cleanup)
+/// Handle DELETE requests to MCP endpoint (session termination)
+///
+/// Terminates the session named by `mcp-session-id` (or `session_id`):
+/// connected streams receive a `session_closed` event, in-flight requests are
+/// cancelled and subscriptions are dropped. Servers without session management
+/// answer `405 Method Not Allowed`, as the StreamableHTTP spec requires.
 pub async fn mcp_delete_handler<S>(
     State(state): State<S>,
     Query(params): Query<McpQueryParams>,
-    _headers: HeaderMap,
+    headers: HeaderMap,
 ) -> impl IntoResponse
 where
     S: McpHandlerState,
 {
     debug!("MCP DELETE request with params: {:?}", params);
 
-    if let Some(session_id) = &params.session_id {
-        if let Some(session_manager) = state.session_manager() {
-            match session_manager.remove_session(session_id).await {
-                Ok(_) => {
-                    info!("Session {} removed successfully", session_id);
-                    (StatusCode::OK, "Session removed").into_response()
-                }
-                Err(e) => {
-                    error!("Failed to remove session {}: {}", session_id, e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to remove session",
-                    )
-                        .into_response()
-                }
-            }
-        } else {
-            (StatusCode::NOT_FOUND, "Session management not available").into_response()
+    let Some(session_manager) = state.session_manager() else {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Session management not available",
+        )
+            .into_response();
+    };
+    let Some(session_id) = params.session_id.or_else(|| session_id_header(&headers)) else {
+        return (StatusCode::BAD_REQUEST, "Session ID required").into_response();
+    };
+    if let Some(response) = misdirected(&state, &session_id, None) {
+        return response;
+    }
+
+    match session_manager.terminate_session(&session_id).await {
+        Ok(_) => {
+            state
+                .mcp_server()
+                .session_stores()
+                .remove(&session_id)
+                .await;
+            state.mcp_server().duplicate_requests().remove(&session_id);
+            state.mcp_server().webhooks().emit(
+                WebhookEventKind::SessionClosed,
+                serde_json::json!({ "sessionId": session_id }),
+            );
+            (StatusCode::OK, "Session terminated").into_response()
+        }
+        Err(e) => {
+            debug!("Not terminating session {}: {}", session_id, e);
+            session_not_found_response(None)
         }
-    } else {
-        (StatusCode::BAD_REQUEST, "Session ID required").into_response()
     }
 }
 
@@ -359,30 +1140,78 @@ pub fn mcp_routes<S>() -> axum::Router<S>
 where
     S: McpHandlerState + Clone + Send + Sync + 'static,
 {
-    axum::Router::new()
+    mcp_routes_with_base_path(DEFAULT_BASE_PATH)
+}
+
+/// Create MCP routes mounted under a custom base path (e.g. `/api/v1/mcp`)
+///
+/// The JSON-RPC endpoint is served at the base path and the SSE stream at
+/// `{base_path}/sse`. With the `sse` feature, legacy HTTP+SSE clients post to
+/// `{base_path}/messages`.
+/// An OpenAPI description is served at `{base_path}/openapi.json`, and with
+/// the `ui` feature a resource browser at `{base_path}/ui`.
+pub fn mcp_routes_with_base_path<S>(base_path: &str) -> axum::Router<S>
+where
+    S: McpHandlerState + Clone + Send + Sync + 'static,
+{
+    let base_path = normalize_base_path(base_path);
+    let sub_path = |name: &str| {
+        if base_path == "/" {
+            format!("/{}", name)
+        } else {
+            format!("{}/{}", base_path, name)
+        }
+    };
+    let sse_path = sub_path("sse");
+    #[cfg(feature = "sse")]
+    let messages_path = sub_path("messages");
+    let openapi_path = sub_path(super::openapi::OPENAPI_PATH);
+    let openapi_base_path = base_path.clone();
+
+    let router = axum::Router::new()
         .route(
-            "/mcp",
+            &base_path,
             axum::routing::get(
                 |State(state): State<S>,
                  Query(params): Query<McpQueryParams>,
+                 OriginalUri(uri): OriginalUri,
                  headers: HeaderMap| async move {
-                    mcp_get_handler(State(state), Query(params), headers).await
+                    if accepts_event_stream(&headers) {
+                        let messages_path = messages_path_for(uri.path());
+                        return handle_sse(state, params, headers, messages_path).await;
+                    }
+                    mcp_get_handler(State(state), Query(params), headers)
+                        .await
+                        .into_response()
                 },
             ),
         )
         .route(
-            "/mcp",
+            &base_path,
             axum::routing::post(
                 |State(state): State<S>,
                  Query(params): Query<McpQueryParams>,
                  headers: HeaderMap,
-                 Json(request): Json<JsonRpcRequest>| async move {
-                    mcp_post_handler(State(state), Query(params), headers, Json(request)).await
+                 extensions: Extensions,
+                 body: Bytes| async move {
+                    let request = match parse_post_body(&body) {
+                        Ok(PostBody::Request(request)) => request,
+                        Ok(PostBody::Response(response)) => {
+                            return accept_client_response(&state, &params, &headers, response)
+                        }
+                        Err(response) => return invalid_body_response(*response),
+                    };
+                    debug!(
+                        "MCP POST request: {} (id: {:?})",
+                        request.method, request.id
+                    );
+                    let security_context = request_security_context(&state, &headers, &extensions);
+                    handle_post(state, params, security_context, request, &headers).await
                 },
             ),
         )
         .route(
-            "/mcp",
+            &base_path,
             axum::routing::delete(
                 |State(state): State<S>,
                  Query(params): Query<McpQueryParams>,
@@ -392,23 +1221,93 @@ where
             ),
         )
         .route(
-            "/mcp/sse",
+            &sse_path,
             axum::routing::get(
                 |State(state): State<S>,
                  Query(params): Query<McpQueryParams>,
+                 OriginalUri(uri): OriginalUri,
                  headers: HeaderMap| async move {
-                    mcp_sse_handler(State(state), Query(params), headers).await
+                    let messages_path = messages_path_for(uri.path());
+                    handle_sse(state, params, headers, messages_path).await
+                },
+            ),
+        )
+        .route(
+            &openapi_path,
+            axum::routing::get(
+                |State(state): State<S>, headers: HeaderMap, extensions: Extensions| async move {
+                    let security_context = request_security_context(&state, &headers, &extensions);
+                    super::openapi::openapi_document(
+                        state.mcp_server(),
+                        &openapi_base_path,
+                        &security_context,
+                    )
+                    .await
+                    .map(Json)
                 },
             ),
+        );
+
+    #[cfg(feature = "sse")]
+    let router = router.route(
+        &messages_path,
+        axum::routing::post(
+            |State(state): State<S>,
+             Query(params): Query<McpQueryParams>,
+             headers: HeaderMap,
+             extensions: Extensions,
+             body: Bytes| async move {
+                let request = match parse_request_body(&body) {
+                    Ok(request) => request,
+                    Err(response) => return invalid_body_response(*response),
+                };
+                handle_legacy_message(state, params, headers, extensions, request).await
+            },
+        ),
+    );
+
+    #[cfg(feature = "ui")]
+    let router = {
+        let page = super::ui::ui_page(&base_path);
+        router.route(
+            &sub_path("ui"),
+            axum::routing::get(move || async move { page }),
         )
+    };
+
+    router
+}
+
+/// Whether the request asks for an SSE stream
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(axum::http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Normalize a base path to have a leading slash and no trailing slash
+pub fn normalize_base_path(path: &str) -> String {
+    let trimmed = path.trim().trim_matches('/');
+    if trimmed.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{}", trimmed)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
+        protocol::{Tool, ToolContent, ToolsCallResult},
         security::McpAuth,
-        server::{config::McpServerConfig, registry::InMemoryToolRegistry, service::McpServer},
+        server::{
+            config::McpServerConfig,
+            http_status::HttpStatusConfig,
+            registry::{InMemoryToolRegistry, McpTool, ToolExecutionContext, ToolRegistry},
+            service::McpServer,
+        },
         McpResult,
     };
 
@@ -481,6 +1380,485 @@ mod tests {
         let router: axum::Router<TestHandlerState> = mcp_routes().with_state(handler_state);
 
         // Router should compile without errors
-        assert_eq!(format!("{:?}", router).contains("Router"), true);
+        assert!(format!("{:?}", router).contains("Router"));
+    }
+
+    #[test]
+    fn test_normalize_base_path() {
+        assert_eq!(normalize_base_path("/mcp"), "/mcp");
+        assert_eq!(normalize_base_path("api/v1/mcp/"), "/api/v1/mcp");
+        assert_eq!(normalize_base_path(""), "/");
+        assert_eq!(normalize_base_path("/"), "/");
+    }
+
+    /// Registry whose only tool reports progress and then sleeps
+    #[derive(Clone)]
+    struct SlowTools;
+
+    #[async_trait::async_trait]
+    impl ToolRegistry for SlowTools {
+        async fn list_tools(&self, _context: &SecurityContext) -> McpResult<Vec<Tool>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_tool(
+            &self,
+            _name: &str,
+            _context: &SecurityContext,
+        ) -> McpResult<Option<McpTool>> {
+            Ok(None)
+        }
+
+        async fn execute_tool(
+            &self,
+            _name: &str,
+            context: ToolExecutionContext,
+        ) -> McpResult<ToolsCallResult> {
+            context.report_progress(1, 2, "halfway").await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(ToolsCallResult {
+                content: vec![ToolContent::Text {
+                    text: "done".to_string(),
+                }],
+                is_error: false,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn can_access_tool(&self, _name: &str, _context: &SecurityContext) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone)]
+    struct SlowServerState;
+
+    impl crate::server::McpServerState for SlowServerState {
+        type ToolRegistry = SlowTools;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &SlowTools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+    }
+
+    #[derive(Clone)]
+    struct SlowHandlerState {
+        server: McpServer<SlowServerState>,
+        sessions: Option<SessionManager>,
+    }
+
+    impl McpHandlerState for SlowHandlerState {
+        type ServerState = SlowServerState;
+
+        fn mcp_server(&self) -> &McpServer<Self::ServerState> {
+            &self.server
+        }
+
+        fn session_manager(&self) -> Option<&SessionManager> {
+            self.sessions.as_ref()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_long_tool_call_streams_sse() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let config =
+            McpServerConfig::default().with_sse_response_threshold(Some(Duration::from_millis(20)));
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server: McpServer::new(config, SlowServerState),
+            sessions: None,
+        });
+        let call = r#"{"jsonrpc":"2.0","method":"tools/call","id":1,
+            "params":{"name":"slow","_meta":{"progressToken":42}}}"#;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::post("/mcp")
+                    .header("content-type", "application/json")
+                    .header("accept", "application/json, text/event-stream")
+                    .body(Body::from(call))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let progress = body.find("notifications/progress").unwrap();
+        let result = body.find("\"done\"").unwrap();
+        assert!(progress < result);
+        assert!(body.contains("\"progressToken\":42"));
+
+        // Clients that only accept JSON keep getting a plain response
+        let response = app
+            .oneshot(
+                Request::post("/mcp")
+                    .header("content-type", "application/json")
+                    .body(Body::from(call))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+
+    #[derive(Clone)]
+    struct PromptServerState {
+        tools: InMemoryToolRegistry,
+        prompts: crate::server::InMemoryPromptRegistry,
+    }
+
+    impl crate::server::McpServerState for PromptServerState {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+
+        fn prompt_registry(&self) -> Option<&dyn crate::server::PromptRegistry> {
+            Some(&self.prompts)
+        }
+    }
+
+    #[derive(Clone)]
+    struct PromptHandlerState {
+        server: McpServer<PromptServerState>,
+    }
+
+    impl McpHandlerState for PromptHandlerState {
+        type ServerState = PromptServerState;
+
+        fn mcp_server(&self) -> &McpServer<Self::ServerState> {
+            &self.server
+        }
+
+        fn session_manager(&self) -> Option<&SessionManager> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompt_get_streams_chunks() {
+        use crate::server::{
+            MessageRole, Prompt, PromptContent, PromptMessage, PromptStreamConfig,
+        };
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let mut prompts = crate::server::InMemoryPromptRegistry::new();
+        prompts.add_prompt(Prompt {
+            name: "review".to_string(),
+            description: "Review {{file}}".to_string(),
+            version: "1.0.0".to_string(),
+            parameters: Vec::new(),
+            messages: vec![PromptMessage {
+                role: MessageRole::User,
+                content: PromptContent::Text {
+                    text: format!("Review {{{{file}}}}:\n{}", "x".repeat(10_000)),
+                },
+            }],
+            metadata: HashMap::new(),
+        });
+        let config = McpServerConfig::default().with_prompt_stream(PromptStreamConfig {
+            chunk_bytes: 4096,
+            ..Default::default()
+        });
+        let state = PromptServerState {
+            tools: InMemoryToolRegistry::new(),
+            prompts,
+        };
+        let app = mcp_routes().with_state(PromptHandlerState {
+            server: McpServer::new(config, state),
+        });
+        let get = |meta: &str| {
+            let body = format!(
+                r#"{{"jsonrpc":"2.0","method":"prompts/get","id":7,
+                "params":{{"name":"review","arguments":{{"file":"main.rs"}},"_meta":{}}}}}"#,
+                meta
+            );
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::post("/mcp")
+                            .header("content-type", "application/json")
+                            .header("accept", "application/json, text/event-stream")
+                            .body(Body::from(body))
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.headers()["content-type"], "text/event-stream");
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(body.to_vec())
+                    .unwrap()
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("data: "))
+                    .map(|data| serde_json::from_str(data).unwrap())
+                    .collect::<Vec<serde_json::Value>>()
+            }
+        };
+
+        let messages = get(r#"{"stream":true}"#).await;
+        let (result, chunks) = messages.split_last().unwrap();
+        assert_eq!(chunks.len(), 3);
+        let text: String = chunks
+            .iter()
+            .map(|chunk| {
+                assert_eq!(chunk["params"]["requestId"], 7);
+                chunk["params"]["content"]["text"].as_str().unwrap()
+            })
+            .collect();
+        assert!(text.starts_with("Review main.rs:"));
+        assert_eq!(result["id"], 7);
+        assert_eq!(result["result"]["description"], "Review main.rs");
+        assert_eq!(result["result"]["messages"], serde_json::json!([]));
+        assert_eq!(result["result"]["_meta"]["streamed"]["messages"], 1);
+
+        // A token budget needs the whole prompt, so it is not streamed
+        let messages = get(r#"{"stream":true,"tokenBudget":100000}"#).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0]["result"]["messages"].as_array().unwrap().len(),
+            1
+        );
+        assert!(messages[0]["result"]["_meta"].get("streamed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_terminates_session() {
+        use crate::transport::streamable_http::InMemoryEventStore;
+        use axum::{body::Body, http::Request};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let delete = |session_id: &str| {
+            Request::delete("/mcp")
+                .header("mcp-session-id", session_id)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Without session management DELETE is not allowed
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server: McpServer::new(McpServerConfig::default(), SlowServerState),
+            sessions: None,
+        });
+        let response = app.oneshot(delete("any")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let sessions = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(10, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let session_id = sessions.create_session().await.unwrap();
+        let config = McpServerConfig::default()
+            .with_duplicate_requests(crate::server::DuplicateRequestConfig::replay());
+        let server = McpServer::new(config, SlowServerState);
+        let duplicates = server.duplicate_requests().clone();
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server,
+            sessions: Some(sessions.clone()),
+        });
+
+        let call = tokio::spawn(
+            app.clone().oneshot(
+                Request::post("/mcp")
+                    .header("content-type", "application/json")
+                    .header("mcp-session-id", &session_id)
+                    .body(Body::from(
+                        r#"{"jsonrpc":"2.0","method":"tools/call","id":1,"params":{"name":"slow"}}"#,
+                    ))
+                    .unwrap(),
+            ),
+        );
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(duplicates.len(), 1);
+
+        let response = app.clone().oneshot(delete(&session_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(sessions.get_session(&session_id).await.is_none());
+        // Request IDs of the session are forgotten with it
+        assert!(duplicates.is_empty());
+
+        // The in-flight call is cancelled rather than completed
+        let response = call.await.unwrap().unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Session terminated"));
+        assert!(!body.contains("done"));
+
+        let response = app.oneshot(delete(&session_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payloads_get_jsonrpc_errors() {
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server: McpServer::new(McpServerConfig::default().with_batch(4), SlowServerState),
+            sessions: None,
+        });
+
+        let cases: &[(&str, StatusCode, i32)] = &[
+            (
+                r#"{"jsonrpc":"2.0","method":"#,
+                StatusCode::BAD_REQUEST,
+                -32700,
+            ),
+            ("[]", StatusCode::BAD_REQUEST, -32600),
+            (
+                r#"{"jsonrpc":"2.0","id":3}"#,
+                StatusCode::BAD_REQUEST,
+                -32600,
+            ),
+            (
+                r#"{"jsonrpc":"1.0","method":"ping","id":4}"#,
+                StatusCode::OK,
+                -32600,
+            ),
+            (
+                r#"{"jsonrpc":"2.0","method":"tools/call","id":5,"params":[1]}"#,
+                StatusCode::OK,
+                -32602,
+            ),
+            (
+                r#"{"jsonrpc":"2.0","method":"batch","id":6,"params":{"requests":"nope"}}"#,
+                StatusCode::OK,
+                -32602,
+            ),
+        ];
+
+        for (body, status, code) in cases {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::post("/mcp")
+                        .header("content-type", "application/json")
+                        .body(Body::from(*body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), *status, "{}", body);
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let response: JsonRpcResponse = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(response.error.unwrap().code, *code, "{}", body);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_errors_get_mapped_http_status() {
+        use crate::security::MethodAuthorization;
+        use axum::{body::Body, http::Request};
+        use tower::ServiceExt;
+
+        let config = McpServerConfig::default()
+            .with_method_authorization(MethodAuthorization::new().require("tools/list", ["ops"]))
+            .with_http_status(HttpStatusConfig::enabled());
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server: McpServer::new(config, SlowServerState),
+            sessions: None,
+        });
+        let post = |body: &'static str| {
+            Request::post("/mcp")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(r#"{"jsonrpc":"2.0","method":"tools/list","id":1}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: JsonRpcResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            response.error.unwrap().data.unwrap()["code"],
+            "authorization"
+        );
+
+        let response = app
+            .oneshot(post(r#"{"jsonrpc":"2.0","method":"ping","id":2}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_client_answers_server_request() {
+        use crate::transport::streamable_http::InMemoryEventStore;
+        use axum::{body::Body, http::Request};
+        use std::sync::Arc;
+        use tower::ServiceExt;
+
+        let sessions = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(10, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let session_id = sessions.create_session().await.unwrap();
+        let mut stream = Box::pin(sessions.subscribe_to_session(&session_id).await);
+        let app = mcp_routes().with_state(SlowHandlerState {
+            server: McpServer::new(McpServerConfig::default(), SlowServerState),
+            sessions: Some(sessions.clone()),
+        });
+
+        let client = sessions.client(&session_id);
+        let request = tokio::spawn(async move {
+            client
+                .request("elicitation/create", Some(serde_json::json!({})))
+                .await
+        });
+        let id = stream.next().await.unwrap().data["id"].clone();
+
+        let answer = serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": {"action": "accept"},
+            "id": id
+        });
+        let response = app
+            .oneshot(
+                Request::post("/mcp")
+                    .header("content-type", "application/json")
+                    .header("mcp-session-id", &session_id)
+                    .body(Body::from(answer.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        assert_eq!(
+            request.await.unwrap().unwrap(),
+            serde_json::json!({"action": "accept"})
+        );
     }
 }
@@ -0,0 +1,174 @@
+//! HTTP status codes for JSON-RPC errors
+//!
+//! JSON-RPC over HTTP answers `200 OK` whether or not the request failed, so
+//! proxies and load balancers cannot tell an overloaded server from a healthy
+//! one. With [`HttpStatusConfig::enabled`], POST responses carrying an error
+//! get the status mapped from the error's code (`data.code`, see
+//! [`McpError::code`](crate::McpError::code)):
+//!
+//! ```yaml
+//! http_status:
+//!   enabled: true
+//!   statuses:
+//!     authentication: 401
+//!     overloaded: 429
+//!     tool_not_found: 404
+//! ```
+//!
+//! Configured statuses replace the defaults for their codes: `401` for
+//! authentication, `403` for authorization, `429` for rate limits and quotas,
+//! `503` for overload and maintenance and `504` for server
+//! timeouts. Other errors keep `200 OK`. The JSON-RPC body is unchanged, and
+//! `429` and `503` responses carry `Retry-After` when the error knows when to
+//! retry. Streamed responses have sent their status before the result is
+//! known and are not affected.
+
+#[cfg(feature = "axum")]
+use axum::{
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::protocol::JsonRpcResponse;
+
+/// Mapping of error codes to HTTP statuses
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpStatusConfig {
+    /// Answer failed requests with the mapped status instead of `200 OK`
+    pub enabled: bool,
+
+    /// Status per error code, replacing the defaults for those codes
+    pub statuses: BTreeMap<String, u16>,
+}
+
+impl HttpStatusConfig {
+    /// Map errors to statuses, with the default mapping
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Answer errors with code `code` with `status`
+    pub fn with_status(mut self, code: impl Into<String>, status: StatusCode) -> Self {
+        self.statuses.insert(code.into(), status.as_u16());
+        self
+    }
+
+    /// Configured statuses that are not valid HTTP statuses
+    pub fn invalid(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.statuses
+            .iter()
+            .filter(|(_, status)| StatusCode::from_u16(**status).is_err())
+            .map(|(code, status)| (code.as_str(), *status))
+    }
+
+    /// Status for errors with code `code`, if it is mapped
+    pub fn status_for(&self, code: &str) -> Option<StatusCode> {
+        match self.statuses.get(code) {
+            Some(status) => StatusCode::from_u16(*status).ok(),
+            None => default_status(code),
+        }
+    }
+
+    /// Status to answer `response` with
+    pub fn status_of(&self, response: &JsonRpcResponse) -> StatusCode {
+        let code = response
+            .error
+            .as_ref()
+            .and_then(|error| error.data.as_ref()?.get("code")?.as_str());
+        match code {
+            Some(code) if self.enabled => self.status_for(code).unwrap_or(StatusCode::OK),
+            _ => StatusCode::OK,
+        }
+    }
+
+    /// HTTP response for `response`, with its mapped status
+    #[cfg(feature = "axum")]
+    pub fn respond(&self, response: JsonRpcResponse) -> Response {
+        let status = self.status_of(&response);
+        let retry_after = response
+            .error
+            .as_ref()
+            .and_then(|error| error.data.as_ref()?.get("retryAfterMs")?.as_u64())
+            .filter(|_| {
+                status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+            });
+        let mut http_response = (status, Json(response)).into_response();
+        if let Some(ms) = retry_after {
+            http_response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(ms.div_ceil(1000)));
+        }
+        http_response
+    }
+}
+
+fn default_status(code: &str) -> Option<StatusCode> {
+    match code {
+        "authentication" => Some(StatusCode::UNAUTHORIZED),
+        "authorization" => Some(StatusCode::FORBIDDEN),
+        "rate_limited" | "quota_exceeded" => Some(StatusCode::TOO_MANY_REQUESTS),
+        "overloaded" | "under_maintenance" => Some(StatusCode::SERVICE_UNAVAILABLE),
+        "server_timeout" => Some(StatusCode::GATEWAY_TIMEOUT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{protocol::JsonRpcError, McpError};
+
+    fn failed(error: McpError) -> JsonRpcResponse {
+        let error = JsonRpcError::new(
+            error.error_code(),
+            error.client_message(),
+            error.client_data(),
+        );
+        JsonRpcResponse::error(error, Some(serde_json::json!(1)))
+    }
+
+    #[test]
+    fn test_maps_error_codes() {
+        let overloaded = failed(McpError::Overloaded {
+            reason: "busy".to_string(),
+            retry_after: 2,
+        });
+        assert_eq!(
+            HttpStatusConfig::default().status_of(&overloaded),
+            StatusCode::OK
+        );
+
+        let config: HttpStatusConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "statuses": {"overloaded": 429, "tool_not_found": 404, "internal": 1000},
+        }))
+        .unwrap();
+        assert_eq!(config.status_of(&overloaded), StatusCode::TOO_MANY_REQUESTS);
+        let missing = failed(McpError::ToolNotFound {
+            name: "x".to_string(),
+        });
+        assert_eq!(config.status_of(&missing), StatusCode::NOT_FOUND);
+        let denied = failed(McpError::Authorization {
+            message: "no".to_string(),
+        });
+        assert_eq!(config.status_of(&denied), StatusCode::FORBIDDEN);
+        let broken = failed(McpError::internal("bug"));
+        assert_eq!(config.status_of(&broken), StatusCode::OK);
+        assert_eq!(config.invalid().collect::<Vec<_>>(), [("internal", 1000)]);
+
+        #[cfg(feature = "axum")]
+        {
+            let response = config.respond(overloaded);
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+        }
+    }
+}
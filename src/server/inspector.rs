@@ -0,0 +1,382 @@
+//! JSON-RPC request inspector
+//!
+//! When switched on, the inspector keeps the last few requests and the
+//! responses sent for them, so that "what did the client actually send"
+//! questions can be answered without a packet capture. Values under sensitive
+//! keys such as `password` or `token` are redacted before anything is kept.
+//!
+//! Captured exchanges are available through [`RequestInspector::recent`], the
+//! `admin/inspector/list` method (restricted to system contexts), and as
+//! `debug` events on the [`INSPECTOR_TARGET`] tracing target. The inspector
+//! can be switched on and off at runtime with [`RequestInspector::set_enabled`]
+//! or `admin/inspector/configure`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{protocol::ToolsCallResult, security::SecurityContext, server::scrub::Scrubber};
+
+/// Tracing target captured exchanges are logged to
+pub const INSPECTOR_TARGET: &str = "axum_mcp::inspector";
+
+/// Replacement for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Request inspector configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InspectorConfig {
+    /// Whether the inspector starts switched on
+    pub enabled: bool,
+
+    /// Number of exchanges kept; the oldest are dropped first
+    pub capacity: usize,
+
+    /// Object keys whose values are redacted, compared case-insensitively and
+    /// ignoring `-` and `_`
+    pub redact_keys: Vec<String>,
+}
+
+impl Default for InspectorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 100,
+            redact_keys: [
+                "password",
+                "secret",
+                "client_secret",
+                "token",
+                "access_token",
+                "refresh_token",
+                "api_key",
+                "authorization",
+                "cookie",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+impl InspectorConfig {
+    /// Start the inspector switched on, keeping `capacity` exchanges
+    pub fn enabled(capacity: usize) -> Self {
+        Self {
+            enabled: true,
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    /// Also redact values under `key`
+    pub fn redact_key(mut self, key: impl Into<String>) -> Self {
+        self.redact_keys.push(key.into());
+        self
+    }
+}
+
+/// A captured request and the response sent for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InspectedExchange {
+    /// Exchange identifier
+    pub id: String,
+
+    /// JSON-RPC method of the request
+    pub method: String,
+
+    /// Principal that sent the request (see [`SecurityContext::principal`])
+    pub principal: String,
+
+    /// Session the request was sent in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// When the request was received
+    pub received_at: DateTime<Utc>,
+
+    /// How long handling took in milliseconds
+    pub duration_ms: u64,
+
+    /// The request, redacted
+    pub request: serde_json::Value,
+
+    /// The response, redacted
+    pub response: serde_json::Value,
+}
+
+/// Ring buffer of recent JSON-RPC exchanges
+#[derive(Debug, Clone)]
+pub struct RequestInspector {
+    enabled: Arc<AtomicBool>,
+    exchanges: Arc<Mutex<VecDeque<InspectedExchange>>>,
+    config: Arc<InspectorConfig>,
+    redact_keys: Arc<Vec<String>>,
+    scrubber: Option<Scrubber>,
+}
+
+impl RequestInspector {
+    /// Create an empty inspector
+    pub fn new(config: InspectorConfig) -> Self {
+        let redact_keys = config
+            .redact_keys
+            .iter()
+            .map(|key| normalize(key))
+            .collect();
+        Self {
+            enabled: Arc::new(AtomicBool::new(config.enabled)),
+            exchanges: Arc::new(Mutex::new(VecDeque::new())),
+            config: Arc::new(config),
+            redact_keys: Arc::new(redact_keys),
+            scrubber: None,
+        }
+    }
+
+    /// Also scrub personal data from captured exchanges with `scrubber`
+    pub fn with_scrubber(mut self, scrubber: Scrubber) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+
+    /// Configuration the inspector was created with
+    pub fn config(&self) -> &InspectorConfig {
+        &self.config
+    }
+
+    /// Whether exchanges are currently captured
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Switch capturing on or off; captured exchanges are kept
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Capture an exchange, if the inspector is on
+    pub fn record(
+        &self,
+        context: &SecurityContext,
+        received_at: DateTime<Utc>,
+        duration: Duration,
+        request: &serde_json::Value,
+        response: &serde_json::Value,
+    ) {
+        if !self.is_enabled() || self.config.capacity == 0 {
+            return;
+        }
+
+        let method = request
+            .get("method")
+            .and_then(|method| method.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let (request, response) = (self.redact(request), self.redact(response));
+        let (request, response) = match &self.scrubber {
+            Some(scrubber) => scrub_exchange(scrubber, &method, request, response),
+            None => (request, response),
+        };
+        let exchange = InspectedExchange {
+            id: uuid::Uuid::new_v4().to_string(),
+            method,
+            principal: context.principal(),
+            session_id: context.client.session_id.clone(),
+            received_at,
+            duration_ms: duration.as_millis() as u64,
+            request,
+            response,
+        };
+        tracing::debug!(
+            target: INSPECTOR_TARGET,
+            method = %exchange.method,
+            principal = %exchange.principal,
+            duration_ms = exchange.duration_ms,
+            request = %exchange.request,
+            response = %exchange.response,
+            "MCP exchange"
+        );
+
+        let mut exchanges = self.lock();
+        exchanges.push_back(exchange);
+        while exchanges.len() > self.config.capacity {
+            exchanges.pop_front();
+        }
+    }
+
+    /// Captured exchanges, newest first, at most `limit` of them
+    pub fn recent(&self, limit: Option<usize>) -> Vec<InspectedExchange> {
+        self.lock()
+            .iter()
+            .rev()
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of captured exchanges
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no exchanges are captured
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Drop all captured exchanges
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Copy of `value` with the values of sensitive keys replaced
+    pub fn redact(&self, value: &serde_json::Value) -> serde_json::Value {
+        redact(value, &self.redact_keys)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<InspectedExchange>> {
+        self.exchanges
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Parameters of `admin/inspector/list`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InspectorListParams {
+    /// Maximum number of exchanges to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Parameters of `admin/inspector/configure`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InspectorConfigureParams {
+    /// Switch capturing on or off
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Drop the captured exchanges
+    #[serde(default)]
+    pub clear: bool,
+}
+
+/// Scrub a redacted exchange: tool arguments and results like
+/// [`Scrubber::scrub_value`], and pattern matches everywhere
+fn scrub_exchange(
+    scrubber: &Scrubber,
+    method: &str,
+    mut request: serde_json::Value,
+    mut response: serde_json::Value,
+) -> (serde_json::Value, serde_json::Value) {
+    if method == "tools/call" {
+        if let Some(arguments) = request.pointer_mut("/params/arguments") {
+            scrubber.scrub_value(arguments);
+        }
+        if let Some(result) = response.get_mut("result") {
+            if let Ok(mut tool_result) = serde_json::from_value::<ToolsCallResult>(result.clone()) {
+                scrubber.scrub_tool_result(&mut tool_result);
+                *result = serde_json::to_value(tool_result).unwrap_or_default();
+            }
+        }
+    }
+    scrubber.scrub_strings(&mut request);
+    scrubber.scrub_strings(&mut response);
+    (request, response)
+}
+
+/// Copy of `value` with the values under `keys`, [normalized](normalize), replaced
+pub(crate) fn redact(value: &serde_json::Value, keys: &[String]) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = if keys.contains(&normalize(key)) {
+                    serde_json::Value::String(REDACTED.to_string())
+                } else {
+                    redact(value, keys)
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(|item| redact(item, keys)).collect(),
+        other => other.clone(),
+    }
+}
+
+/// `key` lowercased without `-` and `_`, as redaction compares keys
+pub(crate) fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redacts_sensitive_keys_at_any_depth() {
+        let inspector = RequestInspector::new(InspectorConfig::default().redact_key("ssn"));
+        let redacted = inspector.redact(&json!({
+            "method": "tools/call",
+            "params": {
+                "name": "login",
+                "arguments": {"user": "ann", "Password": "hunter2", "SSN": "123"},
+                "_meta": {"progressToken": "p1"},
+                "headers": [{"apiKey": "k"}, {"Access-Token": "t"}]
+            }
+        }));
+
+        assert_eq!(redacted["params"]["arguments"]["user"], "ann");
+        assert_eq!(redacted["params"]["arguments"]["Password"], REDACTED);
+        assert_eq!(redacted["params"]["arguments"]["SSN"], REDACTED);
+        assert_eq!(redacted["params"]["_meta"]["progressToken"], "p1");
+        assert_eq!(redacted["params"]["headers"][0]["apiKey"], REDACTED);
+        assert_eq!(redacted["params"]["headers"][1]["Access-Token"], REDACTED);
+    }
+
+    #[test]
+    fn test_keeps_last_exchanges_only_while_enabled() {
+        let inspector = RequestInspector::new(InspectorConfig::enabled(2));
+        let context = SecurityContext::system();
+        for method in ["ping", "tools/list", "tools/call"] {
+            inspector.record(
+                &context,
+                Utc::now(),
+                Duration::ZERO,
+                &json!({"method": method}),
+                &json!({"result": {}}),
+            );
+        }
+
+        let methods: Vec<_> = inspector
+            .recent(None)
+            .into_iter()
+            .map(|exchange| exchange.method)
+            .collect();
+        assert_eq!(methods, ["tools/call", "tools/list"]);
+
+        inspector.set_enabled(false);
+        inspector.record(
+            &context,
+            Utc::now(),
+            Duration::ZERO,
+            &json!({"method": "ping"}),
+            &json!({}),
+        );
+        assert_eq!(inspector.len(), 2);
+        assert_eq!(inspector.recent(Some(1))[0].method, "tools/call");
+    }
+}
@@ -0,0 +1,773 @@
+//! Tool execution in isolated worker processes
+//!
+//! With the `isolation` feature, [`IsolatedToolExecutor`] wraps a
+//! [`ToolRegistry`] and runs tool calls in a pool of worker processes, so
+//! native tool code that crashes, leaks or corrupts memory takes down a
+//! worker instead of the server:
+//!
+//! - Workers are started when the executor is, and each serves one call at a
+//!   time over its stdin and stdout.
+//! - A worker is replaced after `max_requests_per_worker` calls, and when it
+//!   exits, fails or exceeds `request_timeout`.
+//! - On Unix, workers apply `max_memory_bytes` and `max_cpu_seconds` to
+//!   themselves as resource limits before serving.
+//!
+//! Tools with a [`SandboxProfile`] get a fresh worker per call, which confines
+//! itself to the profile with the `sandbox` feature on Linux.
+//!
+//! By default workers are the server's own executable, started with
+//! [`WORKER_ENV`] set. Its `main` enters the sandbox, if any, before starting
+//! the runtime and hands over to [`run_worker`] with the same registry:
+//!
+//! ```rust,ignore
+//! fn main() -> McpResult<()> {
+//!     if isolation::is_worker() {
+//!         isolation::enter_sandbox()?;
+//!         return tokio::runtime::Runtime::new()?.block_on(isolation::run_worker(build_registry()));
+//!     }
+//!     // ...
+//!     let tools = IsolatedToolExecutor::start(build_registry(), IsolationConfig::default()).await?;
+//! }
+//! ```
+//!
+//! The pipe protocol is one JSON object per line: the server writes a
+//! [`WorkerRequest`] and the worker answers with a [`WorkerResponse`] carrying
+//! the same `id`. Other output on the worker's stdout is skipped, so workers
+//! may be written in any language.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::{Mutex, Semaphore},
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::{Tool, ToolsCallResult},
+    security::{ClientContext, SecurityContext},
+    server::{
+        registry::{McpTool, ToolExecutionContext, ToolRegistry},
+        sandbox::SandboxProfile,
+    },
+};
+
+/// Environment variable marking a process as an isolated tool worker
+pub const WORKER_ENV: &str = "AXUM_MCP_ISOLATED_WORKER";
+
+/// Environment variable carrying the worker's address space limit in bytes
+pub const WORKER_MAX_MEMORY_ENV: &str = "AXUM_MCP_WORKER_MAX_MEMORY";
+
+/// Environment variable carrying the worker's CPU time limit in seconds
+pub const WORKER_MAX_CPU_ENV: &str = "AXUM_MCP_WORKER_MAX_CPU_SECONDS";
+
+/// Environment variable carrying the worker's sandbox profile as JSON
+pub const WORKER_SANDBOX_ENV: &str = "AXUM_MCP_WORKER_SANDBOX";
+
+/// Whether [`enter_sandbox`] confined this process
+static SANDBOXED: AtomicBool = AtomicBool::new(false);
+
+/// Worker pool configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IsolationConfig {
+    /// Worker executable; `None` runs the server's own executable
+    pub command: Option<PathBuf>,
+
+    /// Arguments passed to the worker executable
+    pub args: Vec<String>,
+
+    /// Number of worker processes
+    pub workers: usize,
+
+    /// Calls a worker serves before it is replaced
+    pub max_requests_per_worker: usize,
+
+    /// Time a call may take before its worker is killed
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+
+    /// Address space limit of each worker, in bytes
+    pub max_memory_bytes: Option<u64>,
+
+    /// CPU time limit of each worker, in seconds
+    pub max_cpu_seconds: Option<u64>,
+}
+
+impl Default for IsolationConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            args: Vec::new(),
+            workers: 2,
+            max_requests_per_worker: 100,
+            request_timeout: Duration::from_secs(30),
+            max_memory_bytes: None,
+            max_cpu_seconds: None,
+        }
+    }
+}
+
+impl IsolationConfig {
+    /// Run `command` with `args` as the worker
+    pub fn with_command(
+        mut self,
+        command: impl Into<PathBuf>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.command = Some(command.into());
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keep `workers` worker processes
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Limit each worker's address space and CPU time
+    pub fn with_limits(
+        mut self,
+        max_memory_bytes: Option<u64>,
+        max_cpu_seconds: Option<u64>,
+    ) -> Self {
+        self.max_memory_bytes = max_memory_bytes;
+        self.max_cpu_seconds = max_cpu_seconds;
+        self
+    }
+}
+
+/// A tool call sent to a worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerRequest {
+    /// Correlates the response
+    pub id: u64,
+
+    /// Tool to call
+    pub tool: String,
+
+    /// Tool arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Value>,
+
+    /// JSON-RPC request ID of the call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// Execution metadata
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, Value>,
+
+    /// Caller's client ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+
+    /// Caller's session ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Whether the caller is authenticated
+    #[serde(default)]
+    pub authenticated: bool,
+
+    /// Caller's capabilities
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub capabilities: Vec<String>,
+}
+
+impl WorkerRequest {
+    /// Execution context the worker runs the call with
+    ///
+    /// Progress reporting and session working memory stay in the server and
+    /// are not available to isolated tools.
+    pub fn execution_context(&self) -> ToolExecutionContext {
+        let client = ClientContext {
+            user_agent: "isolated-worker".to_string(),
+            client_id: self.client_id.clone(),
+            session_id: self.session_id.clone(),
+            ..Default::default()
+        };
+        let security = if self.authenticated {
+            SecurityContext::authenticated(client, self.capabilities.clone())
+        } else {
+            SecurityContext {
+                client,
+                ..SecurityContext::anonymous()
+            }
+        };
+        let mut context = ToolExecutionContext::new(security);
+        context.arguments = self.arguments.clone();
+        context.request_id = self.request_id.clone();
+        context.metadata = self.metadata.clone();
+        context
+    }
+}
+
+/// A worker's answer to a [`WorkerRequest`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerResponse {
+    /// ID of the request answered
+    pub id: u64,
+
+    /// Result or error
+    #[serde(flatten)]
+    pub outcome: WorkerOutcome,
+}
+
+/// Outcome of an isolated tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerOutcome {
+    /// The tool returned a result
+    Result(ToolsCallResult),
+    /// The tool failed
+    Error(String),
+}
+
+/// Whether this process was started as an isolated tool worker
+pub fn is_worker() -> bool {
+    std::env::var_os(WORKER_ENV).is_some()
+}
+
+/// Confine this worker to the sandbox profile the server passed, if any
+///
+/// Call before starting the async runtime: Landlock only restricts the
+/// calling thread and the threads it starts afterwards. Fails when a profile
+/// was passed but sandboxing is unavailable, so the call does not run
+/// unconfined.
+pub fn enter_sandbox() -> McpResult<()> {
+    let Ok(profile) = std::env::var(WORKER_SANDBOX_ENV) else {
+        return Ok(());
+    };
+    let profile: SandboxProfile = serde_json::from_str(&profile)?;
+
+    #[cfg(all(target_os = "linux", feature = "sandbox"))]
+    {
+        profile.enforce()?;
+        SANDBOXED.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "sandbox")))]
+    {
+        let _ = profile;
+        Err(McpError::Configuration {
+            message: "Sandboxed tools need the `sandbox` feature on Linux".to_string(),
+        })
+    }
+}
+
+/// Serve tool calls from stdin until it closes
+///
+/// Applies the resource limits the server passed in the environment first.
+pub async fn run_worker<R>(registry: R) -> McpResult<()>
+where
+    R: ToolRegistry,
+{
+    if std::env::var_os(WORKER_SANDBOX_ENV).is_some() && !SANDBOXED.load(Ordering::SeqCst) {
+        return Err(McpError::Configuration {
+            message: "Call enter_sandbox before starting the worker's runtime".to_string(),
+        });
+    }
+    apply_limits()?;
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: WorkerRequest = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Ignoring malformed worker request: {}", e);
+                continue;
+            }
+        };
+
+        let outcome = match registry
+            .execute_tool(&request.tool, request.execution_context())
+            .await
+        {
+            Ok(result) => WorkerOutcome::Result(result),
+            Err(e) => WorkerOutcome::Error(e.to_string()),
+        };
+        let mut response = serde_json::to_vec(&WorkerResponse {
+            id: request.id,
+            outcome,
+        })?;
+        response.push(b'\n');
+        stdout.write_all(&response).await?;
+        stdout.flush().await?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn apply_limits() -> McpResult<()> {
+    let limit = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    let apply = |resource: rlimit::Resource, value: u64| {
+        rlimit::setrlimit(resource, value, value).map_err(|e| McpError::Configuration {
+            message: format!("Failed to apply worker limit {:?}: {}", resource, e),
+        })
+    };
+    if let Some(bytes) = limit(WORKER_MAX_MEMORY_ENV) {
+        apply(rlimit::Resource::AS, bytes)?;
+    }
+    if let Some(seconds) = limit(WORKER_MAX_CPU_ENV) {
+        apply(rlimit::Resource::CPU, seconds)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_limits() -> McpResult<()> {
+    Ok(())
+}
+
+/// A running worker process
+struct Worker {
+    // Killed when the worker is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    served: usize,
+}
+
+impl Worker {
+    async fn spawn(config: &IsolationConfig, sandbox: Option<&SandboxProfile>) -> McpResult<Self> {
+        let program = match &config.command {
+            Some(command) => command.clone(),
+            None => std::env::current_exe()?,
+        };
+        let mut command = Command::new(program);
+        command
+            .args(&config.args)
+            .env(WORKER_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true);
+        if let Some(bytes) = config.max_memory_bytes {
+            command.env(WORKER_MAX_MEMORY_ENV, bytes.to_string());
+        }
+        if let Some(seconds) = config.max_cpu_seconds {
+            command.env(WORKER_MAX_CPU_ENV, seconds.to_string());
+        }
+        if let Some(profile) = sandbox {
+            command.env(WORKER_SANDBOX_ENV, serde_json::to_string(profile)?);
+        }
+
+        let mut child = command.spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(McpError::Internal {
+                message: "Worker process has no pipes".to_string(),
+                source: None,
+            });
+        };
+        debug!("Started isolated tool worker {:?}", child.id());
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            served: 0,
+        })
+    }
+
+    async fn call(&mut self, request: &WorkerRequest) -> McpResult<WorkerOutcome> {
+        let mut line = serde_json::to_vec(request)?;
+        line.push(b'\n');
+        self.stdin.write_all(&line).await?;
+        self.stdin.flush().await?;
+
+        while let Some(line) = self.stdout.next_line().await? {
+            match serde_json::from_str::<WorkerResponse>(&line) {
+                Ok(response) if response.id == request.id => return Ok(response.outcome),
+                Ok(_) => debug!("Skipping stale worker response"),
+                Err(_) => debug!("Skipping worker output: {}", line),
+            }
+        }
+        Err(McpError::Internal {
+            message: "Worker exited".to_string(),
+            source: None,
+        })
+    }
+}
+
+struct Pool {
+    config: IsolationConfig,
+    idle: Mutex<Vec<Worker>>,
+    permits: Semaphore,
+    next_id: AtomicU64,
+}
+
+impl Pool {
+    /// Start a replacement for a discarded worker in the background
+    fn replenish(self: &Arc<Self>) {
+        let pool = Arc::clone(self);
+        tokio::spawn(async move {
+            match Worker::spawn(&pool.config, None).await {
+                Ok(worker) => pool.idle.lock().await.push(worker),
+                Err(e) => warn!("Failed to start isolated tool worker: {}", e),
+            }
+        });
+    }
+}
+
+/// Tool registry running its tools' calls in worker processes
+///
+/// Listing and access checks are answered by the wrapped registry in the
+/// server process; only `execute_tool` crosses into a worker.
+#[derive(Clone)]
+pub struct IsolatedToolExecutor<R> {
+    registry: R,
+    pool: Arc<Pool>,
+}
+
+impl<R> IsolatedToolExecutor<R>
+where
+    R: ToolRegistry,
+{
+    /// Start the worker pool for `registry`
+    pub async fn start(registry: R, config: IsolationConfig) -> McpResult<Self> {
+        let workers = config.workers.max(1);
+        let mut idle = Vec::with_capacity(workers);
+        for _ in 0..workers {
+            idle.push(Worker::spawn(&config, None).await?);
+        }
+        info!("Started {} isolated tool workers", workers);
+
+        Ok(Self {
+            registry,
+            pool: Arc::new(Pool {
+                config,
+                idle: Mutex::new(idle),
+                permits: Semaphore::new(workers),
+                next_id: AtomicU64::new(1),
+            }),
+        })
+    }
+
+    /// Get the pool configuration
+    pub fn config(&self) -> &IsolationConfig {
+        &self.pool.config
+    }
+
+    /// Number of workers waiting for a call
+    pub async fn idle_workers(&self) -> usize {
+        self.pool.idle.lock().await.len()
+    }
+
+    async fn execute_isolated(
+        &self,
+        name: &str,
+        context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        let sandbox = self
+            .registry
+            .get_tool(name, &context.security)
+            .await?
+            .and_then(|tool| tool.sandbox);
+        let request = WorkerRequest {
+            id: self.pool.next_id.fetch_add(1, Ordering::Relaxed),
+            tool: name.to_string(),
+            arguments: context.arguments,
+            request_id: context.request_id,
+            metadata: context.metadata,
+            client_id: context.security.client.client_id.clone(),
+            session_id: context.security.client.session_id.clone(),
+            authenticated: context.security.authenticated,
+            capabilities: context.security.capabilities.clone(),
+        };
+        let tool_error = |message: String| McpError::ToolExecution {
+            tool: name.to_string(),
+            message,
+        };
+
+        let _permit = self
+            .pool
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| tool_error("Worker pool is closed".to_string()))?;
+        // Confinement cannot be lifted, so sandboxed calls get a fresh worker
+        let pooled = sandbox.is_none();
+        let idle = if pooled {
+            self.pool.idle.lock().await.pop()
+        } else {
+            None
+        };
+        let mut worker = match idle {
+            Some(worker) => worker,
+            None => Worker::spawn(&self.pool.config, sandbox.as_ref()).await?,
+        };
+
+        let timeout = self.pool.config.request_timeout;
+        let outcome = match tokio::time::timeout(timeout, worker.call(&request)).await {
+            Ok(Ok(outcome)) => outcome,
+            Ok(Err(e)) => {
+                drop(worker);
+                if pooled {
+                    self.pool.replenish();
+                }
+                return Err(tool_error(format!("Isolated worker failed: {}", e)));
+            }
+            Err(_) => {
+                drop(worker);
+                if pooled {
+                    self.pool.replenish();
+                }
+                return Err(tool_error(format!(
+                    "Isolated worker timed out after {:?}",
+                    timeout
+                )));
+            }
+        };
+
+        worker.served += 1;
+        if !pooled {
+            drop(worker);
+        } else if worker.served < self.pool.config.max_requests_per_worker {
+            self.pool.idle.lock().await.push(worker);
+        } else {
+            debug!(
+                "Recycling isolated tool worker after {} calls",
+                worker.served
+            );
+            drop(worker);
+            self.pool.replenish();
+        }
+
+        match outcome {
+            WorkerOutcome::Result(result) => Ok(result),
+            WorkerOutcome::Error(message) => Err(tool_error(message)),
+        }
+    }
+}
+
+#[async_trait]
+impl<R> ToolRegistry for IsolatedToolExecutor<R>
+where
+    R: ToolRegistry,
+{
+    async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        self.registry.list_tools(context).await
+    }
+
+    async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        self.registry.get_tool(name, context).await
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        execution_context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        self.execute_isolated(name, execution_context).await
+    }
+
+    async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
+        self.registry.can_access_tool(name, context).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ToolContent;
+    use std::collections::BTreeMap;
+
+    struct WorkerTools;
+
+    #[async_trait]
+    impl ToolRegistry for WorkerTools {
+        async fn list_tools(&self, _context: &SecurityContext) -> McpResult<Vec<Tool>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_tool(
+            &self,
+            name: &str,
+            _context: &SecurityContext,
+        ) -> McpResult<Option<McpTool>> {
+            let sandbox = SandboxProfile::default()
+                .allow_read(sandbox_dir().join("allowed"))
+                .deny_exec();
+            Ok(matches!(name, "read" | "exec").then(|| {
+                McpTool::new(name, "Sandboxed", serde_json::json!({}), "test").with_sandbox(sandbox)
+            }))
+        }
+
+        async fn execute_tool(
+            &self,
+            name: &str,
+            context: ToolExecutionContext,
+        ) -> McpResult<ToolsCallResult> {
+            match name {
+                "pid" => Ok(ToolsCallResult {
+                    content: vec![ToolContent::Text {
+                        text: std::process::id().to_string(),
+                    }],
+                    is_error: false,
+                    metadata: BTreeMap::from([(
+                        "principal".to_string(),
+                        Value::String(context.security.principal()),
+                    )]),
+                }),
+                "crash" => std::process::exit(3),
+                "read" => {
+                    let path = context.arguments.as_ref().unwrap()["path"]
+                        .as_str()
+                        .unwrap();
+                    let text = std::fs::read_to_string(path)?;
+                    Ok(ToolsCallResult {
+                        content: vec![ToolContent::Text { text }],
+                        is_error: false,
+                        metadata: Default::default(),
+                    })
+                }
+                "exec" => {
+                    std::process::Command::new("true").status()?;
+                    Ok(ToolsCallResult {
+                        content: Vec::new(),
+                        is_error: false,
+                        metadata: Default::default(),
+                    })
+                }
+                _ => Err(McpError::ToolNotFound {
+                    name: name.to_string(),
+                }),
+            }
+        }
+
+        async fn can_access_tool(&self, _name: &str, _context: &SecurityContext) -> bool {
+            true
+        }
+    }
+
+    fn sandbox_dir() -> PathBuf {
+        std::env::temp_dir().join("axum-mcp-sandbox-test")
+    }
+
+    /// Worker entry point, run by the tests below in a child process
+    #[test]
+    fn isolated_worker_entry() {
+        if !is_worker() {
+            return;
+        }
+        enter_sandbox().unwrap();
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run_worker(WorkerTools))
+            .unwrap();
+    }
+
+    async fn pid(executor: &IsolatedToolExecutor<WorkerTools>) -> String {
+        let client = ClientContext {
+            client_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let context = ToolExecutionContext::new(SecurityContext::authenticated(client, vec![]));
+        let result = executor.execute_tool("pid", context).await.unwrap();
+        assert_eq!(result.metadata["principal"], "alice");
+        match &result.content[0] {
+            ToolContent::Text { text } => text.clone(),
+            other => panic!("unexpected content {:?}", other),
+        }
+    }
+
+    fn worker_config() -> IsolationConfig {
+        IsolationConfig::default()
+            .with_command(
+                std::env::current_exe().unwrap(),
+                [
+                    "server::isolation::tests::isolated_worker_entry",
+                    "--exact",
+                    // Keeps the harness from printing on response lines
+                    "--quiet",
+                    "--nocapture",
+                ],
+            )
+            .with_workers(1)
+    }
+
+    #[tokio::test]
+    async fn test_workers_are_recycled_and_replaced() {
+        let config = IsolationConfig {
+            max_requests_per_worker: 2,
+            ..worker_config()
+        };
+        let executor = IsolatedToolExecutor::start(WorkerTools, config)
+            .await
+            .unwrap();
+
+        let first = pid(&executor).await;
+        assert_ne!(first, std::process::id().to_string());
+        assert_eq!(pid(&executor).await, first);
+        // The worker served its two calls and was replaced
+        let second = pid(&executor).await;
+        assert_ne!(second, first);
+
+        // A crash takes down the worker, not the server
+        let error = executor
+            .execute_tool(
+                "crash",
+                ToolExecutionContext::new(SecurityContext::system()),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Isolated worker failed"));
+        let third = pid(&executor).await;
+        assert_ne!(third, second);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "sandbox"))]
+    #[tokio::test]
+    async fn test_sandboxed_tools_are_confined() {
+        let (allowed, denied) = (sandbox_dir().join("allowed"), sandbox_dir().join("denied"));
+        for dir in [&allowed, &denied] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("file.txt"), "contents").unwrap();
+        }
+        let executor = IsolatedToolExecutor::start(WorkerTools, worker_config())
+            .await
+            .unwrap();
+        let call = |name: &'static str, path: PathBuf| {
+            let mut context = ToolExecutionContext::new(SecurityContext::system());
+            context.arguments = Some(serde_json::json!({ "path": path }));
+            executor.execute_tool(name, context)
+        };
+
+        let result = call("read", allowed.join("file.txt")).await.unwrap();
+        assert_eq!(
+            result.content,
+            [ToolContent::Text {
+                text: "contents".to_string()
+            }]
+        );
+        assert!(call("read", denied.join("file.txt")).await.is_err());
+        assert!(call("exec", allowed.clone()).await.is_err());
+
+        // Sandboxed calls leave the pool's worker alone
+        assert_eq!(executor.idle_workers().await, 1);
+    }
+}
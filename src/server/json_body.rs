@@ -0,0 +1,169 @@
+//! JSON decoding of HTTP request bodies
+//!
+//! Request bodies are parsed with serde_json. With the `simd-json` feature,
+//! bodies of at least [`SIMD_THRESHOLD`] bytes, such as large batches and big
+//! tool arguments, are parsed with simd-json instead. simd-json parses in
+//! place, so the body is copied first; below the threshold that copy costs
+//! more than the faster parse saves.
+//!
+//! Both paths accept the same documents and produce the same values. Whenever
+//! simd-json rejects a body, or the body nests deeper than serde_json allows,
+//! it is parsed again with serde_json, so errors are always serde_json's.
+//! Bodies that simd-json reads differently also go to serde_json: negative
+//! zeros, which serde_json reads as floats and simd-json as the integer zero,
+//! and `\u` escapes of UTF-16 surrogates, which serde_json validates.
+
+use serde_json::Value;
+
+/// Body size from which the `simd-json` feature parses with simd-json
+pub const SIMD_THRESHOLD: usize = 16 * 1024;
+
+/// Parse a request body as JSON
+pub fn parse_json_body(body: &[u8]) -> serde_json::Result<Value> {
+    #[cfg(feature = "simd-json")]
+    if body.len() >= SIMD_THRESHOLD {
+        if let Some(value) = simd::parse(body) {
+            return Ok(value);
+        }
+    }
+    serde_json::from_slice(body)
+}
+
+#[cfg(feature = "simd-json")]
+mod simd {
+    use serde_json::Value;
+    use simd_json::{Node, Tape};
+
+    /// Nesting serde_json accepts before failing with a recursion limit error
+    pub(super) const MAX_DEPTH: usize = 127;
+
+    /// Parse `body` with simd-json, or `None` to leave it to serde_json
+    pub(super) fn parse(body: &[u8]) -> Option<Value> {
+        if diverges(body) {
+            return None;
+        }
+        let mut input = body.to_vec();
+        let tape = simd_json::to_tape(&mut input).ok()?;
+        // Deserializing from the tape recurses, unlike serde_json's limited parser
+        if depth(&tape) > MAX_DEPTH {
+            return None;
+        }
+        tape.deserialize().ok()
+    }
+
+    /// Whether `body` may hold a negative zero or a surrogate escape
+    fn diverges(body: &[u8]) -> bool {
+        (0..body.len()).any(|i| match body[i..] {
+            [b'-', b'0', ref rest @ ..] => {
+                let number = i == 0
+                    || matches!(
+                        body[i - 1],
+                        b':' | b',' | b'[' | b' ' | b'\t' | b'\n' | b'\r'
+                    );
+                number && rest.first() != Some(&b'.')
+            }
+            [b'\\', b'u', b'd' | b'D', b'8'..=b'9' | b'a'..=b'f' | b'A'..=b'F', ..] => true,
+            _ => false,
+        })
+    }
+
+    /// Deepest nesting of arrays and objects on `tape`
+    fn depth(tape: &Tape) -> usize {
+        // End index of each open array or object
+        let mut open: Vec<usize> = Vec::new();
+        let mut deepest = 0;
+        for (i, node) in tape.0.iter().enumerate() {
+            while open.last().is_some_and(|&end| end <= i) {
+                open.pop();
+            }
+            if let Node::Array { count, .. } | Node::Object { count, .. } = node {
+                open.push(i + count + 1);
+                deepest = deepest.max(open.len());
+            }
+        }
+        deepest
+    }
+}
+
+#[cfg(all(test, feature = "simd-json"))]
+mod tests {
+    use super::*;
+
+    /// Pad `json` past the threshold without changing its value
+    fn large(json: &str) -> Vec<u8> {
+        let mut body = json.as_bytes().to_vec();
+        body.extend(std::iter::repeat_n(b' ', SIMD_THRESHOLD));
+        body
+    }
+
+    fn assert_equivalent(json: &str) {
+        assert_bytes_equivalent(&large(json));
+    }
+
+    fn assert_bytes_equivalent(body: &[u8]) {
+        let text = String::from_utf8_lossy(&body[..body.len().min(200)]);
+        match (parse_json_body(body), serde_json::from_slice::<Value>(body)) {
+            (Ok(simd), Ok(serde)) => assert_eq!(simd, serde, "{}", text),
+            (Err(simd), Err(serde)) => assert_eq!(simd.to_string(), serde.to_string()),
+            (simd, serde) => panic!("{}: {:?} != {:?}", text, simd, serde),
+        }
+    }
+
+    #[test]
+    fn test_simd_parsing_matches_serde_json() {
+        let batch: Vec<Value> = (0..500)
+            .map(|i| {
+                serde_json::json!({
+                    "id": format!("item-{}", i),
+                    "method": "tools/call",
+                    "params": {"name": "search", "arguments": {"query": "ünïcödé \u{1F600}", "limit": i}}
+                })
+            })
+            .collect();
+        let batch = serde_json::json!({"jsonrpc": "2.0", "method": "batch", "params": {"requests": batch}, "id": 1});
+        assert!(batch.to_string().len() > SIMD_THRESHOLD);
+        let body = batch.to_string().into_bytes();
+        assert_eq!(simd::parse(&body).unwrap(), batch);
+        assert_eq!(parse_json_body(&body).unwrap(), batch);
+        // Truncated and corrupted documents fail the same way
+        for cut in (SIMD_THRESHOLD..body.len()).step_by(1009) {
+            assert_bytes_equivalent(&body[..cut]);
+            let mut corrupted = body.clone();
+            corrupted[cut] = b'}';
+            assert_bytes_equivalent(&corrupted);
+        }
+
+        let corpus = [
+            r#"{"a": 1, "a": 2}"#,
+            r#"[0, -0, -0.0, 1e3, 1.5e-7, 0.1, 2.2250738585072014e-308, 1.7976931348623157e308]"#,
+            r#"[-0e1, -0E-2, -0.0e1, "item-0", "2024-01-02"]"#,
+            r#"[9007199254740993, 18446744073709551615, -9223372036854775808]"#,
+            r#"[18446744073709551616, 1e400, -1e400]"#,
+            r#""tab\tquote\"escape\\slash\/é😀""#,
+            r#""\ud800""#,
+            r#""\ud83d\ude00 \u00e9""#,
+            r#""\x""#,
+            "\"raw\ttab\"",
+            r#"[1, 2,]"#,
+            r#"{"a": 1} trailing"#,
+            r#"[01]"#,
+            r#"[1.]"#,
+            r#"[NaN]"#,
+            r#"{"unterminated": "#,
+            "",
+        ];
+        for json in corpus {
+            assert_equivalent(json);
+        }
+        for depth in [
+            simd::MAX_DEPTH - 1,
+            simd::MAX_DEPTH,
+            simd::MAX_DEPTH + 1,
+            10_000,
+        ] {
+            assert_equivalent(&format!("{}{}", "[".repeat(depth), "]".repeat(depth)));
+            let object = format!("{}1{}", r#"{"a":"#.repeat(depth), "}".repeat(depth));
+            assert_equivalent(&object);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Legacy HTTP+SSE transport sessions
+//!
+//! Clients built against the 2024-11-05 transport open an SSE stream first,
+//! receive an `endpoint` event naming a message URL, POST JSON-RPC requests to
+//! that URL and read the responses back from the stream as `message` events.
+//! This module tracks the open streams so responses can be routed to them.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::mpsc;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::JsonRpcResponse,
+};
+
+/// Registry of open legacy SSE streams, keyed by session ID
+#[derive(Clone, Default)]
+pub struct LegacySseSessions {
+    sessions: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<JsonRpcResponse>>>>,
+}
+
+impl LegacySseSessions {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new stream, returning its session and the response receiver
+    ///
+    /// The session is removed when the returned [`LegacySseSession`] is dropped.
+    pub fn open(&self) -> (LegacySseSession, mpsc::UnboundedReceiver<JsonRpcResponse>) {
+        let session_id = Uuid::new_v4().to_string();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.lock().insert(session_id.clone(), sender);
+        debug!("Opened legacy SSE session {}", session_id);
+
+        let session = LegacySseSession {
+            session_id,
+            sessions: self.clone(),
+        };
+        (session, receiver)
+    }
+
+    /// Whether a stream is open for the session
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.lock().contains_key(session_id)
+    }
+
+    /// Number of open legacy streams
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no legacy streams are open
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Deliver a response to the session's SSE stream
+    pub fn send(&self, session_id: &str, response: JsonRpcResponse) -> McpResult<()> {
+        let sessions = self.lock();
+        let sender = sessions.get(session_id).ok_or_else(|| McpError::Session {
+            message: format!("Session not found: {}", session_id),
+        })?;
+        sender.send(response).map_err(|_| McpError::Transport {
+            message: format!("SSE stream for session {} is closed", session_id),
+        })
+    }
+
+    fn lock(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<String, mpsc::UnboundedSender<JsonRpcResponse>>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Handle for an open legacy SSE stream; unregisters the session on drop
+pub struct LegacySseSession {
+    session_id: String,
+    sessions: LegacySseSessions,
+}
+
+impl LegacySseSession {
+    /// Session ID clients pass back on the message endpoint
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+}
+
+impl Drop for LegacySseSession {
+    fn drop(&mut self) {
+        self.sessions.lock().remove(&self.session_id);
+        debug!("Closed legacy SSE session {}", self.session_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let sessions = LegacySseSessions::new();
+        let (session, mut receiver) = sessions.open();
+        let session_id = session.session_id().to_string();
+        assert!(sessions.contains(&session_id));
+
+        let response = JsonRpcResponse::success(serde_json::json!({}), None);
+        sessions.send(&session_id, response.clone()).unwrap();
+        assert_eq!(receiver.recv().await.unwrap().result, response.result);
+
+        drop(session);
+        assert!(sessions.is_empty());
+        assert!(sessions.send(&session_id, response).is_err());
+    }
+}
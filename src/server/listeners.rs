@@ -0,0 +1,654 @@
+//! Serving one server on several listeners
+//!
+//! A [`TransportSet`] runs the same [`McpServer`] on any mix of TCP, TLS, Unix
+//! domain socket and stdio [`Listener`]s, for example stdio for a local agent
+//! host, a Unix socket for operators and HTTPS for remote clients. Sessions,
+//! notifications and registries are shared; each listener brings its own:
+//!
+//! - authentication, an [`McpAuth`] that turns the request's
+//!   [`ClientContext`] into the [`SecurityContext`] its requests run with
+//!   (the `Authorization` header is in the metadata under
+//!   [`AUTHORIZATION_KEY`], and the signature headers and body for a
+//!   [`SignedRequestAuth`](crate::security::SignedRequestAuth)),
+//! - optionally guest access for clients without an `Authorization` header
+//!   (see [`anonymous`](crate::security::anonymous)),
+//! - a rate limit per client ID, or per remote IP address for clients
+//!   without one,
+//! - the exposure profiles its clients see (see
+//!   [`exposure`](crate::server::exposure)).
+//!
+//! [`TransportSet::serve`] stops every listener when its shutdown future
+//! resolves or when any listener stops, such as stdio reaching the end of
+//! its input.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::{future::Future, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::watch,
+    task::JoinSet,
+};
+use tracing::{debug, info, warn};
+
+#[cfg(feature = "tls")]
+use super::config::TlsConfig;
+use crate::{
+    axum_integration::McpServerWrapper,
+    error::{McpError, McpResult},
+    protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse},
+    security::{
+        auth::EXPOSURE_KEY,
+        fingerprint::{REMOTE_ADDR_KEY, REMOTE_IP_KEY, TRANSPORT_KEY},
+        signature::{
+            NONCE_HEADER, NONCE_KEY, REQUEST_BODY_KEY, SIGNATURE_HEADER, SIGNATURE_KEY,
+            TIMESTAMP_HEADER, TIMESTAMP_KEY,
+        },
+        AnonymousAuth, ClientContext, McpAuth, RateLimitConfig, RateLimiter, SecurityContext,
+    },
+    server::{exposure::LISTENER_KEY, service::McpServer, McpServerState},
+    transport::{InMemoryEventStore, SessionManager},
+};
+
+/// Client metadata key for the `Authorization` header of a request
+pub const AUTHORIZATION_KEY: &str = "authorization";
+
+/// How long listeners may finish open requests after shutdown by default
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where a listener accepts clients
+#[derive(Debug, Clone)]
+enum Endpoint {
+    Tcp(String),
+    #[cfg(feature = "tls")]
+    Tls(String, TlsConfig),
+    #[cfg(unix)]
+    Unix(PathBuf),
+    Stdio,
+}
+
+/// One listener of a [`TransportSet`]
+#[derive(Clone)]
+pub struct Listener {
+    name: String,
+    endpoint: Endpoint,
+    auth: Option<Arc<dyn McpAuth>>,
+    guest: Option<AnonymousAuth>,
+    rate_limit: Option<RateLimitConfig>,
+    profiles: Option<Vec<String>>,
+}
+
+impl std::fmt::Debug for Listener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Listener")
+            .field("name", &self.name)
+            .field("endpoint", &self.endpoint)
+            .field("guest", &self.guest.as_ref().map(AnonymousAuth::config))
+            .field("rate_limit", &self.rate_limit)
+            .field("profiles", &self.profiles)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Listener {
+    fn new(name: impl Into<String>, endpoint: Endpoint) -> Self {
+        Self {
+            name: name.into(),
+            endpoint,
+            auth: None,
+            guest: None,
+            rate_limit: None,
+            profiles: None,
+        }
+    }
+
+    /// Serve HTTP on a TCP address such as `127.0.0.1:3000`
+    pub fn tcp(name: impl Into<String>, address: impl Into<String>) -> Self {
+        Self::new(name, Endpoint::Tcp(address.into()))
+    }
+
+    /// Serve HTTPS on a TCP address
+    #[cfg(feature = "tls")]
+    pub fn tls(name: impl Into<String>, address: impl Into<String>, tls: TlsConfig) -> Self {
+        Self::new(name, Endpoint::Tls(address.into(), tls))
+    }
+
+    /// Serve HTTP on a Unix domain socket created at `path`
+    #[cfg(unix)]
+    pub fn unix(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self::new(name, Endpoint::Unix(path.into()))
+    }
+
+    /// Answer newline-delimited JSON-RPC requests on stdin and stdout
+    pub fn stdio(name: impl Into<String>) -> Self {
+        Self::new(name, Endpoint::Stdio)
+    }
+
+    /// Authenticate this listener's clients with `auth`
+    ///
+    /// Without one, clients get an authenticated context, as over plain HTTP.
+    pub fn with_auth(mut self, auth: impl McpAuth + 'static) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Admit clients without an `Authorization` header as guests of `guest`
+    ///
+    /// Clients sending one are still authenticated by the listener's auth.
+    pub fn with_guest_access(mut self, guest: AnonymousAuth) -> Self {
+        self.guest = Some(guest);
+        self
+    }
+
+    /// Limit how often each client of this listener may send requests
+    pub fn with_rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Only show this listener's clients items with one of `profiles`
+    ///
+    /// Takes precedence over the listener entries of the server's
+    /// [`ExposureConfig`](crate::server::ExposureConfig).
+    pub fn with_exposure<I, P>(mut self, profiles: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<String>,
+    {
+        self.profiles = Some(profiles.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Name of this listener, recorded as `listener` in the client metadata
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Authentication, rate limit and exposure of one listener
+struct ListenerPolicy {
+    name: String,
+    auth: Option<Arc<dyn McpAuth>>,
+    guest: Option<AnonymousAuth>,
+    rate_limiter: Option<RateLimiter>,
+    profiles: Option<String>,
+}
+
+impl ListenerPolicy {
+    fn new(listener: &Listener) -> Self {
+        Self {
+            name: listener.name.clone(),
+            auth: listener.auth.clone(),
+            guest: listener.guest.clone(),
+            rate_limiter: listener.rate_limit.clone().map(RateLimiter::new),
+            profiles: listener
+                .profiles
+                .as_ref()
+                .map(|profiles| profiles.join(",")),
+        }
+    }
+
+    /// Authenticate and rate limit a client of this listener
+    async fn security_context(&self, client: ClientContext) -> McpResult<SecurityContext> {
+        let guest = self
+            .guest
+            .as_ref()
+            .filter(|_| self.auth.is_none() || !client.metadata.contains_key(AUTHORIZATION_KEY));
+        let mut context = match (guest, &self.auth) {
+            (Some(guest), _) => guest.authenticate(&client).await?,
+            (None, Some(auth)) => auth.authenticate(&client).await?,
+            (None, None) => SecurityContext::authenticated(client.clone(), Vec::new()),
+        };
+
+        // Keep what the transport knows about the client, but not credentials
+        for (key, value) in client.metadata {
+            if !CREDENTIAL_KEYS.contains(&key.as_str()) {
+                context.client.metadata.entry(key).or_insert(value);
+            }
+        }
+        for key in CREDENTIAL_KEYS {
+            context.client.metadata.remove(*key);
+        }
+        context
+            .client
+            .metadata
+            .insert(LISTENER_KEY.to_string(), self.name.clone());
+        if let Some(profiles) = &self.profiles {
+            // Profiles chosen by the auth, such as a guest's, are only narrowed
+            let profiles = match context.client.metadata.get(EXPOSURE_KEY) {
+                Some(own) => own
+                    .split(',')
+                    .filter(|profile| profiles.split(',').any(|p| p == *profile))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                None => profiles.clone(),
+            };
+            context
+                .client
+                .metadata
+                .insert(EXPOSURE_KEY.to_string(), profiles);
+        }
+
+        if let Some(limiter) = &self.rate_limiter {
+            let key = context
+                .client
+                .client_id
+                .clone()
+                .or_else(|| context.client.metadata.get(REMOTE_IP_KEY).cloned())
+                .unwrap_or_else(|| context.principal());
+            limiter.check_rate_limit(&key).await?;
+        }
+        Ok(context)
+    }
+}
+
+/// Client metadata that only authentication gets to see
+const CREDENTIAL_KEYS: &[&str] = &[AUTHORIZATION_KEY, SIGNATURE_KEY, REQUEST_BODY_KEY];
+
+/// Authenticate HTTP requests, leaving the context in the request extensions
+async fn enforce_policy(
+    State(policy): State<Arc<ListenerPolicy>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    // Buffer the body for auths that verify it, such as request signatures
+    let mut body = None;
+    if let Some(limit) = policy
+        .auth
+        .as_ref()
+        .and_then(|auth| auth.request_body_limit())
+    {
+        let (parts, content) = request.into_parts();
+        let bytes = match axum::body::to_bytes(content, limit).await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                return McpError::Validation {
+                    message: format!("Request body exceeds {} bytes", limit),
+                }
+                .into_response()
+            }
+        };
+        body = String::from_utf8(bytes.to_vec()).ok();
+        request = Request::from_parts(parts, axum::body::Body::from(bytes));
+    }
+
+    let headers = request.headers();
+    let mut client = ClientContext {
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("unknown")
+            .to_string(),
+        ..Default::default()
+    };
+    for (name, key) in [
+        (header::AUTHORIZATION.as_str(), AUTHORIZATION_KEY),
+        (TIMESTAMP_HEADER, TIMESTAMP_KEY),
+        (NONCE_HEADER, NONCE_KEY),
+        (SIGNATURE_HEADER, SIGNATURE_KEY),
+    ] {
+        if let Some(value) = headers.get(name).and_then(|v| v.to_str().ok()) {
+            client.metadata.insert(key.to_string(), value.to_string());
+        }
+    }
+    if let Some(body) = body {
+        client.metadata.insert(REQUEST_BODY_KEY.to_string(), body);
+    }
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        client
+            .metadata
+            .insert(REMOTE_ADDR_KEY.to_string(), addr.to_string());
+        client
+            .metadata
+            .insert(REMOTE_IP_KEY.to_string(), addr.ip().to_string());
+    }
+    #[cfg(unix)]
+    if let Some(peer) = super::uds::PeerCredentials::from_extensions(request.extensions()) {
+        peer.apply_to(&mut client);
+    }
+
+    match policy.security_context(client).await {
+        Ok(context) => {
+            request.extensions_mut().insert(context);
+            next.run(request).await
+        }
+        Err(e) => {
+            debug!("Request rejected by listener {}: {}", policy.name, e);
+            e.into_response()
+        }
+    }
+}
+
+/// Runs one server on several listeners with a shared shutdown
+pub struct TransportSet<S: McpServerState> {
+    server: McpServer<S>,
+    sessions: SessionManager,
+    router: Router,
+    listeners: Vec<Listener>,
+    shutdown_timeout: Duration,
+}
+
+impl<S: McpServerState> TransportSet<S> {
+    /// Serve `server` with sessions configured by its `session` settings
+    ///
+    /// Sessions expire on the server's [`Clock`](crate::Clock).
+    pub fn new(server: McpServer<S>) -> Self {
+        let session = &server.config().session;
+        let events = Arc::new(InMemoryEventStore::new(session.max_events, session.timeout));
+        let sessions = SessionManager::new(events, session.timeout, session.cleanup_interval)
+            .with_clock(server.clock().clone());
+        Self::with_session_manager(server, sessions)
+    }
+
+    /// Serve `server` with the given session manager
+    pub fn with_session_manager(server: McpServer<S>, sessions: SessionManager) -> Self {
+        let sessions = sessions
+            .with_wire_tap(server.wire_tap().clone())
+            .with_ids(server.ids().clone());
+        let router =
+            McpServerWrapper::with_session_manager(server.clone(), sessions.clone()).into_router();
+        Self {
+            server,
+            sessions,
+            router,
+            listeners: Vec::new(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+        }
+    }
+
+    /// Serve `router` on the HTTP listeners instead of the plain MCP routes
+    ///
+    /// Use this to add routes with
+    /// [`McpServerWrapper::into_router_with`]; the wrapper should share this
+    /// set's session manager.
+    pub fn with_router(mut self, router: Router) -> Self {
+        self.router = router;
+        self
+    }
+
+    /// Add a listener
+    pub fn listen(mut self, listener: Listener) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Give open requests up to `timeout` to finish once shutdown starts
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Session manager shared by the HTTP listeners
+    pub fn session_manager(&self) -> &SessionManager {
+        &self.sessions
+    }
+
+    /// Serve on every listener until `shutdown` resolves or a listener stops
+    ///
+    /// Listeners are bound before any of them starts serving, so an address
+    /// in use fails the whole set. Returns the first listener error, if any.
+    pub async fn serve(self, shutdown: impl Future<Output = ()> + Send + 'static) -> McpResult<()> {
+        if self.listeners.is_empty() {
+            return Err(McpError::Configuration {
+                message: "TransportSet has no listeners".to_string(),
+            });
+        }
+        if self
+            .listeners
+            .iter()
+            .filter(|listener| matches!(listener.endpoint, Endpoint::Stdio))
+            .count()
+            > 1
+        {
+            return Err(McpError::Configuration {
+                message: "Only one stdio listener can be served".to_string(),
+            });
+        }
+
+        let (stop, stopped) = watch::channel(false);
+        let mut tasks = JoinSet::new();
+        for listener in &self.listeners {
+            let policy = Arc::new(ListenerPolicy::new(listener));
+            let app = self
+                .router
+                .clone()
+                .layer(axum::middleware::from_fn_with_state(
+                    Arc::clone(&policy),
+                    enforce_policy,
+                ));
+            let stopped = wait_for_stop(stopped.clone());
+            match &listener.endpoint {
+                Endpoint::Tcp(address) => {
+                    let tcp = TcpListener::bind(address).await?;
+                    info!("Listener {} serving MCP on {}", listener.name, address);
+                    tasks.spawn(async move {
+                        axum::serve(tcp, app.into_make_service_with_connect_info::<SocketAddr>())
+                            .with_graceful_shutdown(stopped)
+                            .await
+                            .map_err(McpError::from)
+                    });
+                }
+                #[cfg(feature = "tls")]
+                Endpoint::Tls(address, tls) => {
+                    let tcp = TcpListener::bind(address).await?;
+                    info!(
+                        "Listener {} serving MCP on https://{}",
+                        listener.name, address
+                    );
+                    let tls = tls.clone();
+                    tasks.spawn(async move {
+                        tokio::select! {
+                            result = super::tls::serve_tls(tcp, app, &tls) => result,
+                            _ = stopped => Ok(()),
+                        }
+                    });
+                }
+                #[cfg(unix)]
+                Endpoint::Unix(path) => {
+                    let uds = super::uds::bind_uds(path)?;
+                    info!(
+                        "Listener {} serving MCP on unix:{}",
+                        listener.name,
+                        path.display()
+                    );
+                    tasks.spawn(async move {
+                        axum::serve(
+                            uds,
+                            app.into_make_service_with_connect_info::<super::uds::UdsConnectInfo>(),
+                        )
+                        .with_graceful_shutdown(stopped)
+                        .await
+                        .map_err(McpError::from)
+                    });
+                }
+                Endpoint::Stdio => {
+                    info!("Listener {} serving MCP on stdio", listener.name);
+                    let server = self.server.clone();
+                    tasks.spawn(async move {
+                        tokio::select! {
+                            result = serve_stdio(server, policy) => result,
+                            _ = stopped => Ok(()),
+                        }
+                    });
+                }
+            }
+        }
+
+        let delivery = self
+            .server
+            .notifications()
+            .deliver_to(self.sessions.clone());
+        let cleanup = self.sessions.start_cleanup_task();
+
+        let mut result = tokio::select! {
+            _ = shutdown => Ok(()),
+            Some(finished) = tasks.join_next() => flatten(finished),
+        };
+        info!("Stopping {} listeners", tasks.len());
+        let _ = stop.send(true);
+
+        let drained = tokio::time::timeout(self.shutdown_timeout, async {
+            let mut result = Ok(());
+            while let Some(finished) = tasks.join_next().await {
+                if let Err(e) = flatten(finished) {
+                    warn!("Listener failed during shutdown: {}", e);
+                    result = result.and(Err(e));
+                }
+            }
+            result
+        })
+        .await;
+        match drained {
+            Ok(drained) => result = result.and(drained),
+            Err(_) => {
+                warn!("Listeners did not stop in time, closing open connections");
+                tasks.shutdown().await;
+            }
+        }
+
+        delivery.abort();
+        cleanup.abort();
+        result
+    }
+}
+
+/// Resolves once `stop` is set
+async fn wait_for_stop(mut stopped: watch::Receiver<bool>) {
+    let _ = stopped.wait_for(|stop| *stop).await;
+}
+
+fn flatten(finished: Result<McpResult<()>, tokio::task::JoinError>) -> McpResult<()> {
+    finished.map_err(|e| McpError::Internal {
+        message: format!("Listener task failed: {}", e),
+        source: None,
+    })?
+}
+
+/// Answer newline-delimited JSON-RPC on stdin until it closes
+async fn serve_stdio<S: McpServerState>(
+    server: McpServer<S>,
+    policy: Arc<ListenerPolicy>,
+) -> McpResult<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    // The process serves a single client for its whole lifetime
+    let mut client = ClientContext {
+        user_agent: "stdio".to_string(),
+        session_id: Some(uuid::Uuid::new_v4().to_string()),
+        ..Default::default()
+    };
+    client
+        .metadata
+        .insert(TRANSPORT_KEY.to_string(), "stdio".to_string());
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = answer_line(&server, &policy, &client, &line).await else {
+            continue;
+        };
+        let mut output = serde_json::to_vec(&response)?;
+        output.push(b'\n');
+        stdout.write_all(&output).await?;
+        stdout.flush().await?;
+    }
+    debug!("Listener {} reached the end of stdin", policy.name);
+    Ok(())
+}
+
+/// Answer one line of a stdio listener; notifications get no answer
+async fn answer_line<S: McpServerState>(
+    server: &McpServer<S>,
+    policy: &ListenerPolicy,
+    client: &ClientContext,
+    line: &str,
+) -> Option<JsonRpcResponse> {
+    let request: JsonRpcRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return Some(JsonRpcResponse::error(
+                JsonRpcError::parse_error(Some(serde_json::json!(e.to_string()))),
+                None,
+            ))
+        }
+    };
+    let is_notification = request.id.is_none();
+    let response = match policy.security_context(client.clone()).await {
+        Ok(context) => server.handle_request(request, context).await,
+        Err(e) => JsonRpcResponse::error(e.into(), request.id),
+    };
+    (!is_notification).then_some(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::exposure::PUBLIC;
+
+    #[tokio::test]
+    async fn test_listener_policy() {
+        let listener = Listener::stdio("local")
+            .with_rate_limit(RateLimitConfig::per_minute(1))
+            .with_exposure([PUBLIC]);
+        let policy = ListenerPolicy::new(&listener);
+
+        let mut client = ClientContext::default();
+        client
+            .metadata
+            .insert(AUTHORIZATION_KEY.to_string(), "Bearer secret".to_string());
+        let context = policy.security_context(client.clone()).await.unwrap();
+        assert_eq!(context.client.metadata[LISTENER_KEY], "local");
+        assert!(!context.client.metadata.contains_key(AUTHORIZATION_KEY));
+        assert!(context.exposes(PUBLIC));
+        assert!(!context.exposes("admin"));
+
+        assert!(matches!(
+            policy.security_context(client).await,
+            Err(McpError::RateLimitExceeded { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_guest_access() {
+        struct TokenAuth;
+
+        #[async_trait::async_trait]
+        impl McpAuth for TokenAuth {
+            async fn authenticate(&self, client: &ClientContext) -> McpResult<SecurityContext> {
+                Ok(SecurityContext::authenticated(client.clone(), Vec::new()))
+            }
+
+            async fn authorize(&self, _: &SecurityContext, _: &str, _: &str) -> bool {
+                true
+            }
+        }
+
+        let listener = Listener::tcp("demo", "127.0.0.1:0")
+            .with_auth(TokenAuth)
+            .with_guest_access(AnonymousAuth::new())
+            .with_exposure([PUBLIC, "internal"]);
+        let policy = ListenerPolicy::new(&listener);
+
+        let guest = policy
+            .security_context(ClientContext::default())
+            .await
+            .unwrap();
+        assert!(guest.is_guest());
+        assert!(guest.exposes(PUBLIC) && !guest.exposes("internal"));
+
+        let mut client = ClientContext::default();
+        client
+            .metadata
+            .insert(AUTHORIZATION_KEY.to_string(), "Bearer secret".to_string());
+        let member = policy.security_context(client).await.unwrap();
+        assert!(!member.is_guest() && member.exposes("internal"));
+    }
+}
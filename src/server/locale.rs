@@ -0,0 +1,300 @@
+//! Client locale and time zone
+//!
+//! Clients can send their locale and time zone with `initialize`, as the
+//! `locale` and `timezone` fields of `clientInfo`:
+//!
+//! ```json
+//! {"clientInfo": {"name": "desk", "version": "1.0", "locale": "de-CH", "timezone": "Europe/Zurich"}}
+//! ```
+//!
+//! They are kept in the session's [`ClientFingerprint`](crate::security::ClientFingerprint),
+//! so every later request of the session carries them in its
+//! [`SecurityContext`](crate::security::SecurityContext). Tools format dates
+//! and numbers for the client with the [`ClientLocale`] from
+//! [`ToolExecutionContext::locale`](crate::server::ToolExecutionContext::locale),
+//! and prompts of an [`InMemoryPromptRegistry`](crate::server::InMemoryPromptRegistry)
+//! can use `{{locale}}` and `{{timezone}}`.
+//!
+//! Time zones are UTC offsets such as `+02:00`, or with the `timezones`
+//! feature IANA names such as `Europe/Zurich`. Unknown time zones are ignored.
+
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::InitializeParams;
+
+/// `clientInfo` field holding the client's locale
+pub const LOCALE_FIELD: &str = "locale";
+
+/// `clientInfo` field holding the client's time zone
+pub const TIMEZONE_FIELD: &str = "timezone";
+
+/// Locale of clients that did not send one
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Locale and time zone of a client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientLocale {
+    /// BCP 47 language tag, such as `de-CH`
+    pub locale: String,
+
+    /// UTC offset or IANA time zone name, UTC if `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl Default for ClientLocale {
+    fn default() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_string(),
+            timezone: None,
+        }
+    }
+}
+
+impl ClientLocale {
+    /// A locale such as `de-CH` or `de_CH.UTF-8`, in UTC
+    pub fn new(locale: &str) -> Self {
+        Self {
+            locale: normalize_locale(locale).unwrap_or_else(|| DEFAULT_LOCALE.to_string()),
+            timezone: None,
+        }
+    }
+
+    /// Use the time zone `timezone`, if it is known
+    pub fn with_timezone(mut self, timezone: &str) -> Self {
+        self.timezone = is_known_timezone(timezone).then(|| timezone.to_string());
+        self
+    }
+
+    /// Locale and time zone sent in `clientInfo`, if the client sent either
+    pub fn from_initialize(params: &InitializeParams) -> Option<Self> {
+        let info = params.client_info.as_ref()?;
+        let field = |name: &str| info.metadata.get(name).and_then(|v| v.as_str());
+        let locale = field(LOCALE_FIELD).and_then(normalize_locale);
+        let timezone = field(TIMEZONE_FIELD).filter(|tz| is_known_timezone(tz));
+        if locale.is_none() && timezone.is_none() {
+            return None;
+        }
+        Some(Self {
+            locale: locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string()),
+            timezone: timezone.map(str::to_string),
+        })
+    }
+
+    /// Primary language subtag, such as `de` for `de-CH`
+    pub fn language(&self) -> &str {
+        self.locale.split('-').next().unwrap_or_default()
+    }
+
+    /// Region subtag, such as `CH` for `de-CH`
+    pub fn region(&self) -> Option<&str> {
+        self.locale
+            .split('-')
+            .skip(1)
+            .find(|subtag| subtag.len() == 2 || subtag.chars().all(|c| c.is_ascii_digit()))
+    }
+
+    /// Offset of the client's time zone from UTC at `at`
+    pub fn offset_at(&self, at: DateTime<Utc>) -> FixedOffset {
+        let utc = FixedOffset::east_opt(0).unwrap();
+        match &self.timezone {
+            Some(timezone) => timezone_offset(timezone, at).unwrap_or(utc),
+            None => utc,
+        }
+    }
+
+    /// `at` in the client's time zone
+    pub fn local_time(&self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        at.with_timezone(&self.offset_at(at))
+    }
+
+    /// Date and time of `at` as the client writes them, such as
+    /// `17.10.2026 14:30` for `de-CH`
+    pub fn format_datetime(&self, at: DateTime<Utc>) -> String {
+        let (date, time) = self.patterns();
+        let local = self.local_time(at);
+        format!("{} {}", local.format(date), local.format(time))
+    }
+
+    /// Date of `at` as the client writes it, such as `10/17/2026` for `en-US`
+    pub fn format_date(&self, at: DateTime<Utc>) -> String {
+        self.local_time(at).format(self.patterns().0).to_string()
+    }
+
+    /// `value` with `decimals` decimals and the client's separators, such as
+    /// `1’234.50` for `de-CH`
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let (group, decimal) = self.separators();
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut formatted = String::new();
+        if value.is_sign_negative() && digits.chars().any(|c| c != '0' && c != '.') {
+            formatted.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push_str(group);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push_str(decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+
+    /// Digit group and decimal separators
+    fn separators(&self) -> (&'static str, &'static str) {
+        match (self.language(), self.region()) {
+            ("de" | "it" | "rm", Some("CH" | "LI")) => ("’", "."),
+            ("es", Some("MX" | "US")) => (",", "."),
+            (
+                "de" | "nl" | "it" | "es" | "pt" | "id" | "da" | "tr" | "el" | "ro" | "hr" | "sl"
+                | "sr" | "vi",
+                _,
+            ) => (".", ","),
+            ("fr", _) => ("\u{202f}", ","),
+            (
+                "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" | "bg" | "lt"
+                | "lv" | "et",
+                _,
+            ) => ("\u{a0}", ","),
+            _ => (",", "."),
+        }
+    }
+
+    /// `strftime` patterns of dates and times
+    fn patterns(&self) -> (&'static str, &'static str) {
+        match (self.language(), self.region()) {
+            ("en", None | Some("US")) => ("%m/%d/%Y", "%-I:%M %p"),
+            ("en" | "fr" | "es" | "it" | "pt" | "el" | "vi", _) => ("%d/%m/%Y", "%H:%M"),
+            (
+                "de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "no" | "da" | "tr" | "uk" | "ro"
+                | "hr" | "sl" | "sr" | "bg" | "et" | "lv",
+                _,
+            ) => ("%d.%m.%Y", "%H:%M"),
+            ("nl", _) => ("%d-%m-%Y", "%H:%M"),
+            ("ja" | "zh", _) => ("%Y/%m/%d", "%H:%M"),
+            ("ko", _) => ("%Y. %m. %d.", "%H:%M"),
+            _ => ("%Y-%m-%d", "%H:%M"),
+        }
+    }
+}
+
+/// `de-CH` for `de_CH.UTF-8`, or `None` for something that is no language tag
+fn normalize_locale(locale: &str) -> Option<String> {
+    let tag = locale.split(['.', '@']).next()?.replace('_', "-");
+    let valid = (2..=35).contains(&tag.len())
+        && tag.split('-').all(|subtag| {
+            !subtag.is_empty()
+                && subtag.len() <= 8
+                && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if !valid {
+        return None;
+    }
+    // Canonical case: lowercase language, uppercase region
+    let mut subtags = tag.split('-');
+    let mut normalized = subtags.next()?.to_ascii_lowercase();
+    for subtag in subtags {
+        normalized.push('-');
+        if subtag.len() == 2 {
+            normalized.push_str(&subtag.to_ascii_uppercase());
+        } else {
+            normalized.push_str(subtag);
+        }
+    }
+    Some(normalized)
+}
+
+fn is_known_timezone(timezone: &str) -> bool {
+    timezone_offset(timezone, Utc::now()).is_some()
+}
+
+/// Offset of `timezone` from UTC at `at`, if the time zone is known
+fn timezone_offset(timezone: &str, at: DateTime<Utc>) -> Option<FixedOffset> {
+    if matches!(timezone, "UTC" | "Etc/UTC" | "GMT" | "Z") {
+        return FixedOffset::east_opt(0);
+    }
+    if let Some(offset) = parse_offset(timezone) {
+        return Some(offset);
+    }
+    #[cfg(feature = "timezones")]
+    if let Ok(tz) = timezone.parse::<chrono_tz::Tz>() {
+        use chrono::{Offset, TimeZone};
+        return Some(tz.offset_from_utc_datetime(&at.naive_utc()).fix());
+    }
+    let _ = at;
+    None
+}
+
+/// A UTC offset such as `+02:00`, `-0530` or `UTC+1`
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let offset = offset
+        .strip_prefix("UTC")
+        .or_else(|| offset.strip_prefix("GMT"))
+        .unwrap_or(offset);
+    let sign = match offset.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = if digits.len() > 2 {
+        digits.split_at(digits.len() - 2)
+    } else {
+        (digits.as_str(), "0")
+    };
+    let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+    FixedOffset::east_opt(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_client_locales() {
+        let params: InitializeParams = serde_json::from_value(serde_json::json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "desk", "version": "1.0", "locale": "de_CH.UTF-8", "timezone": "+02:00"}
+        }))
+        .unwrap();
+        let locale = ClientLocale::from_initialize(&params).unwrap();
+        assert_eq!(locale.locale, "de-CH");
+        assert_eq!((locale.language(), locale.region()), ("de", Some("CH")));
+
+        let at = Utc.with_ymd_and_hms(2026, 10, 17, 12, 30, 0).unwrap();
+        assert_eq!(locale.format_datetime(at), "17.10.2026 14:30");
+        assert_eq!(locale.format_number(-1234.5, 2), "-1’234.50");
+
+        let default = ClientLocale::default();
+        assert_eq!(default.format_datetime(at), "10/17/2026 12:30 PM");
+        assert_eq!(default.format_number(1234567.0, 0), "1,234,567");
+        assert_eq!(ClientLocale::new("fr").format_number(0.5, 1), "0,5");
+
+        assert_eq!(
+            ClientLocale::new("en-GB")
+                .with_timezone("Mars/Olympus")
+                .timezone,
+            None
+        );
+        #[cfg(feature = "timezones")]
+        assert_eq!(
+            ClientLocale::new("de-CH")
+                .with_timezone("Europe/Zurich")
+                .format_datetime(at),
+            "17.10.2026 14:30"
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! Maintenance mode for tools, prompts and resource schemes
+//!
+//! Operators take an item out of service at runtime without unregistering it
+//! and losing its configuration. While an item is in maintenance:
+//!
+//! - `tools/list`, `prompts/list` and `resources/list` still list it, with
+//!   `_meta.maintenance` holding the operator's message and since when,
+//! - `tools/call`, `prompts/get` and `resources/read` fail with
+//!   [`McpError::UnderMaintenance`], which clients see as "temporarily
+//!   unavailable" with the message.
+//!
+//! Resources are put in maintenance by URI scheme, such as `files`. The
+//! `admin/maintenance/*` methods, which require a system context, switch
+//! maintenance on and off and list what is in it.
+//!
+//! [`MaintenanceWindow`]s make the whole server read-only for a scheduled
+//! time, for example while a backend database is migrated: tools not
+//! annotated with `readOnlyHint` fail as if they were in maintenance until the
+//! window ends. [`Maintenance::announce_windows`] tells connected clients when
+//! a window starts and ends.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+use tracing::{debug, info};
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    protocol::{LogLevel, LoggingMessageNotification, Map, McpMethod, McpNotification},
+    server::notifications::NotificationDispatcher,
+};
+
+/// `_meta` key of the maintenance annotation on listed items
+pub const MAINTENANCE_META: &str = "maintenance";
+
+/// List the items in maintenance
+pub const ADMIN_MAINTENANCE_LIST: &str = "admin/maintenance/list";
+
+/// Put an item in maintenance
+pub const ADMIN_MAINTENANCE_SET: &str = "admin/maintenance/set";
+
+/// Take an item out of maintenance
+pub const ADMIN_MAINTENANCE_CLEAR: &str = "admin/maintenance/clear";
+
+/// Message used when the operator gives none
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str = "under maintenance, try again later";
+
+/// Kind of item that can be put in maintenance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceKind {
+    /// A tool, by name
+    Tool,
+    /// A prompt, by name
+    Prompt,
+    /// Resources whose URIs have a scheme, such as `files`
+    ResourceScheme,
+}
+
+impl MaintenanceKind {
+    /// How an item of this kind is named in errors
+    fn describe(self, name: &str) -> String {
+        match self {
+            MaintenanceKind::Tool => format!("Tool '{}'", name),
+            MaintenanceKind::Prompt => format!("Prompt '{}'", name),
+            MaintenanceKind::ResourceScheme => format!("Resources under '{}:'", name),
+        }
+    }
+}
+
+/// An item in maintenance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceEntry {
+    /// Kind of item
+    pub kind: MaintenanceKind,
+
+    /// Tool or prompt name, or resource scheme
+    pub name: String,
+
+    /// Message for clients
+    pub message: String,
+
+    /// When the item was put in maintenance
+    pub since: DateTime<Utc>,
+}
+
+/// Params of `admin/maintenance/set` and `admin/maintenance/clear`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceParams {
+    /// Kind of item
+    pub kind: MaintenanceKind,
+
+    /// Tool or prompt name, or resource scheme
+    pub name: String,
+
+    /// Message for clients; defaults to [`DEFAULT_MAINTENANCE_MESSAGE`]
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// A scheduled time in which the server is read-only
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    /// When the window starts
+    pub start: DateTime<Utc>,
+
+    /// When the window ends
+    pub end: DateTime<Utc>,
+
+    /// Message for clients; by default it says until when the server is read-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// A window from `start` to `end`
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            end,
+            message: None,
+        }
+    }
+
+    /// Tell clients `message` instead of the default
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Whether the window is open at `at`
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+
+    /// Whole seconds from `at` until the window ends, rounded up
+    pub fn seconds_left(&self, at: DateTime<Utc>) -> u64 {
+        let millis = (self.end - at).num_milliseconds().max(0) as u64;
+        millis.div_ceil(1000)
+    }
+
+    /// Message for clients
+    pub fn message(&self) -> String {
+        self.message.clone().unwrap_or_else(|| {
+            format!(
+                "the server is read-only for maintenance until {}",
+                self.end.to_rfc3339()
+            )
+        })
+    }
+}
+
+/// Scheduled maintenance settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// Times in which the server is read-only
+    pub windows: Vec<MaintenanceWindow>,
+}
+
+impl MaintenanceConfig {
+    /// Make the server read-only during `window`
+    pub fn with_window(mut self, window: MaintenanceWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+}
+
+/// Items in maintenance
+#[derive(Debug, Clone, Default)]
+pub struct Maintenance {
+    entries: Arc<RwLock<BTreeMap<(MaintenanceKind, String), MaintenanceEntry>>>,
+    windows: Arc<Vec<MaintenanceWindow>>,
+    clock: Clock,
+}
+
+impl Maintenance {
+    /// No items in maintenance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timestamp entries on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Make the server read-only during `windows`
+    pub fn with_windows(mut self, windows: Vec<MaintenanceWindow>) -> Self {
+        self.windows = Arc::new(windows);
+        self
+    }
+
+    /// Put an item in maintenance, replacing its message if it already is
+    pub fn set(
+        &self,
+        kind: MaintenanceKind,
+        name: impl Into<String>,
+        message: Option<String>,
+    ) -> MaintenanceEntry {
+        let name = name.into();
+        let entry = MaintenanceEntry {
+            kind,
+            name: name.clone(),
+            message: message.unwrap_or_else(|| DEFAULT_MAINTENANCE_MESSAGE.to_string()),
+            since: self.clock.system_time().into(),
+        };
+        info!("{} is now in maintenance", kind.describe(&name));
+        self.entries
+            .write()
+            .unwrap()
+            .insert((kind, name), entry.clone());
+        entry
+    }
+
+    /// Take an item out of maintenance, returning its entry if it was in it
+    pub fn clear(&self, kind: MaintenanceKind, name: &str) -> Option<MaintenanceEntry> {
+        let entry = self
+            .entries
+            .write()
+            .unwrap()
+            .remove(&(kind, name.to_string()));
+        if entry.is_some() {
+            info!("{} is back in service", kind.describe(name));
+        }
+        entry
+    }
+
+    /// The entry of an item, if it is in maintenance
+    pub fn get(&self, kind: MaintenanceKind, name: &str) -> Option<MaintenanceEntry> {
+        let entries = self.entries.read().unwrap();
+        entries.get(&(kind, name.to_string())).cloned()
+    }
+
+    /// All items in maintenance
+    pub fn list(&self) -> Vec<MaintenanceEntry> {
+        self.entries.read().unwrap().values().cloned().collect()
+    }
+
+    /// Fail if the item is in maintenance
+    pub fn check(&self, kind: MaintenanceKind, name: &str) -> McpResult<()> {
+        match self.get(kind, name) {
+            Some(entry) => Err(McpError::UnderMaintenance {
+                item: kind.describe(name),
+                message: entry.message,
+                retry_after: None,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail if the scheme of `uri` is in maintenance
+    pub fn check_uri(&self, uri: &str) -> McpResult<()> {
+        match scheme(uri) {
+            Some(scheme) => self.check(MaintenanceKind::ResourceScheme, scheme),
+            None => Ok(()),
+        }
+    }
+
+    /// The maintenance window open now, if any
+    pub fn active_window(&self) -> Option<MaintenanceWindow> {
+        let now: DateTime<Utc> = self.clock.system_time().into();
+        self.windows.iter().find(|w| w.contains(now)).cloned()
+    }
+
+    /// Scheduled windows, including past ones
+    pub fn windows(&self) -> &[MaintenanceWindow] {
+        &self.windows
+    }
+
+    /// Fail if the tool is in maintenance, or is not `read_only` while a
+    /// window is open, telling clients to retry when the window ends
+    pub fn check_tool(&self, name: &str, read_only: bool) -> McpResult<()> {
+        self.check(MaintenanceKind::Tool, name)?;
+        let now: DateTime<Utc> = self.clock.system_time().into();
+        match self.windows.iter().find(|w| w.contains(now)) {
+            Some(window) if !read_only => Err(McpError::UnderMaintenance {
+                item: MaintenanceKind::Tool.describe(name),
+                message: window.message(),
+                retry_after: Some(window.seconds_left(now)),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// [`annotate`](Self::annotate) a listed tool, which is also unavailable
+    /// while a window is open unless it is `read_only`
+    pub fn annotate_tool(&self, name: &str, read_only: bool, metadata: &mut Map<String, Value>) {
+        if self.get(MaintenanceKind::Tool, name).is_some() {
+            self.annotate(MaintenanceKind::Tool, name, metadata);
+        } else if let Some(window) = self.active_window().filter(|_| !read_only) {
+            insert_meta(
+                metadata,
+                serde_json::json!({"message": window.message(), "since": window.start}),
+            );
+        }
+    }
+
+    /// Tell connected clients when maintenance windows start and end
+    ///
+    /// Clients get a `notifications/message` warning and
+    /// `notifications/tools/list_changed` at both ends of every window, on
+    /// the clock this was created with. Call once per replica; the task ends
+    /// after the last window.
+    pub fn announce_windows(
+        &self,
+        notifications: NotificationDispatcher,
+    ) -> tokio::task::JoinHandle<()> {
+        let maintenance = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let now: DateTime<Utc> = maintenance.clock.system_time().into();
+                let next = maintenance
+                    .windows
+                    .iter()
+                    .flat_map(|w| [(w.start, Some(w)), (w.end, None)])
+                    .filter(|(at, _)| *at > now)
+                    .min_by_key(|(at, _)| *at);
+                let Some((at, starting)) = next else {
+                    debug!("No maintenance windows left");
+                    return;
+                };
+                let starting = starting.cloned();
+                maintenance
+                    .clock
+                    .sleep((at - now).to_std().unwrap_or_default())
+                    .await;
+
+                let message = match &starting {
+                    Some(window) => {
+                        info!("Maintenance window started: {}", window.message());
+                        format!("Maintenance started: {}", window.message())
+                    }
+                    None => {
+                        info!("Maintenance window ended");
+                        "Maintenance ended, the server is fully available again".to_string()
+                    }
+                };
+                let log = McpNotification::new(McpMethod::NotificationsMessage(
+                    LoggingMessageNotification {
+                        level: LogLevel::Warning,
+                        logger: Some("maintenance".to_string()),
+                        data: Value::String(message),
+                    },
+                ));
+                for notification in [
+                    log,
+                    McpNotification::new(McpMethod::NotificationsToolsListChanged),
+                ] {
+                    if let Err(e) = notifications.publish(notification).await {
+                        debug!("Failed to announce maintenance: {}", e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Add `_meta.maintenance` to the metadata of a listed item in maintenance
+    pub fn annotate(&self, kind: MaintenanceKind, name: &str, metadata: &mut Map<String, Value>) {
+        let Some(entry) = self.get(kind, name) else {
+            return;
+        };
+        insert_meta(
+            metadata,
+            serde_json::json!({"message": entry.message, "since": entry.since}),
+        );
+    }
+
+    /// [`annotate`](Self::annotate) a resource by the scheme of its URI
+    pub fn annotate_uri(&self, uri: &str, metadata: &mut Map<String, Value>) {
+        if let Some(scheme) = scheme(uri) {
+            self.annotate(MaintenanceKind::ResourceScheme, scheme, metadata);
+        }
+    }
+
+    /// Whether nothing is in maintenance
+    pub fn is_empty(&self) -> bool {
+        self.entries.read().unwrap().is_empty()
+    }
+}
+
+/// Set `_meta.maintenance` in item metadata, keeping other `_meta` keys
+fn insert_meta(metadata: &mut Map<String, Value>, maintenance: Value) {
+    let meta = metadata
+        .entry("_meta".to_string())
+        .or_insert_with(|| Value::Object(Default::default()));
+    if let Value::Object(meta) = meta {
+        meta.insert(MAINTENANCE_META.to_string(), maintenance);
+    }
+}
+
+/// The scheme of `uri`, such as `files` in `files://notes.md`
+fn scheme(uri: &str) -> Option<&str> {
+    uri.split_once(':').map(|(scheme, _)| scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_in_maintenance() {
+        let maintenance = Maintenance::new().with_clock(Clock::manual());
+        maintenance.set(MaintenanceKind::Tool, "search", None);
+        maintenance.set(
+            MaintenanceKind::ResourceScheme,
+            "files",
+            Some("Disk replacement until 14:00".to_string()),
+        );
+
+        let error = maintenance
+            .check(MaintenanceKind::Tool, "search")
+            .unwrap_err();
+        assert_eq!(error.code(), "under_maintenance");
+        assert_eq!(error.retry_after(), None);
+        assert_eq!(
+            error.client_message(),
+            "Tool 'search' is temporarily unavailable: under maintenance, try again later"
+        );
+        assert!(maintenance.check(MaintenanceKind::Prompt, "search").is_ok());
+        assert!(maintenance.check_uri("files://notes.md").is_err());
+        assert!(maintenance.check_uri("db://users").is_ok());
+
+        let mut metadata = Map::new();
+        metadata.insert("_meta".to_string(), serde_json::json!({"owner": "ops"}));
+        maintenance.annotate_uri("files://notes.md", &mut metadata);
+        assert_eq!(metadata["_meta"]["owner"], "ops");
+        assert_eq!(
+            metadata["_meta"][MAINTENANCE_META]["message"],
+            "Disk replacement until 14:00"
+        );
+
+        assert!(maintenance.clear(MaintenanceKind::Tool, "search").is_some());
+        assert!(maintenance.check(MaintenanceKind::Tool, "search").is_ok());
+        assert_eq!(maintenance.list().len(), 1);
+    }
+
+    #[test]
+    fn test_maintenance_windows_are_read_only() {
+        use std::time::Duration;
+        let clock = Clock::manual();
+        let now: DateTime<Utc> = clock.system_time().into();
+        let window = MaintenanceWindow::new(
+            now + chrono::Duration::minutes(10),
+            now + chrono::Duration::minutes(40),
+        );
+        let maintenance = Maintenance::new()
+            .with_clock(clock.clone())
+            .with_windows(vec![window]);
+
+        assert!(maintenance.check_tool("delete_note", false).is_ok());
+
+        clock.advance(Duration::from_secs(15 * 60));
+        let error = maintenance.check_tool("delete_note", false).unwrap_err();
+        assert_eq!(error.code(), "under_maintenance");
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(25 * 60)));
+        assert_eq!(error.client_data().unwrap()["retryAfterMs"], 25 * 60 * 1000);
+        assert!(maintenance.check_tool("search", true).is_ok());
+        let mut metadata = Map::new();
+        maintenance.annotate_tool("delete_note", false, &mut metadata);
+        assert!(metadata["_meta"][MAINTENANCE_META]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("the server is read-only for maintenance until"));
+
+        clock.advance(Duration::from_secs(30 * 60));
+        assert!(maintenance.check_tool("delete_note", false).is_ok());
+    }
+}
@@ -0,0 +1,324 @@
+//! Approximate memory accounting with a soft limit
+//!
+//! The [`MemoryAccountant`] of a server keeps three gauges:
+//!
+//! - in-flight: the approximate size of the parameters of running requests
+//! - cache: bytes held by registered [`MemoryCache`]s, such as the archive cache
+//! - notifications: bytes queued in notification buffers
+//!
+//! With a soft limit configured, a request arriving while the total is over it
+//! first makes the caches evict, least recently cached first. If that does not
+//! bring the total under the limit, expensive requests (see
+//! [`MemoryConfig::shed_methods`]) fail with [`McpError::Overloaded`] until
+//! memory is released. The numbers are estimates of payload sizes, not of the
+//! allocator's view; admission control's resident memory check complements
+//! them.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+use tracing::debug;
+
+use crate::error::{McpError, McpResult};
+
+/// Reports the bytes held by a buffer
+pub type MemoryGauge = Arc<dyn Fn() -> u64 + Send + Sync>;
+
+/// A cache that can give memory back
+pub trait MemoryCache: Send + Sync {
+    /// Bytes currently cached
+    fn cached_bytes(&self) -> u64;
+
+    /// Evict at least `bytes` if possible, returning the bytes freed
+    fn evict(&self, bytes: u64) -> u64;
+}
+
+/// Memory accounting configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// Whether memory is accounted and the soft limit enforced
+    pub enabled: bool,
+
+    /// Total accounted bytes above which caches are evicted and load is shed
+    pub soft_limit_bytes: u64,
+
+    /// Methods that are shed over the soft limit; all others still run
+    pub shed_methods: Vec<String>,
+
+    /// Seconds clients are told to wait before retrying a shed request
+    pub retry_after: u64,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            soft_limit_bytes: 512 * 1024 * 1024,
+            shed_methods: ["tools/call", "batch", "resources/read", "prompts/get"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            retry_after: 5,
+        }
+    }
+}
+
+impl MemoryConfig {
+    /// Account memory and keep the total under `soft_limit_bytes`
+    pub fn enabled(soft_limit_bytes: u64) -> Self {
+        Self {
+            enabled: true,
+            soft_limit_bytes,
+            ..Default::default()
+        }
+    }
+}
+
+/// Accounted memory, in bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryUsage {
+    /// Parameters of running requests
+    pub in_flight_bytes: u64,
+
+    /// Registered caches
+    pub cache_bytes: u64,
+
+    /// Notification buffers
+    pub notification_bytes: u64,
+
+    /// Sum of the above
+    pub total_bytes: u64,
+
+    /// Bytes evicted from caches to stay under the soft limit, since startup
+    pub evicted_bytes: u64,
+
+    /// Requests shed over the soft limit, since startup
+    pub shed: u64,
+}
+
+/// Memory held by a running request; released on drop
+#[derive(Debug)]
+pub struct MemoryReservation {
+    in_flight: Option<Arc<AtomicU64>>,
+    bytes: u64,
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        if let Some(in_flight) = &self.in_flight {
+            in_flight.fetch_sub(self.bytes, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Keeps the memory gauges of a server and enforces its soft limit
+#[derive(Clone)]
+pub struct MemoryAccountant {
+    config: MemoryConfig,
+    in_flight: Arc<AtomicU64>,
+    caches: Arc<Mutex<Vec<Weak<dyn MemoryCache>>>>,
+    buffers: Arc<Mutex<HashMap<String, MemoryGauge>>>,
+    evicted: Arc<AtomicU64>,
+    shed: Arc<AtomicU64>,
+}
+
+impl std::fmt::Debug for MemoryAccountant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryAccountant")
+            .field("config", &self.config)
+            .field("usage", &self.usage())
+            .finish()
+    }
+}
+
+impl MemoryAccountant {
+    /// Account memory as configured
+    pub fn new(config: MemoryConfig) -> Self {
+        Self {
+            config,
+            in_flight: Arc::new(AtomicU64::new(0)),
+            caches: Arc::new(Mutex::new(Vec::new())),
+            buffers: Arc::new(Mutex::new(HashMap::new())),
+            evicted: Arc::new(AtomicU64::new(0)),
+            shed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &MemoryConfig {
+        &self.config
+    }
+
+    /// Count `cache` and evict from it over the soft limit
+    ///
+    /// The accountant only holds a weak reference; dropped caches are forgotten.
+    pub fn register_cache(&self, cache: &Arc<dyn MemoryCache>) {
+        self.caches.lock().unwrap().push(Arc::downgrade(cache));
+    }
+
+    /// Count the notification buffer `name` as reported by `gauge`
+    ///
+    /// Registering the same name again replaces the earlier gauge.
+    pub fn register_buffer(&self, name: impl Into<String>, gauge: MemoryGauge) {
+        self.buffers.lock().unwrap().insert(name.into(), gauge);
+    }
+
+    /// Hold `bytes` of in-flight memory until the reservation is dropped
+    pub fn reserve(&self, bytes: u64) -> MemoryReservation {
+        if !self.config.enabled {
+            return MemoryReservation {
+                in_flight: None,
+                bytes: 0,
+            };
+        }
+        self.in_flight.fetch_add(bytes, Ordering::SeqCst);
+        MemoryReservation {
+            in_flight: Some(Arc::clone(&self.in_flight)),
+            bytes,
+        }
+    }
+
+    /// Current gauges and counters
+    pub fn usage(&self) -> MemoryUsage {
+        let in_flight_bytes = self.in_flight.load(Ordering::SeqCst);
+        let cache_bytes = self.caches().iter().map(|cache| cache.cached_bytes()).sum();
+        let notification_bytes = self
+            .buffers
+            .lock()
+            .unwrap()
+            .values()
+            .map(|gauge| gauge())
+            .sum();
+        MemoryUsage {
+            in_flight_bytes,
+            cache_bytes,
+            notification_bytes,
+            total_bytes: in_flight_bytes + cache_bytes + notification_bytes,
+            evicted_bytes: self.evicted.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Check the soft limit before running `method`
+    ///
+    /// Over the limit, caches are evicted first; if that is not enough and
+    /// `method` is one of the shed methods, the request is rejected.
+    pub fn check(&self, method: &str) -> McpResult<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let mut total = self.usage().total_bytes;
+        if total <= self.config.soft_limit_bytes {
+            return Ok(());
+        }
+
+        let mut excess = total - self.config.soft_limit_bytes;
+        for cache in self.caches() {
+            let freed = cache.evict(excess);
+            self.evicted.fetch_add(freed, Ordering::Relaxed);
+            excess = excess.saturating_sub(freed);
+            if excess == 0 {
+                break;
+            }
+        }
+        total = self.usage().total_bytes;
+        if total <= self.config.soft_limit_bytes
+            || !self.config.shed_methods.iter().any(|m| m == method)
+        {
+            return Ok(());
+        }
+
+        self.shed.fetch_add(1, Ordering::Relaxed);
+        debug!("Shedding {}: {} bytes accounted", method, total);
+        Err(McpError::Overloaded {
+            reason: format!(
+                "{} bytes accounted, over the soft limit of {}",
+                total, self.config.soft_limit_bytes
+            ),
+            retry_after: self.config.retry_after,
+        })
+    }
+
+    /// Live registered caches, forgetting dropped ones
+    fn caches(&self) -> Vec<Arc<dyn MemoryCache>> {
+        let mut caches = self.caches.lock().unwrap();
+        caches.retain(|cache| cache.strong_count() > 0);
+        caches.iter().filter_map(Weak::upgrade).collect()
+    }
+}
+
+/// Approximate bytes `value` takes when serialized
+pub fn approximate_size(value: &Value) -> u64 {
+    match value {
+        Value::Null | Value::Bool(_) | Value::Number(_) => 8,
+        Value::String(text) => text.len() as u64 + 2,
+        Value::Array(items) => {
+            2 + items
+                .iter()
+                .map(|item| approximate_size(item) + 1)
+                .sum::<u64>()
+        }
+        Value::Object(fields) => {
+            2 + fields
+                .iter()
+                .map(|(key, field)| key.len() as u64 + 4 + approximate_size(field))
+                .sum::<u64>()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestCache(AtomicU64);
+
+    impl MemoryCache for TestCache {
+        fn cached_bytes(&self) -> u64 {
+            self.0.load(Ordering::SeqCst)
+        }
+
+        fn evict(&self, bytes: u64) -> u64 {
+            let cached = self.0.load(Ordering::SeqCst);
+            let freed = bytes.min(cached);
+            self.0.store(cached - freed, Ordering::SeqCst);
+            freed
+        }
+    }
+
+    #[test]
+    fn test_soft_limit_evicts_then_sheds() {
+        let memory = MemoryAccountant::new(MemoryConfig::enabled(1000));
+        let cache: Arc<dyn MemoryCache> = Arc::new(TestCache(AtomicU64::new(600)));
+        memory.register_cache(&cache);
+        memory.register_buffer("notifications", Arc::new(|| 100));
+
+        // Evicting part of the cache makes room
+        let request = memory.reserve(500);
+        assert!(memory.check("tools/call").is_ok());
+        let usage = memory.usage();
+        assert_eq!((usage.cache_bytes, usage.total_bytes), (400, 1000));
+        assert_eq!(usage.evicted_bytes, 200);
+
+        // Without cache to give back, expensive requests are shed
+        let more = memory.reserve(1000);
+        assert!(matches!(
+            memory.check("tools/call"),
+            Err(McpError::Overloaded { .. })
+        ));
+        assert!(memory.check("ping").is_ok());
+        assert_eq!(memory.usage().shed, 1);
+
+        drop((request, more));
+        assert!(memory.check("tools/call").is_ok());
+        assert_eq!(memory.usage().in_flight_bytes, 0);
+    }
+}
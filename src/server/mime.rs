@@ -0,0 +1,138 @@
+//! MIME type detection and text decoding for resource content
+//!
+//! Registries serving files or HTTP responses rarely know what they hold.
+//! [`Resource::from_bytes`](crate::server::resource::Resource::from_bytes)
+//! uses these helpers to fill in `mime_type` and to return text as UTF-8:
+//!
+//! - [`detect_mime_type`] trusts magic bytes first, then the file extension,
+//!   and falls back to `text/plain` for content that decodes as text.
+//! - [`decode_text`] honors a byte order mark, then a declared charset, then
+//!   UTF-8, and falls back to Windows-1252, which decodes any byte sequence.
+//!   Content in other encodings must declare its charset.
+
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+/// MIME type of content that is neither recognized nor text
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Detect the MIME type of `bytes`, stored at `path` if known
+pub fn detect_mime_type(bytes: &[u8], path: Option<&str>) -> String {
+    if let Some(kind) = infer::get(bytes) {
+        return kind.mime_type().to_string();
+    }
+    if let Some(mime_type) = path.and_then(|path| mime_guess::from_path(path).first_raw()) {
+        return mime_type.to_string();
+    }
+    if looks_like_text(bytes) {
+        "text/plain".to_string()
+    } else {
+        OCTET_STREAM.to_string()
+    }
+}
+
+/// Whether content of `mime_type` is text
+pub fn is_text_mime_type(mime_type: &str) -> bool {
+    let essence = mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let Some((kind, subtype)) = essence.split_once('/') else {
+        return false;
+    };
+    kind == "text"
+        || subtype.ends_with("+json")
+        || subtype.ends_with("+xml")
+        || matches!(
+            subtype,
+            "json"
+                | "xml"
+                | "javascript"
+                | "ecmascript"
+                | "yaml"
+                | "x-yaml"
+                | "toml"
+                | "sql"
+                | "graphql"
+                | "x-sh"
+                | "x-httpd-php"
+        )
+}
+
+/// `charset` parameter of a `Content-Type` value
+pub fn charset_of(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Decode text to UTF-8
+///
+/// Returns the text and the name of the encoding it was decoded from.
+pub fn decode_text(bytes: &[u8], charset: Option<&str>) -> (String, &'static str) {
+    let (encoding, bom_length) = Encoding::for_bom(bytes)
+        .or_else(|| {
+            charset
+                .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+                .map(|encoding| (encoding, 0))
+        })
+        .unwrap_or_else(|| {
+            let encoding = match std::str::from_utf8(bytes) {
+                Ok(_) => UTF_8,
+                Err(_) => WINDOWS_1252,
+            };
+            (encoding, 0)
+        });
+    let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+    (text.into_owned(), encoding.name())
+}
+
+/// UTF-8 or marked with a byte order mark, and free of NUL bytes
+fn looks_like_text(bytes: &[u8]) -> bool {
+    Encoding::for_bom(bytes).is_some()
+        || (std::str::from_utf8(bytes).is_ok() && !bytes.contains(&0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection_and_decoding() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0];
+        assert_eq!(detect_mime_type(&png, Some("logo.txt")), "image/png");
+        assert_eq!(
+            detect_mime_type(b"a,b\n1,2\n", Some("data.csv")),
+            "text/csv"
+        );
+        assert_eq!(detect_mime_type(b"plain words", None), "text/plain");
+        assert_eq!(detect_mime_type(&[0, 159, 146, 150], None), OCTET_STREAM);
+
+        assert!(is_text_mime_type("application/vnd.api+json; charset=utf-8"));
+        assert!(!is_text_mime_type("image/png"));
+        assert_eq!(
+            charset_of("text/html; Charset=\"ISO-8859-1\""),
+            Some("ISO-8859-1")
+        );
+
+        // UTF-16LE with a byte order mark
+        assert_eq!(
+            decode_text(&[0xFF, 0xFE, b'h', 0, b'i', 0], None),
+            ("hi".to_string(), "UTF-16LE")
+        );
+        // Latin-1 declared and undeclared
+        assert_eq!(
+            decode_text(b"caf\xe9", Some("iso-8859-1")),
+            ("café".to_string(), "windows-1252")
+        );
+        assert_eq!(decode_text(b"caf\xe9", None).0, "café");
+        assert_eq!(
+            decode_text("naïve".as_bytes(), None),
+            ("naïve".to_string(), "UTF-8")
+        );
+    }
+}
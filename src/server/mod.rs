@@ -1,28 +1,267 @@
 //! Generic MCP server framework with trait-based architecture
 
+pub mod admission;
+pub mod affinity;
+pub mod aliases;
+pub mod approval;
+#[cfg(feature = "resources")]
+pub mod archive;
+pub mod audit;
+pub mod batch_resume;
+pub mod batch_template;
+pub mod build_info;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod concurrency;
 pub mod config;
+pub mod dependencies;
+pub mod diff;
+pub mod discover;
+#[cfg(feature = "documents")]
+pub mod documents;
+pub mod duplicates;
+pub mod exposure;
+#[cfg(feature = "axum")]
+pub mod extract;
+pub mod formatters;
+#[cfg(feature = "axum")]
 pub mod handler;
+pub mod http_status;
+pub mod inspector;
+#[cfg(feature = "isolation")]
+pub mod isolation;
+#[cfg(feature = "axum")]
+pub mod json_body;
+pub mod legacy_sse;
+#[cfg(feature = "axum")]
+pub mod listeners;
+pub mod locale;
+pub mod maintenance;
+pub mod memory;
+pub mod mime;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod negotiation;
+pub mod notification_filter;
+pub mod notifications;
+pub mod openapi;
+pub mod pagination;
 pub mod progress;
 pub mod prompt;
+pub mod prompt_bundle;
+#[cfg(feature = "prompts")]
+pub mod prompt_golden;
+#[cfg(feature = "prompts")]
+pub mod prompt_layers;
+pub mod prompt_lint;
+pub mod prompt_stream;
+pub mod quota;
+pub mod read_many;
+pub mod reduction;
 pub mod registry;
+pub mod registry_changelog;
 pub mod resource;
+pub mod sandbox;
+pub mod scheduler;
+pub mod schema_compat;
+pub mod scrub;
+pub mod search;
+pub mod secrets;
+pub mod serialization;
 pub mod service;
+pub mod services;
+pub mod session_store;
+pub mod smoke;
+pub mod snapshot;
+#[cfg(feature = "axum")]
+pub mod sse_encoder;
+pub mod tasks;
+#[cfg(feature = "tls")]
+pub mod tls;
+pub mod tokens;
+pub mod tool_limits;
+pub mod toolsets;
+pub mod transcript;
+pub mod transform;
+#[cfg(all(unix, feature = "axum"))]
+pub mod uds;
+#[cfg(feature = "ui")]
+pub mod ui;
+pub mod validation;
+pub mod webhooks;
+pub mod wire_tap;
 
-pub use config::McpServerConfig;
+pub use admission::{
+    resident_memory, AdmissionConfig, AdmissionController, AdmissionStats, MemoryProbe,
+};
+pub use affinity::{
+    affinity_key, session_owner, HashRing, SessionAffinityConfig, SESSION_OWNER_HEADER,
+};
+pub use aliases::{MethodAlias, MethodAliasConfig, DEPRECATION_LOGGER};
+pub use approval::{
+    ApprovalConfig, ApprovalDecision, ApprovalDecisionParams, ApprovalManager, ApprovalRequest,
+};
+#[cfg(feature = "resources")]
+pub use archive::{split_archive_uri, ArchiveResourceRegistry, ARCHIVE_SEPARATOR};
+pub use audit::{
+    AuditRetention, ExecutionAuditTrail, ExecutionOutcome, ToolExecutionRecord, AUDIT_SCHEME,
+};
+pub use batch_resume::{BatchJournal, BatchProgress, BatchResumeConfig, RESUMED_META};
+pub use build_info::{BuildInfo, BuildInfoConfig, BUILD_INFO_KEY, GIT_HASH_ENV, SERVER_INFO};
+#[cfg(feature = "chaos")]
+pub use chaos::{inject_faults, ChaosFault, ChaosProfile};
+pub use concurrency::{PrincipalConcurrencyConfig, PrincipalLimits, PrincipalPermit};
+pub use config::{McpServerConfig, TlsConfig};
+pub use dependencies::{
+    Access, DependencyEdge, DependencyGraph, ResourceDependencies, ADMIN_DEPENDENCIES,
+    TOOL_RESOURCES_META,
+};
+pub use diff::{unified_diff, ResourcesDiffParams, ResourcesDiffResult, TextDiff, RESOURCES_DIFF};
+pub use discover::{openrpc_document, MethodDescription, RPC_DISCOVER};
+#[cfg(feature = "documents")]
+pub use documents::{DocxTextExtractor, PdfTextExtractor};
+pub use duplicates::{DuplicateRequestConfig, DuplicateRequestPolicy, DuplicateRequests};
+#[cfg(feature = "axum")]
+pub use exposure::name_listener;
+pub use exposure::{ExposureConfig, ListenerName, EXPOSURE_META};
+#[cfg(feature = "axum")]
+pub use extract::{McpRequestParts, McpState};
+pub use formatters::{
+    markdown_table, Citations, FormatterConfig, MarkdownTables, PrettyJson, ResultFormatter,
+    ResultFormatters, CITATIONS_META,
+};
+#[cfg(feature = "axum")]
 pub use handler::McpHandlerState;
+pub use http_status::HttpStatusConfig;
+pub use inspector::{InspectedExchange, InspectorConfig, RequestInspector, INSPECTOR_TARGET};
+#[cfg(feature = "isolation")]
+pub use isolation::{IsolatedToolExecutor, IsolationConfig};
+#[cfg(feature = "axum")]
+pub use json_body::parse_json_body;
+#[cfg(feature = "axum")]
+pub use listeners::{Listener, TransportSet};
+pub use locale::ClientLocale;
+pub use maintenance::{
+    Maintenance, MaintenanceConfig, MaintenanceEntry, MaintenanceKind, MaintenanceWindow,
+};
+pub use memory::{
+    approximate_size, MemoryAccountant, MemoryCache, MemoryConfig, MemoryGauge, MemoryReservation,
+    MemoryUsage,
+};
+pub use negotiation::{negotiate, parse_accept, MediaRange};
+pub use notification_filter::{
+    NotificationCategory, NotificationFilter, NOTIFICATIONS_CAPABILITY, NOTIFICATIONS_FILTER,
+};
+pub use notifications::{
+    InProcessBroker, NotificationBroker, NotificationDispatcher, NotificationSender,
+    NOTIFICATION_EVENT,
+};
+pub use pagination::{paginate, Page, PageCursor, PaginationConfig, SnapshotMap};
 pub use progress::{ProgressLevel, ProgressReporter, ProgressUpdate};
 pub use prompt::{
     EmbeddedResource, GetPromptRequest, GetPromptResult, InMemoryPromptRegistry, MessageRole,
     Prompt, PromptCategory, PromptContent, PromptMessage, PromptParameter, PromptRegistry,
     ResourceAnnotation, SimpleTemplateEngine, TemplateEngine,
 };
+pub use prompt_bundle::{
+    ConflictResolution, PromptBundle, PromptImportReport, PROMPT_BUNDLE_VERSION,
+};
+#[cfg(feature = "prompts")]
+pub use prompt_golden::{
+    GoldenMismatch, GoldenPrompts, GoldenReport, PromptExample, PROMPT_EXAMPLES_META,
+    UPDATE_GOLDENS_ENV,
+};
+#[cfg(feature = "prompts")]
+pub use prompt_layers::{
+    LayeredPromptRegistry, BASE_LAYER, ENVIRONMENT_LABEL, PROMPT_LAYER_META, TENANT_LABEL,
+};
+pub use prompt_lint::{
+    DiagnosticSeverity, PromptDiagnostic, PromptLinter, DEFAULT_MAX_SYSTEM_PROMPT_TOKENS,
+};
+pub use prompt_stream::{
+    PromptChunk, PromptStream, PromptStreamConfig, PromptStreams, PROMPTS_CHUNK, STREAMED_META,
+    STREAM_META,
+};
+pub use quota::{
+    InMemoryQuotaStore, QuotaConfig, QuotaManager, QuotaMetric, QuotaStatus, QuotaStore, QuotaUsage,
+};
+pub use read_many::{
+    ReadManyConfig, ResourceReadOutcome, ResourcesReadManyParams, ResourcesReadManyResult,
+    RESOURCES_READ_MANY,
+};
+pub use reduction::{ContentReducer, HeadTailReducer, LineSamplingReducer, TruncatingReducer};
 pub use registry::{InMemoryToolRegistry, McpTool, ToolExecutionContext, ToolRegistry};
+pub use registry_changelog::{
+    ChangelogQuery, RegistryAction, RegistryChange, RegistryChangelog, RegistryKind,
+    ADMIN_REGISTRY_CHANGELOG,
+};
 pub use resource::{
-    InMemoryResourceRegistry, MultiSchemeResourceRegistry, ParsedUri, Resource, ResourceChangeType,
-    ResourceChanged, ResourceContent, ResourceRegistry, ResourceSubscription, ResourceTemplate,
-    UriSchemeConfig,
+    content_etag, etag_matches, split_version, InMemoryResourceRegistry,
+    MultiSchemeResourceRegistry, ParsedUri, Resource, ResourceChangeType, ResourceChanged,
+    ResourceContent, ResourceRegistry, ResourceSubscription, ResourceTemplate, ResourceVersion,
+    ResourcesVersionsParams, UriSchemeConfig, VersionedResourceRegistry, ETAG_META,
+    REPRESENTATIONS_META, RESOURCES_VERSIONS, VERSION_QUERY_PARAM,
+};
+pub use sandbox::SandboxProfile;
+pub use scheduler::{
+    RequestPriority, RequestScheduler, SchedulerConfig, SchedulerPermit, SchedulerStats,
+    ROLE_LABEL, SCHEDULED_METHODS,
+};
+pub use schema_compat::{check_schema, SchemaChange, SchemaCompatibility};
+pub use scrub::{BuiltinPattern, ScrubConfig, Scrubber};
+pub use search::{
+    InMemorySearchIndex, ResourceSearch, ResourcesSearchParams, ResourcesSearchResult, SearchHit,
+    SearchIndex, RESOURCES_SEARCH,
+};
+#[cfg(feature = "vault")]
+pub use secrets::VaultSecretProvider;
+pub use secrets::{
+    EnvSecretProvider, FileSecretProvider, Secret, SecretProvider, Secrets, SecretsConfig,
+    SECRETS_TARGET,
+};
+pub use serialization::{
+    BinaryEncoding, LargeIntegers, NonFiniteFloats, SerializationConfig, MAX_SAFE_INTEGER,
 };
 pub use service::McpServer;
+pub use services::{Services, REQUIRES_META};
+pub use session_store::{SessionStore, SessionStores};
+pub use smoke::{
+    server_stats, SmokeTools, SmokeToolsConfig, SMOKE_CATEGORY, SMOKE_ECHO, SMOKE_FAIL,
+    SMOKE_SERVER_STATS, SMOKE_SLEEP,
+};
+pub use snapshot::{
+    CapabilityChange, CapabilityDiff, CapabilityKind, CapabilitySnapshot, ChangeType,
+    PromptParameterSnapshot, PromptSnapshot, ResourceSnapshot, ToolSnapshot,
+};
+#[cfg(feature = "axum")]
+pub use sse_encoder::{sse_response, SseEncoder};
+pub use tasks::{TaskConfig, TaskInfo, TaskManager, TaskStatus, TASKS_CAPABILITY};
+#[cfg(feature = "tiktoken")]
+pub use tokens::TiktokenEstimator;
+pub use tokens::{
+    default_estimator, CharRatioEstimator, TokenBudgeter, TokenConfig, TokenEstimator,
+    TruncationStrategy,
+};
+pub use tool_limits::{declared_limits, LimitScope, ToolLimit, ToolLimits, TOOL_LIMITS_META};
+pub use toolsets::{
+    Toolset, ToolsetConfig, ToolsetInfo, Toolsets, TOOLSETS_CAPABILITY, TOOLSETS_DISABLE,
+    TOOLSETS_ENABLE, TOOLSETS_LIST,
+};
+pub use transcript::{
+    Exchange, GoldenTranscripts, Scenario, ScenarioStep, Transcript, TranscriptMismatch,
+    TranscriptReport, UPDATE_TRANSCRIPTS_ENV,
+};
+pub use transform::{
+    CsvToJson, HtmlToMarkdown, MarkdownToHtml, ResourceTransformer, ResourceTransformers,
+};
+pub use validation::{ValidationIssue, ValidationReport};
+pub use webhooks::{
+    RetryPolicy, WebhookConfig, WebhookDispatcher, WebhookEvent, WebhookEventKind, WebhooksConfig,
+};
+pub use wire_tap::{
+    FrameDirection, TapFrame, TapSink, WireTap, WireTapConfig, WireTapParams, WireTapStatus,
+    ADMIN_WIRE_TAP, WIRE_TAP_TARGET,
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -62,19 +301,24 @@ pub trait McpServerState: Send + Sync + Clone + 'static {
         None
     }
 
+    /// Get the services handed to tool calls (optional)
+    fn services(&self) -> Option<&Services> {
+        None
+    }
+
     /// Get server information for the initialize response
     fn server_info(&self) -> ServerInfo {
         ServerInfo {
             name: "MCP Server".to_string(),
             version: "0.1.0".to_string(),
-            metadata: HashMap::new(),
+            metadata: Default::default(),
         }
     }
 
     /// Get server capabilities
     fn server_capabilities(&self) -> ServerCapabilities {
         ServerCapabilities {
-            experimental: HashMap::new(),
+            experimental: Default::default(),
             logging: None,
             prompts: if self.prompt_registry().is_some() {
                 Some(messages::PromptsCapability {
@@ -111,6 +355,25 @@ pub trait McpServerState: Send + Sync + Clone + 'static {
         })
     }
 
+    /// Custom methods handled by [`handle_custom_method`](Self::handle_custom_method)
+    ///
+    /// Only consulted in strict method mode, where other unknown methods are
+    /// rejected before reaching the handler.
+    fn custom_methods(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Descriptions of the custom methods for `rpc.discover`
+    ///
+    /// Defaults to the [`custom_methods`](Self::custom_methods) without
+    /// schemas; override it to publish their params and result schemas.
+    fn describe_custom_methods(&self) -> Vec<MethodDescription> {
+        self.custom_methods()
+            .into_iter()
+            .map(|name| MethodDescription::new(name, "Custom method"))
+            .collect()
+    }
+
     /// Handle custom methods not covered by the standard MCP protocol
     async fn handle_custom_method(
         &self,
@@ -134,6 +397,10 @@ pub enum BatchExecutionMode {
     Sequential,
     /// Execute operations sequentially but stop on first error
     FailFast,
+    /// Execute operations in dependency order, rendering result placeholders
+    Dependency,
+    /// Like `Dependency`, but higher-priority ready operations run first
+    PriorityDependency,
 }
 
 /// Batch execution context
@@ -0,0 +1,364 @@
+//! NATS transport
+//!
+//! With the `nats` feature, [`NatsTransport`] serves an [`McpServer`] over NATS
+//! subjects instead of HTTP, so servers can sit behind a message bus without
+//! listening on a port:
+//!
+//! - JSON-RPC requests are sent with NATS request/reply to `{prefix}.rpc` and
+//!   answered on the reply subject. Notifications are plain publishes.
+//! - Sessions are carried in the `Mcp-Session-Id` header. `initialize`
+//!   without one starts a session, whose ID is returned in the reply headers.
+//! - Server notifications for a session, such as `notifications/progress` for
+//!   calls that sent a progress token, are published to
+//!   `{prefix}.sessions.{session_id}.notifications`.
+//!
+//! Instances subscribe in a queue group, so several servers can share the
+//! request subject.
+//!
+//! [`NatsNotificationBroker`] fans server notifications out between replicas
+//! over `{prefix}.notifications`, whichever transport the clients use.
+
+use async_trait::async_trait;
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::{
+        JsonRpcError, JsonRpcRequest, JsonRpcResponse, McpMethod, McpNotification,
+        ProgressNotification,
+    },
+    security::{fingerprint::TRANSPORT_KEY, ClientContext, SecurityContext},
+    server::{notifications::NotificationBroker, McpServer, McpServerState},
+};
+
+/// Header carrying the session ID on requests and replies
+pub const NATS_SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Subjects used by the NATS transport
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NatsConfig {
+    /// Prefix of all subjects
+    pub subject_prefix: String,
+
+    /// Queue group shared by server instances; `None` makes every instance
+    /// answer every request
+    pub queue_group: Option<String>,
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
+        Self {
+            subject_prefix: "mcp".to_string(),
+            queue_group: Some("mcp".to_string()),
+        }
+    }
+}
+
+impl NatsConfig {
+    /// Use subjects below `prefix`
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Self {
+            subject_prefix: prefix.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Subject requests are sent to
+    pub fn request_subject(&self) -> String {
+        format!("{}.rpc", self.subject_prefix)
+    }
+
+    /// Subject server notifications are fanned out on between replicas
+    pub fn broker_subject(&self) -> String {
+        format!("{}.notifications", self.subject_prefix)
+    }
+
+    /// Subject a session's notifications are published to
+    pub fn notification_subject(&self, session_id: &str) -> String {
+        format!(
+            "{}.sessions.{}.notifications",
+            self.subject_prefix, session_id
+        )
+    }
+}
+
+/// Builds the security context for a request from its NATS headers
+pub type NatsContextFactory =
+    Arc<dyn Fn(Option<&async_nats::HeaderMap>) -> SecurityContext + Send + Sync>;
+
+/// Serves an MCP server over NATS
+#[derive(Clone)]
+pub struct NatsTransport<S>
+where
+    S: McpServerState,
+{
+    server: McpServer<S>,
+    client: async_nats::Client,
+    config: NatsConfig,
+    context_factory: NatsContextFactory,
+}
+
+impl<S> NatsTransport<S>
+where
+    S: McpServerState,
+{
+    /// Serve `server` on a connected NATS client
+    ///
+    /// Requests get an authenticated context, as over HTTP; use
+    /// [`with_context_factory`](Self::with_context_factory) to authenticate
+    /// from message headers instead.
+    pub fn new(server: McpServer<S>, client: async_nats::Client, config: NatsConfig) -> Self {
+        Self {
+            server,
+            client,
+            config,
+            context_factory: Arc::new(|_| {
+                SecurityContext::authenticated(
+                    ClientContext {
+                        user_agent: "nats".to_string(),
+                        ..Default::default()
+                    },
+                    Vec::new(),
+                )
+            }),
+        }
+    }
+
+    /// Build security contexts from message headers
+    pub fn with_context_factory(
+        mut self,
+        factory: impl Fn(Option<&async_nats::HeaderMap>) -> SecurityContext + Send + Sync + 'static,
+    ) -> Self {
+        self.context_factory = Arc::new(factory);
+        self
+    }
+
+    /// Subjects this transport uses
+    pub fn config(&self) -> &NatsConfig {
+        &self.config
+    }
+
+    /// Answer requests until the subscription ends
+    pub async fn serve(self) -> McpResult<()> {
+        let subject = self.config.request_subject();
+        let mut subscriber = match &self.config.queue_group {
+            Some(group) => self
+                .client
+                .queue_subscribe(subject.clone(), group.clone())
+                .await
+                .map_err(transport_error)?,
+            None => self
+                .client
+                .subscribe(subject.clone())
+                .await
+                .map_err(transport_error)?,
+        };
+        info!("MCP server listening on NATS subject {}", subject);
+
+        while let Some(message) = subscriber.next().await {
+            let transport = self.clone();
+            tokio::spawn(async move { transport.handle_message(message).await });
+        }
+        Ok(())
+    }
+
+    /// Publish a notification to a session's notification subject
+    pub async fn notify(&self, session_id: &str, notification: &McpNotification) -> McpResult<()> {
+        self.client
+            .publish(
+                self.config.notification_subject(session_id),
+                serde_json::to_vec(notification)?.into(),
+            )
+            .await
+            .map_err(transport_error)
+    }
+
+    async fn handle_message(&self, message: async_nats::Message) {
+        let request_session = message
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get(NATS_SESSION_HEADER))
+            .map(|value| value.as_str().to_string());
+        let mut context = (self.context_factory)(message.headers.as_ref());
+
+        let request: JsonRpcRequest = match serde_json::from_slice(&message.payload) {
+            Ok(request) => request,
+            Err(e) => {
+                debug!("Invalid JSON-RPC message on NATS: {}", e);
+                let response = JsonRpcResponse::error(
+                    JsonRpcError::parse_error(Some(serde_json::json!(e.to_string()))),
+                    None,
+                );
+                self.reply(message.reply, None, &response).await;
+                return;
+            }
+        };
+
+        let session_id = request_session
+            .or_else(|| (request.method == "initialize").then(|| uuid::Uuid::new_v4().to_string()));
+        context.client.session_id = session_id.clone();
+        context
+            .client
+            .metadata
+            .insert(TRANSPORT_KEY.to_string(), "nats".to_string());
+
+        let token = request
+            .params
+            .as_ref()
+            .and_then(|params| params.get("_meta")?.get("progressToken").cloned());
+        let forwarder = match (&session_id, token) {
+            (Some(session_id), Some(token)) => Some(self.forward_progress(session_id, token)),
+            _ => None,
+        };
+        let is_notification = request.id.is_none();
+        let response = self.server.handle_request(request, context).await;
+        if let Some(forwarder) = forwarder {
+            forwarder.abort();
+        }
+
+        if !is_notification {
+            self.reply(message.reply, session_id.as_deref(), &response)
+                .await;
+        }
+    }
+
+    /// Publish progress updates for `token` until aborted
+    fn forward_progress(
+        &self,
+        session_id: &str,
+        token: serde_json::Value,
+    ) -> tokio::task::JoinHandle<()> {
+        // Progress is reported under the token's string form
+        let operation_id = token
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| token.to_string());
+        let mut updates = self.server.progress_reporter().subscribe();
+        let transport = self.clone();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) if update.operation_id == operation_id => {
+                        let notification = McpNotification::new(McpMethod::NotificationsProgress(
+                            ProgressNotification {
+                                progress_token: token.clone(),
+                                progress: update.current as f64,
+                                total: (update.total > 0).then_some(update.total as u64),
+                                message: Some(update.message.clone()),
+                            },
+                        ));
+                        if let Err(e) = transport.notify(&session_id, &notification).await {
+                            warn!("Failed to publish progress on NATS: {}", e);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    async fn reply(
+        &self,
+        reply: Option<async_nats::Subject>,
+        session_id: Option<&str>,
+        response: &JsonRpcResponse,
+    ) {
+        let Some(reply) = reply else {
+            debug!("NATS request without reply subject, dropping response");
+            return;
+        };
+        let payload = match serde_json::to_vec(response) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize JSON-RPC response: {}", e);
+                return;
+            }
+        };
+
+        let mut headers = async_nats::HeaderMap::new();
+        if let Some(session_id) = session_id {
+            headers.insert(NATS_SESSION_HEADER, session_id);
+        }
+        if let Err(e) = self
+            .client
+            .publish_with_headers(reply, headers, payload.into())
+            .await
+        {
+            warn!("Failed to publish NATS reply: {}", e);
+        }
+    }
+}
+
+/// Notification broker publishing on a NATS subject
+///
+/// Every replica receives every notification; the subject is not shared with
+/// a queue group.
+#[derive(Clone)]
+pub struct NatsNotificationBroker {
+    client: async_nats::Client,
+    subject: String,
+}
+
+impl NatsNotificationBroker {
+    /// Publish on the broker subject of `config`
+    pub fn new(client: async_nats::Client, config: &NatsConfig) -> Self {
+        Self {
+            client,
+            subject: config.broker_subject(),
+        }
+    }
+}
+
+#[async_trait]
+impl NotificationBroker for NatsNotificationBroker {
+    async fn publish(&self, notification: &McpNotification) -> McpResult<()> {
+        self.client
+            .publish(
+                self.subject.clone(),
+                serde_json::to_vec(notification)?.into(),
+            )
+            .await
+            .map_err(transport_error)
+    }
+
+    async fn subscribe(&self) -> McpResult<BoxStream<'static, McpNotification>> {
+        let subscriber = self
+            .client
+            .subscribe(self.subject.clone())
+            .await
+            .map_err(transport_error)?;
+        Ok(subscriber
+            .filter_map(|message| std::future::ready(serde_json::from_slice(&message.payload).ok()))
+            .boxed())
+    }
+}
+
+fn transport_error(error: impl std::fmt::Display) -> McpError {
+    McpError::Transport {
+        message: format!("NATS: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subjects() {
+        let config = NatsConfig::with_prefix("tenants.acme.mcp");
+        assert_eq!(config.request_subject(), "tenants.acme.mcp.rpc");
+        assert_eq!(
+            config.notification_subject("abc"),
+            "tenants.acme.mcp.sessions.abc.notifications"
+        );
+        assert_eq!(config.broker_subject(), "tenants.acme.mcp.notifications");
+        assert_eq!(config.queue_group.as_deref(), Some("mcp"));
+    }
+}
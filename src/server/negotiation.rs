@@ -0,0 +1,152 @@
+//! Content negotiation for resources with several representations
+//!
+//! A registry can serve one resource in several formats, such as JSON, CSV
+//! and HTML, by listing them from
+//! [`ResourceRegistry::list_representations`](crate::server::ResourceRegistry::list_representations).
+//! `resources/read` then picks the representation best matching its `accept`
+//! argument, which follows HTTP's `Accept` header: MIME types or file
+//! extensions, wildcards such as `text/*`, and `q` values from 0 to 1, where
+//! 0 rules a format out:
+//!
+//! ```json
+//! {"uri": "data://sales", "accept": "text/html;q=0.5, application/json, */*;q=0.1"}
+//! ```
+//!
+//! Formats of equal quality are preferred in the order given. When no
+//! representation is acceptable, the default one is converted by the
+//! [resource transformers](crate::server::transform) if possible.
+
+use crate::server::transform::resolve_format;
+
+/// One entry of an `accept` list
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaRange {
+    /// MIME type, possibly with wildcards such as `text/*` or `*/*`
+    pub mime_type: String,
+
+    /// Preference from 0 to 1
+    pub quality: f32,
+}
+
+impl MediaRange {
+    /// Whether `mime_type` falls in this range
+    pub fn matches(&self, mime_type: &str) -> bool {
+        let mime_type = resolve_format(mime_type);
+        match self.mime_type.split_once('/') {
+            Some(("*", "*")) => true,
+            Some((kind, "*")) => mime_type
+                .split_once('/')
+                .is_some_and(|(other, _)| other == kind),
+            _ => self.mime_type == mime_type,
+        }
+    }
+
+    /// How specific the range is: exact types over `type/*` over `*/*`
+    fn specificity(&self) -> u8 {
+        match self.mime_type.split_once('/') {
+            Some(("*", _)) => 0,
+            Some((_, "*")) => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// Parse an `accept` list, most preferred first
+///
+/// Entries without a `q` parameter have quality 1. Malformed `q` values count
+/// as 1 too, and entries of quality 0 are kept so they can rule formats out.
+pub fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    let mut ranges: Vec<MediaRange> = accept
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let mime_type = resolve_format(parts.next()?);
+            if mime_type.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+                .map_or(1.0, |q| q.clamp(0.0, 1.0));
+            Some(MediaRange { mime_type, quality })
+        })
+        .collect();
+    // Stable, so equal qualities keep the client's order
+    ranges.sort_by(|a, b| b.quality.total_cmp(&a.quality));
+    ranges
+}
+
+/// Quality of `mime_type` under `ranges`, from the most specific range
+/// matching it, or 0 if none does
+pub fn quality_of(ranges: &[MediaRange], mime_type: &str) -> f32 {
+    ranges
+        .iter()
+        .filter(|range| range.matches(mime_type))
+        .max_by_key(|range| range.specificity())
+        .map_or(0.0, |range| range.quality)
+}
+
+/// The available MIME type `accept` prefers, if any is acceptable
+///
+/// Ties go to the type named earlier in `accept`, then to the one listed
+/// earlier in `available`.
+pub fn negotiate<'a>(accept: &str, available: &'a [String]) -> Option<&'a str> {
+    let ranges = parse_accept(accept);
+    let rank = |mime_type: &str| {
+        let position = ranges
+            .iter()
+            .position(|range| range.quality > 0.0 && range.matches(mime_type))
+            .unwrap_or(usize::MAX);
+        (quality_of(&ranges, mime_type), position)
+    };
+    let mut best: Option<(&'a str, (f32, usize))> = None;
+    for mime_type in available {
+        let (quality, position) = rank(mime_type);
+        if quality <= 0.0 {
+            continue;
+        }
+        let better = match best {
+            None => true,
+            Some((_, (best_quality, best_position))) => {
+                quality > best_quality || (quality == best_quality && position < best_position)
+            }
+        };
+        if better {
+            best = Some((mime_type, (quality, position)));
+        }
+    }
+    best.map(|(mime_type, _)| mime_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let available = vec![
+            "application/json".to_string(),
+            "text/csv".to_string(),
+            "text/html".to_string(),
+        ];
+        assert_eq!(
+            negotiate("text/html;q=0.5, csv, */*;q=0.1", &available),
+            Some("text/csv")
+        );
+        assert_eq!(negotiate("text/*", &available), Some("text/csv"));
+        assert_eq!(
+            negotiate("text/html, text/csv", &available),
+            Some("text/html")
+        );
+        assert_eq!(
+            negotiate("*/*, application/json;q=0", &available),
+            Some("text/csv")
+        );
+        assert_eq!(negotiate("application/xml", &available), None);
+
+        let ranges = parse_accept("a/b;q=0.2, c/d, e/f;q=oops");
+        let order: Vec<&str> = ranges.iter().map(|r| r.mime_type.as_str()).collect();
+        assert_eq!(order, vec!["c/d", "e/f", "a/b"]);
+    }
+}
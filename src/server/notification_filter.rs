@@ -0,0 +1,201 @@
+//! Notification categories clients opt into
+//!
+//! A simple client may only care about progress, and every resource update
+//! or log message streamed to it is noise. Clients name the categories of
+//! notifications they want when they initialize:
+//!
+//! ```json
+//! {"capabilities": {"experimental": {"notifications": {"categories": ["progress", "resources"]}}}}
+//! ```
+//!
+//! or later with `notifications/filter`, which takes the same object and
+//! returns the filter now in effect (`{}` restores all categories). The
+//! filter is kept in the StreamableHTTP session state and applied to every
+//! notification sent to the session, including progress streamed with a
+//! `tools/call` response. Protocol notifications such as
+//! `notifications/cancelled`, and the chunks of a streamed `prompts/get`, are
+//! always sent.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use tracing::debug;
+
+use crate::protocol::ClientCapabilities;
+
+/// Experimental capability naming the notification categories
+pub const NOTIFICATIONS_CAPABILITY: &str = "notifications";
+
+/// Replaces the notification filter of the session
+pub const NOTIFICATIONS_FILTER: &str = "notifications/filter";
+
+/// Kind of notification a client can opt into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationCategory {
+    /// `notifications/progress`
+    Progress,
+    /// `notifications/resources/updated` and `notifications/resources/list_changed`
+    Resources,
+    /// `notifications/tools/list_changed` and `notifications/prompts/list_changed`
+    Lists,
+    /// `notifications/message`
+    Logging,
+    /// Any other notification, such as those sent with
+    /// [`NotificationSender::custom`](crate::server::NotificationSender::custom)
+    Custom,
+}
+
+impl NotificationCategory {
+    /// Every category
+    pub const ALL: [Self; 5] = [
+        Self::Progress,
+        Self::Resources,
+        Self::Lists,
+        Self::Logging,
+        Self::Custom,
+    ];
+
+    /// Category of notifications with `method`
+    ///
+    /// `None` for protocol notifications, which no filter holds back.
+    pub fn of(method: &str) -> Option<Self> {
+        match method {
+            "notifications/progress" => Some(Self::Progress),
+            "notifications/resources/updated" | "notifications/resources/list_changed" => {
+                Some(Self::Resources)
+            }
+            "notifications/tools/list_changed" | "notifications/prompts/list_changed" => {
+                Some(Self::Lists)
+            }
+            "notifications/message" => Some(Self::Logging),
+            "notifications/cancelled"
+            | "notifications/initialized"
+            | "notifications/prompts/chunk" => None,
+            _ => Some(Self::Custom),
+        }
+    }
+}
+
+/// Notification categories a session receives
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationFilter {
+    /// Categories sent to the client; all of them if absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categories: Option<BTreeSet<NotificationCategory>>,
+}
+
+impl NotificationFilter {
+    /// Send notifications of `categories` only
+    pub fn only(categories: impl IntoIterator<Item = NotificationCategory>) -> Self {
+        Self {
+            categories: Some(categories.into_iter().collect()),
+        }
+    }
+
+    /// Filter a client asked for in its `initialize` capabilities
+    ///
+    /// A malformed request is ignored, leaving every category on.
+    pub fn from_capabilities(capabilities: &ClientCapabilities) -> Self {
+        let Some(requested) = capabilities.experimental.get(NOTIFICATIONS_CAPABILITY) else {
+            return Self::default();
+        };
+        serde_json::from_value(requested.clone()).unwrap_or_else(|e| {
+            debug!("Ignoring notification filter {}: {}", requested, e);
+            Self::default()
+        })
+    }
+
+    /// Whether notifications of `category` are sent
+    pub fn allows(&self, category: Option<NotificationCategory>) -> bool {
+        match (&self.categories, category) {
+            (Some(categories), Some(category)) => categories.contains(&category),
+            _ => true,
+        }
+    }
+
+    /// Whether the JSON-RPC notification `message` is sent
+    pub fn allows_message(&self, message: &Value) -> bool {
+        match message.get("method").and_then(Value::as_str) {
+            Some(method) => self.allows(NotificationCategory::of(method)),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filters_by_category() {
+        let capabilities: ClientCapabilities = serde_json::from_value(json!({
+            "experimental": {"notifications": {"categories": ["progress", "lists"]}}
+        }))
+        .unwrap();
+        let filter = NotificationFilter::from_capabilities(&capabilities);
+        assert_eq!(
+            filter,
+            NotificationFilter::only([NotificationCategory::Progress, NotificationCategory::Lists])
+        );
+
+        let message = |method: &str| json!({"jsonrpc": "2.0", "method": method});
+        assert!(filter.allows_message(&message("notifications/progress")));
+        assert!(filter.allows_message(&message("notifications/tools/list_changed")));
+        assert!(filter.allows_message(&message("notifications/cancelled")));
+        assert!(!filter.allows_message(&message("notifications/resources/updated")));
+        assert!(!filter.allows_message(&message("notifications/message")));
+        assert!(!filter.allows_message(&message("notifications/index/rebuilt")));
+
+        let capabilities: ClientCapabilities = serde_json::from_value(json!({
+            "experimental": {"notifications": {"categories": ["everything"]}}
+        }))
+        .unwrap();
+        let filter = NotificationFilter::from_capabilities(&capabilities);
+        assert!(filter.allows(Some(NotificationCategory::Logging)));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_receive_wanted_categories() {
+        use crate::{
+            server::{NotificationDispatcher, NOTIFICATION_EVENT},
+            transport::{InMemoryEventStore, SessionManager},
+        };
+        use futures_util::StreamExt;
+        use std::{sync::Arc, time::Duration};
+
+        let sessions = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let dispatcher = NotificationDispatcher::default();
+        let task = dispatcher.deliver_to(sessions.clone());
+        tokio::task::yield_now().await;
+        let session = sessions.create_session().await.unwrap();
+        sessions
+            .update_session_state(&session, |state| {
+                state.notification_filter = NotificationFilter::only([NotificationCategory::Lists]);
+            })
+            .await
+            .unwrap();
+        let mut stream = Box::pin(sessions.subscribe_to_session(&session).await);
+
+        dispatcher.resources_list_changed().await.unwrap();
+        dispatcher.tools_list_changed().await.unwrap();
+
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.event_type, NOTIFICATION_EVENT);
+        assert_eq!(event.data["method"], "notifications/tools/list_changed");
+        assert_eq!(
+            sessions
+                .get_events_since(&session, None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        task.abort();
+    }
+}
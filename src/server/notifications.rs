@@ -0,0 +1,438 @@
+//! Server notification fan-out
+//!
+//! Resource updates and list changes can originate on any replica, while the
+//! affected clients may be connected to others. The [`NotificationDispatcher`]
+//! on every [`McpServer`](crate::server::McpServer) publishes such
+//! notifications through a [`NotificationBroker`], and every replica delivers
+//! what the broker carries to the StreamableHTTP sessions streaming from it:
+//!
+//! - `notifications/resources/updated` reaches sessions subscribed to the URI.
+//! - `notifications/*/list_changed` reaches every session.
+//!
+//! [`InProcessBroker`] is the default and covers a single process. For
+//! several replicas, share a broker across them: `RedisNotificationBroker`
+//! with the `redis` feature, or `NatsNotificationBroker` with `nats`.
+//!
+//! Application code outside request handlers, such as a file watcher or a
+//! background job, sends notifications through a [`NotificationSender`] from
+//! [`McpServer::notifier`](crate::server::McpServer::notifier).
+
+use async_trait::async_trait;
+use futures_util::{stream::BoxStream, StreamExt};
+use serde_json::Value;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{debug, warn};
+
+use crate::{
+    error::McpResult,
+    protocol::{
+        LogLevel, LoggingMessageNotification, McpMethod, McpNotification,
+        ResourceUpdatedNotification,
+    },
+    server::progress::{ProgressLevel, ProgressReporter, ProgressUpdate},
+    transport::{McpEvent, SessionManager},
+};
+
+/// Event type of notifications on session streams
+pub const NOTIFICATION_EVENT: &str = "notification";
+
+/// Carries notifications between the replicas of a server
+#[async_trait]
+pub trait NotificationBroker: Send + Sync {
+    /// Send a notification to every subscriber, on every replica
+    async fn publish(&self, notification: &McpNotification) -> McpResult<()>;
+
+    /// Notifications published from now on
+    async fn subscribe(&self) -> McpResult<BoxStream<'static, McpNotification>>;
+
+    /// Approximate bytes buffered for subscribers that have not caught up
+    fn buffered_bytes(&self) -> u64 {
+        0
+    }
+}
+
+/// Capacity of the in-process notification channel
+const BROKER_CAPACITY: usize = 256;
+
+/// Broker reaching subscribers in the same process only
+#[derive(Clone)]
+pub struct InProcessBroker {
+    sender: broadcast::Sender<McpNotification>,
+    /// Notifications published and their total size, for the average size
+    published: Arc<(AtomicU64, AtomicU64)>,
+}
+
+impl InProcessBroker {
+    /// Create a broker without subscribers
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(BROKER_CAPACITY).0,
+            published: Arc::new((AtomicU64::new(0), AtomicU64::new(0))),
+        }
+    }
+}
+
+impl Default for InProcessBroker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl NotificationBroker for InProcessBroker {
+    async fn publish(&self, notification: &McpNotification) -> McpResult<()> {
+        // No receivers just means nobody delivers notifications yet
+        let _ = self.sender.send(notification.clone());
+        let bytes = serde_json::to_vec(notification).map_or(0, |json| json.len() as u64);
+        self.published.0.fetch_add(1, Ordering::Relaxed);
+        self.published.1.fetch_add(bytes, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> McpResult<BoxStream<'static, McpNotification>> {
+        Ok(BroadcastStream::new(self.sender.subscribe())
+            .filter_map(|notification| std::future::ready(notification.ok()))
+            .boxed())
+    }
+
+    fn buffered_bytes(&self) -> u64 {
+        let count = self.published.0.load(Ordering::Relaxed);
+        let bytes = self.published.1.load(Ordering::Relaxed);
+        (self.sender.len() as u64 * bytes)
+            .checked_div(count)
+            .unwrap_or(0)
+    }
+}
+
+/// Publishes server notifications and delivers them to sessions
+#[derive(Clone)]
+pub struct NotificationDispatcher {
+    broker: Arc<dyn NotificationBroker>,
+}
+
+impl std::fmt::Debug for NotificationDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationDispatcher")
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for NotificationDispatcher {
+    fn default() -> Self {
+        Self::new(Arc::new(InProcessBroker::new()))
+    }
+}
+
+impl NotificationDispatcher {
+    /// Create a dispatcher publishing through `broker`
+    pub fn new(broker: Arc<dyn NotificationBroker>) -> Self {
+        Self { broker }
+    }
+
+    /// Get the broker notifications are published through
+    pub fn broker(&self) -> &Arc<dyn NotificationBroker> {
+        &self.broker
+    }
+
+    /// Publish a notification to every replica
+    pub async fn publish(&self, notification: McpNotification) -> McpResult<()> {
+        self.broker.publish(&notification).await
+    }
+
+    /// Publish a notification in the background
+    ///
+    /// For synchronous callers; failures are logged.
+    pub fn emit(&self, notification: McpNotification) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("Dropping notification: no async runtime");
+            return;
+        };
+        let broker = Arc::clone(&self.broker);
+        runtime.spawn(async move {
+            if let Err(e) = broker.publish(&notification).await {
+                warn!("Failed to publish notification: {}", e);
+            }
+        });
+    }
+
+    /// Announce that a resource changed to sessions subscribed to it
+    pub async fn resource_updated(&self, uri: impl Into<String>) -> McpResult<()> {
+        self.publish(McpNotification::new(
+            McpMethod::NotificationsResourcesUpdated(ResourceUpdatedNotification {
+                uri: uri.into(),
+            }),
+        ))
+        .await
+    }
+
+    /// Announce that the list of resources changed
+    pub async fn resources_list_changed(&self) -> McpResult<()> {
+        self.publish(McpNotification::new(
+            McpMethod::NotificationsResourcesListChanged,
+        ))
+        .await
+    }
+
+    /// Announce that the list of tools changed
+    pub async fn tools_list_changed(&self) -> McpResult<()> {
+        self.publish(McpNotification::new(
+            McpMethod::NotificationsToolsListChanged,
+        ))
+        .await
+    }
+
+    /// Announce that the list of prompts changed
+    pub async fn prompts_list_changed(&self) -> McpResult<()> {
+        self.publish(McpNotification::new(
+            McpMethod::NotificationsPromptsListChanged,
+        ))
+        .await
+    }
+
+    /// Deliver notifications from the broker to this replica's sessions
+    ///
+    /// Call once per replica, next to
+    /// [`SessionManager::start_cleanup_task`]. The task ends when the broker
+    /// stream does.
+    pub fn deliver_to(&self, session_manager: SessionManager) -> tokio::task::JoinHandle<()> {
+        let broker = Arc::clone(&self.broker);
+        tokio::spawn(async move {
+            let mut notifications = match broker.subscribe().await {
+                Ok(notifications) => notifications,
+                Err(e) => {
+                    warn!("Failed to subscribe to notifications: {}", e);
+                    return;
+                }
+            };
+            while let Some(notification) = notifications.next().await {
+                deliver(&session_manager, &notification).await;
+            }
+            debug!("Notification broker stream ended");
+        })
+    }
+}
+
+/// Typed notifications from application code
+///
+/// Cloning is cheap, so a sender can be handed to background tasks. Each
+/// notification goes where clients expect it:
+///
+/// - [`resource_updated`](Self::resource_updated) to sessions subscribed to
+///   the URI
+/// - [`log`](Self::log) and [`custom`](Self::custom) to every session
+/// - [`progress`](Self::progress) to the call that carries the progress token
+#[derive(Clone)]
+pub struct NotificationSender {
+    dispatcher: NotificationDispatcher,
+    progress: Arc<ProgressReporter>,
+    logger: Option<String>,
+}
+
+impl std::fmt::Debug for NotificationSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationSender")
+            .field("logger", &self.logger)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NotificationSender {
+    /// Send through `dispatcher`, reporting progress to `progress`
+    pub fn new(dispatcher: NotificationDispatcher, progress: Arc<ProgressReporter>) -> Self {
+        Self {
+            dispatcher,
+            progress,
+            logger: None,
+        }
+    }
+
+    /// Name log messages as coming from `logger`
+    pub fn with_logger(mut self, logger: impl Into<String>) -> Self {
+        self.logger = Some(logger.into());
+        self
+    }
+
+    /// Announce that a resource changed to sessions subscribed to it
+    pub async fn resource_updated(&self, uri: impl Into<String>) -> McpResult<()> {
+        self.dispatcher.resource_updated(uri).await
+    }
+
+    /// Send a `notifications/message` log message to every session
+    pub async fn log(&self, level: LogLevel, message: impl Into<String>) -> McpResult<()> {
+        self.dispatcher
+            .publish(McpNotification::new(McpMethod::NotificationsMessage(
+                LoggingMessageNotification {
+                    level,
+                    logger: self.logger.clone(),
+                    data: Value::String(message.into()),
+                },
+            )))
+            .await
+    }
+
+    /// Report `percent` (0 to 100) done for the call with progress `token`
+    ///
+    /// Reaches the client while the call's response is streamed; updates for
+    /// tokens no call is waiting on are dropped.
+    pub async fn progress(&self, token: impl Into<String>, percent: f64) {
+        let percent = percent.clamp(0.0, 100.0);
+        self.progress
+            .report_progress(ProgressUpdate::new(
+                token.into(),
+                format!("{:.0}% complete", percent),
+                ProgressLevel::Info,
+                percent.round() as usize,
+                100,
+            ))
+            .await;
+    }
+
+    /// Send a notification with any method to every session
+    pub async fn custom(&self, method: impl Into<String>, params: Option<Value>) -> McpResult<()> {
+        self.dispatcher
+            .publish(McpNotification::new(McpMethod::Custom {
+                method: method.into(),
+                params,
+            }))
+            .await
+    }
+}
+
+async fn deliver(session_manager: &SessionManager, notification: &McpNotification) {
+    let sessions = match &notification.method {
+        McpMethod::NotificationsResourcesUpdated(update) => {
+            match session_manager.subscribers(&update.uri).await {
+                Ok(sessions) => sessions,
+                Err(e) => {
+                    warn!("Failed to look up subscribers of {}: {}", update.uri, e);
+                    return;
+                }
+            }
+        }
+        _ => session_manager.session_ids().await,
+    };
+
+    let data = match serde_json::to_value(notification) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Failed to serialize notification: {}", e);
+            return;
+        }
+    };
+    for session_id in sessions {
+        let event = McpEvent::new(session_id, NOTIFICATION_EVENT.to_string(), data.clone());
+        if let Err(e) = session_manager.deliver_local(&event).await {
+            warn!(
+                "Failed to deliver notification to session {}: {}",
+                event.session_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{InMemoryEventStore, InMemoryStateBackend};
+    use std::time::Duration;
+
+    fn replica(backend: &InMemoryStateBackend) -> SessionManager {
+        SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        )
+        .with_backend(Arc::new(backend.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_notifications_reach_sessions_on_other_replicas() {
+        let backend = InMemoryStateBackend::new();
+        let (first, second) = (replica(&backend), replica(&backend));
+        let dispatcher = NotificationDispatcher::default();
+        let first_task = dispatcher.deliver_to(first.clone());
+        let second_task = dispatcher.deliver_to(second.clone());
+        tokio::task::yield_now().await;
+
+        let subscribed = second.create_session().await.unwrap();
+        second
+            .update_session_state(&subscribed, |state| {
+                state.subscriptions.insert("file:///a".into());
+            })
+            .await
+            .unwrap();
+        let other = second.create_session().await.unwrap();
+        let mut subscribed_stream = Box::pin(second.subscribe_to_session(&subscribed).await);
+        let mut other_stream = Box::pin(second.subscribe_to_session(&other).await);
+
+        // Published by a node the clients are not connected to
+        dispatcher.resource_updated("file:///a").await.unwrap();
+        dispatcher.tools_list_changed().await.unwrap();
+
+        let event = subscribed_stream.next().await.unwrap();
+        assert_eq!(event.event_type, NOTIFICATION_EVENT);
+        assert_eq!(event.data["method"], "notifications/resources/updated");
+        assert_eq!(event.data["params"]["uri"], "file:///a");
+        let event = subscribed_stream.next().await.unwrap();
+        assert_eq!(event.data["method"], "notifications/tools/list_changed");
+
+        // Only the list change reaches the unsubscribed session, exactly once
+        let event = other_stream.next().await.unwrap();
+        assert_eq!(event.data["method"], "notifications/tools/list_changed");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), other_stream.next())
+                .await
+                .is_err()
+        );
+
+        first_task.abort();
+        second_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_notification_sender() {
+        let sessions = replica(&InMemoryStateBackend::new());
+        let dispatcher = NotificationDispatcher::default();
+        let task = dispatcher.deliver_to(sessions.clone());
+        tokio::task::yield_now().await;
+        let session = sessions.create_session().await.unwrap();
+        let mut stream = Box::pin(sessions.subscribe_to_session(&session).await);
+
+        let progress = Arc::new(ProgressReporter::new());
+        let mut updates = progress.subscribe();
+        let notifier = NotificationSender::new(dispatcher, progress).with_logger("indexer");
+
+        notifier
+            .log(LogLevel::Warning, "Disk almost full")
+            .await
+            .unwrap();
+        notifier
+            .custom(
+                "notifications/index/rebuilt",
+                Some(serde_json::json!({"docs": 3})),
+            )
+            .await
+            .unwrap();
+        notifier.progress("call-1", 142.0).await;
+
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.data["method"], "notifications/message");
+        assert_eq!(event.data["params"]["level"], "warning");
+        assert_eq!(event.data["params"]["logger"], "indexer");
+        assert_eq!(event.data["params"]["data"], "Disk almost full");
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.data["method"], "notifications/index/rebuilt");
+        assert_eq!(event.data["params"]["docs"], 3);
+
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.operation_id, "call-1");
+        assert_eq!((update.current, update.total), (100, 100));
+
+        task.abort();
+    }
+}
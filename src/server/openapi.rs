@@ -0,0 +1,216 @@
+//! OpenAPI description of the HTTP surface
+//!
+//! Every MCP mount serves `{base_path}/openapi.json`, an OpenAPI 3.1 document
+//! describing the HTTP endpoints so API gateways and client generators can
+//! consume the server. JSON-RPC does not map onto OpenAPI operations, so the
+//! document also carries an `x-mcp` extension with the catalog of the JSON-RPC
+//! methods the server answers, as served by [`rpc.discover`](crate::server::discover),
+//! and the tools visible to the caller, with their input schemas.
+
+use serde_json::{json, Value};
+
+use crate::{
+    error::McpResult,
+    protocol::SUPPORTED_PROTOCOL_VERSIONS,
+    security::SecurityContext,
+    server::{registry::ToolRegistry, McpServer, McpServerState},
+};
+
+/// Path of the document below the base path
+pub const OPENAPI_PATH: &str = "openapi.json";
+
+/// Build the OpenAPI document for a server mounted at `base_path`
+///
+/// Tools are listed with `context`, so callers only learn about the tools
+/// `tools/list` would show them.
+pub async fn openapi_document<S>(
+    server: &McpServer<S>,
+    base_path: &str,
+    context: &SecurityContext,
+) -> McpResult<Value>
+where
+    S: McpServerState,
+{
+    let config = server.config();
+    let sub_path = |name: &str| format!("{}/{}", base_path.trim_end_matches('/'), name);
+
+    let mut paths = serde_json::Map::new();
+    paths.insert(
+        base_path.to_string(),
+        json!({
+            "get": {
+                "summary": "Endpoint information, health (?transport=health) or an SSE stream",
+                "parameters": [session_parameter(), {
+                    "name": "transport",
+                    "in": "query",
+                    "schema": {"type": "string", "enum": ["health"]}
+                }],
+                "responses": {
+                    "200": {
+                        "description": "Endpoint information or health as JSON, or an SSE stream when `Accept: text/event-stream`",
+                        "content": {
+                            "application/json": {"schema": {"type": "object"}},
+                            "text/event-stream": {"schema": {"type": "string"}}
+                        }
+                    }
+                }
+            },
+            "post": {
+                "summary": "Send a JSON-RPC request or notification (StreamableHTTP)",
+                "parameters": [session_header()],
+                "requestBody": {
+                    "required": true,
+                    "content": {"application/json": {"schema": {"$ref": "#/components/schemas/JsonRpcRequest"}}}
+                },
+                "responses": {
+                    "200": {
+                        "description": "JSON-RPC response, streamed as SSE for long tool calls when accepted",
+                        "headers": {"Mcp-Session-Id": {"schema": {"type": "string"}}},
+                        "content": {
+                            "application/json": {"schema": {"$ref": "#/components/schemas/JsonRpcResponse"}},
+                            "text/event-stream": {"schema": {"type": "string"}}
+                        }
+                    },
+                    "202": {"description": "Notification accepted"},
+                    "404": {"description": "Unknown or expired session"}
+                }
+            },
+            "delete": {
+                "summary": "Terminate a StreamableHTTP session",
+                "parameters": [session_header()],
+                "responses": {
+                    "200": {"description": "Session terminated"},
+                    "404": {"description": "Unknown session"}
+                }
+            }
+        }),
+    );
+    paths.insert(
+        sub_path("sse"),
+        json!({
+            "get": {
+                "summary": "Open a server-sent event stream",
+                "parameters": [session_parameter()],
+                "responses": {
+                    "200": {
+                        "description": "Event stream",
+                        "content": {"text/event-stream": {"schema": {"type": "string"}}}
+                    }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        sub_path("messages"),
+        json!({
+            "post": {
+                "summary": "Send a JSON-RPC message on a legacy HTTP+SSE session",
+                "parameters": [session_parameter()],
+                "requestBody": {
+                    "required": true,
+                    "content": {"application/json": {"schema": {"$ref": "#/components/schemas/JsonRpcRequest"}}}
+                },
+                "responses": {
+                    "202": {"description": "Accepted; the response is sent on the event stream"},
+                    "404": {"description": "Unknown session"}
+                }
+            }
+        }),
+    );
+    paths.insert(
+        sub_path(OPENAPI_PATH),
+        json!({
+            "get": {
+                "summary": "This document",
+                "responses": {
+                    "200": {"description": "OpenAPI document", "content": {"application/json": {}}}
+                }
+            }
+        }),
+    );
+    #[cfg(feature = "ui")]
+    paths.insert(
+        sub_path("ui"),
+        json!({
+            "get": {
+                "summary": "Resource browser",
+                "responses": {"200": {"description": "HTML page", "content": {"text/html": {}}}}
+            }
+        }),
+    );
+
+    let mut tools: Vec<Value> = server
+        .state()
+        .tool_registry()
+        .list_tools(context)
+        .await?
+        .into_iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": tool.input_schema,
+            })
+        })
+        .collect();
+    tools.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+    Ok(json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": config.name,
+            "version": config.version,
+            "description": "Model Context Protocol server. Operations are JSON-RPC 2.0 methods; see `x-mcp`."
+        },
+        "paths": paths,
+        "components": {"schemas": rpc_schemas()},
+        "x-mcp": {
+            "protocolVersions": SUPPORTED_PROTOCOL_VERSIONS,
+            "methods": server.method_catalog(),
+            "tools": tools,
+        }
+    }))
+}
+
+fn session_parameter() -> Value {
+    json!({"name": "session_id", "in": "query", "schema": {"type": "string"}})
+}
+
+fn session_header() -> Value {
+    json!({"name": "Mcp-Session-Id", "in": "header", "schema": {"type": "string"}})
+}
+
+fn rpc_schemas() -> Value {
+    let id = json!({"oneOf": [{"type": "string"}, {"type": "integer"}, {"type": "null"}]});
+    json!({
+        "JsonRpcRequest": {
+            "type": "object",
+            "required": ["jsonrpc", "method"],
+            "properties": {
+                "jsonrpc": {"const": "2.0"},
+                "method": {"type": "string"},
+                "params": {},
+                "id": id
+            }
+        },
+        "JsonRpcResponse": {
+            "type": "object",
+            "required": ["jsonrpc"],
+            "properties": {
+                "jsonrpc": {"const": "2.0"},
+                "result": {},
+                "error": {"$ref": "#/components/schemas/JsonRpcError"},
+                "id": id
+            }
+        },
+        "JsonRpcError": {
+            "type": "object",
+            "required": ["code", "message"],
+            "properties": {
+                "code": {"type": "integer"},
+                "message": {"type": "string"},
+                "data": {}
+            }
+        }
+    })
+}
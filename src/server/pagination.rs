@@ -0,0 +1,303 @@
+//! Cursor-stable pagination of listings
+//!
+//! With [`PaginationConfig::page_size`] set, `tools/list`, `prompts/list` and
+//! `resources/list` return at most that many entries and a `nextCursor` for
+//! the rest. Registries may change while a client pages through them, so the
+//! in-memory registries keep their entries in a [`SnapshotMap`]: every change
+//! starts a new generation, and a cursor names the generation it was issued
+//! for and the last key it returned. Later pages are read from that
+//! generation's snapshot, so a client sees exactly the entries registered when
+//! it started listing, each once, however the registry changes in between.
+//!
+//! Snapshots are copy-on-write and only the last
+//! [`SnapshotMap::RETAINED_SNAPSHOTS`] generations that handed out a cursor
+//! are kept. A cursor for an older generation continues after its last key in
+//! the current entries: still without duplicates, but entries registered
+//! meanwhile may show up and removed ones are skipped.
+//!
+//! Registries without generations page through their sorted listing the same
+//! way, see [`paginate`].
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ops::Bound,
+    sync::{Arc, Mutex},
+};
+
+use crate::error::{McpError, McpResult};
+
+/// Pagination settings of listings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaginationConfig {
+    /// Most entries per page; everything in one page if `None`
+    pub page_size: Option<usize>,
+}
+
+/// One page of a listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// Entries of this page
+    pub items: Vec<T>,
+
+    /// Cursor for the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+/// Position in a listing: the generation listed and the last key returned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    /// Generation of the registry the listing started at
+    pub generation: u64,
+
+    /// Key of the last entry returned
+    pub after: String,
+}
+
+impl PageCursor {
+    /// Opaque string form handed to clients
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.generation, self.after))
+    }
+
+    /// Parse a cursor from [`encode`](Self::encode)
+    pub fn decode(cursor: &str) -> McpResult<Self> {
+        let invalid = || McpError::Validation {
+            message: format!("Invalid cursor '{}'", cursor),
+        };
+        let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (generation, after) = decoded.split_once(':').ok_or_else(invalid)?;
+        Ok(Self {
+            generation: generation.parse().map_err(|_| invalid())?,
+            after: after.to_string(),
+        })
+    }
+}
+
+/// Page through `entries` by key, for listings without generations
+///
+/// Entries are sorted by key; a cursor continues after the last key it
+/// returned.
+pub fn paginate<T>(
+    mut entries: Vec<(String, T)>,
+    cursor: Option<&str>,
+    limit: usize,
+) -> McpResult<Page<T>> {
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let after = cursor.map(PageCursor::decode).transpose()?.map(|c| c.after);
+    let start = after.map_or(0, |after| entries.partition_point(|(key, _)| *key <= after));
+    let mut rest = entries.into_iter().skip(start);
+    let items: Vec<(String, T)> = rest.by_ref().take(limit.max(1)).collect();
+    let next_cursor = match (rest.next(), items.last()) {
+        (Some(_), Some((key, _))) => Some(
+            PageCursor {
+                generation: 0,
+                after: key.clone(),
+            }
+            .encode(),
+        ),
+        _ => None,
+    };
+    Ok(Page {
+        items: items.into_iter().map(|(_, item)| item).collect(),
+        next_cursor,
+    })
+}
+
+type Entries<T> = Arc<BTreeMap<String, T>>;
+
+/// Map whose changes start new generations, with snapshots for paging
+///
+/// Clones start with no retained snapshots of their own.
+#[derive(Debug)]
+pub struct SnapshotMap<T> {
+    entries: Entries<T>,
+    generation: u64,
+    retained: Mutex<VecDeque<(u64, Entries<T>)>>,
+}
+
+impl<T: Clone> SnapshotMap<T> {
+    /// Generations whose snapshots are kept for open cursors
+    pub const RETAINED_SNAPSHOTS: usize = 8;
+
+    /// An empty map at generation 0
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(BTreeMap::new()),
+            generation: 0,
+            retained: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Current generation, advanced by every change
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The entry under `key`
+    pub fn get(&self, key: &str) -> Option<&T> {
+        self.entries.get(key)
+    }
+
+    /// Whether there is an entry under `key`
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Entries in key order
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.values()
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add or replace the entry under `key`
+    pub fn insert(&mut self, key: impl Into<String>, value: T) -> Option<T> {
+        self.generation += 1;
+        Arc::make_mut(&mut self.entries).insert(key.into(), value)
+    }
+
+    /// Remove the entry under `key`
+    pub fn remove(&mut self, key: &str) -> Option<T> {
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+        self.generation += 1;
+        Arc::make_mut(&mut self.entries).remove(key)
+    }
+
+    /// Up to `limit` entries after `cursor`, mapped by `visible`, which skips
+    /// entries by returning `None`
+    pub fn page<R>(
+        &self,
+        cursor: Option<&str>,
+        limit: usize,
+        mut visible: impl FnMut(&T) -> Option<R>,
+    ) -> McpResult<Page<R>> {
+        let cursor = cursor.map(PageCursor::decode).transpose()?;
+        let mut retained = self.retained.lock().unwrap();
+        let (generation, entries) = match &cursor {
+            Some(cursor) if cursor.generation != self.generation => retained
+                .iter()
+                .find(|(generation, _)| *generation == cursor.generation)
+                .cloned()
+                .unwrap_or_else(|| (self.generation, self.entries.clone())),
+            _ => (self.generation, self.entries.clone()),
+        };
+
+        let lower = match &cursor {
+            Some(cursor) => Bound::Excluded(cursor.after.clone()),
+            None => Bound::Unbounded,
+        };
+        let mut rest = entries
+            .range::<String, _>((lower, Bound::Unbounded))
+            .filter_map(|(key, value)| visible(value).map(|item| (key, item)));
+        let items: Vec<(&String, R)> = rest.by_ref().take(limit.max(1)).collect();
+        let next_cursor = match (rest.next(), items.last()) {
+            (Some(_), Some((key, _))) => Some(
+                PageCursor {
+                    generation,
+                    after: key.to_string(),
+                }
+                .encode(),
+            ),
+            _ => None,
+        };
+
+        if next_cursor.is_some() && !retained.iter().any(|(g, _)| *g == generation) {
+            retained.push_back((generation, entries.clone()));
+            while retained.len() > Self::RETAINED_SNAPSHOTS {
+                retained.pop_front();
+            }
+        }
+        Ok(Page {
+            items: items.into_iter().map(|(_, item)| item).collect(),
+            next_cursor,
+        })
+    }
+}
+
+impl<T: Clone> Default for SnapshotMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for SnapshotMap<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            generation: self.generation,
+            retained: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(page: &Page<String>) -> Vec<&str> {
+        page.items.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_pages_are_stable_while_the_map_changes() {
+        let mut map = SnapshotMap::new();
+        for key in ["b", "d", "f", "h"] {
+            map.insert(key, key.to_string());
+        }
+
+        let first = map.page(None, 2, |v| Some(v.clone())).unwrap();
+        assert_eq!(keys(&first), vec!["b", "d"]);
+
+        // Changes after the first page do not show up in later ones
+        map.insert("e", "e".to_string());
+        map.remove("f");
+        map.insert("a", "a".to_string());
+        let second = map
+            .page(first.next_cursor.as_deref(), 2, |v| Some(v.clone()))
+            .unwrap();
+        assert_eq!(keys(&second), vec!["f", "h"]);
+        assert_eq!(second.next_cursor, None);
+
+        // A new listing sees the current entries
+        let fresh = map.page(None, 10, |v| Some(v.clone())).unwrap();
+        assert_eq!(keys(&fresh), vec!["a", "b", "d", "e", "h"]);
+
+        // Cursors of evicted generations continue in the current entries
+        let first = map.page(None, 2, |v| Some(v.clone())).unwrap();
+        for i in 0..=SnapshotMap::<String>::RETAINED_SNAPSHOTS {
+            let key = format!("z{}", i);
+            map.insert(key.clone(), key.clone());
+            map.page(None, 1, |v| Some(v.clone())).unwrap();
+        }
+        map.remove("d");
+        let second = map
+            .page(first.next_cursor.as_deref(), 2, |v| Some(v.clone()))
+            .unwrap();
+        assert_eq!(keys(&second), vec!["e", "h"]);
+
+        let listed = paginate(vec![("y".to_string(), 1), ("x".to_string(), 2)], None, 1).unwrap();
+        assert_eq!(listed.items, vec![2]);
+        let rest = paginate(
+            vec![("y".to_string(), 1), ("x".to_string(), 2)],
+            listed.next_cursor.as_deref(),
+            1,
+        )
+        .unwrap();
+        assert_eq!((rest.items, rest.next_cursor), (vec![1], None));
+        assert!(PageCursor::decode("not a cursor").is_err());
+    }
+}
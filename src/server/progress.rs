@@ -144,6 +144,7 @@ struct ProgressOperation {
 }
 
 /// Progress reporter for tracking and broadcasting operation progress
+#[derive(Debug)]
 pub struct ProgressReporter {
     /// Active operations
     operations: Arc<RwLock<HashMap<String, ProgressOperation>>>,
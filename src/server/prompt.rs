@@ -4,12 +4,23 @@
 //! reusable AI interaction patterns, workflow templates, and dynamic prompt generation.
 
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::{cmp::Ordering, collections::HashMap, fmt};
+use tracing::warn;
 
 use crate::{
     error::{McpError, McpResult},
     security::SecurityContext,
+    server::exposure::profile_of,
+    server::locale::{LOCALE_FIELD, TIMEZONE_FIELD},
+    server::pagination::{paginate, Page, SnapshotMap},
+    server::prompt_bundle::{
+        compare_versions, ConflictResolution, PromptBundle, PromptImportReport,
+    },
+    server::prompt_lint::{PromptDiagnostic, PromptLinter},
+    server::prompt_stream::PromptStream,
+    server::registry_changelog::{added, RegistryAction, RegistryChangelog, RegistryKind},
 };
 
 /// Prompt content with support for text and embedded resources
@@ -78,6 +89,19 @@ pub struct PromptParameter {
     pub default: Option<serde_json::Value>,
 }
 
+impl PromptParameter {
+    /// Required string parameter `name`, still to be described
+    pub fn stub(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            required: true,
+            schema: Some(serde_json::json!({"type": "string"})),
+            default: None,
+        }
+    }
+}
+
 /// Complete prompt template definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Prompt {
@@ -113,6 +137,9 @@ pub struct GetPromptResult {
     pub messages: Vec<PromptMessage>,
     /// Description with any parameter substitutions
     pub description: String,
+    /// Result metadata, such as token estimates
+    #[serde(default, rename = "_meta", skip_serializing_if = "HashMap::is_empty")]
+    pub meta: HashMap<String, serde_json::Value>,
 }
 
 /// Prompt category for organization
@@ -144,6 +171,17 @@ pub trait TemplateEngine: Send + Sync {
         params: &HashMap<String, serde_json::Value>,
         required: &[String],
     ) -> McpResult<()>;
+
+    /// Parameter a `{{expression}}` refers to, or `None` if it cannot be rendered
+    ///
+    /// By default only plain `{{name}}` references are understood.
+    fn placeholder_name<'a>(&self, expression: &'a str) -> Option<&'a str> {
+        let valid = !expression.is_empty()
+            && expression
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.');
+        valid.then_some(expression)
+    }
 }
 
 /// Simple template engine using basic string replacement
@@ -203,6 +241,24 @@ pub trait PromptRegistry: Send + Sync {
     /// List all available prompts
     async fn list_prompts(&self, context: &SecurityContext) -> McpResult<Vec<Prompt>>;
 
+    /// List up to `limit` prompts after `cursor`, for paginated `prompts/list`
+    ///
+    /// Defaults to paging through [`list_prompts`](Self::list_prompts) by
+    /// name, see [`paginate`].
+    async fn list_prompts_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<Prompt>> {
+        let prompts = self.list_prompts(context).await?;
+        paginate(
+            prompts.into_iter().map(|p| (p.name.clone(), p)).collect(),
+            cursor,
+            limit,
+        )
+    }
+
     /// Get a specific prompt by name
     async fn get_prompt(&self, name: &str, context: &SecurityContext) -> McpResult<Option<Prompt>>;
 
@@ -213,6 +269,20 @@ pub trait PromptRegistry: Send + Sync {
         context: &SecurityContext,
     ) -> McpResult<GetPromptResult>;
 
+    /// Render a prompt, one message at a time as the stream is consumed
+    ///
+    /// Used for streamed `prompts/get` requests, see
+    /// [`prompt_stream`](crate::server::prompt_stream). By default the prompt is
+    /// rendered with [`get_prompt_with_args`](Self::get_prompt_with_args) and
+    /// its messages are streamed from memory.
+    async fn stream_prompt_with_args(
+        &self,
+        request: GetPromptRequest,
+        context: &SecurityContext,
+    ) -> McpResult<PromptStream> {
+        Ok(self.get_prompt_with_args(request, context).await?.into())
+    }
+
     /// List prompt categories for organization
     async fn list_categories(&self, context: &SecurityContext) -> McpResult<Vec<PromptCategory>>;
 
@@ -226,29 +296,100 @@ pub trait PromptRegistry: Send + Sync {
         params: &HashMap<String, serde_json::Value>,
         context: &SecurityContext,
     ) -> McpResult<()>;
+
+    /// Export the prompts and categories visible to `context` as a bundle
+    async fn export_bundle(&self, context: &SecurityContext) -> McpResult<PromptBundle> {
+        Ok(PromptBundle::new(
+            self.list_prompts(context).await?,
+            self.list_categories(context).await?,
+        ))
+    }
 }
 
 /// In-memory prompt registry implementation
 #[derive(Debug, Clone)]
 pub struct InMemoryPromptRegistry {
-    prompts: HashMap<String, Prompt>,
+    prompts: SnapshotMap<Prompt>,
     categories: Vec<PromptCategory>,
     template_engine: SimpleTemplateEngine,
+    linter: PromptLinter,
+    changelog: Option<RegistryChangelog>,
 }
 
 impl InMemoryPromptRegistry {
     /// Create a new in-memory prompt registry
     pub fn new() -> Self {
         Self {
-            prompts: HashMap::new(),
+            prompts: SnapshotMap::new(),
             categories: Vec::new(),
             template_engine: SimpleTemplateEngine,
+            linter: PromptLinter::new(),
+            changelog: None,
         }
     }
 
+    /// Lint prompts passed to [`try_add_prompt`](Self::try_add_prompt) with the given linter
+    pub fn with_linter(mut self, linter: PromptLinter) -> Self {
+        self.linter = linter;
+        self
+    }
+
+    /// Record additions and removals in `changelog`
+    pub fn with_changelog(mut self, changelog: RegistryChangelog) -> Self {
+        self.changelog = Some(changelog);
+        self
+    }
+
     /// Add a prompt to the registry
+    ///
+    /// Placeholders without a declared parameter are logged; use
+    /// [`try_add_prompt`](Self::try_add_prompt) to reject such prompts.
     pub fn add_prompt(&mut self, prompt: Prompt) {
-        self.prompts.insert(prompt.name.clone(), prompt);
+        let undeclared = self.linter.undeclared(&prompt);
+        if !undeclared.is_empty() {
+            warn!(
+                "Prompt '{}' uses undeclared parameters: {}",
+                prompt.name,
+                undeclared.join(", ")
+            );
+        }
+        let name = prompt.name.clone();
+        let previous = self.prompts.insert(name.clone(), prompt);
+        if let Some(changelog) = &self.changelog {
+            changelog.record(RegistryKind::Prompt, name, added(&previous));
+        }
+    }
+
+    /// Remove a prompt from the registry
+    pub fn remove_prompt(&mut self, name: &str) -> Option<Prompt> {
+        let removed = self.prompts.remove(name);
+        if let (Some(changelog), Some(_)) = (&self.changelog, &removed) {
+            changelog.record(RegistryKind::Prompt, name, RegistryAction::Removed);
+        }
+        removed
+    }
+
+    /// Lint a prompt and add it if no errors are found
+    ///
+    /// Returns the warnings; errors reject the prompt with a validation error
+    /// listing every diagnostic.
+    pub fn try_add_prompt(&mut self, prompt: Prompt) -> McpResult<Vec<PromptDiagnostic>> {
+        let diagnostics = self.linter.lint(&prompt);
+        if diagnostics.iter().any(PromptDiagnostic::is_error) {
+            return Err(McpError::Validation {
+                message: format!(
+                    "Prompt '{}' failed linting: {}",
+                    prompt.name,
+                    diagnostics
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                ),
+            });
+        }
+        self.add_prompt(prompt);
+        Ok(diagnostics)
     }
 
     /// Add a category to the registry
@@ -356,36 +497,146 @@ impl InMemoryPromptRegistry {
         self.add_prompt(prompt);
     }
 
-    /// Render a prompt message with parameter substitution
-    fn render_message(
+    /// The prompt a request asks for, with its arguments completed and checked
+    async fn prepare(
         &self,
-        message: &PromptMessage,
-        params: &HashMap<String, serde_json::Value>,
-    ) -> McpResult<PromptMessage> {
-        let rendered_content = match &message.content {
-            PromptContent::Text { text } => {
-                let rendered_text = self.template_engine.substitute(text, params)?;
-                PromptContent::Text {
-                    text: rendered_text,
-                }
+        request: &mut GetPromptRequest,
+        context: &SecurityContext,
+    ) -> McpResult<(Prompt, HashMap<String, serde_json::Value>)> {
+        let prompt = self
+            .get_prompt(&request.name, context)
+            .await?
+            .ok_or_else(|| McpError::InvalidResource {
+                uri: format!("prompt:{}", request.name),
+                message: "Prompt not found".to_string(),
+            })?;
+
+        let mut params = request.arguments.take().unwrap_or_default();
+
+        // The client's locale and time zone, unless passed as arguments
+        let locale = context.locale().cloned().unwrap_or_default();
+        params
+            .entry(LOCALE_FIELD.to_string())
+            .or_insert_with(|| locale.locale.into());
+        params
+            .entry(TIMEZONE_FIELD.to_string())
+            .or_insert_with(|| locale.timezone.unwrap_or_else(|| "UTC".to_string()).into());
+
+        // Validate required parameters
+        for param in prompt.parameters.iter().filter(|p| p.required) {
+            if !params.contains_key(&param.name) {
+                return Err(McpError::Validation {
+                    message: format!("Required parameter '{}' not provided", param.name),
+                });
+            }
+        }
+
+        Ok((prompt, params))
+    }
+}
+
+/// Render a prompt message with parameter substitution
+fn render_message(
+    engine: &SimpleTemplateEngine,
+    message: &PromptMessage,
+    params: &HashMap<String, serde_json::Value>,
+) -> McpResult<PromptMessage> {
+    let rendered_content = match &message.content {
+        PromptContent::Text { text } => {
+            let rendered_text = engine.substitute(text, params)?;
+            PromptContent::Text {
+                text: rendered_text,
+            }
+        }
+        PromptContent::EmbeddedResource { resource, text } => {
+            let rendered_text = if let Some(t) = text {
+                Some(engine.substitute(t, params)?)
+            } else {
+                None
+            };
+            PromptContent::EmbeddedResource {
+                resource: resource.clone(),
+                text: rendered_text,
+            }
+        }
+    };
+
+    Ok(PromptMessage {
+        role: message.role.clone(),
+        content: rendered_content,
+    })
+}
+
+impl InMemoryPromptRegistry {
+    /// Add the prompts and categories of a bundle
+    ///
+    /// Categories with an id that is already registered gain the bundled
+    /// category's prompts; with [`ConflictResolution::Overwrite`] they also
+    /// take its name and description.
+    pub fn import_bundle(
+        &mut self,
+        bundle: PromptBundle,
+        resolution: ConflictResolution,
+    ) -> McpResult<PromptImportReport> {
+        if resolution == ConflictResolution::Fail {
+            let conflicts: Vec<&str> = bundle
+                .prompts
+                .iter()
+                .filter(|prompt| self.prompts.contains_key(&prompt.name))
+                .map(|prompt| prompt.name.as_str())
+                .collect();
+            if !conflicts.is_empty() {
+                return Err(McpError::Validation {
+                    message: format!("Prompts already registered: {}", conflicts.join(", ")),
+                });
             }
-            PromptContent::EmbeddedResource { resource, text } => {
-                let rendered_text = if let Some(t) = text {
-                    Some(self.template_engine.substitute(t, params)?)
-                } else {
-                    None
-                };
-                PromptContent::EmbeddedResource {
-                    resource: resource.clone(),
-                    text: rendered_text,
+        }
+
+        let mut report = PromptImportReport::default();
+        for prompt in bundle.prompts {
+            let replace = match self.prompts.get(&prompt.name) {
+                None => {
+                    report.added.push(prompt.name.clone());
+                    self.add_prompt(prompt);
+                    continue;
                 }
+                Some(existing) => match resolution {
+                    ConflictResolution::Overwrite => true,
+                    ConflictResolution::KeepNewer => {
+                        compare_versions(&prompt.version, &existing.version) == Ordering::Greater
+                    }
+                    ConflictResolution::Fail | ConflictResolution::Skip => false,
+                },
+            };
+            if replace {
+                report.replaced.push(prompt.name.clone());
+                self.add_prompt(prompt);
+            } else {
+                report.skipped.push(prompt.name);
             }
-        };
+        }
 
-        Ok(PromptMessage {
-            role: message.role.clone(),
-            content: rendered_content,
-        })
+        for category in bundle.categories {
+            match self
+                .categories
+                .iter_mut()
+                .find(|existing| existing.id == category.id)
+            {
+                Some(existing) => {
+                    if resolution == ConflictResolution::Overwrite {
+                        existing.name = category.name;
+                        existing.description = category.description;
+                    }
+                    for name in category.prompts {
+                        if !existing.prompts.contains(&name) {
+                            existing.prompts.push(name);
+                        }
+                    }
+                }
+                None => self.add_category(category),
+            }
+        }
+        Ok(report)
     }
 }
 
@@ -397,53 +648,47 @@ impl Default for InMemoryPromptRegistry {
 
 #[async_trait]
 impl PromptRegistry for InMemoryPromptRegistry {
-    async fn list_prompts(&self, _context: &SecurityContext) -> McpResult<Vec<Prompt>> {
-        Ok(self.prompts.values().cloned().collect())
+    async fn list_prompts(&self, context: &SecurityContext) -> McpResult<Vec<Prompt>> {
+        Ok(self
+            .prompts
+            .values()
+            .filter(|prompt| context.exposes(profile_of(&prompt.metadata)))
+            .cloned()
+            .collect())
     }
 
-    async fn get_prompt(
+    async fn list_prompts_page(
         &self,
-        name: &str,
-        _context: &SecurityContext,
-    ) -> McpResult<Option<Prompt>> {
-        Ok(self.prompts.get(name).cloned())
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<Prompt>> {
+        self.prompts.page(cursor, limit, |prompt| {
+            context
+                .exposes(profile_of(&prompt.metadata))
+                .then(|| prompt.clone())
+        })
+    }
+
+    async fn get_prompt(&self, name: &str, context: &SecurityContext) -> McpResult<Option<Prompt>> {
+        Ok(self
+            .prompts
+            .get(name)
+            .filter(|prompt| context.exposes(profile_of(&prompt.metadata)))
+            .cloned())
     }
 
     async fn get_prompt_with_args(
         &self,
-        request: GetPromptRequest,
+        mut request: GetPromptRequest,
         context: &SecurityContext,
     ) -> McpResult<GetPromptResult> {
-        let prompt = self
-            .get_prompt(&request.name, context)
-            .await?
-            .ok_or_else(|| McpError::InvalidResource {
-                uri: format!("prompt:{}", request.name),
-                message: "Prompt not found".to_string(),
-            })?;
-
-        let params = request.arguments.unwrap_or_default();
-
-        // Validate required parameters
-        let required_params: Vec<String> = prompt
-            .parameters
-            .iter()
-            .filter(|p| p.required)
-            .map(|p| p.name.clone())
-            .collect();
-
-        for param_name in &required_params {
-            if !params.contains_key(param_name) {
-                return Err(McpError::Validation {
-                    message: format!("Required parameter '{}' not provided", param_name),
-                });
-            }
-        }
+        let (prompt, params) = self.prepare(&mut request, context).await?;
 
         // Render all messages with parameter substitution
         let mut rendered_messages = Vec::new();
         for message in &prompt.messages {
-            let rendered_message = self.render_message(message, &params)?;
+            let rendered_message = render_message(&self.template_engine, message, &params)?;
             rendered_messages.push(rendered_message);
         }
 
@@ -456,6 +701,27 @@ impl PromptRegistry for InMemoryPromptRegistry {
             name: request.name,
             messages: rendered_messages,
             description: rendered_description,
+            meta: HashMap::new(),
+        })
+    }
+
+    async fn stream_prompt_with_args(
+        &self,
+        mut request: GetPromptRequest,
+        context: &SecurityContext,
+    ) -> McpResult<PromptStream> {
+        let (prompt, params) = self.prepare(&mut request, context).await?;
+        let description = self
+            .template_engine
+            .substitute(&prompt.description, &params)?;
+        let engine = self.template_engine.clone();
+        let messages = futures_util::stream::iter(prompt.messages)
+            .map(move |message| render_message(&engine, &message, &params));
+
+        Ok(PromptStream {
+            name: request.name,
+            description,
+            messages: messages.boxed(),
         })
     }
 
@@ -0,0 +1,243 @@
+//! Prompt library bundles
+//!
+//! A bundle is a JSON document carrying prompts and their categories, so that
+//! prompt libraries can be shared between servers and checked into version
+//! control. Bundles are written by
+//! [`PromptRegistry::export_bundle`](crate::server::PromptRegistry::export_bundle)
+//! and read by
+//! [`InMemoryPromptRegistry::import_bundle`](crate::server::InMemoryPromptRegistry::import_bundle):
+//!
+//! ```json
+//! {
+//!   "bundleVersion": 1,
+//!   "metadata": {"source": "team-prompts"},
+//!   "prompts": [{"name": "summarize", "description": "...", "version": "1.2.0",
+//!                "parameters": [], "messages": [], "metadata": {}}],
+//!   "categories": [{"id": "writing", "name": "Writing", "description": "...",
+//!                   "prompts": ["summarize"]}]
+//! }
+//! ```
+//!
+//! [`PromptBundle::to_json`] sorts prompts, categories and object keys, so
+//! exporting an unchanged library gives the same file.
+
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashMap};
+
+use crate::{
+    error::{McpError, McpResult},
+    server::prompt::{Prompt, PromptCategory},
+};
+
+/// Bundle format written by this version
+pub const PROMPT_BUNDLE_VERSION: u32 = 1;
+
+/// A prompt library as exchanged between servers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptBundle {
+    /// Format version of the bundle
+    pub bundle_version: u32,
+
+    /// Free-form information about the bundle, such as its source
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, serde_json::Value>,
+
+    /// Prompts in the bundle
+    #[serde(default)]
+    pub prompts: Vec<Prompt>,
+
+    /// Categories of the bundled prompts
+    #[serde(default)]
+    pub categories: Vec<PromptCategory>,
+}
+
+impl PromptBundle {
+    /// Create a bundle, sorting prompts by name and categories by id
+    pub fn new(mut prompts: Vec<Prompt>, mut categories: Vec<PromptCategory>) -> Self {
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        categories.sort_by(|a, b| a.id.cmp(&b.id));
+        Self {
+            bundle_version: PROMPT_BUNDLE_VERSION,
+            metadata: HashMap::new(),
+            prompts,
+            categories,
+        }
+    }
+
+    /// Add information about the bundle
+    pub fn with_metadata(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.metadata.insert(key.into(), value);
+        self
+    }
+
+    /// Read a bundle, rejecting formats newer than this version understands
+    pub fn from_json(json: &str) -> McpResult<Self> {
+        let bundle: Self = serde_json::from_str(json).map_err(|e| McpError::Validation {
+            message: format!("Invalid prompt bundle: {}", e),
+        })?;
+        if bundle.bundle_version > PROMPT_BUNDLE_VERSION {
+            return Err(McpError::Validation {
+                message: format!(
+                    "Prompt bundle version {} is newer than the supported version {}",
+                    bundle.bundle_version, PROMPT_BUNDLE_VERSION
+                ),
+            });
+        }
+        Ok(bundle)
+    }
+
+    /// Write the bundle as pretty-printed JSON with sorted keys
+    pub fn to_json(&self) -> McpResult<String> {
+        // Going through a value sorts the keys of every map
+        let value = serde_json::to_value(self)?;
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+}
+
+/// What to do with a bundled prompt whose name is already registered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Import nothing if any prompt is already registered
+    #[default]
+    Fail,
+    /// Keep the registered prompt
+    Skip,
+    /// Replace the registered prompt
+    Overwrite,
+    /// Replace the registered prompt if the bundled version is higher
+    KeepNewer,
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptImportReport {
+    /// Prompts that were not registered before
+    pub added: Vec<String>,
+
+    /// Registered prompts replaced by the bundled ones
+    pub replaced: Vec<String>,
+
+    /// Bundled prompts left out because of a conflict
+    pub skipped: Vec<String>,
+}
+
+/// Compare dotted versions numerically where both parts are numbers
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| {
+        version
+            .trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (parts(a), parts(b));
+    for (a, b) in a.iter().zip(&b) {
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        security::SecurityContext,
+        server::prompt::{InMemoryPromptRegistry, PromptRegistry},
+    };
+
+    fn registry(version: &str) -> InMemoryPromptRegistry {
+        let mut registry = InMemoryPromptRegistry::new();
+        registry.add_prompt(Prompt {
+            name: "summarize".to_string(),
+            description: "Summarize a text".to_string(),
+            version: version.to_string(),
+            parameters: Vec::new(),
+            messages: Vec::new(),
+            metadata: HashMap::from([("type".to_string(), "writing".into())]),
+        });
+        registry.add_category(PromptCategory {
+            id: "writing".to_string(),
+            name: "Writing".to_string(),
+            description: "Writing aids".to_string(),
+            prompts: vec!["summarize".to_string()],
+        });
+        registry
+    }
+
+    async fn export(registry: &InMemoryPromptRegistry) -> PromptBundle {
+        registry
+            .export_bundle(&SecurityContext::system())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let context = SecurityContext::system();
+        let source = registry("1.2.0");
+        let json = export(&source)
+            .await
+            .with_metadata("source", "team-prompts".into())
+            .to_json()
+            .unwrap();
+        assert!(json.contains("\"bundleVersion\": 1"));
+
+        let bundle = PromptBundle::from_json(&json).unwrap();
+        assert_eq!(bundle.metadata["source"], "team-prompts");
+        let mut target = InMemoryPromptRegistry::new();
+        let report = target
+            .import_bundle(bundle, ConflictResolution::Fail)
+            .unwrap();
+        assert_eq!(report.added, ["summarize"]);
+        assert!(target.prompt_exists("summarize", &context).await.unwrap());
+        assert_eq!(
+            target.list_categories(&context).await.unwrap()[0].prompts,
+            ["summarize"]
+        );
+
+        let newer = json.replace("\"bundleVersion\": 1", "\"bundleVersion\": 2");
+        assert!(PromptBundle::from_json(&newer).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bundle_conflicts() {
+        let context = SecurityContext::system();
+        let newer = export(&registry("1.10.0")).await;
+        let older = export(&registry("1.2.0")).await;
+
+        let mut target = registry("1.9.0");
+        assert!(target
+            .import_bundle(newer.clone(), ConflictResolution::Fail)
+            .is_err());
+        let report = target
+            .import_bundle(newer.clone(), ConflictResolution::Skip)
+            .unwrap();
+        assert_eq!(report.skipped, ["summarize"]);
+
+        let report = target
+            .import_bundle(newer, ConflictResolution::KeepNewer)
+            .unwrap();
+        assert_eq!(report.replaced, ["summarize"]);
+        let report = target
+            .import_bundle(older.clone(), ConflictResolution::KeepNewer)
+            .unwrap();
+        assert_eq!(report.skipped, ["summarize"]);
+        let prompt = target.get_prompt("summarize", &context).await.unwrap();
+        assert_eq!(prompt.unwrap().version, "1.10.0");
+
+        let report = target
+            .import_bundle(older, ConflictResolution::Overwrite)
+            .unwrap();
+        assert_eq!(report.replaced, ["summarize"]);
+        assert_eq!(target.list_categories(&context).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+//! Golden-file regression tests for prompts
+//!
+//! Prompts carry sample argument sets under the `examples` metadata key (see
+//! [`Prompt::with_example`]). [`GoldenPrompts`] renders every example, compares
+//! the rendered description and messages with JSON files checked in next to
+//! the tests, and reports any difference as a unified diff:
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn prompts_render_as_before() {
+//!     GoldenPrompts::new("tests/prompts")
+//!         .check(&my_prompt_registry())
+//!         .await
+//!         .unwrap()
+//!         .assert_unchanged();
+//! }
+//! ```
+//!
+//! Missing golden files are written on the first run. Set
+//! `UPDATE_PROMPT_GOLDENS=1` to accept changed renderings.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{McpError, McpResult},
+    security::SecurityContext,
+    server::{
+        diff::unified_diff,
+        prompt::{GetPromptRequest, Prompt, PromptRegistry},
+    },
+};
+
+/// Prompt metadata key holding the sample argument sets
+pub const PROMPT_EXAMPLES_META: &str = "examples";
+
+/// Environment variable that makes [`GoldenPrompts`] accept changed renderings
+pub const UPDATE_GOLDENS_ENV: &str = "UPDATE_PROMPT_GOLDENS";
+
+/// A named set of sample arguments for a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptExample {
+    /// Name of the example, unique within the prompt
+    pub name: String,
+
+    /// Arguments the prompt is rendered with
+    #[serde(default)]
+    pub arguments: HashMap<String, serde_json::Value>,
+}
+
+impl Prompt {
+    /// Add a sample argument set for golden tests
+    pub fn with_example(mut self, name: impl Into<String>, arguments: serde_json::Value) -> Self {
+        let example = serde_json::json!({"name": name.into(), "arguments": arguments});
+        match self.metadata.get_mut(PROMPT_EXAMPLES_META) {
+            Some(serde_json::Value::Array(examples)) => examples.push(example),
+            _ => {
+                self.metadata.insert(
+                    PROMPT_EXAMPLES_META.to_string(),
+                    serde_json::Value::Array(vec![example]),
+                );
+            }
+        }
+        self
+    }
+
+    /// Sample argument sets declared in the prompt's metadata
+    pub fn examples(&self) -> McpResult<Vec<PromptExample>> {
+        match self.metadata.get(PROMPT_EXAMPLES_META) {
+            Some(examples) => {
+                serde_json::from_value(examples.clone()).map_err(|e| McpError::Validation {
+                    message: format!("Invalid examples in prompt '{}': {}", self.name, e),
+                })
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// A rendering that differs from its golden file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenMismatch {
+    /// Prompt that rendered differently
+    pub prompt: String,
+
+    /// Example it was rendered with
+    pub example: String,
+
+    /// Unified diff from the golden rendering to the current one
+    pub diff: String,
+}
+
+/// Outcome of checking prompts against their golden files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoldenReport {
+    /// Examples rendered
+    pub checked: usize,
+
+    /// Golden files written for the first time
+    pub created: Vec<PathBuf>,
+
+    /// Golden files rewritten because updating was enabled
+    pub updated: Vec<PathBuf>,
+
+    /// Renderings that differ from their golden files
+    pub mismatches: Vec<GoldenMismatch>,
+}
+
+impl GoldenReport {
+    /// Panic with the diffs if any rendering changed
+    pub fn assert_unchanged(&self) {
+        if self.mismatches.is_empty() {
+            return;
+        }
+        let diffs: Vec<String> = self
+            .mismatches
+            .iter()
+            .map(|m| {
+                format!(
+                    "prompt '{}', example '{}':\n{}",
+                    m.prompt, m.example, m.diff
+                )
+            })
+            .collect();
+        panic!(
+            "{} prompt rendering(s) changed; rerun with {}=1 to accept them\n\n{}",
+            self.mismatches.len(),
+            UPDATE_GOLDENS_ENV,
+            diffs.join("\n")
+        );
+    }
+}
+
+/// Renders prompt examples and compares them with golden files
+#[derive(Debug, Clone)]
+pub struct GoldenPrompts {
+    dir: PathBuf,
+    update: bool,
+}
+
+impl GoldenPrompts {
+    /// Keep golden files in `dir`, one `<prompt>.json` per prompt
+    ///
+    /// Updating is enabled when [`UPDATE_GOLDENS_ENV`] is set to anything but `0`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            update: std::env::var(UPDATE_GOLDENS_ENV).is_ok_and(|value| value != "0"),
+        }
+    }
+
+    /// Rewrite golden files that differ instead of reporting them
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Check every prompt in `registry` that declares examples
+    pub async fn check(&self, registry: &dyn PromptRegistry) -> McpResult<GoldenReport> {
+        let mut report = GoldenReport::default();
+        let mut prompts = registry.list_prompts(&SecurityContext::system()).await?;
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        for prompt in prompts {
+            self.check_into(registry, &prompt, &mut report).await?;
+        }
+        Ok(report)
+    }
+
+    /// Check a single prompt
+    pub async fn check_prompt(
+        &self,
+        registry: &dyn PromptRegistry,
+        name: &str,
+    ) -> McpResult<GoldenReport> {
+        let prompt = registry
+            .get_prompt(name, &SecurityContext::system())
+            .await?
+            .ok_or_else(|| McpError::Validation {
+                message: format!("Prompt '{}' not found", name),
+            })?;
+        let mut report = GoldenReport::default();
+        self.check_into(registry, &prompt, &mut report).await?;
+        Ok(report)
+    }
+
+    async fn check_into(
+        &self,
+        registry: &dyn PromptRegistry,
+        prompt: &Prompt,
+        report: &mut GoldenReport,
+    ) -> McpResult<()> {
+        let examples = prompt.examples()?;
+        if examples.is_empty() {
+            return Ok(());
+        }
+
+        let mut rendered = BTreeMap::new();
+        for example in examples {
+            let mut result = registry
+                .get_prompt_with_args(
+                    GetPromptRequest {
+                        name: prompt.name.clone(),
+                        arguments: Some(example.arguments),
+                    },
+                    &SecurityContext::system(),
+                )
+                .await?;
+            // Token counts and the like are not part of the rendering
+            result.meta.clear();
+            rendered.insert(example.name, serde_json::to_value(result)?);
+            report.checked += 1;
+        }
+
+        let path = self.path_for(&prompt.name);
+        let current = format!("{}\n", serde_json::to_string_pretty(&rendered)?);
+        let golden = match std::fs::read_to_string(&path) {
+            Ok(golden) => golden,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                write(&path, &current)?;
+                report.created.push(path);
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if golden == current {
+            return Ok(());
+        }
+        if self.update {
+            write(&path, &current)?;
+            report.updated.push(path);
+            return Ok(());
+        }
+
+        let golden: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&golden).map_err(|e| McpError::Validation {
+                message: format!("Invalid golden file {}: {}", path.display(), e),
+            })?;
+        let names: std::collections::BTreeSet<&String> =
+            golden.keys().chain(rendered.keys()).collect();
+        for name in names {
+            let text = |value: Option<&serde_json::Value>| {
+                value
+                    .map(|value| format!("{}\n", serde_json::to_string_pretty(value).unwrap()))
+                    .unwrap_or_default()
+            };
+            let (old, new) = (text(golden.get(name)), text(rendered.get(name)));
+            if old != new {
+                report.mismatches.push(GoldenMismatch {
+                    prompt: prompt.name.clone(),
+                    example: name.clone(),
+                    diff: unified_diff(&old, &new, "golden", "rendered", 3).unified,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Golden file of a prompt, with characters unsafe in file names replaced
+    fn path_for(&self, prompt: &str) -> PathBuf {
+        let file: String = prompt
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+}
+
+fn write(path: &Path, contents: &str) -> McpResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::prompt::{
+        InMemoryPromptRegistry, MessageRole, PromptContent, PromptMessage, PromptParameter,
+    };
+
+    fn registry(template: &str) -> InMemoryPromptRegistry {
+        let mut registry = InMemoryPromptRegistry::new();
+        registry.add_prompt(
+            Prompt {
+                name: "team/greet".to_string(),
+                description: "Greet {{who}}".to_string(),
+                version: "1.0.0".to_string(),
+                parameters: vec![PromptParameter {
+                    name: "who".to_string(),
+                    description: "Who to greet".to_string(),
+                    required: true,
+                    schema: None,
+                    default: None,
+                }],
+                messages: vec![PromptMessage {
+                    role: MessageRole::User,
+                    content: PromptContent::Text {
+                        text: template.to_string(),
+                    },
+                }],
+                metadata: HashMap::new(),
+            }
+            .with_example("world", serde_json::json!({"who": "world"}))
+            .with_example("team", serde_json::json!({"who": "the team"})),
+        );
+        registry
+    }
+
+    #[tokio::test]
+    async fn test_golden_prompts() {
+        let dir = std::env::temp_dir().join(format!("prompt-goldens-{}", uuid::Uuid::new_v4()));
+        let goldens = GoldenPrompts::new(&dir).with_update(false);
+
+        let report = goldens.check(&registry("Hello {{who}}!")).await.unwrap();
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.created, [dir.join("team_greet.json")]);
+        let report = goldens.check(&registry("Hello {{who}}!")).await.unwrap();
+        assert!(report.created.is_empty());
+        report.assert_unchanged();
+
+        let report = goldens.check(&registry("Hi {{who}}!")).await.unwrap();
+        assert_eq!(report.mismatches.len(), 2);
+        assert_eq!(report.mismatches[0].example, "team");
+        assert!(report.mismatches[0].diff.contains(
+            "-        \"text\": \"Hello the team!\",\n+        \"text\": \"Hi the team!\""
+        ));
+        let changed = std::panic::catch_unwind(|| report.assert_unchanged());
+        assert!(changed.is_err());
+
+        let report = goldens
+            .with_update(true)
+            .check_prompt(&registry("Hi {{who}}!"), "team/greet")
+            .await
+            .unwrap();
+        assert_eq!(report.updated.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+//! Layered prompt registries
+//!
+//! [`LayeredPromptRegistry`] serves prompts from a base registry with override
+//! layers on top. Each layer applies to requests whose client metadata carries
+//! its labels, such as `environment=staging` or `tenant=acme`, so staging can
+//! try modified prompts while production keeps the stable ones. The first
+//! matching layer that has a prompt serves it; prompts no layer overrides come
+//! from the base.
+//!
+//! Rendered prompts name the layer that served them in `_meta.promptLayer`, and
+//! [`LayeredPromptRegistry::serving_layer`] answers the same question without
+//! rendering.
+
+use async_trait::async_trait;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    error::McpResult,
+    security::SecurityContext,
+    server::prompt::{GetPromptRequest, GetPromptResult, Prompt, PromptCategory, PromptRegistry},
+    server::prompt_stream::PromptStream,
+};
+
+/// Client metadata key naming the tenant
+pub const TENANT_LABEL: &str = "tenant";
+
+/// Client metadata key naming the environment
+pub const ENVIRONMENT_LABEL: &str = "environment";
+
+/// `_meta` key naming the layer that served a rendered prompt
+pub const PROMPT_LAYER_META: &str = "promptLayer";
+
+/// Layer name reported for prompts served by the base registry
+pub const BASE_LAYER: &str = "base";
+
+struct PromptLayer {
+    name: String,
+    labels: HashMap<String, String>,
+    registry: Arc<dyn PromptRegistry>,
+}
+
+impl PromptLayer {
+    fn applies_to(&self, context: &SecurityContext) -> bool {
+        self.labels
+            .iter()
+            .all(|(key, value)| context.client.metadata.get(key) == Some(value))
+    }
+}
+
+/// Prompt registry resolving prompts through override layers
+pub struct LayeredPromptRegistry {
+    base: Arc<dyn PromptRegistry>,
+    layers: Vec<PromptLayer>,
+}
+
+impl LayeredPromptRegistry {
+    /// Serve prompts from `base` unless a layer overrides them
+    pub fn new(base: Arc<dyn PromptRegistry>) -> Self {
+        Self {
+            base,
+            layers: Vec::new(),
+        }
+    }
+
+    /// Add a layer for requests whose client metadata has all of `labels`
+    ///
+    /// Layers are consulted in the order they are added.
+    pub fn with_layer<K, V>(
+        mut self,
+        name: impl Into<String>,
+        labels: impl IntoIterator<Item = (K, V)>,
+        registry: Arc<dyn PromptRegistry>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.layers.push(PromptLayer {
+            name: name.into(),
+            labels: labels
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+            registry,
+        });
+        self
+    }
+
+    /// Name of the layer serving `name` to `context`, if any has the prompt
+    pub async fn serving_layer(
+        &self,
+        name: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Option<String>> {
+        Ok(self
+            .resolve(name, context)
+            .await?
+            .map(|(layer, _)| layer.to_string()))
+    }
+
+    /// Layer name and registry serving `name` to `context`
+    async fn resolve(
+        &self,
+        name: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Option<(&str, &dyn PromptRegistry)>> {
+        for layer in self.applicable(context) {
+            if layer.registry.prompt_exists(name, context).await? {
+                return Ok(Some((&layer.name, layer.registry.as_ref())));
+            }
+        }
+        if self.base.prompt_exists(name, context).await? {
+            return Ok(Some((BASE_LAYER, self.base.as_ref())));
+        }
+        Ok(None)
+    }
+
+    /// Layers applying to `context`, in order of precedence
+    fn applicable(&self, context: &SecurityContext) -> Vec<&PromptLayer> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.applies_to(context))
+            .collect()
+    }
+
+    /// Registry serving `name`, the base if nothing has it
+    async fn registry_for(
+        &self,
+        name: &str,
+        context: &SecurityContext,
+    ) -> McpResult<(&str, &dyn PromptRegistry)> {
+        Ok(self
+            .resolve(name, context)
+            .await?
+            .unwrap_or((BASE_LAYER, self.base.as_ref())))
+    }
+}
+
+#[async_trait]
+impl PromptRegistry for LayeredPromptRegistry {
+    async fn list_prompts(&self, context: &SecurityContext) -> McpResult<Vec<Prompt>> {
+        let mut prompts: HashMap<String, Prompt> = HashMap::new();
+        for registry in self
+            .applicable(context)
+            .into_iter()
+            .map(|layer| layer.registry.as_ref())
+            .chain([self.base.as_ref()])
+        {
+            for prompt in registry.list_prompts(context).await? {
+                prompts.entry(prompt.name.clone()).or_insert(prompt);
+            }
+        }
+        let mut prompts: Vec<Prompt> = prompts.into_values().collect();
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(prompts)
+    }
+
+    async fn get_prompt(&self, name: &str, context: &SecurityContext) -> McpResult<Option<Prompt>> {
+        match self.resolve(name, context).await? {
+            Some((_, registry)) => registry.get_prompt(name, context).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_prompt_with_args(
+        &self,
+        request: GetPromptRequest,
+        context: &SecurityContext,
+    ) -> McpResult<GetPromptResult> {
+        let (layer, registry) = self.registry_for(&request.name, context).await?;
+        let mut result = registry.get_prompt_with_args(request, context).await?;
+        result
+            .meta
+            .insert(PROMPT_LAYER_META.to_string(), layer.into());
+        Ok(result)
+    }
+
+    async fn stream_prompt_with_args(
+        &self,
+        request: GetPromptRequest,
+        context: &SecurityContext,
+    ) -> McpResult<PromptStream> {
+        let (_, registry) = self.registry_for(&request.name, context).await?;
+        registry.stream_prompt_with_args(request, context).await
+    }
+
+    async fn list_categories(&self, context: &SecurityContext) -> McpResult<Vec<PromptCategory>> {
+        let mut categories: Vec<PromptCategory> = self.base.list_categories(context).await?;
+        for layer in self.applicable(context) {
+            for category in layer.registry.list_categories(context).await? {
+                match categories.iter_mut().find(|c| c.id == category.id) {
+                    Some(existing) => {
+                        for name in category.prompts {
+                            if !existing.prompts.contains(&name) {
+                                existing.prompts.push(name);
+                            }
+                        }
+                    }
+                    None => categories.push(category),
+                }
+            }
+        }
+        Ok(categories)
+    }
+
+    async fn prompt_exists(&self, name: &str, context: &SecurityContext) -> McpResult<bool> {
+        Ok(self.resolve(name, context).await?.is_some())
+    }
+
+    async fn validate_prompt_parameters(
+        &self,
+        name: &str,
+        params: &HashMap<String, serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<()> {
+        let (_, registry) = self.registry_for(name, context).await?;
+        registry
+            .validate_prompt_parameters(name, params, context)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        security::ClientContext,
+        server::prompt::{InMemoryPromptRegistry, PromptContent},
+    };
+
+    fn registry(prompts: &[(&str, &str)]) -> Arc<dyn PromptRegistry> {
+        let mut registry = InMemoryPromptRegistry::new();
+        for (name, system_prompt) in prompts {
+            registry.add_workflow_prompt(name, name, system_prompt, "Hello", vec![]);
+        }
+        Arc::new(registry)
+    }
+
+    fn context(labels: &[(&str, &str)]) -> SecurityContext {
+        let mut client = ClientContext::default();
+        for (key, value) in labels {
+            client.metadata.insert(key.to_string(), value.to_string());
+        }
+        SecurityContext::authenticated(client, Vec::new())
+    }
+
+    #[tokio::test]
+    async fn test_layered_prompt_registry() {
+        let layered = LayeredPromptRegistry::new(registry(&[
+            ("greet", "Be polite."),
+            ("summarize", "Be brief."),
+        ]))
+        .with_layer(
+            "acme-staging",
+            [(TENANT_LABEL, "acme"), (ENVIRONMENT_LABEL, "staging")],
+            registry(&[("greet", "Be polite and cheerful.")]),
+        )
+        .with_layer(
+            "staging",
+            [(ENVIRONMENT_LABEL, "staging")],
+            registry(&[("greet", "Be polite, staging."), ("draft", "Experiment.")]),
+        );
+
+        let production = context(&[(ENVIRONMENT_LABEL, "production")]);
+        let staging = context(&[(ENVIRONMENT_LABEL, "staging")]);
+        let acme = context(&[(ENVIRONMENT_LABEL, "staging"), (TENANT_LABEL, "acme")]);
+        for (context, layer, system_prompt) in [
+            (&production, BASE_LAYER, "Be polite."),
+            (&staging, "staging", "Be polite, staging."),
+            (&acme, "acme-staging", "Be polite and cheerful."),
+        ] {
+            let result = layered
+                .get_prompt_with_args(
+                    GetPromptRequest {
+                        name: "greet".to_string(),
+                        arguments: None,
+                    },
+                    context,
+                )
+                .await
+                .unwrap();
+            assert_eq!(result.meta[PROMPT_LAYER_META], layer);
+            match &result.messages[0].content {
+                PromptContent::Text { text } => assert_eq!(text, system_prompt),
+                other => panic!("Unexpected content {:?}", other),
+            }
+        }
+
+        let names = |prompts: Vec<Prompt>| prompts.into_iter().map(|p| p.name).collect::<Vec<_>>();
+        assert_eq!(
+            names(layered.list_prompts(&production).await.unwrap()),
+            ["greet", "summarize"]
+        );
+        assert_eq!(
+            names(layered.list_prompts(&staging).await.unwrap()),
+            ["draft", "greet", "summarize"]
+        );
+        assert_eq!(
+            layered.serving_layer("summarize", &acme).await.unwrap(),
+            Some(BASE_LAYER.to_string())
+        );
+        assert_eq!(
+            layered.serving_layer("draft", &production).await.unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Static checks for prompt templates
+//!
+//! [`PromptLinter`] reads a prompt's description and messages and reports
+//! problems before the prompt is served:
+//!
+//! - `undeclared-placeholder`: a `{{name}}` with no parameter of that name
+//! - `unsupported-syntax`: a `{{...}}` the template engine cannot render,
+//!   such as `{{#if x}}` with [`SimpleTemplateEngine`]
+//! - `unused-parameter`: a parameter no template refers to
+//! - `missing-description`: a prompt or parameter without a description
+//! - `long-system-prompt`: a system message over the token limit
+//!
+//! Placeholders and unsupported syntax are errors, the rest are warnings.
+//! [`InMemoryPromptRegistry::try_add_prompt`](crate::server::InMemoryPromptRegistry::try_add_prompt)
+//! lints prompts as they are registered, and
+//! [`add_prompt`](crate::server::InMemoryPromptRegistry::add_prompt) logs
+//! undeclared placeholders.
+//!
+//! To keep `parameters` in step with the templates, derive them instead:
+//! [`PromptLinter::infer_parameters`] declares a stub for every placeholder
+//! that has no parameter yet, to be completed with a description.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fmt, sync::Arc};
+
+use crate::server::{
+    prompt::{
+        MessageRole, Prompt, PromptContent, PromptParameter, SimpleTemplateEngine, TemplateEngine,
+    },
+    tokens::{default_estimator, TokenEstimator},
+};
+
+/// System prompt length above which a warning is reported
+pub const DEFAULT_MAX_SYSTEM_PROMPT_TOKENS: usize = 2000;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    /// The prompt would render incorrectly
+    Error,
+    /// The prompt renders but is probably not what was meant
+    Warning,
+}
+
+/// A problem found in a prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptDiagnostic {
+    /// How serious the problem is
+    pub severity: DiagnosticSeverity,
+
+    /// Rule that found the problem, such as `unused-parameter`
+    pub code: String,
+
+    /// Human-readable explanation
+    pub message: String,
+
+    /// Where in the prompt, such as `messages[1]` or `parameters.topic`
+    pub location: String,
+}
+
+impl PromptDiagnostic {
+    fn new(
+        severity: DiagnosticSeverity,
+        code: &str,
+        location: impl Into<String>,
+        message: String,
+    ) -> Self {
+        Self {
+            severity,
+            code: code.to_string(),
+            message,
+            location: location.into(),
+        }
+    }
+
+    /// Whether this diagnostic is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == DiagnosticSeverity::Error
+    }
+}
+
+impl fmt::Display for PromptDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.location, self.code, self.message)
+    }
+}
+
+/// Checks prompts against the template engine that renders them
+#[derive(Clone)]
+pub struct PromptLinter {
+    engine: Arc<dyn TemplateEngine>,
+    estimator: Arc<dyn TokenEstimator>,
+    max_system_prompt_tokens: usize,
+}
+
+impl PromptLinter {
+    /// Lint for [`SimpleTemplateEngine`] with the default token limit
+    pub fn new() -> Self {
+        Self {
+            engine: Arc::new(SimpleTemplateEngine),
+            estimator: default_estimator(),
+            max_system_prompt_tokens: DEFAULT_MAX_SYSTEM_PROMPT_TOKENS,
+        }
+    }
+
+    /// Check syntax against the given template engine
+    pub fn with_template_engine(mut self, engine: Arc<dyn TemplateEngine>) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Count system prompt tokens with the given estimator
+    pub fn with_token_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Warn about system prompts longer than `tokens`
+    pub fn with_max_system_prompt_tokens(mut self, tokens: usize) -> Self {
+        self.max_system_prompt_tokens = tokens;
+        self
+    }
+
+    /// Problems found in `prompt`
+    pub fn lint(&self, prompt: &Prompt) -> Vec<PromptDiagnostic> {
+        let mut diagnostics = Vec::new();
+        if prompt.description.trim().is_empty() {
+            diagnostics.push(PromptDiagnostic::new(
+                DiagnosticSeverity::Warning,
+                "missing-description",
+                "description",
+                format!("Prompt '{}' has no description", prompt.name),
+            ));
+        }
+
+        let mut templates = vec![("description".to_string(), prompt.description.as_str())];
+        for (index, message) in prompt.messages.iter().enumerate() {
+            let text = template_of(&message.content);
+            let location = format!("messages[{}]", index);
+            if matches!(message.role, MessageRole::System) {
+                let tokens = self.estimator.estimate(text);
+                if tokens > self.max_system_prompt_tokens {
+                    diagnostics.push(PromptDiagnostic::new(
+                        DiagnosticSeverity::Warning,
+                        "long-system-prompt",
+                        location.clone(),
+                        format!(
+                            "System prompt is about {} tokens, over the limit of {}",
+                            tokens, self.max_system_prompt_tokens
+                        ),
+                    ));
+                }
+            }
+            templates.push((location, text));
+        }
+
+        let declared: HashSet<&str> = prompt.parameters.iter().map(|p| p.name.as_str()).collect();
+        let mut used = HashSet::new();
+        for (location, template) in templates {
+            for expression in expressions(template) {
+                let Some(expression) = expression else {
+                    diagnostics.push(PromptDiagnostic::new(
+                        DiagnosticSeverity::Error,
+                        "unsupported-syntax",
+                        location.clone(),
+                        "Unclosed '{{'".to_string(),
+                    ));
+                    continue;
+                };
+                match self.engine.placeholder_name(expression) {
+                    Some(name) if declared.contains(name) => {
+                        used.insert(name.to_string());
+                    }
+                    Some(name) => diagnostics.push(PromptDiagnostic::new(
+                        DiagnosticSeverity::Error,
+                        "undeclared-placeholder",
+                        location.clone(),
+                        format!("Placeholder '{}' is not a declared parameter", name),
+                    )),
+                    None => diagnostics.push(PromptDiagnostic::new(
+                        DiagnosticSeverity::Error,
+                        "unsupported-syntax",
+                        location.clone(),
+                        format!(
+                            "'{{{{{}}}}}' is not supported by the template engine",
+                            expression
+                        ),
+                    )),
+                }
+            }
+        }
+
+        for parameter in &prompt.parameters {
+            let location = format!("parameters.{}", parameter.name);
+            if parameter.description.trim().is_empty() {
+                diagnostics.push(PromptDiagnostic::new(
+                    DiagnosticSeverity::Warning,
+                    "missing-description",
+                    location.clone(),
+                    format!("Parameter '{}' has no description", parameter.name),
+                ));
+            }
+            if !used.contains(&parameter.name) {
+                diagnostics.push(PromptDiagnostic::new(
+                    DiagnosticSeverity::Warning,
+                    "unused-parameter",
+                    location,
+                    format!("Parameter '{}' is not used by any template", parameter.name),
+                ));
+            }
+        }
+        diagnostics
+    }
+}
+
+impl PromptLinter {
+    /// Names of the parameters `prompt`'s templates refer to, in order of first use
+    pub fn placeholders(&self, prompt: &Prompt) -> Vec<String> {
+        let templates = std::iter::once(prompt.description.as_str())
+            .chain(prompt.messages.iter().map(|m| template_of(&m.content)));
+        let mut names: Vec<String> = Vec::new();
+        for template in templates {
+            for expression in expressions(template).into_iter().flatten() {
+                if let Some(name) = self.engine.placeholder_name(expression) {
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Placeholders of `prompt` without a declared parameter
+    pub fn undeclared(&self, prompt: &Prompt) -> Vec<String> {
+        let mut names = self.placeholders(prompt);
+        names.retain(|name| !prompt.parameters.iter().any(|p| &p.name == name));
+        names
+    }
+
+    /// Declare a stub parameter for each undeclared placeholder of `prompt`
+    ///
+    /// Stubs are required strings without a description, so linting keeps
+    /// reporting them until they are described. Returns the added names.
+    pub fn infer_parameters(&self, prompt: &mut Prompt) -> Vec<String> {
+        let names = self.undeclared(prompt);
+        prompt
+            .parameters
+            .extend(names.iter().map(PromptParameter::stub));
+        names
+    }
+}
+
+impl Default for PromptLinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for PromptLinter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PromptLinter")
+            .field("max_system_prompt_tokens", &self.max_system_prompt_tokens)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Template text of a message's content
+fn template_of(content: &PromptContent) -> &str {
+    match content {
+        PromptContent::Text { text } => text,
+        PromptContent::EmbeddedResource { text, .. } => text.as_deref().unwrap_or_default(),
+    }
+}
+
+/// Contents of each `{{...}}` in `template`, `None` for an unclosed one
+fn expressions(template: &str) -> Vec<Option<&str>> {
+    let mut expressions = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        match rest.find("}}") {
+            Some(end) => {
+                expressions.push(Some(&rest[..end]));
+                rest = &rest[end + 2..];
+            }
+            None => {
+                expressions.push(None);
+                break;
+            }
+        }
+    }
+    expressions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::prompt::{InMemoryPromptRegistry, PromptMessage, PromptParameter};
+    use std::collections::HashMap;
+
+    fn parameter(name: &str, description: &str) -> PromptParameter {
+        PromptParameter {
+            name: name.to_string(),
+            description: description.to_string(),
+            required: true,
+            schema: None,
+            default: None,
+        }
+    }
+
+    fn codes(diagnostics: &[PromptDiagnostic]) -> Vec<(&str, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.code.as_str(), d.location.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_prompt_linter() {
+        let prompt = Prompt {
+            name: "review".to_string(),
+            description: String::new(),
+            version: "1.0.0".to_string(),
+            parameters: vec![parameter("code", "Code to review"), parameter("style", "")],
+            messages: vec![
+                PromptMessage {
+                    role: MessageRole::System,
+                    content: PromptContent::Text {
+                        text: "You review code. ".repeat(20),
+                    },
+                },
+                PromptMessage {
+                    role: MessageRole::User,
+                    content: PromptContent::Text {
+                        text:
+                            "Review {{code}} in {{language}}{{#if strict}} strictly{{/if}}. {{oops"
+                                .to_string(),
+                    },
+                },
+            ],
+            metadata: HashMap::new(),
+        };
+
+        let linter = PromptLinter::new().with_max_system_prompt_tokens(50);
+        let diagnostics = linter.lint(&prompt);
+        assert_eq!(
+            codes(&diagnostics),
+            [
+                ("missing-description", "description"),
+                ("long-system-prompt", "messages[0]"),
+                ("undeclared-placeholder", "messages[1]"),
+                ("unsupported-syntax", "messages[1]"),
+                ("unsupported-syntax", "messages[1]"),
+                ("unsupported-syntax", "messages[1]"),
+                ("missing-description", "parameters.style"),
+                ("unused-parameter", "parameters.style"),
+            ]
+        );
+        assert_eq!(
+            diagnostics[2].to_string(),
+            "messages[1] [undeclared-placeholder]: Placeholder 'language' is not a declared parameter"
+        );
+
+        let mut registry = InMemoryPromptRegistry::new().with_linter(linter);
+        assert!(registry.try_add_prompt(prompt).is_err());
+        let warnings = registry
+            .try_add_prompt(Prompt {
+                name: "greet".to_string(),
+                description: "Greet {{who}}".to_string(),
+                version: "1.0.0".to_string(),
+                parameters: vec![parameter("who", "Who to greet"), parameter("tone", "Tone")],
+                messages: Vec::new(),
+                metadata: HashMap::new(),
+            })
+            .unwrap();
+        assert_eq!(codes(&warnings), [("unused-parameter", "parameters.tone")]);
+    }
+
+    #[test]
+    fn test_infers_parameters_from_templates() {
+        let mut prompt = Prompt {
+            name: "translate".to_string(),
+            description: "Translate into {{language}}".to_string(),
+            version: "1.0.0".to_string(),
+            parameters: vec![parameter("language", "Target language")],
+            messages: vec![PromptMessage {
+                role: MessageRole::User,
+                content: PromptContent::Text {
+                    text: "Translate {{text}} into {{language}}, {{tone}}.{{#if x}}".to_string(),
+                },
+            }],
+            metadata: HashMap::new(),
+        };
+
+        let linter = PromptLinter::new();
+        assert_eq!(linter.placeholders(&prompt), ["language", "text", "tone"]);
+        assert_eq!(linter.infer_parameters(&mut prompt), ["text", "tone"]);
+        assert!(linter.undeclared(&prompt).is_empty());
+        let stub = &prompt.parameters[1];
+        assert_eq!(stub.name, "text");
+        assert!(stub.required && stub.description.is_empty());
+        assert!(codes(&linter.lint(&prompt)).contains(&("missing-description", "parameters.tone")));
+    }
+}
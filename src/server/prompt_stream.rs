@@ -0,0 +1,285 @@
+//! Streaming of rendered prompts
+//!
+//! A prompt embedding large documents can render to megabytes. Rather than
+//! building the whole conversation before responding, a StreamableHTTP
+//! client accepting `text/event-stream` can ask for it in pieces:
+//!
+//! ```json
+//! {"method": "prompts/get", "params": {"name": "review", "_meta": {"stream": true}}}
+//! ```
+//!
+//! The registry then renders one message at a time (see
+//! [`PromptRegistry::stream_prompt_with_args`](crate::server::PromptRegistry::stream_prompt_with_args))
+//! and each message is sent as `notifications/prompts/chunk` events of at
+//! most [`PromptStreamConfig::chunk_bytes`] of text:
+//!
+//! ```json
+//! {"method": "notifications/prompts/chunk",
+//!  "params": {"requestId": 7, "message": 0, "role": "user",
+//!             "content": {"type": "Text", "text": "..."}}}
+//! ```
+//!
+//! A message's text is the concatenation of its chunks, in order. The
+//! response that closes the stream has no messages; its
+//! `_meta.streamed.messages` holds how many were sent. Without a streaming
+//! transport the flag is ignored and the full result is returned. So is it
+//! when a token budget or resource reduction limit applies to the request:
+//! no chunks are sent and the response carries the full, budgeted result.
+
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+    error::McpResult,
+    server::prompt::{GetPromptResult, MessageRole, PromptContent, PromptMessage},
+};
+
+/// Notification carrying a piece of a streamed prompt
+pub const PROMPTS_CHUNK: &str = "notifications/prompts/chunk";
+
+/// `_meta` key asking for a streamed `prompts/get`
+pub const STREAM_META: &str = "stream";
+
+/// `_meta` key of a streamed result, holding the number of messages sent
+pub const STREAMED_META: &str = "streamed";
+
+/// Prompt streaming configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptStreamConfig {
+    /// Largest piece of text sent in one chunk
+    pub chunk_bytes: usize,
+
+    /// Chunks rendered ahead of the client before rendering waits
+    pub buffer: usize,
+}
+
+impl Default for PromptStreamConfig {
+    fn default() -> Self {
+        Self {
+            chunk_bytes: 64 * 1024,
+            buffer: 16,
+        }
+    }
+}
+
+/// A prompt whose messages are rendered as they are consumed
+pub struct PromptStream {
+    /// Prompt name
+    pub name: String,
+    /// Rendered description
+    pub description: String,
+    /// Rendered messages, in order
+    pub messages: BoxStream<'static, McpResult<PromptMessage>>,
+}
+
+impl From<GetPromptResult> for PromptStream {
+    fn from(result: GetPromptResult) -> Self {
+        Self {
+            name: result.name,
+            description: result.description,
+            messages: futures_util::stream::iter(result.messages.into_iter().map(Ok)).boxed(),
+        }
+    }
+}
+
+/// Piece of a streamed prompt message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptChunk {
+    /// Index of the message the chunk belongs to
+    pub message: usize,
+    /// Role of the message
+    pub role: MessageRole,
+    /// Content, with a piece of the message's text
+    pub content: PromptContent,
+}
+
+impl PromptChunk {
+    /// Split `message` into chunks of at most `chunk_bytes` of text
+    pub fn split(index: usize, message: PromptMessage, chunk_bytes: usize) -> Vec<Self> {
+        let chunk = |content| Self {
+            message: index,
+            role: message.role.clone(),
+            content,
+        };
+        match &message.content {
+            PromptContent::Text { text } => split_text(text, chunk_bytes)
+                .map(|text| chunk(PromptContent::Text { text }))
+                .collect(),
+            PromptContent::EmbeddedResource {
+                resource,
+                text: Some(text),
+            } => split_text(text, chunk_bytes)
+                .map(|text| {
+                    chunk(PromptContent::EmbeddedResource {
+                        resource: resource.clone(),
+                        text: Some(text),
+                    })
+                })
+                .collect(),
+            PromptContent::EmbeddedResource { .. } => vec![chunk(message.content.clone())],
+        }
+    }
+
+    /// `notifications/prompts/chunk` for the request `request_id`
+    pub fn notification(&self, request_id: &Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "method": PROMPTS_CHUNK,
+            "params": {
+                "requestId": request_id,
+                "message": self.message,
+                "role": self.role,
+                "content": self.content,
+            },
+        })
+    }
+}
+
+/// Pieces of `text` of at most `max_bytes`, split on character boundaries
+fn split_text(text: &str, max_bytes: usize) -> impl Iterator<Item = String> + '_ {
+    let max_bytes = max_bytes.max(4);
+    let mut rest = text;
+    let mut first = true;
+    std::iter::from_fn(move || {
+        if rest.is_empty() && !std::mem::take(&mut first) {
+            return None;
+        }
+        first = false;
+        let mut end = rest.len().min(max_bytes);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (piece, tail) = rest.split_at(end);
+        rest = tail;
+        Some(piece.to_string())
+    })
+}
+
+/// Streams waiting for a `prompts/get` to render into them
+///
+/// The HTTP handler opens a stream and puts its ID in the request's
+/// `_meta.stream`; the server takes it when it renders the prompt.
+#[derive(Clone, Default)]
+pub struct PromptStreams {
+    senders: Arc<Mutex<HashMap<String, mpsc::Sender<PromptChunk>>>>,
+    config: PromptStreamConfig,
+}
+
+impl PromptStreams {
+    /// Streams as configured
+    pub fn new(config: PromptStreamConfig) -> Self {
+        Self {
+            senders: Arc::default(),
+            config,
+        }
+    }
+
+    /// Open a stream, returning its ID and the receiving end
+    pub fn open(&self) -> (String, mpsc::Receiver<PromptChunk>) {
+        let (sender, receiver) = mpsc::channel(self.config.buffer.max(1));
+        let id = Uuid::new_v4().to_string();
+        self.lock().insert(id.clone(), sender);
+        (id, receiver)
+    }
+
+    /// Drop the stream `id` if it was not taken
+    pub fn close(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    /// Sending end of the stream a request asks for, if it is open
+    pub(crate) fn take(&self, params: Option<&Value>) -> Option<mpsc::Sender<PromptChunk>> {
+        let id = params?.get("_meta")?.get(STREAM_META)?.as_str()?;
+        self.lock().remove(id)
+    }
+
+    /// Render `stream` into `sender`, returning the result closing the stream
+    ///
+    /// Rendering stops early if the client goes away.
+    pub(crate) async fn forward(
+        &self,
+        mut stream: PromptStream,
+        sender: mpsc::Sender<PromptChunk>,
+    ) -> McpResult<GetPromptResult> {
+        let mut sent = 0;
+        'messages: while let Some(message) = stream.messages.next().await {
+            for chunk in PromptChunk::split(sent, message?, self.config.chunk_bytes) {
+                if sender.send(chunk).await.is_err() {
+                    break 'messages;
+                }
+            }
+            sent += 1;
+        }
+        Ok(GetPromptResult {
+            name: stream.name,
+            messages: Vec::new(),
+            description: stream.description,
+            meta: HashMap::from([(STREAMED_META.to_string(), json!({ "messages": sent }))]),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, mpsc::Sender<PromptChunk>>> {
+        self.senders
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_streams_messages_in_chunks() {
+        let streams = PromptStreams::new(PromptStreamConfig {
+            chunk_bytes: 4,
+            buffer: 1,
+        });
+        let (id, mut receiver) = streams.open();
+        let params = json!({"name": "review", "_meta": {"stream": id}});
+        let sender = streams.take(Some(&params)).unwrap();
+        assert!(streams.take(Some(&params)).is_none());
+
+        let message = |text: &str| PromptMessage {
+            role: MessageRole::User,
+            content: PromptContent::Text {
+                text: text.to_string(),
+            },
+        };
+        let prompt = GetPromptResult {
+            name: "review".to_string(),
+            messages: vec![message("héllo wörld"), message("")],
+            description: "Review".to_string(),
+            meta: HashMap::new(),
+        };
+        let forwarding = tokio::spawn(async move { streams.forward(prompt.into(), sender).await });
+
+        let mut texts: Vec<(usize, String)> = Vec::new();
+        while let Some(chunk) = receiver.recv().await {
+            let PromptContent::Text { text } = chunk.content else {
+                panic!("Expected text");
+            };
+            assert!(text.len() <= 4);
+            texts.push((chunk.message, text));
+        }
+        let result = forwarding.await.unwrap().unwrap();
+        assert!(result.messages.is_empty());
+        assert_eq!(result.meta[STREAMED_META]["messages"], 2);
+
+        let first: String = texts
+            .iter()
+            .filter(|(m, _)| *m == 0)
+            .map(|(_, t)| t.as_str())
+            .collect();
+        assert_eq!(first, "héllo wörld");
+        assert_eq!(texts.last().unwrap(), &(1, String::new()));
+    }
+}
@@ -0,0 +1,507 @@
+//! Quota accounting per principal
+//!
+//! Quotas meter what each principal (see [`SecurityContext::principal`]) uses
+//! in fixed time windows: tool calls, bytes of resources read and batch items.
+//! Usage is kept in a [`QuotaStore`], in memory by default; implement the trait
+//! to share quotas between server instances or keep them across restarts.
+//!
+//! Calls over a limit fail with [`McpError::QuotaExceeded`], which tells the
+//! client how much of each quota is left and when the window resets.
+//! Resource reads are admitted while any byte quota remains and metered by the
+//! size actually served, so the last read of a window may overshoot.
+//!
+//! Concurrent calls are checked against the usage before they are recorded, so
+//! a burst can exceed a limit by the number of calls in flight.
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::RwLock;
+
+use crate::{
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// What a quota meters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaMetric {
+    /// `tools/call` requests
+    ToolCalls,
+    /// Bytes of resource content read
+    ResourceBytes,
+    /// Items in batch requests
+    BatchItems,
+}
+
+impl QuotaMetric {
+    /// Name used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaMetric::ToolCalls => "tool_calls",
+            QuotaMetric::ResourceBytes => "resource_bytes",
+            QuotaMetric::BatchItems => "batch_items",
+        }
+    }
+}
+
+/// Quota configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Whether usage is metered and limits enforced
+    pub enabled: bool,
+
+    /// Length of a quota window
+    #[serde(with = "humantime_serde")]
+    pub window: Duration,
+
+    /// Limit per metric and window; metrics without a limit are only metered
+    #[serde(default)]
+    pub limits: BTreeMap<QuotaMetric, u64>,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: Duration::from_secs(3600),
+            limits: BTreeMap::new(),
+        }
+    }
+}
+
+impl QuotaConfig {
+    /// Meter usage in windows of the given length
+    pub fn per_window(window: Duration) -> Self {
+        Self {
+            enabled: true,
+            window,
+            limits: BTreeMap::new(),
+        }
+    }
+
+    /// Limit a metric per window
+    pub fn with_limit(mut self, metric: QuotaMetric, limit: u64) -> Self {
+        self.limits.insert(metric, limit);
+        self
+    }
+}
+
+/// Usage of a principal in one window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaUsage {
+    /// Principal the usage belongs to
+    pub principal: String,
+
+    /// Start of the window
+    pub window_start: DateTime<Utc>,
+
+    /// Usage per metric
+    pub used: BTreeMap<QuotaMetric, u64>,
+}
+
+impl QuotaUsage {
+    /// Empty usage for a window
+    pub fn new(principal: impl Into<String>, window_start: DateTime<Utc>) -> Self {
+        Self {
+            principal: principal.into(),
+            window_start,
+            used: BTreeMap::new(),
+        }
+    }
+
+    /// Usage of a metric
+    pub fn get(&self, metric: QuotaMetric) -> u64 {
+        self.used.get(&metric).copied().unwrap_or(0)
+    }
+}
+
+/// Quota state of a principal, as reported by the admin methods
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaStatus {
+    /// Principal the quota belongs to
+    pub principal: String,
+
+    /// Start of the current window
+    pub window_start: DateTime<Utc>,
+
+    /// When the current window ends
+    pub resets_at: DateTime<Utc>,
+
+    /// Usage per metric in the current window
+    pub used: BTreeMap<QuotaMetric, u64>,
+
+    /// Configured limits
+    pub limits: BTreeMap<QuotaMetric, u64>,
+
+    /// Remaining quota per limited metric
+    pub remaining: BTreeMap<QuotaMetric, u64>,
+}
+
+/// Storage for quota usage
+///
+/// Windows are identified by their start; usage recorded for an earlier
+/// window must not count towards a later one.
+#[async_trait]
+pub trait QuotaStore: Send + Sync {
+    /// Usage of a principal in the window starting at `window_start`
+    async fn usage(&self, principal: &str, window_start: DateTime<Utc>) -> McpResult<QuotaUsage>;
+
+    /// Add `amount` to a metric, returning the updated usage
+    async fn add(
+        &self,
+        principal: &str,
+        window_start: DateTime<Utc>,
+        metric: QuotaMetric,
+        amount: u64,
+    ) -> McpResult<QuotaUsage>;
+
+    /// Usage of every principal with usage in the window
+    async fn list(&self, window_start: DateTime<Utc>) -> McpResult<Vec<QuotaUsage>>;
+
+    /// Forget the usage of a principal
+    async fn reset(&self, principal: &str) -> McpResult<()>;
+}
+
+/// In-memory quota store
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryQuotaStore {
+    usage: Arc<RwLock<HashMap<String, QuotaUsage>>>,
+}
+
+impl InMemoryQuotaStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl QuotaStore for InMemoryQuotaStore {
+    async fn usage(&self, principal: &str, window_start: DateTime<Utc>) -> McpResult<QuotaUsage> {
+        Ok(self
+            .usage
+            .read()
+            .await
+            .get(principal)
+            .filter(|usage| usage.window_start == window_start)
+            .cloned()
+            .unwrap_or_else(|| QuotaUsage::new(principal, window_start)))
+    }
+
+    async fn add(
+        &self,
+        principal: &str,
+        window_start: DateTime<Utc>,
+        metric: QuotaMetric,
+        amount: u64,
+    ) -> McpResult<QuotaUsage> {
+        let mut usage = self.usage.write().await;
+        let entry = usage
+            .entry(principal.to_string())
+            .or_insert_with(|| QuotaUsage::new(principal, window_start));
+        if entry.window_start != window_start {
+            *entry = QuotaUsage::new(principal, window_start);
+        }
+        let used = entry.used.entry(metric).or_insert(0);
+        *used = used.saturating_add(amount);
+        Ok(entry.clone())
+    }
+
+    async fn list(&self, window_start: DateTime<Utc>) -> McpResult<Vec<QuotaUsage>> {
+        let mut usage = self.usage.write().await;
+        usage.retain(|_, usage| usage.window_start >= window_start);
+        let mut current: Vec<_> = usage.values().cloned().collect();
+        current.sort_by(|a, b| a.principal.cmp(&b.principal));
+        Ok(current)
+    }
+
+    async fn reset(&self, principal: &str) -> McpResult<()> {
+        self.usage.write().await.remove(principal);
+        Ok(())
+    }
+}
+
+/// Meters usage and enforces the configured limits
+#[derive(Clone)]
+pub struct QuotaManager {
+    config: QuotaConfig,
+    store: Arc<dyn QuotaStore>,
+}
+
+impl std::fmt::Debug for QuotaManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuotaManager")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl QuotaManager {
+    /// Create a manager keeping usage in memory
+    pub fn new(config: QuotaConfig) -> Self {
+        Self::with_store(config, Arc::new(InMemoryQuotaStore::new()))
+    }
+
+    /// Create a manager keeping usage in the given store
+    pub fn with_store(config: QuotaConfig, store: Arc<dyn QuotaStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Quota configuration
+    pub fn config(&self) -> &QuotaConfig {
+        &self.config
+    }
+
+    /// Check that `amount` fits in the principal's quota and record it
+    pub async fn consume(
+        &self,
+        context: &SecurityContext,
+        metric: QuotaMetric,
+        amount: u64,
+    ) -> McpResult<()> {
+        let principal = context.principal();
+        let window_start = self.window_start(Utc::now());
+        let usage = self.store.usage(&principal, window_start).await?;
+        if let Some(limit) = self.config.limits.get(&metric) {
+            if usage.get(metric).saturating_add(amount) > *limit {
+                return Err(self.exceeded(&usage, metric));
+            }
+        }
+        self.store
+            .add(&principal, window_start, metric, amount)
+            .await?;
+        Ok(())
+    }
+
+    /// Record usage without enforcing the limit
+    pub async fn record(
+        &self,
+        context: &SecurityContext,
+        metric: QuotaMetric,
+        amount: u64,
+    ) -> McpResult<()> {
+        let window_start = self.window_start(Utc::now());
+        self.store
+            .add(&context.principal(), window_start, metric, amount)
+            .await?;
+        Ok(())
+    }
+
+    /// Check that some of the principal's quota for `metric` is left
+    pub async fn ensure_available(
+        &self,
+        context: &SecurityContext,
+        metric: QuotaMetric,
+    ) -> McpResult<()> {
+        let Some(limit) = self.config.limits.get(&metric) else {
+            return Ok(());
+        };
+        let usage = self
+            .store
+            .usage(&context.principal(), self.window_start(Utc::now()))
+            .await?;
+        if usage.get(metric) >= *limit {
+            return Err(self.exceeded(&usage, metric));
+        }
+        Ok(())
+    }
+
+    /// Quota state of a principal in the current window
+    pub async fn status(&self, principal: &str) -> McpResult<QuotaStatus> {
+        let usage = self
+            .store
+            .usage(principal, self.window_start(Utc::now()))
+            .await?;
+        Ok(self.status_of(usage))
+    }
+
+    /// Quota state of every principal with usage in the current window
+    pub async fn list(&self) -> McpResult<Vec<QuotaStatus>> {
+        let usage = self.store.list(self.window_start(Utc::now())).await?;
+        Ok(usage
+            .into_iter()
+            .map(|usage| self.status_of(usage))
+            .collect())
+    }
+
+    /// Forget a principal's usage, restoring its full quota
+    pub async fn reset(&self, principal: &str) -> McpResult<()> {
+        self.store.reset(principal).await
+    }
+
+    fn status_of(&self, usage: QuotaUsage) -> QuotaStatus {
+        QuotaStatus {
+            resets_at: self.window_end(usage.window_start),
+            remaining: self.remaining(&usage),
+            limits: self.config.limits.clone(),
+            principal: usage.principal,
+            window_start: usage.window_start,
+            used: usage.used,
+        }
+    }
+
+    fn remaining(&self, usage: &QuotaUsage) -> BTreeMap<QuotaMetric, u64> {
+        self.config
+            .limits
+            .iter()
+            .map(|(metric, limit)| (*metric, limit.saturating_sub(usage.get(*metric))))
+            .collect()
+    }
+
+    fn exceeded(&self, usage: &QuotaUsage, metric: QuotaMetric) -> McpError {
+        let reset_after = (self.window_end(usage.window_start) - Utc::now())
+            .num_seconds()
+            .max(0) as u64;
+        McpError::QuotaExceeded {
+            principal: usage.principal.clone(),
+            metric: metric.as_str().to_string(),
+            remaining: self
+                .remaining(usage)
+                .into_iter()
+                .map(|(metric, remaining)| (metric.as_str().to_string(), remaining))
+                .collect(),
+            reset_after,
+        }
+    }
+
+    /// Start of the window containing `now`; windows are aligned to the epoch
+    fn window_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let window = self.config.window.as_millis().max(1) as i64;
+        let millis = now.timestamp_millis();
+        Utc.timestamp_millis_opt(millis - millis.rem_euclid(window))
+            .single()
+            .unwrap_or(now)
+    }
+
+    fn window_end(&self, window_start: DateTime<Utc>) -> DateTime<Utc> {
+        chrono::Duration::from_std(self.config.window)
+            .ok()
+            .and_then(|window| window_start.checked_add_signed(window))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::ClientContext;
+
+    fn client(id: &str) -> SecurityContext {
+        SecurityContext::authenticated(
+            ClientContext {
+                client_id: Some(id.to_string()),
+                ..Default::default()
+            },
+            Vec::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_quota_enforcement() {
+        let quotas = QuotaManager::new(
+            QuotaConfig::per_window(Duration::from_secs(3600))
+                .with_limit(QuotaMetric::ToolCalls, 2)
+                .with_limit(QuotaMetric::ResourceBytes, 100),
+        );
+        let alice = client("alice");
+
+        quotas
+            .consume(&alice, QuotaMetric::ToolCalls, 1)
+            .await
+            .unwrap();
+        quotas
+            .consume(&alice, QuotaMetric::ToolCalls, 1)
+            .await
+            .unwrap();
+        let error = quotas
+            .consume(&alice, QuotaMetric::ToolCalls, 1)
+            .await
+            .unwrap_err();
+        let McpError::QuotaExceeded {
+            metric, remaining, ..
+        } = &error
+        else {
+            panic!("expected quota error, got {:?}", error);
+        };
+        assert_eq!(metric, "tool_calls");
+        assert_eq!(remaining["tool_calls"], 0);
+        assert_eq!(remaining["resource_bytes"], 100);
+        assert_eq!(error.client_data().unwrap()["remaining"]["tool_calls"], 0);
+
+        // Other principals have their own quota
+        quotas
+            .consume(&client("bob"), QuotaMetric::ToolCalls, 1)
+            .await
+            .unwrap();
+
+        // Byte quotas admit reads while any quota is left
+        quotas
+            .consume(&alice, QuotaMetric::ResourceBytes, 150)
+            .await
+            .unwrap_err();
+        quotas
+            .ensure_available(&alice, QuotaMetric::ResourceBytes)
+            .await
+            .unwrap();
+        quotas
+            .record(&alice, QuotaMetric::ResourceBytes, 150)
+            .await
+            .unwrap();
+        quotas
+            .ensure_available(&alice, QuotaMetric::ResourceBytes)
+            .await
+            .unwrap_err();
+
+        let statuses = quotas.list().await.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].principal, "alice");
+        assert_eq!(statuses[0].used[&QuotaMetric::ToolCalls], 2);
+
+        quotas.reset("alice").await.unwrap();
+        let status = quotas.status("alice").await.unwrap();
+        assert_eq!(status.remaining[&QuotaMetric::ToolCalls], 2);
+    }
+
+    #[tokio::test]
+    async fn test_quota_windows() {
+        let store = InMemoryQuotaStore::new();
+        let first = Utc.timestamp_opt(3600, 0).unwrap();
+        let second = Utc.timestamp_opt(7200, 0).unwrap();
+
+        store
+            .add("alice", first, QuotaMetric::BatchItems, 5)
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .usage("alice", first)
+                .await
+                .unwrap()
+                .get(QuotaMetric::BatchItems),
+            5
+        );
+        assert_eq!(
+            store
+                .usage("alice", second)
+                .await
+                .unwrap()
+                .get(QuotaMetric::BatchItems),
+            0
+        );
+        assert!(store.list(second).await.unwrap().is_empty());
+
+        let quotas = QuotaManager::new(QuotaConfig::per_window(Duration::from_secs(3600)));
+        let now = Utc.timestamp_opt(5000, 0).unwrap();
+        assert_eq!(quotas.window_start(now), first);
+        assert_eq!(quotas.window_end(first), second);
+    }
+}
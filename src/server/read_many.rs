@@ -0,0 +1,123 @@
+//! Reading several resources in one request
+//!
+//! Prompt assembly often embeds a dozen resources, and one `resources/read`
+//! per resource costs a round trip each. `resources/read_many` reads a list of
+//! URIs concurrently and answers with one result per URI, in request order:
+//!
+//! ```json
+//! {"method": "resources/read_many", "params": {
+//!     "uris": ["file:///README.md", "file:///missing.md"], "timeoutMs": 2000}}
+//! ```
+//!
+//! Each URI is read exactly like `resources/read`, with the same `accept`
+//! negotiation, quotas and maintenance checks. A URI that fails carries its
+//! own `error` while the others still return their `result`. All reads share
+//! one deadline; reads still running when it passes fail with a timeout.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::protocol::{JsonRpcError, ResourcesReadResult};
+
+/// Reads several resources concurrently
+pub const RESOURCES_READ_MANY: &str = "resources/read_many";
+
+/// Limits of `resources/read_many`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadManyConfig {
+    /// Most URIs one request may name
+    pub max_uris: usize,
+
+    /// Most reads running at once per request
+    pub max_parallel: usize,
+
+    /// Deadline of all reads of a request, which may ask for a shorter one
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl Default for ReadManyConfig {
+    fn default() -> Self {
+        Self {
+            max_uris: 100,
+            max_parallel: 16,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReadManyConfig {
+    /// Deadline of a request asking for `timeout_ms`, capped by [`timeout`](Self::timeout)
+    pub fn deadline(&self, timeout_ms: Option<u64>) -> Duration {
+        timeout_ms
+            .map(Duration::from_millis)
+            .map_or(self.timeout, |requested| requested.min(self.timeout))
+    }
+}
+
+/// Params of the `resources/read_many` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesReadManyParams {
+    /// Resources to read, `?format=` and `?version=` included
+    pub uris: Vec<String>,
+
+    /// Preferred formats of all resources, as for `resources/read`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accept: Option<String>,
+
+    /// Deadline of all reads in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Outcome of reading one URI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceReadOutcome {
+    /// URI as requested
+    pub uri: String,
+
+    /// Read result, if the read succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<ResourcesReadResult>,
+
+    /// Why the read failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+impl ResourceReadOutcome {
+    /// Whether the read succeeded
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Result of the `resources/read_many` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourcesReadManyResult {
+    /// One outcome per requested URI, in request order
+    pub results: Vec<ResourceReadOutcome>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_is_capped() {
+        let config = ReadManyConfig {
+            timeout: Duration::from_secs(5),
+            ..Default::default()
+        };
+        assert_eq!(config.deadline(None), Duration::from_secs(5));
+        assert_eq!(config.deadline(Some(200)), Duration::from_millis(200));
+        assert_eq!(config.deadline(Some(60_000)), Duration::from_secs(5));
+
+        let params: ResourcesReadManyParams =
+            serde_json::from_value(serde_json::json!({"uris": ["a://1"], "timeoutMs": 10}))
+                .unwrap();
+        assert_eq!(params.timeout_ms, Some(10));
+    }
+}
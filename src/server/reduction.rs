@@ -0,0 +1,304 @@
+//! Reduction of oversized resource content
+//!
+//! Resources larger than [`TokenConfig::max_resource_tokens`] or
+//! [`TokenConfig::max_resource_bytes`] are passed through a [`ContentReducer`]
+//! before they are returned, both from `resources/read` and when embedded in
+//! prompt and tool results. Each reduction is recorded in the result's
+//! `_meta.reducedResources` with the reducer's name and the token counts
+//! before and after.
+//!
+//! Built-in reducers cut the text to size: [`HeadTailReducer`] (the default)
+//! keeps its beginning and end, [`TruncatingReducer`] one of them, and
+//! [`LineSamplingReducer`] evenly spaced lines. Implement [`ContentReducer`]
+//! to summarize instead, for example with a model of your own.
+//!
+//! [`TokenConfig::max_resource_tokens`]: crate::server::tokens::TokenConfig::max_resource_tokens
+//! [`TokenConfig::max_resource_bytes`]: crate::server::tokens::TokenConfig::max_resource_bytes
+
+use async_trait::async_trait;
+
+use crate::{
+    error::McpResult,
+    server::tokens::{TokenEstimator, TruncationStrategy},
+};
+
+/// `_meta` key listing the resources that were reduced
+pub const REDUCED_RESOURCES_META: &str = "reducedResources";
+
+/// Line standing in for the content a reducer left out
+pub const ELISION_MARKER: &str = "[...]";
+
+/// Shrinks resource text that exceeds the configured threshold
+#[async_trait]
+pub trait ContentReducer: Send + Sync {
+    /// Name recorded with each reduction
+    fn name(&self) -> &str;
+
+    /// Reduce the text of the resource at `uri` to about `max_tokens`
+    async fn reduce(
+        &self,
+        uri: &str,
+        text: &str,
+        max_tokens: usize,
+        estimator: &dyn TokenEstimator,
+    ) -> McpResult<String>;
+}
+
+/// Keeps the beginning or the end of the text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TruncatingReducer {
+    strategy: TruncationStrategy,
+}
+
+impl TruncatingReducer {
+    /// Cut the text with `strategy`
+    pub fn new(strategy: TruncationStrategy) -> Self {
+        Self { strategy }
+    }
+}
+
+#[async_trait]
+impl ContentReducer for TruncatingReducer {
+    fn name(&self) -> &str {
+        "truncate"
+    }
+
+    async fn reduce(
+        &self,
+        _uri: &str,
+        text: &str,
+        max_tokens: usize,
+        estimator: &dyn TokenEstimator,
+    ) -> McpResult<String> {
+        Ok(match self.strategy {
+            TruncationStrategy::Head => cut(estimator, text, max_tokens, false).to_string(),
+            TruncationStrategy::Tail => cut(estimator, text, max_tokens, true).to_string(),
+            TruncationStrategy::Omit => String::new(),
+        })
+    }
+}
+
+/// Keeps the beginning and the end of the text around an elision marker
+#[derive(Debug, Clone, Copy)]
+pub struct HeadTailReducer {
+    head_share: f64,
+}
+
+impl HeadTailReducer {
+    /// Spend `head_share` of the tokens on the beginning, the rest on the end
+    pub fn new(head_share: f64) -> Self {
+        Self {
+            head_share: head_share.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for HeadTailReducer {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+#[async_trait]
+impl ContentReducer for HeadTailReducer {
+    fn name(&self) -> &str {
+        "head_tail"
+    }
+
+    async fn reduce(
+        &self,
+        _uri: &str,
+        text: &str,
+        max_tokens: usize,
+        estimator: &dyn TokenEstimator,
+    ) -> McpResult<String> {
+        let marker = format!("\n{}\n", ELISION_MARKER);
+        let available = max_tokens.saturating_sub(estimator.estimate(&marker));
+        let head_tokens = (available as f64 * self.head_share) as usize;
+        let head = cut(estimator, text, head_tokens, false);
+        let tail = cut(
+            estimator,
+            &text[head.len()..],
+            available - head_tokens,
+            true,
+        );
+        Ok(format!("{}{}{}", head, marker, tail))
+    }
+}
+
+/// Keeps evenly spaced lines, including the first and the last
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LineSamplingReducer;
+
+#[async_trait]
+impl ContentReducer for LineSamplingReducer {
+    fn name(&self) -> &str {
+        "line_sampling"
+    }
+
+    async fn reduce(
+        &self,
+        _uri: &str,
+        text: &str,
+        max_tokens: usize,
+        estimator: &dyn TokenEstimator,
+    ) -> McpResult<String> {
+        let lines: Vec<&str> = text.lines().collect();
+        let sample = |count: usize| -> String {
+            match count {
+                0 => String::new(),
+                1 => lines[0].to_string(),
+                _ => (0..count)
+                    .map(|i| lines[i * (lines.len() - 1) / (count - 1)])
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            }
+        };
+
+        // Binary search for the most lines that fit
+        let (mut low, mut high) = (0, lines.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if estimator.estimate(&sample(mid)) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(sample(low))
+    }
+}
+
+/// The longest prefix, or suffix with `tail`, of `text` within `max_tokens`
+pub(crate) fn cut<'a>(
+    estimator: &dyn TokenEstimator,
+    text: &'a str,
+    max_tokens: usize,
+    tail: bool,
+) -> &'a str {
+    let boundaries: Vec<usize> = text
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain([text.len()])
+        .collect();
+    let part = |chars: usize| {
+        if tail {
+            &text[boundaries[boundaries.len() - 1 - chars]..]
+        } else {
+            &text[..boundaries[chars]]
+        }
+    };
+
+    // Binary search for the most characters that fit
+    let (mut low, mut high) = (0, boundaries.len() - 1);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if estimator.estimate(part(mid)) <= max_tokens {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    part(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tokens::CharRatioEstimator;
+
+    #[tokio::test]
+    async fn test_builtin_reducers_fit_the_limit() {
+        let estimator = CharRatioEstimator::new(1.0);
+        let text = (1..=20)
+            .map(|i| format!("line {:02}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let reduced = HeadTailReducer::default()
+            .reduce("file:///log", &text, 25, &estimator)
+            .await
+            .unwrap();
+        assert_eq!(reduced, "line 01\nl\n[...]\n9\nline 20");
+
+        let reduced = LineSamplingReducer
+            .reduce("file:///log", &text, 25, &estimator)
+            .await
+            .unwrap();
+        assert_eq!(reduced, "line 01\nline 10\nline 20");
+
+        let reduced = TruncatingReducer::new(TruncationStrategy::Tail)
+            .reduce("file:///log", &text, 7, &estimator)
+            .await
+            .unwrap();
+        assert_eq!(reduced, "line 20");
+    }
+
+    struct Summarizer;
+
+    #[async_trait]
+    impl ContentReducer for Summarizer {
+        fn name(&self) -> &str {
+            "summary"
+        }
+
+        async fn reduce(
+            &self,
+            uri: &str,
+            text: &str,
+            _max_tokens: usize,
+            _estimator: &dyn TokenEstimator,
+        ) -> McpResult<String> {
+            Ok(format!("{} has {} lines", uri, text.lines().count()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_resources_are_reduced_and_recorded() {
+        use crate::{
+            protocol::{messages::ResourceReference, ToolContent, ToolsCallResult},
+            server::tokens::{TokenBudgeter, TokenConfig},
+        };
+        use std::sync::Arc;
+
+        let resource = |uri: &str, text: String| ToolContent::Resource {
+            resource: ResourceReference {
+                uri: uri.to_string(),
+            },
+            text: Some(text),
+            blob: None,
+        };
+        let mut result = ToolsCallResult {
+            content: vec![
+                resource("file:///small", "ok".to_string()),
+                resource("file:///big", "x\n".repeat(50)),
+            ],
+            is_error: false,
+            metadata: Default::default(),
+        };
+        let budgeter = TokenBudgeter::new(
+            Arc::new(CharRatioEstimator::new(1.0)),
+            TokenConfig::default().with_max_resource_bytes(20),
+        )
+        .with_reducer(Arc::new(Summarizer));
+        budgeter.apply_to_tool_result(&mut result, None).await;
+
+        assert_eq!(
+            result.content[0],
+            resource("file:///small", "ok".to_string())
+        );
+        assert_eq!(
+            result.content[1],
+            resource("file:///big", "file:///big has 50 lines".to_string())
+        );
+        assert_eq!(
+            result.metadata["_meta"][REDUCED_RESOURCES_META],
+            serde_json::json!([{
+                "uri": "file:///big",
+                "reducer": "summary",
+                "originalTokens": 100,
+                "tokens": 24,
+            }])
+        );
+    }
+}
@@ -3,12 +3,27 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     error::{McpError, McpResult},
     protocol::{Tool, ToolsCallResult},
     security::SecurityContext,
+    server::{
+        dependencies::{ResourceDependencies, TOOL_RESOURCES_META},
+        locale::ClientLocale,
+        pagination::{paginate, Page, SnapshotMap},
+        progress::{ProgressLevel, ProgressReporter, ProgressUpdate},
+        registry_changelog::{added, RegistryAction, RegistryChangelog, RegistryKind},
+        sandbox::SandboxProfile,
+        schema_compat::{check_schema, SchemaCompatibility},
+        secrets::{Secret, Secrets},
+        serialization::SerializationConfig,
+        services::Services,
+        session_store::SessionStore,
+        snapshot::CapabilitySnapshot,
+        tool_limits::TOOL_LIMITS_META,
+    },
 };
 
 /// MCP tool definition with execution capability
@@ -25,6 +40,14 @@ pub struct McpTool {
 
     /// Whether this tool can be called by any client
     pub public: bool,
+
+    /// Whether calls must be approved by an operator before they run
+    #[serde(default)]
+    pub requires_approval: bool,
+
+    /// Sandbox the tool runs in when executed in an isolated worker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxProfile>,
 }
 
 impl McpTool {
@@ -40,11 +63,13 @@ impl McpTool {
                 name: name.into(),
                 description: description.into(),
                 input_schema,
-                metadata: HashMap::new(),
+                metadata: Default::default(),
             },
             category: category.into(),
             requires_auth: true,
             public: false,
+            requires_approval: false,
+            sandbox: None,
         }
     }
 
@@ -62,6 +87,18 @@ impl McpTool {
         self
     }
 
+    /// Hold calls to this tool until an operator approves them
+    pub fn require_approval(mut self) -> Self {
+        self.requires_approval = true;
+        self
+    }
+
+    /// Confine isolated calls of this tool to `profile`
+    pub fn with_sandbox(mut self, profile: SandboxProfile) -> Self {
+        self.sandbox = Some(profile);
+        self
+    }
+
     /// Add metadata to the tool
     pub fn with_metadata(mut self, key: impl Into<String>, value: Value) -> Self {
         self.tool.metadata.insert(key.into(), value);
@@ -73,6 +110,82 @@ impl McpTool {
         self.category = category.into();
         self
     }
+
+    /// Mark this tool as not modifying anything, with the `readOnlyHint`
+    /// annotation, so it stays available during maintenance windows
+    pub fn read_only(mut self) -> Self {
+        let annotations = self
+            .tool
+            .metadata
+            .entry(ANNOTATIONS_KEY.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        if let Value::Object(annotations) = annotations {
+            annotations.insert(READ_ONLY_HINT.to_string(), Value::Bool(true));
+        }
+        self
+    }
+
+    /// Whether this tool is annotated as read-only
+    pub fn is_read_only(&self) -> bool {
+        is_read_only(&self.tool)
+    }
+
+    /// Declare that this tool reads the resources matching `pattern`, which
+    /// may hold `{argument}` placeholders and `*` wildcards
+    pub fn reads_resource(self, pattern: impl Into<String>) -> Self {
+        self.with_dependency(|dependencies| dependencies.reads.push(pattern.into()))
+    }
+
+    /// Declare that this tool writes the resources matching `pattern`, so
+    /// successful calls invalidate and announce them
+    pub fn writes_resource(self, pattern: impl Into<String>) -> Self {
+        self.with_dependency(|dependencies| dependencies.writes.push(pattern.into()))
+    }
+
+    /// Limit calls to this tool, e.g. `5/min per principal` or `1000/day global`
+    ///
+    /// See [`ToolLimit`](crate::server::ToolLimit) for the syntax.
+    pub fn with_limit(mut self, limit: impl Into<String>) -> Self {
+        let limits = self
+            .tool
+            .metadata
+            .entry(TOOL_LIMITS_META.to_string())
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(limits) = limits {
+            limits.push(Value::String(limit.into()));
+        }
+        self
+    }
+
+    /// Resource patterns this tool reads and writes
+    pub fn resource_dependencies(&self) -> ResourceDependencies {
+        ResourceDependencies::of(&self.tool)
+    }
+
+    fn with_dependency(mut self, add: impl FnOnce(&mut ResourceDependencies)) -> Self {
+        let mut dependencies = self.resource_dependencies();
+        add(&mut dependencies);
+        self.tool.metadata.insert(
+            TOOL_RESOURCES_META.to_string(),
+            serde_json::to_value(dependencies).unwrap_or_default(),
+        );
+        self
+    }
+}
+
+/// Tool metadata key of the MCP tool annotations
+pub const ANNOTATIONS_KEY: &str = "annotations";
+
+/// Annotation marking tools that do not modify their environment
+pub const READ_ONLY_HINT: &str = "readOnlyHint";
+
+/// Whether `tool` is annotated as read-only
+pub fn is_read_only(tool: &Tool) -> bool {
+    tool.metadata
+        .get(ANNOTATIONS_KEY)
+        .and_then(|annotations| annotations.get(READ_ONLY_HINT))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
 }
 
 /// Tool execution context
@@ -89,6 +202,22 @@ pub struct ToolExecutionContext {
 
     /// Additional execution metadata
     pub metadata: HashMap<String, Value>,
+
+    /// Reporter for progress updates, keyed by `request_id`
+    pub progress: Option<Arc<ProgressReporter>>,
+
+    /// Working memory of the session the call belongs to
+    pub session: Option<SessionStore>,
+
+    /// Shared services from the server state
+    pub services: Option<Services>,
+
+    /// Encodings the server uses for tool results; serialize structured
+    /// output with [`SerializationConfig::to_value`]
+    pub serialization: SerializationConfig,
+
+    /// Secret providers of the server, behind [`secret`](Self::secret)
+    pub secrets: Option<Secrets>,
 }
 
 impl ToolExecutionContext {
@@ -98,7 +227,12 @@ impl ToolExecutionContext {
             security,
             arguments: None,
             request_id: None,
-            metadata: HashMap::new(),
+            metadata: Default::default(),
+            progress: None,
+            session: None,
+            services: None,
+            serialization: SerializationConfig::default(),
+            secrets: None,
         }
     }
 
@@ -119,6 +253,96 @@ impl ToolExecutionContext {
         self.metadata.insert(key.into(), value);
         self
     }
+
+    /// Set the progress reporter
+    pub fn with_progress_reporter(mut self, reporter: Arc<ProgressReporter>) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    /// Set the session store
+    pub fn with_session_store(mut self, store: SessionStore) -> Self {
+        self.session = Some(store);
+        self
+    }
+
+    /// Set the service container
+    pub fn with_services(mut self, services: Services) -> Self {
+        self.services = Some(services);
+        self
+    }
+
+    /// Set the encodings for tool results
+    pub fn with_serialization(mut self, serialization: SerializationConfig) -> Self {
+        self.serialization = serialization;
+        self
+    }
+
+    /// Set the secret providers
+    pub fn with_secrets(mut self, secrets: Secrets) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Look up the secret `name`, such as a database password or API token
+    ///
+    /// Fails if no provider has it. Lookups are logged without the value.
+    pub async fn secret(&self, name: &str) -> McpResult<Secret> {
+        match &self.secrets {
+            Some(secrets) => {
+                secrets
+                    .get(name, &self.security, self.request_id.as_deref())
+                    .await
+            }
+            None => Err(McpError::Configuration {
+                message: format!("Secret '{}' is not available", name),
+            }),
+        }
+    }
+
+    /// Locale and time zone of the client, or [`ClientLocale::default`] if it
+    /// sent none, for formatting dates and numbers in results
+    pub fn locale(&self) -> ClientLocale {
+        self.security.locale().cloned().unwrap_or_default()
+    }
+
+    /// Get the service of type `T` from the server state
+    ///
+    /// Fails if the server state offers no services or none of type `T`.
+    pub fn service<T: Send + Sync + 'static>(&self) -> McpResult<Arc<T>> {
+        match &self.services {
+            Some(services) => services.require(),
+            None => Err(McpError::Configuration {
+                message: format!("Service '{}' is not registered", std::any::type_name::<T>()),
+            }),
+        }
+    }
+
+    /// Working memory shared by calls in the same session
+    ///
+    /// Only available when the request belongs to a session (StreamableHTTP,
+    /// legacy SSE) or an identified client.
+    pub fn session_store(&self) -> Option<&SessionStore> {
+        self.session.as_ref()
+    }
+
+    /// Report progress for this call
+    ///
+    /// Updates are streamed to clients that sent a progress token; without a
+    /// request ID or progress reporter this is a no-op.
+    pub async fn report_progress(&self, current: usize, total: usize, message: impl Into<String>) {
+        if let (Some(reporter), Some(request_id)) = (&self.progress, &self.request_id) {
+            reporter
+                .report_progress(ProgressUpdate::new(
+                    request_id.clone(),
+                    message.into(),
+                    ProgressLevel::Info,
+                    current,
+                    total,
+                ))
+                .await;
+        }
+    }
 }
 
 /// Tool registry trait for managing available tools
@@ -127,6 +351,24 @@ pub trait ToolRegistry: Send + Sync {
     /// List all available tools
     async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>>;
 
+    /// List up to `limit` tools after `cursor`, for paginated `tools/list`
+    ///
+    /// Defaults to paging through [`list_tools`](Self::list_tools) by name,
+    /// see [`paginate`].
+    async fn list_tools_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<Tool>> {
+        let tools = self.list_tools(context).await?;
+        paginate(
+            tools.into_iter().map(|t| (t.name.clone(), t)).collect(),
+            cursor,
+            limit,
+        )
+    }
+
     /// Get a specific tool by name
     async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>>;
 
@@ -170,25 +412,85 @@ pub trait ToolRegistry: Send + Sync {
 /// Simple in-memory tool registry implementation
 #[derive(Clone)]
 pub struct InMemoryToolRegistry {
-    tools: HashMap<String, McpTool>,
+    tools: SnapshotMap<McpTool>,
+    baseline: Option<CapabilitySnapshot>,
+    changelog: Option<RegistryChangelog>,
 }
 
 impl InMemoryToolRegistry {
     /// Create a new empty registry
     pub fn new() -> Self {
         Self {
-            tools: HashMap::new(),
+            tools: SnapshotMap::new(),
+            baseline: None,
+            changelog: None,
         }
     }
 
+    /// Record registrations and removals in `changelog`
+    pub fn with_changelog(mut self, changelog: RegistryChangelog) -> Self {
+        self.changelog = Some(changelog);
+        self
+    }
+
+    /// Check tools that are not registered yet against a previous deployment
+    ///
+    /// Used by [`try_register_tool`](Self::try_register_tool) for the first
+    /// registration of a tool in this process.
+    pub fn with_baseline(mut self, snapshot: CapabilitySnapshot) -> Self {
+        self.baseline = Some(snapshot);
+        self
+    }
+
     /// Add a tool to the registry
     pub fn register_tool(&mut self, tool: McpTool) {
-        self.tools.insert(tool.tool.name.clone(), tool);
+        let name = tool.tool.name.clone();
+        let previous = self.tools.insert(name.clone(), tool);
+        if let Some(changelog) = &self.changelog {
+            changelog.record(RegistryKind::Tool, name, added(&previous));
+        }
+    }
+
+    /// Add or replace a tool, rejecting input schema changes that may break callers
+    ///
+    /// The schema is compared with the registered tool of the same name, or
+    /// with the baseline snapshot if the tool is not registered yet. On success
+    /// the returned compatibility lists the non-breaking changes.
+    pub fn try_register_tool(&mut self, tool: McpTool) -> McpResult<SchemaCompatibility> {
+        let name = &tool.tool.name;
+        let compatibility = match (self.tools.get(name), &self.baseline) {
+            (Some(previous), _) => {
+                check_schema(&previous.tool.input_schema, &tool.tool.input_schema)
+            }
+            (None, Some(baseline)) => baseline.check_tool(name, &tool.tool.input_schema),
+            (None, None) => SchemaCompatibility::default(),
+        };
+
+        if compatibility.is_breaking() {
+            let reasons: Vec<String> = compatibility
+                .breaking_changes()
+                .map(ToString::to_string)
+                .collect();
+            return Err(McpError::Validation {
+                message: format!(
+                    "Incompatible input schema for tool '{}': {}",
+                    name,
+                    reasons.join("; ")
+                ),
+            });
+        }
+
+        self.register_tool(tool);
+        Ok(compatibility)
     }
 
     /// Remove a tool from the registry
     pub fn unregister_tool(&mut self, name: &str) -> Option<McpTool> {
-        self.tools.remove(name)
+        let removed = self.tools.remove(name);
+        if let (Some(changelog), Some(_)) = (&self.changelog, &removed) {
+            changelog.record(RegistryKind::Tool, name, RegistryAction::Removed);
+        }
+        removed
     }
 
     /// Get all registered tools
@@ -209,18 +511,30 @@ impl ToolRegistry for InMemoryToolRegistry {
         let tools = self
             .tools
             .values()
-            .filter(|tool| {
-                // Filter tools based on authentication requirements
-                !(tool.requires_auth && context.is_anonymous())
-            })
+            .filter(|tool| is_listed(tool, context))
             .map(|mcp_tool| mcp_tool.tool.clone())
             .collect();
 
         Ok(tools)
     }
 
+    async fn list_tools_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<Tool>> {
+        self.tools.page(cursor, limit, |tool| {
+            is_listed(tool, context).then(|| tool.tool.clone())
+        })
+    }
+
     async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>> {
         if let Some(tool) = self.tools.get(name) {
+            // Tools outside the listener's exposure do not exist for the client
+            if !context.exposes(tool.exposure()) {
+                return Ok(None);
+            }
             // Check access permissions
             if tool.requires_auth && context.is_anonymous() {
                 return Err(McpError::Authorization {
@@ -247,7 +561,7 @@ impl ToolRegistry for InMemoryToolRegistry {
 
     async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
         if let Some(tool) = self.tools.get(name) {
-            !(tool.requires_auth && context.is_anonymous())
+            !(tool.requires_auth && context.is_anonymous()) && context.exposes(tool.exposure())
         } else {
             false
         }
@@ -257,7 +571,8 @@ impl ToolRegistry for InMemoryToolRegistry {
         let mut categories = std::collections::HashSet::new();
 
         for tool in self.tools.values() {
-            if !tool.requires_auth || !context.is_anonymous() {
+            if (!tool.requires_auth || !context.is_anonymous()) && context.exposes(tool.exposure())
+            {
                 categories.insert(tool.category.clone());
             }
         }
@@ -266,6 +581,12 @@ impl ToolRegistry for InMemoryToolRegistry {
     }
 }
 
+/// Whether `tool` is listed to `context`, given its authentication
+/// requirement and exposure
+fn is_listed(tool: &McpTool, context: &SecurityContext) -> bool {
+    !(tool.requires_auth && context.is_anonymous()) && context.exposes(tool.exposure())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,6 +625,31 @@ mod tests {
         assert!(!registry.can_access_tool("nonexistent", &context).await);
     }
 
+    #[tokio::test]
+    async fn test_tool_exposure() {
+        let mut registry = InMemoryToolRegistry::new();
+        let schema = serde_json::json!({"type": "object"});
+        registry.register_tool(McpTool::new("search", "Search", schema.clone(), "general"));
+        registry.register_tool(
+            McpTool::new("reindex", "Rebuild the index", schema, "ops")
+                .with_exposure(crate::server::exposure::ADMIN),
+        );
+
+        let exposure = crate::server::ExposureConfig::default();
+        let mut remote = SecurityContext::authenticated(Default::default(), Vec::new());
+        exposure.with_listener("unix", ["admin"]).apply(&mut remote);
+
+        let tools = registry.list_tools(&remote).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "search");
+        assert!(registry
+            .get_tool("reindex", &remote)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!registry.can_access_tool("reindex", &remote).await);
+    }
+
     #[tokio::test]
     async fn test_tool_authentication() {
         let mut registry = InMemoryToolRegistry::new();
@@ -344,4 +690,46 @@ mod tests {
         assert!(!registry.can_access_tool("auth_tool", &anon_context).await);
         assert!(registry.can_access_tool("public_tool", &anon_context).await);
     }
+
+    #[test]
+    fn test_try_register_tool_rejects_breaking_schemas() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}},
+            "required": ["query"]
+        });
+        let mut baseline = CapabilitySnapshot::default();
+        baseline.tools.insert(
+            "search".to_string(),
+            crate::server::ToolSnapshot::from(
+                &McpTool::new("search", "Search", schema.clone(), "test").tool,
+            ),
+        );
+        let mut registry = InMemoryToolRegistry::new().with_baseline(baseline);
+
+        let stricter = serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}, "index": {"type": "string"}},
+            "required": ["query", "index"]
+        });
+        let error = registry
+            .try_register_tool(McpTool::new("search", "Search", stricter, "test"))
+            .unwrap_err();
+        assert!(error.to_string().contains("`index` became required"));
+        assert!(registry.get_all_tools().is_empty());
+
+        let relaxed = serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}}
+        });
+        let compatibility = registry
+            .try_register_tool(McpTool::new("search", "Search", relaxed, "test"))
+            .unwrap();
+        assert!(!compatibility.changes.is_empty());
+
+        // Once registered, updates are checked against the live version
+        assert!(registry
+            .try_register_tool(McpTool::new("search", "Search", schema, "test"))
+            .is_err());
+    }
 }
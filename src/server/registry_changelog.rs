@@ -0,0 +1,236 @@
+//! Changelog of registry mutations
+//!
+//! Registries change while a server runs: deployments add tools, feature
+//! flags remove prompts, templates get replaced. To answer "when did this
+//! tool disappear and who removed it", hand a [`RegistryChangelog`] to the
+//! in-memory registries and to the server:
+//!
+//! ```rust,ignore
+//! let changelog = RegistryChangelog::new(1000);
+//! let tools = InMemoryToolRegistry::new().with_changelog(changelog.by("startup"));
+//! let server = McpServer::new(config, state).with_registry_changelog(changelog);
+//! ```
+//!
+//! Each registration, replacement and removal is recorded with its time and
+//! the actor of the handle that made it. `admin/registry/changelog` returns
+//! the recorded changes, newest first, optionally for one kind or name only,
+//! and [`RegistryChangelog::export_jsonl`] writes them out as JSON lines.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::clock::Clock;
+
+/// Returns the recorded registry changes
+pub const ADMIN_REGISTRY_CHANGELOG: &str = "admin/registry/changelog";
+
+/// Actor of changes made through a handle without one
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Kind of registry entry that changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryKind {
+    Tool,
+    Prompt,
+    Resource,
+    ResourceTemplate,
+}
+
+/// What happened to the entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistryAction {
+    /// Added under a new name
+    Registered,
+    /// Added under the name of an existing entry
+    Replaced,
+    /// Removed
+    Removed,
+}
+
+/// A recorded registry change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryChange {
+    pub at: DateTime<Utc>,
+    pub kind: RegistryKind,
+    /// Tool or prompt name, resource URI or URI template
+    pub name: String,
+    pub action: RegistryAction,
+    pub actor: String,
+}
+
+/// Parameters of `admin/registry/changelog`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChangelogQuery {
+    /// Only changes to entries of this kind
+    pub kind: Option<RegistryKind>,
+
+    /// Only changes to the entry with this name
+    pub name: Option<String>,
+
+    /// Only changes made after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Maximum number of changes to return
+    pub limit: Option<usize>,
+}
+
+impl ChangelogQuery {
+    fn matches(&self, change: &RegistryChange) -> bool {
+        self.kind.is_none_or(|kind| change.kind == kind)
+            && self.name.as_ref().is_none_or(|name| &change.name == name)
+            && self.since.is_none_or(|since| change.at > since)
+    }
+}
+
+/// Bounded log of registry changes
+///
+/// Clones share the log; [`by`](Self::by) returns one recording as another
+/// actor.
+#[derive(Debug, Clone)]
+pub struct RegistryChangelog {
+    changes: Arc<Mutex<VecDeque<RegistryChange>>>,
+    max_entries: usize,
+    actor: String,
+    clock: Clock,
+}
+
+impl RegistryChangelog {
+    /// Changelog keeping the last `max_entries` changes
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            changes: Arc::new(Mutex::new(VecDeque::new())),
+            max_entries,
+            actor: SYSTEM_ACTOR.to_string(),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Timestamp changes with `clock`
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Handle on the same log recording changes as made by `actor`
+    pub fn by(&self, actor: impl Into<String>) -> Self {
+        Self {
+            actor: actor.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Actor changes made through this handle are attributed to
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// Record that the entry `name` of `kind` was changed by this handle's actor
+    pub fn record(&self, kind: RegistryKind, name: impl Into<String>, action: RegistryAction) {
+        let change = RegistryChange {
+            at: self.clock.system_time().into(),
+            kind,
+            name: name.into(),
+            action,
+            actor: self.actor.clone(),
+        };
+        let mut changes = self.lock();
+        changes.push_back(change);
+        while changes.len() > self.max_entries {
+            changes.pop_front();
+        }
+    }
+
+    /// Recorded changes matching `query`, newest first
+    pub fn changes(&self, query: &ChangelogQuery) -> Vec<RegistryChange> {
+        self.lock()
+            .iter()
+            .rev()
+            .filter(|change| query.matches(change))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    /// Recorded changes as JSON lines, oldest first
+    pub fn export_jsonl(&self) -> String {
+        self.lock()
+            .iter()
+            .filter_map(|change| serde_json::to_string(change).ok())
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<RegistryChange>> {
+        self.changes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Action of adding an entry, given the entry it replaced
+pub(crate) fn added<T>(previous: &Option<T>) -> RegistryAction {
+    match previous {
+        Some(_) => RegistryAction::Replaced,
+        None => RegistryAction::Registered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{InMemoryToolRegistry, McpTool};
+    use std::time::Duration;
+
+    #[test]
+    fn test_records_who_changed_what() {
+        let clock = Clock::manual();
+        let changelog = RegistryChangelog::new(3).with_clock(clock.clone());
+        let tool = |name: &str| McpTool::new(name, "A tool", serde_json::json!({}), "test");
+        let mut startup = InMemoryToolRegistry::new().with_changelog(changelog.by("startup"));
+        startup.register_tool(tool("export"));
+        startup.register_tool(tool("export"));
+
+        clock.advance(Duration::from_secs(60));
+        let removed_after = clock.system_time().into();
+        clock.advance(Duration::from_secs(1));
+        let mut deploy = startup.clone().with_changelog(changelog.by("deploy"));
+        assert!(deploy.unregister_tool("export").is_some());
+        assert!(deploy.unregister_tool("missing").is_none());
+
+        let changes = changelog.changes(&ChangelogQuery {
+            name: Some("export".to_string()),
+            ..Default::default()
+        });
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.action, c.actor.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (RegistryAction::Removed, "deploy"),
+                (RegistryAction::Replaced, "startup"),
+                (RegistryAction::Registered, "startup"),
+            ]
+        );
+
+        let recent = changelog.changes(&ChangelogQuery {
+            since: Some(removed_after),
+            ..Default::default()
+        });
+        assert_eq!(recent.len(), 1);
+        assert_eq!(changelog.export_jsonl().lines().count(), 3);
+
+        startup.register_tool(tool("import"));
+        let kept = changelog.changes(&ChangelogQuery::default());
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].name, "import");
+    }
+}
@@ -4,13 +4,20 @@
 //! custom URI schemes for different projects (ratchet://, layercake://, etc.)
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{collections::HashMap, fmt};
 use url::Url;
 
 use crate::{
     error::{McpError, McpResult},
+    ids::{IdKind, Ids},
     security::SecurityContext,
+    server::exposure::profile_of,
+    server::mime::{charset_of, decode_text, detect_mime_type, is_text_mime_type, OCTET_STREAM},
+    server::pagination::{paginate, Page, SnapshotMap},
+    server::registry_changelog::{added, RegistryAction, RegistryChangelog, RegistryKind},
 };
 
 /// Resource content types
@@ -40,6 +47,59 @@ pub struct Resource {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+impl Resource {
+    /// Create a resource from raw content, such as a file or HTTP response
+    ///
+    /// A `content_type` like an HTTP `Content-Type` header is trusted unless it
+    /// is `application/octet-stream`; otherwise the MIME type is detected from
+    /// the bytes and the URI's extension. Text is decoded to UTF-8, recording a
+    /// different source encoding as `sourceCharset` in the metadata; other
+    /// content becomes a base64 blob.
+    pub fn from_bytes(
+        uri: impl Into<String>,
+        name: impl Into<String>,
+        bytes: &[u8],
+        content_type: Option<&str>,
+    ) -> Self {
+        use base64::Engine;
+
+        let uri = uri.into();
+        let declared = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|essence| essence.trim().to_ascii_lowercase())
+            .filter(|essence| !essence.is_empty() && essence != OCTET_STREAM);
+        let mime_type = declared.unwrap_or_else(|| {
+            let path = Url::parse(&uri)
+                .map(|url| url.path().to_string())
+                .unwrap_or_else(|_| uri.clone());
+            detect_mime_type(bytes, Some(&path))
+        });
+
+        let mut metadata = HashMap::new();
+        let content = if is_text_mime_type(&mime_type) {
+            let (text, charset) = decode_text(bytes, content_type.and_then(charset_of));
+            if charset != "UTF-8" {
+                metadata.insert("sourceCharset".to_string(), charset.into());
+            }
+            ResourceContent::Text { text }
+        } else {
+            ResourceContent::Blob {
+                blob: base64::engine::general_purpose::STANDARD.encode(bytes),
+                mime_type: mime_type.clone(),
+            }
+        };
+
+        Self {
+            uri,
+            name: name.into(),
+            description: None,
+            mime_type: Some(mime_type),
+            content,
+            metadata,
+        }
+    }
+}
+
 /// Resource template for listing available resources
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceTemplate {
@@ -185,6 +245,29 @@ pub trait ResourceRegistry: Send + Sync {
         context: &SecurityContext,
     ) -> McpResult<Vec<ResourceTemplate>>;
 
+    /// List up to `limit` resource templates after `cursor`, for paginated
+    /// `resources/list`
+    ///
+    /// Defaults to paging through
+    /// [`list_resource_templates`](Self::list_resource_templates) by URI
+    /// template, see [`paginate`].
+    async fn list_resource_templates_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<ResourceTemplate>> {
+        let templates = self.list_resource_templates(context).await?;
+        paginate(
+            templates
+                .into_iter()
+                .map(|t| (t.uri_template.clone(), t))
+                .collect(),
+            cursor,
+            limit,
+        )
+    }
+
     /// Get a specific resource by URI
     async fn get_resource(&self, uri: &str, context: &SecurityContext) -> McpResult<Resource>;
 
@@ -209,6 +292,153 @@ pub trait ResourceRegistry: Send + Sync {
     fn can_handle_uri(&self, uri: &str) -> bool {
         self.uri_scheme().matches_uri(uri)
     }
+
+    /// This registry's version history, if it keeps one
+    fn as_versioned(&self) -> Option<&dyn VersionedResourceRegistry> {
+        None
+    }
+
+    /// MIME types the resource at `uri` can be read as, its default first
+    ///
+    /// `resources/read` negotiates among them with its `accept` argument.
+    /// Empty, the default, for resources with a single representation.
+    async fn list_representations(
+        &self,
+        _uri: &str,
+        _context: &SecurityContext,
+    ) -> McpResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// The resource at `uri` as `mime_type`, one of its
+    /// [representations](Self::list_representations)
+    async fn get_representation(
+        &self,
+        uri: &str,
+        _mime_type: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Resource> {
+        self.get_resource(uri, context).await
+    }
+
+    /// Drop anything cached for the resource at `uri`, which a tool has
+    /// [declared](crate::server::McpTool::writes_resource) to have written
+    ///
+    /// Registries without caches keep the default, which does nothing.
+    fn invalidate(&self, _uri: &str) {}
+}
+
+/// Resource registry that keeps earlier versions of its resources
+///
+/// Implementations return `Some(self)` from
+/// [`ResourceRegistry::as_versioned`] so the server can find them.
+#[async_trait]
+pub trait VersionedResourceRegistry: ResourceRegistry {
+    /// Get a resource as it was at `version`
+    async fn get_resource_at(
+        &self,
+        uri: &str,
+        version: u64,
+        context: &SecurityContext,
+    ) -> McpResult<Resource>;
+
+    /// List the versions of a resource, oldest first
+    async fn list_versions(
+        &self,
+        uri: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Vec<ResourceVersion>>;
+}
+
+/// A stored version of a resource
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceVersion {
+    /// Version number, starting at 1
+    pub version: u64,
+    /// When this version was stored
+    pub modified_at: DateTime<Utc>,
+}
+
+/// Lists the stored versions of a resource
+pub const RESOURCES_VERSIONS: &str = "resources/versions";
+
+/// Params of the `resources/versions` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourcesVersionsParams {
+    /// Resource whose versions to list
+    pub uri: String,
+}
+
+/// Query parameter selecting a resource version, as in `docs://readme?version=3`
+pub const VERSION_QUERY_PARAM: &str = "version";
+
+/// Split a `?version=N` query parameter off a resource URI
+///
+/// Returns the URI without the parameter, and the version if there was one.
+pub fn split_version(uri: &str) -> McpResult<(String, Option<u64>)> {
+    let (rest, version) = split_query_param(uri, VERSION_QUERY_PARAM);
+    let version = version
+        .map(|version| {
+            version
+                .parse::<u64>()
+                .map_err(|_| McpError::InvalidResource {
+                    uri: uri.to_string(),
+                    message: format!("Invalid resource version '{}'", version),
+                })
+        })
+        .transpose()?;
+    Ok((rest, version))
+}
+
+/// Split the query parameter `name` off a resource URI
+///
+/// Returns the URI without the parameter, and its value if there was one.
+pub fn split_query_param(uri: &str, name: &str) -> (String, Option<String>) {
+    let Ok(mut url) = Url::parse(uri) else {
+        return (uri.to_string(), None);
+    };
+    let mut value = None;
+    let mut rest = Vec::new();
+    for (key, pair_value) in url.query_pairs() {
+        if key == name {
+            value = Some(pair_value.into_owned());
+        } else {
+            rest.push((key.into_owned(), pair_value.into_owned()));
+        }
+    }
+    if value.is_none() {
+        return (uri.to_string(), None);
+    }
+    if rest.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(rest);
+    }
+    (url.to_string(), value)
+}
+
+/// `_meta` key of the entity tag of a read resource
+pub const ETAG_META: &str = "etag";
+
+/// Template metadata key listing the MIME types a resource can be read as
+pub const REPRESENTATIONS_META: &str = "representations";
+
+/// Entity tag of resource content: the hex SHA-256 of the text or base64 blob
+pub fn content_etag(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether an `ifNoneMatch` value matches `etag`
+///
+/// As with HTTP's `If-None-Match`, the value may list tags separated by
+/// commas, quoted or weak (`W/"..."`), or be `*` to match any.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/").trim_matches('"') == etag)
 }
 
 /// Multi-scheme resource registry that delegates to scheme-specific registries
@@ -328,16 +558,89 @@ impl ResourceRegistry for MultiSchemeResourceRegistry {
     fn can_handle_uri(&self, uri: &str) -> bool {
         self.get_registry_for_uri(uri).is_ok()
     }
+
+    fn as_versioned(&self) -> Option<&dyn VersionedResourceRegistry> {
+        Some(self)
+    }
+
+    async fn list_representations(
+        &self,
+        uri: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Vec<String>> {
+        self.get_registry_for_uri(uri)?
+            .list_representations(uri, context)
+            .await
+    }
+
+    async fn get_representation(
+        &self,
+        uri: &str,
+        mime_type: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Resource> {
+        self.get_registry_for_uri(uri)?
+            .get_representation(uri, mime_type, context)
+            .await
+    }
+
+    fn invalidate(&self, uri: &str) {
+        if let Ok(registry) = self.get_registry_for_uri(uri) {
+            registry.invalidate(uri);
+        }
+    }
+}
+
+#[async_trait]
+impl VersionedResourceRegistry for MultiSchemeResourceRegistry {
+    async fn get_resource_at(
+        &self,
+        uri: &str,
+        version: u64,
+        context: &SecurityContext,
+    ) -> McpResult<Resource> {
+        self.get_registry_for_uri(uri)?
+            .as_versioned()
+            .ok_or_else(|| unversioned(uri))?
+            .get_resource_at(uri, version, context)
+            .await
+    }
+
+    async fn list_versions(
+        &self,
+        uri: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Vec<ResourceVersion>> {
+        self.get_registry_for_uri(uri)?
+            .as_versioned()
+            .ok_or_else(|| unversioned(uri))?
+            .list_versions(uri, context)
+            .await
+    }
+}
+
+/// Error for a version requested from a registry without history
+pub(crate) fn unversioned(uri: &str) -> McpError {
+    McpError::InvalidResource {
+        uri: uri.to_string(),
+        message: "Resource versions are not kept".to_string(),
+    }
 }
 
 /// In-memory resource registry implementation for testing
+///
+/// Every resource added is kept as a new version.
 #[derive(Debug, Clone)]
 pub struct InMemoryResourceRegistry {
     scheme_config: UriSchemeConfig,
     resources: HashMap<String, Resource>,
-    templates: Vec<ResourceTemplate>,
+    history: HashMap<String, Vec<(ResourceVersion, Resource)>>,
+    representations: HashMap<String, Vec<Resource>>,
+    templates: SnapshotMap<ResourceTemplate>,
     #[allow(dead_code)]
     subscriptions: HashMap<String, ResourceSubscription>,
+    changelog: Option<RegistryChangelog>,
+    ids: Ids,
 }
 
 impl InMemoryResourceRegistry {
@@ -346,19 +649,121 @@ impl InMemoryResourceRegistry {
         Self {
             scheme_config,
             resources: HashMap::new(),
-            templates: Vec::new(),
+            history: HashMap::new(),
+            representations: HashMap::new(),
+            templates: SnapshotMap::new(),
             subscriptions: HashMap::new(),
+            changelog: None,
+            ids: Ids::default(),
+        }
+    }
+
+    /// Record additions and removals in `changelog`
+    pub fn with_changelog(mut self, changelog: RegistryChangelog) -> Self {
+        self.changelog = Some(changelog);
+        self
+    }
+
+    /// Generate subscription IDs with `ids`, usually
+    /// [`McpServer::ids`](crate::server::McpServer::ids)
+    pub fn with_ids(mut self, ids: Ids) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Add a resource to the registry, or a new version of it
+    ///
+    /// Returns the version number of the added resource.
+    pub fn add_resource(&mut self, resource: Resource) -> u64 {
+        let history = self.history.entry(resource.uri.clone()).or_default();
+        let version = ResourceVersion {
+            version: history.len() as u64 + 1,
+            modified_at: Utc::now(),
+        };
+        history.push((version.clone(), resource.clone()));
+        let uri = resource.uri.clone();
+        let previous = self.resources.insert(uri.clone(), resource);
+        if let Some(changelog) = &self.changelog {
+            changelog.record(RegistryKind::Resource, uri, added(&previous));
+        }
+        version.version
+    }
+
+    /// Remove the resource at `uri` and its other representations
+    ///
+    /// Its previous versions stay readable.
+    pub fn remove_resource(&mut self, uri: &str) -> Option<Resource> {
+        self.representations.remove(uri);
+        let removed = self.resources.remove(uri);
+        if let (Some(changelog), Some(_)) = (&self.changelog, &removed) {
+            changelog.record(RegistryKind::Resource, uri, RegistryAction::Removed);
         }
+        removed
     }
 
-    /// Add a resource to the registry
-    pub fn add_resource(&mut self, resource: Resource) {
-        self.resources.insert(resource.uri.clone(), resource);
+    /// Add another representation of the resource at `resource.uri`
+    ///
+    /// The resource added with [`add_resource`](Self::add_resource) stays the
+    /// default; this one is read when `resources/read` accepts its MIME type
+    /// rather than the default's. Replaces a representation of the same type.
+    pub fn add_representation(&mut self, resource: Resource) {
+        let representations = self
+            .representations
+            .entry(resource.uri.clone())
+            .or_default();
+        representations.retain(|other| other.mime_type != resource.mime_type);
+        representations.push(resource);
     }
 
-    /// Add a resource template
+    /// Add a resource template, replacing one with the same URI template
     pub fn add_template(&mut self, template: ResourceTemplate) {
-        self.templates.push(template);
+        let uri_template = template.uri_template.clone();
+        let previous = self.templates.insert(uri_template.clone(), template);
+        if let Some(changelog) = &self.changelog {
+            changelog.record(
+                RegistryKind::ResourceTemplate,
+                uri_template,
+                added(&previous),
+            );
+        }
+    }
+
+    /// `template` with the MIME types of its representations in its metadata
+    fn annotated(
+        &self,
+        template: &ResourceTemplate,
+        context: &SecurityContext,
+    ) -> ResourceTemplate {
+        let mut template = template.clone();
+        let mime_types = self.mime_types_of(&template.uri_template, context);
+        if !mime_types.is_empty() {
+            template
+                .metadata
+                .insert(REPRESENTATIONS_META.to_string(), mime_types.into());
+        }
+        template
+    }
+
+    /// MIME types of the representations of the resource at `uri`, its
+    /// default first, or none if it has a single one
+    fn mime_types_of(&self, uri: &str, context: &SecurityContext) -> Vec<String> {
+        let Some(alternatives) = self.representations.get(uri) else {
+            return Vec::new();
+        };
+        let Some(default) = self
+            .resources
+            .get(uri)
+            .filter(|resource| context.exposes(profile_of(&resource.metadata)))
+        else {
+            return Vec::new();
+        };
+        let mut mime_types: Vec<String> = default.mime_type.iter().cloned().collect();
+        for mime_type in alternatives.iter().filter_map(|r| r.mime_type.as_ref()) {
+            if !mime_types.contains(mime_type) {
+                mime_types.push(mime_type.clone());
+            }
+        }
+        mime_types
     }
 }
 
@@ -370,22 +775,41 @@ impl ResourceRegistry for InMemoryResourceRegistry {
 
     async fn list_resource_templates(
         &self,
-        _context: &SecurityContext,
+        context: &SecurityContext,
     ) -> McpResult<Vec<ResourceTemplate>> {
-        Ok(self.templates.clone())
+        Ok(self
+            .templates
+            .values()
+            .map(|template| self.annotated(template, context))
+            .collect())
+    }
+
+    async fn list_resource_templates_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<ResourceTemplate>> {
+        self.templates.page(cursor, limit, |template| {
+            Some(self.annotated(template, context))
+        })
     }
 
-    async fn get_resource(&self, uri: &str, _context: &SecurityContext) -> McpResult<Resource> {
+    async fn get_resource(&self, uri: &str, context: &SecurityContext) -> McpResult<Resource> {
         self.resources
             .get(uri)
+            .filter(|resource| context.exposes(profile_of(&resource.metadata)))
             .cloned()
             .ok_or_else(|| McpError::ResourceNotFound {
                 uri: uri.to_string(),
             })
     }
 
-    async fn resource_exists(&self, uri: &str, _context: &SecurityContext) -> McpResult<bool> {
-        Ok(self.resources.contains_key(uri))
+    async fn resource_exists(&self, uri: &str, context: &SecurityContext) -> McpResult<bool> {
+        Ok(self
+            .resources
+            .get(uri)
+            .is_some_and(|resource| context.exposes(profile_of(&resource.metadata))))
     }
 
     async fn subscribe_to_resource(
@@ -395,7 +819,7 @@ impl ResourceRegistry for InMemoryResourceRegistry {
     ) -> McpResult<ResourceSubscription> {
         let subscription = ResourceSubscription {
             uri: uri.to_string(),
-            subscription_id: uuid::Uuid::new_v4().to_string(),
+            subscription_id: self.ids.generate(IdKind::Subscription),
         };
         Ok(subscription)
     }
@@ -408,6 +832,72 @@ impl ResourceRegistry for InMemoryResourceRegistry {
         // In-memory implementation just accepts all unsubscriptions
         Ok(())
     }
+
+    fn as_versioned(&self) -> Option<&dyn VersionedResourceRegistry> {
+        Some(self)
+    }
+
+    async fn list_representations(
+        &self,
+        uri: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Vec<String>> {
+        Ok(self.mime_types_of(uri, context))
+    }
+
+    async fn get_representation(
+        &self,
+        uri: &str,
+        mime_type: &str,
+        context: &SecurityContext,
+    ) -> McpResult<Resource> {
+        let default = self.get_resource(uri, context).await?;
+        if default.mime_type.as_deref() == Some(mime_type) {
+            return Ok(default);
+        }
+        self.representations
+            .get(uri)
+            .and_then(|alternatives| {
+                alternatives
+                    .iter()
+                    .find(|r| r.mime_type.as_deref() == Some(mime_type))
+            })
+            .cloned()
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: uri.to_string(),
+            })
+    }
+}
+
+#[async_trait]
+impl VersionedResourceRegistry for InMemoryResourceRegistry {
+    async fn get_resource_at(
+        &self,
+        uri: &str,
+        version: u64,
+        _context: &SecurityContext,
+    ) -> McpResult<Resource> {
+        self.history
+            .get(uri)
+            .and_then(|history| history.iter().find(|(v, _)| v.version == version))
+            .map(|(_, resource)| resource.clone())
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: format!("{}?{}={}", uri, VERSION_QUERY_PARAM, version),
+            })
+    }
+
+    async fn list_versions(
+        &self,
+        uri: &str,
+        _context: &SecurityContext,
+    ) -> McpResult<Vec<ResourceVersion>> {
+        self.history
+            .get(uri)
+            .map(|history| history.iter().map(|(v, _)| v.clone()).collect())
+            .ok_or_else(|| McpError::ResourceNotFound {
+                uri: uri.to_string(),
+            })
+    }
 }
 
 impl fmt::Display for ResourceChangeType {
@@ -518,4 +1008,55 @@ mod tests {
         assert!(scheme_names.contains(&"ratchet"));
         assert!(scheme_names.contains(&"layercake"));
     }
+
+    #[test]
+    fn test_resource_from_bytes() {
+        let resource = Resource::from_bytes(
+            "https://example.com/menu",
+            "Menu",
+            b"caf\xe9",
+            Some("text/html; charset=ISO-8859-1"),
+        );
+        assert_eq!(resource.mime_type.as_deref(), Some("text/html"));
+        assert!(matches!(&resource.content, ResourceContent::Text { text } if text == "café"));
+        assert_eq!(resource.metadata["sourceCharset"], "windows-1252");
+
+        let resource = Resource::from_bytes("file:///srv/logo", "Logo", b"GIF89a\x01\x00", None);
+        assert_eq!(resource.mime_type.as_deref(), Some("image/gif"));
+        assert!(matches!(
+            &resource.content,
+            ResourceContent::Blob { blob, mime_type } if blob == "R0lGODlhAQA=" && mime_type == "image/gif"
+        ));
+
+        let resource = Resource::from_bytes("file:///srv/config.yaml", "Config", b"a: 1", None);
+        assert_eq!(resource.mime_type.as_deref(), Some("text/x-yaml"));
+        assert!(resource.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = content_etag(b"final\n");
+        assert_eq!(etag.len(), 64);
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"stale\", W/\"{}\"", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("stale", &etag));
+    }
+
+    #[test]
+    fn test_split_version() {
+        assert_eq!(
+            split_version("docs://readme?version=3").unwrap(),
+            ("docs://readme".to_string(), Some(3))
+        );
+        assert_eq!(
+            split_version("docs://readme?lang=en&version=2").unwrap(),
+            ("docs://readme?lang=en".to_string(), Some(2))
+        );
+        assert_eq!(
+            split_version("docs://readme?lang=en").unwrap(),
+            ("docs://readme?lang=en".to_string(), None)
+        );
+        assert!(split_version("docs://readme?version=latest").is_err());
+    }
 }
@@ -0,0 +1,201 @@
+//! Sandbox profiles for isolated tools
+//!
+//! A tool declares the filesystem paths and system calls it needs with
+//! [`McpTool::with_sandbox`](crate::server::McpTool::with_sandbox). With the
+//! `sandbox` feature on Linux, an
+//! [`IsolatedToolExecutor`](crate::server::isolation::IsolatedToolExecutor)
+//! runs each call of such a tool in a fresh worker process that confines
+//! itself before serving it:
+//!
+//! - Landlock limits filesystem access to the profile's read and write paths.
+//!   A profile without paths allows no filesystem access.
+//! - A seccomp filter makes system calls fail with `EPERM`: only the allowed
+//!   ones run when the profile lists any, and the denied ones never run.
+//!
+//! Both are defense in depth on top of the worker's process boundary. Denying
+//! system calls by name is a denylist that misses equivalent calls, so
+//! profiles that can should list the system calls they allow instead.
+//!
+//! The sandbox fails closed: on kernels that can't fully enforce the
+//! Landlock rules the call is refused, unless the profile opts in to
+//! [`best_effort`](SandboxProfile::best_effort) confinement, which runs it
+//! with what the kernel supports and logs a warning.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[cfg(all(target_os = "linux", feature = "sandbox"))]
+use crate::error::{McpError, McpResult};
+
+/// Error number returned by denied system calls
+#[cfg(all(target_os = "linux", feature = "sandbox"))]
+const EPERM: u32 = 1;
+
+/// Filesystem paths and system calls a tool may use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxProfile {
+    /// Paths the tool may read and execute below
+    pub read_paths: Vec<PathBuf>,
+
+    /// Paths the tool may read and modify below
+    pub write_paths: Vec<PathBuf>,
+
+    /// System calls allowed, by name; when empty every system call not
+    /// denied is allowed
+    pub allowed_syscalls: Vec<String>,
+
+    /// System calls that fail with `EPERM`, by name
+    pub denied_syscalls: Vec<String>,
+
+    /// Run calls when the kernel only partially enforces the profile, or not
+    /// at all, instead of refusing them
+    pub best_effort: bool,
+}
+
+impl SandboxProfile {
+    /// Allow reading below `path`
+    pub fn allow_read(mut self, path: impl Into<PathBuf>) -> Self {
+        self.read_paths.push(path.into());
+        self
+    }
+
+    /// Allow reading and writing below `path`
+    pub fn allow_write(mut self, path: impl Into<PathBuf>) -> Self {
+        self.write_paths.push(path.into());
+        self
+    }
+
+    /// Allow the system call `name`, making every system call not allowed fail
+    pub fn allow_syscall(mut self, name: impl Into<String>) -> Self {
+        self.allowed_syscalls.push(name.into());
+        self
+    }
+
+    /// Make the system call `name` fail
+    pub fn deny_syscall(mut self, name: impl Into<String>) -> Self {
+        self.denied_syscalls.push(name.into());
+        self
+    }
+
+    /// Deny the system calls used to start other programs
+    pub fn deny_exec(self) -> Self {
+        self.deny_syscall("execve").deny_syscall("execveat")
+    }
+
+    /// Run calls with whatever confinement the kernel supports, rather than
+    /// refusing them when the profile can't be fully enforced
+    pub fn best_effort(mut self) -> Self {
+        self.best_effort = true;
+        self
+    }
+
+    /// Confine the calling process
+    ///
+    /// Landlock restricts the calling thread and the threads it starts
+    /// afterwards, so call this before starting any runtime.
+    #[cfg(all(target_os = "linux", feature = "sandbox"))]
+    pub(crate) fn enforce(&self) -> McpResult<()> {
+        use landlock::{
+            path_beneath_rules, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr,
+            RulesetStatus, ABI,
+        };
+
+        let abi = ABI::V5;
+        let sandbox_error = |e: &dyn std::fmt::Display| McpError::Configuration {
+            message: format!("Failed to apply sandbox profile: {}", e),
+        };
+        let status = Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))
+            .and_then(|ruleset| ruleset.create())
+            .and_then(|ruleset| {
+                ruleset.add_rules(path_beneath_rules(
+                    &self.read_paths,
+                    AccessFs::from_read(abi),
+                ))
+            })
+            .and_then(|ruleset| {
+                ruleset.add_rules(path_beneath_rules(
+                    &self.write_paths,
+                    AccessFs::from_all(abi),
+                ))
+            })
+            .and_then(|ruleset| ruleset.restrict_self())
+            .map_err(|e| sandbox_error(&e))?;
+        self.check_landlock(status.ruleset != RulesetStatus::FullyEnforced)?;
+
+        let (syscalls, mismatch_action, match_action) = if self.allowed_syscalls.is_empty() {
+            if self.denied_syscalls.is_empty() {
+                return Ok(());
+            }
+            let denied = self.denied_syscalls.iter().collect::<Vec<_>>();
+            (
+                denied,
+                serde_json::json!("allow"),
+                serde_json::json!({ "errno": EPERM }),
+            )
+        } else {
+            let allowed = self
+                .allowed_syscalls
+                .iter()
+                .filter(|name| !self.denied_syscalls.contains(name))
+                .collect::<Vec<_>>();
+            (
+                allowed,
+                serde_json::json!({ "errno": EPERM }),
+                serde_json::json!("allow"),
+            )
+        };
+        let filter = serde_json::json!({
+            "worker": {
+                "mismatch_action": mismatch_action,
+                "match_action": match_action,
+                "filter": syscalls
+                    .into_iter()
+                    .map(|name| serde_json::json!({ "syscall": name }))
+                    .collect::<Vec<_>>(),
+            }
+        });
+        let arch = std::env::consts::ARCH
+            .try_into()
+            .map_err(|e| sandbox_error(&e))?;
+        let programs = seccompiler::compile_from_json(filter.to_string().as_bytes(), arch)
+            .map_err(|e| sandbox_error(&e))?;
+        seccompiler::apply_filter_all_threads(&programs["worker"]).map_err(|e| sandbox_error(&e))
+    }
+
+    /// Refuse to run when Landlock is not fully enforced, unless the profile
+    /// is best effort
+    #[cfg(all(target_os = "linux", feature = "sandbox"))]
+    fn check_landlock(&self, unenforced: bool) -> McpResult<()> {
+        if !unenforced {
+            return Ok(());
+        }
+        if !self.best_effort {
+            return Err(McpError::Configuration {
+                message: "This kernel can't fully enforce the sandbox profile's Landlock rules"
+                    .to_string(),
+            });
+        }
+        tracing::warn!(
+            "Landlock is not fully supported by this kernel; filesystem access is not fully confined"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux", feature = "sandbox"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unenforced_profiles_fail_closed() {
+        let profile = SandboxProfile::default().allow_read("/srv/docs");
+        assert!(profile.check_landlock(false).is_ok());
+        assert!(matches!(
+            profile.check_landlock(true),
+            Err(McpError::Configuration { .. })
+        ));
+        assert!(profile.best_effort().check_landlock(true).is_ok());
+    }
+}
@@ -0,0 +1,427 @@
+//! Priority scheduling of heavy requests
+//!
+//! `tools/call` and `batch` requests can be expensive, so when scheduling is
+//! enabled at most [`SchedulerConfig::max_concurrent`] of them run at once.
+//! The rest wait in a bounded queue and are started highest priority first,
+//! oldest first within a priority. Everything else, including `ping`,
+//! cancellations and admin methods, bypasses the queue.
+//!
+//! A request's [`RequestPriority`] comes from the client's role (the
+//! [`ROLE_LABEL`] entry of its client metadata) via
+//! [`SchedulerConfig::role_priorities`], or from the method: `batch` is low,
+//! `tools/call` normal, and system contexts are high. A request may lower its
+//! own priority with `_meta.priority`, but not raise it.
+//!
+//! When the queue is full the newest request of the lowest priority is shed:
+//! the incoming one, or a queued one of lower priority than it. Shed requests
+//! fail with [`McpError::RateLimitExceeded`] so clients back off and retry.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// Client metadata key naming the client's role
+pub const ROLE_LABEL: &str = "role";
+
+/// Methods that are scheduled; all others bypass the queue
+pub const SCHEDULED_METHODS: &[&str] = &["tools/call", "batch"];
+
+/// Priority class of a scheduled request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequestPriority {
+    /// Background work, shed first
+    Low,
+    /// Regular calls
+    Normal,
+    /// Interactive or operator calls
+    High,
+}
+
+impl RequestPriority {
+    /// Lowercase name, as used in `_meta.priority`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestPriority::Low => "low",
+            RequestPriority::Normal => "normal",
+            RequestPriority::High => "high",
+        }
+    }
+}
+
+/// Request scheduling configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Whether heavy requests are scheduled; when off they all run at once
+    pub enabled: bool,
+
+    /// Scheduled requests running at the same time
+    pub max_concurrent: usize,
+
+    /// Scheduled requests waiting to run before requests are shed
+    pub max_queued: usize,
+
+    /// Priority of requests from clients with the given role
+    pub role_priorities: HashMap<String, RequestPriority>,
+
+    /// Seconds clients are told to wait before retrying a shed request
+    pub retry_after: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_concurrent: 32,
+            max_queued: 256,
+            role_priorities: HashMap::new(),
+            retry_after: 1,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// Run at most `max_concurrent` heavy requests, queueing up to `max_queued`
+    pub fn enabled(max_concurrent: usize, max_queued: usize) -> Self {
+        Self {
+            enabled: true,
+            max_concurrent,
+            max_queued,
+            ..Default::default()
+        }
+    }
+
+    /// Schedule requests from clients with `role` at `priority`
+    pub fn with_role_priority(
+        mut self,
+        role: impl Into<String>,
+        priority: RequestPriority,
+    ) -> Self {
+        self.role_priorities.insert(role.into(), priority);
+        self
+    }
+}
+
+/// Queue depth and load-shedding counters
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerStats {
+    /// Scheduled requests currently running
+    pub running: usize,
+
+    /// Requests waiting to run
+    pub queued: usize,
+
+    /// Waiting requests per priority
+    pub queued_by_priority: BTreeMap<RequestPriority, usize>,
+
+    /// Requests shed because the queue was full, since startup
+    pub shed: u64,
+}
+
+tokio::task_local! {
+    /// Set while a scheduled request runs, so the items of a batch do not
+    /// queue behind the batch itself
+    static SCHEDULED: ();
+}
+
+/// Waiting requests, ordered so the last entry runs next and the first is
+/// shed first
+type WaitQueue =
+    BTreeMap<(RequestPriority, Reverse<u64>), (Instant, oneshot::Sender<SchedulerPermit>)>;
+
+#[derive(Debug, Default)]
+struct State {
+    running: usize,
+    waiting: WaitQueue,
+    next_seq: u64,
+}
+
+/// Bounded priority queue in front of heavy requests
+#[derive(Debug, Clone)]
+pub struct RequestScheduler {
+    config: SchedulerConfig,
+    state: Arc<Mutex<State>>,
+    shed: Arc<AtomicU64>,
+    clock: Clock,
+}
+
+/// A running scheduled request; dropping it starts the next one
+#[derive(Debug)]
+pub struct SchedulerPermit {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        let next = {
+            let mut state = self.state.lock().unwrap();
+            match state.waiting.pop_last() {
+                Some((_, (_, waiter))) => waiter,
+                None => {
+                    state.running -= 1;
+                    return;
+                }
+            }
+        };
+        // Hand the slot over; if the waiter has gone away the permit is
+        // dropped again and moves on to the next one
+        let _ = next.send(SchedulerPermit {
+            state: Arc::clone(&self.state),
+        });
+    }
+}
+
+impl RequestScheduler {
+    /// Schedule requests as configured
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(Mutex::new(State::default())),
+            shed: Arc::new(AtomicU64::new(0)),
+            clock: Clock::default(),
+        }
+    }
+
+    /// Measure queue latency on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    /// Priority a request is scheduled at, `None` if it bypasses the queue
+    pub fn priority(
+        &self,
+        method: &str,
+        params: Option<&serde_json::Value>,
+        context: &SecurityContext,
+    ) -> Option<RequestPriority> {
+        if !self.config.enabled || !SCHEDULED_METHODS.contains(&method) {
+            return None;
+        }
+
+        let role = context
+            .client
+            .metadata
+            .get(ROLE_LABEL)
+            .and_then(|role| self.config.role_priorities.get(role));
+        let base = match role {
+            Some(priority) => *priority,
+            None if context.is_system() => RequestPriority::High,
+            None if method == "batch" => RequestPriority::Low,
+            None => RequestPriority::Normal,
+        };
+        let requested = params
+            .and_then(|params| params.get("_meta"))
+            .and_then(|meta| meta.get("priority"))
+            .and_then(|priority| serde_json::from_value(priority.clone()).ok());
+        Some(requested.map_or(base, |requested: RequestPriority| requested.min(base)))
+    }
+
+    /// Wait for a slot to run a request of the given priority
+    ///
+    /// Fails if the request was shed because the queue is full.
+    pub async fn acquire(&self, priority: RequestPriority) -> McpResult<SchedulerPermit> {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if state.running < self.config.max_concurrent && state.waiting.is_empty() {
+                state.running += 1;
+                return Ok(self.permit());
+            }
+
+            if state.waiting.len() >= self.config.max_queued {
+                let lowest = state.waiting.first_key_value().map(|(key, _)| key.0);
+                match lowest {
+                    Some(lowest) if lowest < priority => {
+                        // Dropping the sender sheds the queued request
+                        state.waiting.pop_first();
+                    }
+                    _ => {
+                        drop(state);
+                        return Err(self.shed_error(priority));
+                    }
+                }
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state
+                .waiting
+                .insert((priority, Reverse(seq)), (self.clock.now(), sender));
+            receiver
+        };
+
+        receiver.await.map_err(|_| self.shed_error(priority))
+    }
+
+    /// Run `task` once a slot is free
+    ///
+    /// Requests made from within a scheduled request, such as the items of a
+    /// batch, run straight away on the slot of the outer request.
+    pub async fn schedule<F: Future>(
+        &self,
+        priority: RequestPriority,
+        task: F,
+    ) -> McpResult<F::Output> {
+        if SCHEDULED.try_with(|_| ()).is_ok() {
+            return Ok(task.await);
+        }
+        let _permit = self.acquire(priority).await?;
+        Ok(SCHEDULED.scope((), task).await)
+    }
+
+    /// Current queue depth and shed count
+    pub fn stats(&self) -> SchedulerStats {
+        let state = self.state.lock().unwrap();
+        let mut queued_by_priority = BTreeMap::new();
+        for (priority, _) in state.waiting.keys() {
+            *queued_by_priority.entry(*priority).or_insert(0) += 1;
+        }
+        SchedulerStats {
+            running: state.running,
+            queued: state.waiting.len(),
+            queued_by_priority,
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// How long the longest-waiting queued request has waited
+    pub fn queue_latency(&self) -> Duration {
+        let now = self.clock.now();
+        let state = self.state.lock().unwrap();
+        state
+            .waiting
+            .values()
+            .map(|(queued_at, _)| now.saturating_duration_since(*queued_at))
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn permit(&self) -> SchedulerPermit {
+        SchedulerPermit {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    fn shed_error(&self, priority: RequestPriority) -> McpError {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+        debug!("Shedding {} priority request", priority.as_str());
+        McpError::RateLimitExceeded {
+            message: "Server is overloaded".to_string(),
+            retry_after: Some(self.config.retry_after),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_priority() {
+        let scheduler = RequestScheduler::new(
+            SchedulerConfig::enabled(1, 1).with_role_priority("batch-worker", RequestPriority::Low),
+        );
+        let user = SecurityContext::anonymous();
+        let mut worker = SecurityContext::anonymous();
+        worker
+            .client
+            .metadata
+            .insert(ROLE_LABEL.to_string(), "batch-worker".to_string());
+
+        assert_eq!(scheduler.priority("ping", None, &user), None);
+        assert_eq!(
+            scheduler.priority("tools/call", None, &user),
+            Some(RequestPriority::Normal)
+        );
+        assert_eq!(
+            scheduler.priority("tools/call", None, &SecurityContext::system()),
+            Some(RequestPriority::High)
+        );
+        assert_eq!(
+            scheduler.priority("batch", None, &user),
+            Some(RequestPriority::Low)
+        );
+        assert_eq!(
+            scheduler.priority("tools/call", None, &worker),
+            Some(RequestPriority::Low)
+        );
+
+        // `_meta.priority` can lower but not raise the priority
+        let low = serde_json::json!({"_meta": {"priority": "low"}});
+        let high = serde_json::json!({"_meta": {"priority": "high"}});
+        assert_eq!(
+            scheduler.priority("tools/call", Some(&low), &user),
+            Some(RequestPriority::Low)
+        );
+        assert_eq!(
+            scheduler.priority("tools/call", Some(&high), &user),
+            Some(RequestPriority::Normal)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_queues_and_sheds() {
+        let scheduler = RequestScheduler::new(SchedulerConfig::enabled(1, 2));
+        let running = scheduler.acquire(RequestPriority::Normal).await.unwrap();
+
+        let low = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(RequestPriority::Low).await.map(drop) }
+        });
+        let normal = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(RequestPriority::Normal).await }
+        });
+        tokio::task::yield_now().await;
+        while scheduler.stats().queued < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        // A full queue sheds the incoming request unless a lower one is queued
+        assert!(scheduler.acquire(RequestPriority::Low).await.is_err());
+        let high = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(RequestPriority::High).await }
+        });
+        assert!(low.await.unwrap().is_err());
+        while scheduler.stats().queued < 2 {
+            tokio::task::yield_now().await;
+        }
+        let stats = scheduler.stats();
+        assert_eq!(stats.running, 1);
+        assert_eq!(stats.shed, 2);
+        assert_eq!(stats.queued_by_priority[&RequestPriority::High], 1);
+
+        // Slots go to the highest priority first
+        drop(running);
+        let high = high.await.unwrap().unwrap();
+        assert_eq!(scheduler.stats().queued, 1);
+        drop(high);
+        drop(normal.await.unwrap().unwrap());
+        assert_eq!(scheduler.stats().running, 0);
+    }
+}
@@ -0,0 +1,381 @@
+//! Compatibility checks for tool input schemas
+//!
+//! [`check_schema`] compares a tool's new input schema with the previous one
+//! and lists the differences a caller could notice. A change is breaking when
+//! arguments that were valid before may now be rejected: a new required field,
+//! a narrowed type or enum, or a removed property on a closed object. Dropping
+//! a requirement or adding an optional property is compatible.
+//!
+//! [`InMemoryToolRegistry::try_register_tool`](crate::server::InMemoryToolRegistry::try_register_tool)
+//! runs the check against the registered tool, or the tool in a baseline
+//! [`CapabilitySnapshot`], and rejects breaking updates.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeSet, fmt};
+
+use crate::server::snapshot::CapabilitySnapshot;
+
+/// A difference between two input schemas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum SchemaChange {
+    /// A field became required
+    RequiredAdded { field: String },
+    /// A field is no longer required
+    RequiredRemoved { field: String },
+    /// An optional property was added
+    PropertyAdded { field: String },
+    /// A property was removed; breaking when the object is closed
+    PropertyRemoved { field: String, closed: bool },
+    /// The accepted types no longer include all previously accepted ones
+    TypeNarrowed { field: String, removed: Vec<String> },
+    /// The accepted types grew
+    TypeWidened { field: String, added: Vec<String> },
+    /// Enum values were removed, or an enum was introduced
+    EnumNarrowed { field: String, removed: Vec<Value> },
+    /// Enum values were added, or the enum was dropped
+    EnumWidened { field: String },
+    /// The object stopped accepting unknown properties
+    AdditionalPropertiesClosed { field: String },
+}
+
+impl SchemaChange {
+    /// Whether arguments valid under the old schema may be rejected
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            Self::RequiredAdded { .. }
+            | Self::TypeNarrowed { .. }
+            | Self::EnumNarrowed { .. }
+            | Self::AdditionalPropertiesClosed { .. } => true,
+            Self::PropertyRemoved { closed, .. } => *closed,
+            Self::RequiredRemoved { .. }
+            | Self::PropertyAdded { .. }
+            | Self::TypeWidened { .. }
+            | Self::EnumWidened { .. } => false,
+        }
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RequiredAdded { field } => write!(f, "`{}` became required", field),
+            Self::RequiredRemoved { field } => write!(f, "`{}` is no longer required", field),
+            Self::PropertyAdded { field } => write!(f, "`{}` was added", field),
+            Self::PropertyRemoved { field, .. } => write!(f, "`{}` was removed", field),
+            Self::TypeNarrowed { field, removed } => {
+                write!(f, "`{}` no longer accepts {}", field, removed.join(", "))
+            }
+            Self::TypeWidened { field, added } => {
+                write!(f, "`{}` also accepts {}", field, added.join(", "))
+            }
+            Self::EnumNarrowed { field, removed } if removed.is_empty() => {
+                write!(f, "`{}` is now restricted to an enum", field)
+            }
+            Self::EnumNarrowed { field, removed } => {
+                let removed: Vec<String> = removed.iter().map(Value::to_string).collect();
+                write!(f, "`{}` no longer accepts {}", field, removed.join(", "))
+            }
+            Self::EnumWidened { field } => write!(f, "`{}` accepts more values", field),
+            Self::AdditionalPropertiesClosed { field } => {
+                write!(f, "`{}` no longer accepts unknown properties", field)
+            }
+        }
+    }
+}
+
+/// Result of comparing two input schemas
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemaCompatibility {
+    /// All differences, breaking or not
+    pub changes: Vec<SchemaChange>,
+}
+
+impl SchemaCompatibility {
+    /// Whether any change may reject previously valid arguments
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(SchemaChange::is_breaking)
+    }
+
+    /// Changes that may reject previously valid arguments
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SchemaChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+/// Compare a tool's previous input schema with its new one
+pub fn check_schema(previous: &Value, new: &Value) -> SchemaCompatibility {
+    let mut changes = Vec::new();
+    compare(previous, new, "", &mut changes);
+    SchemaCompatibility { changes }
+}
+
+impl CapabilitySnapshot {
+    /// Compare a tool's new input schema with the one in this snapshot
+    ///
+    /// Tools missing from the snapshot are new and always compatible.
+    pub fn check_tool(&self, name: &str, input_schema: &Value) -> SchemaCompatibility {
+        self.tools
+            .get(name)
+            .map(|previous| check_schema(&previous.input_schema, input_schema))
+            .unwrap_or_default()
+    }
+}
+
+fn compare(previous: &Value, new: &Value, path: &str, changes: &mut Vec<SchemaChange>) {
+    let label = if path.is_empty() { "$" } else { path };
+
+    compare_types(previous, new, label, changes);
+    compare_enums(previous, new, label, changes);
+
+    let previous_required = string_set(previous.get("required"));
+    let new_required = string_set(new.get("required"));
+    for field in new_required.difference(&previous_required) {
+        changes.push(SchemaChange::RequiredAdded {
+            field: join(path, field),
+        });
+    }
+    for field in previous_required.difference(&new_required) {
+        changes.push(SchemaChange::RequiredRemoved {
+            field: join(path, field),
+        });
+    }
+
+    let closed = new.get("additionalProperties") == Some(&Value::Bool(false));
+    if closed && previous.get("additionalProperties") != Some(&Value::Bool(false)) {
+        changes.push(SchemaChange::AdditionalPropertiesClosed {
+            field: label.to_string(),
+        });
+    }
+
+    let empty = serde_json::Map::new();
+    let previous_properties = previous
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    let new_properties = new
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    for (name, previous_property) in previous_properties {
+        match new_properties.get(name) {
+            Some(new_property) => {
+                compare(previous_property, new_property, &join(path, name), changes)
+            }
+            None => changes.push(SchemaChange::PropertyRemoved {
+                field: join(path, name),
+                closed,
+            }),
+        }
+    }
+    for name in new_properties.keys() {
+        if !previous_properties.contains_key(name) && !new_required.contains(name) {
+            changes.push(SchemaChange::PropertyAdded {
+                field: join(path, name),
+            });
+        }
+    }
+
+    if let (Some(previous_items), Some(new_items)) = (previous.get("items"), new.get("items")) {
+        compare(previous_items, new_items, &format!("{}[]", label), changes);
+    }
+}
+
+fn compare_types(previous: &Value, new: &Value, field: &str, changes: &mut Vec<SchemaChange>) {
+    // A missing `type` accepts anything
+    let (previous_types, new_types) = match (types(previous), types(new)) {
+        (Some(previous_types), Some(new_types)) => (previous_types, new_types),
+        (None, Some(new_types)) => {
+            changes.push(SchemaChange::TypeNarrowed {
+                field: field.to_string(),
+                removed: vec![format!("types other than {}", join_set(&new_types))],
+            });
+            return;
+        }
+        _ => return,
+    };
+
+    // Integers remain acceptable where numbers are
+    let accepts = |types: &BTreeSet<String>, ty: &str| {
+        types.contains(ty) || (ty == "integer" && types.contains("number"))
+    };
+    let removed: Vec<String> = previous_types
+        .iter()
+        .filter(|ty| !accepts(&new_types, ty))
+        .cloned()
+        .collect();
+    let added: Vec<String> = new_types
+        .iter()
+        .filter(|ty| !accepts(&previous_types, ty))
+        .cloned()
+        .collect();
+    if !removed.is_empty() {
+        changes.push(SchemaChange::TypeNarrowed {
+            field: field.to_string(),
+            removed,
+        });
+    }
+    if !added.is_empty() {
+        changes.push(SchemaChange::TypeWidened {
+            field: field.to_string(),
+            added,
+        });
+    }
+}
+
+fn compare_enums(previous: &Value, new: &Value, field: &str, changes: &mut Vec<SchemaChange>) {
+    let previous_values = previous.get("enum").and_then(Value::as_array);
+    let new_values = new.get("enum").and_then(Value::as_array);
+    match (previous_values, new_values) {
+        (None, Some(_)) => changes.push(SchemaChange::EnumNarrowed {
+            field: field.to_string(),
+            removed: Vec::new(),
+        }),
+        (Some(_), None) => changes.push(SchemaChange::EnumWidened {
+            field: field.to_string(),
+        }),
+        (Some(previous_values), Some(new_values)) => {
+            let removed: Vec<Value> = previous_values
+                .iter()
+                .filter(|value| !new_values.contains(value))
+                .cloned()
+                .collect();
+            if !removed.is_empty() {
+                changes.push(SchemaChange::EnumNarrowed {
+                    field: field.to_string(),
+                    removed,
+                });
+            } else if new_values.len() > previous_values.len() {
+                changes.push(SchemaChange::EnumWidened {
+                    field: field.to_string(),
+                });
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+fn types(schema: &Value) -> Option<BTreeSet<String>> {
+    match schema.get("type")? {
+        Value::String(ty) => Some(BTreeSet::from([ty.clone()])),
+        Value::Array(_) => Some(string_set(schema.get("type"))),
+        _ => None,
+    }
+}
+
+fn string_set(value: Option<&Value>) -> BTreeSet<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn join(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+fn join_set(values: &BTreeSet<String>) -> String {
+    values.iter().cloned().collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_required_fields() {
+        let previous = json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}, "limit": {"type": "integer"}},
+            "required": ["query", "limit"]
+        });
+
+        let relaxed = json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}, "limit": {"type": "integer"}},
+            "required": ["query"]
+        });
+        let compatibility = check_schema(&previous, &relaxed);
+        assert_eq!(
+            compatibility.changes,
+            [SchemaChange::RequiredRemoved {
+                field: "limit".into()
+            }]
+        );
+        assert!(!compatibility.is_breaking());
+
+        let stricter = json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer"},
+                "filter": {"type": "object", "properties": {"tag": {"type": "string"}}}
+            },
+            "required": ["query", "limit", "filter"]
+        });
+        let compatibility = check_schema(&previous, &stricter);
+        assert_eq!(
+            compatibility.breaking_changes().collect::<Vec<_>>(),
+            [&SchemaChange::RequiredAdded {
+                field: "filter".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_nested_types_and_enums() {
+        let previous = json!({
+            "type": "object",
+            "properties": {
+                "options": {
+                    "type": "object",
+                    "properties": {
+                        "mode": {"type": "string", "enum": ["fast", "full"]},
+                        "depth": {"type": "integer"}
+                    }
+                },
+                "tags": {"type": "array", "items": {"type": ["string", "number"]}}
+            }
+        });
+        let new = json!({
+            "type": "object",
+            "properties": {
+                "options": {
+                    "type": "object",
+                    "properties": {
+                        "mode": {"type": "string", "enum": ["full", "smart"]},
+                        "depth": {"type": "number"}
+                    },
+                    "additionalProperties": false
+                },
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        });
+
+        let breaking: Vec<String> = check_schema(&previous, &new)
+            .breaking_changes()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            breaking,
+            [
+                "`options` no longer accepts unknown properties",
+                "`options.mode` no longer accepts \"fast\"",
+                "`tags[]` no longer accepts number",
+            ]
+        );
+        assert!(check_schema(&new, &new).changes.is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+//! Scrubbing personal data from tool arguments and results
+//!
+//! A [`Scrubber`] replaces matches of regular expressions, such as the
+//! built-in patterns for email addresses, API keys and credit card numbers,
+//! with [`ScrubConfig::replacement`] in any string. JSONPath rules such as
+//! `$.customer.ssn` or `$..phone` replace whole values of tool arguments and
+//! of JSON tool results.
+//!
+//! When [`ScrubConfig::enabled`] is set, the server scrubs:
+//!
+//! - failure messages kept by the execution audit trail and sent in webhook
+//!   events,
+//! - exchanges captured by the request inspector,
+//! - with [`ScrubConfig::results`], tool results before they are sent, except
+//!   for tools listed in [`ScrubConfig::exempt_tools`].
+//!
+//! Applications exporting metrics can pass labels through
+//! [`Scrubber::scrub_str`] too.
+//!
+//! Supported JSONPath syntax is `$`, `.name`, `['name']`, `[N]`, `.*`, `[*]`
+//! and `..name`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{borrow::Cow, collections::BTreeSet, sync::Arc};
+use tracing::warn;
+
+use crate::protocol::{ToolContent, ToolsCallResult};
+
+/// Built-in patterns for common personal data and credentials
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinPattern {
+    /// Email addresses
+    Email,
+    /// API keys and bearer tokens in well-known formats
+    ApiKey,
+    /// Card numbers of 13 to 19 digits passing the Luhn check
+    CreditCard,
+}
+
+impl BuiltinPattern {
+    fn regex(self) -> &'static str {
+        match self {
+            BuiltinPattern::Email => r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+            BuiltinPattern::ApiKey => concat!(
+                r"\b(?:sk|pk|rk)[-_](?:live[-_]|test[-_])?[A-Za-z0-9]{16,}",
+                r"|\bAKIA[0-9A-Z]{16}\b",
+                r"|\bgh[pousr]_[A-Za-z0-9]{36,}",
+                r"|\bxox[abprs]-[A-Za-z0-9-]{10,}",
+                r"|(?i:bearer)\s+[A-Za-z0-9._~+/-]{16,}=*",
+            ),
+            BuiltinPattern::CreditCard => r"\b\d(?:[ -]?\d){12,18}\b",
+        }
+    }
+}
+
+/// Scrubbing configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrubConfig {
+    /// Whether audit records, inspector captures and webhook events are scrubbed
+    pub enabled: bool,
+
+    /// Built-in patterns to apply
+    pub builtin: Vec<BuiltinPattern>,
+
+    /// Additional regular expressions
+    pub patterns: Vec<String>,
+
+    /// JSONPath expressions whose values are replaced whole
+    pub paths: Vec<String>,
+
+    /// Whether tool results are scrubbed before they are sent to clients
+    pub results: bool,
+
+    /// Tools whose results are sent unscrubbed
+    pub exempt_tools: BTreeSet<String>,
+
+    /// Text that replaces scrubbed data
+    pub replacement: String,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            builtin: vec![
+                BuiltinPattern::Email,
+                BuiltinPattern::ApiKey,
+                BuiltinPattern::CreditCard,
+            ],
+            patterns: Vec::new(),
+            paths: Vec::new(),
+            results: false,
+            exempt_tools: BTreeSet::new(),
+            replacement: "[REDACTED]".to_string(),
+        }
+    }
+}
+
+impl ScrubConfig {
+    /// Scrub with the built-in patterns
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Also replace matches of `pattern`
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Also replace values at the JSONPath `path`
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Scrub tool results too
+    pub fn with_results(mut self) -> Self {
+        self.results = true;
+        self
+    }
+
+    /// Send the results of `tool` unscrubbed
+    pub fn exempt_tool(mut self, tool: impl Into<String>) -> Self {
+        self.exempt_tools.insert(tool.into());
+        self
+    }
+
+    /// Patterns and paths that do not parse, with the reason
+    pub fn invalid_rules(&self) -> Vec<(String, String)> {
+        let patterns = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).err().map(|e| (pattern, e.to_string())));
+        let paths = self
+            .paths
+            .iter()
+            .filter_map(|path| parse_path(path).err().map(|e| (path, e)));
+        patterns
+            .chain(paths)
+            .map(|(rule, reason)| (rule.clone(), reason))
+            .collect()
+    }
+}
+
+/// One step of a JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Child(String),
+    Index(usize),
+    Wildcard,
+    Descendant(String),
+}
+
+/// Compiled scrubbing rules
+///
+/// Cloning is cheap; clones share the compiled rules.
+#[derive(Debug, Clone, Default)]
+pub struct Scrubber {
+    config: Arc<ScrubConfig>,
+    patterns: Arc<Vec<(Regex, bool)>>,
+    paths: Arc<Vec<Vec<Segment>>>,
+}
+
+impl Scrubber {
+    /// Compile the rules of `config`, skipping invalid ones with a warning
+    pub fn new(config: ScrubConfig) -> Self {
+        let mut patterns = Vec::new();
+        let builtin = config
+            .builtin
+            .iter()
+            .map(|pattern| (pattern.regex(), *pattern == BuiltinPattern::CreditCard));
+        let custom = config
+            .patterns
+            .iter()
+            .map(|pattern| (pattern.as_str(), false));
+        for (pattern, luhn) in builtin.chain(custom) {
+            match Regex::new(pattern) {
+                Ok(regex) => patterns.push((regex, luhn)),
+                Err(e) => warn!("Skipping invalid scrub pattern '{}': {}", pattern, e),
+            }
+        }
+        let paths = config
+            .paths
+            .iter()
+            .filter_map(|path| match parse_path(path) {
+                Ok(segments) => Some(segments),
+                Err(e) => {
+                    warn!("Skipping invalid scrub path '{}': {}", path, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            config: Arc::new(config),
+            patterns: Arc::new(patterns),
+            paths: Arc::new(paths),
+        }
+    }
+
+    /// Whether scrubbing is switched on
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Whether the results of `tool` are scrubbed
+    pub fn scrubs_results_of(&self, tool: &str) -> bool {
+        self.config.enabled && self.config.results && !self.config.exempt_tools.contains(tool)
+    }
+
+    /// `text` with pattern matches replaced
+    pub fn scrub_str<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for (regex, luhn) in self.patterns.iter() {
+            if !regex.is_match(&text) {
+                continue;
+            }
+            let replaced = regex.replace_all(&text, |captures: &regex::Captures| {
+                let found = &captures[0];
+                if *luhn && !passes_luhn(found) {
+                    found.to_string()
+                } else {
+                    self.config.replacement.clone()
+                }
+            });
+            text = Cow::Owned(replaced.into_owned());
+        }
+        text
+    }
+
+    /// Replace values at the configured paths, then pattern matches in all
+    /// strings of `value`
+    pub fn scrub_value(&self, value: &mut Value) {
+        for path in self.paths.iter() {
+            replace_at(value, path, &self.config.replacement);
+        }
+        self.scrub_strings(value);
+    }
+
+    /// Replace pattern matches in all strings and object keys of `value`
+    pub fn scrub_strings(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => {
+                if let Cow::Owned(scrubbed) = self.scrub_str(text) {
+                    *text = scrubbed;
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.scrub_strings(item)),
+            Value::Object(map) => {
+                let entries = std::mem::take(map);
+                for (key, mut item) in entries {
+                    self.scrub_strings(&mut item);
+                    map.insert(self.scrub_str(&key).into_owned(), item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Scrub the text content and metadata of a tool result
+    ///
+    /// Text holding a JSON object or array is scrubbed like
+    /// [`scrub_value`](Self::scrub_value) and re-serialized.
+    pub fn scrub_tool_result(&self, result: &mut ToolsCallResult) {
+        for content in &mut result.content {
+            match content {
+                ToolContent::Text { text }
+                | ToolContent::Resource {
+                    text: Some(text), ..
+                } => *text = self.scrub_text(text),
+                _ => {}
+            }
+        }
+        for value in result.metadata.values_mut() {
+            self.scrub_value(value);
+        }
+    }
+
+    fn scrub_text(&self, text: &str) -> String {
+        if !self.paths.is_empty() {
+            if let Ok(mut value @ (Value::Object(_) | Value::Array(_))) =
+                serde_json::from_str::<Value>(text)
+            {
+                self.scrub_value(&mut value);
+                return value.to_string();
+            }
+        }
+        self.scrub_str(text).into_owned()
+    }
+}
+
+/// Whether the digits of `candidate` pass the Luhn checksum
+fn passes_luhn(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Parse the supported JSONPath subset
+fn parse_path(path: &str) -> Result<Vec<Segment>, String> {
+    let rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| "must start with '$'".to_string())?;
+    let mut segments = Vec::new();
+    let mut chars = rest;
+    while !chars.is_empty() {
+        if let Some(after) = chars.strip_prefix("..") {
+            let (name, remaining) = split_name(after);
+            if name.is_empty() {
+                return Err("'..' must be followed by a name".to_string());
+            }
+            segments.push(Segment::Descendant(name.to_string()));
+            chars = remaining;
+        } else if let Some(after) = chars.strip_prefix('.') {
+            let (name, remaining) = split_name(after);
+            segments.push(match name {
+                "" => return Err("'.' must be followed by a name or '*'".to_string()),
+                "*" => Segment::Wildcard,
+                name => Segment::Child(name.to_string()),
+            });
+            chars = remaining;
+        } else if let Some(after) = chars.strip_prefix('[') {
+            let (inner, remaining) = after
+                .split_once(']')
+                .ok_or_else(|| "unclosed '['".to_string())?;
+            let quoted = inner
+                .strip_prefix('\'')
+                .and_then(|inner| inner.strip_suffix('\''));
+            segments.push(match (inner, quoted) {
+                (_, Some(name)) => Segment::Child(name.to_string()),
+                ("*", None) => Segment::Wildcard,
+                (index, None) => Segment::Index(
+                    index
+                        .parse()
+                        .map_err(|_| format!("invalid index '{}'", index))?,
+                ),
+            });
+            chars = remaining;
+        } else {
+            return Err(format!("unexpected '{}'", chars));
+        }
+    }
+    Ok(segments)
+}
+
+/// Split a member name off the front of `path`
+fn split_name(path: &str) -> (&str, &str) {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    path.split_at(end)
+}
+
+/// Replace the values of `value` at `path` with `replacement`
+fn replace_at(value: &mut Value, path: &[Segment], replacement: &str) {
+    let Some((segment, rest)) = path.split_first() else {
+        *value = Value::String(replacement.to_string());
+        return;
+    };
+    match (segment, value) {
+        (Segment::Child(name), Value::Object(map)) => {
+            if let Some(child) = map.get_mut(name) {
+                replace_at(child, rest, replacement);
+            }
+        }
+        (Segment::Index(index), Value::Array(items)) => {
+            if let Some(item) = items.get_mut(*index) {
+                replace_at(item, rest, replacement);
+            }
+        }
+        (Segment::Wildcard, Value::Object(map)) => {
+            for child in map.values_mut() {
+                replace_at(child, rest, replacement);
+            }
+        }
+        (Segment::Wildcard, Value::Array(items)) => {
+            for item in items {
+                replace_at(item, rest, replacement);
+            }
+        }
+        (Segment::Descendant(name), Value::Object(map)) => {
+            for (key, child) in map.iter_mut() {
+                if key == name {
+                    replace_at(child, rest, replacement);
+                } else {
+                    replace_at(child, path, replacement);
+                }
+            }
+        }
+        (Segment::Descendant(_), Value::Array(items)) => {
+            for item in items {
+                replace_at(item, path, replacement);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrubbing_patterns_and_paths() {
+        let scrubber = Scrubber::new(
+            ScrubConfig::enabled()
+                .with_pattern(r"\b\d{3}-\d{2}-\d{4}\b")
+                .with_path("$.customer.phone")
+                .with_path("$..iban")
+                .with_path("$.orders[*].note"),
+        );
+
+        assert_eq!(
+            scrubber
+                .scrub_str("Mail ada@example.com, card 4111 1111 1111 1111, order 1234567890123"),
+            "Mail [REDACTED], card [REDACTED], order 1234567890123"
+        );
+        assert_eq!(
+            scrubber.scrub_str("key sk-live-abcdefghijklmnopqrstu and SSN 078-05-1120"),
+            "key [REDACTED] and SSN [REDACTED]"
+        );
+
+        let mut value = serde_json::json!({
+            "customer": {"phone": "+31 6 1234 5678", "name": "Ada"},
+            "payment": {"accounts": [{"iban": "NL91ABNA0417164300"}]},
+            "orders": [{"note": "leave at door"}, {"note": "call first"}],
+            "contact": "ada@example.com",
+        });
+        scrubber.scrub_value(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "customer": {"phone": "[REDACTED]", "name": "Ada"},
+                "payment": {"accounts": [{"iban": "[REDACTED]"}]},
+                "orders": [{"note": "[REDACTED]"}, {"note": "[REDACTED]"}],
+                "contact": "[REDACTED]",
+            })
+        );
+
+        let config = ScrubConfig::enabled()
+            .with_pattern("(unclosed")
+            .with_path("customer.phone");
+        assert_eq!(config.invalid_rules().len(), 2);
+    }
+}
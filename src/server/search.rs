@@ -0,0 +1,358 @@
+//! Full-text search across resources
+//!
+//! The `resources/search` method ranks indexed text resources against a query
+//! and returns their URIs with a snippet around the first match. Resources are
+//! added with [`McpServer::index_resource`](crate::server::McpServer::index_resource)
+//! or [`McpServer::index_resources`](crate::server::McpServer::index_resources)
+//! and kept up to date from
+//! [`McpServer::resource_changed`](crate::server::McpServer::resource_changed):
+//! deleted resources leave the index at once, changed ones are re-read before
+//! the next search.
+//!
+//! ```json
+//! {"method": "resources/search", "params": {"query": "rate limit", "scheme": "docs", "limit": 5}}
+//! ```
+//!
+//! [`InMemorySearchIndex`] ranks with BM25 and matches query terms as word
+//! prefixes; other engines plug in through [`SearchIndex`].
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, RwLock},
+};
+
+/// Searches indexed resources
+pub const RESOURCES_SEARCH: &str = "resources/search";
+
+/// Hits returned when the request sets no limit
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// Characters of context kept on either side of a snippet's first match
+const SNIPPET_CONTEXT: usize = 80;
+
+/// BM25 term frequency saturation
+const BM25_K1: f64 = 1.2;
+
+/// BM25 document length normalization
+const BM25_B: f64 = 0.75;
+
+/// Params of the `resources/search` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesSearchParams {
+    /// Words to search for
+    pub query: String,
+
+    /// Only search resources with this URI scheme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+
+    /// Maximum number of hits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Result of the `resources/search` method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesSearchResult {
+    /// Hits, best first
+    pub results: Vec<SearchHit>,
+}
+
+/// A resource matching a search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Matching resource
+    pub uri: String,
+
+    /// Relevance, higher is better
+    pub score: f64,
+
+    /// Text around the first match
+    pub snippet: String,
+}
+
+/// Index of resource text
+pub trait SearchIndex: Send + Sync {
+    /// Add a resource, replacing any text indexed for it before
+    fn index(&self, uri: &str, text: &str);
+
+    /// Drop a resource from the index
+    fn remove(&self, uri: &str);
+
+    /// Hits for a search, best first
+    fn search(&self, params: &ResourcesSearchParams) -> Vec<SearchHit>;
+}
+
+struct IndexedDocument {
+    text: String,
+    terms: HashMap<String, usize>,
+    length: usize,
+}
+
+/// In-memory [`SearchIndex`] ranking with BM25
+#[derive(Default)]
+pub struct InMemorySearchIndex {
+    documents: RwLock<HashMap<String, IndexedDocument>>,
+}
+
+impl InMemorySearchIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of indexed resources
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether no resources are indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, HashMap<String, IndexedDocument>> {
+        self.documents
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, HashMap<String, IndexedDocument>> {
+        self.documents
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SearchIndex for InMemorySearchIndex {
+    fn index(&self, uri: &str, text: &str) {
+        let mut terms = HashMap::new();
+        let mut length = 0;
+        for (_, word) in words(text) {
+            *terms.entry(word.to_lowercase()).or_insert(0) += 1;
+            length += 1;
+        }
+        self.write().insert(
+            uri.to_string(),
+            IndexedDocument {
+                text: text.to_string(),
+                terms,
+                length,
+            },
+        );
+    }
+
+    fn remove(&self, uri: &str) {
+        self.write().remove(uri);
+    }
+
+    fn search(&self, params: &ResourcesSearchParams) -> Vec<SearchHit> {
+        let query: Vec<String> = words(&params.query)
+            .map(|(_, word)| word.to_lowercase())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let documents = self.read();
+        let prefix = params.scheme.as_ref().map(|scheme| format!("{}:", scheme));
+        let candidates: Vec<(&String, &IndexedDocument)> = documents
+            .iter()
+            .filter(|(uri, _)| prefix.as_ref().is_none_or(|p| uri.starts_with(p)))
+            .collect();
+        let total = candidates.len() as f64;
+        let average_length =
+            candidates.iter().map(|(_, doc)| doc.length).sum::<usize>() as f64 / total.max(1.0);
+
+        // Occurrences of each query term in each candidate
+        let frequencies: Vec<Vec<usize>> = candidates
+            .iter()
+            .map(|(_, doc)| {
+                query
+                    .iter()
+                    .map(|term| {
+                        doc.terms
+                            .iter()
+                            .filter(|(word, _)| word.starts_with(term.as_str()))
+                            .map(|(_, count)| count)
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+        let idf: Vec<f64> = (0..query.len())
+            .map(|term| {
+                let matching = frequencies.iter().filter(|f| f[term] > 0).count() as f64;
+                (1.0 + (total - matching + 0.5) / (matching + 0.5)).ln()
+            })
+            .collect();
+
+        let mut hits: Vec<SearchHit> = candidates
+            .iter()
+            .zip(&frequencies)
+            .filter(|(_, frequencies)| frequencies.iter().any(|&f| f > 0))
+            .map(|((uri, doc), frequencies)| {
+                let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc.length as f64 / average_length);
+                let score = frequencies
+                    .iter()
+                    .zip(&idf)
+                    .map(|(&f, idf)| {
+                        let f = f as f64;
+                        idf * f * (BM25_K1 + 1.0) / (f + norm)
+                    })
+                    .sum();
+                SearchHit {
+                    uri: uri.to_string(),
+                    score,
+                    snippet: snippet(&doc.text, &query),
+                }
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.uri.cmp(&b.uri)));
+        hits.truncate(params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT));
+        hits
+    }
+}
+
+/// A search index and the resources to re-read before the next search
+#[derive(Clone)]
+pub struct ResourceSearch {
+    index: Arc<dyn SearchIndex>,
+    stale: Arc<Mutex<HashSet<String>>>,
+}
+
+impl ResourceSearch {
+    /// Search through the given index
+    pub fn new(index: Arc<dyn SearchIndex>) -> Self {
+        Self {
+            index,
+            stale: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// The underlying index
+    pub fn index(&self) -> &dyn SearchIndex {
+        self.index.as_ref()
+    }
+
+    /// Re-read a resource before the next search
+    pub fn mark_stale(&self, uri: &str) {
+        self.lock().insert(uri.to_string());
+    }
+
+    /// Drop a resource from the index
+    pub fn remove(&self, uri: &str) {
+        self.lock().remove(uri);
+        self.index.remove(uri);
+    }
+
+    /// Resources changed since the last call
+    pub fn take_stale(&self) -> Vec<String> {
+        self.lock().drain().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.stale
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Alphanumeric words of `text` with their byte offsets
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(move |word| (word.as_ptr() as usize - text.as_ptr() as usize, word))
+}
+
+/// Text around the first word matching a query term, on one line
+fn snippet(text: &str, query: &[String]) -> String {
+    let position = words(text)
+        .find(|(_, word)| {
+            let word = word.to_lowercase();
+            query.iter().any(|term| word.starts_with(term.as_str()))
+        })
+        .map_or(0, |(position, _)| position);
+
+    let start = text[..position]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(0, |(index, _)| index);
+    let end = text[position..]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(text.len(), |(index, _)| position + index);
+
+    let mut snippet = text[start..end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(index: &InMemorySearchIndex, query: &str, scheme: Option<&str>) -> Vec<SearchHit> {
+        index.search(&ResourcesSearchParams {
+            query: query.to_string(),
+            scheme: scheme.map(str::to_string),
+            limit: None,
+        })
+    }
+
+    #[test]
+    fn test_in_memory_search_index() {
+        let index = InMemorySearchIndex::new();
+        index.index(
+            "docs://limits.md",
+            "Rate limits apply per client. Limited clients get 429.",
+        );
+        index.index(
+            "docs://auth.md",
+            "Clients authenticate with a bearer token.",
+        );
+        index.index(
+            "notes://limits.txt",
+            "Ask ops about the rate limit for batch jobs.",
+        );
+
+        let hits = search(&index, "rate LIMIT", None);
+        let uris: Vec<&str> = hits.iter().map(|hit| hit.uri.as_str()).collect();
+        assert_eq!(uris, ["docs://limits.md", "notes://limits.txt"]);
+        assert!(hits[0].score > hits[1].score);
+        assert!(hits[0].snippet.starts_with("Rate limits apply"));
+
+        let hits = search(&index, "limit", Some("notes"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].uri, "notes://limits.txt");
+
+        index.index("docs://limits.md", "Nothing to see here.");
+        index.remove("notes://limits.txt");
+        assert!(search(&index, "limit", None).is_empty());
+        assert_eq!(index.len(), 2);
+    }
+
+    #[test]
+    fn test_snippet() {
+        let text = format!("{} needle {}", "hay ".repeat(50), "stack ".repeat(50));
+        let snippet = snippet(&text, &["needle".to_string()]);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("hay needle stack"));
+        assert!(snippet.chars().count() <= 2 * SNIPPET_CONTEXT + 2);
+    }
+}
@@ -0,0 +1,375 @@
+//! Secrets for tool credentials
+//!
+//! Tools that call HTTP APIs, databases or commands need credentials, which
+//! should not sit in plaintext in configuration files. A [`SecretProvider`]
+//! looks secrets up by name, from environment variables, files such as
+//! mounted Kubernetes secrets or, with the `vault` feature, HashiCorp Vault.
+//! Tools ask for them with
+//! [`ToolExecutionContext::secret`](crate::server::ToolExecutionContext::secret).
+//!
+//! Providers added with
+//! [`McpServer::with_secret_provider`](crate::server::McpServer::with_secret_provider)
+//! are tried in order. Values are cached for [`SecretsConfig::cache_ttl`], and
+//! every lookup is logged on the [`SECRETS_TARGET`] tracing target with the
+//! secret's name, the principal and the request, but never the value.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// Tracing target of secret lookups
+pub const SECRETS_TARGET: &str = "axum_mcp::secrets";
+
+/// A secret value, redacted from `Debug` output
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(Arc<str>);
+
+impl Secret {
+    /// Wrap a secret value
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into().into())
+    }
+
+    /// The secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+/// Source of secrets
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Name of the provider, for logs
+    fn name(&self) -> &str;
+
+    /// The secret `name`, or `None` if this provider does not have it
+    async fn get(&self, name: &str) -> McpResult<Option<Secret>>;
+}
+
+/// Secrets in environment variables
+///
+/// The secret `db-password` is read from `{prefix}DB_PASSWORD`: the name is
+/// upper-cased and other characters than letters and digits become `_`.
+#[derive(Debug, Clone)]
+pub struct EnvSecretProvider {
+    prefix: String,
+}
+
+impl EnvSecretProvider {
+    /// Read secrets from variables starting with `prefix`, such as `MCP_SECRET_`
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Environment variable holding the secret `name`
+    pub fn variable(&self, name: &str) -> String {
+        let name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}{}", self.prefix, name)
+    }
+}
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    fn name(&self) -> &str {
+        "env"
+    }
+
+    async fn get(&self, name: &str) -> McpResult<Option<Secret>> {
+        Ok(std::env::var(self.variable(name)).ok().map(Secret::new))
+    }
+}
+
+/// Secrets in files named after them, such as mounted Docker or Kubernetes
+/// secrets
+///
+/// A trailing newline is removed from the file's content.
+#[derive(Debug, Clone)]
+pub struct FileSecretProvider {
+    dir: PathBuf,
+}
+
+impl FileSecretProvider {
+    /// Read secrets from files in `dir`, such as `/run/secrets`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn get(&self, name: &str) -> McpResult<Option<Secret>> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(McpError::Validation {
+                message: format!("Invalid secret name '{}'", name),
+            });
+        }
+        match tokio::fs::read_to_string(self.dir.join(name)).await {
+            Ok(content) => Ok(Some(Secret::new(content.trim_end_matches(['\r', '\n'])))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Secrets in a HashiCorp Vault KV version 2 engine
+///
+/// The secret `db/postgres#password` is the `password` field of the Vault
+/// secret at `db/postgres`; without `#`, the field is `value`.
+#[cfg(feature = "vault")]
+#[derive(Clone)]
+pub struct VaultSecretProvider {
+    address: String,
+    token: Secret,
+    mount: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "vault")]
+impl VaultSecretProvider {
+    /// Read secrets from the Vault at `address`, such as
+    /// `https://vault.internal:8200`, with `token`
+    pub fn new(address: impl Into<String>, token: Secret) -> Self {
+        Self {
+            address: address.into().trim_end_matches('/').to_string(),
+            token,
+            mount: "secret".to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Read the address and token from `VAULT_ADDR` and `VAULT_TOKEN`
+    pub fn from_env() -> McpResult<Self> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| McpError::Configuration {
+                message: format!("{} is not set", name),
+            })
+        };
+        Ok(Self::new(
+            var("VAULT_ADDR")?,
+            Secret::new(var("VAULT_TOKEN")?),
+        ))
+    }
+
+    /// Use the KV engine mounted at `mount` instead of `secret`
+    pub fn with_mount(mut self, mount: impl Into<String>) -> Self {
+        self.mount = mount.into();
+        self
+    }
+}
+
+#[cfg(feature = "vault")]
+#[async_trait]
+impl SecretProvider for VaultSecretProvider {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    async fn get(&self, name: &str) -> McpResult<Option<Secret>> {
+        let (path, field) = name.split_once('#').unwrap_or((name, "value"));
+        let url = format!("{}/v1/{}/data/{}", self.address, self.mount, path);
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Vault-Token", self.token.expose())
+            .send()
+            .await
+            .map_err(|e| McpError::Network {
+                message: format!("Vault request failed: {}", e),
+            })?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(McpError::Network {
+                message: format!("Vault returned {} for '{}'", response.status(), path),
+            });
+        }
+        let body: serde_json::Value = response.json().await.map_err(|e| McpError::Network {
+            message: format!("Invalid Vault response: {}", e),
+        })?;
+        Ok(body["data"]["data"][field].as_str().map(Secret::new))
+    }
+}
+
+/// Secret caching settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecretsConfig {
+    /// How long looked up secrets are reused; zero disables caching
+    #[serde(with = "humantime_serde")]
+    pub cache_ttl: Duration,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Secret providers of a server, with a shared cache
+///
+/// Cloning is cheap; clones share the providers and cache.
+#[derive(Clone, Default)]
+pub struct Secrets {
+    providers: Arc<Vec<Arc<dyn SecretProvider>>>,
+    cache: Arc<RwLock<HashMap<String, (Secret, Instant)>>>,
+    config: SecretsConfig,
+    clock: Clock,
+}
+
+impl Secrets {
+    /// No providers yet, caching as configured
+    pub fn new(config: SecretsConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Try `provider` after those added before
+    pub fn with_provider(mut self, provider: Arc<dyn SecretProvider>) -> Self {
+        Arc::make_mut(&mut self.providers).push(provider);
+        self
+    }
+
+    /// Expire cached secrets on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether no providers were added
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Look up the secret `name` for a tool call, from the cache or providers
+    pub async fn get(
+        &self,
+        name: &str,
+        context: &SecurityContext,
+        request_id: Option<&str>,
+    ) -> McpResult<Secret> {
+        let principal = context.principal();
+        let now = self.clock.now();
+        if let Some((secret, expires)) = self.cache.read().unwrap().get(name) {
+            if *expires > now {
+                info!(target: SECRETS_TARGET, secret = name, %principal, request_id, "Secret read from cache");
+                return Ok(secret.clone());
+            }
+        }
+
+        for provider in self.providers.iter() {
+            match provider.get(name).await {
+                Ok(Some(secret)) => {
+                    info!(target: SECRETS_TARGET, secret = name, %principal, request_id, provider = provider.name(), "Secret read");
+                    if !self.config.cache_ttl.is_zero() {
+                        self.cache.write().unwrap().insert(
+                            name.to_string(),
+                            (secret.clone(), now + self.config.cache_ttl),
+                        );
+                    }
+                    return Ok(secret);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!(target: SECRETS_TARGET, secret = name, %principal, request_id, provider = provider.name(), "Secret lookup failed: {}", e);
+                    return Err(e);
+                }
+            }
+        }
+        warn!(target: SECRETS_TARGET, secret = name, %principal, request_id, "Secret not found");
+        Err(McpError::Configuration {
+            message: format!("Secret '{}' is not available", name),
+        })
+    }
+
+    /// Drop a cached secret, e.g. after rotating it
+    pub fn invalidate(&self, name: &str) {
+        self.cache.write().unwrap().remove(name);
+    }
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let providers: Vec<&str> = self.providers.iter().map(|p| p.name()).collect();
+        f.debug_struct("Secrets")
+            .field("providers", &providers)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_secrets_from_providers_in_order() {
+        let dir = std::env::temp_dir().join(format!("mcp-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("api-token"), "from-file\n").unwrap();
+        std::fs::write(dir.join("db-password"), "from-file\n").unwrap();
+        std::env::set_var("MCP_TEST_SECRET_DB_PASSWORD", "from-env");
+
+        let clock = Clock::manual();
+        let secrets = Secrets::new(SecretsConfig::default())
+            .with_provider(Arc::new(EnvSecretProvider::new("MCP_TEST_SECRET_")))
+            .with_provider(Arc::new(FileSecretProvider::new(&dir)))
+            .with_clock(clock.clone());
+        let context = SecurityContext::system();
+
+        let secret = secrets.get("db-password", &context, None).await.unwrap();
+        assert_eq!(secret.expose(), "from-env");
+        assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+        let secret = secrets.get("api-token", &context, None).await.unwrap();
+        assert_eq!(secret.expose(), "from-file");
+        assert!(secrets.get("missing", &context, None).await.is_err());
+        assert!(secrets.get("../etc/passwd", &context, None).await.is_err());
+
+        // Cached until the TTL passes
+        std::fs::write(dir.join("api-token"), "rotated").unwrap();
+        let secret = secrets.get("api-token", &context, None).await.unwrap();
+        assert_eq!(secret.expose(), "from-file");
+        clock.advance(Duration::from_secs(301));
+        let secret = secrets.get("api-token", &context, None).await.unwrap();
+        assert_eq!(secret.expose(), "rotated");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,633 @@
+//! Encoding of large integers, non-finite floats and binary data
+//!
+//! MCP clients disagree on what they accept: JavaScript clients round integers
+//! beyond 2^53, some clients expect `NaN` spelled out rather than `null`, and
+//! some want binary content as hex or URL-safe base64. A server's
+//! [`SerializationConfig`] decides these encodings in one place:
+//!
+//! - [`SerializationConfig::apply_to_tool_result`] runs on every `tools/call`
+//!   result. It encodes the integers in the result's metadata (such as
+//!   `structuredContent`) and re-encodes the base64 `data` of images and
+//!   `blob` of embedded resources.
+//! - Tools serialize their own values with [`SerializationConfig::to_value`],
+//!   available as [`ToolExecutionContext::serialization`]. Unlike
+//!   `serde_json::to_value`, it also encodes NaN and infinities, 128-bit
+//!   integers and bytes written with `serialize_bytes`.
+//!
+//! Integers outside the 64-bit range are always written as strings; JSON
+//! numbers cannot hold them exactly.
+//!
+//! [`ToolExecutionContext::serialization`]: crate::server::registry::ToolExecutionContext::serialization
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use serde::{
+    ser::{
+        SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+        SerializeTupleStruct, SerializeTupleVariant,
+    },
+    Deserialize, Serialize, Serializer,
+};
+use serde_json::Value;
+use std::fmt::Write;
+use tracing::warn;
+
+use crate::{
+    error::McpResult,
+    protocol::{ToolContent, ToolsCallResult},
+};
+
+/// Largest integer every JSON client represents exactly, 2^53 - 1
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How integers beyond [`MAX_SAFE_INTEGER`] are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeIntegers {
+    /// As JSON numbers, exact only for clients with 64-bit integers
+    #[default]
+    Number,
+    /// As decimal strings
+    String,
+}
+
+/// How NaN and infinities are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonFiniteFloats {
+    /// As `null`, like serde_json
+    #[default]
+    Null,
+    /// As `"NaN"`, `"Infinity"` and `"-Infinity"`
+    String,
+}
+
+/// How binary data is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryEncoding {
+    /// Standard base64 with padding, as the MCP specification expects
+    #[default]
+    Base64,
+    /// URL-safe base64 without padding
+    Base64Url,
+    /// Lowercase hex
+    Hex,
+}
+
+/// Encodings used for tool results
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SerializationConfig {
+    /// Integers beyond [`MAX_SAFE_INTEGER`]
+    pub large_integers: LargeIntegers,
+
+    /// NaN and infinities
+    pub non_finite_floats: NonFiniteFloats,
+
+    /// Image data, resource blobs and serialized bytes
+    pub binary: BinaryEncoding,
+}
+
+impl SerializationConfig {
+    /// Write integers beyond [`MAX_SAFE_INTEGER`] as `large_integers`
+    pub fn with_large_integers(mut self, large_integers: LargeIntegers) -> Self {
+        self.large_integers = large_integers;
+        self
+    }
+
+    /// Write NaN and infinities as `non_finite_floats`
+    pub fn with_non_finite_floats(mut self, non_finite_floats: NonFiniteFloats) -> Self {
+        self.non_finite_floats = non_finite_floats;
+        self
+    }
+
+    /// Write binary data as `binary`
+    pub fn with_binary(mut self, binary: BinaryEncoding) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Encode `bytes` as configured
+    pub fn encode_binary(&self, bytes: &[u8]) -> String {
+        match self.binary {
+            BinaryEncoding::Base64 => STANDARD.encode(bytes),
+            BinaryEncoding::Base64Url => URL_SAFE_NO_PAD.encode(bytes),
+            BinaryEncoding::Hex => {
+                bytes
+                    .iter()
+                    .fold(String::with_capacity(bytes.len() * 2), |mut hex, byte| {
+                        let _ = write!(hex, "{:02x}", byte);
+                        hex
+                    })
+            }
+        }
+    }
+
+    /// Serialize `value` to JSON with the configured encodings
+    pub fn to_value<T: Serialize + ?Sized>(&self, value: &T) -> McpResult<Value> {
+        Ok(Encoded {
+            value,
+            config: self,
+        }
+        .serialize(serde_json::value::Serializer)?)
+    }
+
+    /// Apply the configured encodings to a tool result
+    ///
+    /// Binary content is expected as standard base64; content that is not is
+    /// left as it is.
+    pub fn apply_to_tool_result(&self, result: &mut ToolsCallResult) {
+        if self.binary != BinaryEncoding::Base64 {
+            for content in &mut result.content {
+                if let ToolContent::Image { data, .. }
+                | ToolContent::Resource {
+                    blob: Some(data), ..
+                } = content
+                {
+                    match STANDARD.decode(data.as_bytes()) {
+                        Ok(bytes) => *data = self.encode_binary(&bytes),
+                        Err(e) => warn!("Leaving binary tool content as is: {}", e),
+                    }
+                }
+            }
+        }
+        if self.large_integers == LargeIntegers::String {
+            result.metadata.values_mut().for_each(encode_large_integers);
+        }
+    }
+
+    /// Whether an integer of `magnitude` is written as a string
+    fn is_string(&self, magnitude: u128) -> bool {
+        self.large_integers == LargeIntegers::String && magnitude > u128::from(MAX_SAFE_INTEGER)
+    }
+}
+
+/// Replace integers beyond [`MAX_SAFE_INTEGER`] in `value` with strings
+fn encode_large_integers(value: &mut Value) {
+    match value {
+        Value::Number(number) => {
+            let magnitude = number
+                .as_u64()
+                .or_else(|| number.as_i64().map(i64::unsigned_abs));
+            if magnitude.is_some_and(|magnitude| magnitude > MAX_SAFE_INTEGER) {
+                *value = Value::String(number.to_string());
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(encode_large_integers),
+        Value::Object(fields) => fields.values_mut().for_each(encode_large_integers),
+        _ => {}
+    }
+}
+
+/// `value`, serialized with the encodings of `config`
+struct Encoded<'a, T: ?Sized> {
+    value: &'a T,
+    config: &'a SerializationConfig,
+}
+
+impl<T: Serialize + ?Sized> Serialize for Encoded<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(Encoder {
+            inner: serializer,
+            config: self.config,
+        })
+    }
+}
+
+/// Serializer applying the encodings of `config` on top of `inner`
+struct Encoder<'a, S> {
+    inner: S,
+    config: &'a SerializationConfig,
+}
+
+impl<'a, S> Encoder<'a, S> {
+    fn encoded<'b, T: ?Sized>(&self, value: &'b T) -> Encoded<'b, T>
+    where
+        'a: 'b,
+    {
+        Encoded {
+            value,
+            config: self.config,
+        }
+    }
+}
+
+/// Sequences, tuples, maps and structs whose elements are encoded
+struct Compound<'a, C> {
+    inner: C,
+    config: &'a SerializationConfig,
+}
+
+impl<'a, C> Compound<'a, C> {
+    fn encoded<'b, T: ?Sized>(&self, value: &'b T) -> Encoded<'b, T>
+    where
+        'a: 'b,
+    {
+        Encoded {
+            value,
+            config: self.config,
+        }
+    }
+}
+
+macro_rules! forward {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(fn $method(self, $($arg: $ty),*) -> Result<S::Ok, S::Error> {
+            self.inner.$method($($arg),*)
+        })*
+    };
+}
+
+impl<'a, S: Serializer> Serializer for Encoder<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'a, S::SerializeSeq>;
+    type SerializeTuple = Compound<'a, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'a, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'a, S::SerializeMap>;
+    type SerializeStruct = Compound<'a, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'a, S::SerializeStructVariant>;
+
+    forward! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<S::Ok, S::Error> {
+        if self.config.is_string(u128::from(v.unsigned_abs())) {
+            return self.inner.collect_str(&v);
+        }
+        self.inner.serialize_i64(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<S::Ok, S::Error> {
+        if self.config.is_string(u128::from(v)) {
+            return self.inner.collect_str(&v);
+        }
+        self.inner.serialize_u64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<S::Ok, S::Error> {
+        match (i64::try_from(v), u64::try_from(v)) {
+            (Ok(v), _) => self.serialize_i64(v),
+            (_, Ok(v)) => self.serialize_u64(v),
+            _ => self.inner.collect_str(&v),
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<S::Ok, S::Error> {
+        match u64::try_from(v) {
+            Ok(v) => self.serialize_u64(v),
+            Err(_) => self.inner.collect_str(&v),
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        if v.is_finite() {
+            return self.inner.serialize_f32(v);
+        }
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        match self.config.non_finite_floats {
+            _ if v.is_finite() => self.inner.serialize_f64(v),
+            NonFiniteFloats::Null => self.inner.serialize_unit(),
+            NonFiniteFloats::String if v.is_nan() => self.inner.serialize_str("NaN"),
+            NonFiniteFloats::String if v > 0.0 => self.inner.serialize_str("Infinity"),
+            NonFiniteFloats::String => self.inner.serialize_str("-Infinity"),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.inner.serialize_str(&self.config.encode_binary(v))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_some(&value)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_newtype_struct(name, &value)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let value = self.encoded(value);
+        self.inner
+            .serialize_newtype_variant(name, index, variant, &value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self.inner.serialize_seq(len)?,
+            config,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self.inner.serialize_tuple(len)?,
+            config,
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self.inner.serialize_tuple_struct(name, len)?,
+            config,
+        })
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self
+                .inner
+                .serialize_tuple_variant(name, index, variant, len)?,
+            config,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self.inner.serialize_map(len)?,
+            config,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self.inner.serialize_struct(name, len)?,
+            config,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let config = self.config;
+        Ok(Compound {
+            inner: self
+                .inner
+                .serialize_struct_variant(name, index, variant, len)?,
+            config,
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<C: SerializeSeq> SerializeSeq for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTuple> SerializeTuple for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_element(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleStruct> SerializeTupleStruct for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeTupleVariant> SerializeTupleVariant for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_field(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeMap> SerializeMap for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        let key = self.encoded(key);
+        self.inner.serialize_key(&key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_value(&value)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStruct> SerializeStruct for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<C: SerializeStructVariant> SerializeStructVariant for Compound<'_, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        let value = self.encoded(value);
+        self.inner.serialize_field(key, &value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Serialize)]
+    struct Measurement {
+        id: u64,
+        offset: i128,
+        ratio: f64,
+        limits: Vec<f32>,
+        #[serde(with = "bytes")]
+        digest: Vec<u8>,
+        label: Option<&'static str>,
+    }
+
+    mod bytes {
+        pub fn serialize<S: serde::Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(v)
+        }
+    }
+
+    #[test]
+    fn test_configured_encodings() {
+        let measurement = Measurement {
+            id: u64::MAX,
+            offset: -(1 << 70),
+            ratio: f64::NAN,
+            limits: vec![1.5, f32::INFINITY, f32::NEG_INFINITY],
+            digest: vec![0xfb, 0xff, 0x01],
+            label: Some("probe"),
+        };
+
+        let defaults = SerializationConfig::default();
+        assert_eq!(
+            defaults.to_value(&measurement).unwrap(),
+            json!({"id": u64::MAX, "offset": "-1180591620717411303424", "ratio": null,
+                   "limits": [1.5, null, null], "digest": "+/8B", "label": "probe"})
+        );
+
+        let strict = SerializationConfig::default()
+            .with_large_integers(LargeIntegers::String)
+            .with_non_finite_floats(NonFiniteFloats::String)
+            .with_binary(BinaryEncoding::Hex);
+        assert_eq!(
+            strict.to_value(&measurement).unwrap(),
+            json!({"id": "18446744073709551615", "offset": "-1180591620717411303424",
+                   "ratio": "NaN", "limits": [1.5, "Infinity", "-Infinity"],
+                   "digest": "fbff01", "label": "probe"})
+        );
+
+        // Results are re-encoded centrally
+        let mut result = ToolsCallResult {
+            content: vec![ToolContent::Image {
+                data: "+/8B".to_string(),
+                mime_type: "image/png".to_string(),
+            }],
+            is_error: false,
+            metadata: serde_json::from_value(json!({
+                "structuredContent": {"ids": [9007199254740991_u64, 9007199254740993_u64, -9007199254740993_i64]}
+            }))
+            .unwrap(),
+        };
+        strict
+            .with_binary(BinaryEncoding::Base64Url)
+            .apply_to_tool_result(&mut result);
+        assert_eq!(
+            result.content,
+            vec![ToolContent::Image {
+                data: "-_8B".to_string(),
+                mime_type: "image/png".to_string()
+            }]
+        );
+        assert_eq!(
+            result.metadata["structuredContent"]["ids"],
+            json!([
+                9007199254740991_u64,
+                "9007199254740993",
+                "-9007199254740993"
+            ])
+        );
+    }
+}
@@ -2,19 +2,99 @@
 
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
+    clock::Clock,
     error::{McpError, McpResult},
+    ids::{IdGenerator, IdKind, IdStrategy, Ids, Snowflake},
     protocol::{
-        BatchItemResult, BatchParams, BatchResult, InitializeParams, JsonRpcRequest,
-        JsonRpcResponse, StandardMethod, ToolsCallParams, ToolsListResult,
+        messages, BatchItemResult, BatchParams, BatchResult, InitializeParams, JsonRpcRequest,
+        JsonRpcResponse, Map, McpMethod, McpNotification, PingResult, PromptsListResult,
+        ResourceUpdatedNotification, ServerInfo, StandardMethod, Tool, ToolsCallParams,
+        ToolsCallResult, ToolsListResult,
+    },
+    security::{
+        auth::{LIST_ACTION, LIST_ALL_CAPABILITY, READ_ALL_CAPABILITY, TOOL_RESOURCE_PREFIX},
+        rate_limit::MultiTierRateLimiter,
+        ClientFingerprint, McpAuth, RateLimitConfig, SecurityContext,
     },
-    security::SecurityContext,
     server::{
+        admission::{AdmissionController, MemoryProbe},
+        approval::{ApprovalDecisionParams, ApprovalManager},
+        audit::{ExecutionAuditTrail, ExecutionOutcome, ToolExecutionRecord},
+        batch_resume::{BatchJournal, BatchProgress},
+        batch_template,
+        build_info::{BuildInfo, SERVER_INFO},
+        concurrency::PrincipalLimits,
         config::McpServerConfig,
-        progress::{ProgressReporter, ProgressUpdate},
-        registry::{ToolExecutionContext, ToolRegistry},
+        dependencies::{self, DependencyGraph, ADMIN_DEPENDENCIES},
+        diff::{
+            unified_diff, ResourcesDiffParams, ResourcesDiffResult, DEFAULT_CONTEXT_LINES,
+            RESOURCES_DIFF,
+        },
+        discover::{self, openrpc_document, MethodDescription, RPC_DISCOVER},
+        duplicates::DuplicateRequests,
+        formatters::{ResultFormatter, ResultFormatters},
+        inspector::{InspectorConfigureParams, InspectorListParams, RequestInspector},
+        legacy_sse::LegacySseSessions,
+        maintenance::{
+            Maintenance, MaintenanceKind, MaintenanceParams, ADMIN_MAINTENANCE_CLEAR,
+            ADMIN_MAINTENANCE_LIST, ADMIN_MAINTENANCE_SET,
+        },
+        memory::{approximate_size, MemoryAccountant},
+        negotiation::negotiate,
+        notification_filter::{
+            NotificationCategory, NotificationFilter, NOTIFICATIONS_CAPABILITY,
+            NOTIFICATIONS_FILTER,
+        },
+        notifications::{NotificationBroker, NotificationDispatcher, NotificationSender},
+        pagination::{paginate, Page},
+        progress::{ProgressLevel, ProgressReporter, ProgressUpdate},
+        prompt::PromptContent,
+        prompt_lint::PromptLinter,
+        prompt_stream::PromptStreams,
+        quota::{QuotaManager, QuotaMetric, QuotaStore},
+        read_many::{
+            ResourceReadOutcome, ResourcesReadManyParams, ResourcesReadManyResult,
+            RESOURCES_READ_MANY,
+        },
+        reduction::{ContentReducer, REDUCED_RESOURCES_META},
+        registry::{is_read_only, McpTool, ToolExecutionContext, ToolRegistry},
+        registry_changelog::{ChangelogQuery, RegistryChangelog, ADMIN_REGISTRY_CHANGELOG},
+        resource::{
+            content_etag, etag_matches, split_query_param, split_version, unversioned, Resource,
+            ResourceChangeType, ResourceChanged, ResourceContent, ResourceRegistry,
+            ResourcesVersionsParams, ETAG_META, RESOURCES_VERSIONS,
+        },
+        scheduler::RequestScheduler,
+        scrub::Scrubber,
+        search::{
+            ResourceSearch, ResourcesSearchParams, ResourcesSearchResult, SearchIndex,
+            RESOURCES_SEARCH,
+        },
+        secrets::{SecretProvider, Secrets},
+        session_store::{SessionStore, SessionStores},
+        smoke::{server_stats, SmokeTools, SMOKE_SERVER_STATS},
+        snapshot::{CapabilitySnapshot, PromptSnapshot, ResourceSnapshot, ToolSnapshot},
+        tasks::{
+            TaskManager, TaskParams, TASKS_CANCEL, TASKS_CAPABILITY, TASKS_CREATE, TASKS_LIST,
+            TASKS_RESULT, TASKS_STATUS, TASK_METHODS,
+        },
+        tokens::{default_estimator, TokenBudgeter, TokenEstimator},
+        tool_limits::{declared_limits, ToolLimit, ToolLimits},
+        toolsets::{
+            Toolsets, ToolsetsConfigureParams, ToolsetsParams, ADMIN_TOOLSETS_CONFIGURE,
+            TOOLSETS_CAPABILITY, TOOLSETS_DISABLE, TOOLSETS_ENABLE, TOOLSETS_LIST, TOOLSET_METHODS,
+        },
+        transform::{
+            ResourceTransformer, ResourceTransformers, FORMAT_QUERY_PARAM, TRANSFORMED_FROM_META,
+        },
+        validation::{
+            check_config, check_security, check_tool_schema, ValidationIssue, ValidationReport,
+        },
+        webhooks::{WebhookDispatcher, WebhookEventKind},
+        wire_tap::{FrameDirection, WireTap, WireTapParams, ADMIN_WIRE_TAP},
         BatchContext, McpServerState, ServerHealth,
     },
 };
@@ -42,6 +122,105 @@ where
 
     /// Server start time
     start_time: std::time::Instant,
+
+    /// Wall-clock time the server started, reported in the server info
+    started_at: std::time::SystemTime,
+
+    /// Per-client-type rate limits from the client policy
+    client_rate_limiter: Arc<MultiTierRateLimiter>,
+
+    /// Open legacy HTTP+SSE streams
+    legacy_sessions: LegacySseSessions,
+
+    /// Per-session working memory for tools
+    session_stores: SessionStores,
+
+    /// Recent tool executions, when auditing is enabled
+    audit_trail: Option<ExecutionAuditTrail>,
+
+    /// Tool calls waiting for an operator's approval
+    approvals: ApprovalManager,
+
+    /// Per-principal quotas, when enabled
+    quotas: Option<QuotaManager>,
+
+    /// Outbound webhooks for server events
+    webhooks: WebhookDispatcher,
+
+    /// Recent JSON-RPC exchanges, when capturing is switched on
+    inspector: RequestInspector,
+
+    /// Mirror of the frames of tapped sessions
+    wire_tap: WireTap,
+
+    /// Log of registry mutations, when attached
+    registry_changelog: Option<RegistryChangelog>,
+
+    /// Streamed `prompts/get` responses waiting to be rendered
+    prompt_streams: PromptStreams,
+
+    /// Generator of session, subscription, progress and request IDs
+    ids: Ids,
+
+    /// Recently seen request IDs per session, for retried requests
+    duplicates: DuplicateRequests,
+
+    /// Priority queue in front of heavy requests
+    scheduler: RequestScheduler,
+
+    /// Rejection of expensive requests under overload
+    admission: AdmissionController,
+
+    /// Fan-out of server notifications to sessions on every replica
+    notifications: NotificationDispatcher,
+
+    /// Token estimates and budgets for prompt and tool results
+    tokens: TokenBudgeter,
+
+    /// Format conversions offered on `resources/read`
+    transformers: ResourceTransformers,
+
+    /// Full-text index behind `resources/search`, when enabled
+    search: Option<ResourceSearch>,
+
+    /// Linter applied to prompts by [`validate`](Self::validate)
+    prompt_linter: PromptLinter,
+
+    /// Tool calls running in the background, started with `tasks/create`
+    tasks: TaskManager,
+
+    /// Tool groups enabled per session
+    toolsets: Toolsets,
+
+    /// Tools, prompts and resource schemes taken out of service
+    maintenance: Maintenance,
+
+    /// Credentials tools look up by name
+    secrets: Secrets,
+
+    /// Personal data rules for audit records, captures and tool results
+    scrubber: Scrubber,
+
+    /// Completed items of batches sent with a correlation token
+    batches: BatchJournal,
+
+    /// Concurrent tool executions per principal
+    principal_limits: PrincipalLimits,
+
+    /// Rate limits and quotas declared on tools
+    tool_limits: ToolLimits,
+
+    /// Built-in tools for smoke testing, when enabled
+    smoke_tools: SmokeTools,
+
+    /// Post-processing of tool results
+    formatters: ResultFormatters,
+
+    /// Approximate memory held by requests, caches and notification buffers
+    memory: MemoryAccountant,
+
+    /// Time source of rate limits, expiry, queueing and approval timeouts
+    clock: Clock,
 }
 
 impl<S> McpServer<S>
@@ -50,6 +229,50 @@ where
 {
     /// Create a new MCP server with the given state
     pub fn new(config: McpServerConfig, state: S) -> Self {
+        let mut client_rate_limiter = MultiTierRateLimiter::new();
+        for (client_type, per_minute) in &config.security.client_policy.rate_limits_per_minute {
+            client_rate_limiter.add_limiter(client_type, RateLimitConfig::per_minute(*per_minute));
+        }
+
+        let session_stores = SessionStores::new(config.session.timeout);
+        let audit_trail = config
+            .security
+            .enable_audit
+            .then(|| ExecutionAuditTrail::new(config.security.audit_retention.clone()));
+        let approvals = ApprovalManager::new(config.approval.clone());
+        let quotas = config
+            .security
+            .quota
+            .enabled
+            .then(|| QuotaManager::new(config.security.quota.clone()));
+        let webhooks = WebhookDispatcher::new(config.name.clone(), config.webhooks.clone());
+        let scrubber = Scrubber::new(config.scrub.clone());
+        let mut inspector = RequestInspector::new(config.inspector.clone());
+        if scrubber.is_enabled() {
+            inspector = inspector.with_scrubber(scrubber.clone());
+        }
+        let smoke_tools = SmokeTools::new(config.smoke_tools.clone());
+        let prompt_streams = PromptStreams::new(config.prompt_stream.clone());
+        let ids = config.ids.build();
+        let mut wire_tap = WireTap::new(config.wire_tap.clone());
+        if scrubber.is_enabled() {
+            wire_tap = wire_tap.with_scrubber(scrubber.clone());
+        }
+        let duplicates = DuplicateRequests::new(config.duplicates.clone());
+        let scheduler = RequestScheduler::new(config.scheduler.clone());
+        let admission = AdmissionController::new(config.admission.clone(), scheduler.clone());
+        let tokens = TokenBudgeter::new(default_estimator(), config.tokens.clone());
+        let tasks = TaskManager::new(config.tasks.clone());
+        let toolsets = Toolsets::new(config.toolsets.clone());
+        let secrets = Secrets::new(config.secrets.clone());
+        let maintenance = Maintenance::new().with_windows(config.maintenance.windows.clone());
+        let batches = BatchJournal::new(config.batch_resume.clone());
+        let principal_limits = PrincipalLimits::new(config.principal_concurrency.clone());
+        let formatters = ResultFormatters::new(config.formatters.clone());
+        let memory = MemoryAccountant::new(config.memory.clone());
+        let notifications = NotificationDispatcher::default();
+        track_notifications(&memory, notifications.broker());
+
         Self {
             config,
             state,
@@ -57,7 +280,313 @@ where
             health: Arc::new(RwLock::new(ServerHealth::default())),
             active_connections: Arc::new(RwLock::new(0)),
             start_time: std::time::Instant::now(),
+            started_at: std::time::SystemTime::now(),
+            client_rate_limiter: Arc::new(client_rate_limiter),
+            legacy_sessions: LegacySseSessions::new(),
+            session_stores,
+            audit_trail,
+            approvals,
+            quotas,
+            webhooks,
+            inspector,
+            wire_tap,
+            registry_changelog: None,
+            prompt_streams,
+            ids,
+            duplicates,
+            scheduler,
+            admission,
+            notifications,
+            tokens,
+            transformers: ResourceTransformers::default(),
+            search: None,
+            prompt_linter: PromptLinter::new(),
+            tasks,
+            toolsets,
+            maintenance,
+            secrets,
+            scrubber,
+            batches,
+            principal_limits,
+            tool_limits: ToolLimits::new(),
+            smoke_tools,
+            formatters,
+            memory,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Read time from `clock` instead of real time
+    ///
+    /// Rate limits, session stores, duplicate request windows, the scheduler,
+    /// admission control, per-principal and tool limits, approval timeouts and task and
+    /// batch expiry all follow it, so tests can advance time with a
+    /// [`Clock::manual`] instead of sleeping.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        let mut client_rate_limiter = MultiTierRateLimiter::new().with_clock(clock.clone());
+        for (client_type, per_minute) in &self.config.security.client_policy.rate_limits_per_minute
+        {
+            client_rate_limiter.add_limiter(client_type, RateLimitConfig::per_minute(*per_minute));
+        }
+        self.client_rate_limiter = Arc::new(client_rate_limiter);
+        self.session_stores = self.session_stores.with_clock(clock.clone());
+        self.duplicates = self.duplicates.with_clock(clock.clone());
+        self.scheduler = self.scheduler.with_clock(clock.clone());
+        self.admission = self.admission.with_clock(clock.clone());
+        self.approvals = self.approvals.with_clock(clock.clone());
+        self.tasks = self.tasks.with_clock(clock.clone());
+        self.maintenance = self.maintenance.with_clock(clock.clone());
+        self.secrets = self.secrets.with_clock(clock.clone());
+        self.batches = self.batches.with_clock(clock.clone());
+        self.principal_limits = self.principal_limits.with_clock(clock.clone());
+        self.tool_limits = self.tool_limits.with_clock(clock.clone());
+        self.smoke_tools = self.smoke_tools.with_clock(clock.clone());
+        self.started_at = clock.system_time();
+        self.clock = clock;
+        self
+    }
+
+    /// Time source of the server
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// Server info of the state, with the build and runtime metadata unless
+    /// disabled in the configuration
+    pub fn server_info(&self) -> ServerInfo {
+        let mut info = self.state.server_info();
+        self.insert_build_info(&mut info);
+        info
+    }
+
+    /// Methods this server answers, as returned by `rpc.discover`
+    pub fn method_catalog(&self) -> Vec<MethodDescription> {
+        let mut standard = vec!["initialize", "notifications/initialized", "ping"];
+        standard.extend(["tools/list", "tools/call"]);
+        if self.config.enable_batch {
+            standard.push("batch");
+        }
+        let has_resources = !self.resource_registries().is_empty();
+        if has_resources || self.audit_trail.is_some() {
+            standard.extend(["resources/list", "resources/read"]);
+        }
+        if self.state.prompt_registry().is_some() {
+            standard.extend(["prompts/list", "prompts/get"]);
+        }
+        let mut methods: Vec<MethodDescription> = standard
+            .into_iter()
+            .map(discover::standard_method)
+            .collect();
+
+        methods.push(
+            MethodDescription::new(RPC_DISCOVER, "Describe the methods of this server")
+                .with_result(serde_json::json!({"type": "object"})),
+        );
+        methods.push(
+            MethodDescription::new(SERVER_INFO, "Server info with build and runtime metadata")
+                .with_result(serde_json::json!({"type": "object"})),
+        );
+        let categories = serde_json::json!({
+            "categories": {
+                "type": "array",
+                "items": {"enum": NotificationCategory::ALL},
+            },
+        });
+        methods.push(
+            MethodDescription::new(
+                NOTIFICATIONS_FILTER,
+                "Choose the notification categories sent to this session",
+            )
+            .with_params(discover::object_schema(categories.clone(), &[]))
+            .with_result(discover::object_schema(categories, &[])),
+        );
+        if has_resources {
+            methods.push(MethodDescription::new(
+                RESOURCES_DIFF,
+                "Compare two versions of a resource",
+            ));
+            methods.push(MethodDescription::new(
+                RESOURCES_VERSIONS,
+                "List the stored versions of a resource",
+            ));
+            methods.push(
+                MethodDescription::new(RESOURCES_READ_MANY, "Read several resources concurrently")
+                    .with_params(discover::object_schema(
+                        serde_json::json!({
+                            "uris": {"type": "array", "items": {"type": "string"}},
+                            "accept": {"type": "string"},
+                            "timeoutMs": {"type": "integer"},
+                        }),
+                        &["uris"],
+                    ))
+                    .with_result(discover::object_schema(
+                        serde_json::json!({"results": {"type": "array", "items": {"type": "object"}}}),
+                        &["results"],
+                    )),
+            );
+        }
+        if self.search.is_some() {
+            methods.push(MethodDescription::new(
+                RESOURCES_SEARCH,
+                "Search indexed resources",
+            ));
+        }
+        if self.tasks.is_enabled() {
+            for (name, summary) in [
+                (TASKS_CREATE, "Start a tool call as a task"),
+                (TASKS_STATUS, "Report the state of a task"),
+                (TASKS_RESULT, "Fetch the result of a finished task"),
+                (TASKS_CANCEL, "Stop a working task"),
+                (TASKS_LIST, "List the caller's tasks"),
+            ] {
+                methods.push(MethodDescription::new(name, summary));
+            }
+        }
+        if self.toolsets.is_enabled() {
+            for (name, summary) in [
+                (TOOLSETS_LIST, "List the toolsets of the session"),
+                (TOOLSETS_ENABLE, "Enable toolsets in the session"),
+                (TOOLSETS_DISABLE, "Disable toolsets in the session"),
+            ] {
+                methods.push(MethodDescription::new(name, summary));
+            }
+        }
+        for (name, summary) in [
+            (ADMIN_APPROVALS_LIST, "List tool calls waiting for approval"),
+            (
+                ADMIN_APPROVALS_DECIDE,
+                "Approve or deny a pending tool call",
+            ),
+            (ADMIN_QUOTAS_INSPECT, "Report quota usage"),
+            (ADMIN_QUOTAS_RESET, "Restore a principal's quota"),
+            (ADMIN_INSPECTOR_LIST, "Return captured JSON-RPC exchanges"),
+            (
+                ADMIN_INSPECTOR_CONFIGURE,
+                "Switch the request inspector on or off",
+            ),
+            (
+                ADMIN_WIRE_TAP,
+                "Switch the wire tap for a session or all sessions",
+            ),
+            (
+                ADMIN_TOOLSETS_CONFIGURE,
+                "Enable and disable toolsets in any session",
+            ),
+            (ADMIN_MAINTENANCE_LIST, "List the items in maintenance"),
+            (ADMIN_MAINTENANCE_SET, "Put an item in maintenance"),
+            (ADMIN_MAINTENANCE_CLEAR, "Take an item out of maintenance"),
+            (
+                ADMIN_DEPENDENCIES,
+                "Graph of the resources tools read and write",
+            ),
+            (
+                ADMIN_REGISTRY_CHANGELOG,
+                "When registry entries changed and who changed them",
+            ),
+        ] {
+            methods.push(MethodDescription::new(name, summary).system_only());
+        }
+        methods.extend(self.state.describe_custom_methods());
+        methods
+    }
+
+    /// Add the build and runtime metadata to `info`, if enabled
+    fn insert_build_info(&self, info: &mut ServerInfo) {
+        if self.config.build_info.enabled {
+            BuildInfo::collect(
+                &self.config.build_info,
+                self.started_at,
+                self.clock.system_time(),
+            )
+            .insert_into(info);
+        }
+    }
+
+    /// Keep quota usage in the given store instead of in memory
+    ///
+    /// Has no effect unless quotas are enabled in the configuration.
+    pub fn with_quota_store(mut self, store: Arc<dyn QuotaStore>) -> Self {
+        if let Some(quotas) = &self.quotas {
+            self.quotas = Some(QuotaManager::with_store(quotas.config().clone(), store));
         }
+        self
+    }
+
+    /// Publish notifications through the given broker instead of in process
+    ///
+    /// Replicas sharing a broker deliver each other's notifications.
+    pub fn with_notification_broker(mut self, broker: Arc<dyn NotificationBroker>) -> Self {
+        track_notifications(&self.memory, &broker);
+        self.notifications = NotificationDispatcher::new(broker);
+        self
+    }
+
+    /// Look up secrets for tools with `provider`, after those added before
+    pub fn with_secret_provider(mut self, provider: Arc<dyn SecretProvider>) -> Self {
+        self.secrets = self.secrets.with_provider(provider);
+        self
+    }
+
+    /// Estimate result sizes with the given estimator
+    pub fn with_token_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.tokens = self.tokens.with_estimator(estimator);
+        self
+    }
+
+    /// Reduce oversized resources with the given reducer
+    pub fn with_content_reducer(mut self, reducer: Arc<dyn ContentReducer>) -> Self {
+        self.tokens = self.tokens.with_reducer(reducer);
+        self
+    }
+
+    /// Offer another format conversion on `resources/read`
+    pub fn with_resource_transformer(mut self, transformer: Arc<dyn ResourceTransformer>) -> Self {
+        self.transformers = self.transformers.with_transformer(transformer);
+        self
+    }
+
+    /// Offer another formatter to the chains of
+    /// [`McpServerConfig::formatters`](crate::server::McpServerConfig::formatters)
+    pub fn with_result_formatter(mut self, formatter: Arc<dyn ResultFormatter>) -> Self {
+        self.formatters = self.formatters.with_formatter(formatter);
+        self
+    }
+
+    /// Serve `resources/search` from the given index
+    pub fn with_search_index(mut self, index: Arc<dyn SearchIndex>) -> Self {
+        self.search = Some(ResourceSearch::new(index));
+        self
+    }
+
+    /// Lint prompts with the given linter during [`validate`](Self::validate)
+    ///
+    /// Needed when prompts are rendered by a template engine other than
+    /// [`SimpleTemplateEngine`](crate::server::SimpleTemplateEngine).
+    pub fn with_prompt_linter(mut self, linter: PromptLinter) -> Self {
+        self.prompt_linter = linter;
+        self
+    }
+
+    /// Answer `admin/registry/changelog` from `changelog`
+    ///
+    /// The changelog is filled by the registries it is attached to.
+    pub fn with_registry_changelog(mut self, changelog: RegistryChangelog) -> Self {
+        self.registry_changelog = Some(changelog);
+        self
+    }
+
+    /// Generate IDs with `generator` instead of the configured strategy
+    pub fn with_id_generator(mut self, generator: impl IdGenerator + 'static) -> Self {
+        self.ids = Ids::new(generator);
+        self
+    }
+
+    /// Read resident memory for admission control with `probe`
+    ///
+    /// By default it is read from the operating system where supported.
+    pub fn with_memory_probe(mut self, probe: MemoryProbe) -> Self {
+        self.admission = self.admission.with_memory_probe(probe);
+        self
     }
 
     /// Get server configuration
@@ -70,6 +599,470 @@ where
         &self.state
     }
 
+    /// Get the registry of open legacy HTTP+SSE streams
+    pub fn legacy_sessions(&self) -> &LegacySseSessions {
+        &self.legacy_sessions
+    }
+
+    /// Get the per-session working memory handed to tools
+    pub fn session_stores(&self) -> &SessionStores {
+        &self.session_stores
+    }
+
+    /// Get the tool execution audit trail, if auditing is enabled
+    pub fn audit_trail(&self) -> Option<&ExecutionAuditTrail> {
+        self.audit_trail.as_ref()
+    }
+
+    /// Get the tool calls waiting for approval
+    pub fn approvals(&self) -> &ApprovalManager {
+        &self.approvals
+    }
+
+    /// Get the per-principal quotas, if enabled
+    pub fn quotas(&self) -> Option<&QuotaManager> {
+        self.quotas.as_ref()
+    }
+
+    /// Get the dispatcher for outbound webhooks
+    pub fn webhooks(&self) -> &WebhookDispatcher {
+        &self.webhooks
+    }
+
+    /// Get the recently seen request IDs
+    pub fn duplicate_requests(&self) -> &DuplicateRequests {
+        &self.duplicates
+    }
+
+    /// Get the request scheduler
+    pub fn scheduler(&self) -> &RequestScheduler {
+        &self.scheduler
+    }
+
+    /// Get the per-principal execution limits
+    pub fn principal_limits(&self) -> &PrincipalLimits {
+        &self.principal_limits
+    }
+
+    /// Get the memory accountant, e.g. to register caches with it
+    pub fn memory(&self) -> &MemoryAccountant {
+        &self.memory
+    }
+
+    /// Get the admission controller
+    pub fn admission(&self) -> &AdmissionController {
+        &self.admission
+    }
+
+    /// Get the JSON-RPC request inspector
+    pub fn inspector(&self) -> &RequestInspector {
+        &self.inspector
+    }
+
+    /// Get the wire tap mirroring the frames of tapped sessions
+    pub fn wire_tap(&self) -> &WireTap {
+        &self.wire_tap
+    }
+
+    /// Get the generator of session, subscription, progress and request IDs
+    pub fn ids(&self) -> &Ids {
+        &self.ids
+    }
+
+    /// Get the tool calls running as tasks
+    pub fn tasks(&self) -> &TaskManager {
+        &self.tasks
+    }
+
+    /// Get the tools, prompts and resource schemes in maintenance
+    pub fn maintenance(&self) -> &Maintenance {
+        &self.maintenance
+    }
+
+    /// Get the dispatcher for server notifications
+    pub fn notifications(&self) -> &NotificationDispatcher {
+        &self.notifications
+    }
+
+    /// Get a sender for notifications from application code
+    pub fn notifier(&self) -> NotificationSender {
+        NotificationSender::new(
+            self.notifications.clone(),
+            Arc::clone(&self.progress_reporter),
+        )
+    }
+
+    /// Announce a change to a resource
+    ///
+    /// Fires `resource.changed` webhooks and notifies subscribed sessions;
+    /// created and deleted resources also change the resource list. With
+    /// search enabled, the resource is re-indexed before the next search.
+    pub fn resource_changed(&self, change: ResourceChanged) {
+        if let Some(search) = &self.search {
+            match (&change.change_type, &change.content) {
+                (ResourceChangeType::Deleted, _) => search.remove(&change.uri),
+                (_, Some(ResourceContent::Text { text })) => {
+                    search.index().index(&change.uri, text)
+                }
+                _ => search.mark_stale(&change.uri),
+            }
+        }
+        self.notifications.emit(McpNotification::new(
+            McpMethod::NotificationsResourcesUpdated(ResourceUpdatedNotification {
+                uri: change.uri.clone(),
+            }),
+        ));
+        if !matches!(change.change_type, ResourceChangeType::Updated) {
+            self.notifications.emit(McpNotification::new(
+                McpMethod::NotificationsResourcesListChanged,
+            ));
+        }
+        self.webhooks.emit(
+            WebhookEventKind::ResourceChanged,
+            serde_json::json!({
+                "uri": change.uri,
+                "changeType": change.change_type,
+            }),
+        );
+    }
+
+    /// Add a resource to the search index, or refresh it
+    ///
+    /// Resources are read as the [`SEARCH_INDEXER`] service account, which
+    /// may read every resource, so registries and audits can tell indexing
+    /// from client reads. Binary
+    /// resources are indexed
+    /// if a transformer converts them to `text/plain`, and skipped otherwise;
+    /// resources that no longer exist leave the index.
+    pub async fn index_resource(&self, uri: &str) -> McpResult<()> {
+        let Some(search) = &self.search else {
+            return Err(search_disabled());
+        };
+        let registries = self.resource_registries();
+        if registries.is_empty() {
+            return Ok(());
+        }
+        let registry = self.resource_registry_for(&registries, uri);
+        let mut resource = match registry
+            .get_resource(uri, &internal_account(SEARCH_INDEXER))
+            .await
+        {
+            Ok(resource) => resource,
+            Err(McpError::ResourceNotFound { .. }) => {
+                search.remove(uri);
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+        if matches!(resource.content, ResourceContent::Blob { .. })
+            && self
+                .transform_resource(&mut resource, "text/plain", &mut Map::new())
+                .await
+                .is_err()
+        {
+            debug!("Not indexing {}: no text/plain conversion", uri);
+        }
+        match &resource.content {
+            ResourceContent::Text { text } => search.index().index(uri, text),
+            ResourceContent::Blob { .. } => search.remove(uri),
+        }
+        Ok(())
+    }
+
+    /// Index every resource the registries list by a concrete URI
+    ///
+    /// Templates with `{placeholders}` are skipped. Returns the number of
+    /// resources read.
+    pub async fn index_resources(&self) -> McpResult<usize> {
+        let context = internal_account(SEARCH_INDEXER);
+        let mut uris = Vec::new();
+        for registry in self.resource_registries() {
+            for template in registry.list_resource_templates(&context).await? {
+                if !template.uri_template.contains('{') {
+                    uris.push(template.uri_template);
+                }
+            }
+        }
+        for uri in &uris {
+            self.index_resource(uri).await?;
+        }
+        Ok(uris.len())
+    }
+
+    /// Describe the tools, resources and prompts this server offers
+    ///
+    /// Registries are listed with a system context, so the snapshot covers
+    /// everything regardless of per-client visibility. See
+    /// [`CapabilitySnapshot::diff`] for comparing snapshots.
+    pub async fn capability_snapshot(&self) -> McpResult<CapabilitySnapshot> {
+        let context = SecurityContext::system();
+        let mut snapshot = CapabilitySnapshot {
+            server: self.config.name.clone(),
+            version: self.config.version.clone(),
+            ..Default::default()
+        };
+
+        for tool in self.tools().list_tools(&context).await? {
+            snapshot
+                .tools
+                .insert(tool.name.clone(), ToolSnapshot::from(&tool));
+        }
+        for registry in self.resource_registries() {
+            for template in registry.list_resource_templates(&context).await? {
+                snapshot.resources.insert(
+                    template.uri_template.clone(),
+                    ResourceSnapshot::from(&template),
+                );
+            }
+        }
+        if let Some(prompt_registry) = self.state.prompt_registry() {
+            for prompt in prompt_registry.list_prompts(&context).await? {
+                snapshot
+                    .prompts
+                    .insert(prompt.name.clone(), PromptSnapshot::from(&prompt));
+            }
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Check that every service required by a tool or prompt is registered
+    ///
+    /// Call this at startup so a missing service fails the deployment instead
+    /// of the first call that needs it. The error lists every missing service.
+    pub async fn check_dependencies(&self) -> McpResult<()> {
+        let context = internal_account(VALIDATOR);
+        let services = self.state.services().cloned().unwrap_or_default();
+        let mut missing = Vec::new();
+
+        for tool in self.tools().list_tools(&context).await? {
+            for name in services.missing(&tool.metadata) {
+                missing.push(format!("tool '{}' requires '{}'", tool.name, name));
+            }
+        }
+        if let Some(prompt_registry) = self.state.prompt_registry() {
+            for prompt in prompt_registry.list_prompts(&context).await? {
+                for name in services.missing(&prompt.metadata) {
+                    missing.push(format!("prompt '{}' requires '{}'", prompt.name, name));
+                }
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(McpError::Configuration {
+                message: format!("Missing services: {}", missing.join("; ")),
+            })
+        }
+    }
+
+    /// Check the configuration and everything the server offers before serving
+    ///
+    /// Registries are listed as the [`VALIDATOR`] service account, which may
+    /// list everything. Problems
+    /// are collected
+    /// rather than returned one by one; see [`ValidationReport::into_result`]
+    /// for failing on errors.
+    pub async fn validate(&self) -> ValidationReport {
+        let context = internal_account(VALIDATOR);
+        let services = self.state.services().cloned().unwrap_or_default();
+        let mut report = ValidationReport::default();
+        report.issues.extend(check_config(&self.config));
+        report.issues.extend(check_security(&self.config));
+        for name in self.formatters.unknown() {
+            report.push(ValidationIssue::error(
+                "unknown-formatter",
+                format!("formatter '{}'", name),
+                "No result formatter is registered under this name",
+            ));
+        }
+        if let IdStrategy::Snowflake { node_id } = self.config.ids {
+            if node_id > Snowflake::MAX_NODE_ID {
+                report.push(ValidationIssue::error(
+                    "invalid-snowflake-node",
+                    "ids",
+                    format!(
+                        "Snowflake node ID {} does not fit in 10 bits; IDs of nodes may collide",
+                        node_id
+                    ),
+                ));
+            }
+        }
+        for (code, status) in self.config.http_status.invalid() {
+            report.push(ValidationIssue::error(
+                "invalid-http-status",
+                format!("http_status '{}'", code),
+                format!(
+                    "{} is not an HTTP status; these errors are answered with 200",
+                    status
+                ),
+            ));
+        }
+        if self.smoke_tools.is_enabled() {
+            report.push(ValidationIssue::warning(
+                "smoke-tools-enabled",
+                "smoke_tools",
+                "Built-in mcp.* test tools are exposed to every authenticated caller",
+            ));
+        }
+        let custom = self.state.custom_methods();
+        for pattern in self.config.security.method_authorization.patterns() {
+            let known = pattern.ends_with('*')
+                || StandardMethod::NAMES.contains(&pattern)
+                || BUILTIN_METHODS.contains(&pattern)
+                || custom.iter().any(|m| m == pattern);
+            if !known {
+                report.push(ValidationIssue::warning(
+                    "unknown-method-rule",
+                    format!("method '{}'", pattern),
+                    "Authorization rule names a method the server does not handle",
+                ));
+            }
+        }
+
+        match self.tools().list_tools(&context).await {
+            Ok(tools) => {
+                let mut names = std::collections::HashSet::new();
+                for tool in tools {
+                    if !names.insert(tool.name.clone()) {
+                        report.push(ValidationIssue::error(
+                            "duplicate-tool",
+                            format!("tool '{}'", tool.name),
+                            "Tool is listed more than once",
+                        ));
+                    }
+                    report
+                        .issues
+                        .extend(check_tool_schema(&tool.name, &tool.input_schema));
+                    for spec in declared_limits(&tool) {
+                        if let Err(e) = spec.parse::<ToolLimit>() {
+                            report.push(ValidationIssue::error(
+                                "invalid-tool-limit",
+                                format!("tool '{}'", tool.name),
+                                e.to_string(),
+                            ));
+                        }
+                    }
+                    for name in services.missing(&tool.metadata) {
+                        report.push(ValidationIssue::error(
+                            "missing-service",
+                            format!("tool '{}'", tool.name),
+                            format!("Required service '{}' is not registered", name),
+                        ));
+                    }
+                }
+            }
+            Err(e) => report.push(ValidationIssue::error("registry", "tools", e.to_string())),
+        }
+
+        let registries = self.resource_registries();
+        for registry in &registries {
+            let templates = match registry.list_resource_templates(&context).await {
+                Ok(templates) => templates,
+                Err(e) => {
+                    report.push(ValidationIssue::error(
+                        "registry",
+                        format!("resources '{}'", registry.uri_scheme().scheme),
+                        e.to_string(),
+                    ));
+                    continue;
+                }
+            };
+            for template in templates {
+                let serving = self.resource_registry_for(&registries, &template.uri_template);
+                if !std::ptr::addr_eq(serving, *registry) {
+                    report.push(ValidationIssue::error(
+                        "shadowed-resource",
+                        format!("resource '{}'", template.uri_template),
+                        format!(
+                            "Served by the '{}' registry instead of '{}'",
+                            serving.uri_scheme().scheme,
+                            registry.uri_scheme().scheme
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(prompt_registry) = self.state.prompt_registry() {
+            match prompt_registry.list_prompts(&context).await {
+                Ok(prompts) => {
+                    for prompt in prompts {
+                        let subject = format!("prompt '{}'", prompt.name);
+                        for diagnostic in self.prompt_linter.lint(&prompt) {
+                            report.push(ValidationIssue {
+                                severity: diagnostic.severity,
+                                code: diagnostic.code,
+                                subject: format!("{} {}", subject, diagnostic.location),
+                                message: diagnostic.message,
+                            });
+                        }
+                        for name in services.missing(&prompt.metadata) {
+                            report.push(ValidationIssue::error(
+                                "missing-service",
+                                subject.clone(),
+                                format!("Required service '{}' is not registered", name),
+                            ));
+                        }
+                        for message in &prompt.messages {
+                            let PromptContent::EmbeddedResource { resource, .. } = &message.content
+                            else {
+                                continue;
+                            };
+                            // Templated URIs are only known once the prompt is rendered
+                            if resource.uri.contains("{{") {
+                                continue;
+                            }
+                            if let Some(problem) =
+                                self.unreachable_resource(&registries, &resource.uri).await
+                            {
+                                report.push(ValidationIssue::error(
+                                    "unreachable-resource",
+                                    subject.clone(),
+                                    problem,
+                                ));
+                            }
+                        }
+                    }
+                }
+                Err(e) => report.push(ValidationIssue::error("registry", "prompts", e.to_string())),
+            }
+        }
+
+        report
+    }
+
+    /// Why `uri` cannot be read, if it cannot
+    async fn unreachable_resource(
+        &self,
+        registries: &[&dyn ResourceRegistry],
+        uri: &str,
+    ) -> Option<String> {
+        let Some(registry) = registries
+            .iter()
+            .find(|registry| registry.can_handle_uri(uri))
+        else {
+            return Some(format!("No resource registry serves '{}'", uri));
+        };
+        match registry
+            .resource_exists(uri, &internal_account(VALIDATOR))
+            .await
+        {
+            Ok(true) => None,
+            Ok(false) => Some(format!("Embedded resource '{}' does not exist", uri)),
+            Err(e) => Some(format!(
+                "Embedded resource '{}' is not readable: {}",
+                uri, e
+            )),
+        }
+    }
+
+    /// Streams for `prompts/get` requests asking to be streamed
+    pub fn prompt_streams(&self) -> &PromptStreams {
+        &self.prompt_streams
+    }
+
     /// Get progress reporter
     pub fn progress_reporter(&self) -> Arc<ProgressReporter> {
         Arc::clone(&self.progress_reporter)
@@ -82,6 +1075,79 @@ where
         context: SecurityContext,
     ) -> std::pin::Pin<Box<dyn std::future::Future<Output = JsonRpcResponse> + Send + '_>> {
         Box::pin(async move {
+            let session_id = context.client.session_id.clone();
+            if !self.wire_tap.is_tapped(session_id.as_deref()) {
+                return self.inspect(request, context).await;
+            }
+
+            let frame = serde_json::to_value(&request).unwrap_or_default();
+            self.wire_tap
+                .record(session_id.as_deref(), FrameDirection::Inbound, &frame);
+            let response = self.inspect(request, context).await;
+            let frame = serde_json::to_value(&response).unwrap_or_default();
+            self.wire_tap
+                .record(session_id.as_deref(), FrameDirection::Outbound, &frame);
+            response
+        })
+    }
+
+    /// Answer a JSON-RPC request, capturing the exchange if the inspector is on
+    async fn inspect(&self, request: JsonRpcRequest, context: SecurityContext) -> JsonRpcResponse {
+        // Reading the inspector is not itself captured
+        if !self.inspector.is_enabled() || request.method.starts_with("admin/inspector/") {
+            return self.dispatch_once(request, context).await;
+        }
+
+        let received_at = chrono::Utc::now();
+        let started = std::time::Instant::now();
+        let captured = serde_json::to_value(&request).unwrap_or_default();
+        let response = self.dispatch_once(request, context.clone()).await;
+        self.inspector.record(
+            &context,
+            received_at,
+            started.elapsed(),
+            &captured,
+            &serde_json::to_value(&response).unwrap_or_default(),
+        );
+        response
+    }
+
+    /// Answer a JSON-RPC request, unless it repeats a recent one
+    async fn dispatch_once(
+        &self,
+        request: JsonRpcRequest,
+        context: SecurityContext,
+    ) -> JsonRpcResponse {
+        self.duplicates
+            .handle(request, context, |request, context| {
+                self.dispatch_request(request, context)
+            })
+            .await
+    }
+
+    /// Authorize, route and answer a JSON-RPC request
+    fn dispatch_request(
+        &self,
+        request: JsonRpcRequest,
+        context: SecurityContext,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = JsonRpcResponse> + Send + '_>> {
+        Box::pin(async move {
+            let mut context = context;
+            self.config.exposure.apply(&mut context);
+            let mut request = request;
+            if let Some(alias) = self.config.aliases.resolve(&mut request) {
+                if self
+                    .config
+                    .aliases
+                    .get(&alias)
+                    .is_some_and(|alias| alias.deprecated)
+                {
+                    warn!(
+                        "Client called deprecated method {} (now {})",
+                        alias, request.method
+                    );
+                }
+            }
             debug!(
                 "Handling MCP request: {} (id: {:?})",
                 request.method, request.id
@@ -92,6 +1158,21 @@ where
                 return JsonRpcResponse::error(error.into(), request.id);
             }
 
+            if let Err(error) = self.check_client_rate_limit(&context).await {
+                return JsonRpcResponse::error(error.into(), request.id);
+            }
+
+            // Requests hold their approximate size while they run; over the
+            // soft limit caches are evicted and expensive requests shed
+            let size = match &request.params {
+                Some(params) if self.memory.config().enabled => approximate_size(params),
+                _ => 0,
+            };
+            let _reservation = self.memory.reserve(size);
+            if let Err(error) = self.memory.check(&request.method) {
+                return JsonRpcResponse::error(error.into(), request.id);
+            }
+
             // Parse the method
             let method = match self.parse_method(&request.method) {
                 Ok(method) => method,
@@ -100,25 +1181,102 @@ where
                 }
             };
 
+            // Heavy requests wait for a slot in the scheduler's queue
+            let priority =
+                self.scheduler
+                    .priority(&request.method, request.params.as_ref(), &context);
+
             // Handle the request based on method type
-            let result = match method {
-                InternalMcpMethod::Standard(standard_method) => {
-                    self.handle_standard_method(standard_method, request.params, &context)
-                        .await
+            let params = request.params;
+            let routed = async {
+                match method {
+                    InternalMcpMethod::Standard(standard_method) => {
+                        self.handle_standard_method(standard_method, params, &context)
+                            .await
+                    }
+                    InternalMcpMethod::Custom(custom_method) if is_admin_method(&custom_method) => {
+                        self.handle_admin_method(&custom_method, params, &context)
+                            .await
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if TASK_METHODS.contains(&custom_method.as_str()) =>
+                    {
+                        self.handle_task_method(&custom_method, params, &context)
+                            .await
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if TOOLSET_METHODS.contains(&custom_method.as_str()) =>
+                    {
+                        self.handle_toolset_method(&custom_method, params, &context)
+                            .await
+                    }
+                    InternalMcpMethod::Custom(custom_method) if custom_method == RPC_DISCOVER => {
+                        Ok(Some(openrpc_document(
+                            &self.config.name,
+                            &self.config.version,
+                            &self.method_catalog(),
+                            &self.config.aliases,
+                        )))
+                    }
+                    InternalMcpMethod::Custom(custom_method) if custom_method == SERVER_INFO => {
+                        Ok(Some(serde_json::to_value(self.server_info())?))
+                    }
+                    InternalMcpMethod::Custom(custom_method) if custom_method == RESOURCES_DIFF => {
+                        self.handle_resources_diff(params, &context).await
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if custom_method == RESOURCES_VERSIONS =>
+                    {
+                        self.handle_resources_versions(params, &context).await
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if custom_method == RESOURCES_READ_MANY =>
+                    {
+                        self.handle_resources_read_many(params, &context).await
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if custom_method == NOTIFICATIONS_FILTER =>
+                    {
+                        let filter: NotificationFilter = match params {
+                            Some(params) => parse_params(NOTIFICATIONS_FILTER, Some(params))?,
+                            None => NotificationFilter::default(),
+                        };
+                        Ok(Some(serde_json::to_value(filter)?))
+                    }
+                    InternalMcpMethod::Custom(custom_method)
+                        if custom_method == RESOURCES_SEARCH =>
+                    {
+                        self.handle_resources_search(params, &context).await
+                    }
+                    InternalMcpMethod::Custom(custom_method) => {
+                        self.state
+                            .handle_custom_method(&custom_method, params, &context)
+                            .await
+                    }
                 }
-                InternalMcpMethod::Custom(custom_method) => {
-                    self.state
-                        .handle_custom_method(&custom_method, request.params, &context)
-                        .await
+            };
+            let scheduled = async {
+                match priority {
+                    Some(priority) => match self.scheduler.schedule(priority, routed).await {
+                        Ok(result) => result,
+                        Err(error) => Err(error),
+                    },
+                    None => routed.await,
                 }
             };
 
+            // Expensive requests are turned away while the server is overloaded
+            let result = match self.admission.run(&request.method, scheduled).await {
+                Ok(result) => result,
+                Err(error) => Err(error),
+            };
+
             // Convert result to JSON-RPC response
             match result {
                 Ok(Some(value)) => JsonRpcResponse::success(value, request.id),
                 Ok(None) => JsonRpcResponse::success(serde_json::Value::Null, request.id),
                 Err(error) => {
-                    error!("Request failed: {} - {}", request.method, error);
+                    error!("Request failed: {} - {}", request.method, error.report());
                     JsonRpcResponse::error(error.into(), request.id)
                 }
             }
@@ -134,17 +1292,53 @@ where
     ) -> McpResult<Option<serde_json::Value>> {
         match method {
             StandardMethod::Initialize => {
-                let init_params: InitializeParams = if let Some(params) = params {
-                    serde_json::from_value(params).map_err(|e| McpError::Protocol {
-                        message: format!("Invalid initialize params: {}", e),
-                    })?
-                } else {
-                    return Err(McpError::Protocol {
-                        message: "Initialize requires parameters".to_string(),
-                    });
-                };
+                let init_params: InitializeParams = parse_params("initialize", params)?;
 
-                let result = self.state.initialize(init_params).await?;
+                // Fingerprint the client, apply the client policy and let the
+                // auth implementation see the full client description
+                let fingerprint = ClientFingerprint::from_initialize(&init_params, &context.client);
+                self.config.security.client_policy.check(&fingerprint)?;
+
+                let mut client = context.client.clone();
+                client.fingerprint = Some(fingerprint);
+                self.state.auth_manager().authenticate(&client).await?;
+                info!(
+                    "Client initializing: {} {}",
+                    client
+                        .fingerprint
+                        .as_ref()
+                        .map_or("unknown", |fp| fp.client_type()),
+                    client
+                        .fingerprint
+                        .as_ref()
+                        .and_then(|fp| fp.version.as_deref())
+                        .unwrap_or("")
+                );
+
+                let mut result = self.state.initialize(init_params).await?;
+                self.insert_build_info(&mut result.server_info);
+                if self.audit_trail.is_some() && result.capabilities.resources.is_none() {
+                    result.capabilities.resources = Some(messages::ResourcesCapability {
+                        subscribe: false,
+                        list_changed: false,
+                    });
+                }
+                if self.tasks.is_enabled() {
+                    result
+                        .capabilities
+                        .experimental
+                        .insert(TASKS_CAPABILITY.to_string(), self.tasks.capability());
+                }
+                if self.toolsets.is_enabled() {
+                    result
+                        .capabilities
+                        .experimental
+                        .insert(TOOLSETS_CAPABILITY.to_string(), self.toolsets.capability());
+                }
+                result.capabilities.experimental.insert(
+                    NOTIFICATIONS_CAPABILITY.to_string(),
+                    serde_json::json!({"categories": NotificationCategory::ALL}),
+                );
                 Ok(Some(serde_json::to_value(result)?))
             }
 
@@ -162,38 +1356,154 @@ where
 
             StandardMethod::Ping => {
                 // Simple ping/pong for health checking
-                Ok(Some(serde_json::json!({"status": "pong"})))
+                Ok(Some(serde_json::to_value(PingResult::pong())?))
             }
 
             StandardMethod::ToolsList => {
-                let tools = self.state.tool_registry().list_tools(context).await?;
-                let result = ToolsListResult {
-                    tools,
-                    next_cursor: None,
+                let registry = self.tools();
+                let (mut tools, next_cursor) = match self.config.pagination.page_size {
+                    Some(limit) => {
+                        let page = registry
+                            .list_tools_page(context, list_cursor(params.as_ref()), limit)
+                            .await?;
+                        (page.items, page.next_cursor)
+                    }
+                    None => (registry.list_tools(context).await?, None),
                 };
+                if self.toolsets.is_enabled() {
+                    let store = self.session_store(context).await;
+                    let enabled = self.toolsets.enabled_in(store.as_ref()).await;
+                    tools = self.toolsets.filter(tools, &enabled);
+                }
+                if self.config.authorize_tool_listing {
+                    tools = self.authorized_tools(tools, context).await;
+                }
+                for tool in &mut tools {
+                    let read_only = is_read_only(tool);
+                    self.maintenance
+                        .annotate_tool(&tool.name, read_only, &mut tool.metadata);
+                }
+                let result = ToolsListResult { tools, next_cursor };
                 Ok(Some(serde_json::to_value(result)?))
             }
 
             StandardMethod::ToolsCall => {
-                let call_params: ToolsCallParams = if let Some(params) = params {
-                    serde_json::from_value(params).map_err(|e| McpError::Protocol {
-                        message: format!("Invalid tools/call params: {}", e),
-                    })?
-                } else {
-                    return Err(McpError::Protocol {
-                        message: "tools/call requires parameters".to_string(),
-                    });
+                let progress_token = params.as_ref().and_then(progress_token);
+                let token_budget = self.tokens.budget(params.as_ref());
+                let call_params: ToolsCallParams = parse_params("tools/call", params)?;
+                let session_store = self.session_store(context).await;
+                if self.toolsets.is_enabled() {
+                    let enabled = self.toolsets.enabled_in(session_store.as_ref()).await;
+                    if !self.toolsets.allows(&call_params.name, &enabled) {
+                        return Err(McpError::ToolNotFound {
+                            name: call_params.name,
+                        });
+                    }
+                }
+                let read_only = match self.maintenance.active_window() {
+                    Some(_) => self
+                        .tools()
+                        .get_tool(&call_params.name, context)
+                        .await?
+                        .is_some_and(|tool| tool.is_read_only()),
+                    None => true,
                 };
+                self.maintenance.check_tool(&call_params.name, read_only)?;
+                let declared = self
+                    .tools()
+                    .get_tool(&call_params.name, context)
+                    .await
+                    .ok()
+                    .flatten();
+                if let Some(tool) = &declared {
+                    self.tool_limits.check(&tool.tool, context).await?;
+                }
+                if let Some(quotas) = &self.quotas {
+                    quotas.consume(context, QuotaMetric::ToolCalls, 1).await?;
+                }
 
-                let execution_context = ToolExecutionContext::new(context.clone())
-                    .with_arguments(call_params.arguments.unwrap_or(serde_json::Value::Null));
+                let arguments = call_params.arguments.unwrap_or(serde_json::Value::Null);
+                if self.requires_approval(&call_params.name, context).await {
+                    self.await_approval(
+                        &call_params.name,
+                        context,
+                        arguments.clone(),
+                        progress_token.as_deref(),
+                    )
+                    .await?;
+                }
+                let written = written_resources(declared.as_ref(), &arguments);
+                let audited_arguments = self.audit_trail.as_ref().map(|_| arguments.clone());
+                let mut execution_context = ToolExecutionContext::new(context.clone())
+                    .with_arguments(arguments)
+                    .with_progress_reporter(self.progress_reporter())
+                    .with_serialization(self.config.serialization.clone());
+                if let Some(services) = self.state.services() {
+                    execution_context = execution_context.with_services(services.clone());
+                }
+                if let Some(progress_token) = progress_token {
+                    execution_context = execution_context.with_request_id(progress_token);
+                }
+                if let Some(store) = session_store {
+                    execution_context = execution_context.with_session_store(store);
+                }
+                if !self.secrets.is_empty() {
+                    execution_context = execution_context.with_secrets(self.secrets.clone());
+                }
 
+                // Held until the tool finishes
+                let _permit = self.principal_limits.acquire(context).await?;
+                let started_at = chrono::Utc::now();
+                let started = std::time::Instant::now();
                 let result = self
-                    .state
-                    .tool_registry()
+                    .tools()
                     .execute_tool(&call_params.name, execution_context)
-                    .await?;
+                    .await;
+                match &result {
+                    Ok(result) => {
+                        self.webhooks
+                            .tool_outcome(&call_params.name, result.is_error, None)
+                    }
+                    Err(error) => self.webhooks.tool_outcome(
+                        &call_params.name,
+                        true,
+                        Some(&self.scrubbed(&error.client_message())),
+                    ),
+                }
+
+                if let (Some(audit_trail), Some(arguments)) = (&self.audit_trail, audited_arguments)
+                {
+                    audit_trail
+                        .record(ToolExecutionRecord::new(
+                            &call_params.name,
+                            context,
+                            &arguments,
+                            started_at,
+                            started.elapsed(),
+                            match ExecutionOutcome::from_result(&result) {
+                                ExecutionOutcome::Failed { error } => ExecutionOutcome::Failed {
+                                    error: self.scrubbed(&error).into_owned(),
+                                },
+                                outcome => outcome,
+                            },
+                        ))
+                        .await;
+                }
 
+                let mut result = result?;
+                if !result.is_error {
+                    self.resources_written(&written).await;
+                }
+                self.config.serialization.apply_to_tool_result(&mut result);
+                self.formatters
+                    .apply(&call_params.name, &mut result, context)
+                    .await;
+                if self.scrubber.scrubs_results_of(&call_params.name) {
+                    self.scrubber.scrub_tool_result(&mut result);
+                }
+                self.tokens
+                    .apply_to_tool_result(&mut result, token_budget)
+                    .await;
                 Ok(Some(serde_json::to_value(result)?))
             }
 
@@ -204,35 +1514,52 @@ where
                     });
                 }
 
-                let batch_params: BatchParams = if let Some(params) = params {
-                    serde_json::from_value(params).map_err(|e| McpError::Protocol {
-                        message: format!("Invalid batch params: {}", e),
-                    })?
-                } else {
-                    return Err(McpError::Protocol {
-                        message: "batch requires parameters".to_string(),
-                    });
-                };
+                let batch_params: BatchParams = parse_params("batch", params)?;
 
                 let result = self.handle_batch_request(batch_params, context).await?;
                 Ok(Some(serde_json::to_value(result)?))
             }
 
             StandardMethod::ResourcesList => {
-                if let Some(resource_registry) = self.state.resource_registry() {
-                    let templates = resource_registry.list_resource_templates(context).await?;
+                let registries = self.resource_registries();
+                if !registries.is_empty() {
+                    let (templates, next_cursor) = match self.config.pagination.page_size {
+                        Some(limit) => {
+                            let page = list_templates_page(
+                                &registries,
+                                list_cursor(params.as_ref()),
+                                limit,
+                                context,
+                            )
+                            .await?;
+                            (page.items, page.next_cursor)
+                        }
+                        None => {
+                            let mut templates = Vec::new();
+                            for registry in registries {
+                                templates.extend(registry.list_resource_templates(context).await?);
+                            }
+                            (templates, None)
+                        }
+                    };
                     let result = crate::protocol::ResourcesListResult {
                         resources: templates
                             .into_iter()
-                            .map(|template| crate::protocol::messages::Resource {
-                                uri: template.uri_template,
-                                name: template.name,
-                                description: template.description,
-                                mime_type: template.mime_type,
-                                metadata: template.metadata,
+                            .map(|template| {
+                                let mut metadata: Map<_, _> =
+                                    template.metadata.into_iter().collect();
+                                self.maintenance
+                                    .annotate_uri(&template.uri_template, &mut metadata);
+                                crate::protocol::messages::Resource {
+                                    uri: template.uri_template,
+                                    name: template.name,
+                                    description: template.description,
+                                    mime_type: template.mime_type,
+                                    metadata,
+                                }
                             })
                             .collect(),
-                        next_cursor: None,
+                        next_cursor,
                     };
                     Ok(Some(serde_json::to_value(result)?))
                 } else {
@@ -243,25 +1570,59 @@ where
             }
 
             StandardMethod::ResourcesRead => {
-                if let Some(resource_registry) = self.state.resource_registry() {
+                let registries = self.resource_registries();
+                if !registries.is_empty() {
                     let read_params: crate::protocol::ResourcesReadParams =
-                        if let Some(params) = params {
-                            serde_json::from_value(params).map_err(|e| McpError::Protocol {
-                                message: format!("Invalid resources/read params: {}", e),
-                            })?
-                        } else {
-                            return Err(McpError::Protocol {
-                                message: "resources/read requires parameters".to_string(),
-                            });
-                        };
+                        parse_params("resources/read", params)?;
+                    self.maintenance.check_uri(&read_params.uri)?;
+                    if let Some(quotas) = &self.quotas {
+                        quotas
+                            .ensure_available(context, QuotaMetric::ResourceBytes)
+                            .await?;
+                    }
 
-                    let resource = resource_registry
-                        .get_resource(&read_params.uri, context)
-                        .await?;
+                    // `?format=` converts, `?version=N` reads an earlier version
+                    let (uri, format) = split_query_param(&read_params.uri, FORMAT_QUERY_PARAM);
+                    let (uri, version) = split_version(&uri)?;
+                    let registry = self.resource_registry_for(&registries, &uri);
+                    let accept = format.or(read_params.accept);
+
+                    // Current resources with several representations are
+                    // negotiated; the others are converted if need be
+                    let mut representation = None;
+                    if let (Some(accept), None) = (&accept, version) {
+                        let mime_types = registry.list_representations(&uri, context).await?;
+                        representation = negotiate(accept, &mime_types).map(str::to_string);
+                    }
+                    let mut resource = match &representation {
+                        Some(mime_type) => {
+                            registry
+                                .get_representation(&uri, mime_type, context)
+                                .await?
+                        }
+                        None => resource_at(registry, &uri, version, context).await?,
+                    };
+                    if uri != read_params.uri {
+                        resource.uri = read_params.uri;
+                    }
+
+                    let mut meta = Map::new();
+                    if let (Some(accept), None) = (&accept, &representation) {
+                        self.transform_resource(&mut resource, accept, &mut meta)
+                            .await?;
+                    }
 
                     // Convert ResourceContent from server to protocol
                     let protocol_content = match resource.content {
-                        crate::server::resource::ResourceContent::Text { text } => {
+                        crate::server::resource::ResourceContent::Text { mut text } => {
+                            if let Some(reduction) =
+                                self.tokens.reduce_resource(&resource.uri, &mut text).await
+                            {
+                                meta.insert(
+                                    REDUCED_RESOURCES_META.to_string(),
+                                    serde_json::Value::Array(vec![reduction]),
+                                );
+                            }
                             crate::protocol::messages::ResourceContent::Text {
                                 text,
                                 uri: resource.uri.clone(),
@@ -277,8 +1638,40 @@ where
                         }
                     };
 
+                    let body = match &protocol_content {
+                        crate::protocol::messages::ResourceContent::Text { text, .. } => {
+                            text.as_bytes()
+                        }
+                        crate::protocol::messages::ResourceContent::Blob { blob, .. } => {
+                            blob.as_bytes()
+                        }
+                    };
+                    let etag = content_etag(body);
+                    let bytes = body.len();
+                    meta.insert(ETAG_META.to_string(), etag.clone().into());
+                    if read_params
+                        .if_none_match
+                        .is_some_and(|tags| etag_matches(&tags, &etag))
+                    {
+                        let result = crate::protocol::ResourcesReadResult {
+                            contents: Vec::new(),
+                            not_modified: true,
+                            meta,
+                        };
+                        return Ok(Some(serde_json::to_value(result)?));
+                    }
+
+                    if let Some(quotas) = &self.quotas {
+                        // Admitted reads are served in full, even past the limit
+                        quotas
+                            .record(context, QuotaMetric::ResourceBytes, bytes as u64)
+                            .await?;
+                    }
+
                     let result = crate::protocol::ResourcesReadResult {
                         contents: vec![protocol_content],
+                        not_modified: false,
+                        meta,
                     };
                     Ok(Some(serde_json::to_value(result)?))
                 } else {
@@ -290,25 +1683,37 @@ where
 
             StandardMethod::PromptsList => {
                 if let Some(prompt_registry) = self.state.prompt_registry() {
-                    let prompts = prompt_registry.list_prompts(context).await?;
-                    // For now, return a simple JSON response since the framework doesn't have PromptsListResult
-                    let result = serde_json::json!({
-                        "prompts": prompts.into_iter().map(|prompt| {
-                            serde_json::json!({
-                                "name": prompt.name,
-                                "description": prompt.description,
-                                "arguments": prompt.parameters.into_iter().map(|param| {
-                                    serde_json::json!({
-                                        "name": param.name,
-                                        "description": param.description,
-                                        "required": param.required,
-                                        "schema": param.schema
+                    let (prompts, next_cursor) = match self.config.pagination.page_size {
+                        Some(limit) => {
+                            let page = prompt_registry
+                                .list_prompts_page(context, list_cursor(params.as_ref()), limit)
+                                .await?;
+                            (page.items, page.next_cursor)
+                        }
+                        None => (prompt_registry.list_prompts(context).await?, None),
+                    };
+                    let result = PromptsListResult {
+                        prompts: prompts
+                            .into_iter()
+                            .map(|prompt| messages::Prompt {
+                                metadata: self.prompt_metadata(&prompt.name),
+                                name: prompt.name,
+                                description: Some(prompt.description),
+                                arguments: prompt
+                                    .parameters
+                                    .into_iter()
+                                    .map(|param| messages::PromptArgument {
+                                        name: param.name,
+                                        description: Some(param.description),
+                                        required: param.required,
+                                        schema: param.schema,
                                     })
-                                }).collect::<Vec<_>>()
+                                    .collect(),
                             })
-                        }).collect::<Vec<_>>()
-                    });
-                    Ok(Some(result))
+                            .collect(),
+                        next_cursor,
+                    };
+                    Ok(Some(serde_json::to_value(result)?))
                 } else {
                     Err(McpError::Protocol {
                         message: "Prompts not supported by this server".to_string(),
@@ -318,20 +1723,29 @@ where
 
             StandardMethod::PromptsGet => {
                 if let Some(prompt_registry) = self.state.prompt_registry() {
+                    let token_budget = self.tokens.budget(params.as_ref());
+                    // Budgets need the whole prompt, so limited requests are not streamed
+                    let stream = self
+                        .prompt_streams
+                        .take(params.as_ref())
+                        .filter(|_| !self.tokens.limits(token_budget));
                     let get_params: crate::server::prompt::GetPromptRequest =
-                        if let Some(params) = params {
-                            serde_json::from_value(params).map_err(|e| McpError::Protocol {
-                                message: format!("Invalid prompts/get params: {}", e),
-                            })?
-                        } else {
-                            return Err(McpError::Protocol {
-                                message: "prompts/get requires parameters".to_string(),
-                            });
-                        };
+                        parse_params("prompts/get", params)?;
+                    self.maintenance
+                        .check(MaintenanceKind::Prompt, &get_params.name)?;
 
-                    let result = prompt_registry
+                    if let Some(sender) = stream {
+                        let stream = prompt_registry
+                            .stream_prompt_with_args(get_params, context)
+                            .await?;
+                        let result = self.prompt_streams.forward(stream, sender).await?;
+                        return Ok(Some(serde_json::to_value(result)?));
+                    }
+
+                    let mut result = prompt_registry
                         .get_prompt_with_args(get_params, context)
                         .await?;
+                    self.tokens.apply_to_prompt(&mut result, token_budget).await;
                     Ok(Some(serde_json::to_value(result)?))
                 } else {
                     Err(McpError::Protocol {
@@ -349,123 +1763,812 @@ where
         }
     }
 
-    /// Handle a batch request
-    async fn handle_batch_request(
+    /// Handle the built-in `admin/*` methods, which require a system context
+    async fn handle_admin_method(
         &self,
-        batch: BatchParams,
+        method: &str,
+        params: Option<serde_json::Value>,
         context: &SecurityContext,
-    ) -> McpResult<BatchResult> {
-        if batch.requests.len() > self.config.max_batch_size {
-            return Err(McpError::Validation {
-                message: format!(
-                    "Batch size {} exceeds maximum {}",
-                    batch.requests.len(),
-                    self.config.max_batch_size
-                ),
+    ) -> McpResult<Option<serde_json::Value>> {
+        if !context.is_system() {
+            return Err(McpError::Authorization {
+                message: format!("{} requires a system context", method),
             });
         }
 
-        let batch_context = BatchContext {
-            mode: match batch.execution_mode {
-                crate::protocol::BatchExecutionMode::Parallel => {
-                    crate::server::BatchExecutionMode::Parallel
+        match method {
+            ADMIN_APPROVALS_LIST => Ok(Some(serde_json::json!({
+                "approvals": self.approvals.pending(),
+            }))),
+            ADMIN_APPROVALS_DECIDE => {
+                let params: ApprovalDecisionParams = parse_params(method, params)?;
+                let request = self.approvals.decide(&params.id, params.decision)?;
+                Ok(Some(serde_json::json!({ "approval": request })))
+            }
+            ADMIN_QUOTAS_INSPECT | ADMIN_QUOTAS_RESET => {
+                let quotas = self.quotas.as_ref().ok_or_else(|| McpError::Protocol {
+                    message: "Quotas are not enabled on this server".to_string(),
+                })?;
+                let params: QuotaParams = match params {
+                    Some(params) => parse_params(method, Some(params))?,
+                    None => QuotaParams::default(),
+                };
+
+                match (method, params.principal) {
+                    (ADMIN_QUOTAS_INSPECT, None) => Ok(Some(serde_json::json!({
+                        "quotas": quotas.list().await?,
+                    }))),
+                    (ADMIN_QUOTAS_INSPECT, Some(principal)) => Ok(Some(serde_json::json!({
+                        "quotas": [quotas.status(&principal).await?],
+                    }))),
+                    (_, Some(principal)) => {
+                        quotas.reset(&principal).await?;
+                        Ok(Some(serde_json::json!({
+                            "quotas": [quotas.status(&principal).await?],
+                        })))
+                    }
+                    (_, None) => Err(McpError::Validation {
+                        message: format!("{} requires a principal", method),
+                    }),
                 }
-                crate::protocol::BatchExecutionMode::Sequential => {
-                    crate::server::BatchExecutionMode::Sequential
+            }
+            ADMIN_INSPECTOR_LIST => {
+                let params: InspectorListParams = match params {
+                    Some(params) => parse_params(method, Some(params))?,
+                    None => InspectorListParams::default(),
+                };
+                Ok(Some(serde_json::json!({
+                    "enabled": self.inspector.is_enabled(),
+                    "exchanges": self.inspector.recent(params.limit),
+                })))
+            }
+            ADMIN_INSPECTOR_CONFIGURE => {
+                let params: InspectorConfigureParams = parse_params(method, params)?;
+                if let Some(enabled) = params.enabled {
+                    self.inspector.set_enabled(enabled);
+                    info!(
+                        "Request inspector switched {}",
+                        if enabled { "on" } else { "off" }
+                    );
                 }
-                crate::protocol::BatchExecutionMode::Dependency => {
-                    crate::server::BatchExecutionMode::Sequential
+                if params.clear {
+                    self.inspector.clear();
                 }
-                crate::protocol::BatchExecutionMode::PriorityDependency => {
-                    crate::server::BatchExecutionMode::Sequential
+                Ok(Some(serde_json::json!({
+                    "enabled": self.inspector.is_enabled(),
+                    "captured": self.inspector.len(),
+                })))
+            }
+            ADMIN_WIRE_TAP => {
+                let params: WireTapParams = match params {
+                    Some(params) => parse_params(method, Some(params))?,
+                    None => WireTapParams::default(),
+                };
+                if let Some(enabled) = params.enabled {
+                    match &params.session_id {
+                        Some(session_id) => self.wire_tap.set_session(session_id, enabled),
+                        None => self.wire_tap.set_all_sessions(enabled),
+                    }
+                    info!(
+                        "Wire tap switched {} for {}",
+                        if enabled { "on" } else { "off" },
+                        params.session_id.as_deref().unwrap_or("all sessions")
+                    );
                 }
-            },
-            max_parallel: batch.max_parallel.map(|v| v as usize),
-            timeout: batch.timeout_ms.map(std::time::Duration::from_millis),
-            security: context.clone(),
-        };
+                Ok(Some(serde_json::to_value(self.wire_tap.status())?))
+            }
+            ADMIN_TOOLSETS_CONFIGURE => {
+                let params: ToolsetsConfigureParams = parse_params(method, params)?;
+                let store = self.session_stores.store(&params.session_id).await;
+                let enabled = self
+                    .toolsets
+                    .configure(&store, &params.enable, &params.disable)
+                    .await?;
+                Ok(Some(serde_json::json!({
+                    "toolsets": self.toolsets.describe(&enabled),
+                })))
+            }
+            ADMIN_MAINTENANCE_LIST => Ok(Some(serde_json::json!({
+                "maintenance": self.maintenance.list(),
+                "windows": self.maintenance.windows(),
+            }))),
+            ADMIN_MAINTENANCE_SET => {
+                let params: MaintenanceParams = parse_params(method, params)?;
+                let entry = self
+                    .maintenance
+                    .set(params.kind, params.name, params.message);
+                Ok(Some(serde_json::json!({ "maintenance": entry })))
+            }
+            ADMIN_MAINTENANCE_CLEAR => {
+                let params: MaintenanceParams = parse_params(method, params)?;
+                let entry = self.maintenance.clear(params.kind, &params.name);
+                Ok(Some(serde_json::json!({ "cleared": entry.is_some() })))
+            }
+            ADMIN_REGISTRY_CHANGELOG => {
+                let changelog =
+                    self.registry_changelog
+                        .as_ref()
+                        .ok_or_else(|| McpError::Protocol {
+                            message: "Registry changelog is not enabled on this server".to_string(),
+                        })?;
+                let query: ChangelogQuery = match params {
+                    Some(params) => parse_params(method, Some(params))?,
+                    None => ChangelogQuery::default(),
+                };
+                Ok(Some(serde_json::json!({
+                    "changes": changelog.changes(&query),
+                })))
+            }
+            ADMIN_DEPENDENCIES => {
+                let tools = self.tools().list_tools(context).await?;
+                let graph = DependencyGraph::of(&tools);
+                Ok(Some(serde_json::json!({
+                    "edges": graph.edges,
+                    "dot": graph.to_dot(),
+                })))
+            }
+            _ => Err(McpError::ToolNotFound {
+                name: method.to_string(),
+            }),
+        }
+    }
 
-        let progress_id = uuid::Uuid::new_v4().to_string();
-        let total_items = batch.requests.len();
+    /// `text` with personal data scrubbed, if scrubbing is on
+    fn scrubbed<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        if self.scrubber.is_enabled() {
+            self.scrubber.scrub_str(text)
+        } else {
+            std::borrow::Cow::Borrowed(text)
+        }
+    }
 
-        // Send initial progress update
-        self.progress_reporter
-            .report_progress(ProgressUpdate::started(
-                progress_id.clone(),
-                "Processing batch request".to_string(),
-                total_items,
-            ))
+    /// The tools the auth allows `context` to list, checked in one batch
+    async fn authorized_tools(&self, tools: Vec<Tool>, context: &SecurityContext) -> Vec<Tool> {
+        let resources: Vec<String> = tools
+            .iter()
+            .map(|tool| format!("{}{}", TOOL_RESOURCE_PREFIX, tool.name))
+            .collect();
+        let checks: Vec<(&str, &str)> = resources
+            .iter()
+            .map(|resource| (resource.as_str(), LIST_ACTION))
+            .collect();
+        let allowed = self
+            .state
+            .auth_manager()
+            .authorize_many(context, &checks)
             .await;
+        tools
+            .into_iter()
+            .zip(allowed)
+            .filter_map(|(tool, allowed)| allowed.then_some(tool))
+            .collect()
+    }
 
-        let batch_requests = batch.requests.clone();
-        let results = match batch_context.mode {
-            crate::server::BatchExecutionMode::Parallel => {
-                self.execute_batch_parallel(batch_requests, &batch_context, &progress_id)
-                    .await
+    /// Metadata of a listed prompt, annotated if it is in maintenance
+    fn prompt_metadata(&self, name: &str) -> crate::protocol::Map<String, serde_json::Value> {
+        let mut metadata = crate::protocol::Map::new();
+        self.maintenance
+            .annotate(MaintenanceKind::Prompt, name, &mut metadata);
+        metadata
+    }
+
+    /// Handle the `toolsets/*` methods for the caller's session
+    async fn handle_toolset_method(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        if !self.toolsets.is_enabled() {
+            return Err(McpError::Protocol {
+                message: "Toolsets are not enabled on this server".to_string(),
+            });
+        }
+
+        let store = self.session_store(context).await;
+        let enabled = match (method, &store) {
+            (TOOLSETS_ENABLE | TOOLSETS_DISABLE, None) => {
+                return Err(McpError::Validation {
+                    message: "Toolsets can only be switched within a session".to_string(),
+                })
             }
-            crate::server::BatchExecutionMode::Sequential
-            | crate::server::BatchExecutionMode::FailFast => {
-                self.execute_batch_sequential(batch_requests, &batch_context, &progress_id)
+            (TOOLSETS_ENABLE, Some(store)) => {
+                let params: ToolsetsParams = parse_params(method, params)?;
+                self.toolsets
+                    .configure(store, &params.toolsets, &[])
+                    .await?
+            }
+            (TOOLSETS_DISABLE, Some(store)) => {
+                let params: ToolsetsParams = parse_params(method, params)?;
+                self.toolsets
+                    .configure(store, &[], &params.toolsets)
+                    .await?
+            }
+            _ => self.toolsets.enabled_in(store.as_ref()).await,
+        };
+        Ok(Some(serde_json::json!({
+            "toolsets": self.toolsets.describe(&enabled),
+        })))
+    }
+
+    /// Working memory of the caller's session, or of its client without one
+    async fn session_store(&self, context: &SecurityContext) -> Option<SessionStore> {
+        let scope = context
+            .client
+            .session_id
+            .as_deref()
+            .or(context.client.client_id.as_deref())?;
+        Some(self.session_stores.store(scope).await)
+    }
+
+    /// Handle the experimental `tasks/*` methods
+    async fn handle_task_method(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        if !self.tasks.is_enabled() {
+            return Err(McpError::Protocol {
+                message: "Tasks are not enabled on this server".to_string(),
+            });
+        }
+
+        let info = match method {
+            TASKS_CREATE => {
+                let call_params: ToolsCallParams = parse_params(method, params.clone())?;
+                if !self
+                    .tools()
+                    .can_access_tool(&call_params.name, context)
                     .await
+                {
+                    return Err(McpError::ToolNotFound {
+                        name: call_params.name,
+                    });
+                }
+
+                // The call runs as if sent as `tools/call`, after this request is answered
+                let server = self.clone();
+                let caller = context.clone();
+                self.tasks.spawn(context, &call_params.name, async move {
+                    server
+                        .handle_standard_method(StandardMethod::ToolsCall, params, &caller)
+                        .await
+                        .map(Option::unwrap_or_default)
+                })?
+            }
+            TASKS_STATUS => {
+                let params: TaskParams = parse_params(method, params)?;
+                self.tasks.status(context, &params.task_id)?
+            }
+            TASKS_RESULT => {
+                let params: TaskParams = parse_params(method, params)?;
+                return self.tasks.result(context, &params.task_id).map(Some);
+            }
+            TASKS_CANCEL => {
+                let params: TaskParams = parse_params(method, params)?;
+                self.tasks.cancel(context, &params.task_id)?
+            }
+            _ => {
+                return Ok(Some(serde_json::json!({
+                    "tasks": self.tasks.list(context),
+                })))
             }
         };
+        Ok(Some(serde_json::json!({ "task": info })))
+    }
 
-        // Send completion progress update
-        self.progress_reporter
-            .report_progress(ProgressUpdate::completed(
-                progress_id,
-                "Batch request completed".to_string(),
-            ))
+    /// Tools of the state, with the smoke-test tools when enabled
+    fn tools(&self) -> ServerTools<'_, S> {
+        ServerTools { server: self }
+    }
+
+    /// Whether calls to a tool must be approved first
+    async fn requires_approval(&self, name: &str, context: &SecurityContext) -> bool {
+        // Lookup errors surface when the tool is executed
+        matches!(
+            self.tools().get_tool(name, context).await,
+            Ok(Some(tool)) if tool.requires_approval
+        )
+    }
+
+    /// Hold a tool call until it is approved, reporting status via progress
+    async fn await_approval(
+        &self,
+        tool: &str,
+        context: &SecurityContext,
+        arguments: serde_json::Value,
+        progress_token: Option<&str>,
+    ) -> McpResult<()> {
+        let request = self.approvals.new_request(tool, context, arguments);
+        let approval_id = request.id.clone();
+        self.report_approval_status(
+            progress_token,
+            &approval_id,
+            0,
+            ProgressLevel::Info,
+            format!("Awaiting approval to call {}", tool),
+        )
+        .await;
+
+        let result = self.approvals.await_decision(request).await;
+        let (level, message) = match &result {
+            Ok(()) => (ProgressLevel::Info, format!("Call to {} approved", tool)),
+            Err(e) => (ProgressLevel::Warning, e.client_message()),
+        };
+        self.report_approval_status(progress_token, &approval_id, 1, level, message)
             .await;
+        result
+    }
 
-        let successful_count = results.iter().filter(|r| r.error.is_none()).count() as u32;
-        let failed_count = results.iter().filter(|r| r.error.is_some()).count() as u32;
+    async fn report_approval_status(
+        &self,
+        progress_token: Option<&str>,
+        approval_id: &str,
+        step: usize,
+        level: ProgressLevel,
+        message: String,
+    ) {
+        if let Some(progress_token) = progress_token {
+            self.progress_reporter
+                .report_progress(
+                    ProgressUpdate::new(progress_token.to_string(), message, level, step, 0)
+                        .with_metadata("approvalId", approval_id.into()),
+                )
+                .await;
+        }
+    }
 
-        Ok(BatchResult {
-            stats: crate::protocol::BatchStats {
-                total_requests: total_items as u32,
-                successful_requests: successful_count,
-                failed_requests: failed_count,
-                skipped_requests: 0,
-                total_execution_time_ms: 0, // TODO: Calculate actual time
-                average_execution_time_ms: 0.0, // TODO: Calculate actual time
-                max_parallel_executed: batch_context.max_parallel.unwrap_or(1) as u32,
-            },
-            results,
-            correlation_token: None,
-            metadata: std::collections::HashMap::new(),
-        })
+    /// Resource registries serving this server: the application's, then the built-in ones
+    fn resource_registries(&self) -> Vec<&dyn ResourceRegistry> {
+        let mut registries = Vec::new();
+        registries.extend(self.state.resource_registry());
+        if let Some(audit_trail) = &self.audit_trail {
+            registries.push(audit_trail as &dyn ResourceRegistry);
+        }
+        registries
     }
 
-    /// Execute batch items in parallel
-    async fn execute_batch_parallel(
+    /// Registry serving `uri`; built-in schemes take precedence over the application registry
+    fn resource_registry_for<'a>(
         &self,
-        items: Vec<crate::protocol::BatchRequest>,
-        context: &BatchContext,
-        progress_id: &str,
-    ) -> Vec<BatchItemResult> {
-        use futures_util::stream::{self, StreamExt};
+        registries: &[&'a dyn ResourceRegistry],
+        uri: &str,
+    ) -> &'a dyn ResourceRegistry {
+        registries
+            .iter()
+            .rev()
+            .find(|registry| registry.can_handle_uri(uri))
+            .copied()
+            .unwrap_or(registries[0])
+    }
 
-        let max_parallel = context.max_parallel.unwrap_or(10).min(items.len());
-        let mut completed = 0;
+    /// Invalidate and announce the resources matching `patterns` after a
+    /// tool wrote them
+    ///
+    /// Patterns with wildcards are matched against the resources the
+    /// registries list to a system context.
+    async fn resources_written(&self, patterns: &[String]) {
+        let registries = self.resource_registries();
+        if patterns.is_empty() || registries.is_empty() {
+            return;
+        }
+        let mut uris = std::collections::BTreeSet::new();
+        for pattern in patterns {
+            if !pattern.contains('*') {
+                uris.insert(pattern.clone());
+                continue;
+            }
+            for registry in &registries {
+                let templates = registry
+                    .list_resource_templates(&SecurityContext::system())
+                    .await
+                    .unwrap_or_default();
+                uris.extend(
+                    templates
+                        .into_iter()
+                        .map(|template| template.uri_template)
+                        .filter(|uri| dependencies::matches(pattern, uri)),
+                );
+            }
+        }
+        for uri in uris {
+            self.resource_registry_for(&registries, &uri)
+                .invalidate(&uri);
+            self.resource_changed(ResourceChanged {
+                uri,
+                change_type: ResourceChangeType::Updated,
+                content: None,
+            });
+        }
+    }
 
-        stream::iter(items)
-            .map(|item| async move {
-                // Prevent batch requests within batch requests to avoid recursion
-                if item.method == "batch" {
-                    completed += 1;
+    /// Convert a resource to the first acceptable format
+    async fn transform_resource(
+        &self,
+        resource: &mut Resource,
+        accept: &str,
+        meta: &mut Map<String, serde_json::Value>,
+    ) -> McpResult<()> {
+        let source = match &resource.content {
+            ResourceContent::Text { .. } => resource.mime_type.clone().unwrap_or_default(),
+            ResourceContent::Blob { mime_type, .. } => mime_type.clone(),
+        };
+        let Some(transformer) = self.transformers.select(&resource.uri, &source, accept)? else {
+            return Ok(());
+        };
+        let text = match &resource.content {
+            ResourceContent::Text { text } => transformer.transform(&resource.uri, text).await?,
+            ResourceContent::Blob { blob, .. } => {
+                use base64::Engine;
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(blob)
+                    .map_err(|e| McpError::InvalidResource {
+                        uri: resource.uri.clone(),
+                        message: format!("Invalid base64 content: {}", e),
+                    })?;
+                transformer.transform_bytes(&resource.uri, &bytes).await?
+            }
+        };
+        resource.content = ResourceContent::Text { text };
+        resource.mime_type = Some(transformer.target().to_string());
+        meta.insert(TRANSFORMED_FROM_META.to_string(), source.into());
+        Ok(())
+    }
 
-                    // Report progress
-                    self.progress_reporter
-                        .report_progress(ProgressUpdate::progress(
-                            progress_id.to_string(),
-                            format!("Processed {} items", completed),
-                            completed,
-                        ))
-                        .await;
+    /// Handle `resources/diff`
+    async fn handle_resources_diff(
+        &self,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        let params: ResourcesDiffParams = parse_params(RESOURCES_DIFF, params)?;
+        if params.content.is_some() && params.to_version.is_some() {
+            return Err(McpError::Validation {
+                message: "resources/diff takes either toVersion or content".to_string(),
+            });
+        }
+        let registries = self.resource_registries();
+        if registries.is_empty() {
+            return Err(McpError::Protocol {
+                message: "Resources not supported by this server".to_string(),
+            });
+        }
+        let registry = self.resource_registry_for(&registries, &params.uri);
 
-                    return BatchItemResult {
-                        id: item.id,
+        let label = |version: Option<u64>| match version {
+            Some(version) => format!("{}@{}", params.uri, version),
+            None => params.uri.clone(),
+        };
+
+        let old = resource_text(registry, &params.uri, params.from_version, context).await?;
+        let (new, new_label) = match &params.content {
+            Some(content) => (content.clone(), format!("{} (provided)", params.uri)),
+            None => (
+                resource_text(registry, &params.uri, params.to_version, context).await?,
+                label(params.to_version),
+            ),
+        };
+        let diff = unified_diff(
+            &old,
+            &new,
+            &label(params.from_version),
+            &new_label,
+            params.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES),
+        );
+        Ok(Some(serde_json::to_value(ResourcesDiffResult {
+            uri: params.uri.clone(),
+            diff: diff.unified,
+            additions: diff.additions,
+            deletions: diff.deletions,
+        })?))
+    }
+
+    /// Handle `resources/versions`
+    async fn handle_resources_versions(
+        &self,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        let params: ResourcesVersionsParams = parse_params(RESOURCES_VERSIONS, params)?;
+        let registries = self.resource_registries();
+        if registries.is_empty() {
+            return Err(McpError::Protocol {
+                message: "Resources not supported by this server".to_string(),
+            });
+        }
+        let versions = self
+            .resource_registry_for(&registries, &params.uri)
+            .as_versioned()
+            .ok_or_else(|| unversioned(&params.uri))?
+            .list_versions(&params.uri, context)
+            .await?;
+        Ok(Some(serde_json::json!({
+            "uri": params.uri,
+            "versions": versions,
+        })))
+    }
+
+    /// Handle `resources/read_many`
+    ///
+    /// Reads run like `resources/read`, up to `max_parallel` at once, and
+    /// fail individually, including when the shared deadline passes.
+    async fn handle_resources_read_many(
+        &self,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        use futures_util::stream::{self, StreamExt};
+
+        let params: ResourcesReadManyParams = parse_params(RESOURCES_READ_MANY, params)?;
+        let limits = &self.config.read_many;
+        if params.uris.len() > limits.max_uris {
+            return Err(McpError::Validation {
+                message: format!(
+                    "{} reads at most {} URIs, got {}",
+                    RESOURCES_READ_MANY,
+                    limits.max_uris,
+                    params.uris.len()
+                ),
+            });
+        }
+        if self.resource_registries().is_empty() {
+            return Err(McpError::Protocol {
+                message: "Resources not supported by this server".to_string(),
+            });
+        }
+
+        let timeout = limits.deadline(params.timeout_ms);
+        let deadline = tokio::time::Instant::now() + timeout;
+        let accept = params.accept;
+        let results = stream::iter(params.uris)
+            .map(|uri| {
+                let read_params = serde_json::json!({"uri": uri, "accept": accept});
+                async move {
+                    let read = self.handle_standard_method(
+                        StandardMethod::ResourcesRead,
+                        Some(read_params),
+                        context,
+                    );
+                    let result = match tokio::time::timeout_at(deadline, read).await {
+                        Ok(Ok(Some(value))) => serde_json::from_value(value).map_err(Into::into),
+                        Ok(Ok(None)) => Err(McpError::Internal {
+                            message: format!("resources/read of {} returned nothing", uri),
+                            source: None,
+                        }),
+                        Ok(Err(error)) => Err(error),
+                        Err(_) => Err(McpError::ServerTimeout { timeout }),
+                    };
+                    match result {
+                        Ok(result) => ResourceReadOutcome {
+                            uri,
+                            result: Some(result),
+                            error: None,
+                        },
+                        Err(error) => ResourceReadOutcome {
+                            uri,
+                            result: None,
+                            error: Some(error.into()),
+                        },
+                    }
+                }
+            })
+            .buffered(limits.max_parallel.max(1))
+            .collect()
+            .await;
+        Ok(Some(serde_json::to_value(ResourcesReadManyResult {
+            results,
+        })?))
+    }
+
+    /// Handle `resources/search`
+    ///
+    /// Hits the caller cannot see are dropped, so fewer than `limit` may come back.
+    async fn handle_resources_search(
+        &self,
+        params: Option<serde_json::Value>,
+        context: &SecurityContext,
+    ) -> McpResult<Option<serde_json::Value>> {
+        let params: ResourcesSearchParams = parse_params(RESOURCES_SEARCH, params)?;
+        let Some(search) = &self.search else {
+            return Err(search_disabled());
+        };
+        for uri in search.take_stale() {
+            if let Err(error) = self.index_resource(&uri).await {
+                debug!("Failed to re-index {}: {}", uri, error);
+                search.mark_stale(&uri);
+            }
+        }
+
+        let registries = self.resource_registries();
+        let mut results = Vec::new();
+        for hit in search.index().search(&params) {
+            if registries.is_empty() {
+                break;
+            }
+            let visible = self
+                .resource_registry_for(&registries, &hit.uri)
+                .resource_exists(&hit.uri, context)
+                .await
+                .unwrap_or(false);
+            if visible {
+                results.push(hit);
+            }
+        }
+        Ok(Some(serde_json::to_value(ResourcesSearchResult {
+            results,
+        })?))
+    }
+
+    /// Handle a batch request
+    async fn handle_batch_request(
+        &self,
+        batch: BatchParams,
+        context: &SecurityContext,
+    ) -> McpResult<BatchResult> {
+        if batch.requests.len() > self.config.max_batch_size {
+            return Err(McpError::Validation {
+                message: format!(
+                    "Batch size {} exceeds maximum {}",
+                    batch.requests.len(),
+                    self.config.max_batch_size
+                ),
+            });
+        }
+        let order = match batch.execution_mode {
+            crate::protocol::BatchExecutionMode::Dependency => {
+                Some(batch_template::dependency_order(&batch.requests, false)?)
+            }
+            crate::protocol::BatchExecutionMode::PriorityDependency => {
+                Some(batch_template::dependency_order(&batch.requests, true)?)
+            }
+            _ => None,
+        };
+        if let Some(quotas) = &self.quotas {
+            quotas
+                .consume(
+                    context,
+                    QuotaMetric::BatchItems,
+                    batch.requests.len() as u64,
+                )
+                .await?;
+        }
+
+        // A batch sent again with its correlation token skips completed items
+        let progress = match &batch.correlation_token {
+            Some(token) => self.batches.begin(context, token).await,
+            None => None,
+        };
+
+        let batch_context = BatchContext {
+            mode: match batch.execution_mode {
+                crate::protocol::BatchExecutionMode::Parallel => {
+                    crate::server::BatchExecutionMode::Parallel
+                }
+                crate::protocol::BatchExecutionMode::Sequential => {
+                    crate::server::BatchExecutionMode::Sequential
+                }
+                crate::protocol::BatchExecutionMode::Dependency => {
+                    crate::server::BatchExecutionMode::Dependency
+                }
+                crate::protocol::BatchExecutionMode::PriorityDependency => {
+                    crate::server::BatchExecutionMode::PriorityDependency
+                }
+            },
+            max_parallel: batch.max_parallel.map(|v| v as usize),
+            timeout: batch.timeout_ms.map(std::time::Duration::from_millis),
+            security: context.clone(),
+        };
+
+        let progress_id = self.ids.generate(IdKind::Progress);
+        let total_items = batch.requests.len();
+
+        // Send initial progress update
+        self.progress_reporter
+            .report_progress(ProgressUpdate::started(
+                progress_id.clone(),
+                "Processing batch request".to_string(),
+                total_items,
+            ))
+            .await;
+
+        let batch_requests = batch.requests.clone();
+        let results = match batch_context.mode {
+            crate::server::BatchExecutionMode::Parallel => {
+                self.execute_batch_parallel(
+                    batch_requests,
+                    &batch_context,
+                    &progress_id,
+                    progress.as_ref(),
+                )
+                .await
+            }
+            crate::server::BatchExecutionMode::Sequential
+            | crate::server::BatchExecutionMode::FailFast => {
+                self.execute_batch_sequential(
+                    batch_requests,
+                    &batch_context,
+                    &progress_id,
+                    progress.as_ref(),
+                )
+                .await
+            }
+            crate::server::BatchExecutionMode::Dependency
+            | crate::server::BatchExecutionMode::PriorityDependency => {
+                self.execute_batch_dependency(
+                    batch_requests,
+                    order.unwrap_or_default(),
+                    &batch_context,
+                    &progress_id,
+                    progress.as_ref(),
+                )
+                .await
+            }
+        };
+
+        // Send completion progress update
+        self.progress_reporter
+            .report_progress(ProgressUpdate::completed(
+                progress_id,
+                "Batch request completed".to_string(),
+            ))
+            .await;
+
+        let successful_count = results.iter().filter(|r| r.error.is_none()).count() as u32;
+        let skipped_count = results.iter().filter(|r| r.skipped).count() as u32;
+        let failed_count =
+            results.iter().filter(|r| r.error.is_some()).count() as u32 - skipped_count;
+
+        Ok(BatchResult {
+            stats: crate::protocol::BatchStats {
+                total_requests: total_items as u32,
+                successful_requests: successful_count,
+                failed_requests: failed_count,
+                skipped_requests: skipped_count,
+                total_execution_time_ms: 0, // TODO: Calculate actual time
+                average_execution_time_ms: 0.0, // TODO: Calculate actual time
+                max_parallel_executed: batch_context.max_parallel.unwrap_or(1) as u32,
+            },
+            results,
+            correlation_token: batch.correlation_token,
+            metadata: Map::new(),
+        })
+    }
+
+    /// Execute batch items in parallel
+    async fn execute_batch_parallel(
+        &self,
+        items: Vec<crate::protocol::BatchRequest>,
+        context: &BatchContext,
+        progress_id: &str,
+        progress: Option<&BatchProgress>,
+    ) -> Vec<BatchItemResult> {
+        use futures_util::stream::{self, StreamExt};
+
+        let max_parallel = context.max_parallel.unwrap_or(10).min(items.len());
+        let mut completed = 0;
+
+        stream::iter(items)
+            .map(|item| async move {
+                if let Some(result) = progress.and_then(|progress| progress.completed(&item.id)) {
+                    return result;
+                }
+
+                // Prevent batch requests within batch requests to avoid recursion
+                if item.method == "batch" {
+                    completed += 1;
+
+                    // Report progress
+                    self.progress_reporter
+                        .report_progress(ProgressUpdate::progress(
+                            progress_id.to_string(),
+                            format!("Processed {} items", completed),
+                            completed,
+                        ))
+                        .await;
+
+                    return BatchItemResult {
+                        id: item.id,
                         result: None,
                         error: Some(crate::protocol::JsonRpcError {
                             code: -32600,
@@ -474,7 +2577,7 @@ where
                         }),
                         execution_time_ms: 0,
                         skipped: false,
-                        metadata: HashMap::new(),
+                        metadata: Map::new(),
                     };
                 }
 
@@ -485,7 +2588,7 @@ where
                     id: Some(serde_json::Value::String(item.id.clone())),
                 };
                 let result = self
-                    .handle_request(json_rpc_request, context.security.clone())
+                    .execute_batch_item(&item, json_rpc_request, context)
                     .await;
                 completed += 1;
 
@@ -498,7 +2601,7 @@ where
                     ))
                     .await;
 
-                BatchItemResult {
+                let item_result = BatchItemResult {
                     id: item.id,
                     result: if result.error.is_none() {
                         result.result
@@ -508,8 +2611,12 @@ where
                     error: result.error,
                     execution_time_ms: 0, // TODO: Add timing
                     skipped: false,
-                    metadata: HashMap::new(),
+                    metadata: Map::new(),
+                };
+                if let Some(progress) = progress {
+                    progress.record(&item_result);
                 }
+                item_result
             })
             .buffer_unordered(max_parallel)
             .collect()
@@ -522,6 +2629,7 @@ where
         items: Vec<crate::protocol::BatchRequest>,
         context: &BatchContext,
         progress_id: &str,
+        progress: Option<&BatchProgress>,
     ) -> Vec<BatchItemResult> {
         let mut results = Vec::with_capacity(items.len());
         let stop_on_error = context
@@ -533,6 +2641,11 @@ where
             .unwrap_or(false);
 
         for (index, item) in items.into_iter().enumerate() {
+            if let Some(result) = progress.and_then(|progress| progress.completed(&item.id)) {
+                results.push(result);
+                continue;
+            }
+
             // Prevent batch requests within batch requests to avoid recursion
             if item.method == "batch" {
                 let batch_result = BatchItemResult {
@@ -545,7 +2658,7 @@ where
                     }),
                     execution_time_ms: 0,
                     skipped: false,
-                    metadata: HashMap::new(),
+                    metadata: Map::new(),
                 };
                 results.push(batch_result);
 
@@ -568,7 +2681,7 @@ where
                 id: Some(serde_json::Value::String(item.id.clone())),
             };
             let result = self
-                .handle_request(json_rpc_request, context.security.clone())
+                .execute_batch_item(&item, json_rpc_request, context)
                 .await;
 
             let batch_result = BatchItemResult {
@@ -581,8 +2694,11 @@ where
                 error: result.error.clone(),
                 execution_time_ms: 0, // TODO: Add timing
                 skipped: false,
-                metadata: HashMap::new(),
+                metadata: Map::new(),
             };
+            if let Some(progress) = progress {
+                progress.record(&batch_result);
+            }
 
             results.push(batch_result);
 
@@ -604,12 +2720,174 @@ where
         results
     }
 
+    /// Execute batch items one at a time in dependency order
+    ///
+    /// Placeholders in an item's parameters are rendered from the results of
+    /// the items before it; an item whose dependency did not succeed is
+    /// skipped. Results keep the batch order.
+    async fn execute_batch_dependency(
+        &self,
+        items: Vec<crate::protocol::BatchRequest>,
+        order: Vec<usize>,
+        context: &BatchContext,
+        progress_id: &str,
+        progress: Option<&BatchProgress>,
+    ) -> Vec<BatchItemResult> {
+        let item_error = |id: &str, code: i32, message: String, skipped: bool| BatchItemResult {
+            id: id.to_string(),
+            result: None,
+            error: Some(crate::protocol::JsonRpcError {
+                code,
+                message,
+                data: None,
+            }),
+            execution_time_ms: 0,
+            skipped,
+            metadata: Map::new(),
+        };
+
+        let mut slots: Vec<Option<BatchItemResult>> = vec![None; items.len()];
+        let mut succeeded = HashMap::new();
+        for (done, index) in order.into_iter().enumerate() {
+            let item = &items[index];
+            let failed_dependency = batch_template::dependencies(item)
+                .into_iter()
+                .find(|id| !succeeded.contains_key(id));
+
+            let item_result =
+                if let Some(result) = progress.and_then(|progress| progress.completed(&item.id)) {
+                    result
+                } else if let Some(dependency) = failed_dependency {
+                    item_error(
+                        &item.id,
+                        -32603,
+                        format!("Skipped because '{}' did not succeed", dependency),
+                        true,
+                    )
+                } else if item.method == "batch" {
+                    // Prevent batch requests within batch requests to avoid recursion
+                    item_error(
+                        &item.id,
+                        -32600,
+                        "Nested batch requests are not allowed".to_string(),
+                        false,
+                    )
+                } else {
+                    let params = item
+                        .params
+                        .as_ref()
+                        .map(|params| batch_template::render(params, &succeeded))
+                        .transpose();
+                    match params {
+                        Err(message) => item_error(&item.id, -32602, message, false),
+                        Ok(params) => {
+                            let json_rpc_request = crate::protocol::JsonRpcRequest {
+                                jsonrpc: "2.0".to_string(),
+                                method: item.method.clone(),
+                                params,
+                                id: Some(serde_json::Value::String(item.id.clone())),
+                            };
+                            let result = self
+                                .execute_batch_item(item, json_rpc_request, context)
+                                .await;
+                            BatchItemResult {
+                                id: item.id.clone(),
+                                result: if result.error.is_none() {
+                                    result.result
+                                } else {
+                                    None
+                                },
+                                error: result.error,
+                                execution_time_ms: 0, // TODO: Add timing
+                                skipped: false,
+                                metadata: Map::new(),
+                            }
+                        }
+                    }
+                };
+
+            if item_result.error.is_none() {
+                succeeded.insert(
+                    item.id.clone(),
+                    item_result
+                        .result
+                        .clone()
+                        .unwrap_or(serde_json::Value::Null),
+                );
+            }
+            if let Some(progress) = progress {
+                progress.record(&item_result);
+            }
+            slots[index] = Some(item_result);
+
+            // Report progress
+            self.progress_reporter
+                .report_progress(ProgressUpdate::progress(
+                    progress_id.to_string(),
+                    format!("Processed {} items", done + 1),
+                    done + 1,
+                ))
+                .await;
+        }
+
+        slots.into_iter().flatten().collect()
+    }
+
+    /// Run one batch item, on behalf of its `onBehalfOf` principal if allowed
+    async fn execute_batch_item(
+        &self,
+        item: &crate::protocol::BatchRequest,
+        request: JsonRpcRequest,
+        context: &BatchContext,
+    ) -> JsonRpcResponse {
+        // Item IDs are scoped to their batch, so they stay out of duplicate
+        // detection for the session
+        let security = context.security.executed_by(BATCH_EXECUTOR);
+        let Some(principal) = &item.on_behalf_of else {
+            return self.dispatch_request(request, security).await;
+        };
+        let allowed = self
+            .state
+            .auth_manager()
+            .authorize_delegation(&context.security, principal)
+            .await;
+        if !allowed {
+            let error = McpError::Authorization {
+                message: format!(
+                    "{} may not act on behalf of {}",
+                    context.security.principal(),
+                    principal
+                ),
+            };
+            return JsonRpcResponse::error(error.into(), request.id);
+        }
+        debug!(
+            "Running batch item {} on behalf of {} for {}",
+            item.id,
+            principal,
+            context.security.principal()
+        );
+        self.dispatch_request(request, security.on_behalf_of(principal))
+            .await
+    }
+
     /// Validate a request
     async fn validate_request(
         &self,
         request: &JsonRpcRequest,
         context: &SecurityContext,
     ) -> McpResult<()> {
+        if request.jsonrpc != crate::protocol::jsonrpc::JSONRPC_VERSION {
+            return Err(McpError::Protocol {
+                message: format!("Unsupported JSON-RPC version: {:?}", request.jsonrpc),
+            });
+        }
+
+        self.config
+            .security
+            .method_authorization
+            .check(&request.method, context)?;
+
         // Check if method requires initialization
         if let Ok(InternalMcpMethod::Standard(method)) = self.parse_method(&request.method) {
             if method.requires_initialization() && !context.is_authenticated() {
@@ -626,17 +2904,51 @@ where
         Ok(())
     }
 
+    /// Apply the per-client-type rate limit for fingerprinted clients
+    async fn check_client_rate_limit(&self, context: &SecurityContext) -> McpResult<()> {
+        let Some(fingerprint) = &context.client.fingerprint else {
+            return Ok(());
+        };
+
+        let key = context
+            .client
+            .session_id
+            .as_deref()
+            .or(context.client.client_id.as_deref())
+            .or(fingerprint.remote_addr.as_deref())
+            .unwrap_or("anonymous");
+
+        self.client_rate_limiter
+            .check_rate_limit(fingerprint.client_type(), key)
+            .await
+    }
+
     /// Parse a method string into an MCP method
     fn parse_method(&self, method: &str) -> McpResult<InternalMcpMethod> {
         // Try to parse as standard method first
         if let Ok(standard_method) =
             serde_json::from_value::<StandardMethod>(serde_json::Value::String(method.to_string()))
         {
-            Ok(InternalMcpMethod::Standard(standard_method))
-        } else {
-            // Treat as custom method
-            Ok(InternalMcpMethod::Custom(method.to_string()))
+            return Ok(InternalMcpMethod::Standard(standard_method));
+        }
+
+        if self.config.strict_methods && !BUILTIN_METHODS.contains(&method) {
+            let custom = self.state.custom_methods();
+            if !custom.iter().any(|m| m == method) {
+                let known = StandardMethod::NAMES
+                    .iter()
+                    .chain(BUILTIN_METHODS)
+                    .copied()
+                    .chain(custom.iter().map(String::as_str));
+                return Err(McpError::MethodNotFound {
+                    method: method.to_string(),
+                    suggestion: closest_method(method, known).map(str::to_string),
+                });
+            }
         }
+
+        // Treat as custom method
+        Ok(InternalMcpMethod::Custom(method.to_string()))
     }
 
     /// Get current server health
@@ -644,9 +2956,34 @@ where
         let mut health = self.health.read().await.clone();
         health.uptime_seconds = self.start_time.elapsed().as_secs();
         health.active_connections = *self.active_connections.read().await;
+        #[cfg(feature = "metrics")]
+        self.insert_load_metrics(&mut health);
         health
     }
 
+    /// Report scheduler, admission and memory load in the health metrics
+    #[cfg(feature = "metrics")]
+    fn insert_load_metrics(&self, health: &mut ServerHealth) {
+        if self.scheduler.config().enabled {
+            health.metrics.insert(
+                "scheduler".to_string(),
+                serde_json::to_value(self.scheduler.stats()).unwrap_or_default(),
+            );
+        }
+        if self.admission.config().enabled {
+            health.metrics.insert(
+                "admission".to_string(),
+                serde_json::to_value(self.admission.stats()).unwrap_or_default(),
+            );
+        }
+        if self.memory.config().enabled {
+            health.metrics.insert(
+                "memory".to_string(),
+                serde_json::to_value(self.memory.usage()).unwrap_or_default(),
+            );
+        }
+    }
+
     /// Update server health status
     pub async fn update_health(&self, healthy: bool, status: String) {
         let mut health = self.health.write().await;
@@ -669,30 +3006,391 @@ where
     }
 }
 
-/// MCP method enumeration
-#[derive(Debug, Clone)]
-enum InternalMcpMethod {
-    /// Standard MCP protocol method
-    Standard(StandardMethod),
-    /// Custom application-specific method
-    Custom(String),
-}
+/// Service account reading resources for the search index
+pub const SEARCH_INDEXER: &str = "search-indexer";
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        security::{McpAuth, SecurityContext},
-        server::registry::InMemoryToolRegistry,
-    };
-    use async_trait::async_trait;
+/// Service account listing registries in [`McpServer::validate`] and
+/// [`McpServer::check_dependencies`]
+pub const VALIDATOR: &str = "validator";
 
-    // Test server state implementation
-    #[derive(Clone)]
-    struct TestServerState {
-        tools: InMemoryToolRegistry,
-        auth: TestAuth,
-    }
+/// Service account running the items of `batch` requests for the caller
+pub const BATCH_EXECUTOR: &str = "batch-executor";
+
+/// Context of the internal service account `name`
+///
+/// Internal accounts hold [`LIST_ALL_CAPABILITY`] and [`READ_ALL_CAPABILITY`],
+/// so tools and resources hidden from other non-system contexts are still
+/// validated and indexed.
+fn internal_account(name: &str) -> SecurityContext {
+    let mut context = SecurityContext::service_account(name);
+    context.add_capability(LIST_ALL_CAPABILITY);
+    context.add_capability(READ_ALL_CAPABILITY);
+    context
+}
+
+/// Lists the tool calls waiting for approval
+pub const ADMIN_APPROVALS_LIST: &str = "admin/approvals/list";
+
+/// Approves or denies a pending tool call
+pub const ADMIN_APPROVALS_DECIDE: &str = "admin/approvals/decide";
+
+/// Reports quota usage, for one principal or all with usage in the window
+pub const ADMIN_QUOTAS_INSPECT: &str = "admin/quotas/inspect";
+
+/// Restores a principal's full quota
+pub const ADMIN_QUOTAS_RESET: &str = "admin/quotas/reset";
+
+/// Returns the exchanges captured by the request inspector
+pub const ADMIN_INSPECTOR_LIST: &str = "admin/inspector/list";
+
+/// Switches the request inspector on or off, or clears it
+pub const ADMIN_INSPECTOR_CONFIGURE: &str = "admin/inspector/configure";
+
+/// Non-standard methods answered by the server itself
+const BUILTIN_METHODS: &[&str] = &[
+    ADMIN_APPROVALS_LIST,
+    ADMIN_APPROVALS_DECIDE,
+    ADMIN_QUOTAS_INSPECT,
+    ADMIN_QUOTAS_RESET,
+    ADMIN_INSPECTOR_LIST,
+    ADMIN_INSPECTOR_CONFIGURE,
+    ADMIN_WIRE_TAP,
+    ADMIN_TOOLSETS_CONFIGURE,
+    ADMIN_MAINTENANCE_LIST,
+    ADMIN_MAINTENANCE_SET,
+    ADMIN_MAINTENANCE_CLEAR,
+    ADMIN_DEPENDENCIES,
+    ADMIN_REGISTRY_CHANGELOG,
+    RESOURCES_DIFF,
+    RESOURCES_VERSIONS,
+    RESOURCES_READ_MANY,
+    RESOURCES_SEARCH,
+    NOTIFICATIONS_FILTER,
+    RPC_DISCOVER,
+    SERVER_INFO,
+    TASKS_CREATE,
+    TASKS_STATUS,
+    TASKS_RESULT,
+    TASKS_CANCEL,
+    TASKS_LIST,
+    TOOLSETS_LIST,
+    TOOLSETS_ENABLE,
+    TOOLSETS_DISABLE,
+];
+
+/// The known method closest to a mistyped `method`, if any is close enough
+fn closest_method<'a>(method: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (method.chars().count() / 3).max(1);
+    known
+        .map(|candidate| (edit_distance(method, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Tools of a server's state, with its smoke-test tools
+struct ServerTools<'a, S: McpServerState> {
+    server: &'a McpServer<S>,
+}
+
+#[async_trait::async_trait]
+impl<S: McpServerState> ToolRegistry for ServerTools<'_, S> {
+    async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        let mut tools = self
+            .server
+            .state
+            .tool_registry()
+            .list_tools(context)
+            .await?;
+        tools.extend(self.server.smoke_tools.list_tools(context).await?);
+        Ok(tools)
+    }
+
+    async fn list_tools_page(
+        &self,
+        context: &SecurityContext,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> McpResult<Page<Tool>> {
+        if !self.server.smoke_tools.is_enabled() {
+            return self
+                .server
+                .state
+                .tool_registry()
+                .list_tools_page(context, cursor, limit)
+                .await;
+        }
+        let tools = self.list_tools(context).await?;
+        paginate(
+            tools.into_iter().map(|t| (t.name.clone(), t)).collect(),
+            cursor,
+            limit,
+        )
+    }
+
+    async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        if self.server.smoke_tools.contains(name) {
+            return self.server.smoke_tools.get_tool(name, context).await;
+        }
+        self.server
+            .state
+            .tool_registry()
+            .get_tool(name, context)
+            .await
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        execution_context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        if !self.server.smoke_tools.contains(name) {
+            return self
+                .server
+                .state
+                .tool_registry()
+                .execute_tool(name, execution_context)
+                .await;
+        }
+        if name == SMOKE_SERVER_STATS {
+            let caller = &execution_context.security;
+            return server_stats(
+                &self.server.server_info(),
+                &self.server.get_health().await,
+                self.list_tools(caller).await?.len(),
+                caller,
+            );
+        }
+        self.server
+            .smoke_tools
+            .execute_tool(name, execution_context)
+            .await
+    }
+
+    async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
+        if self.server.smoke_tools.contains(name) {
+            return self.server.smoke_tools.can_access_tool(name, context).await;
+        }
+        self.server
+            .state
+            .tool_registry()
+            .can_access_tool(name, context)
+            .await
+    }
+
+    async fn get_categories(&self, context: &SecurityContext) -> McpResult<Vec<String>> {
+        let mut categories = self
+            .server
+            .state
+            .tool_registry()
+            .get_categories(context)
+            .await?;
+        categories.extend(self.server.smoke_tools.get_categories(context).await?);
+        Ok(categories)
+    }
+}
+
+fn is_admin_method(method: &str) -> bool {
+    matches!(
+        method,
+        ADMIN_APPROVALS_LIST
+            | ADMIN_APPROVALS_DECIDE
+            | ADMIN_QUOTAS_INSPECT
+            | ADMIN_QUOTAS_RESET
+            | ADMIN_INSPECTOR_LIST
+            | ADMIN_INSPECTOR_CONFIGURE
+            | ADMIN_WIRE_TAP
+            | ADMIN_TOOLSETS_CONFIGURE
+            | ADMIN_MAINTENANCE_LIST
+            | ADMIN_MAINTENANCE_SET
+            | ADMIN_MAINTENANCE_CLEAR
+            | ADMIN_DEPENDENCIES
+            | ADMIN_REGISTRY_CHANGELOG
+    )
+}
+
+/// Params of the `admin/quotas/*` methods
+#[derive(Debug, Default, serde::Deserialize)]
+struct QuotaParams {
+    principal: Option<String>,
+}
+
+/// URIs `tool` declares it writes, resolved with `arguments`
+fn written_resources(tool: Option<&McpTool>, arguments: &serde_json::Value) -> Vec<String> {
+    tool.map(|tool| {
+        tool.resource_dependencies()
+            .writes
+            .iter()
+            .map(|pattern| dependencies::resolve(pattern, arguments))
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// `cursor` argument of a listing request
+fn list_cursor(params: Option<&serde_json::Value>) -> Option<&str> {
+    params?.get("cursor")?.as_str()
+}
+
+/// Up to `limit` templates of `registries` after `cursor`
+///
+/// The registries are listed one after another, so the cursor is the index of
+/// the registry being listed, a `.` and that registry's own cursor, which is
+/// empty when it is to be listed from the start.
+async fn list_templates_page(
+    registries: &[&dyn ResourceRegistry],
+    cursor: Option<&str>,
+    limit: usize,
+    context: &SecurityContext,
+) -> McpResult<Page<crate::server::resource::ResourceTemplate>> {
+    let (mut index, mut inner) = match cursor {
+        Some(cursor) => {
+            let invalid = || McpError::Validation {
+                message: format!("Invalid cursor '{}'", cursor),
+            };
+            let (index, inner) = cursor.split_once('.').ok_or_else(invalid)?;
+            let index: usize = index.parse().map_err(|_| invalid())?;
+            (index, Some(inner).filter(|inner| !inner.is_empty()))
+        }
+        None => (0, None),
+    };
+    let limit = limit.max(1);
+    let mut items = Vec::new();
+    while let Some(registry) = registries.get(index) {
+        let page = registry
+            .list_resource_templates_page(context, inner, limit - items.len())
+            .await?;
+        items.extend(page.items);
+        if let Some(next) = page.next_cursor {
+            return Ok(Page {
+                items,
+                next_cursor: Some(format!("{}.{}", index, next)),
+            });
+        }
+        index += 1;
+        inner = None;
+        if items.len() >= limit {
+            let next_cursor = (index < registries.len()).then(|| format!("{}.", index));
+            return Ok(Page { items, next_cursor });
+        }
+    }
+    Ok(Page {
+        items,
+        next_cursor: None,
+    })
+}
+
+/// Decode the params of a standard method
+///
+/// Missing or malformed params are reported as invalid params (`-32602`).
+fn parse_params<T: serde::de::DeserializeOwned>(
+    method: &str,
+    params: Option<serde_json::Value>,
+) -> McpResult<T> {
+    let params = params.ok_or_else(|| McpError::Validation {
+        message: format!("{} requires parameters", method),
+    })?;
+    serde_json::from_value(params).map_err(|e| McpError::Validation {
+        message: format!("Invalid {} params: {}", method, e),
+    })
+}
+
+/// Error for search on a server without a search index
+fn search_disabled() -> McpError {
+    McpError::Protocol {
+        message: "Resource search is not enabled on this server".to_string(),
+    }
+}
+
+/// A resource at `version`, or its current content
+async fn resource_at(
+    registry: &dyn ResourceRegistry,
+    uri: &str,
+    version: Option<u64>,
+    context: &SecurityContext,
+) -> McpResult<Resource> {
+    match version {
+        Some(version) => {
+            registry
+                .as_versioned()
+                .ok_or_else(|| unversioned(uri))?
+                .get_resource_at(uri, version, context)
+                .await
+        }
+        None => registry.get_resource(uri, context).await,
+    }
+}
+
+/// Text of a resource, at `version` or its current content
+async fn resource_text(
+    registry: &dyn ResourceRegistry,
+    uri: &str,
+    version: Option<u64>,
+    context: &SecurityContext,
+) -> McpResult<String> {
+    match resource_at(registry, uri, version, context).await?.content {
+        ResourceContent::Text { text } => Ok(text),
+        ResourceContent::Blob { .. } => Err(McpError::InvalidResource {
+            uri: uri.to_string(),
+            message: "Only text resources can be compared".to_string(),
+        }),
+    }
+}
+
+/// Count the notifications buffered by `broker` in `memory`
+fn track_notifications(memory: &MemoryAccountant, broker: &Arc<dyn NotificationBroker>) {
+    let broker = Arc::clone(broker);
+    memory.register_buffer("notifications", Arc::new(move || broker.buffered_bytes()));
+}
+
+/// Extract `_meta.progressToken` from request params as a string
+pub fn progress_token(params: &serde_json::Value) -> Option<String> {
+    match params.get("_meta")?.get("progressToken")? {
+        serde_json::Value::String(token) => Some(token.clone()),
+        serde_json::Value::Number(token) => Some(token.to_string()),
+        _ => None,
+    }
+}
+
+/// MCP method enumeration
+#[derive(Debug, Clone)]
+enum InternalMcpMethod {
+    /// Standard MCP protocol method
+    Standard(StandardMethod),
+    /// Custom application-specific method
+    Custom(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        security::{McpAuth, SecurityContext},
+        server::{registry::InMemoryToolRegistry, RESUMED_META},
+    };
+    use async_trait::async_trait;
+
+    // Test server state implementation
+    #[derive(Clone)]
+    struct TestServerState {
+        tools: InMemoryToolRegistry,
+        auth: TestAuth,
+    }
 
     #[derive(Clone)]
     struct TestAuth;
@@ -714,6 +3412,10 @@ mod tests {
         ) -> bool {
             true
         }
+
+        async fn authorize_delegation(&self, _context: &SecurityContext, principal: &str) -> bool {
+            principal == "alice"
+        }
     }
 
     impl McpServerState for TestServerState {
@@ -767,4 +3469,1977 @@ mod tests {
         assert!(response.error.is_none());
         assert!(response.result.is_some());
     }
+
+    #[tokio::test]
+    async fn test_server_info_includes_build_metadata() {
+        let state = TestServerState {
+            tools: InMemoryToolRegistry::new(),
+            auth: TestAuth,
+        };
+        let clock = Clock::manual();
+        let config = McpServerConfig::default()
+            .with_strict_methods()
+            .with_git_hash("3f9c2ab");
+        let server = McpServer::new(config, state).with_clock(clock.clone());
+        clock.advance(std::time::Duration::from_secs(90));
+
+        let request = JsonRpcRequest::new(SERVER_INFO, None, Some(serde_json::json!(1)));
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        let info = response.result.unwrap();
+        assert_eq!(info["name"], "MCP Server");
+        assert_eq!(info["build"]["gitHash"], "3f9c2ab");
+        assert_eq!(info["build"]["uptimeSeconds"], 90);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_discover_lists_methods() {
+        let state = TestServerState {
+            tools: InMemoryToolRegistry::new(),
+            auth: TestAuth,
+        };
+        let config = McpServerConfig::default()
+            .with_strict_methods()
+            .with_method_aliases(
+                crate::server::MethodAliasConfig::default().with_alias("tool/run", "tools/call"),
+            );
+        let server = McpServer::new(config, state);
+
+        let request = JsonRpcRequest::new(RPC_DISCOVER, None, Some(serde_json::json!(1)));
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        let document = response.result.unwrap();
+        let methods = document["methods"].as_array().unwrap();
+        let method = |name: &str| methods.iter().find(|m| m["name"] == name);
+
+        let call = method("tools/call").unwrap();
+        assert_eq!(call["x-aliases"][0], "tool/run");
+        assert!(call["params"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|param| param["name"] == "name" && param["required"] == true));
+        assert!(method(RPC_DISCOVER).is_some());
+        assert_eq!(
+            method(ADMIN_QUOTAS_RESET).unwrap()["x-requires-system-context"],
+            true
+        );
+        // No prompt registry, no prompt methods
+        assert!(method("prompts/get").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_strict_methods() {
+        let state = TestServerState {
+            tools: InMemoryToolRegistry::new(),
+            auth: TestAuth,
+        };
+        let strict = McpServer::new(McpServerConfig::default().with_strict_methods(), state);
+        let request = |method: &str| JsonRpcRequest::new(method, None, Some(serde_json::json!(1)));
+
+        let response = strict
+            .handle_request(request("tool/call"), SecurityContext::system())
+            .await;
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32601);
+        assert_eq!(error.data.unwrap()["suggestion"], "tools/call");
+
+        let response = strict
+            .handle_request(request("frobnicate"), SecurityContext::system())
+            .await;
+        assert!(response
+            .error
+            .unwrap()
+            .data
+            .unwrap()
+            .get("suggestion")
+            .is_none());
+
+        let response = strict
+            .handle_request(request("ping"), SecurityContext::system())
+            .await;
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_method_aliases() {
+        let state = TestServerState {
+            tools: InMemoryToolRegistry::new(),
+            auth: TestAuth,
+        };
+        let aliases =
+            crate::server::MethodAliasConfig::default().with_deprecated("tool/list", "tools/list");
+        let config = McpServerConfig::default()
+            .with_strict_methods()
+            .with_method_aliases(aliases);
+        let server = McpServer::new(config, state);
+
+        let request = JsonRpcRequest::new("tool/list", None, Some(serde_json::json!(1)));
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        assert!(response.error.is_none());
+        assert!(response.result.unwrap()["tools"].is_array());
+    }
+
+    #[test]
+    fn test_closest_method() {
+        let known = ["tools/list", "tools/call", "prompts/get"];
+        assert_eq!(
+            closest_method("tools/lsit", known.into_iter()),
+            Some("tools/list")
+        );
+        assert_eq!(closest_method("resources/read", known.into_iter()), None);
+    }
+
+    #[tokio::test]
+    async fn test_initialize_applies_client_policy() {
+        let config = McpServerConfig::default().with_client_policy(
+            crate::security::ClientPolicy::default().with_min_version("Test Client", "2.0"),
+        );
+        let state = TestServerState {
+            tools: InMemoryToolRegistry::new(),
+            auth: TestAuth,
+        };
+        let server = McpServer::new(config, state);
+
+        let initialize = |version: &str| JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "initialize".to_string(),
+            params: Some(serde_json::json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "Test Client", "version": version}
+            })),
+            id: Some(serde_json::json!(1)),
+        };
+
+        let response = server
+            .handle_request(initialize("1.9.0"), SecurityContext::system())
+            .await;
+        assert!(response.error.is_some());
+
+        let response = server
+            .handle_request(initialize("v2.1.0"), SecurityContext::system())
+            .await;
+        assert!(response.error.is_none());
+    }
+
+    /// Registry whose `count` tool counts calls in the session store
+    #[derive(Clone)]
+    struct CounterTools;
+
+    #[async_trait]
+    impl ToolRegistry for CounterTools {
+        async fn list_tools(
+            &self,
+            _context: &SecurityContext,
+        ) -> McpResult<Vec<crate::protocol::Tool>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_tool(
+            &self,
+            name: &str,
+            _context: &SecurityContext,
+        ) -> McpResult<Option<crate::server::McpTool>> {
+            Ok((name == "guarded").then(|| {
+                crate::server::McpTool::new(name, "Needs approval", serde_json::json!({}), "test")
+                    .require_approval()
+            }))
+        }
+
+        async fn execute_tool(
+            &self,
+            _name: &str,
+            context: ToolExecutionContext,
+        ) -> McpResult<crate::protocol::ToolsCallResult> {
+            let store = context.session_store().ok_or_else(|| McpError::Internal {
+                message: "no session store".to_string(),
+                source: None,
+            })?;
+            let count = store.get_as::<u32>("count").await?.unwrap_or(0) + 1;
+            store.set("count", count).await?;
+            Ok(crate::protocol::ToolsCallResult {
+                content: vec![crate::protocol::ToolContent::Text {
+                    text: count.to_string(),
+                }],
+                is_error: false,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn can_access_tool(&self, _name: &str, _context: &SecurityContext) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone)]
+    struct CounterServerState;
+
+    impl McpServerState for CounterServerState {
+        type ToolRegistry = CounterTools;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &CounterTools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tools_share_session_store() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let call = |session_id: &str| {
+            let mut context = SecurityContext::system();
+            context.client.session_id = Some(session_id.to_string());
+            let request = JsonRpcRequest::new(
+                "tools/call",
+                Some(serde_json::json!({"name": "count"})),
+                Some(serde_json::json!(1)),
+            );
+            let server = server.clone();
+            async move {
+                let response = server.handle_request(request, context).await;
+                response.result.unwrap()["content"][0]["text"].clone()
+            }
+        };
+
+        assert_eq!(call("a").await, "1");
+        assert_eq!(call("a").await, "2");
+        assert_eq!(call("b").await, "1");
+
+        server.session_stores().remove("a").await;
+        assert_eq!(call("a").await, "1");
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_are_formatted() {
+        struct Tally;
+
+        #[async_trait]
+        impl ResultFormatter for Tally {
+            fn name(&self) -> &str {
+                "tally"
+            }
+
+            async fn format(
+                &self,
+                tool: &str,
+                result: &mut crate::protocol::ToolsCallResult,
+                _context: &SecurityContext,
+            ) -> McpResult<()> {
+                result.content.push(crate::protocol::ToolContent::Text {
+                    text: format!("counted by {}", tool),
+                });
+                Ok(())
+            }
+        }
+
+        let config = McpServerConfig::default().with_formatters(
+            crate::server::FormatterConfig::default().with_global(&["pretty-json", "tally"]),
+        );
+        let server =
+            McpServer::new(config, CounterServerState).with_result_formatter(Arc::new(Tally));
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("a".to_string());
+        let request = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count"})),
+            Some(serde_json::json!(1)),
+        );
+        let result = server
+            .handle_request(request, context)
+            .await
+            .result
+            .unwrap();
+        assert_eq!(result["content"][0]["text"], "1");
+        assert_eq!(result["content"][1]["text"], "counted by count");
+        assert!(server.validate().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_toolsets_are_switched_per_session() {
+        use crate::server::toolsets::{Toolset, ToolsetConfig};
+
+        let config = McpServerConfig::default().with_toolsets(
+            ToolsetConfig::enabled()
+                .with_toolset("counting", Toolset::new("Counting").with_tool("count")),
+        );
+        let server = McpServer::new(config, CounterServerState);
+        let send = |session_id: &str, method: &str, params: serde_json::Value| {
+            let mut context = SecurityContext::system();
+            context.client.session_id = Some(session_id.to_string());
+            let request = JsonRpcRequest::new(method, Some(params), Some(serde_json::json!(1)));
+            let server = server.clone();
+            async move { server.handle_request(request, context).await }
+        };
+        let count = serde_json::json!({"name": "count"});
+
+        let response = send("a", "tools/call", count.clone()).await;
+        assert!(response.error.is_some());
+
+        let response = send(
+            "a",
+            TOOLSETS_ENABLE,
+            serde_json::json!({"toolsets": ["counting"]}),
+        )
+        .await;
+        assert_eq!(response.result.unwrap()["toolsets"][0]["enabled"], true);
+        let response = send("a", "tools/call", count.clone()).await;
+        assert_eq!(response.result.unwrap()["content"][0]["text"], "1");
+        // Other sessions keep the defaults until an operator switches them
+        assert!(send("b", "tools/call", count.clone()).await.error.is_some());
+
+        let configure = serde_json::json!({"sessionId": "b", "enable": ["counting"]});
+        let response = send("a", ADMIN_TOOLSETS_CONFIGURE, configure).await;
+        assert!(response.error.is_none());
+        assert!(send("b", "tools/call", count).await.error.is_none());
+
+        let response = send("a", TOOLSETS_LIST, serde_json::json!({})).await;
+        assert_eq!(
+            response.result.unwrap()["toolsets"],
+            serde_json::json!([{"name": "counting", "description": "Counting", "tools": ["count"], "enabled": true}])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_in_maintenance_are_unavailable() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let send = |method: &str, params: serde_json::Value| {
+            let request = JsonRpcRequest::new(method, Some(params), Some(serde_json::json!(1)));
+            let server = server.clone();
+            let mut context = SecurityContext::system();
+            context.client.session_id = Some("a".to_string());
+            async move { server.handle_request(request, context).await }
+        };
+        let count = serde_json::json!({"name": "count"});
+
+        let maintenance =
+            serde_json::json!({"kind": "tool", "name": "count", "message": "Recounting"});
+        let response = send(ADMIN_MAINTENANCE_SET, maintenance).await;
+        assert_eq!(response.result.unwrap()["maintenance"]["name"], "count");
+
+        let error = send("tools/call", count.clone()).await.error.unwrap();
+        assert_eq!(
+            error.message,
+            "Tool 'count' is temporarily unavailable: Recounting"
+        );
+
+        let clear = serde_json::json!({"kind": "tool", "name": "count"});
+        let response = send(ADMIN_MAINTENANCE_CLEAR, clear).await;
+        assert_eq!(response.result.unwrap()["cleared"], true);
+        assert!(send("tools/call", count).await.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_responses_roundtrip_through_protocol_crate() {
+        use axum_mcp_protocol::{InitializeResult, ToolsCallResult, ToolsListResult};
+
+        fn roundtrip<T: serde::de::DeserializeOwned + serde::Serialize>(value: &serde_json::Value) {
+            let typed: T = serde_json::from_value(value.clone()).unwrap();
+            assert_eq!(&serde_json::to_value(typed).unwrap(), value);
+        }
+
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let requests = [
+            (
+                "initialize",
+                serde_json::json!({
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": {"name": "Firmware", "version": "1.0"}
+                }),
+            ),
+            ("tools/list", serde_json::json!({})),
+            ("tools/call", serde_json::json!({"name": "count"})),
+        ];
+        for (method, params) in requests {
+            let request = JsonRpcRequest::new(method, Some(params), Some(serde_json::json!(1)));
+            let mut context = SecurityContext::system();
+            context.client.session_id = Some("device".to_string());
+            let response =
+                serde_json::to_value(server.handle_request(request, context).await).unwrap();
+            roundtrip::<axum_mcp_protocol::JsonRpcResponse>(&response);
+            let result = &response["result"];
+            match method {
+                "initialize" => roundtrip::<InitializeResult>(result),
+                "tools/list" => roundtrip::<ToolsListResult>(result),
+                _ => roundtrip::<ToolsCallResult>(result),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct ServicesState {
+        tools: InMemoryToolRegistry,
+        services: crate::server::Services,
+    }
+
+    impl McpServerState for ServicesState {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+
+        fn services(&self) -> Option<&crate::server::Services> {
+            Some(&self.services)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_dependencies() {
+        struct Pool;
+        let mut tools = InMemoryToolRegistry::new();
+        tools.register_tool(
+            crate::server::McpTool::new("query", "Run a query", serde_json::json!({}), "db")
+                .requires::<Pool>(),
+        );
+        let state = ServicesState {
+            tools,
+            services: crate::server::Services::new(),
+        };
+
+        let server = McpServer::new(McpServerConfig::default(), state.clone());
+        let error = server.check_dependencies().await.unwrap_err();
+        assert!(error.to_string().contains("tool 'query' requires"));
+
+        let state = ServicesState {
+            services: state.services.with(Pool),
+            ..state
+        };
+        let server = McpServer::new(McpServerConfig::default(), state);
+        server.check_dependencies().await.unwrap();
+    }
+
+    #[derive(Clone)]
+    struct ListingAuth;
+
+    #[async_trait]
+    impl McpAuth for ListingAuth {
+        async fn authenticate(
+            &self,
+            _client_info: &crate::security::ClientContext,
+        ) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::system())
+        }
+
+        async fn authorize(
+            &self,
+            _context: &SecurityContext,
+            resource: &str,
+            action: &str,
+        ) -> bool {
+            resource == "tool:public" && action == LIST_ACTION
+        }
+    }
+
+    #[derive(Clone)]
+    struct ListingState {
+        tools: InMemoryToolRegistry,
+        auth: crate::security::CachedAuth<ListingAuth>,
+    }
+
+    impl McpServerState for ListingState {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = crate::security::CachedAuth<ListingAuth>;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &self.auth
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_is_filtered_by_authorization() {
+        let mut tools = InMemoryToolRegistry::new();
+        for name in ["public", "internal"] {
+            tools.register_tool(crate::server::McpTool::new(
+                name,
+                "A tool",
+                serde_json::json!({}),
+                "test",
+            ));
+        }
+        let auth = crate::security::CachedAuth::new(ListingAuth);
+        let cache = auth.cache().clone();
+        let config = McpServerConfig::default().with_tool_listing_authorization();
+        let server = McpServer::new(config, ListingState { tools, auth });
+
+        let request = JsonRpcRequest::new("tools/list", None, Some(serde_json::json!(1)));
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        let result = response.result.unwrap();
+        let names: Vec<&str> = result["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["public"]);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tool_limits_are_enforced() {
+        let mut tools = InMemoryToolRegistry::new();
+        tools.register_tool(
+            crate::server::McpTool::new("export", "Export data", serde_json::json!({}), "test")
+                .with_limit("2/min per principal"),
+        );
+        let state = TestServerState {
+            tools,
+            auth: TestAuth,
+        };
+        let clock = Clock::manual();
+        let server = McpServer::new(McpServerConfig::default(), state).with_clock(clock.clone());
+        let call = |principal: &str| {
+            let request = JsonRpcRequest::new(
+                "tools/call",
+                Some(serde_json::json!({"name": "export"})),
+                Some(serde_json::json!(1)),
+            );
+            server.handle_request(request, SecurityContext::service_account(principal))
+        };
+        let is_limited = |response: JsonRpcResponse| {
+            response
+                .error
+                .and_then(|error| error.data)
+                .is_some_and(|data| data["code"] == "rate_limited")
+        };
+
+        assert!(!is_limited(call("reporter").await));
+        assert!(!is_limited(call("reporter").await));
+        assert!(is_limited(call("reporter").await));
+        // Other principals have their own budget
+        assert!(!is_limited(call("auditor").await));
+
+        clock.advance(std::time::Duration::from_secs(61));
+        assert!(!is_limited(call("reporter").await));
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_is_paginated() {
+        let mut tools = InMemoryToolRegistry::new();
+        for name in ["e", "a", "d", "b", "c"] {
+            tools.register_tool(crate::server::McpTool::new(
+                name,
+                "A tool",
+                serde_json::json!({}),
+                "test",
+            ));
+        }
+        let state = TestServerState {
+            tools,
+            auth: TestAuth,
+        };
+        let server = McpServer::new(McpServerConfig::default().with_page_size(2), state);
+
+        let mut names = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..3 {
+            let params = cursor.map(|cursor| serde_json::json!({"cursor": cursor}));
+            let request = JsonRpcRequest::new("tools/list", params, Some(serde_json::json!(1)));
+            let result = server
+                .handle_request(request, SecurityContext::system())
+                .await
+                .result
+                .unwrap();
+            names.extend(
+                result["tools"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|tool| tool["name"].as_str().unwrap().to_string()),
+            );
+            cursor = result["nextCursor"].as_str().map(str::to_string);
+        }
+        assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(cursor, None);
+
+        let request = JsonRpcRequest::new(
+            "tools/list",
+            Some(serde_json::json!({"cursor": "not a cursor"})),
+            Some(serde_json::json!(1)),
+        );
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        struct Pool;
+        let mut tools = InMemoryToolRegistry::new();
+        tools.register_tool(
+            crate::server::McpTool::new("query", "Run a query", serde_json::json!({}), "db")
+                .requires::<Pool>(),
+        );
+        let state = ServicesState {
+            tools,
+            services: crate::server::Services::new(),
+        };
+        let config = McpServerConfig {
+            max_batch_size: 0,
+            ..Default::default()
+        };
+
+        let report = McpServer::new(config, state).validate().await;
+        let codes: Vec<(&str, &str)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.code.as_str(), issue.subject.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                ("config", "max_batch_size"),
+                ("invalid-tool-schema", "tool 'query'"),
+                ("missing-service", "tool 'query'"),
+            ]
+        );
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.contains("3 error(s)"));
+    }
+
+    /// Tools and resources only listed and read by privileged contexts
+    #[derive(Clone)]
+    struct Restricted<R>(R);
+
+    #[async_trait]
+    impl ToolRegistry for Restricted<InMemoryToolRegistry> {
+        async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>> {
+            if !context.can_list_all() {
+                return Ok(Vec::new());
+            }
+            self.0.list_tools(context).await
+        }
+
+        async fn get_tool(
+            &self,
+            name: &str,
+            context: &SecurityContext,
+        ) -> McpResult<Option<crate::server::McpTool>> {
+            if !context.can_list_all() {
+                return Ok(None);
+            }
+            self.0.get_tool(name, context).await
+        }
+
+        async fn execute_tool(
+            &self,
+            name: &str,
+            execution_context: ToolExecutionContext,
+        ) -> McpResult<ToolsCallResult> {
+            self.0.execute_tool(name, execution_context).await
+        }
+
+        async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
+            context.can_list_all() && self.0.can_access_tool(name, context).await
+        }
+    }
+
+    #[async_trait]
+    impl ResourceRegistry for Restricted<crate::server::resource::InMemoryResourceRegistry> {
+        fn uri_scheme(&self) -> &crate::server::resource::UriSchemeConfig {
+            self.0.uri_scheme()
+        }
+
+        async fn list_resource_templates(
+            &self,
+            context: &SecurityContext,
+        ) -> McpResult<Vec<crate::server::resource::ResourceTemplate>> {
+            if !context.can_list_all() {
+                return Ok(Vec::new());
+            }
+            self.0.list_resource_templates(context).await
+        }
+
+        async fn get_resource(&self, uri: &str, context: &SecurityContext) -> McpResult<Resource> {
+            if !context.can_read_all() {
+                return Err(McpError::ResourceNotFound {
+                    uri: uri.to_string(),
+                });
+            }
+            self.0.get_resource(uri, context).await
+        }
+
+        async fn resource_exists(&self, uri: &str, context: &SecurityContext) -> McpResult<bool> {
+            Ok(context.can_read_all() && self.0.resource_exists(uri, context).await?)
+        }
+
+        async fn subscribe_to_resource(
+            &self,
+            uri: &str,
+            context: &SecurityContext,
+        ) -> McpResult<crate::server::resource::ResourceSubscription> {
+            self.0.subscribe_to_resource(uri, context).await
+        }
+
+        async fn unsubscribe_from_resource(
+            &self,
+            subscription_id: &str,
+            context: &SecurityContext,
+        ) -> McpResult<()> {
+            self.0
+                .unsubscribe_from_resource(subscription_id, context)
+                .await
+        }
+    }
+
+    #[derive(Clone)]
+    struct RestrictedState {
+        tools: Restricted<InMemoryToolRegistry>,
+        docs: Restricted<crate::server::resource::InMemoryResourceRegistry>,
+    }
+
+    impl McpServerState for RestrictedState {
+        type ToolRegistry = Restricted<InMemoryToolRegistry>;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+
+        fn resource_registry(&self) -> Option<&dyn ResourceRegistry> {
+            Some(&self.docs)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restricted_items_are_validated_and_indexed() {
+        struct Pool;
+        let mut tools = InMemoryToolRegistry::new();
+        tools.register_tool(
+            crate::server::McpTool::new("deploy", "Deploy a release", serde_json::json!({}), "ops")
+                .requires::<Pool>(),
+        );
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        let uri = "docs://runbooks/deploy.md";
+        docs.add_resource(Resource::from_bytes(
+            uri,
+            uri,
+            b"Roll back with the previous tag.",
+            None,
+        ));
+        docs.add_template(crate::server::resource::ResourceTemplate {
+            uri_template: uri.to_string(),
+            name: uri.to_string(),
+            description: None,
+            mime_type: None,
+            metadata: HashMap::new(),
+        });
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            RestrictedState {
+                tools: Restricted(tools),
+                docs: Restricted(docs),
+            },
+        )
+        .with_search_index(Arc::new(crate::server::search::InMemorySearchIndex::new()));
+
+        // Hidden from ordinary service accounts
+        let job = SecurityContext::service_account("nightly-job");
+        assert!(server.tools().list_tools(&job).await.unwrap().is_empty());
+
+        let error = server.check_dependencies().await.unwrap_err();
+        assert!(error.to_string().contains("tool 'deploy' requires"));
+        let report = server.validate().await;
+        assert!(report
+            .issues
+            .iter()
+            .any(|issue| issue.code == "missing-service" && issue.subject == "tool 'deploy'"));
+
+        assert_eq!(server.index_resources().await.unwrap(), 1);
+        let request = JsonRpcRequest::new(
+            RESOURCES_SEARCH,
+            Some(serde_json::json!({"query": "roll back"})),
+            Some(1.into()),
+        );
+        let results = server
+            .handle_request(request, SecurityContext::system())
+            .await
+            .result
+            .unwrap()["results"]
+            .clone();
+        assert_eq!(results[0]["uri"], uri);
+    }
+
+    #[tokio::test]
+    async fn test_batch_items_run_on_the_batch_slot() {
+        let config = McpServerConfig::default()
+            .with_scheduler(crate::server::SchedulerConfig::enabled(1, 0));
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("scheduled".to_string());
+
+        let batch = JsonRpcRequest::new(
+            "batch",
+            Some(serde_json::json!({
+                "requests": [
+                    {"id": "a", "method": "tools/call", "params": {"name": "count"}},
+                    {"id": "b", "method": "tools/call", "params": {"name": "count"}}
+                ]
+            })),
+            Some(serde_json::json!(1)),
+        );
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            server.handle_request(batch, context),
+        )
+        .await
+        .expect("batch items queued behind their batch");
+        assert!(response.error.is_none());
+
+        let stats = server.scheduler().stats();
+        assert_eq!((stats.running, stats.shed), (0, 0));
+        #[cfg(feature = "metrics")]
+        assert!(server.get_health().await.metrics.contains_key("scheduler"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_item_ids_are_not_tracked_as_duplicates() {
+        let config = McpServerConfig::default()
+            .with_duplicate_requests(crate::server::DuplicateRequestConfig::reject());
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("batched".to_string());
+
+        let batch = JsonRpcRequest::new(
+            "batch",
+            Some(serde_json::json!({
+                "requests": [{"id": "7", "method": "tools/call", "params": {"name": "count"}}]
+            })),
+            Some(serde_json::json!(1)),
+        );
+        let result = server
+            .handle_request(batch, context.clone())
+            .await
+            .result
+            .unwrap();
+        assert!(result["results"][0]["error"].is_null());
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count"})),
+            Some(serde_json::json!("7")),
+        );
+        assert!(server.handle_request(call, context).await.error.is_none());
+        assert_eq!(server.duplicate_requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batches_resume_by_correlation_token() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("resumed".to_string());
+        let send = || {
+            let batch = JsonRpcRequest::new(
+                "batch",
+                Some(serde_json::json!({
+                    "correlationToken": "nightly",
+                    "requests": [
+                        {"id": "a", "method": "tools/call", "params": {"name": "count"}},
+                        {"id": "b", "method": "missing/method"}
+                    ]
+                })),
+                Some(serde_json::json!(1)),
+            );
+            let server = server.clone();
+            let context = context.clone();
+            async move { server.handle_request(batch, context).await.result.unwrap() }
+        };
+
+        let first = send().await;
+        assert_eq!(first["correlationToken"], "nightly");
+        assert!(first["results"][1]["error"].is_object());
+
+        // The counted item is answered from the record, the failed one runs again
+        let second = send().await;
+        let a = &second["results"][0];
+        assert_eq!(a["result"]["content"][0]["text"], "1");
+        assert_eq!(a[RESUMED_META], true);
+        assert!(second["results"][1]["error"].is_object());
+        assert!(second["results"][1].get(RESUMED_META).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_dependency_batches_template_results() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("dependent".to_string());
+
+        let batch = JsonRpcRequest::new(
+            "batch",
+            Some(serde_json::json!({
+                "executionMode": "dependency",
+                "requests": [
+                    {"id": "second", "method": "tools/call", "params": {
+                        "name": "count",
+                        "arguments": {"after": "${items.first.result.content.0.text}"}
+                    }},
+                    {"id": "first", "method": "tools/call", "params": {"name": "count"}},
+                    {"id": "broken", "method": "missing/method"},
+                    {"id": "after", "method": "ping", "dependencies": ["broken"]}
+                ]
+            })),
+            Some(serde_json::json!(1)),
+        );
+        let result = server.handle_request(batch, context).await.result.unwrap();
+        let results = &result["results"];
+
+        // Results keep batch order, but items ran after their dependencies
+        assert_eq!(results[0]["id"], "second");
+        assert_eq!(results[0]["result"]["content"][0]["text"], "2");
+        assert_eq!(results[1]["result"]["content"][0]["text"], "1");
+        assert_eq!(results[3]["skipped"], true);
+        assert_eq!(result["stats"]["skippedRequests"], 1);
+        assert_eq!(result["stats"]["failedRequests"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_items_run_on_behalf_of_delegated_principals() {
+        let config = McpServerConfig::default()
+            .with_audit_trail(crate::server::audit::AuditRetention::default());
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("gateway".to_string());
+
+        let batch = JsonRpcRequest::new(
+            "batch",
+            Some(serde_json::json!({
+                "requests": [
+                    {"id": "a", "method": "tools/call", "params": {"name": "count"},
+                     "onBehalfOf": "alice"},
+                    {"id": "m", "method": "tools/call", "params": {"name": "count"},
+                     "onBehalfOf": "mallory"}
+                ],
+                "executionMode": "sequential"
+            })),
+            Some(serde_json::json!(1)),
+        );
+        let result = server
+            .handle_request(batch, context.clone())
+            .await
+            .result
+            .unwrap();
+        assert!(result["results"][0]["error"].is_null());
+        assert_eq!(
+            result["results"][1]["error"]["code"],
+            McpError::Authorization {
+                message: String::new()
+            }
+            .error_code()
+        );
+
+        let records = server.audit_trail().unwrap().recent(&context).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].principal, "alice");
+        assert_eq!(records[0].delegated_by.as_deref(), Some("system"));
+        assert_eq!(records[0].executed_by.as_deref(), Some(BATCH_EXECUTOR));
+    }
+
+    #[tokio::test]
+    async fn test_overloaded_server_stays_responsive() {
+        let config = McpServerConfig::default()
+            .with_admission(crate::server::AdmissionConfig::enabled().with_max_memory(1024));
+        let server =
+            McpServer::new(config, CounterServerState).with_memory_probe(Arc::new(|| Some(4096)));
+        let context = SecurityContext::system();
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count"})),
+            Some(serde_json::json!(1)),
+        );
+        let error = server
+            .handle_request(call, context.clone())
+            .await
+            .error
+            .unwrap();
+        assert_eq!(error.message, "Server overloaded, retry later");
+        assert_eq!(error.data.unwrap()["retryAfter"], 5);
+
+        let ping = JsonRpcRequest::new("ping", None, Some(serde_json::json!(2)));
+        assert!(server.handle_request(ping, context).await.error.is_none());
+        assert_eq!(server.admission().stats().rejected, 1);
+        #[cfg(feature = "metrics")]
+        assert!(server.get_health().await.metrics.contains_key("admission"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_audited() {
+        let config = McpServerConfig::default()
+            .with_audit_trail(crate::server::audit::AuditRetention::default());
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("audited".to_string());
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count", "arguments": {"step": 1}})),
+            Some(serde_json::json!(1)),
+        );
+        assert!(server
+            .handle_request(call, context.clone())
+            .await
+            .error
+            .is_none());
+
+        let records = server.audit_trail().unwrap().recent(&context).await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tool, "count");
+        assert_eq!(records[0].principal, "system");
+        assert_eq!(records[0].outcome, ExecutionOutcome::Success);
+
+        let read = JsonRpcRequest::new(
+            "resources/read",
+            Some(serde_json::json!({"uri": records[0].uri()})),
+            Some(serde_json::json!(2)),
+        );
+        let response = server.handle_request(read, context.clone()).await;
+        let text = response.result.unwrap()["contents"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let record: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(record["sessionId"], "audited");
+
+        let list = JsonRpcRequest::new("resources/list", None, Some(serde_json::json!(3)));
+        let response = server.handle_request(list, context).await;
+        assert_eq!(
+            response.result.unwrap()["resources"][0]["uri"],
+            "mcp-audit://executions"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_await_approval() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "guarded", "arguments": {"env": "prod"}})),
+            Some(serde_json::json!(1)),
+        );
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("approvals".to_string());
+        let waiting = tokio::spawn({
+            let server = server.clone();
+            async move { server.handle_request(call, context).await }
+        });
+        while server.approvals().pending_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // Only system contexts may use the admin methods
+        let list = JsonRpcRequest::new("admin/approvals/list", None, Some(serde_json::json!(2)));
+        let response = server
+            .handle_request(
+                list.clone(),
+                SecurityContext::authenticated(Default::default(), Vec::new()),
+            )
+            .await;
+        assert!(response.error.is_some());
+
+        let response = server.handle_request(list, SecurityContext::system()).await;
+        let pending = &response.result.unwrap()["approvals"][0];
+        assert_eq!(pending["tool"], "guarded");
+        assert_eq!(pending["arguments"]["env"], "prod");
+
+        let decide = JsonRpcRequest::new(
+            "admin/approvals/decide",
+            Some(serde_json::json!({"id": pending["id"], "decision": "approved"})),
+            Some(serde_json::json!(3)),
+        );
+        let response = server
+            .handle_request(decide, SecurityContext::system())
+            .await;
+        assert!(response.error.is_none());
+
+        let response = waiting.await.unwrap();
+        assert_eq!(response.result.unwrap()["content"][0]["text"], "1");
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_run_as_tasks() {
+        let config = McpServerConfig::default().with_tasks(crate::server::TaskConfig::enabled());
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("tasks".to_string());
+        let request = |method: &str, params: serde_json::Value| {
+            let server = server.clone();
+            let request = JsonRpcRequest::new(method, Some(params), Some(serde_json::json!(1)));
+            let context = context.clone();
+            async move { server.handle_request(request, context).await }
+        };
+
+        let response = request(
+            "initialize",
+            serde_json::json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "1.0.0"}
+            }),
+        )
+        .await;
+        assert!(response.result.unwrap()["capabilities"]["experimental"]["tasks"].is_object());
+
+        let response = request("tasks/create", serde_json::json!({"name": "count"})).await;
+        let task_id = response.result.unwrap()["task"]["taskId"].clone();
+        loop {
+            let response = request("tasks/status", serde_json::json!({"taskId": task_id})).await;
+            if response.result.unwrap()["task"]["status"] == "completed" {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        let response = request("tasks/result", serde_json::json!({"taskId": task_id})).await;
+        assert_eq!(response.result.unwrap()["content"][0]["text"], "1");
+
+        let response = request("tasks/list", serde_json::json!({})).await;
+        assert_eq!(
+            response.result.unwrap()["tasks"].as_array().unwrap().len(),
+            1
+        );
+
+        // Without the feature enabled the methods are refused
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let response = server
+            .handle_request(
+                JsonRpcRequest::new(
+                    "tasks/create",
+                    Some(serde_json::json!({"name": "count"})),
+                    Some(serde_json::json!(1)),
+                ),
+                SecurityContext::system(),
+            )
+            .await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_are_metered() {
+        let config = McpServerConfig::default().with_quota(
+            crate::server::QuotaConfig::per_window(std::time::Duration::from_secs(3600))
+                .with_limit(QuotaMetric::ToolCalls, 1),
+        );
+        let server = McpServer::new(config, CounterServerState);
+        let mut context = SecurityContext::system();
+        context.client.session_id = Some("metered".to_string());
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count"})),
+            Some(serde_json::json!(1)),
+        );
+
+        let response = server.handle_request(call.clone(), context.clone()).await;
+        assert!(response.error.is_none());
+        let error = server
+            .handle_request(call.clone(), context.clone())
+            .await
+            .error
+            .unwrap();
+        assert_eq!(error.code, -32000);
+        assert_eq!(error.data.unwrap()["remaining"]["tool_calls"], 0);
+
+        let inspect = JsonRpcRequest::new(
+            "admin/quotas/inspect",
+            Some(serde_json::json!({"principal": "system"})),
+            Some(serde_json::json!(2)),
+        );
+        let response = server.handle_request(inspect, context.clone()).await;
+        assert_eq!(
+            response.result.unwrap()["quotas"][0]["used"]["tool_calls"],
+            1
+        );
+
+        let reset = JsonRpcRequest::new(
+            "admin/quotas/reset",
+            Some(serde_json::json!({"principal": "system"})),
+            Some(serde_json::json!(3)),
+        );
+        assert!(server
+            .handle_request(reset, context.clone())
+            .await
+            .error
+            .is_none());
+        assert!(server.handle_request(call, context).await.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_inspector_captures_redacted_exchanges() {
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let context = SecurityContext::system();
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "count", "arguments": {"token": "s3cret"}})),
+            Some(serde_json::json!(1)),
+        );
+        server.handle_request(call.clone(), context.clone()).await;
+        assert!(server.inspector().is_empty());
+
+        let configure = JsonRpcRequest::new(
+            "admin/inspector/configure",
+            Some(serde_json::json!({"enabled": true})),
+            Some(serde_json::json!(2)),
+        );
+        let response = server.handle_request(configure, context.clone()).await;
+        assert_eq!(response.result.unwrap()["enabled"], true);
+        server.handle_request(call, context.clone()).await;
+
+        let list = JsonRpcRequest::new("admin/inspector/list", None, Some(serde_json::json!(3)));
+        let result = server.handle_request(list, context).await.result.unwrap();
+        let exchanges = result["exchanges"].as_array().unwrap();
+        assert_eq!(exchanges.len(), 1);
+        assert_eq!(exchanges[0]["method"], "tools/call");
+        assert_eq!(
+            exchanges[0]["request"]["params"]["arguments"]["token"],
+            crate::server::inspector::REDACTED
+        );
+        assert_eq!(exchanges[0]["response"]["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_wire_tap_mirrors_tapped_sessions() {
+        let dir = std::env::temp_dir().join(format!("wire-tap-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wire.jsonl");
+        let config =
+            McpServerConfig::default().with_wire_tap(crate::server::WireTapConfig::file(&path));
+        let server = McpServer::new(config, CounterServerState);
+        let session = |id: &str| {
+            let mut context = SecurityContext::system();
+            context.client.session_id = Some(id.to_string());
+            context
+        };
+        let ping = JsonRpcRequest::new("ping", None, Some(serde_json::json!(1)));
+
+        let configure = JsonRpcRequest::new(
+            ADMIN_WIRE_TAP,
+            Some(serde_json::json!({"sessionId": "a", "enabled": true})),
+            Some(serde_json::json!(2)),
+        );
+        let response = server
+            .handle_request(configure, SecurityContext::system())
+            .await;
+        assert_eq!(response.result.unwrap()["sessions"][0], "a");
+        server.handle_request(ping.clone(), session("a")).await;
+        server.handle_request(ping, session("b")).await;
+
+        let frames: Vec<crate::server::TapFrame> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(frames.iter().all(|f| f.session_id.as_deref() == Some("a")));
+        assert_eq!(frames[0].direction, FrameDirection::Inbound);
+        assert_eq!(frames[0].frame["method"], "ping");
+        assert_eq!(frames[1].direction, FrameDirection::Outbound);
+        assert_eq!(frames[1].frame["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_smoke_tools_are_listed_and_called() {
+        let config =
+            McpServerConfig::default().with_smoke_tools(crate::server::SmokeToolsConfig::enabled());
+        let server = McpServer::new(config, CounterServerState);
+        let mut caller = SecurityContext::system();
+        caller.client.session_id = Some("smoke".to_string());
+
+        let list = JsonRpcRequest::new("tools/list", None, Some(serde_json::json!(1)));
+        let response = server.handle_request(list, caller.clone()).await;
+        let tools = response.result.unwrap()["tools"].clone();
+        assert!(tools
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|t| t["name"] == crate::server::SMOKE_ECHO));
+
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": crate::server::SMOKE_SERVER_STATS})),
+            Some(serde_json::json!(2)),
+        );
+        let response = server.handle_request(call, caller).await;
+        let text = response.result.unwrap()["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let stats: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(stats["caller"]["sessionId"], "smoke");
+        assert_eq!(stats["tools"], tools.as_array().unwrap().len());
+
+        let report = server.validate().await;
+        assert!(report.warnings().any(|w| w.code == "smoke-tools-enabled"));
+    }
+
+    #[tokio::test]
+    async fn test_method_authorization_matrix() {
+        let matrix = crate::security::MethodAuthorization::new()
+            .require("tools/call", ["tool_user"])
+            .require("tool/call", ["tool_user"]);
+        let server = McpServer::new(
+            McpServerConfig::default().with_method_authorization(matrix),
+            CounterServerState,
+        );
+        let call = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "increment"})),
+            Some(serde_json::json!(1)),
+        );
+        let mut context = SecurityContext::system();
+        context.capabilities = vec!["initialized".to_string()];
+
+        let response = server.handle_request(call.clone(), context.clone()).await;
+        let denied = |response: &JsonRpcResponse| {
+            response
+                .error
+                .as_ref()
+                .and_then(|e| e.data.as_ref())
+                .is_some_and(|data| data["code"] == "authorization")
+        };
+        assert!(denied(&response));
+
+        context.add_capability("tool_user");
+        let response = server.handle_request(call, context).await;
+        assert!(!denied(&response));
+
+        let report = server.validate().await;
+        let unknown: Vec<_> = report
+            .warnings()
+            .filter(|w| w.code == "unknown-method-rule")
+            .collect();
+        assert_eq!(unknown.len(), 1);
+        assert!(unknown[0].subject.contains("tool/call"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_changelog_admin_method() {
+        let changelog = crate::server::RegistryChangelog::new(10);
+        let server = McpServer::new(McpServerConfig::default(), CounterServerState);
+        let request = |id| {
+            JsonRpcRequest::new(
+                ADMIN_REGISTRY_CHANGELOG,
+                Some(serde_json::json!({"kind": "prompt"})),
+                Some(serde_json::json!(id)),
+            )
+        };
+        let response = server
+            .handle_request(request(1), SecurityContext::system())
+            .await;
+        assert!(response.error.is_some());
+
+        let server = server.with_registry_changelog(changelog.clone());
+        let mut prompts = crate::server::InMemoryPromptRegistry::new()
+            .with_changelog(changelog.by("ops@example.com"));
+        prompts.add_code_analysis_prompt("review", "Review code", "file:///src/main.rs");
+        prompts.remove_prompt("review");
+        changelog.record(
+            crate::server::RegistryKind::Tool,
+            "export",
+            crate::server::RegistryAction::Registered,
+        );
+
+        let response = server
+            .handle_request(request(2), SecurityContext::system())
+            .await;
+        let changes = response.result.unwrap()["changes"].clone();
+        assert_eq!(changes.as_array().unwrap().len(), 2);
+        assert_eq!(changes[0]["action"], "removed");
+        assert_eq!(changes[0]["actor"], "ops@example.com");
+
+        let response = server
+            .handle_request(request(3), SecurityContext::anonymous())
+            .await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_capability_snapshot_is_deterministic() {
+        let server = McpServer::new(
+            McpServerConfig::default().with_audit_trail(Default::default()),
+            CounterServerState,
+        );
+        let snapshot = server.capability_snapshot().await.unwrap();
+        assert!(snapshot.resources.contains_key("mcp-audit://executions"));
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            serde_json::to_string(&server.capability_snapshot().await.unwrap()).unwrap()
+        );
+        assert!(snapshot
+            .diff(&server.capability_snapshot().await.unwrap())
+            .is_empty());
+    }
+
+    #[derive(Clone)]
+    struct DocsServerState {
+        tools: InMemoryToolRegistry,
+        docs: Arc<crate::server::resource::InMemoryResourceRegistry>,
+    }
+
+    impl McpServerState for DocsServerState {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.tools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+
+        fn resource_registry(&self) -> Option<&dyn ResourceRegistry> {
+            Some(self.docs.as_ref())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resources_diff_against_provided_content() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        docs.add_resource(crate::server::resource::Resource {
+            uri: "docs://readme".to_string(),
+            name: "Readme".to_string(),
+            description: None,
+            mime_type: Some("text/markdown".to_string()),
+            content: ResourceContent::Text {
+                text: "# Title\nold line\n".to_string(),
+            },
+            metadata: HashMap::new(),
+        });
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        );
+
+        let diff = JsonRpcRequest::new(
+            RESOURCES_DIFF,
+            Some(serde_json::json!({
+                "uri": "docs://readme",
+                "content": "# Title\nnew line\n",
+            })),
+            Some(serde_json::json!(1)),
+        );
+        let response = server.handle_request(diff, SecurityContext::system()).await;
+        assert_eq!(
+            response.result.unwrap(),
+            serde_json::json!({
+                "uri": "docs://readme",
+                "diff": "--- docs://readme\n+++ docs://readme (provided)\n\
+                         @@ -1,2 +1,2 @@\n # Title\n-old line\n+new line\n",
+                "additions": 1,
+                "deletions": 1,
+            })
+        );
+
+        let diff = JsonRpcRequest::new(
+            RESOURCES_DIFF,
+            Some(serde_json::json!({
+                "uri": "docs://readme",
+                "toVersion": 1,
+                "content": "",
+            })),
+            Some(serde_json::json!(2)),
+        );
+        let response = server.handle_request(diff, SecurityContext::system()).await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_versioned_resources() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        for text in ["draft\n", "final\n"] {
+            docs.add_resource(Resource {
+                uri: "docs://notes".to_string(),
+                name: "Notes".to_string(),
+                description: None,
+                mime_type: Some("text/plain".to_string()),
+                content: ResourceContent::Text {
+                    text: text.to_string(),
+                },
+                metadata: HashMap::new(),
+            });
+        }
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        );
+        let call = |method: &str, params: serde_json::Value| {
+            let server = server.clone();
+            let request = JsonRpcRequest::new(method, Some(params), Some(serde_json::json!(1)));
+            async move {
+                server
+                    .handle_request(request, SecurityContext::system())
+                    .await
+            }
+        };
+
+        let response = call(
+            "resources/read",
+            serde_json::json!({"uri": "docs://notes?version=1"}),
+        )
+        .await;
+        let content = &response.result.unwrap()["contents"][0];
+        assert_eq!(content["text"], "draft\n");
+        assert_eq!(content["uri"], "docs://notes?version=1");
+
+        let response = call("resources/read", serde_json::json!({"uri": "docs://notes"})).await;
+        assert_eq!(response.result.unwrap()["contents"][0]["text"], "final\n");
+
+        let response = call(
+            RESOURCES_VERSIONS,
+            serde_json::json!({"uri": "docs://notes"}),
+        )
+        .await;
+        let versions = response.result.unwrap()["versions"].clone();
+        assert_eq!(versions[0]["version"], 1);
+        assert_eq!(versions[1]["version"], 2);
+
+        let response = call(
+            RESOURCES_DIFF,
+            serde_json::json!({"uri": "docs://notes", "fromVersion": 1}),
+        )
+        .await;
+        assert_eq!(
+            response.result.unwrap()["diff"],
+            "--- docs://notes@1\n+++ docs://notes\n@@ -1 +1 @@\n-draft\n+final\n"
+        );
+
+        let response = call(
+            "resources/read",
+            serde_json::json!({"uri": "docs://notes?version=3"}),
+        )
+        .await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_resources_are_not_sent_again() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        docs.add_resource(Resource {
+            uri: "docs://notes".to_string(),
+            name: "Notes".to_string(),
+            description: None,
+            mime_type: Some("text/plain".to_string()),
+            content: ResourceContent::Text {
+                text: "final\n".to_string(),
+            },
+            metadata: HashMap::new(),
+        });
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        );
+        let read = |params: serde_json::Value| {
+            let server = server.clone();
+            let request = JsonRpcRequest::new("resources/read", Some(params), Some(1.into()));
+            async move {
+                let response = server
+                    .handle_request(request, SecurityContext::system())
+                    .await;
+                response.result.unwrap()
+            }
+        };
+
+        let result = read(serde_json::json!({"uri": "docs://notes"})).await;
+        let etag = result["_meta"][ETAG_META].as_str().unwrap().to_string();
+        assert_eq!(etag, content_etag(b"final\n"));
+        assert_eq!(result["contents"][0]["text"], "final\n");
+
+        let tags = format!("\"stale\", \"{}\"", etag);
+        let result = read(serde_json::json!({"uri": "docs://notes", "ifNoneMatch": tags})).await;
+        assert_eq!(result["notModified"], true);
+        assert_eq!(result["contents"], serde_json::json!([]));
+        assert_eq!(result["_meta"][ETAG_META], etag);
+
+        let result =
+            read(serde_json::json!({"uri": "docs://notes", "if_none_match": "stale"})).await;
+        assert!(result.get("notModified").is_none());
+        assert_eq!(result["contents"][0]["text"], "final\n");
+    }
+
+    #[tokio::test]
+    async fn test_resources_are_transformed_on_read() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        docs.add_resource(Resource::from_bytes(
+            "docs://handbook/guide.md",
+            "Guide",
+            b"# Guide\n",
+            None,
+        ));
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        );
+
+        for params in [
+            serde_json::json!({"uri": "docs://handbook/guide.md?format=html"}),
+            serde_json::json!({"uri": "docs://handbook/guide.md", "accept": "text/html"}),
+        ] {
+            let read = JsonRpcRequest::new("resources/read", Some(params), Some(1.into()));
+            let result = server
+                .handle_request(read, SecurityContext::system())
+                .await
+                .result
+                .unwrap();
+            assert_eq!(result["contents"][0]["text"], "<h1>Guide</h1>\n");
+            assert_eq!(result["contents"][0]["mimeType"], "text/html");
+            assert_eq!(result["_meta"][TRANSFORMED_FROM_META], "text/markdown");
+        }
+
+        let read = JsonRpcRequest::new(
+            "resources/read",
+            Some(
+                serde_json::json!({"uri": "docs://handbook/guide.md", "accept": "application/pdf"}),
+            ),
+            Some(2.into()),
+        );
+        let response = server.handle_request(read, SecurityContext::system()).await;
+        assert!(response.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_resource_representations_are_negotiated() {
+        let mut data = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        let uri = "docs://sales";
+        data.add_resource(Resource::from_bytes(
+            uri,
+            "Sales",
+            b"[{\"month\":\"May\"}]",
+            Some("application/json"),
+        ));
+        data.add_representation(Resource::from_bytes(
+            uri,
+            "Sales",
+            b"month\nMay\n",
+            Some("text/csv"),
+        ));
+        data.add_template(crate::server::resource::ResourceTemplate {
+            uri_template: uri.to_string(),
+            name: "Sales".to_string(),
+            description: None,
+            mime_type: Some("application/json".to_string()),
+            metadata: HashMap::new(),
+        });
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(data),
+            },
+        );
+        let send = |method: &str, params: serde_json::Value| {
+            let request = JsonRpcRequest::new(method, Some(params), Some(1.into()));
+            server.handle_request(request, SecurityContext::system())
+        };
+
+        let list = send("resources/list", serde_json::json!({})).await;
+        assert_eq!(
+            list.result.unwrap()["resources"][0]["representations"],
+            serde_json::json!(["application/json", "text/csv"])
+        );
+
+        let read = send(
+            "resources/read",
+            serde_json::json!({"uri": uri, "accept": "application/json;q=0.5, text/csv"}),
+        )
+        .await;
+        let result = read.result.unwrap();
+        assert_eq!(result["contents"][0]["mimeType"], "text/csv");
+        assert_eq!(result["contents"][0]["text"], "month\nMay\n");
+        assert!(result["_meta"].get(TRANSFORMED_FROM_META).is_none());
+
+        let read = send("resources/read", serde_json::json!({"uri": uri})).await;
+        assert_eq!(
+            read.result.unwrap()["contents"][0]["mimeType"],
+            "application/json"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resources_read_many_reports_failures_per_uri() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        for uri in ["docs://a", "docs://b"] {
+            docs.add_resource(Resource::from_bytes(uri, uri, uri.as_bytes(), None));
+        }
+        let config = McpServerConfig::default().with_read_many(crate::server::ReadManyConfig {
+            max_uris: 3,
+            ..Default::default()
+        });
+        let server = McpServer::new(
+            config,
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        );
+        let send = |params: serde_json::Value| {
+            let request = JsonRpcRequest::new(RESOURCES_READ_MANY, Some(params), Some(1.into()));
+            server.handle_request(request, SecurityContext::system())
+        };
+
+        let response = send(serde_json::json!({
+            "uris": ["docs://b", "docs://missing", "docs://a"]
+        }))
+        .await;
+        let result: ResourcesReadManyResult =
+            serde_json::from_value(response.result.unwrap()).unwrap();
+        let uris: Vec<&str> = result.results.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, vec!["docs://b", "docs://missing", "docs://a"]);
+        assert!(result.results[0].is_ok());
+        assert!(!result.results[1].is_ok());
+        assert!(result.results[1].result.is_none());
+        match &result.results[2].result.as_ref().unwrap().contents[0] {
+            crate::protocol::messages::ResourceContent::Text { text, .. } => {
+                assert_eq!(text, "docs://a")
+            }
+            other => panic!("unexpected content {:?}", other),
+        }
+
+        let response = send(serde_json::json!({
+            "uris": ["docs://a", "docs://a", "docs://a", "docs://a"]
+        }))
+        .await;
+        assert!(response.error.is_some());
+    }
+
+    /// Registry whose `save_note` tool declares the notes it writes
+    struct NoteTools;
+
+    #[async_trait]
+    impl ToolRegistry for NoteTools {
+        async fn list_tools(
+            &self,
+            context: &SecurityContext,
+        ) -> McpResult<Vec<crate::protocol::Tool>> {
+            let tool = self.get_tool("save_note", context).await?;
+            Ok(tool.into_iter().map(|tool| tool.tool).collect())
+        }
+
+        async fn get_tool(
+            &self,
+            name: &str,
+            _context: &SecurityContext,
+        ) -> McpResult<Option<crate::server::McpTool>> {
+            Ok((name == "save_note").then(|| {
+                crate::server::McpTool::new(name, "Save a note", serde_json::json!({}), "notes")
+                    .reads_resource("docs://index")
+                    .writes_resource("docs://notes/{id}")
+                    .writes_resource("docs://drafts/*")
+            }))
+        }
+
+        async fn execute_tool(
+            &self,
+            _name: &str,
+            _context: ToolExecutionContext,
+        ) -> McpResult<crate::protocol::ToolsCallResult> {
+            Ok(crate::protocol::ToolsCallResult {
+                content: Vec::new(),
+                is_error: false,
+                metadata: Default::default(),
+            })
+        }
+
+        async fn can_access_tool(&self, _name: &str, _context: &SecurityContext) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone)]
+    struct NotesState {
+        docs: Arc<crate::server::resource::InMemoryResourceRegistry>,
+    }
+
+    impl McpServerState for NotesState {
+        type ToolRegistry = NoteTools;
+        type AuthManager = TestAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &NoteTools
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &TestAuth
+        }
+
+        fn resource_registry(&self) -> Option<&dyn ResourceRegistry> {
+            Some(self.docs.as_ref())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_declared_writes_are_announced() {
+        use futures_util::StreamExt;
+
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        for uri in ["docs://drafts/a", "docs://drafts/b", "docs://index"] {
+            docs.add_template(crate::server::resource::ResourceTemplate {
+                uri_template: uri.to_string(),
+                name: uri.to_string(),
+                description: None,
+                mime_type: None,
+                metadata: HashMap::new(),
+            });
+        }
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            NotesState {
+                docs: Arc::new(docs),
+            },
+        );
+        let mut published = server.notifications().broker().subscribe().await.unwrap();
+
+        let request = JsonRpcRequest::new(
+            "tools/call",
+            Some(serde_json::json!({"name": "save_note", "arguments": {"id": 7}})),
+            Some(serde_json::json!(1)),
+        );
+        let response = server
+            .handle_request(request, SecurityContext::system())
+            .await;
+        assert!(response.error.is_none());
+
+        let mut updated = Vec::new();
+        while let Ok(Some(notification)) =
+            tokio::time::timeout(std::time::Duration::from_millis(50), published.next()).await
+        {
+            if let McpMethod::NotificationsResourcesUpdated(update) = notification.method {
+                updated.push(update.uri);
+            }
+        }
+        assert_eq!(
+            updated,
+            vec!["docs://drafts/a", "docs://drafts/b", "docs://notes/7"]
+        );
+
+        let request = JsonRpcRequest::new(ADMIN_DEPENDENCIES, None, Some(serde_json::json!(2)));
+        let result = server
+            .handle_request(request, SecurityContext::system())
+            .await
+            .result
+            .unwrap();
+        assert_eq!(result["edges"].as_array().unwrap().len(), 3);
+        assert!(result["dot"]
+            .as_str()
+            .unwrap()
+            .contains("\"save_note\" -> \"docs://notes/{id}\";"));
+    }
+
+    #[tokio::test]
+    async fn test_resources_search() {
+        let mut docs = crate::server::resource::InMemoryResourceRegistry::new(
+            crate::server::resource::UriSchemeConfig::new("docs", "Documents"),
+        );
+        for (uri, text) in [
+            ("docs://handbook/limits.md", "Rate limits apply per client."),
+            ("docs://handbook/auth.md", "Clients send a bearer token."),
+        ] {
+            docs.add_resource(Resource::from_bytes(uri, uri, text.as_bytes(), None));
+            docs.add_template(crate::server::resource::ResourceTemplate {
+                uri_template: uri.to_string(),
+                name: uri.to_string(),
+                description: None,
+                mime_type: None,
+                metadata: HashMap::new(),
+            });
+        }
+        let server = McpServer::new(
+            McpServerConfig::default(),
+            DocsServerState {
+                tools: InMemoryToolRegistry::new(),
+                docs: Arc::new(docs),
+            },
+        )
+        .with_search_index(Arc::new(crate::server::search::InMemorySearchIndex::new()));
+        assert_eq!(server.index_resources().await.unwrap(), 2);
+
+        let search = |query: &str| {
+            let request = JsonRpcRequest::new(
+                RESOURCES_SEARCH,
+                Some(serde_json::json!({"query": query})),
+                Some(1.into()),
+            );
+            let server = &server;
+            async move {
+                server
+                    .handle_request(request, SecurityContext::system())
+                    .await
+                    .result
+                    .unwrap()["results"]
+                    .clone()
+            }
+        };
+        let results = search("rate limit").await;
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["uri"], "docs://handbook/limits.md");
+        assert_eq!(results[0]["snippet"], "Rate limits apply per client.");
+
+        // Changes are picked up; URIs the registry no longer has are dropped
+        server.resource_changed(ResourceChanged {
+            uri: "docs://handbook/auth.md".to_string(),
+            change_type: ResourceChangeType::Updated,
+            content: Some(ResourceContent::Text {
+                text: "Rate limited clients should back off.".to_string(),
+            }),
+        });
+        assert_eq!(search("rate").await.as_array().unwrap().len(), 2);
+        server.resource_changed(ResourceChanged {
+            uri: "docs://handbook/limits.md".to_string(),
+            change_type: ResourceChangeType::Deleted,
+            content: None,
+        });
+        assert_eq!(search("rate").await[0]["uri"], "docs://handbook/auth.md");
+        assert_eq!(search("rate").await.as_array().unwrap().len(), 1);
+    }
 }
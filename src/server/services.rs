@@ -0,0 +1,195 @@
+//! Typed service container for tools and prompts
+//!
+//! Tools often need shared services such as a database pool or an HTTP client.
+//! Instead of every registry capturing them by hand, the server state offers a
+//! [`Services`] container through
+//! [`McpServerState::services`](crate::server::McpServerState::services), and
+//! tool calls reach it through
+//! [`ToolExecutionContext::service`](crate::server::ToolExecutionContext::service).
+//!
+//! Tools and prompts declare what they need with `requires::<T>()`, and
+//! [`McpServer::check_dependencies`](crate::server::McpServer::check_dependencies)
+//! reports missing services at startup rather than on the first call.
+
+use serde_json::Value;
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::Arc,
+};
+
+use crate::{
+    error::{McpError, McpResult},
+    server::{prompt::Prompt, registry::McpTool},
+};
+
+/// Tool and prompt metadata key listing the services they require
+pub const REQUIRES_META: &str = "requires";
+
+#[derive(Clone)]
+struct Entry {
+    name: &'static str,
+    service: Arc<dyn Any + Send + Sync>,
+}
+
+/// Shared services keyed by their type
+///
+/// Cloning is cheap; clones share the registered services.
+#[derive(Clone, Default)]
+pub struct Services {
+    entries: Arc<HashMap<TypeId, Entry>>,
+}
+
+impl Services {
+    /// Create an empty container
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a service, replacing any previous one of the same type
+    pub fn with<T: Send + Sync + 'static>(self, service: T) -> Self {
+        self.with_arc(Arc::new(service))
+    }
+
+    /// Register a service that is already shared
+    pub fn with_arc<T: Send + Sync + 'static>(mut self, service: Arc<T>) -> Self {
+        self.insert(service);
+        self
+    }
+
+    /// Register a service that is already shared, replacing any previous one
+    pub fn insert<T: Send + Sync + 'static>(&mut self, service: Arc<T>) {
+        Arc::make_mut(&mut self.entries).insert(
+            TypeId::of::<T>(),
+            Entry {
+                name: type_name::<T>(),
+                service,
+            },
+        );
+    }
+
+    /// Get the service of type `T`, if registered
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .and_then(|entry| Arc::clone(&entry.service).downcast().ok())
+    }
+
+    /// Get the service of type `T`, failing if it is not registered
+    pub fn require<T: Send + Sync + 'static>(&self) -> McpResult<Arc<T>> {
+        self.get().ok_or_else(|| McpError::Configuration {
+            message: format!("Service '{}' is not registered", type_name::<T>()),
+        })
+    }
+
+    /// Whether a service with the given type name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.values().any(|entry| entry.name == name)
+    }
+
+    /// Type names of the registered services
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.entries.values().map(|entry| entry.name).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Names in `requires` metadata that are not registered
+    pub fn missing<'a>(
+        &self,
+        metadata: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    ) -> Vec<String> {
+        required(metadata)
+            .into_iter()
+            .filter(|name| !self.contains(name))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for Services {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Services")
+            .field("services", &self.names())
+            .finish()
+    }
+}
+
+/// Service type names listed under [`REQUIRES_META`] in tool or prompt metadata
+pub fn required<'a>(metadata: impl IntoIterator<Item = (&'a String, &'a Value)>) -> Vec<String> {
+    let requires = metadata.into_iter().find(|(key, _)| *key == REQUIRES_META);
+    match requires {
+        Some((_, Value::Array(names))) => names
+            .iter()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Add `T` to the [`REQUIRES_META`] entry `requires`
+fn add_requirement<T: 'static>(requires: &mut Value) {
+    let name = Value::String(type_name::<T>().to_string());
+    match requires {
+        Value::Array(names) => {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        _ => *requires = Value::Array(vec![name]),
+    }
+}
+
+impl McpTool {
+    /// Declare that calls to this tool need the service of type `T`
+    pub fn requires<T: Send + Sync + 'static>(mut self) -> Self {
+        add_requirement::<T>(
+            self.tool
+                .metadata
+                .entry(REQUIRES_META.to_string())
+                .or_insert(Value::Null),
+        );
+        self
+    }
+}
+
+impl Prompt {
+    /// Declare that rendering this prompt needs the service of type `T`
+    pub fn requires<T: Send + Sync + 'static>(mut self) -> Self {
+        add_requirement::<T>(
+            self.metadata
+                .entry(REQUIRES_META.to_string())
+                .or_insert(Value::Null),
+        );
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pool(&'static str);
+
+    #[test]
+    fn test_services() {
+        let services = Services::new().with(Pool("primary")).with(42u32);
+        assert_eq!(services.require::<Pool>().unwrap().0, "primary");
+        assert_eq!(*services.get::<u32>().unwrap(), 42);
+        assert!(services.get::<String>().is_none());
+        assert!(services
+            .require::<String>()
+            .unwrap_err()
+            .to_string()
+            .contains("alloc::string::String"));
+
+        let tool = McpTool::new("query", "Run a query", serde_json::json!({}), "db")
+            .requires::<Pool>()
+            .requires::<Pool>()
+            .requires::<String>();
+        assert_eq!(required(&tool.tool.metadata).len(), 2);
+        assert_eq!(
+            services.missing(&tool.tool.metadata),
+            [type_name::<String>()]
+        );
+    }
+}
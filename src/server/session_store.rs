@@ -0,0 +1,245 @@
+//! Per-session working memory for tools
+//!
+//! Tools are usually stateless, but some need to remember things between calls
+//! in the same conversation: a pagination cursor, a token fetched earlier, the
+//! last query a user ran. [`SessionStore`] is a small key-value store scoped to
+//! one session and handed to tools through
+//! [`ToolExecutionContext::session_store`](crate::server::ToolExecutionContext::session_store).
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use crate::{clock::Clock, error::McpResult};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: Value,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Key-value working memory for a single session
+///
+/// Values are stored as JSON. Entries set with a TTL disappear once it
+/// elapses; the rest live as long as the session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStore {
+    entries: Arc<RwLock<HashMap<String, Entry>>>,
+    clock: Clock,
+}
+
+impl SessionStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expire entries on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get a value, if it is set and not expired
+    pub async fn get(&self, key: &str) -> Option<Value> {
+        let now = self.clock.now();
+        {
+            let entries = self.entries.read().await;
+            match entries.get(key) {
+                Some(entry) if !entry.is_expired(now) => return Some(entry.value.clone()),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+
+        // Drop the expired entry while we are here
+        let mut entries = self.entries.write().await;
+        if entries.get(key).is_some_and(|entry| entry.is_expired(now)) {
+            entries.remove(key);
+        }
+        None
+    }
+
+    /// Get a value deserialized into `T`
+    pub async fn get_as<T: DeserializeOwned>(&self, key: &str) -> McpResult<Option<T>> {
+        match self.get(key).await {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set a value that lives as long as the session
+    pub async fn set(&self, key: impl Into<String>, value: impl Serialize) -> McpResult<()> {
+        self.insert(key.into(), serde_json::to_value(value)?, None)
+            .await;
+        Ok(())
+    }
+
+    /// Set a value that expires after `ttl`
+    pub async fn set_with_ttl(
+        &self,
+        key: impl Into<String>,
+        value: impl Serialize,
+        ttl: Duration,
+    ) -> McpResult<()> {
+        self.insert(
+            key.into(),
+            serde_json::to_value(value)?,
+            Some(self.clock.now() + ttl),
+        )
+        .await;
+        Ok(())
+    }
+
+    /// Remove a value, returning it if it was set and not expired
+    pub async fn delete(&self, key: &str) -> Option<Value> {
+        let entry = self.entries.write().await.remove(key)?;
+        (!entry.is_expired(self.clock.now())).then_some(entry.value)
+    }
+
+    /// Remove all values
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+
+    /// Number of values that have not expired
+    pub async fn len(&self) -> usize {
+        let now = self.clock.now();
+        self.entries
+            .read()
+            .await
+            .values()
+            .filter(|entry| !entry.is_expired(now))
+            .count()
+    }
+
+    /// Whether the store holds no unexpired values
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    async fn insert(&self, key: String, value: Value, expires_at: Option<Instant>) {
+        let now = self.clock.now();
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, entry| !entry.is_expired(now));
+        entries.insert(key, Entry { value, expires_at });
+    }
+}
+
+/// Session stores for all sessions of a server
+///
+/// Stores are created on first use and dropped once their session has been
+/// idle for longer than the idle timeout, or explicitly via
+/// [`SessionStores::remove`].
+#[derive(Debug, Clone)]
+pub struct SessionStores {
+    stores: Arc<RwLock<HashMap<String, (SessionStore, Instant)>>>,
+    idle_timeout: Duration,
+    clock: Clock,
+}
+
+impl SessionStores {
+    /// Create a registry that drops stores idle for longer than `idle_timeout`
+    pub fn new(idle_timeout: Duration) -> Self {
+        Self {
+            stores: Arc::new(RwLock::new(HashMap::new())),
+            idle_timeout,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Measure idle time and entry expiry on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the store for a session, creating it if needed
+    pub async fn store(&self, session_id: &str) -> SessionStore {
+        let now = self.clock.now();
+        let mut stores = self.stores.write().await;
+        stores.retain(|id, (_, last_used)| {
+            let idle = now.duration_since(*last_used) > self.idle_timeout;
+            if idle {
+                debug!("Dropping idle session store for {}", id);
+            }
+            !idle
+        });
+
+        let (store, last_used) = stores
+            .entry(session_id.to_string())
+            .or_insert_with(|| (SessionStore::new().with_clock(self.clock.clone()), now));
+        *last_used = now;
+        store.clone()
+    }
+
+    /// Drop the store for a session
+    pub async fn remove(&self, session_id: &str) {
+        self.stores.write().await.remove(session_id);
+    }
+
+    /// Number of sessions with a store
+    pub async fn len(&self) -> usize {
+        self.stores.read().await.len()
+    }
+
+    /// Whether no session has a store
+    pub async fn is_empty(&self) -> bool {
+        self.stores.read().await.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_session_store() {
+        let clock = Clock::manual();
+        let store = SessionStore::new().with_clock(clock.clone());
+        store.set("cursor", "page-2").await.unwrap();
+        store
+            .set_with_ttl("token", 42, Duration::from_millis(10))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.get_as::<String>("cursor").await.unwrap().as_deref(),
+            Some("page-2")
+        );
+        assert_eq!(store.get_as::<u32>("token").await.unwrap(), Some(42));
+        assert_eq!(store.len().await, 2);
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(store.get("token").await, None);
+        assert_eq!(store.len().await, 1);
+
+        assert_eq!(store.delete("cursor").await, Some("page-2".into()));
+        assert!(store.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_session_stores_are_scoped() {
+        let stores = SessionStores::new(Duration::from_secs(60));
+        stores.store("a").await.set("key", 1).await.unwrap();
+
+        assert_eq!(stores.store("a").await.get("key").await, Some(1.into()));
+        assert_eq!(stores.store("b").await.get("key").await, None);
+        assert_eq!(stores.len().await, 2);
+
+        stores.remove("a").await;
+        assert_eq!(stores.store("a").await.get("key").await, None);
+    }
+}
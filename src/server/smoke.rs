@@ -0,0 +1,317 @@
+//! Built-in tools for smoke testing a new server
+//!
+//! With [`SmokeToolsConfig::enabled`] set, the server lists four tools next
+//! to those of its state, so a freshly deployed server can be exercised end
+//! to end before it has tools of its own:
+//!
+//! - `mcp.echo` returns its arguments, checking transport and serialization.
+//! - `mcp.sleep` waits `ms` milliseconds (at most `max_sleep`), reporting
+//!   progress in `steps`, checking progress streaming and timeouts.
+//! - `mcp.fail` fails with `message`: as a tool error (`mode` `error`, the
+//!   default), an `isError` result (`result`) or a transient overload
+//!   (`transient`), checking error handling and retry hints.
+//! - `mcp.server_stats` returns the server info, health and how the server
+//!   sees the caller, checking authentication.
+//!
+//! Like other tools they require an authenticated caller and go through
+//! limits, quotas and auditing. They are meant for staging;
+//! [`McpServer::validate`](crate::server::McpServer::validate) warns when
+//! they are enabled.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    protocol::{ServerInfo, Tool, ToolContent, ToolsCallResult},
+    security::{fingerprint::TRANSPORT_KEY, SecurityContext},
+    server::{
+        registry::{InMemoryToolRegistry, McpTool, ToolExecutionContext, ToolRegistry},
+        ServerHealth,
+    },
+};
+
+/// Returns its arguments
+pub const SMOKE_ECHO: &str = "mcp.echo";
+
+/// Waits, reporting progress
+pub const SMOKE_SLEEP: &str = "mcp.sleep";
+
+/// Fails on purpose
+pub const SMOKE_FAIL: &str = "mcp.fail";
+
+/// Returns server info, health and the caller
+pub const SMOKE_SERVER_STATS: &str = "mcp.server_stats";
+
+/// Category of the smoke-test tools
+pub const SMOKE_CATEGORY: &str = "smoke";
+
+/// Smoke-test tool configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmokeToolsConfig {
+    /// Whether the smoke-test tools are listed and callable
+    pub enabled: bool,
+
+    /// Longest wait `mcp.sleep` accepts
+    #[serde(with = "humantime_serde")]
+    pub max_sleep: Duration,
+}
+
+impl Default for SmokeToolsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_sleep: Duration::from_secs(30),
+        }
+    }
+}
+
+impl SmokeToolsConfig {
+    /// List and answer the smoke-test tools
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// The smoke-test tools, except `mcp.server_stats`, which the server answers
+#[derive(Clone)]
+pub struct SmokeTools {
+    registry: InMemoryToolRegistry,
+    config: SmokeToolsConfig,
+    clock: Clock,
+}
+
+impl SmokeTools {
+    /// Tools as configured; none unless enabled
+    pub fn new(config: SmokeToolsConfig) -> Self {
+        let mut registry = InMemoryToolRegistry::new();
+        if config.enabled {
+            for tool in definitions() {
+                registry.register_tool(tool);
+            }
+        }
+        Self {
+            registry,
+            config,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Time `mcp.sleep` on `clock`
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether the tools are listed and callable
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Whether `name` is one of the tools
+    pub fn contains(&self, name: &str) -> bool {
+        self.is_enabled()
+            && self
+                .registry
+                .get_all_tools()
+                .iter()
+                .any(|t| t.tool.name == name)
+    }
+}
+
+#[async_trait]
+impl ToolRegistry for SmokeTools {
+    async fn list_tools(&self, context: &SecurityContext) -> McpResult<Vec<Tool>> {
+        self.registry.list_tools(context).await
+    }
+
+    async fn get_tool(&self, name: &str, context: &SecurityContext) -> McpResult<Option<McpTool>> {
+        self.registry.get_tool(name, context).await
+    }
+
+    async fn execute_tool(
+        &self,
+        name: &str,
+        execution_context: ToolExecutionContext,
+    ) -> McpResult<ToolsCallResult> {
+        let arguments = execution_context.arguments.clone().unwrap_or(Value::Null);
+        match name {
+            SMOKE_ECHO => Ok(text_result(serde_json::to_string(&arguments)?, false)),
+            SMOKE_SLEEP => {
+                let requested = arguments.get("ms").and_then(Value::as_u64).unwrap_or(1000);
+                let total = Duration::from_millis(requested).min(self.config.max_sleep);
+                let steps = arguments
+                    .get("steps")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(1)
+                    .clamp(1, 100) as usize;
+                for step in 1..=steps {
+                    self.clock.sleep(total / steps as u32).await;
+                    execution_context
+                        .report_progress(step, steps, format!("Slept {}/{}", step, steps))
+                        .await;
+                }
+                Ok(text_result(
+                    format!("Slept {} ms", total.as_millis()),
+                    false,
+                ))
+            }
+            SMOKE_FAIL => {
+                let message = arguments
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("Failed on purpose")
+                    .to_string();
+                match arguments.get("mode").and_then(Value::as_str) {
+                    None | Some("error") => Err(McpError::ToolExecution {
+                        tool: name.to_string(),
+                        message,
+                    }),
+                    Some("result") => Ok(text_result(message, true)),
+                    Some("transient") => Err(McpError::Overloaded {
+                        reason: message,
+                        retry_after: 1,
+                    }),
+                    Some(mode) => Err(McpError::Validation {
+                        message: format!(
+                            "Unknown mode '{}', expected error, result or transient",
+                            mode
+                        ),
+                    }),
+                }
+            }
+            _ => Err(McpError::ToolNotFound {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn can_access_tool(&self, name: &str, context: &SecurityContext) -> bool {
+        self.registry.can_access_tool(name, context).await
+    }
+}
+
+/// Result of `mcp.server_stats` for `caller`
+pub fn server_stats(
+    info: &ServerInfo,
+    health: &ServerHealth,
+    tools: usize,
+    caller: &SecurityContext,
+) -> McpResult<ToolsCallResult> {
+    let stats = json!({
+        "server": info,
+        "health": health,
+        "tools": tools,
+        "caller": {
+            "principal": caller.principal(),
+            "system": caller.is_system(),
+            "sessionId": caller.client.session_id,
+            "transport": caller.client.metadata.get(TRANSPORT_KEY),
+        },
+    });
+    Ok(text_result(serde_json::to_string_pretty(&stats)?, false))
+}
+
+fn text_result(text: String, is_error: bool) -> ToolsCallResult {
+    ToolsCallResult {
+        content: vec![ToolContent::Text { text }],
+        is_error,
+        metadata: Default::default(),
+    }
+}
+
+fn definitions() -> Vec<McpTool> {
+    let tool = |name, description, properties: Value| {
+        McpTool::new(
+            name,
+            description,
+            json!({"type": "object", "properties": properties}),
+            SMOKE_CATEGORY,
+        )
+    };
+    vec![
+        tool(
+            SMOKE_ECHO,
+            "Return the arguments unchanged, as JSON text",
+            json!({}),
+        ),
+        tool(
+            SMOKE_SLEEP,
+            "Wait, reporting progress along the way",
+            json!({
+                "ms": {"type": "integer", "minimum": 0, "description": "Milliseconds to wait"},
+                "steps": {"type": "integer", "minimum": 1, "maximum": 100,
+                          "description": "Progress updates to send"},
+            }),
+        ),
+        tool(
+            SMOKE_FAIL,
+            "Fail on purpose, to check error handling",
+            json!({
+                "message": {"type": "string"},
+                "mode": {"enum": ["error", "result", "transient"]},
+            }),
+        ),
+        tool(
+            SMOKE_SERVER_STATS,
+            "Server info, health and how the server sees the caller",
+            json!({}),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, arguments: Value) -> ToolExecutionContext {
+        ToolExecutionContext::new(SecurityContext::system())
+            .with_arguments(arguments)
+            .with_request_id(name)
+    }
+
+    #[tokio::test]
+    async fn test_smoke_tools() {
+        let tools = SmokeTools::new(SmokeToolsConfig::default());
+        assert!(!tools.contains(SMOKE_ECHO));
+        let tools = SmokeTools::new(SmokeToolsConfig::enabled()).with_clock(Clock::manual());
+        assert!(tools.contains(SMOKE_SERVER_STATS));
+        assert_eq!(
+            tools
+                .list_tools(&SecurityContext::system())
+                .await
+                .unwrap()
+                .len(),
+            4
+        );
+
+        let echoed = tools
+            .execute_tool(SMOKE_ECHO, call(SMOKE_ECHO, json!({"a": [1]})))
+            .await
+            .unwrap();
+        assert_eq!(
+            echoed.content,
+            vec![ToolContent::Text {
+                text: r#"{"a":[1]}"#.to_string()
+            }]
+        );
+
+        let failed = tools
+            .execute_tool(SMOKE_FAIL, call(SMOKE_FAIL, json!({"mode": "result"})))
+            .await
+            .unwrap();
+        assert!(failed.is_error);
+        let error = tools
+            .execute_tool(SMOKE_FAIL, call(SMOKE_FAIL, json!({"mode": "transient"})))
+            .await
+            .unwrap_err();
+        assert!(error.is_transient());
+    }
+}
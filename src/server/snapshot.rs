@@ -0,0 +1,378 @@
+//! Capability snapshots and diffs
+//!
+//! [`McpServer::capability_snapshot`](crate::server::McpServer::capability_snapshot)
+//! describes the tools, resources and prompts a server offers. Snapshots
+//! serialize deterministically, so one can be committed or stored as a build
+//! artifact and compared with [`CapabilitySnapshot::diff`] after the next
+//! deploy:
+//!
+//! ```rust,ignore
+//! let before: CapabilitySnapshot = serde_json::from_str(&std::fs::read_to_string("mcp.json")?)?;
+//! let diff = before.diff(&server.capability_snapshot().await?);
+//! assert!(!diff.is_breaking(), "{}", serde_json::to_string_pretty(&diff)?);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    protocol::Tool,
+    server::{prompt::Prompt, resource::ResourceTemplate},
+};
+
+/// Fields whose changes break existing clients
+const CONTRACT_FIELDS: &[&str] = &["inputSchema", "parameters", "mimeType"];
+
+/// What a server offers, keyed by name for a stable order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilitySnapshot {
+    /// Server name
+    pub server: String,
+
+    /// Server version
+    pub version: String,
+
+    /// Tools by name
+    pub tools: BTreeMap<String, ToolSnapshot>,
+
+    /// Resources by URI template
+    pub resources: BTreeMap<String, ResourceSnapshot>,
+
+    /// Prompts by name
+    pub prompts: BTreeMap<String, PromptSnapshot>,
+}
+
+/// Contract of a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolSnapshot {
+    /// Tool description
+    pub description: String,
+
+    /// JSON schema of the arguments
+    pub input_schema: serde_json::Value,
+
+    /// Version from the tool's `version` metadata, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl From<&Tool> for ToolSnapshot {
+    fn from(tool: &Tool) -> Self {
+        Self {
+            description: tool.description.clone(),
+            input_schema: tool.input_schema.clone(),
+            version: tool
+                .metadata
+                .get("version")
+                .and_then(|version| version.as_str())
+                .map(String::from),
+        }
+    }
+}
+
+/// Contract of a resource template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSnapshot {
+    /// Human-readable name
+    pub name: String,
+
+    /// Resource description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// MIME type of the resources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+impl From<&ResourceTemplate> for ResourceSnapshot {
+    fn from(template: &ResourceTemplate) -> Self {
+        Self {
+            name: template.name.clone(),
+            description: template.description.clone(),
+            mime_type: template.mime_type.clone(),
+        }
+    }
+}
+
+/// Contract of a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptSnapshot {
+    /// Prompt description
+    pub description: String,
+
+    /// Prompt version
+    pub version: String,
+
+    /// Parameters in declaration order
+    pub parameters: Vec<PromptParameterSnapshot>,
+}
+
+/// Contract of a prompt parameter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptParameterSnapshot {
+    /// Parameter name
+    pub name: String,
+
+    /// Whether the parameter is required
+    pub required: bool,
+
+    /// JSON schema of the parameter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+}
+
+impl From<&Prompt> for PromptSnapshot {
+    fn from(prompt: &Prompt) -> Self {
+        Self {
+            description: prompt.description.clone(),
+            version: prompt.version.clone(),
+            parameters: prompt
+                .parameters
+                .iter()
+                .map(|param| PromptParameterSnapshot {
+                    name: param.name.clone(),
+                    required: param.required,
+                    schema: param.schema.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Kind of capability that changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilityKind {
+    /// A tool
+    Tool,
+    /// A resource template
+    Resource,
+    /// A prompt
+    Prompt,
+}
+
+/// How a capability changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeType {
+    /// Only in the newer snapshot
+    Added,
+    /// Only in the older snapshot
+    Removed,
+    /// In both, with the listed fields changed
+    Modified { fields: Vec<String> },
+}
+
+/// A single difference between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityChange {
+    /// Kind of capability
+    pub kind: CapabilityKind,
+
+    /// Tool or prompt name, or resource URI template
+    pub name: String,
+
+    /// What changed
+    #[serde(flatten)]
+    pub change: ChangeType,
+}
+
+impl CapabilityChange {
+    /// Whether clients relying on the older contract may break
+    ///
+    /// Removals and changes to schemas, prompt parameters or MIME types are
+    /// breaking; additions and description or version changes are not.
+    pub fn is_breaking(&self) -> bool {
+        match &self.change {
+            ChangeType::Added => false,
+            ChangeType::Removed => true,
+            ChangeType::Modified { fields } => fields
+                .iter()
+                .any(|field| CONTRACT_FIELDS.contains(&field.as_str())),
+        }
+    }
+}
+
+/// Differences between two snapshots, ordered by kind and name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityDiff {
+    /// All changes
+    pub changes: Vec<CapabilityChange>,
+}
+
+impl CapabilityDiff {
+    /// Whether the snapshots describe the same capabilities
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether any change may break existing clients
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(CapabilityChange::is_breaking)
+    }
+
+    /// Changes that may break existing clients
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &CapabilityChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+impl CapabilitySnapshot {
+    /// Changes from this snapshot to `newer`
+    ///
+    /// Server name and version are not compared.
+    pub fn diff(&self, newer: &CapabilitySnapshot) -> CapabilityDiff {
+        let mut changes = Vec::new();
+        diff_maps(
+            CapabilityKind::Tool,
+            &self.tools,
+            &newer.tools,
+            &mut changes,
+        );
+        diff_maps(
+            CapabilityKind::Resource,
+            &self.resources,
+            &newer.resources,
+            &mut changes,
+        );
+        diff_maps(
+            CapabilityKind::Prompt,
+            &self.prompts,
+            &newer.prompts,
+            &mut changes,
+        );
+        CapabilityDiff { changes }
+    }
+}
+
+fn diff_maps<T: Serialize + PartialEq>(
+    kind: CapabilityKind,
+    older: &BTreeMap<String, T>,
+    newer: &BTreeMap<String, T>,
+    changes: &mut Vec<CapabilityChange>,
+) {
+    let mut names: Vec<&String> = older.keys().chain(newer.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let change = match (older.get(name), newer.get(name)) {
+            (Some(_), None) => ChangeType::Removed,
+            (None, Some(_)) => ChangeType::Added,
+            (Some(before), Some(after)) if before != after => ChangeType::Modified {
+                fields: changed_fields(before, after),
+            },
+            _ => continue,
+        };
+        changes.push(CapabilityChange {
+            kind,
+            name: name.clone(),
+            change,
+        });
+    }
+}
+
+/// Top-level fields that differ between two serialized capabilities
+fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+
+    let mut fields: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|field| before.get(*field) != after.get(*field))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(schema: serde_json::Value, description: &str) -> ToolSnapshot {
+        ToolSnapshot {
+            description: description.to_string(),
+            input_schema: schema,
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_modified() {
+        let mut before = CapabilitySnapshot::default();
+        before
+            .tools
+            .insert("echo".into(), tool(json!({"type": "object"}), "Echo"));
+        before
+            .tools
+            .insert("legacy".into(), tool(json!({"type": "object"}), "Old"));
+        before
+            .tools
+            .insert("search".into(), tool(json!({"type": "object"}), "Search"));
+
+        let mut after = before.clone();
+        after.tools.remove("legacy");
+        after
+            .tools
+            .insert("fetch".into(), tool(json!({"type": "object"}), "Fetch"));
+        after.tools.get_mut("echo").unwrap().description = "Echo back".into();
+        after.tools.get_mut("search").unwrap().input_schema =
+            json!({"type": "object", "required": ["query"]});
+
+        let diff = before.diff(&after);
+        let summary: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.is_breaking()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("echo", false),
+                ("fetch", false),
+                ("legacy", true),
+                ("search", true)
+            ]
+        );
+        assert_eq!(
+            diff.changes[3].change,
+            ChangeType::Modified {
+                fields: vec!["inputSchema".to_string()]
+            }
+        );
+        assert!(diff.is_breaking());
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_diff_serializes_flat_changes() {
+        let mut after = CapabilitySnapshot::default();
+        after.prompts.insert(
+            "review".into(),
+            PromptSnapshot {
+                description: "Review code".into(),
+                version: "1.0".into(),
+                parameters: Vec::new(),
+            },
+        );
+
+        let diff = CapabilitySnapshot::default().diff(&after);
+        assert_eq!(
+            serde_json::to_value(&diff).unwrap(),
+            json!({"changes": [{"kind": "prompt", "name": "review", "change": "added"}]})
+        );
+    }
+}
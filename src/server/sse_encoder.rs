@@ -0,0 +1,180 @@
+//! Server-Sent Events encoding into a reused buffer
+//!
+//! Building an [`axum::response::sse::Event`] per notification allocates the
+//! JSON string and then the event buffer it is copied into. On streams that
+//! carry many notifications, such as StreamableHTTP sessions during a
+//! fan-out, that is two allocations per event per subscriber.
+//!
+//! An [`SseEncoder`] instead serializes each event straight into one
+//! [`BytesMut`] owned by the stream and splits the finished event off as
+//! [`Bytes`]. Once the transport has written and dropped those bytes, the
+//! next event reuses the same allocation, so a stream in steady state
+//! encodes without allocating. [`sse_response`] turns a stream of encoded
+//! events into a response, with keep-alive comments.
+//!
+//! `benches/sse_encoding.rs` compares both paths under fan-out.
+
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use std::{convert::Infallible, time::Duration};
+
+/// Initial capacity of an encoder's buffer
+pub const DEFAULT_BUFFER_CAPACITY: usize = 4096;
+
+/// Free space ensured before each event; the buffer grows further as needed
+const MIN_FREE: usize = 256;
+
+/// Encodes SSE events into a buffer reused across events
+#[derive(Debug)]
+pub struct SseEncoder {
+    buffer: BytesMut,
+}
+
+impl Default for SseEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SseEncoder {
+    /// Create an encoder with the default buffer capacity
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_BUFFER_CAPACITY)
+    }
+
+    /// Create an encoder whose buffer starts at `capacity` bytes
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buffer: BytesMut::with_capacity(capacity),
+        }
+    }
+
+    /// Encode an event whose data is `data` serialized as JSON
+    pub fn json_event(
+        &mut self,
+        id: Option<&str>,
+        event: Option<&str>,
+        data: &impl Serialize,
+    ) -> Bytes {
+        self.reclaim();
+        self.fields(id, event);
+        self.buffer.put_slice(b"data: ");
+        // Compact JSON escapes newlines, so it always fits one data line
+        if serde_json::to_writer((&mut self.buffer).writer(), data).is_err() {
+            self.buffer.clear();
+            return Bytes::new();
+        }
+        self.buffer.put_slice(b"\n\n");
+        self.buffer.split().freeze()
+    }
+
+    /// Encode an event carrying `data` as text
+    pub fn event(&mut self, id: Option<&str>, event: Option<&str>, data: &str) -> Bytes {
+        self.reclaim();
+        self.fields(id, event);
+        for line in data.split('\n') {
+            self.buffer.put_slice(b"data: ");
+            self.buffer.put_slice(line.as_bytes());
+            self.buffer.put_u8(b'\n');
+        }
+        self.buffer.put_u8(b'\n');
+        self.buffer.split().freeze()
+    }
+
+    /// Encode a comment line, as used for keep-alives
+    pub fn comment(&mut self, text: &str) -> Bytes {
+        self.reclaim();
+        self.buffer.put_u8(b':');
+        self.buffer.put_slice(text.as_bytes());
+        self.buffer.put_slice(b"\n\n");
+        self.buffer.split().freeze()
+    }
+
+    /// Make room for the next event
+    ///
+    /// Events fill the buffer front to back; when it runs out of room and
+    /// the earlier events have been dropped, it starts over at the front.
+    fn reclaim(&mut self) {
+        self.buffer.reserve(MIN_FREE);
+    }
+
+    fn fields(&mut self, id: Option<&str>, event: Option<&str>) {
+        if let Some(id) = id {
+            self.buffer.put_slice(b"id: ");
+            self.buffer.put_slice(id.as_bytes());
+            self.buffer.put_u8(b'\n');
+        }
+        if let Some(event) = event {
+            self.buffer.put_slice(b"event: ");
+            self.buffer.put_slice(event.as_bytes());
+            self.buffer.put_u8(b'\n');
+        }
+    }
+}
+
+/// SSE response streaming encoded `events`, with a keep-alive comment after
+/// every `keep_alive` without events
+pub fn sse_response<S>(events: S, keep_alive: Duration) -> Response
+where
+    S: Stream<Item = Bytes> + Send + 'static,
+{
+    let body = async_stream::stream! {
+        let mut events = std::pin::pin!(events);
+        let mut encoder = SseEncoder::with_capacity(64);
+        loop {
+            match tokio::time::timeout(keep_alive, events.next()).await {
+                Ok(Some(event)) => yield Ok::<_, Infallible>(event),
+                Ok(None) => break,
+                Err(_) => yield Ok(encoder.comment("keep-alive")),
+            }
+        }
+    };
+    (
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoder_reuses_its_buffer() {
+        let mut encoder = SseEncoder::new();
+        let first = encoder.json_event(
+            Some("7"),
+            Some("message"),
+            &serde_json::json!({"text": "a\nb"}),
+        );
+        assert_eq!(
+            &first[..],
+            b"id: 7\nevent: message\ndata: {\"text\":\"a\\nb\"}\n\n"
+        );
+        assert_eq!(
+            &encoder.event(None, None, "a\nb")[..],
+            b"data: a\ndata: b\n\n"
+        );
+
+        assert_eq!(&encoder.comment("keep-alive")[..], b":keep-alive\n\n");
+
+        // Once written events are dropped, later ones land in the same memory
+        let start = first.as_ptr() as usize;
+        drop(first);
+        for i in 0..1000 {
+            let event = encoder.json_event(Some(&i.to_string()), None, &i);
+            let address = event.as_ptr() as usize;
+            assert!((start..start + DEFAULT_BUFFER_CAPACITY).contains(&address));
+        }
+    }
+}
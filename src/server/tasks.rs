@@ -0,0 +1,453 @@
+//! Background tasks for long-running tool calls
+//!
+//! Clients that would rather not hold a connection open while a slow tool
+//! runs can start it as a task with `tasks/create`, which takes the same
+//! params as `tools/call` and returns at once with a task ID. The call runs
+//! in the background, through the same quotas, approvals and audit trail as
+//! a direct call, and the client polls it:
+//!
+//! - `tasks/status` reports whether the task is working, completed, failed
+//!   or cancelled,
+//! - `tasks/result` returns the `tools/call` result of a completed task, or
+//!   the error of a failed one,
+//! - `tasks/cancel` stops a working task,
+//! - `tasks/list` lists the caller's tasks.
+//!
+//! Tasks belong to the principal that created them (see
+//! [`SecurityContext::principal`]); other principals cannot see them. The
+//! methods are experimental: they are only served when [`TaskConfig::enabled`]
+//! is set, in which case the server advertises them under the
+//! [`TASKS_CAPABILITY`] key of its experimental capabilities.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::task::AbortHandle;
+use tracing::debug;
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    security::SecurityContext,
+};
+
+/// Key of the tasks capability in the server's experimental capabilities
+pub const TASKS_CAPABILITY: &str = "tasks";
+
+/// Start a tool call as a task
+pub const TASKS_CREATE: &str = "tasks/create";
+
+/// Report the state of a task
+pub const TASKS_STATUS: &str = "tasks/status";
+
+/// Fetch the result of a finished task
+pub const TASKS_RESULT: &str = "tasks/result";
+
+/// Stop a working task
+pub const TASKS_CANCEL: &str = "tasks/cancel";
+
+/// List the caller's tasks
+pub const TASKS_LIST: &str = "tasks/list";
+
+/// The `tasks/*` methods
+pub const TASK_METHODS: &[&str] = &[
+    TASKS_CREATE,
+    TASKS_STATUS,
+    TASKS_RESULT,
+    TASKS_CANCEL,
+    TASKS_LIST,
+];
+
+/// Background task configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskConfig {
+    /// Whether the `tasks/*` methods are served
+    pub enabled: bool,
+
+    /// Most tasks a principal may have working at once
+    pub max_working: usize,
+
+    /// How long finished tasks and their results are kept
+    #[serde(with = "humantime_serde")]
+    pub retention: Duration,
+}
+
+impl Default for TaskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_working: 16,
+            retention: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl TaskConfig {
+    /// Serve the `tasks/*` methods
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Allow each principal `max_working` tasks at once
+    pub fn with_max_working(mut self, max_working: usize) -> Self {
+        self.max_working = max_working;
+        self
+    }
+
+    /// Keep finished tasks for `retention`
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = retention;
+        self
+    }
+}
+
+/// State of a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// The tool is running
+    Working,
+    /// The tool returned a result
+    Completed,
+    /// The call failed; `tasks/result` returns the error
+    Failed,
+    /// The task was cancelled before it finished
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Whether the task has stopped
+    pub fn is_finished(self) -> bool {
+        self != TaskStatus::Working
+    }
+}
+
+/// A task, as reported to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    /// Task identifier
+    pub task_id: String,
+
+    /// Tool being called
+    pub tool: String,
+
+    /// Current state
+    pub status: TaskStatus,
+
+    /// When the task was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the state last changed
+    pub updated_at: DateTime<Utc>,
+
+    /// Error message of a failed task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Params of `tasks/status`, `tasks/result` and `tasks/cancel`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskParams {
+    /// Task identifier
+    pub task_id: String,
+}
+
+struct Task {
+    info: TaskInfo,
+    owner: String,
+    finished_at: Option<Instant>,
+    result: Option<serde_json::Value>,
+    handle: Option<AbortHandle>,
+}
+
+/// Tasks of a server
+#[derive(Clone)]
+pub struct TaskManager {
+    tasks: Arc<Mutex<HashMap<String, Task>>>,
+    config: TaskConfig,
+    clock: Clock,
+}
+
+impl std::fmt::Debug for TaskManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskManager")
+            .field("config", &self.config)
+            .field("tasks", &self.tasks.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl TaskManager {
+    /// Create a task manager
+    pub fn new(config: TaskConfig) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+            config,
+            clock: Clock::default(),
+        }
+    }
+
+    /// Timestamp tasks and expire finished ones on `clock`
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Whether the `tasks/*` methods are served
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Value advertised under [`TASKS_CAPABILITY`]
+    pub fn capability(&self) -> serde_json::Value {
+        serde_json::json!({
+            "methods": TASK_METHODS,
+            "maxWorking": self.config.max_working,
+            "retentionSeconds": self.config.retention.as_secs(),
+        })
+    }
+
+    /// Run `call` as a task of the caller
+    ///
+    /// `call` resolves to the `tools/call` result. Fails if the caller
+    /// already has [`TaskConfig::max_working`] tasks working.
+    pub fn spawn<F>(&self, context: &SecurityContext, tool: &str, call: F) -> McpResult<TaskInfo>
+    where
+        F: Future<Output = McpResult<serde_json::Value>> + Send + 'static,
+    {
+        let owner = context.principal();
+        let now = self.clock.system_time().into();
+        let info = TaskInfo {
+            task_id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.to_string(),
+            status: TaskStatus::Working,
+            created_at: now,
+            updated_at: now,
+            error: None,
+        };
+        {
+            let mut tasks = self.tasks.lock().unwrap();
+            self.expire(&mut tasks);
+            let working = tasks
+                .values()
+                .filter(|task| task.owner == owner && !task.info.status.is_finished())
+                .count();
+            if working >= self.config.max_working {
+                return Err(McpError::RateLimit {
+                    message: format!(
+                        "{} already has {} tasks working",
+                        owner, self.config.max_working
+                    ),
+                });
+            }
+            tasks.insert(
+                info.task_id.clone(),
+                Task {
+                    info: info.clone(),
+                    owner,
+                    finished_at: None,
+                    result: None,
+                    handle: None,
+                },
+            );
+        }
+
+        let manager = self.clone();
+        let task_id = info.task_id.clone();
+        let handle = tokio::spawn(async move {
+            let outcome = call.await;
+            manager.finish(&task_id, outcome);
+        })
+        .abort_handle();
+        if let Some(task) = self.tasks.lock().unwrap().get_mut(&info.task_id) {
+            if !task.info.status.is_finished() {
+                task.handle = Some(handle);
+            }
+        }
+        debug!("Started task {} calling {}", info.task_id, tool);
+        Ok(info)
+    }
+
+    /// Current state of one of the caller's tasks
+    pub fn status(&self, context: &SecurityContext, task_id: &str) -> McpResult<TaskInfo> {
+        self.with_task(context, task_id, |task| Ok(task.info.clone()))
+    }
+
+    /// Result of one of the caller's tasks
+    ///
+    /// Fails while the task is working, and for failed or cancelled tasks.
+    pub fn result(&self, context: &SecurityContext, task_id: &str) -> McpResult<serde_json::Value> {
+        self.with_task(context, task_id, |task| match task.info.status {
+            TaskStatus::Completed => Ok(task.result.clone().unwrap_or_default()),
+            TaskStatus::Working => Err(McpError::Validation {
+                message: format!("Task {} is still working", task_id),
+            }),
+            TaskStatus::Failed => Err(McpError::ToolExecution {
+                tool: task.info.tool.clone(),
+                message: task.info.error.clone().unwrap_or_default(),
+            }),
+            TaskStatus::Cancelled => Err(McpError::Validation {
+                message: format!("Task {} was cancelled", task_id),
+            }),
+        })
+    }
+
+    /// Cancel one of the caller's tasks
+    ///
+    /// Finished tasks are left as they are.
+    pub fn cancel(&self, context: &SecurityContext, task_id: &str) -> McpResult<TaskInfo> {
+        let now = self.clock.system_time().into();
+        let finished_at = self.clock.now();
+        self.with_task(context, task_id, |task| {
+            if !task.info.status.is_finished() {
+                if let Some(handle) = task.handle.take() {
+                    handle.abort();
+                }
+                task.info.status = TaskStatus::Cancelled;
+                task.info.updated_at = now;
+                task.finished_at = Some(finished_at);
+                debug!("Cancelled task {}", task_id);
+            }
+            Ok(task.info.clone())
+        })
+    }
+
+    /// The caller's tasks, newest first
+    pub fn list(&self, context: &SecurityContext) -> Vec<TaskInfo> {
+        let owner = context.principal();
+        let mut tasks = self.tasks.lock().unwrap();
+        self.expire(&mut tasks);
+        let mut listed: Vec<_> = tasks
+            .values()
+            .filter(|task| task.owner == owner)
+            .map(|task| task.info.clone())
+            .collect();
+        listed.sort_by_key(|task| std::cmp::Reverse(task.created_at));
+        listed
+    }
+
+    fn with_task<T>(
+        &self,
+        context: &SecurityContext,
+        task_id: &str,
+        f: impl FnOnce(&mut Task) -> McpResult<T>,
+    ) -> McpResult<T> {
+        let owner = context.principal();
+        let mut tasks = self.tasks.lock().unwrap();
+        self.expire(&mut tasks);
+        match tasks.get_mut(task_id) {
+            Some(task) if task.owner == owner => f(task),
+            _ => Err(McpError::Validation {
+                message: format!("Unknown task {}", task_id),
+            }),
+        }
+    }
+
+    fn finish(&self, task_id: &str, outcome: McpResult<serde_json::Value>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some(task) = tasks.get_mut(task_id) else {
+            return;
+        };
+        if task.info.status.is_finished() {
+            return;
+        }
+        match outcome {
+            Ok(result) => {
+                task.info.status = TaskStatus::Completed;
+                task.result = Some(result);
+            }
+            Err(error) => {
+                task.info.status = TaskStatus::Failed;
+                task.info.error = Some(error.client_message());
+            }
+        }
+        task.info.updated_at = self.clock.system_time().into();
+        task.finished_at = Some(self.clock.now());
+        task.handle = None;
+        debug!("Task {} {:?}", task_id, task.info.status);
+    }
+
+    /// Drop finished tasks older than the retention period
+    fn expire(&self, tasks: &mut HashMap<String, Task>) {
+        let now = self.clock.now();
+        tasks.retain(|_, task| {
+            task.finished_at
+                .is_none_or(|finished_at| now.duration_since(finished_at) < self.config.retention)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::ClientContext;
+
+    fn caller(client_id: &str) -> SecurityContext {
+        SecurityContext::authenticated(
+            ClientContext {
+                client_id: Some(client_id.to_string()),
+                ..Default::default()
+            },
+            Vec::new(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_task_lifecycle() {
+        let clock = Clock::manual();
+        let tasks =
+            TaskManager::new(TaskConfig::enabled().with_max_working(1)).with_clock(clock.clone());
+        let alice = caller("alice");
+
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let task = tasks
+            .spawn(&alice, "slow", async move {
+                let _ = released.await;
+                Ok(serde_json::json!({"content": []}))
+            })
+            .unwrap();
+        assert_eq!(task.status, TaskStatus::Working);
+        assert!(tasks.result(&alice, &task.task_id).is_err());
+        assert!(tasks.status(&caller("bob"), &task.task_id).is_err());
+        assert!(tasks
+            .spawn(&alice, "slow", async { Ok(serde_json::Value::Null) })
+            .is_err());
+
+        release.send(()).unwrap();
+        while !tasks
+            .status(&alice, &task.task_id)
+            .unwrap()
+            .status
+            .is_finished()
+        {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            tasks.result(&alice, &task.task_id).unwrap(),
+            serde_json::json!({"content": []})
+        );
+
+        let stuck = tasks
+            .spawn(&alice, "stuck", std::future::pending())
+            .unwrap();
+        assert_eq!(
+            tasks.cancel(&alice, &stuck.task_id).unwrap().status,
+            TaskStatus::Cancelled
+        );
+        assert_eq!(tasks.list(&alice).len(), 2);
+
+        clock.advance(Duration::from_secs(3600));
+        assert!(tasks.list(&alice).is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+//! Token estimation and per-request token budgets
+//!
+//! A [`TokenEstimator`] approximates how many model tokens a text takes.
+//! [`TokenBudgeter`] uses it on `prompts/get` and `tools/call` results:
+//!
+//! - With [`TokenConfig::annotate`], results carry their estimated size as
+//!   `_meta.tokenCount`.
+//! - A request may set `_meta.tokenBudget`, or the server a
+//!   [`TokenConfig::default_budget`]. Results over budget have the text of
+//!   their embedded resources cut according to the [`TruncationStrategy`],
+//!   starting from the last resource, and list the cut resources in
+//!   `_meta.truncatedResources`.
+//!
+//! Before budgets are applied, resources over the configured size are reduced,
+//! see [`reduction`](crate::server::reduction).
+//!
+//! The default estimator assumes four characters per token. With the
+//! `tiktoken` feature, [`default_estimator`] counts `o200k_base` BPE tokens
+//! instead.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, sync::Arc};
+
+use tracing::warn;
+
+use crate::{
+    protocol::{ToolContent, ToolsCallResult},
+    server::{
+        prompt::{GetPromptResult, PromptContent},
+        reduction::{cut, ContentReducer, HeadTailReducer, REDUCED_RESOURCES_META},
+    },
+};
+
+/// `_meta` key of a request's token budget
+pub const TOKEN_BUDGET_META: &str = "tokenBudget";
+
+/// `_meta` key of a result's estimated token count
+pub const TOKEN_COUNT_META: &str = "tokenCount";
+
+/// `_meta` key listing the URIs of resources cut to fit the budget
+pub const TRUNCATED_RESOURCES_META: &str = "truncatedResources";
+
+/// Estimates the number of model tokens in a text
+pub trait TokenEstimator: Send + Sync {
+    /// Estimated token count of `text`
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// Estimator dividing the character count by a fixed ratio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharRatioEstimator {
+    chars_per_token: f64,
+}
+
+impl CharRatioEstimator {
+    /// Assume `chars_per_token` characters per token
+    pub fn new(chars_per_token: f64) -> Self {
+        Self {
+            chars_per_token: chars_per_token.max(f64::EPSILON),
+        }
+    }
+}
+
+impl Default for CharRatioEstimator {
+    fn default() -> Self {
+        Self::new(4.0)
+    }
+}
+
+impl TokenEstimator for CharRatioEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        (text.chars().count() as f64 / self.chars_per_token).ceil() as usize
+    }
+}
+
+/// Estimator counting tokens with an OpenAI BPE vocabulary
+#[cfg(feature = "tiktoken")]
+#[derive(Clone, Copy)]
+pub struct TiktokenEstimator {
+    bpe: &'static tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenEstimator {
+    /// The `cl100k_base` vocabulary
+    pub fn cl100k() -> Self {
+        Self {
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+
+    /// The `o200k_base` vocabulary
+    pub fn o200k() -> Self {
+        Self {
+            bpe: tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenEstimator for TiktokenEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+/// Estimator used unless the server is given another
+pub fn default_estimator() -> Arc<dyn TokenEstimator> {
+    #[cfg(feature = "tiktoken")]
+    {
+        Arc::new(TiktokenEstimator::o200k())
+    }
+    #[cfg(not(feature = "tiktoken"))]
+    {
+        Arc::new(CharRatioEstimator::default())
+    }
+}
+
+/// How embedded resources are cut to fit a token budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    /// Keep the beginning of the text
+    #[default]
+    Head,
+    /// Keep the end of the text
+    Tail,
+    /// Drop the text
+    Omit,
+}
+
+/// Token estimation and budget configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenConfig {
+    /// Add `_meta.tokenCount` to prompt and tool results
+    pub annotate: bool,
+
+    /// Budget for requests that do not set `_meta.tokenBudget`
+    pub default_budget: Option<usize>,
+
+    /// How embedded resources over budget are cut
+    pub truncation: TruncationStrategy,
+
+    /// Reduce resources estimated at more tokens than this
+    pub max_resource_tokens: Option<usize>,
+
+    /// Reduce resources with more bytes of text than this
+    pub max_resource_bytes: Option<usize>,
+}
+
+impl TokenConfig {
+    /// Add `_meta.tokenCount` to prompt and tool results
+    pub fn annotate(mut self) -> Self {
+        self.annotate = true;
+        self
+    }
+
+    /// Limit results to `tokens` unless the request sets its own budget
+    pub fn with_default_budget(mut self, tokens: usize) -> Self {
+        self.default_budget = Some(tokens);
+        self
+    }
+
+    /// Cut embedded resources over budget with `strategy`
+    pub fn with_truncation(mut self, strategy: TruncationStrategy) -> Self {
+        self.truncation = strategy;
+        self
+    }
+
+    /// Reduce resources estimated at more than `tokens`
+    pub fn with_max_resource_tokens(mut self, tokens: usize) -> Self {
+        self.max_resource_tokens = Some(tokens);
+        self
+    }
+
+    /// Reduce resources with more than `bytes` of text
+    pub fn with_max_resource_bytes(mut self, bytes: usize) -> Self {
+        self.max_resource_bytes = Some(bytes);
+        self
+    }
+}
+
+/// Applies token annotations, resource reduction and budgets to results
+#[derive(Clone)]
+pub struct TokenBudgeter {
+    estimator: Arc<dyn TokenEstimator>,
+    reducer: Arc<dyn ContentReducer>,
+    config: TokenConfig,
+}
+
+impl std::fmt::Debug for TokenBudgeter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenBudgeter")
+            .field("reducer", &self.reducer.name())
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenBudgeter {
+    /// Create a budgeter estimating with `estimator`
+    pub fn new(estimator: Arc<dyn TokenEstimator>, config: TokenConfig) -> Self {
+        Self {
+            estimator,
+            reducer: Arc::new(HeadTailReducer::default()),
+            config,
+        }
+    }
+
+    /// Estimate with `estimator` instead
+    pub fn with_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.estimator = estimator;
+        self
+    }
+
+    /// Reduce oversized resources with `reducer` instead
+    pub fn with_reducer(mut self, reducer: Arc<dyn ContentReducer>) -> Self {
+        self.reducer = reducer;
+        self
+    }
+
+    /// Get the estimator
+    pub fn estimator(&self) -> &Arc<dyn TokenEstimator> {
+        &self.estimator
+    }
+
+    /// Budget of a request with `params`
+    pub fn budget(&self, params: Option<&Value>) -> Option<usize> {
+        params
+            .and_then(|params| params.get("_meta")?.get(TOKEN_BUDGET_META)?.as_u64())
+            .map(|budget| budget as usize)
+            .or(self.config.default_budget)
+    }
+
+    /// Whether results of a request with `budget` may be cut or reduced
+    pub fn limits(&self, budget: Option<usize>) -> bool {
+        budget.is_some()
+            || self.config.max_resource_tokens.is_some()
+            || self.config.max_resource_bytes.is_some()
+    }
+
+    /// Reduce the text of the resource at `uri` if it exceeds the thresholds
+    ///
+    /// Returns the record of the reduction for `_meta.reducedResources`.
+    /// Reducer failures are logged and leave the text as it is.
+    pub async fn reduce_resource(&self, uri: &str, text: &mut String) -> Option<Value> {
+        let tokens = self.estimator.estimate(text);
+        let by_tokens = self.config.max_resource_tokens.filter(|max| tokens > *max);
+        let by_bytes = self
+            .config
+            .max_resource_bytes
+            .filter(|max| text.len() > *max)
+            .map(|max| tokens * max / text.len());
+        let limit = match (by_tokens, by_bytes) {
+            (Some(a), Some(b)) => a.min(b),
+            (limit, None) | (None, limit) => limit?,
+        };
+
+        match self
+            .reducer
+            .reduce(uri, text, limit, self.estimator.as_ref())
+            .await
+        {
+            Ok(reduced) => {
+                *text = reduced;
+                Some(serde_json::json!({
+                    "uri": uri,
+                    "reducer": self.reducer.name(),
+                    "originalTokens": tokens,
+                    "tokens": self.estimator.estimate(text),
+                }))
+            }
+            Err(e) => {
+                warn!("Failed to reduce resource {}: {}", uri, e);
+                None
+            }
+        }
+    }
+
+    /// Annotate a rendered prompt and fit it into `budget`
+    pub async fn apply_to_prompt(&self, result: &mut GetPromptResult, budget: Option<usize>) {
+        let mut fixed = self.estimator.estimate(&result.description);
+        let mut resources = Vec::new();
+        for message in &mut result.messages {
+            match &mut message.content {
+                PromptContent::Text { text } => fixed += self.estimator.estimate(text),
+                PromptContent::EmbeddedResource { resource, text } => {
+                    resources.push((resource.uri.clone(), text))
+                }
+            }
+        }
+        if let Some(meta) = self.fit(fixed, resources, budget).await {
+            result.meta.extend(meta);
+        }
+    }
+
+    /// Annotate a tool result and fit it into `budget`
+    pub async fn apply_to_tool_result(&self, result: &mut ToolsCallResult, budget: Option<usize>) {
+        let mut fixed = 0;
+        let mut resources = Vec::new();
+        for content in &mut result.content {
+            match content {
+                ToolContent::Text { text } => fixed += self.estimator.estimate(text),
+                ToolContent::Image { .. } => {}
+                ToolContent::Resource { resource, text, .. } => {
+                    resources.push((resource.uri.clone(), text))
+                }
+            }
+        }
+        if let Some(meta) = self.fit(fixed, resources, budget).await {
+            let entry = result
+                .metadata
+                .entry("_meta".to_string())
+                .or_insert_with(|| Value::Object(Default::default()));
+            if let Value::Object(entry) = entry {
+                entry.extend(meta);
+            }
+        }
+    }
+
+    /// Reduce oversized `resources`, then cut them until everything fits
+    /// into `budget`
+    ///
+    /// Returns the `_meta` entries to add, if any.
+    async fn fit(
+        &self,
+        fixed: usize,
+        mut resources: Vec<(String, &mut Option<String>)>,
+        budget: Option<usize>,
+    ) -> Option<HashMap<String, Value>> {
+        let mut reduced = Vec::new();
+        for (uri, text) in resources.iter_mut() {
+            if let Some(text) = text.as_mut() {
+                reduced.extend(self.reduce_resource(uri, text).await);
+            }
+        }
+
+        let estimate = |text: &Option<String>| {
+            text.as_deref()
+                .map_or(0, |text| self.estimator.estimate(text))
+        };
+        let mut total = fixed
+            + resources
+                .iter()
+                .map(|(_, text)| estimate(text))
+                .sum::<usize>();
+
+        let mut truncated = Vec::new();
+        if let Some(budget) = budget {
+            for (uri, text) in resources.iter_mut().rev() {
+                if total <= budget {
+                    break;
+                }
+                let size = estimate(text);
+                if size == 0 {
+                    continue;
+                }
+                let allowance = size.saturating_sub(total - budget);
+                **text = text
+                    .as_deref()
+                    .and_then(|full| self.truncate(full, allowance));
+                total = total - size + estimate(text);
+                truncated.push(Value::String(uri.clone()));
+            }
+        }
+
+        if !self.config.annotate && truncated.is_empty() && reduced.is_empty() {
+            return None;
+        }
+        let mut meta = HashMap::from([(TOKEN_COUNT_META.to_string(), Value::from(total))]);
+        if let Some(budget) = budget {
+            meta.insert(TOKEN_BUDGET_META.to_string(), Value::from(budget));
+        }
+        if !truncated.is_empty() {
+            truncated.reverse();
+            meta.insert(
+                TRUNCATED_RESOURCES_META.to_string(),
+                Value::Array(truncated),
+            );
+        }
+        if !reduced.is_empty() {
+            meta.insert(REDUCED_RESOURCES_META.to_string(), Value::Array(reduced));
+        }
+        Some(meta)
+    }
+
+    /// The longest part of `text` the strategy keeps within `max_tokens`
+    fn truncate(&self, text: &str, max_tokens: usize) -> Option<String> {
+        if max_tokens == 0 {
+            return None;
+        }
+        let part = match self.config.truncation {
+            TruncationStrategy::Head => cut(self.estimator.as_ref(), text, max_tokens, false),
+            TruncationStrategy::Tail => cut(self.estimator.as_ref(), text, max_tokens, true),
+            TruncationStrategy::Omit => return None,
+        };
+        Some(part.to_string()).filter(|part| !part.is_empty())
+    }
+}
+
+impl Default for TokenBudgeter {
+    fn default() -> Self {
+        Self::new(default_estimator(), TokenConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        protocol::messages::ResourceReference,
+        server::prompt::{EmbeddedResource, MessageRole, PromptMessage},
+    };
+
+    fn budgeter(config: TokenConfig) -> TokenBudgeter {
+        TokenBudgeter::new(Arc::new(CharRatioEstimator::new(1.0)), config)
+    }
+
+    fn resource(uri: &str, text: &str) -> ToolContent {
+        ToolContent::Resource {
+            resource: ResourceReference {
+                uri: uri.to_string(),
+            },
+            text: Some(text.to_string()),
+            blob: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_are_cut_to_the_budget() {
+        let mut result = ToolsCallResult {
+            content: vec![
+                ToolContent::Text {
+                    text: "summary".to_string(),
+                },
+                resource("file:///a", "aaaaaaaaaa"),
+                resource("file:///b", "0123456789"),
+            ],
+            is_error: false,
+            metadata: Default::default(),
+        };
+        let budgeter = budgeter(TokenConfig::default().with_truncation(TruncationStrategy::Tail));
+        let params = serde_json::json!({"_meta": {"tokenBudget": 20}});
+        let budget = budgeter.budget(Some(&params));
+        budgeter.apply_to_tool_result(&mut result, budget).await;
+
+        // Only the last resource is cut, keeping its end
+        assert_eq!(result.content[1], resource("file:///a", "aaaaaaaaaa"));
+        assert_eq!(result.content[2], resource("file:///b", "789"));
+        assert_eq!(
+            result.metadata["_meta"],
+            serde_json::json!({
+                "tokenCount": 20,
+                "tokenBudget": 20,
+                "truncatedResources": ["file:///b"],
+            })
+        );
+
+        // Within budget and not annotating, results are left alone
+        let mut untouched = result.clone();
+        untouched.metadata.clear();
+        budgeter.apply_to_tool_result(&mut untouched, None).await;
+        assert!(untouched.metadata.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_prompts_are_annotated_and_resources_omitted() {
+        let message = |content| PromptMessage {
+            role: MessageRole::User,
+            content,
+        };
+        let mut result = GetPromptResult {
+            name: "review".to_string(),
+            description: "Review".to_string(),
+            messages: vec![
+                message(PromptContent::Text {
+                    text: "Review this code".to_string(),
+                }),
+                message(PromptContent::EmbeddedResource {
+                    resource: EmbeddedResource {
+                        uri: "file:///main.rs".to_string(),
+                        mime_type: None,
+                        annotation: None,
+                    },
+                    text: Some("fn main() {}".to_string()),
+                }),
+            ],
+            meta: HashMap::new(),
+        };
+        let budgeter = budgeter(
+            TokenConfig::default()
+                .annotate()
+                .with_default_budget(25)
+                .with_truncation(TruncationStrategy::Omit),
+        );
+        budgeter
+            .apply_to_prompt(&mut result, budgeter.budget(None))
+            .await;
+
+        assert!(matches!(
+            &result.messages[1].content,
+            PromptContent::EmbeddedResource { text: None, .. }
+        ));
+        assert_eq!(result.meta[TOKEN_COUNT_META], 22);
+        assert_eq!(
+            result.meta[TRUNCATED_RESOURCES_META],
+            serde_json::json!(["file:///main.rs"])
+        );
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_estimator() {
+        assert_eq!(TiktokenEstimator::o200k().estimate("hello world"), 2);
+    }
+}
@@ -0,0 +1,267 @@
+//! Rate limits and quotas declared on tools
+//!
+//! Limits live next to the tool they protect instead of in a separate
+//! configuration file. [`McpTool::with_limit`](crate::server::McpTool::with_limit)
+//! adds a limit to the tool's `limits` metadata, written as a count per
+//! window and who it applies to:
+//!
+//! ```text
+//! 5/min per principal     10/s per session     1000/day global
+//! ```
+//!
+//! Windows are `s`, `min`, `h` or `day`, optionally with a count such as
+//! `15min`. The scope defaults to `per principal` (see
+//! [`SecurityContext::principal`]). Short windows act as rate limits, long
+//! ones as quotas; both are sliding windows enforced before the tool runs,
+//! and calls over a limit fail with [`McpError::RateLimitExceeded`]. System
+//! contexts are not limited. Limits that do not parse are reported by
+//! [`McpServer::validate`](crate::server::McpServer::validate) and otherwise
+//! ignored.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    clock::Clock,
+    error::{McpError, McpResult},
+    protocol::Tool,
+    security::{RateLimitConfig, RateLimiter, SecurityContext},
+};
+
+/// Tool metadata key of the declared limits
+pub const TOOL_LIMITS_META: &str = "limits";
+
+/// Who shares the budget of a tool limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitScope {
+    /// Each principal has its own budget
+    Principal,
+    /// Each session has its own budget
+    Session,
+    /// All callers share one budget
+    Global,
+}
+
+/// A limit on calls to a tool, such as `5/min per principal`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ToolLimit {
+    /// Calls allowed per window
+    pub max_calls: u32,
+
+    /// Length of the window
+    pub window: Duration,
+
+    /// Who shares the budget
+    pub scope: LimitScope,
+}
+
+impl ToolLimit {
+    /// `max_calls` per `window` for each principal
+    pub fn new(max_calls: u32, window: Duration) -> Self {
+        Self {
+            max_calls,
+            window,
+            scope: LimitScope::Principal,
+        }
+    }
+
+    /// Apply the limit to `scope` instead
+    pub fn with_scope(mut self, scope: LimitScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Key of the budget `context` draws from
+    fn key(&self, context: &SecurityContext) -> String {
+        match self.scope {
+            LimitScope::Principal => context.principal(),
+            LimitScope::Session => context
+                .client
+                .session_id
+                .clone()
+                .unwrap_or_else(|| context.principal()),
+            LimitScope::Global => String::new(),
+        }
+    }
+}
+
+impl FromStr for ToolLimit {
+    type Err = McpError;
+
+    fn from_str(spec: &str) -> McpResult<Self> {
+        let invalid = |reason: &str| McpError::Validation {
+            message: format!("Invalid tool limit '{}': {}", spec, reason),
+        };
+        let mut words = spec.split_whitespace();
+        let rate = words.next().ok_or_else(|| invalid("empty"))?;
+        let (count, window) = rate
+            .split_once('/')
+            .ok_or_else(|| invalid("expected calls/window"))?;
+        let max_calls = count
+            .parse()
+            .map_err(|_| invalid("call count is not a number"))?;
+
+        let digits = window.len()
+            - window
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        let multiplier: u64 = match &window[..digits] {
+            "" => 1,
+            n => n.parse().map_err(|_| invalid("bad window length"))?,
+        };
+        let unit = match &window[digits..] {
+            "s" | "sec" | "second" => 1,
+            "min" | "minute" => 60,
+            "h" | "hour" => 3600,
+            "d" | "day" => 86_400,
+            _ => return Err(invalid("window is not s, min, h or day")),
+        };
+        if multiplier == 0 {
+            return Err(invalid("window is empty"));
+        }
+
+        let scope = match (words.next(), words.next(), words.next()) {
+            (None, _, _) | (Some("per"), Some("principal"), None) => LimitScope::Principal,
+            (Some("per"), Some("session"), None) => LimitScope::Session,
+            (Some("global"), None, _) => LimitScope::Global,
+            _ => return Err(invalid("scope is not per principal, per session or global")),
+        };
+        Ok(Self {
+            max_calls,
+            window: Duration::from_secs(multiplier * unit),
+            scope,
+        })
+    }
+}
+
+impl fmt::Display for ToolLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.window.as_secs();
+        let (length, unit) = [(86_400, "day"), (3600, "h"), (60, "min")]
+            .into_iter()
+            .find(|(unit, _)| seconds.is_multiple_of(*unit))
+            .map_or((seconds, "s"), |(size, unit)| (seconds / size, unit));
+        write!(f, "{}/", self.max_calls)?;
+        if length != 1 {
+            write!(f, "{}", length)?;
+        }
+        match self.scope {
+            LimitScope::Principal => write!(f, "{} per principal", unit),
+            LimitScope::Session => write!(f, "{} per session", unit),
+            LimitScope::Global => write!(f, "{} global", unit),
+        }
+    }
+}
+
+/// Limit specs declared in the metadata of `tool`, unparsed
+pub fn declared_limits(tool: &Tool) -> Vec<String> {
+    tool.metadata
+        .get(TOOL_LIMITS_META)
+        .and_then(|limits| limits.as_array())
+        .map(|limits| {
+            limits
+                .iter()
+                .filter_map(|limit| limit.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Limiter of each tool and limit
+type Limiters = Arc<Mutex<HashMap<(String, ToolLimit), Arc<RateLimiter>>>>;
+
+/// Enforces the limits declared on tools
+///
+/// Limiters are created on a tool's first call and kept per tool and limit.
+#[derive(Clone, Default)]
+pub struct ToolLimits {
+    limiters: Limiters,
+    clock: Clock,
+}
+
+impl ToolLimits {
+    /// Limits measured on real time
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Measure windows on `clock`, forgetting the calls counted so far
+    pub fn with_clock(self, clock: Clock) -> Self {
+        Self {
+            limiters: Default::default(),
+            clock,
+        }
+    }
+
+    /// Count a call of `tool` by `context` against its declared limits
+    ///
+    /// Fails with [`McpError::RateLimitExceeded`] for the first limit the
+    /// call would exceed.
+    pub async fn check(&self, tool: &Tool, context: &SecurityContext) -> McpResult<()> {
+        if context.is_system() {
+            return Ok(());
+        }
+        for spec in declared_limits(tool) {
+            let Ok(limit) = spec.parse::<ToolLimit>() else {
+                continue;
+            };
+            let limiter = self
+                .limiters
+                .lock()
+                .unwrap()
+                .entry((tool.name.clone(), limit.clone()))
+                .or_insert_with(|| {
+                    Arc::new(
+                        RateLimiter::new(RateLimitConfig::new(limit.max_calls, limit.window))
+                            .with_clock(self.clock.clone()),
+                    )
+                })
+                .clone();
+            limiter
+                .check_rate_limit(&limit.key(context))
+                .await
+                .map_err(|error| match error {
+                    McpError::RateLimitExceeded { retry_after, .. } => {
+                        McpError::RateLimitExceeded {
+                            message: format!("Tool '{}' is limited to {}", tool.name, limit),
+                            retry_after,
+                        }
+                    }
+                    error => error,
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limits() {
+        let limit: ToolLimit = "5/min per principal".parse().unwrap();
+        assert_eq!(limit, ToolLimit::new(5, Duration::from_secs(60)));
+        assert_eq!(limit.to_string(), "5/min per principal");
+
+        let limit: ToolLimit = "1000/day global".parse().unwrap();
+        assert_eq!(limit.scope, LimitScope::Global);
+        assert_eq!(limit.window, Duration::from_secs(86_400));
+
+        let limit: ToolLimit = "10/15min per session".parse().unwrap();
+        assert_eq!(limit.window, Duration::from_secs(900));
+        assert_eq!(limit.to_string(), "10/15min per session");
+        assert_eq!("3/s".parse::<ToolLimit>().unwrap().max_calls, 3);
+
+        for spec in ["", "five/min", "5/fortnight", "5/0s", "5/min per tenant"] {
+            assert!(spec.parse::<ToolLimit>().is_err(), "{}", spec);
+        }
+    }
+}
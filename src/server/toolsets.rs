@@ -0,0 +1,310 @@
+//! Toolsets: named groups of tools enabled per session
+//!
+//! A server hosting hundreds of tools can group them into toolsets, so a
+//! conversation that needs only a few does not see them all. Each toolset
+//! lists tool names, or name prefixes ending in `*`. Within a session:
+//!
+//! - `tools/list` shows the tools of enabled toolsets, and tools in no toolset,
+//! - `tools/call` rejects tools that are only in disabled toolsets,
+//! - `toolsets/list` lists the toolsets, their tools and whether they are
+//!   enabled,
+//! - `toolsets/enable` and `toolsets/disable` switch toolsets on and off,
+//!   taking `{"toolsets": [...]}`.
+//!
+//! Operators switch toolsets for any session with
+//! `admin/toolsets/configure`, which takes the `sessionId` and lists to
+//! `enable` and `disable`. Sessions start with the toolsets that are
+//! [`Toolset::enabled_by_default`]; their choices live in the session's
+//! [`SessionStore`] and end with it. Requests outside a session always use the
+//! defaults. When [`ToolsetConfig::enabled`] is set, the server advertises the
+//! methods under the [`TOOLSETS_CAPABILITY`] key of its experimental
+//! capabilities.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::Tool,
+    server::session_store::SessionStore,
+};
+
+/// Key of the toolsets capability in the server's experimental capabilities
+pub const TOOLSETS_CAPABILITY: &str = "toolsets";
+
+/// List the toolsets and whether they are enabled in the caller's session
+pub const TOOLSETS_LIST: &str = "toolsets/list";
+
+/// Enable toolsets in the caller's session
+pub const TOOLSETS_ENABLE: &str = "toolsets/enable";
+
+/// Disable toolsets in the caller's session
+pub const TOOLSETS_DISABLE: &str = "toolsets/disable";
+
+/// Enable and disable toolsets in any session; requires a system context
+pub const ADMIN_TOOLSETS_CONFIGURE: &str = "admin/toolsets/configure";
+
+/// The `toolsets/*` methods
+pub const TOOLSET_METHODS: &[&str] = &[TOOLSETS_LIST, TOOLSETS_ENABLE, TOOLSETS_DISABLE];
+
+/// Session store key holding the session's enabled toolsets
+pub const ENABLED_TOOLSETS_KEY: &str = "mcp.toolsets.enabled";
+
+/// A named group of tools
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Toolset {
+    /// What the tools in this set are for
+    pub description: String,
+
+    /// Tool names, or prefixes ending in `*`
+    pub tools: Vec<String>,
+
+    /// Whether sessions start with this toolset enabled
+    pub enabled_by_default: bool,
+}
+
+impl Toolset {
+    /// Create an empty toolset
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a tool name, or a prefix ending in `*`
+    pub fn with_tool(mut self, pattern: impl Into<String>) -> Self {
+        self.tools.push(pattern.into());
+        self
+    }
+
+    /// Enable this toolset in new sessions
+    pub fn enabled_by_default(mut self) -> Self {
+        self.enabled_by_default = true;
+        self
+    }
+
+    /// Whether the tool `name` belongs to this toolset
+    pub fn contains(&self, name: &str) -> bool {
+        self.tools
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => pattern == name,
+            })
+    }
+}
+
+/// Toolset configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsetConfig {
+    /// Whether tools are grouped into toolsets and the methods are served
+    pub enabled: bool,
+
+    /// Toolsets by name
+    pub toolsets: BTreeMap<String, Toolset>,
+}
+
+impl ToolsetConfig {
+    /// Group tools into toolsets
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Add the toolset `name`
+    pub fn with_toolset(mut self, name: impl Into<String>, toolset: Toolset) -> Self {
+        self.toolsets.insert(name.into(), toolset);
+        self
+    }
+}
+
+/// Params of `toolsets/enable` and `toolsets/disable`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolsetsParams {
+    /// Toolset names
+    pub toolsets: Vec<String>,
+}
+
+/// Params of `admin/toolsets/configure`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolsetsConfigureParams {
+    /// Session to configure
+    pub session_id: String,
+
+    /// Toolsets to enable
+    #[serde(default)]
+    pub enable: Vec<String>,
+
+    /// Toolsets to disable
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+/// A toolset as reported by `toolsets/list`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolsetInfo {
+    /// Toolset name
+    pub name: String,
+
+    /// What the tools in this set are for
+    pub description: String,
+
+    /// Tool names and prefixes
+    pub tools: Vec<String>,
+
+    /// Whether the toolset is enabled in the session
+    pub enabled: bool,
+}
+
+/// Toolsets of a server and their state in each session
+#[derive(Debug, Clone, Default)]
+pub struct Toolsets {
+    config: ToolsetConfig,
+}
+
+impl Toolsets {
+    /// Group tools as configured
+    pub fn new(config: ToolsetConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether toolsets are in use
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Capability advertised under [`TOOLSETS_CAPABILITY`]
+    pub fn capability(&self) -> Value {
+        serde_json::json!({ "methods": TOOLSET_METHODS })
+    }
+
+    /// Toolsets enabled in the session of `store`, or the defaults
+    pub async fn enabled_in(&self, store: Option<&SessionStore>) -> BTreeSet<String> {
+        if let Some(store) = store {
+            if let Ok(Some(enabled)) = store.get_as(ENABLED_TOOLSETS_KEY).await {
+                return enabled;
+            }
+        }
+        self.config
+            .toolsets
+            .iter()
+            .filter(|(_, toolset)| toolset.enabled_by_default)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Whether the tool `name` is available with `enabled` toolsets
+    ///
+    /// Tools in no toolset are always available.
+    pub fn allows(&self, name: &str, enabled: &BTreeSet<String>) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let mut grouped = false;
+        for (toolset_name, toolset) in &self.config.toolsets {
+            if toolset.contains(name) {
+                if enabled.contains(toolset_name) {
+                    return true;
+                }
+                grouped = true;
+            }
+        }
+        !grouped
+    }
+
+    /// `tools` without those unavailable with `enabled` toolsets
+    pub fn filter(&self, tools: Vec<Tool>, enabled: &BTreeSet<String>) -> Vec<Tool> {
+        tools
+            .into_iter()
+            .filter(|tool| self.allows(&tool.name, enabled))
+            .collect()
+    }
+
+    /// Enable and disable toolsets in the session of `store`
+    ///
+    /// Returns the toolsets enabled afterwards. Unknown names are rejected.
+    pub async fn configure(
+        &self,
+        store: &SessionStore,
+        enable: &[String],
+        disable: &[String],
+    ) -> McpResult<BTreeSet<String>> {
+        if let Some(unknown) = enable
+            .iter()
+            .chain(disable)
+            .find(|name| !self.config.toolsets.contains_key(*name))
+        {
+            return Err(McpError::Validation {
+                message: format!("Unknown toolset '{}'", unknown),
+            });
+        }
+        let mut enabled = self.enabled_in(Some(store)).await;
+        enabled.extend(enable.iter().cloned());
+        for name in disable {
+            enabled.remove(name);
+        }
+        store.set(ENABLED_TOOLSETS_KEY, &enabled).await?;
+        Ok(enabled)
+    }
+
+    /// All toolsets, marked enabled if in `enabled`
+    pub fn describe(&self, enabled: &BTreeSet<String>) -> Vec<ToolsetInfo> {
+        self.config
+            .toolsets
+            .iter()
+            .map(|(name, toolset)| ToolsetInfo {
+                name: name.clone(),
+                description: toolset.description.clone(),
+                tools: toolset.tools.clone(),
+                enabled: enabled.contains(name),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_toolsets_per_session() {
+        let toolsets = Toolsets::new(
+            ToolsetConfig::enabled()
+                .with_toolset(
+                    "files",
+                    Toolset::new("Files")
+                        .with_tool("read_file")
+                        .enabled_by_default(),
+                )
+                .with_toolset("github", Toolset::new("GitHub").with_tool("github_*")),
+        );
+        let store = SessionStore::new();
+
+        let enabled = toolsets.enabled_in(Some(&store)).await;
+        assert!(toolsets.allows("read_file", &enabled));
+        assert!(!toolsets.allows("github_issues", &enabled));
+        assert!(toolsets.allows("echo", &enabled));
+
+        let enabled = toolsets
+            .configure(&store, &["github".to_string()], &["files".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(enabled, toolsets.enabled_in(Some(&store)).await);
+        assert!(toolsets.allows("github_issues", &enabled));
+        assert!(!toolsets.allows("read_file", &enabled));
+        // Other sessions keep the defaults
+        assert!(toolsets.allows("read_file", &toolsets.enabled_in(None).await));
+
+        assert!(toolsets
+            .configure(&store, &["unknown".to_string()], &[])
+            .await
+            .is_err());
+    }
+}
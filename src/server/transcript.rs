@@ -0,0 +1,566 @@
+//! Golden protocol transcripts
+//!
+//! A [`Scenario`] is a scripted conversation with a server: the JSON-RPC
+//! requests and notifications a client sends, in order. [`GoldenTranscripts`]
+//! plays scenarios against an [`McpServer`], records every request, response
+//! and server notification as a canonical JSON [`Transcript`], and compares it
+//! with a golden file checked in next to the tests. Differences are reported
+//! as unified diffs, so a change in protocol behavior shows up in review:
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn protocol_behaves_as_before() {
+//!     GoldenTranscripts::new("tests/transcripts")
+//!         .check_dir(&my_server(), "tests/scenarios")
+//!         .await
+//!         .unwrap()
+//!         .assert_unchanged();
+//! }
+//! ```
+//!
+//! Scenario files are JSON:
+//!
+//! ```json
+//! {
+//!   "steps": [
+//!     {"method": "initialize", "params": {"protocolVersion": "2025-03-26", "capabilities": {}}},
+//!     {"method": "notifications/initialized", "notification": true},
+//!     {"method": "tools/call", "params": {"name": "search", "arguments": {"query": "rust"}}}
+//!   ]
+//! }
+//! ```
+//!
+//! Requests without an `id` are numbered from 1. Missing golden files are
+//! written on the first run, except in comparison mode, which is on when the
+//! `CI` environment variable is set: there a missing golden file is a
+//! mismatch. Set `UPDATE_PROTOCOL_GOLDENS=1` to accept changed transcripts.
+
+use futures_util::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::JsonRpcRequest,
+    security::SecurityContext,
+    server::{build_info::BUILD_INFO_KEY, diff::unified_diff, service::McpServer, McpServerState},
+};
+
+/// Environment variable that makes [`GoldenTranscripts`] accept changed transcripts
+pub const UPDATE_TRANSCRIPTS_ENV: &str = "UPDATE_PROTOCOL_GOLDENS";
+
+/// Environment variable that turns on comparison mode
+pub const CI_ENV: &str = "CI";
+
+/// Placeholder written in place of redacted values
+pub const REDACTED: &str = "<redacted>";
+
+/// Session ID scenarios run under unless they name one
+pub const DEFAULT_SCENARIO_SESSION: &str = "golden-session";
+
+/// One message a scenario sends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// JSON-RPC method
+    pub method: String,
+
+    /// Method parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+
+    /// Request ID; numbered from 1 when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+
+    /// Send the step as a notification, without an ID or a response
+    #[serde(default)]
+    pub notification: bool,
+}
+
+/// A scripted conversation with a server
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    /// Name of the scenario and of its golden file; the file stem if loaded
+    /// from a file without one
+    pub name: String,
+
+    /// Session ID the steps run under
+    pub session_id: Option<String>,
+
+    /// Messages sent, in order
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// Create an empty scenario
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Send a request
+    pub fn request(mut self, method: impl Into<String>, params: Option<Value>) -> Self {
+        self.steps.push(ScenarioStep {
+            method: method.into(),
+            params,
+            id: None,
+            notification: false,
+        });
+        self
+    }
+
+    /// Send a notification
+    pub fn notify(mut self, method: impl Into<String>, params: Option<Value>) -> Self {
+        self.steps.push(ScenarioStep {
+            method: method.into(),
+            params,
+            id: None,
+            notification: true,
+        });
+        self
+    }
+
+    /// Load a scenario file
+    pub fn load(path: impl AsRef<Path>) -> McpResult<Self> {
+        let path = path.as_ref();
+        let mut scenario: Scenario = serde_json::from_str(&std::fs::read_to_string(path)?)
+            .map_err(|e| McpError::Validation {
+                message: format!("Invalid scenario {}: {}", path.display(), e),
+            })?;
+        if scenario.name.is_empty() {
+            scenario.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(scenario)
+    }
+}
+
+/// One message of a scenario and what the server did with it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    /// Message sent
+    pub request: Value,
+
+    /// Response, absent for notifications
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+
+    /// Notifications the server published while handling the message
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<Value>,
+}
+
+/// Canonical record of a scenario run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// Scenario name
+    pub scenario: String,
+
+    /// Exchanges in order
+    pub exchanges: Vec<Exchange>,
+}
+
+/// A transcript that differs from its golden file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptMismatch {
+    /// Scenario whose transcript changed
+    pub scenario: String,
+
+    /// Unified diff from the golden transcript to the current one
+    pub diff: String,
+}
+
+/// Outcome of checking scenarios against their golden files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranscriptReport {
+    /// Scenarios run
+    pub checked: usize,
+
+    /// Golden files written for the first time
+    pub created: Vec<PathBuf>,
+
+    /// Golden files rewritten because updating was enabled
+    pub updated: Vec<PathBuf>,
+
+    /// Transcripts that differ from their golden files, or have none in
+    /// comparison mode
+    pub mismatches: Vec<TranscriptMismatch>,
+}
+
+impl TranscriptReport {
+    /// Panic with the diffs if any transcript changed
+    pub fn assert_unchanged(&self) {
+        if self.mismatches.is_empty() {
+            return;
+        }
+        let diffs: Vec<String> = self
+            .mismatches
+            .iter()
+            .map(|m| format!("scenario '{}':\n{}", m.scenario, m.diff))
+            .collect();
+        panic!(
+            "{} protocol transcript(s) changed; rerun with {}=1 to accept them\n\n{}",
+            self.mismatches.len(),
+            UPDATE_TRANSCRIPTS_ENV,
+            diffs.join("\n")
+        );
+    }
+}
+
+/// Plays scenarios and compares their transcripts with golden files
+#[derive(Debug, Clone)]
+pub struct GoldenTranscripts {
+    dir: PathBuf,
+    update: bool,
+    compare_only: bool,
+    redacted: BTreeSet<String>,
+    context: SecurityContext,
+}
+
+impl GoldenTranscripts {
+    /// Keep golden files in `dir`, one `<scenario>.json` per scenario
+    ///
+    /// Updating is enabled when [`UPDATE_TRANSCRIPTS_ENV`] is set to anything
+    /// but `0`, comparison mode when [`CI_ENV`] is. Scenarios run with a
+    /// system context. The build metadata of the server info, which differs
+    /// between builds and runs, is redacted.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let enabled = |name| std::env::var(name).is_ok_and(|value| value != "0");
+        Self {
+            dir: dir.into(),
+            update: enabled(UPDATE_TRANSCRIPTS_ENV),
+            compare_only: enabled(CI_ENV),
+            redacted: BTreeSet::from([BUILD_INFO_KEY.to_string()]),
+            context: SecurityContext::system(),
+        }
+    }
+
+    /// Rewrite golden files that differ instead of reporting them
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Report missing golden files instead of writing them
+    pub fn with_compare_only(mut self, compare_only: bool) -> Self {
+        self.compare_only = compare_only;
+        self
+    }
+
+    /// Replace the value of every field named `key` with [`REDACTED`]
+    ///
+    /// For values that change from run to run, such as timestamps and
+    /// generated IDs.
+    pub fn with_redacted(mut self, key: impl Into<String>) -> Self {
+        self.redacted.insert(key.into());
+        self
+    }
+
+    /// Run scenarios with `context` instead of a system context
+    pub fn with_context(mut self, context: SecurityContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Play `scenario` and record its transcript
+    pub async fn record<S: McpServerState>(
+        &self,
+        server: &McpServer<S>,
+        scenario: &Scenario,
+    ) -> McpResult<Transcript> {
+        let mut context = self.context.clone();
+        context.client.session_id = Some(
+            scenario
+                .session_id
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SCENARIO_SESSION.to_string()),
+        );
+        let mut published = server.notifications().broker().subscribe().await?;
+
+        let mut exchanges = Vec::with_capacity(scenario.steps.len());
+        let mut next_id = 1;
+        for step in &scenario.steps {
+            let id = match (&step.id, step.notification) {
+                (_, true) => None,
+                (Some(id), false) => Some(id.clone()),
+                (None, false) => {
+                    next_id += 1;
+                    Some(Value::from(next_id - 1))
+                }
+            };
+            let request = JsonRpcRequest::new(step.method.clone(), step.params.clone(), id);
+            let request_json = serde_json::to_value(&request)?;
+            let response = server.handle_request(request, context.clone()).await;
+            let response = (!step.notification)
+                .then(|| serde_json::to_value(&response))
+                .transpose()?;
+
+            // Notifications published in the background get a chance to land
+            for _ in 0..8 {
+                tokio::task::yield_now().await;
+            }
+            let mut notifications = Vec::new();
+            while let Some(Some(notification)) = published.next().now_or_never() {
+                notifications.push(self.canonical(serde_json::to_value(&notification)?));
+            }
+
+            exchanges.push(Exchange {
+                request: self.canonical(request_json),
+                response: response.map(|response| self.canonical(response)),
+                notifications,
+            });
+        }
+
+        Ok(Transcript {
+            scenario: scenario.name.clone(),
+            exchanges,
+        })
+    }
+
+    /// Play `scenario` and compare its transcript with the golden file
+    pub async fn check<S: McpServerState>(
+        &self,
+        server: &McpServer<S>,
+        scenario: &Scenario,
+    ) -> McpResult<TranscriptReport> {
+        let mut report = TranscriptReport::default();
+        self.check_into(server, scenario, &mut report).await?;
+        Ok(report)
+    }
+
+    /// Play every `*.json` scenario in `dir`, in file name order
+    pub async fn check_dir<S: McpServerState>(
+        &self,
+        server: &McpServer<S>,
+        dir: impl AsRef<Path>,
+    ) -> McpResult<TranscriptReport> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut report = TranscriptReport::default();
+        for path in paths {
+            let scenario = Scenario::load(&path)?;
+            self.check_into(server, &scenario, &mut report).await?;
+        }
+        Ok(report)
+    }
+
+    async fn check_into<S: McpServerState>(
+        &self,
+        server: &McpServer<S>,
+        scenario: &Scenario,
+        report: &mut TranscriptReport,
+    ) -> McpResult<()> {
+        let transcript = self.record(server, scenario).await?;
+        report.checked += 1;
+
+        let path = self.path_for(&scenario.name);
+        let current = format!("{}\n", serde_json::to_string_pretty(&transcript)?);
+        let golden = match std::fs::read_to_string(&path) {
+            Ok(golden) => golden,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !self.compare_only => {
+                write(&path, &current)?;
+                report.created.push(path);
+                return Ok(());
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if golden == current {
+            return Ok(());
+        }
+        if self.update {
+            write(&path, &current)?;
+            report.updated.push(path);
+            return Ok(());
+        }
+        report.mismatches.push(TranscriptMismatch {
+            scenario: scenario.name.clone(),
+            diff: unified_diff(&golden, &current, "golden", "recorded", 3).unified,
+        });
+        Ok(())
+    }
+
+    /// Sort object keys and redact configured fields
+    fn canonical(&self, value: Value) -> Value {
+        match value {
+            Value::Object(object) => {
+                let mut entries: Vec<(String, Value)> = object.into_iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let value = if self.redacted.contains(&key) {
+                                Value::String(REDACTED.to_string())
+                            } else {
+                                self.canonical(value)
+                            };
+                            (key, value)
+                        })
+                        .collect(),
+                )
+            }
+            Value::Array(items) => {
+                Value::Array(items.into_iter().map(|item| self.canonical(item)).collect())
+            }
+            value => value,
+        }
+    }
+
+    /// Golden file of a scenario, with characters unsafe in file names replaced
+    fn path_for(&self, scenario: &str) -> PathBuf {
+        let file: String = scenario
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+}
+
+fn write(path: &Path, contents: &str) -> McpResult<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        security::{ClientContext, McpAuth},
+        server::{
+            config::McpServerConfig,
+            registry::{InMemoryToolRegistry, McpTool},
+        },
+    };
+    use async_trait::async_trait;
+
+    #[derive(Clone)]
+    struct OpenAuth;
+
+    #[async_trait]
+    impl McpAuth for OpenAuth {
+        async fn authenticate(&self, _client: &ClientContext) -> McpResult<SecurityContext> {
+            Ok(SecurityContext::system())
+        }
+
+        async fn authorize(
+            &self,
+            _context: &SecurityContext,
+            _resource: &str,
+            _action: &str,
+        ) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone)]
+    struct State(InMemoryToolRegistry, OpenAuth);
+
+    impl McpServerState for State {
+        type ToolRegistry = InMemoryToolRegistry;
+        type AuthManager = OpenAuth;
+
+        fn tool_registry(&self) -> &Self::ToolRegistry {
+            &self.0
+        }
+
+        fn auth_manager(&self) -> &Self::AuthManager {
+            &self.1
+        }
+    }
+
+    fn server(tools: &[&str]) -> McpServer<State> {
+        let mut registry = InMemoryToolRegistry::new();
+        for tool in tools {
+            registry.register_tool(
+                McpTool::new(
+                    *tool,
+                    "A tool",
+                    serde_json::json!({"type": "object"}),
+                    "test",
+                )
+                .public(),
+            );
+        }
+        McpServer::new(McpServerConfig::default(), State(registry, OpenAuth))
+    }
+
+    #[tokio::test]
+    async fn test_golden_transcripts() {
+        let dir = std::env::temp_dir().join(format!("transcripts-{}", uuid::Uuid::new_v4()));
+        let scenario = Scenario::new("handshake")
+            .request(
+                "initialize",
+                Some(serde_json::json!({
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": {"name": "golden", "version": "1.0.0"}
+                })),
+            )
+            .notify("notifications/initialized", None)
+            .request("tools/list", None);
+        let goldens = GoldenTranscripts::new(&dir)
+            .with_update(false)
+            .with_compare_only(false);
+
+        let transcript = goldens
+            .record(&server(&["search"]), &scenario)
+            .await
+            .unwrap();
+        assert_eq!(transcript.exchanges.len(), 3);
+        assert_eq!(transcript.exchanges[2].request["id"], 2);
+        assert!(transcript.exchanges[1].response.is_none());
+
+        let report = goldens
+            .check(&server(&["search"]), &scenario)
+            .await
+            .unwrap();
+        assert_eq!(report.created, [dir.join("handshake.json")]);
+        goldens
+            .check(&server(&["search"]), &scenario)
+            .await
+            .unwrap()
+            .assert_unchanged();
+
+        let report = goldens
+            .check(&server(&["search", "fetch"]), &scenario)
+            .await
+            .unwrap();
+        assert_eq!(report.mismatches.len(), 1);
+        assert!(report.mismatches[0]
+            .diff
+            .contains("+              \"name\": \"fetch\""));
+
+        // Comparison mode does not write missing golden files
+        let report = goldens
+            .with_compare_only(true)
+            .check(&server(&["search"]), &Scenario::new("missing"))
+            .await
+            .unwrap();
+        assert!(report.created.is_empty());
+        assert_eq!(report.mismatches.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,763 @@
+//! Format conversions of resources on `resources/read`
+//!
+//! Clients ask for another format with a `format` query parameter on the URI
+//! (`docs://guide.md?format=html`) or with the `accept` argument of
+//! `resources/read`, a comma-separated list of MIME types in order of
+//! preference, optionally weighted with `q` values as in HTTP's `Accept`
+//! header. Formats may be given as MIME types or file extensions. The
+//! first acceptable format that is either the resource's own or reachable
+//! through a [`ResourceTransformer`] is returned, and the original MIME type is
+//! recorded in the result's `_meta.transformedFrom`.
+//!
+//! Built-in transformers convert Markdown to HTML, HTML to Markdown and CSV to
+//! JSON rows. They cover the common subset of each format; register a
+//! transformer for the same pair to replace one. With the `documents` feature,
+//! text is also extracted from PDF and DOCX documents requested as
+//! `text/plain`.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{
+    error::{McpError, McpResult},
+    server::negotiation::parse_accept,
+};
+
+/// Query parameter selecting a format, as in `docs://guide.md?format=html`
+pub const FORMAT_QUERY_PARAM: &str = "format";
+
+/// `_meta` key holding the MIME type a resource was converted from
+pub const TRANSFORMED_FROM_META: &str = "transformedFrom";
+
+/// Converts resource text from one MIME type to another
+#[async_trait]
+pub trait ResourceTransformer: Send + Sync {
+    /// MIME type this transformer reads
+    fn source(&self) -> &str;
+
+    /// MIME type this transformer produces
+    fn target(&self) -> &str;
+
+    /// Convert the text of the resource at `uri`
+    async fn transform(&self, uri: &str, text: &str) -> McpResult<String>;
+
+    /// Convert the binary content of the resource at `uri`
+    ///
+    /// Transformers of text keep the default, which refuses binary content.
+    async fn transform_bytes(&self, uri: &str, _bytes: &[u8]) -> McpResult<String> {
+        Err(McpError::Validation {
+            message: format!("Cannot convert binary resource {}", uri),
+        })
+    }
+}
+
+/// Transformers available to `resources/read`
+#[derive(Clone)]
+pub struct ResourceTransformers {
+    transformers: Vec<Arc<dyn ResourceTransformer>>,
+}
+
+impl ResourceTransformers {
+    /// No transformers at all
+    pub fn empty() -> Self {
+        Self {
+            transformers: Vec::new(),
+        }
+    }
+
+    /// Add a transformer, taking precedence over those for the same formats
+    pub fn with_transformer(mut self, transformer: Arc<dyn ResourceTransformer>) -> Self {
+        self.transformers.insert(0, transformer);
+        self
+    }
+
+    #[cfg(feature = "documents")]
+    fn with_document_extractors(self) -> Self {
+        use crate::server::documents::{DocxTextExtractor, PdfTextExtractor};
+
+        self.with_transformer(Arc::new(PdfTextExtractor))
+            .with_transformer(Arc::new(DocxTextExtractor))
+    }
+
+    #[cfg(not(feature = "documents"))]
+    fn with_document_extractors(self) -> Self {
+        self
+    }
+
+    /// Transformer from `source` to `target`
+    pub fn find(&self, source: &str, target: &str) -> Option<&dyn ResourceTransformer> {
+        self.transformers
+            .iter()
+            .find(|t| {
+                t.source().eq_ignore_ascii_case(source) && t.target().eq_ignore_ascii_case(target)
+            })
+            .map(|t| t.as_ref())
+    }
+
+    /// Transformer to the first acceptable format for content of type `source`
+    ///
+    /// Returns `None` if the content is acceptable as it is.
+    pub fn select(
+        &self,
+        uri: &str,
+        source: &str,
+        accept: &str,
+    ) -> McpResult<Option<&dyn ResourceTransformer>> {
+        let source = essence(source);
+        for range in parse_accept(accept) {
+            if range.quality <= 0.0 {
+                continue;
+            }
+            if range.matches(&source) {
+                return Ok(None);
+            }
+            if let Some(transformer) = self.find(&source, &range.mime_type) {
+                return Ok(Some(transformer));
+            }
+        }
+        Err(McpError::Validation {
+            message: format!("Cannot convert {} from {} to {}", uri, source, accept),
+        })
+    }
+}
+
+impl Default for ResourceTransformers {
+    fn default() -> Self {
+        Self::empty()
+            .with_transformer(Arc::new(CsvToJson))
+            .with_transformer(Arc::new(HtmlToMarkdown))
+            .with_transformer(Arc::new(MarkdownToHtml))
+            .with_document_extractors()
+    }
+}
+
+/// Lowercased MIME type without parameters
+fn essence(mime_type: &str) -> String {
+    mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// MIME type of a format given as a MIME type or file extension
+pub(crate) fn resolve_format(format: &str) -> String {
+    let format = essence(format);
+    if format.contains('/') || format.is_empty() {
+        return format;
+    }
+    mime_guess::from_ext(&format)
+        .first_raw()
+        .map(str::to_string)
+        .unwrap_or(format)
+}
+
+/// Markdown to HTML: headings, paragraphs, lists, quotes, code, emphasis and links
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownToHtml;
+
+#[async_trait]
+impl ResourceTransformer for MarkdownToHtml {
+    fn source(&self) -> &str {
+        "text/markdown"
+    }
+
+    fn target(&self) -> &str {
+        "text/html"
+    }
+
+    async fn transform(&self, _uri: &str, text: &str) -> McpResult<String> {
+        Ok(markdown_to_html(text))
+    }
+}
+
+/// HTML to Markdown, dropping scripts, styles and unknown tags
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlToMarkdown;
+
+#[async_trait]
+impl ResourceTransformer for HtmlToMarkdown {
+    fn source(&self) -> &str {
+        "text/html"
+    }
+
+    fn target(&self) -> &str {
+        "text/markdown"
+    }
+
+    async fn transform(&self, _uri: &str, text: &str) -> McpResult<String> {
+        Ok(html_to_markdown(text))
+    }
+}
+
+/// CSV with a header row to a JSON array of row objects
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvToJson;
+
+#[async_trait]
+impl ResourceTransformer for CsvToJson {
+    fn source(&self) -> &str {
+        "text/csv"
+    }
+
+    fn target(&self) -> &str {
+        "application/json"
+    }
+
+    async fn transform(&self, uri: &str, text: &str) -> McpResult<String> {
+        let mut records = parse_csv(text).into_iter();
+        let Some(headers) = records.next() else {
+            return Ok("[]".to_string());
+        };
+        let mut rows = Vec::new();
+        for (line, record) in records.enumerate() {
+            if record.len() > headers.len() {
+                return Err(McpError::InvalidResource {
+                    uri: uri.to_string(),
+                    message: format!(
+                        "Row {} has {} fields but the header has {}",
+                        line + 1,
+                        record.len(),
+                        headers.len()
+                    ),
+                });
+            }
+            let mut fields = record.into_iter();
+            let row: serde_json::Map<String, serde_json::Value> = headers
+                .iter()
+                .map(|header| {
+                    let value = fields.next().map_or(serde_json::Value::Null, Into::into);
+                    (header.clone(), value)
+                })
+                .collect();
+            rows.push(serde_json::Value::Object(row));
+        }
+        Ok(serde_json::to_string(&rows)?)
+    }
+}
+
+/// Records of RFC 4180 CSV, skipping blank lines
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                if !record.is_empty() || !field.is_empty() {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if !record.is_empty() || !field.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Block of Markdown being collected
+enum Block {
+    None,
+    Paragraph(Vec<String>),
+    Quote(Vec<String>),
+    List(&'static str),
+    Code(Vec<String>),
+}
+
+fn close_block(html: &mut String, block: &mut Block) {
+    match std::mem::replace(block, Block::None) {
+        Block::None => {}
+        Block::Paragraph(lines) => {
+            html.push_str(&format!("<p>{}</p>\n", markdown_inline(&lines.join(" "))));
+        }
+        Block::Quote(lines) => html.push_str(&format!(
+            "<blockquote><p>{}</p></blockquote>\n",
+            markdown_inline(&lines.join(" "))
+        )),
+        Block::List(tag) => html.push_str(&format!("</{}>\n", tag)),
+        Block::Code(lines) => html.push_str(&format!(
+            "<pre><code>{}</code></pre>\n",
+            escape_html(&lines.join("\n"))
+        )),
+    }
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut block = Block::None;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Block::Code(lines) = &mut block {
+            if trimmed.starts_with("```") {
+                close_block(&mut html, &mut block);
+            } else {
+                lines.push(line.to_string());
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") {
+            close_block(&mut html, &mut block);
+            block = Block::Code(Vec::new());
+        } else if trimmed.is_empty() {
+            close_block(&mut html, &mut block);
+        } else if let Some((level, title)) = heading(trimmed) {
+            close_block(&mut html, &mut block);
+            html.push_str(&format!(
+                "<h{}>{}</h{}>\n",
+                level,
+                markdown_inline(title),
+                level
+            ));
+        } else if matches!(trimmed, "---" | "***" | "___") {
+            close_block(&mut html, &mut block);
+            html.push_str("<hr>\n");
+        } else if let Some((tag, item)) = list_item(trimmed) {
+            if !matches!(block, Block::List(current) if current == tag) {
+                close_block(&mut html, &mut block);
+                html.push_str(&format!("<{}>\n", tag));
+                block = Block::List(tag);
+            }
+            html.push_str(&format!("<li>{}</li>\n", markdown_inline(item)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            if !matches!(block, Block::Quote(_)) {
+                close_block(&mut html, &mut block);
+                block = Block::Quote(Vec::new());
+            }
+            if let Block::Quote(lines) = &mut block {
+                lines.push(quote.trim().to_string());
+            }
+        } else {
+            if !matches!(block, Block::Paragraph(_)) {
+                close_block(&mut html, &mut block);
+                block = Block::Paragraph(Vec::new());
+            }
+            if let Block::Paragraph(lines) = &mut block {
+                lines.push(trimmed.to_string());
+            }
+        }
+    }
+    close_block(&mut html, &mut block);
+    html
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| (level, title.trim_end_matches('#').trim()))
+}
+
+fn list_item(line: &str) -> Option<(&'static str, &str)> {
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(marker) {
+            return Some(("ul", item));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let item = line[digits..].strip_prefix(". ")?;
+    (digits > 0).then_some(("ol", item))
+}
+
+/// Code spans, strong and emphasized text, images and links
+fn markdown_inline(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some((converted, consumed)) = inline_span(rest, &html) {
+            html.push_str(&converted);
+            rest = &rest[consumed..];
+        } else {
+            html.push_str(&escape_html(&rest[..c.len_utf8()]));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    html
+}
+
+/// Inline span at the start of `text` as HTML, with the bytes it used
+fn inline_span(text: &str, preceding: &str) -> Option<(String, usize)> {
+    if let Some(code) = text.strip_prefix('`') {
+        let end = code.find('`')?;
+        let html = format!("<code>{}</code>", escape_html(&code[..end]));
+        return Some((html, end + 2));
+    }
+    if let Some((label, target, consumed)) = link(text) {
+        let html = match label.strip_prefix("![") {
+            Some(alt) => format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                escape_html(target),
+                escape_html(alt)
+            ),
+            None => format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(target),
+                markdown_inline(&label[1..])
+            ),
+        };
+        return Some((html, consumed));
+    }
+    for (marker, tag) in [("**", "strong"), ("__", "strong"), ("*", "em"), ("_", "em")] {
+        let Some(inner) = text.strip_prefix(marker) else {
+            continue;
+        };
+        // Underscores inside words are literal
+        if marker.starts_with('_') && preceding.chars().last().is_some_and(char::is_alphanumeric) {
+            return None;
+        }
+        if let Some(end) = inner.find(marker).filter(|&end| end > 0) {
+            let html = format!("<{}>{}</{}>", tag, markdown_inline(&inner[..end]), tag);
+            return Some((html, end + 2 * marker.len()));
+        }
+    }
+    None
+}
+
+/// `[label](target)` or `![alt](target)` at the start of `text`
+///
+/// Returns the label with its opening bracket, the target and the bytes used.
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let open = if text.starts_with("![") {
+        2
+    } else if text.starts_with('[') {
+        1
+    } else {
+        return None;
+    };
+    let close = open + text[open..].find("](")?;
+    let end = close + 2 + text[close + 2..].find(')')?;
+    Some((&text[..close], &text[close + 2..end], end + 1))
+}
+
+fn html_to_markdown(html: &str) -> String {
+    let mut markdown = String::new();
+    let mut links: Vec<Option<String>> = Vec::new();
+    let mut lists: Vec<Option<usize>> = Vec::new();
+    let mut in_pre = false;
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = decode_entities(&rest[..end]);
+            if in_pre {
+                markdown.push_str(&text);
+            } else {
+                let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                let at_line_start = markdown.is_empty() || markdown.ends_with('\n');
+                if !collapsed.is_empty() {
+                    if text.starts_with(char::is_whitespace) && !at_line_start {
+                        markdown.push(' ');
+                    }
+                    markdown.push_str(&collapsed);
+                    if text.ends_with(char::is_whitespace) {
+                        markdown.push(' ');
+                    }
+                }
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            markdown.push_str(&decode_entities(rest));
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match (name.as_str(), closing) {
+            ("script" | "style" | "head", false) => {
+                let close = format!("</{}", name);
+                rest = rest
+                    .to_ascii_lowercase()
+                    .find(&close)
+                    .and_then(|at| rest[at..].find('>').map(|end| &rest[at + end + 1..]))
+                    .unwrap_or("");
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                block_break(&mut markdown);
+                let level = name[1..].parse().unwrap_or(1);
+                markdown.push_str(&"#".repeat(level));
+                markdown.push(' ');
+            }
+            ("p" | "div" | "section" | "article" | "header" | "footer" | "main" | "table", _)
+            | ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => block_break(&mut markdown),
+            ("br", _) => {
+                trim_trailing_spaces(&mut markdown);
+                markdown.push('\n');
+            }
+            ("tr", true) => line_break(&mut markdown),
+            ("hr", _) => {
+                block_break(&mut markdown);
+                markdown.push_str("---");
+                block_break(&mut markdown);
+            }
+            ("strong" | "b", _) => markdown.push_str("**"),
+            ("em" | "i", _) => markdown.push('*'),
+            ("code", _) if !in_pre => markdown.push('`'),
+            ("pre", false) => {
+                block_break(&mut markdown);
+                markdown.push_str("```\n");
+                in_pre = true;
+            }
+            ("pre", true) => {
+                line_break(&mut markdown);
+                markdown.push_str("```");
+                block_break(&mut markdown);
+                in_pre = false;
+            }
+            ("a", false) => {
+                let href = attribute(tag, "href");
+                if href.is_some() {
+                    markdown.push('[');
+                }
+                links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = links.pop() {
+                    markdown.push_str(&format!("]({})", href));
+                }
+            }
+            ("img", _) => {
+                if let Some(src) = attribute(tag, "src") {
+                    let alt = attribute(tag, "alt").unwrap_or_default();
+                    markdown.push_str(&format!("![{}]({})", alt, src));
+                }
+            }
+            ("ul" | "ol", false) => {
+                line_break(&mut markdown);
+                lists.push((name == "ol").then_some(0));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+                if lists.is_empty() {
+                    block_break(&mut markdown);
+                }
+            }
+            ("li", false) => {
+                line_break(&mut markdown);
+                markdown.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        markdown.push_str(&format!("{}. ", number));
+                    }
+                    _ => markdown.push_str("- "),
+                }
+            }
+            ("blockquote", false) => {
+                block_break(&mut markdown);
+                markdown.push_str("> ");
+            }
+            ("blockquote", true) => block_break(&mut markdown),
+            _ => {}
+        }
+    }
+
+    let mut normalized = String::new();
+    for line in markdown.lines().map(str::trim_end) {
+        if line.is_empty() && (normalized.is_empty() || normalized.ends_with("\n\n")) {
+            continue;
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+    normalized.trim_end().to_string() + "\n"
+}
+
+fn trim_trailing_spaces(markdown: &mut String) {
+    let trimmed = markdown.trim_end_matches(' ').len();
+    markdown.truncate(trimmed);
+}
+
+fn line_break(markdown: &mut String) {
+    trim_trailing_spaces(markdown);
+    if !markdown.is_empty() && !markdown.ends_with('\n') {
+        markdown.push('\n');
+    }
+}
+
+fn block_break(markdown: &mut String) {
+    line_break(markdown);
+    if !markdown.is_empty() && !markdown.ends_with("\n\n") {
+        markdown.push('\n');
+    }
+}
+
+/// Value of the attribute `name` in the inside of a tag
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(at) = lower[from..].find(name) {
+        let start = from + at;
+        from = start + name.len();
+        let preceded = lower[..start].ends_with(char::is_whitespace);
+        let Some(value) = tag[from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !preceded {
+            continue;
+        }
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(char::is_whitespace).next().unwrap_or_default(),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        decoded.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = entity.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => code.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builtin_transformers() {
+        let markdown = "# Guide\n\nSome *emphasis*, **strong** and `code<T>` in snake_case_name.\n\n\
+                        - [Docs](https://example.com/docs)\n- two\n\n> quoted\n\n```\nlet x = 1 < 2;\n```\n";
+        assert_eq!(
+            MarkdownToHtml.transform("docs://guide", markdown).await.unwrap(),
+            "<h1>Guide</h1>\n\
+             <p>Some <em>emphasis</em>, <strong>strong</strong> and <code>code&lt;T&gt;</code> in snake_case_name.</p>\n\
+             <ul>\n<li><a href=\"https://example.com/docs\">Docs</a></li>\n<li>two</li>\n</ul>\n\
+             <blockquote><p>quoted</p></blockquote>\n\
+             <pre><code>let x = 1 &lt; 2;</code></pre>\n"
+        );
+
+        let html = "<html><head><title>x</title></head><body><h2>Notes</h2>\
+                    <p>Read <a href=\"/a?b=1&amp;c=2\">the <b>docs</b></a>&nbsp;first.</p>\
+                    <script>alert(1)</script><ol><li>one</li><li>two</li></ol>\
+                    <pre>fn main() {\n    run();\n}</pre></body></html>";
+        assert_eq!(
+            HtmlToMarkdown
+                .transform("docs://notes", html)
+                .await
+                .unwrap(),
+            "## Notes\n\nRead [the **docs**](/a?b=1&c=2) first.\n\n1. one\n2. two\n\n\
+             ```\nfn main() {\n    run();\n}\n```\n"
+        );
+
+        let csv = "id,name,notes\r\n1,Ada,\"says \"\"hi\"\", twice\"\r\n2,Bob\r\n";
+        assert_eq!(
+            CsvToJson.transform("data://people", csv).await.unwrap(),
+            r#"[{"id":"1","name":"Ada","notes":"says \"hi\", twice"},{"id":"2","name":"Bob","notes":null}]"#
+        );
+        assert!(CsvToJson
+            .transform("data://people", "a\n1,2\n")
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_format_selection() {
+        let transformers = ResourceTransformers::default();
+        let select = |source: &str, accept: &str| {
+            transformers
+                .select("docs://guide", source, accept)
+                .map(|transformer| transformer.map(|t| t.target().to_string()))
+        };
+        assert_eq!(
+            select("text/markdown", "html").unwrap().as_deref(),
+            Some("text/html")
+        );
+        assert_eq!(
+            select(
+                "text/csv; charset=utf-8",
+                "application/xml, application/json;q=0.5"
+            )
+            .unwrap()
+            .as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(
+            select("text/markdown", "text/markdown, text/html").unwrap(),
+            None
+        );
+        assert_eq!(select("image/png", "*/*").unwrap(), None);
+        assert!(select("image/png", "text/html").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Unix domain socket listener support
+//!
+//! Serving MCP over a Unix domain socket restricts access to local processes
+//! that can open the socket file, and lets the server identify the calling
+//! process through its kernel-provided peer credentials.
+
+use std::{
+    io::ErrorKind,
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::Path,
+};
+
+use axum::{
+    extract::connect_info::{ConnectInfo, Connected},
+    http::Extensions,
+    serve::IncomingStream,
+    Router,
+};
+use tokio::net::UnixListener;
+use tracing::{info, warn};
+
+use crate::{
+    error::{McpError, McpResult},
+    security::ClientContext,
+};
+
+/// Client metadata key for the peer user ID
+pub const PEER_UID_KEY: &str = "peer_uid";
+
+/// Client metadata key for the peer group ID
+pub const PEER_GID_KEY: &str = "peer_gid";
+
+/// Client metadata key for the peer process ID
+pub const PEER_PID_KEY: &str = "peer_pid";
+
+/// Credentials of the process connected to a Unix domain socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    /// User ID of the peer process
+    pub uid: u32,
+    /// Group ID of the peer process
+    pub gid: u32,
+    /// Process ID of the peer, if the platform reports it
+    pub pid: Option<i32>,
+}
+
+impl PeerCredentials {
+    /// Look up the peer credentials stored on a request by [`serve_uds`]
+    pub fn from_extensions(extensions: &Extensions) -> Option<Self> {
+        extensions
+            .get::<ConnectInfo<UdsConnectInfo>>()
+            .and_then(|ConnectInfo(info)| info.peer)
+    }
+
+    /// Record the credentials in the client context metadata
+    pub fn apply_to(&self, client: &mut ClientContext) {
+        client
+            .metadata
+            .insert(PEER_UID_KEY.to_string(), self.uid.to_string());
+        client
+            .metadata
+            .insert(PEER_GID_KEY.to_string(), self.gid.to_string());
+        if let Some(pid) = self.pid {
+            client
+                .metadata
+                .insert(PEER_PID_KEY.to_string(), pid.to_string());
+        }
+    }
+}
+
+/// Connection info recorded for each Unix domain socket connection
+#[derive(Debug, Clone, Copy)]
+pub struct UdsConnectInfo {
+    /// Peer credentials, if they could be read from the socket
+    pub peer: Option<PeerCredentials>,
+}
+
+impl Connected<IncomingStream<'_, UnixListener>> for UdsConnectInfo {
+    fn connect_info(stream: IncomingStream<'_, UnixListener>) -> Self {
+        let peer = match stream.io().peer_cred() {
+            Ok(cred) => Some(PeerCredentials {
+                uid: cred.uid(),
+                gid: cred.gid(),
+                pid: cred.pid(),
+            }),
+            Err(e) => {
+                warn!("Failed to read Unix socket peer credentials: {}", e);
+                None
+            }
+        };
+        Self { peer }
+    }
+}
+
+/// Bind a Unix domain socket, replacing a stale socket file at the same path
+///
+/// The socket file is created with owner-only permissions (`0600`).
+pub fn bind_uds(path: impl AsRef<Path>) -> McpResult<UnixListener> {
+    let path = path.as_ref();
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(McpError::Configuration {
+                message: format!("{} exists and is not a socket", path.display()),
+            })
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Serve an axum router over a Unix domain socket
+///
+/// Peer credentials of each connection are made available to the MCP
+/// handlers, which record them in the [`ClientContext`] metadata under
+/// [`PEER_UID_KEY`], [`PEER_GID_KEY`] and [`PEER_PID_KEY`].
+pub async fn serve_uds(path: impl AsRef<Path>, app: Router) -> McpResult<()> {
+    let listener = bind_uds(path.as_ref())?;
+    info!("MCP server listening on unix:{}", path.as_ref().display());
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<UdsConnectInfo>(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixStream,
+    };
+
+    fn socket_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("axum-mcp-{}-{}.sock", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_apply_to_client_context() {
+        let peer = PeerCredentials {
+            uid: 1000,
+            gid: 100,
+            pid: None,
+        };
+        let mut client = ClientContext::default();
+        peer.apply_to(&mut client);
+
+        assert_eq!(client.metadata.get(PEER_UID_KEY).unwrap(), "1000");
+        assert_eq!(client.metadata.get(PEER_GID_KEY).unwrap(), "100");
+        assert!(!client.metadata.contains_key(PEER_PID_KEY));
+    }
+
+    #[test]
+    fn test_bind_refuses_regular_file() {
+        let path = socket_path("regular");
+        std::fs::write(&path, b"not a socket").unwrap();
+        assert!(matches!(
+            bind_uds(&path),
+            Err(McpError::Configuration { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_serve_uds_exposes_peer_credentials() {
+        let path = socket_path("serve");
+        let app = Router::new().route(
+            "/whoami",
+            get(|extensions: Extensions| async move {
+                let peer = PeerCredentials::from_extensions(&extensions).unwrap();
+                peer.uid.to_string()
+            }),
+        );
+
+        // Bind once beforehand to exercise stale socket replacement
+        drop(bind_uds(&path).unwrap());
+        let server = tokio::spawn(serve_uds(path.clone(), app));
+
+        let mut stream = loop {
+            match UnixStream::connect(&path).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+            }
+        };
+        stream
+            .write_all(b"GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let uid = std::fs::metadata(&path).unwrap();
+        let uid = std::os::unix::fs::MetadataExt::uid(&uid);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(&uid.to_string()));
+
+        server.abort();
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,29 @@
+//! Embedded resource browser for development
+//!
+//! With the `ui` feature, `{base_path}/ui` serves a single static page that
+//! talks to the server's own JSON-RPC endpoint. It lists the live tools,
+//! prompts and resources, renders a form for each tool from its input schema
+//! and shows the results of calls. The page has no external dependencies.
+
+use axum::response::Html;
+
+/// The browser page; `{{base_path}}` is replaced with the JSON-RPC endpoint
+const PAGE: &str = include_str!("ui.html");
+
+/// Render the browser page for an MCP endpoint
+pub fn ui_page(endpoint: &str) -> Html<String> {
+    let endpoint = serde_json::to_string(endpoint).unwrap_or_else(|_| "\"/mcp\"".to_string());
+    Html(PAGE.replace("{{base_path}}", &endpoint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_page_targets_endpoint() {
+        let Html(page) = ui_page("/api/mcp");
+        assert!(page.contains(r#"const ENDPOINT = "/api/mcp";"#));
+        assert!(!page.contains("{{base_path}}"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Startup validation of a server's configuration and capabilities
+//!
+//! [`McpServer::validate`](crate::server::McpServer::validate) runs every
+//! check before the server is serving and collects the findings in one
+//! [`ValidationReport`], so a broken deployment fails with the full list of
+//! problems instead of one at a time on the requests that hit them:
+//!
+//! ```rust,ignore
+//! let server = McpServer::new(config, state);
+//! server.validate().await.into_result()?;
+//! ```
+//!
+//! Checks and their codes:
+//!
+//! - `invalid-tool-schema`, `duplicate-tool`: tool input schemas that are not
+//!   object schemas, and names listed twice
+//! - prompt lint codes (see [`PromptLinter`](crate::server::PromptLinter))
+//! - `unreachable-resource`: embedded prompt resources no registry serves
+//! - `shadowed-resource`: resource templates served by another registry,
+//!   such as a built-in scheme
+//! - `missing-service`: services required by tools or prompts (see
+//!   [`Services`](crate::server::Services))
+//! - `auth`, `config`: security and configuration settings that contradict
+//!   each other
+//! - `registry`: a registry that failed to list its contents
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use crate::{
+    error::{McpError, McpResult},
+    server::{config::McpServerConfig, prompt_lint::DiagnosticSeverity},
+};
+
+/// A problem found by startup validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationIssue {
+    /// How serious the problem is
+    pub severity: DiagnosticSeverity,
+
+    /// Check that found the problem, such as `invalid-tool-schema`
+    pub code: String,
+
+    /// What the problem is in, such as `tool 'search'` or `security.cors`
+    pub subject: String,
+
+    /// Human-readable explanation
+    pub message: String,
+}
+
+impl ValidationIssue {
+    /// An issue that must be fixed before serving
+    pub fn error(code: &str, subject: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Error,
+            code: code.to_string(),
+            subject: subject.into(),
+            message: message.into(),
+        }
+    }
+
+    /// An issue that is probably a mistake but does not stop the server
+    pub fn warning(code: &str, subject: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Warning,
+            ..Self::error(code, subject, message)
+        }
+    }
+
+    /// Whether this issue is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == DiagnosticSeverity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}: {} [{}]: {}",
+            severity, self.subject, self.code, self.message
+        )
+    }
+}
+
+/// Findings of startup validation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// Problems found, in the order the checks ran
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Add an issue
+    pub fn push(&mut self, issue: ValidationIssue) {
+        self.issues.push(issue);
+    }
+
+    /// Whether no errors were found; warnings are allowed
+    pub fn is_ok(&self) -> bool {
+        !self.issues.iter().any(ValidationIssue::is_error)
+    }
+
+    /// Issues that must be fixed before serving
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| issue.is_error())
+    }
+
+    /// Issues that do not stop the server
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|issue| !issue.is_error())
+    }
+
+    /// Fail with the whole report if it contains errors
+    pub fn into_result(self) -> McpResult<Self> {
+        if self.is_ok() {
+            Ok(self)
+        } else {
+            Err(McpError::Configuration {
+                message: format!(
+                    "Server validation failed with {} error(s):\n{}",
+                    self.errors().count(),
+                    self
+                ),
+            })
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Problems with a tool's input schema
+///
+/// MCP requires an object schema; `properties` and `required` must be
+/// well-formed and required arguments should be declared.
+pub fn check_tool_schema(name: &str, schema: &Value) -> Vec<ValidationIssue> {
+    let subject = format!("tool '{}'", name);
+    let Some(schema) = schema.as_object() else {
+        return vec![ValidationIssue::error(
+            "invalid-tool-schema",
+            subject,
+            "Input schema is not a JSON object",
+        )];
+    };
+
+    let mut issues = Vec::new();
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        issues.push(ValidationIssue::error(
+            "invalid-tool-schema",
+            subject.clone(),
+            "Input schema must have type \"object\"",
+        ));
+    }
+    let properties = match schema.get("properties") {
+        None => None,
+        Some(Value::Object(properties)) => Some(properties),
+        Some(_) => {
+            issues.push(ValidationIssue::error(
+                "invalid-tool-schema",
+                subject.clone(),
+                "`properties` is not an object",
+            ));
+            None
+        }
+    };
+    match schema.get("required") {
+        None => {}
+        Some(Value::Array(required)) => {
+            for field in required {
+                match field.as_str() {
+                    Some(field) if properties.is_some_and(|p| p.contains_key(field)) => {}
+                    Some(field) => issues.push(ValidationIssue::warning(
+                        "invalid-tool-schema",
+                        subject.clone(),
+                        format!("Required argument `{}` is not a declared property", field),
+                    )),
+                    None => issues.push(ValidationIssue::error(
+                        "invalid-tool-schema",
+                        subject.clone(),
+                        "`required` contains a non-string entry",
+                    )),
+                }
+            }
+        }
+        Some(_) => issues.push(ValidationIssue::error(
+            "invalid-tool-schema",
+            subject,
+            "`required` is not an array",
+        )),
+    }
+    issues
+}
+
+/// Security settings that contradict each other
+pub fn check_security(config: &McpServerConfig) -> Vec<ValidationIssue> {
+    let security = &config.security;
+    let mut issues = Vec::new();
+    let cors = &security.cors;
+    if cors.enabled && cors.allow_credentials && cors.allowed_origins.iter().any(|o| o == "*") {
+        issues.push(ValidationIssue::error(
+            "auth",
+            "security.cors",
+            "Credentials cannot be allowed for the wildcard origin '*'",
+        ));
+    }
+    if security.rate_limit.enabled && security.rate_limit.max_requests == 0 {
+        issues.push(ValidationIssue::error(
+            "auth",
+            "security.rate_limit",
+            "Rate limiting is enabled with max_requests = 0, which rejects every request",
+        ));
+    }
+    if security.quota.enabled && !security.require_auth {
+        issues.push(ValidationIssue::warning(
+            "auth",
+            "security.quota",
+            "Quotas are enabled without require_auth, so anonymous clients share one quota",
+        ));
+    }
+    issues
+}
+
+/// Configuration values that are out of range or inconsistent
+pub fn check_config(config: &McpServerConfig) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if !config.base_path.starts_with('/') {
+        issues.push(ValidationIssue::error(
+            "config",
+            "base_path",
+            format!("Base path '{}' must start with '/'", config.base_path),
+        ));
+    }
+    if config.request_timeout.is_zero() {
+        issues.push(ValidationIssue::error(
+            "config",
+            "request_timeout",
+            "Request timeout is zero",
+        ));
+    }
+    if config.enable_batch && config.max_batch_size == 0 {
+        issues.push(ValidationIssue::error(
+            "config",
+            "max_batch_size",
+            "Batching is enabled with a maximum batch size of 0",
+        ));
+    }
+    if config
+        .sse_response_threshold
+        .is_some_and(|threshold| threshold >= config.request_timeout)
+    {
+        issues.push(ValidationIssue::warning(
+            "config",
+            "sse_response_threshold",
+            "SSE response threshold is not below the request timeout, so calls never stream",
+        ));
+    }
+    for (rule, reason) in config.scrub.invalid_rules() {
+        issues.push(ValidationIssue::error(
+            "config",
+            "scrub",
+            format!("Invalid scrub rule '{}': {}", rule, reason),
+        ));
+    }
+    if config.scheduler.enabled && config.scheduler.max_concurrent == 0 {
+        issues.push(ValidationIssue::error(
+            "config",
+            "scheduler.max_concurrent",
+            "Scheduling is enabled with no concurrent slots, so heavy requests never run",
+        ));
+    }
+    if config.admission.enabled && config.admission.max_in_flight == 0 {
+        issues.push(ValidationIssue::error(
+            "config",
+            "admission.max_in_flight",
+            "Admission control is enabled with max_in_flight = 0, which rejects every call",
+        ));
+    }
+    if config.session.cleanup_interval > config.session.timeout {
+        issues.push(ValidationIssue::warning(
+            "config",
+            "session.cleanup_interval",
+            "Sessions are cleaned up less often than they time out",
+        ));
+    }
+    if let Some(tls) = &config.tls {
+        for (field, path) in [
+            ("tls.cert_path", &tls.cert_path),
+            ("tls.key_path", &tls.key_path),
+        ] {
+            if !path.exists() {
+                issues.push(ValidationIssue::error(
+                    "config",
+                    field,
+                    format!("{} does not exist", path.display()),
+                ));
+            }
+        }
+    }
+    for (index, endpoint) in config.webhooks.endpoints.iter().enumerate() {
+        if let Err(e) = url::Url::parse(&endpoint.url) {
+            issues.push(ValidationIssue::error(
+                "config",
+                format!("webhooks.endpoints[{}]", index),
+                format!("Invalid URL '{}': {}", endpoint.url, e),
+            ));
+        }
+    }
+    if let Some(url) = &config.approval.webhook_url {
+        if let Err(e) = url::Url::parse(url) {
+            issues.push(ValidationIssue::error(
+                "config",
+                "approval.webhook_url",
+                format!("Invalid URL '{}': {}", url, e),
+            ));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<(&str, &str)> {
+        issues
+            .iter()
+            .map(|issue| (issue.code.as_str(), issue.subject.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_check_tool_schema() {
+        let valid = serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}},
+            "required": ["query"]
+        });
+        assert!(check_tool_schema("search", &valid).is_empty());
+
+        let issues = check_tool_schema(
+            "search",
+            &serde_json::json!({"type": "array", "required": ["query", 1]}),
+        );
+        assert_eq!(issues.len(), 3);
+        assert!(issues[1]
+            .message
+            .contains("`query` is not a declared property"));
+        assert!(!issues[1].is_error());
+        assert_eq!(check_tool_schema("search", &Value::Null).len(), 1);
+    }
+
+    #[test]
+    fn test_check_config() {
+        let mut config = McpServerConfig::default();
+        assert!(check_config(&config).is_empty());
+        assert!(check_security(&config).is_empty());
+
+        config.base_path = "mcp".to_string();
+        config.max_batch_size = 0;
+        config.security.cors.allow_credentials = true;
+        config
+            .webhooks
+            .endpoints
+            .push(crate::server::WebhookConfig::new("not a url"));
+        assert_eq!(
+            codes(&check_config(&config)),
+            [
+                ("config", "base_path"),
+                ("config", "max_batch_size"),
+                ("config", "webhooks.endpoints[0]"),
+            ]
+        );
+        assert_eq!(codes(&check_security(&config)), [("auth", "security.cors")]);
+
+        let mut report = ValidationReport::default();
+        report.issues.extend(check_security(&config));
+        let error = report.into_result().unwrap_err().to_string();
+        assert!(error.contains("1 error(s)"));
+        assert!(error.contains("error: security.cors [auth]"));
+    }
+}
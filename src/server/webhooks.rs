@@ -0,0 +1,493 @@
+//! Outbound webhooks for server events
+//!
+//! Operators can have server events POSTed to any HTTP endpoint (Slack and
+//! PagerDuty integrations, incident bots, ...) from configuration alone:
+//!
+//! - `server.started` once [`WebhookDispatcher::server_started`] is called
+//! - `session.opened` / `session.closed` for StreamableHTTP sessions
+//! - `tool.failed` when a tool fails `tool_failure_threshold` times in a row
+//! - `resource.changed` via [`McpServer::resource_changed`](crate::server::McpServer::resource_changed)
+//!
+//! Deliveries run in the background with exponential backoff. When a secret is
+//! configured each attempt carries `X-MCP-Timestamp: <unix seconds>` and
+//! `X-MCP-Signature: sha256=<digest>`, the hex HMAC-SHA256 of
+//! `{timestamp}.{body}`, so receivers can reject stale or replayed deliveries.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+/// Header carrying the HMAC-SHA256 signature of `{timestamp}.{body}`
+pub const SIGNATURE_HEADER: &str = "x-mcp-signature";
+
+/// Header carrying the signing time in Unix seconds
+pub const TIMESTAMP_HEADER: &str = "x-mcp-timestamp";
+
+/// Header carrying the event kind
+pub const EVENT_HEADER: &str = "x-mcp-event";
+
+/// Header carrying the delivery ID, stable across retries
+pub const DELIVERY_HEADER: &str = "x-mcp-delivery";
+
+/// Kinds of events webhooks can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventKind {
+    #[serde(rename = "server.started")]
+    ServerStarted,
+    #[serde(rename = "session.opened")]
+    SessionOpened,
+    #[serde(rename = "session.closed")]
+    SessionClosed,
+    #[serde(rename = "tool.failed")]
+    ToolFailed,
+    #[serde(rename = "resource.changed")]
+    ResourceChanged,
+}
+
+impl WebhookEventKind {
+    /// Name used on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventKind::ServerStarted => "server.started",
+            WebhookEventKind::SessionOpened => "session.opened",
+            WebhookEventKind::SessionClosed => "session.closed",
+            WebhookEventKind::ToolFailed => "tool.failed",
+            WebhookEventKind::ResourceChanged => "resource.changed",
+        }
+    }
+}
+
+/// Retry policy for failed deliveries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Delivery attempts including the first
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, doubled after every attempt
+    #[serde(with = "humantime_serde")]
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts
+    #[serde(with = "humantime_serde")]
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_backoff)
+    }
+}
+
+/// A webhook endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL events are POSTed to
+    pub url: String,
+
+    /// Secret used to sign deliveries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+
+    /// Events to deliver; all events when empty
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+
+    /// Retry policy for failed deliveries
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+impl WebhookConfig {
+    /// Deliver all events to `url`
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            secret: None,
+            events: Vec::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Sign deliveries with `secret`
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    /// Only deliver the given events
+    pub fn with_events(mut self, events: Vec<WebhookEventKind>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Set the retry policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Whether this endpoint wants an event
+    pub fn accepts(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Webhook configuration of a server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    /// Endpoints events are delivered to
+    #[serde(default)]
+    pub endpoints: Vec<WebhookConfig>,
+
+    /// Consecutive failures of a tool that fire `tool.failed`
+    #[serde(default = "default_tool_failure_threshold")]
+    pub tool_failure_threshold: u32,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            tool_failure_threshold: default_tool_failure_threshold(),
+        }
+    }
+}
+
+fn default_tool_failure_threshold() -> u32 {
+    3
+}
+
+/// Body of a webhook delivery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    /// Delivery identifier
+    pub id: String,
+
+    /// Event kind
+    pub event: WebhookEventKind,
+
+    /// Name of the server that fired the event
+    pub server: String,
+
+    /// When the event happened
+    pub timestamp: DateTime<Utc>,
+
+    /// Event details
+    pub data: serde_json::Value,
+}
+
+/// Fires webhook events for a server
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    server: String,
+    config: Arc<WebhooksConfig>,
+    http: reqwest::Client,
+    tool_failures: Arc<Mutex<HashMap<String, u32>>>,
+}
+
+impl std::fmt::Debug for WebhookDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookDispatcher")
+            .field("server", &self.server)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher for the named server
+    pub fn new(server: impl Into<String>, config: WebhooksConfig) -> Self {
+        Self {
+            server: server.into(),
+            config: Arc::new(config),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            tool_failures: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Webhook configuration
+    pub fn config(&self) -> &WebhooksConfig {
+        &self.config
+    }
+
+    /// Fire `server.started`; call once the listener is bound
+    pub fn server_started(&self, address: impl Into<String>) {
+        self.emit(
+            WebhookEventKind::ServerStarted,
+            serde_json::json!({ "address": address.into() }),
+        );
+    }
+
+    /// Count the outcome of a tool call, firing `tool.failed` when a tool
+    /// reaches the configured number of consecutive failures
+    pub fn tool_outcome(&self, tool: &str, failed: bool, error: Option<&str>) {
+        let failures = {
+            let mut counts = self.tool_failures.lock().unwrap_or_else(|e| e.into_inner());
+            if !failed {
+                counts.remove(tool);
+                return;
+            }
+            let count = counts.entry(tool.to_string()).or_insert(0);
+            *count += 1;
+            *count
+        };
+
+        if failures == self.config.tool_failure_threshold.max(1) {
+            self.emit(
+                WebhookEventKind::ToolFailed,
+                serde_json::json!({
+                    "tool": tool,
+                    "consecutiveFailures": failures,
+                    "lastError": error,
+                }),
+            );
+        }
+    }
+
+    /// Deliver an event to every endpoint that wants it
+    ///
+    /// Deliveries run in the background; this never blocks on the network.
+    pub fn emit(&self, kind: WebhookEventKind, data: serde_json::Value) {
+        let endpoints: Vec<_> = self
+            .config
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.accepts(kind))
+            .cloned()
+            .collect();
+        if endpoints.is_empty() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!("Dropping {} webhook: no async runtime", kind.as_str());
+            return;
+        };
+
+        let event = WebhookEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            event: kind,
+            server: self.server.clone(),
+            timestamp: Utc::now(),
+            data,
+        };
+        let body = match serde_json::to_vec(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize {} webhook: {}", kind.as_str(), e);
+                return;
+            }
+        };
+
+        for endpoint in endpoints {
+            let http = self.http.clone();
+            let body = body.clone();
+            let event_id = event.id.clone();
+            runtime.spawn(async move {
+                deliver(&http, &endpoint, kind, &event_id, body).await;
+            });
+        }
+    }
+}
+
+/// POST a delivery, retrying on network errors, 429 and 5xx responses
+async fn deliver(
+    http: &reqwest::Client,
+    endpoint: &WebhookConfig,
+    kind: WebhookEventKind,
+    event_id: &str,
+    body: Vec<u8>,
+) {
+    for attempt in 1..=endpoint.retry.max_attempts.max(1) {
+        let mut request = http
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, kind.as_str())
+            .header(DELIVERY_HEADER, event_id)
+            .body(body.clone());
+        if let Some(secret) = &endpoint.secret {
+            // Signed per attempt so retries are not rejected as stale
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .to_string();
+            let signature = sign(secret.as_bytes(), &signed_payload(&timestamp, &body));
+            request = request
+                .header(TIMESTAMP_HEADER, &timestamp)
+                .header(SIGNATURE_HEADER, format!("sha256={}", signature));
+        }
+
+        let retryable = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered {} webhook {}", kind.as_str(), event_id);
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    "{} webhook {} rejected by {}: {}",
+                    kind.as_str(),
+                    event_id,
+                    endpoint.url,
+                    status
+                );
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => {
+                warn!(
+                    "{} webhook {} to {} failed: {}",
+                    kind.as_str(),
+                    event_id,
+                    endpoint.url,
+                    e
+                );
+                true
+            }
+        };
+
+        if !retryable || attempt == endpoint.retry.max_attempts {
+            break;
+        }
+        tokio::time::sleep(endpoint.retry.backoff(attempt)).await;
+    }
+    warn!("Giving up on {} webhook {}", kind.as_str(), event_id);
+}
+
+/// The bytes a delivery signature covers: `{timestamp}.{body}`
+pub fn signed_payload(timestamp: &str, body: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(timestamp.len() + 1 + body.len());
+    payload.extend_from_slice(timestamp.as_bytes());
+    payload.push(b'.');
+    payload.extend_from_slice(body);
+    payload
+}
+
+/// Hex encoded HMAC-SHA256 of `message`
+pub fn sign(secret: &[u8], message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Router};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_sign_matches_rfc_4231() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_are_signed_and_retried() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let attempts = Arc::new(Mutex::new(0));
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let tx = tx.clone();
+                let attempts = attempts.clone();
+                async move {
+                    let attempt = {
+                        let mut attempts = attempts.lock().unwrap();
+                        *attempts += 1;
+                        *attempts
+                    };
+                    if attempt == 1 {
+                        return axum::http::StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    tx.send((headers, body)).unwrap();
+                    axum::http::StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dispatcher = WebhookDispatcher::new(
+            "Test Server",
+            WebhooksConfig {
+                endpoints: vec![WebhookConfig::new(url)
+                    .with_secret("s3cret")
+                    .with_events(vec![WebhookEventKind::ToolFailed])
+                    .with_retry(RetryPolicy {
+                        max_attempts: 3,
+                        initial_backoff: Duration::from_millis(10),
+                        max_backoff: Duration::from_millis(10),
+                    })],
+                tool_failure_threshold: 2,
+            },
+        );
+
+        // Filtered out, and below the failure threshold
+        dispatcher.server_started("127.0.0.1:0");
+        dispatcher.tool_outcome("deploy", true, Some("boom"));
+        dispatcher.tool_outcome("deploy", false, None);
+        dispatcher.tool_outcome("deploy", true, Some("boom"));
+        dispatcher.tool_outcome("deploy", true, Some("bang"));
+
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(headers[EVENT_HEADER], "tool.failed");
+        let timestamp = headers[TIMESTAMP_HEADER].to_str().unwrap();
+        let signed_at: u64 = timestamp.parse().unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(now.abs_diff(signed_at) <= 5);
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            format!(
+                "sha256={}",
+                sign(b"s3cret", &signed_payload(timestamp, &body))
+            )
+        );
+        // The body alone no longer verifies
+        assert_ne!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            format!("sha256={}", sign(b"s3cret", &body))
+        );
+        let event: WebhookEvent = serde_json::from_slice(&body).unwrap();
+        assert_eq!(event.server, "Test Server");
+        assert_eq!(event.data["consecutiveFailures"], 2);
+        assert_eq!(event.data["lastError"], "bang");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,451 @@
+//! Wire-level tap of JSON-RPC frames
+//!
+//! For client interop problems that the [request inspector](super::inspector)
+//! cannot explain, the tap mirrors every frame of a session as it crosses the
+//! wire: requests and responses, notifications and requests sent to the
+//! client, and the client's responses to them. Frames are redacted like the
+//! inspector's and written as JSON lines, one [`TapFrame`] per line, either
+//! to a file rotated by size or as `debug` events on the [`WIRE_TAP_TARGET`]
+//! tracing target with the session as a field.
+//!
+//! The tap is off for every session until switched on, from code with
+//! [`WireTap::set_session`] or by a system context calling `admin/wire_tap`:
+//!
+//! ```json
+//! {"sessionId": "4f1c...", "enabled": true}
+//! ```
+//!
+//! Without `sessionId`, `enabled` switches the tap for all sessions,
+//! including stdio clients that have none.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing::warn;
+
+use crate::server::{
+    inspector::{self, InspectorConfig},
+    scrub::Scrubber,
+};
+
+/// Tracing target tapped frames are logged to
+pub const WIRE_TAP_TARGET: &str = "axum_mcp::wire";
+
+/// Switches the wire tap for a session or all sessions
+pub const ADMIN_WIRE_TAP: &str = "admin/wire_tap";
+
+/// Where tapped frames go
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TapSink {
+    /// `debug` events on [`WIRE_TAP_TARGET`]
+    Tracing,
+
+    /// JSON lines appended to `path`, which is renamed to `path.1` once it
+    /// would grow past `max_bytes`; the `max_files` newest rotated files are
+    /// kept
+    File {
+        path: PathBuf,
+        #[serde(default = "default_max_bytes")]
+        max_bytes: u64,
+        #[serde(default = "default_max_files")]
+        max_files: usize,
+    },
+}
+
+fn default_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_max_files() -> usize {
+    5
+}
+
+/// Wire tap configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WireTapConfig {
+    /// Whether every session is tapped from the start
+    pub all_sessions: bool,
+
+    /// Where tapped frames go
+    pub sink: TapSink,
+
+    /// Object keys whose values are redacted, as for the inspector
+    pub redact_keys: Vec<String>,
+}
+
+impl Default for WireTapConfig {
+    fn default() -> Self {
+        Self {
+            all_sessions: false,
+            sink: TapSink::Tracing,
+            redact_keys: InspectorConfig::default().redact_keys,
+        }
+    }
+}
+
+impl WireTapConfig {
+    /// Write tapped frames to `path`, rotated at 10 MiB keeping 5 files
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            sink: TapSink::File {
+                path: path.into(),
+                max_bytes: default_max_bytes(),
+                max_files: default_max_files(),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Rotate the tap file at `max_bytes`, keeping `max_files` rotated files
+    ///
+    /// Has no effect on the tracing sink.
+    pub fn with_rotation(mut self, max_bytes: u64, max_files: usize) -> Self {
+        if let TapSink::File {
+            max_bytes: bytes,
+            max_files: files,
+            ..
+        } = &mut self.sink
+        {
+            *bytes = max_bytes;
+            *files = max_files;
+        }
+        self
+    }
+
+    /// Tap every session from the start
+    pub fn all_sessions(mut self) -> Self {
+        self.all_sessions = true;
+        self
+    }
+
+    /// Also redact values under `key`
+    pub fn redact_key(mut self, key: impl Into<String>) -> Self {
+        self.redact_keys.push(key.into());
+        self
+    }
+}
+
+/// Which way a frame crossed the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameDirection {
+    /// From the client to the server
+    Inbound,
+    /// From the server to the client
+    Outbound,
+}
+
+/// A tapped frame, one line of the tap output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TapFrame {
+    /// When the frame was tapped
+    pub at: DateTime<Utc>,
+
+    /// Session the frame belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+
+    /// Which way the frame went
+    pub direction: FrameDirection,
+
+    /// The JSON-RPC message, redacted
+    pub frame: Value,
+}
+
+/// Mirrors the frames of tapped sessions to the configured sink
+#[derive(Clone)]
+pub struct WireTap {
+    all_sessions: Arc<AtomicBool>,
+    sessions: Arc<Mutex<HashSet<String>>>,
+    redact_keys: Arc<Vec<String>>,
+    scrubber: Option<Scrubber>,
+    file: Option<Arc<Mutex<RotatingFile>>>,
+}
+
+impl std::fmt::Debug for WireTap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WireTap")
+            .field("all_sessions", &self.all_sessions)
+            .field("sessions", &self.sessions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WireTap {
+    /// Create a tap, switched on for every session if configured so
+    pub fn new(config: WireTapConfig) -> Self {
+        let file = match config.sink {
+            TapSink::Tracing => None,
+            TapSink::File {
+                path,
+                max_bytes,
+                max_files,
+            } => Some(Arc::new(Mutex::new(RotatingFile::new(
+                path, max_bytes, max_files,
+            )))),
+        };
+        Self {
+            all_sessions: Arc::new(AtomicBool::new(config.all_sessions)),
+            sessions: Default::default(),
+            redact_keys: Arc::new(
+                config
+                    .redact_keys
+                    .iter()
+                    .map(|key| inspector::normalize(key))
+                    .collect(),
+            ),
+            scrubber: None,
+            file,
+        }
+    }
+
+    /// Also scrub personal data from tapped frames with `scrubber`
+    pub fn with_scrubber(mut self, scrubber: Scrubber) -> Self {
+        self.scrubber = Some(scrubber);
+        self
+    }
+
+    /// Whether frames of `session_id` are tapped
+    pub fn is_tapped(&self, session_id: Option<&str>) -> bool {
+        self.all_sessions.load(Ordering::Relaxed)
+            || session_id.is_some_and(|session_id| self.lock().contains(session_id))
+    }
+
+    /// Switch the tap for `session_id` on or off
+    pub fn set_session(&self, session_id: impl Into<String>, enabled: bool) {
+        let session_id = session_id.into();
+        let mut sessions = self.lock();
+        if enabled {
+            sessions.insert(session_id);
+        } else {
+            sessions.remove(&session_id);
+        }
+    }
+
+    /// Switch the tap for all sessions on or off
+    ///
+    /// Switching it off also forgets the sessions tapped one by one.
+    pub fn set_all_sessions(&self, enabled: bool) {
+        self.all_sessions.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.lock().clear();
+        }
+    }
+
+    /// Whether every session is tapped and which are tapped one by one
+    pub fn status(&self) -> WireTapStatus {
+        let mut sessions: Vec<String> = self.lock().iter().cloned().collect();
+        sessions.sort_unstable();
+        WireTapStatus {
+            all_sessions: self.all_sessions.load(Ordering::Relaxed),
+            sessions,
+        }
+    }
+
+    /// Mirror `frame`, if its session is tapped
+    pub fn record(&self, session_id: Option<&str>, direction: FrameDirection, frame: &Value) {
+        if !self.is_tapped(session_id) {
+            return;
+        }
+        let mut frame = inspector::redact(frame, &self.redact_keys);
+        if let Some(scrubber) = &self.scrubber {
+            scrubber.scrub_strings(&mut frame);
+        }
+        let frame = TapFrame {
+            at: Utc::now(),
+            session_id: session_id.map(str::to_string),
+            direction,
+            frame,
+        };
+
+        match &self.file {
+            None => tracing::debug!(
+                target: WIRE_TAP_TARGET,
+                session = frame.session_id.as_deref().unwrap_or_default(),
+                direction = ?frame.direction,
+                frame = %frame.frame,
+                "MCP frame"
+            ),
+            Some(file) => {
+                let line = serde_json::to_string(&frame).unwrap_or_default();
+                let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                if let Err(e) = file.write_line(&line) {
+                    warn!(
+                        "Failed to write tapped frame to {}: {}",
+                        file.path.display(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for WireTap {
+    fn default() -> Self {
+        Self::new(WireTapConfig::default())
+    }
+}
+
+/// Which sessions the tap is on for, as returned by `admin/wire_tap`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireTapStatus {
+    /// Whether every session is tapped
+    pub all_sessions: bool,
+
+    /// Sessions tapped one by one
+    pub sessions: Vec<String>,
+}
+
+/// Parameters of `admin/wire_tap`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WireTapParams {
+    /// Session to switch the tap for; all sessions if absent
+    #[serde(default)]
+    pub session_id: Option<String>,
+
+    /// Switch the tap on or off; only report its status if absent
+    #[serde(default)]
+    pub enabled: Option<bool>,
+}
+
+/// JSONL file renamed aside once it reaches its size limit
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn new(path: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            max_files,
+            file: None,
+            written: 0,
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let size = line.len() as u64 + 1;
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if self.written > 0 && self.written + size > self.max_bytes {
+            self.rotate()?;
+        }
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{}", line)?;
+            self.written += size;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Shift `path.N` to `path.N+1`, dropping the oldest, and start afresh
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                let from = rotated(&self.path, index);
+                if from.exists() {
+                    fs::rename(from, rotated(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        self.open()
+    }
+}
+
+/// `path` with `.{index}` appended
+fn rotated(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_taps_sessions_to_rotating_file() {
+        let dir = std::env::temp_dir().join(format!("wire-tap-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wire.jsonl");
+        let tap = WireTap::new(WireTapConfig::file(&path).with_rotation(200, 1));
+        let request = json!({"jsonrpc": "2.0", "method": "tools/call", "id": 1,
+            "params": {"name": "login", "arguments": {"password": "hunter2"}}});
+
+        tap.record(Some("a"), FrameDirection::Inbound, &request);
+        assert!(!path.exists());
+
+        tap.set_session("a", true);
+        assert!(tap.is_tapped(Some("a")) && !tap.is_tapped(Some("b")));
+        for _ in 0..3 {
+            tap.record(Some("a"), FrameDirection::Inbound, &request);
+            tap.record(Some("b"), FrameDirection::Inbound, &request);
+        }
+
+        let current = fs::read_to_string(&path).unwrap();
+        let frame: TapFrame = serde_json::from_str(current.lines().next().unwrap()).unwrap();
+        assert_eq!(frame.session_id.as_deref(), Some("a"));
+        assert_eq!(frame.direction, FrameDirection::Inbound);
+        assert_eq!(
+            frame.frame["params"]["arguments"]["password"],
+            inspector::REDACTED
+        );
+        assert!(rotated(&path, 1).exists());
+        assert!(!rotated(&path, 2).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        tap.set_all_sessions(true);
+        assert!(tap.is_tapped(None));
+        tap.set_all_sessions(false);
+        assert_eq!(
+            tap.status(),
+            WireTapStatus {
+                all_sessions: false,
+                sessions: Vec::new(),
+            }
+        );
+    }
+}
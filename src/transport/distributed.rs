@@ -0,0 +1,242 @@
+//! Shared session state for multi-replica deployments
+//!
+//! Behind a load balancer, consecutive requests of one StreamableHTTP session
+//! may reach different replicas. A [`SessionManager`](super::SessionManager)
+//! created with [`with_backend`](super::SessionManager::with_backend) keeps
+//! each session's negotiated state, its resource subscriptions and its live
+//! events in a [`DistributedStateBackend`], so any replica can serve any
+//! session:
+//!
+//! - Session records are written through on every change and loaded by
+//!   replicas that have not seen the session yet.
+//! - Resource subscriptions are indexed by URI, so a replica can find every
+//!   session subscribed to a resource.
+//! - Events are published through the backend; SSE streams on any replica
+//!   receive the events of their session.
+//!
+//! [`InMemoryStateBackend`] shares state between managers in one process. With
+//! the `redis` feature, `RedisStateBackend` shares it through Redis.
+
+use async_trait::async_trait;
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{broadcast, RwLock};
+use tokio_stream::wrappers::BroadcastStream;
+
+use crate::{
+    error::McpResult,
+    security::ClientFingerprint,
+    transport::streamable_http::{McpEvent, SessionState},
+};
+
+/// Everything a replica needs to serve a session it did not create
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Negotiated state of the session
+    pub state: SessionState,
+
+    /// Fingerprint of the client that initialized the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<ClientFingerprint>,
+}
+
+/// Store for session records, subscriptions and live events shared by replicas
+#[async_trait]
+pub trait DistributedStateBackend: Send + Sync {
+    /// Load a session record
+    async fn load_session(&self, session_id: &str) -> McpResult<Option<SessionRecord>>;
+
+    /// Save a session record, expiring it after `ttl` without another save
+    async fn save_session(
+        &self,
+        session_id: &str,
+        record: &SessionRecord,
+        ttl: Duration,
+    ) -> McpResult<()>;
+
+    /// Remove a session record
+    async fn remove_session(&self, session_id: &str) -> McpResult<()>;
+
+    /// Record that a session subscribed to a resource
+    async fn add_subscription(&self, uri: &str, session_id: &str) -> McpResult<()>;
+
+    /// Record that a session unsubscribed from a resource
+    async fn remove_subscription(&self, uri: &str, session_id: &str) -> McpResult<()>;
+
+    /// Sessions subscribed to a resource
+    async fn subscribers(&self, uri: &str) -> McpResult<Vec<String>>;
+
+    /// Deliver an event to the session's streams on every replica
+    async fn publish_event(&self, event: &McpEvent) -> McpResult<()>;
+
+    /// Events published for a session from now on
+    async fn subscribe_events(&self, session_id: &str) -> McpResult<BoxStream<'static, McpEvent>>;
+}
+
+/// Capacity of the in-memory event channel
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Backend sharing state between session managers in one process
+///
+/// Records do not expire; removing sessions is left to the managers.
+#[derive(Clone)]
+pub struct InMemoryStateBackend {
+    sessions: Arc<RwLock<HashMap<String, SessionRecord>>>,
+    subscriptions: Arc<RwLock<HashMap<String, BTreeSet<String>>>>,
+    events: broadcast::Sender<McpEvent>,
+}
+
+impl InMemoryStateBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl Default for InMemoryStateBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl DistributedStateBackend for InMemoryStateBackend {
+    async fn load_session(&self, session_id: &str) -> McpResult<Option<SessionRecord>> {
+        Ok(self.sessions.read().await.get(session_id).cloned())
+    }
+
+    async fn save_session(
+        &self,
+        session_id: &str,
+        record: &SessionRecord,
+        _ttl: Duration,
+    ) -> McpResult<()> {
+        self.sessions
+            .write()
+            .await
+            .insert(session_id.to_string(), record.clone());
+        Ok(())
+    }
+
+    async fn remove_session(&self, session_id: &str) -> McpResult<()> {
+        self.sessions.write().await.remove(session_id);
+        Ok(())
+    }
+
+    async fn add_subscription(&self, uri: &str, session_id: &str) -> McpResult<()> {
+        self.subscriptions
+            .write()
+            .await
+            .entry(uri.to_string())
+            .or_default()
+            .insert(session_id.to_string());
+        Ok(())
+    }
+
+    async fn remove_subscription(&self, uri: &str, session_id: &str) -> McpResult<()> {
+        let mut subscriptions = self.subscriptions.write().await;
+        if let Some(sessions) = subscriptions.get_mut(uri) {
+            sessions.remove(session_id);
+            if sessions.is_empty() {
+                subscriptions.remove(uri);
+            }
+        }
+        Ok(())
+    }
+
+    async fn subscribers(&self, uri: &str) -> McpResult<Vec<String>> {
+        Ok(self
+            .subscriptions
+            .read()
+            .await
+            .get(uri)
+            .map(|sessions| sessions.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn publish_event(&self, event: &McpEvent) -> McpResult<()> {
+        // No receivers just means no stream is open for any session
+        let _ = self.events.send(event.clone());
+        Ok(())
+    }
+
+    async fn subscribe_events(&self, session_id: &str) -> McpResult<BoxStream<'static, McpEvent>> {
+        let session_id = session_id.to_string();
+        Ok(BroadcastStream::new(self.events.subscribe())
+            .filter_map(move |event| {
+                let event = event.ok().filter(|event| event.session_id == session_id);
+                std::future::ready(event)
+            })
+            .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{InMemoryEventStore, SessionManager};
+
+    fn replica(backend: &InMemoryStateBackend) -> SessionManager {
+        SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        )
+        .with_backend(Arc::new(backend.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_replicas_share_sessions_subscriptions_and_events() {
+        let backend = InMemoryStateBackend::new();
+        let first = replica(&backend);
+        let second = replica(&backend);
+
+        let session_id = first.create_session().await.unwrap();
+        first
+            .update_session_state(&session_id, |state| {
+                state.protocol_version = Some("2025-06-18".into());
+                state.subscriptions.insert("file:///a".into());
+            })
+            .await
+            .unwrap();
+
+        // The second replica has never seen the session
+        let state = second.resume_session(&session_id).await.unwrap();
+        assert_eq!(state.protocol_version.as_deref(), Some("2025-06-18"));
+        assert_eq!(
+            second.subscribers("file:///a").await.unwrap(),
+            [session_id.as_str()]
+        );
+
+        let mut live = Box::pin(first.subscribe_to_session(&session_id).await);
+        second
+            .store_event(&McpEvent::new(
+                session_id.clone(),
+                "message".into(),
+                serde_json::json!({"n": 1}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(live.next().await.unwrap().data["n"], 1);
+
+        second
+            .update_session_state(&session_id, |state| {
+                state.subscriptions.clear();
+            })
+            .await
+            .unwrap();
+        assert!(first.subscribers("file:///a").await.unwrap().is_empty());
+
+        second.terminate_session(&session_id).await.unwrap();
+        assert!(first.resume_session(&session_id).await.is_err());
+    }
+}
@@ -1,21 +1,47 @@
 //! Transport layer abstractions for MCP communication
 
+#[cfg(feature = "client")]
 pub mod connection;
+#[cfg(feature = "server")]
+pub mod distributed;
+#[cfg(feature = "server")]
+pub mod outbound;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(all(feature = "client", feature = "sse"))]
 pub mod sse;
+#[cfg(all(feature = "client", feature = "stdio"))]
 pub mod stdio;
+#[cfg(feature = "server")]
 pub mod streamable_http;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
 
+#[cfg(feature = "client")]
 pub use connection::{ConnectionHealth, ConnectionPool, HealthMonitor};
+#[cfg(feature = "server")]
+pub use distributed::{DistributedStateBackend, InMemoryStateBackend, SessionRecord};
+#[cfg(feature = "server")]
+pub use outbound::{
+    client_response, ClientHandle, PendingRequests, DEFAULT_CLIENT_REQUEST_TIMEOUT,
+    OUTBOUND_ID_PREFIX, REQUEST_EVENT,
+};
+#[cfg(feature = "redis")]
+pub use redis::{RedisEventStore, RedisNotificationBroker, RedisStateBackend};
+#[cfg(all(feature = "client", feature = "sse"))]
 pub use sse::SseTransport;
+#[cfg(all(feature = "client", feature = "stdio"))]
 pub use stdio::StdioTransport;
-pub use streamable_http::{
-    EventStore, InMemoryEventStore, McpEvent, SessionManager, StreamableHttpTransport,
-};
+#[cfg(feature = "axum")]
+pub use streamable_http::StreamableHttpTransport;
+#[cfg(feature = "server")]
+pub use streamable_http::{EventStore, InMemoryEventStore, McpEvent, SessionManager};
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub use wasm::WasmTransport;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use tokio::time::timeout;
+use std::{future::Future, time::Duration};
 
 use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
 use crate::{McpError, McpResult};
@@ -112,9 +138,26 @@ pub enum SseAuth {
     ApiKey { header: String, key: String },
 }
 
+/// Bounds of [`McpTransport`] implementations
+///
+/// `Send + Sync`, except on wasm32 where browser handles are single-threaded.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait TransportBounds: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> TransportBounds for T {}
+
+/// Bounds of [`McpTransport`] implementations
+///
+/// `Send + Sync`, except on wasm32 where browser handles are single-threaded.
+#[cfg(target_arch = "wasm32")]
+pub trait TransportBounds {}
+#[cfg(target_arch = "wasm32")]
+impl<T> TransportBounds for T {}
+
 /// Transport trait for MCP communication
-#[async_trait]
-pub trait McpTransport: Send + Sync {
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait McpTransport: TransportBounds {
     /// Connect to the remote endpoint
     async fn connect(&mut self) -> McpResult<()>;
 
@@ -134,9 +177,9 @@ pub trait McpTransport: Send + Sync {
         self.send(request).await?;
 
         // Wait for response with timeout
-        timeout(timeout_duration, self.receive())
+        with_timeout(timeout_duration, self.receive())
             .await
-            .map_err(|_| McpError::ServerTimeout {
+            .ok_or(McpError::ServerTimeout {
                 timeout: timeout_duration,
             })?
     }
@@ -221,18 +264,22 @@ impl TransportHealth {
 }
 
 /// Transport factory for creating transport instances
+#[cfg(feature = "client")]
 pub struct TransportFactory;
 
+#[cfg(feature = "client")]
 impl TransportFactory {
     /// Create a transport from configuration
     pub async fn create(config: TransportType) -> McpResult<Box<dyn McpTransport>> {
         match config {
+            #[cfg(feature = "stdio")]
             TransportType::Stdio {
                 command,
                 args,
                 env,
                 cwd,
             } => Ok(Box::new(StdioTransport::new(command, args, env, cwd)?)),
+            #[cfg(feature = "sse")]
             TransportType::Sse {
                 url,
                 headers,
@@ -242,6 +289,7 @@ impl TransportFactory {
             } => Ok(Box::new(SseTransport::new(
                 url, headers, auth, timeout, verify_ssl,
             )?)),
+            #[cfg(feature = "axum")]
             TransportType::StreamableHttp {
                 max_events_per_session,
                 session_timeout,
@@ -258,6 +306,13 @@ impl TransportFactory {
                 ));
                 Ok(Box::new(StreamableHttpTransport::new(session_manager)))
             }
+            #[allow(unreachable_patterns)]
+            other => Err(McpError::Configuration {
+                message: format!(
+                    "The {} transport is not enabled in this build",
+                    other.type_name()
+                ),
+            }),
         }
     }
 }
@@ -325,6 +380,25 @@ impl TransportType {
     }
 }
 
+/// Run `future`, giving up with `None` after `duration`
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub async fn with_timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Run `future`, giving up with `None` after `duration`
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub async fn with_timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    use futures_util::future::{select, Either};
+
+    let millis = duration.as_millis().min(u32::MAX as u128) as u32;
+    let timer = gloo_timers::future::TimeoutFuture::new(millis);
+    match select(std::pin::pin!(future), timer).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
 // Helper functions for serde defaults
 fn default_timeout() -> Duration {
     Duration::from_secs(30)
@@ -416,4 +490,14 @@ mod tests {
         assert_eq!(health.consecutive_failures, 0);
         assert_eq!(health.latency, Some(Duration::from_millis(100)));
     }
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        assert_eq!(
+            with_timeout(Duration::from_secs(1), async { 1 }).await,
+            Some(1)
+        );
+        let pending = std::future::pending::<()>();
+        assert_eq!(with_timeout(Duration::from_millis(10), pending).await, None);
+    }
 }
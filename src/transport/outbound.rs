@@ -0,0 +1,332 @@
+//! Requests from the server to connected clients
+//!
+//! MCP is bidirectional: besides notifications, a server may send requests
+//! such as `sampling/createMessage`, `elicitation/create` or `roots/list` and
+//! wait for the client's answer. A [`ClientHandle`] from
+//! [`SessionManager::client`] sends such a request as a [`REQUEST_EVENT`] on
+//! the session's stream and resolves once the client POSTs the matching
+//! JSON-RPC response back to the MCP endpoint.
+//!
+//! Outbound requests get IDs of their own (prefixed with
+//! [`OUTBOUND_ID_PREFIX`]) so they never collide with the client's request
+//! IDs, and a response is only accepted from the session the request was sent
+//! to. A request that is not answered within the handle's timeout fails with
+//! [`McpError::ClientTimeout`] and the client is sent `notifications/cancelled`.
+//!
+//! Pending requests live on the replica that sent them, so with several
+//! replicas clients must post responses to the node owning their session (see
+//! [`crate::server::affinity`]).
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::{
+    error::{McpError, McpResult},
+    ids::{IdKind, Ids},
+    protocol::{JsonRpcRequest, JsonRpcResponse},
+    server::notifications::NOTIFICATION_EVENT,
+    transport::streamable_http::{McpEvent, SessionManager},
+};
+
+/// Event type of server-to-client requests on session streams
+pub const REQUEST_EVENT: &str = "request";
+
+/// Prefix of the IDs of server-to-client requests
+pub const OUTBOUND_ID_PREFIX: &str = "srv-";
+
+/// How long a client has to answer by default
+pub const DEFAULT_CLIENT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Pending {
+    session_id: String,
+    responder: oneshot::Sender<JsonRpcResponse>,
+}
+
+/// Server-to-client requests awaiting their response, by request ID
+#[derive(Debug, Clone, Default)]
+pub struct PendingRequests {
+    requests: Arc<Mutex<HashMap<String, Pending>>>,
+    ids: Ids,
+}
+
+impl PendingRequests {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate request IDs, after the prefix, with `ids`
+    pub fn with_ids(mut self, ids: Ids) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    /// Route a client's response to the request waiting for it
+    ///
+    /// Returns `false` if no request from `session_id` with the response's ID
+    /// is pending, for example because it already timed out.
+    pub fn complete(&self, session_id: &str, response: JsonRpcResponse) -> bool {
+        let Some(id) = response.id.as_ref().and_then(Value::as_str) else {
+            return false;
+        };
+        let mut requests = self.requests.lock().unwrap();
+        if requests
+            .get(id)
+            .is_none_or(|pending| pending.session_id != session_id)
+        {
+            return false;
+        }
+        let pending = requests.remove(id).expect("checked above");
+        pending.responder.send(response).is_ok()
+    }
+
+    /// Fail every request pending for a session
+    pub fn cancel_session(&self, session_id: &str) {
+        self.requests
+            .lock()
+            .unwrap()
+            .retain(|_, pending| pending.session_id != session_id);
+    }
+
+    /// Number of requests awaiting a response
+    pub fn len(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Whether no requests await a response
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn register(&self, session_id: &str) -> (String, oneshot::Receiver<JsonRpcResponse>) {
+        let id = format!(
+            "{}{}",
+            OUTBOUND_ID_PREFIX,
+            self.ids.generate(IdKind::Request)
+        );
+        let (responder, receiver) = oneshot::channel();
+        self.requests.lock().unwrap().insert(
+            id.clone(),
+            Pending {
+                session_id: session_id.to_string(),
+                responder,
+            },
+        );
+        (id, receiver)
+    }
+
+    fn forget(&self, id: &str) {
+        self.requests.lock().unwrap().remove(id);
+    }
+}
+
+/// Sends requests and notifications to the client of one session
+#[derive(Clone)]
+pub struct ClientHandle {
+    session_id: String,
+    sessions: SessionManager,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for ClientHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientHandle")
+            .field("session_id", &self.session_id)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientHandle {
+    pub(crate) fn new(session_id: String, sessions: SessionManager) -> Self {
+        Self {
+            session_id,
+            sessions,
+            timeout: DEFAULT_CLIENT_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Give the client `timeout` to answer each request
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Session this handle talks to
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Send a request to the client and wait for its result
+    ///
+    /// Fails if the session is unknown or terminated before the client
+    /// answers, if the client answers with an error, or after the timeout.
+    pub async fn request(&self, method: &str, params: Option<Value>) -> McpResult<Value> {
+        let session = self
+            .sessions
+            .get_session(&self.session_id)
+            .await
+            .filter(|session| !session.is_closed())
+            .ok_or_else(|| McpError::Session {
+                message: format!("Session not found or expired: {}", self.session_id),
+            })?;
+
+        let pending = self.sessions.pending_requests();
+        let (id, receiver) = pending.register(&self.session_id);
+        let request = JsonRpcRequest::new(method, params, Some(Value::String(id.clone())));
+        debug!("Sending {} ({}) to session {}", method, id, self.session_id);
+        if let Err(e) = self.send(REQUEST_EVENT, &request).await {
+            pending.forget(&id);
+            return Err(e);
+        }
+
+        // `None` once the session ends, which also drops the pending request
+        let answered = tokio::select! {
+            answered = tokio::time::timeout(self.timeout, receiver) => {
+                answered.map(Result::ok)
+            }
+            _ = session.closed() => Ok(None),
+        };
+        match answered {
+            Ok(Some(response)) => match (response.result, response.error) {
+                (_, Some(error)) => Err(McpError::Protocol {
+                    message: format!(
+                        "Client failed {} with error {}: {}",
+                        method, error.code, error.message
+                    ),
+                }),
+                (result, None) => Ok(result.unwrap_or(Value::Null)),
+            },
+            Ok(None) => Err(McpError::Session {
+                message: format!(
+                    "Session {} ended before the client answered {}",
+                    self.session_id, method
+                ),
+            }),
+            Err(_) => {
+                pending.forget(&id);
+                let cancelled = JsonRpcRequest::new(
+                    "notifications/cancelled",
+                    Some(serde_json::json!({"requestId": id, "reason": "Request timed out"})),
+                    None,
+                );
+                let _ = self.send(NOTIFICATION_EVENT, &cancelled).await;
+                Err(McpError::ClientTimeout {
+                    timeout: self.timeout,
+                })
+            }
+        }
+    }
+
+    /// Send a request and decode the client's result as `T`
+    pub async fn request_as<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> McpResult<T> {
+        let result = self.request(method, params).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Send a notification to the client
+    pub async fn notify(&self, method: &str, params: Option<Value>) -> McpResult<()> {
+        let notification = JsonRpcRequest::new(method, params, None);
+        self.send(NOTIFICATION_EVENT, &notification).await
+    }
+
+    async fn send(&self, event_type: &str, message: &JsonRpcRequest) -> McpResult<()> {
+        let event = McpEvent::new(
+            self.session_id.clone(),
+            event_type.to_string(),
+            serde_json::to_value(message)?,
+        );
+        self.sessions.store_event(&event).await
+    }
+}
+
+/// Decode a POST body that is a JSON-RPC response rather than a request
+///
+/// Clients answer server-to-client requests this way.
+pub fn client_response(body: &[u8]) -> Option<JsonRpcResponse> {
+    client_response_value(&serde_json::from_slice(body).ok()?)
+}
+
+/// [`client_response`] for a body that is already decoded
+pub fn client_response_value(value: &Value) -> Option<JsonRpcResponse> {
+    let message = value.as_object()?;
+    if message.contains_key("method")
+        || !(message.contains_key("result") || message.contains_key("error"))
+    {
+        return None;
+    }
+    JsonRpcResponse::deserialize(value).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::InMemoryEventStore;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_client_requests_are_correlated() {
+        let sessions = SessionManager::new(
+            Arc::new(InMemoryEventStore::new(100, Duration::from_secs(60))),
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+        );
+        let session_id = sessions.create_session().await.unwrap();
+        let mut stream = Box::pin(sessions.subscribe_to_session(&session_id).await);
+        let client = sessions.client(&session_id);
+
+        let request = tokio::spawn({
+            let client = client.clone();
+            async move { client.request("roots/list", None).await }
+        });
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.event_type, REQUEST_EVENT);
+        assert_eq!(event.data["method"], "roots/list");
+        let id = event.data["id"].clone();
+        assert!(id.as_str().unwrap().starts_with(OUTBOUND_ID_PREFIX));
+
+        // Only the session the request went to can answer it
+        let response = JsonRpcResponse::success(serde_json::json!({"roots": []}), Some(id));
+        assert!(!sessions.complete_client_request("other", response.clone()));
+        assert!(sessions.complete_client_request(&session_id, response.clone()));
+        assert_eq!(
+            request.await.unwrap().unwrap(),
+            serde_json::json!({"roots": []})
+        );
+        assert!(!sessions.complete_client_request(&session_id, response));
+
+        let timeout = client
+            .with_timeout(Duration::from_millis(20))
+            .request("sampling/createMessage", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(timeout, McpError::ClientTimeout { .. }));
+        assert!(sessions.pending_requests().is_empty());
+        stream.next().await.unwrap();
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.data["method"], "notifications/cancelled");
+    }
+
+    #[test]
+    fn test_client_response_detection() {
+        assert!(client_response(br#"{"jsonrpc":"2.0","result":{},"id":"srv-1"}"#).is_some());
+        assert!(client_response(
+            br#"{"jsonrpc":"2.0","error":{"code":-1,"message":"no"},"id":"srv-1"}"#
+        )
+        .is_some());
+        assert!(client_response(br#"{"jsonrpc":"2.0","method":"ping","id":1}"#).is_none());
+        assert!(client_response(b"not json").is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! Redis-backed session state
+//!
+//! With the `redis` feature, [`RedisStateBackend`] implements
+//! [`DistributedStateBackend`] and [`RedisEventStore`] implements
+//! [`EventStore`], so replicas behind a load balancer share sessions,
+//! subscriptions, live events and the replay buffer. Keys live below a
+//! configurable prefix:
+//!
+//! - `{prefix}:session:{id}` holds the JSON session record and expires with
+//!   the session.
+//! - `{prefix}:subscribers:{uri}` is the set of sessions subscribed to a URI.
+//! - `{prefix}:events:{id}` is the list of stored events of a session.
+//! - Live events of a session are published on `{prefix}:channel:{id}`.
+//!
+//! [`RedisNotificationBroker`] fans server notifications out over the
+//! `{prefix}:notifications` channel.
+//!
+//! ```rust,ignore
+//! let client = redis::Client::open("redis://cache:6379")?;
+//! let backend = RedisStateBackend::new(client.clone(), "mcp").await?;
+//! let event_store = RedisEventStore::new(client, "mcp", 1000, Duration::from_secs(1800)).await?;
+//! let sessions = SessionManager::new(Arc::new(event_store), timeout, interval)
+//!     .with_backend(Arc::new(backend));
+//! ```
+
+use async_trait::async_trait;
+use futures_util::{stream::BoxStream, StreamExt};
+use redis::{aio::ConnectionManager, AsyncCommands};
+use std::time::Duration;
+
+use crate::{
+    error::{McpError, McpResult},
+    protocol::McpNotification,
+    server::notifications::NotificationBroker,
+    transport::{
+        distributed::{DistributedStateBackend, SessionRecord},
+        streamable_http::{EventStore, McpEvent},
+    },
+};
+
+/// Shares session records, subscriptions and live events through Redis
+#[derive(Clone)]
+pub struct RedisStateBackend {
+    client: redis::Client,
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisStateBackend {
+    /// Connect to Redis, storing keys below `prefix`
+    pub async fn new(client: redis::Client, prefix: impl Into<String>) -> McpResult<Self> {
+        let connection = ConnectionManager::new(client.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(Self {
+            client,
+            connection,
+            prefix: prefix.into(),
+        })
+    }
+
+    fn session_key(&self, session_id: &str) -> String {
+        format!("{}:session:{}", self.prefix, session_id)
+    }
+
+    fn subscribers_key(&self, uri: &str) -> String {
+        format!("{}:subscribers:{}", self.prefix, uri)
+    }
+
+    fn channel(&self, session_id: &str) -> String {
+        format!("{}:channel:{}", self.prefix, session_id)
+    }
+}
+
+#[async_trait]
+impl DistributedStateBackend for RedisStateBackend {
+    async fn load_session(&self, session_id: &str) -> McpResult<Option<SessionRecord>> {
+        let record: Option<String> = self
+            .connection
+            .clone()
+            .get(self.session_key(session_id))
+            .await
+            .map_err(redis_error)?;
+        record
+            .map(|record| serde_json::from_str(&record).map_err(McpError::from))
+            .transpose()
+    }
+
+    async fn save_session(
+        &self,
+        session_id: &str,
+        record: &SessionRecord,
+        ttl: Duration,
+    ) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .set_ex(
+                self.session_key(session_id),
+                serde_json::to_string(record)?,
+                ttl.as_secs().max(1),
+            )
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn remove_session(&self, session_id: &str) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .del(self.session_key(session_id))
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn add_subscription(&self, uri: &str, session_id: &str) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .sadd(self.subscribers_key(uri), session_id)
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn remove_subscription(&self, uri: &str, session_id: &str) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .srem(self.subscribers_key(uri), session_id)
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn subscribers(&self, uri: &str) -> McpResult<Vec<String>> {
+        let mut subscribers: Vec<String> = self
+            .connection
+            .clone()
+            .smembers(self.subscribers_key(uri))
+            .await
+            .map_err(redis_error)?;
+        subscribers.sort();
+        Ok(subscribers)
+    }
+
+    async fn publish_event(&self, event: &McpEvent) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .publish(
+                self.channel(&event.session_id),
+                serde_json::to_string(event)?,
+            )
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn subscribe_events(&self, session_id: &str) -> McpResult<BoxStream<'static, McpEvent>> {
+        let mut pubsub = self.client.get_async_pubsub().await.map_err(redis_error)?;
+        pubsub
+            .subscribe(self.channel(session_id))
+            .await
+            .map_err(redis_error)?;
+        Ok(pubsub
+            .into_on_message()
+            .filter_map(|message| async move {
+                let payload: String = message.get_payload().ok()?;
+                serde_json::from_str(&payload).ok()
+            })
+            .boxed())
+    }
+}
+
+/// Event store keeping each session's replay buffer in a Redis list
+#[derive(Clone)]
+pub struct RedisEventStore {
+    connection: ConnectionManager,
+    prefix: String,
+    max_events_per_session: usize,
+    max_session_age: Duration,
+}
+
+impl RedisEventStore {
+    /// Connect to Redis, storing keys below `prefix`
+    ///
+    /// Lists keep the latest `max_events_per_session` events and expire
+    /// `max_session_age` after the last stored event.
+    pub async fn new(
+        client: redis::Client,
+        prefix: impl Into<String>,
+        max_events_per_session: usize,
+        max_session_age: Duration,
+    ) -> McpResult<Self> {
+        let connection = ConnectionManager::new(client).await.map_err(redis_error)?;
+        Ok(Self {
+            connection,
+            prefix: prefix.into(),
+            max_events_per_session,
+            max_session_age,
+        })
+    }
+
+    fn events_key(&self, session_id: &str) -> String {
+        format!("{}:events:{}", self.prefix, session_id)
+    }
+}
+
+#[async_trait]
+impl EventStore for RedisEventStore {
+    async fn store_event(&self, session_id: &str, event: McpEvent) -> McpResult<()> {
+        let key = self.events_key(session_id);
+        let keep = self.max_events_per_session.max(1) as isize;
+        let _: () = redis::pipe()
+            .atomic()
+            .rpush(&key, serde_json::to_string(&event)?)
+            .ltrim(&key, -keep, -1)
+            .expire(&key, self.max_session_age.as_secs().max(1) as i64)
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn get_events_since(
+        &self,
+        session_id: &str,
+        last_event_id: Option<&str>,
+    ) -> McpResult<Vec<McpEvent>> {
+        let stored: Vec<String> = self
+            .connection
+            .clone()
+            .lrange(self.events_key(session_id), 0, -1)
+            .await
+            .map_err(redis_error)?;
+        let events: Vec<McpEvent> = stored
+            .iter()
+            .filter_map(|event| serde_json::from_str(event).ok())
+            .collect();
+
+        // Unknown event IDs replay everything, as with the in-memory store
+        let start = last_event_id
+            .and_then(|last_id| events.iter().position(|event| event.id == last_id))
+            .map_or(0, |position| position + 1);
+        Ok(events[start..].to_vec())
+    }
+
+    async fn cleanup_expired(&self) -> McpResult<()> {
+        // Lists expire on their own
+        Ok(())
+    }
+
+    async fn remove_session(&self, session_id: &str) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .del(self.events_key(session_id))
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+}
+
+/// Notification broker publishing on a Redis channel
+#[derive(Clone)]
+pub struct RedisNotificationBroker {
+    client: redis::Client,
+    connection: ConnectionManager,
+    channel: String,
+}
+
+impl RedisNotificationBroker {
+    /// Connect to Redis, publishing on `{prefix}:notifications`
+    pub async fn new(client: redis::Client, prefix: impl Into<String>) -> McpResult<Self> {
+        let connection = ConnectionManager::new(client.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(Self {
+            client,
+            connection,
+            channel: format!("{}:notifications", prefix.into()),
+        })
+    }
+}
+
+#[async_trait]
+impl NotificationBroker for RedisNotificationBroker {
+    async fn publish(&self, notification: &McpNotification) -> McpResult<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .publish(&self.channel, serde_json::to_string(notification)?)
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn subscribe(&self) -> McpResult<BoxStream<'static, McpNotification>> {
+        let mut pubsub = self.client.get_async_pubsub().await.map_err(redis_error)?;
+        pubsub.subscribe(&self.channel).await.map_err(redis_error)?;
+        Ok(pubsub
+            .into_on_message()
+            .filter_map(|message| async move {
+                let payload: String = message.get_payload().ok()?;
+                serde_json::from_str(&payload).ok()
+            })
+            .boxed())
+    }
+}
+
+fn redis_error(error: redis::RedisError) -> McpError {
+    McpError::Connection {
+        message: format!("Redis: {}", error),
+    }
+}
@@ -323,6 +323,7 @@ impl McpTransport for SseTransport {
                 let response: JsonRpcResponse =
                     serde_json::from_str(&data).map_err(|e| McpError::Serialization {
                         message: format!("Failed to parse SSE response: {}", e),
+                        source: None,
                     })?;
 
                 // Update health
@@ -406,6 +407,7 @@ impl McpTransport for SseTransport {
             })?
             .map_err(|_| McpError::Internal {
                 message: "Response channel was dropped".to_string(),
+                source: None,
             })?;
 
         Ok(response)
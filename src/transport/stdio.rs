@@ -220,6 +220,7 @@ impl McpTransport for StdioTransport {
         // Serialize message to JSON
         let json = serde_json::to_string(&message).map_err(|e| McpError::Serialization {
             message: format!("Failed to serialize request: {}", e),
+            source: None,
         })?;
 
         // Send the message
@@ -271,6 +272,7 @@ impl McpTransport for StdioTransport {
         let response: JsonRpcResponse =
             serde_json::from_str(&line).map_err(|e| McpError::Serialization {
                 message: format!("Failed to parse response: {}", e),
+                source: None,
             })?;
 
         // Update health
@@ -4,6 +4,7 @@
 //! for streaming responses, providing session management and resumability features.
 
 use async_trait::async_trait;
+#[cfg(feature = "axum")]
 use axum::{
     http::{HeaderMap, HeaderValue, StatusCode},
     response::{
@@ -14,20 +15,37 @@ use axum::{
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
-    convert::Infallible,
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use crate::{
+    clock::Clock,
     error::{McpError, McpResult},
-    protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse},
+    ids::{IdKind, Ids},
+    protocol::{ClientCapabilities, JsonRpcResponse},
+    security::ClientFingerprint,
+    server::{
+        notification_filter::NotificationFilter,
+        notifications::NOTIFICATION_EVENT,
+        wire_tap::{FrameDirection, WireTap},
+    },
+    transport::{
+        distributed::{DistributedStateBackend, SessionRecord},
+        outbound::{ClientHandle, PendingRequests},
+    },
+};
+#[cfg(feature = "axum")]
+use crate::{
+    protocol::{JsonRpcError, JsonRpcRequest},
     transport::{McpTransport, TransportHealth},
 };
+#[cfg(feature = "axum")]
+use std::convert::Infallible;
 
 /// Event store trait for supporting session resumability
 #[async_trait]
@@ -155,6 +173,35 @@ impl EventStore for InMemoryEventStore {
     }
 }
 
+/// Capacity of the live notification channel for each session
+const SESSION_NOTIFICATION_CAPACITY: usize = 256;
+
+/// Header carrying the StreamableHTTP session identifier
+pub const MCP_SESSION_HEADER: &str = "mcp-session-id";
+
+/// Event type sent to a session's streams when it is terminated
+pub const SESSION_CLOSED_EVENT: &str = "session_closed";
+
+/// Negotiated state of a session, restored when a client reconnects
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Protocol version agreed during `initialize`
+    pub protocol_version: Option<String>,
+
+    /// Capabilities the client declared during `initialize`
+    pub client_capabilities: Option<ClientCapabilities>,
+
+    /// Resource URIs the client subscribed to
+    pub subscriptions: BTreeSet<String>,
+
+    /// In-flight requests (JSON-RPC ID to method) whose responses are still pending
+    pub pending_requests: BTreeMap<String, String>,
+
+    /// Notification categories the client asked for
+    #[serde(default)]
+    pub notification_filter: NotificationFilter,
+}
+
 /// Streamable HTTP transport session
 #[derive(Debug)]
 pub struct StreamableHttpSession {
@@ -163,23 +210,68 @@ pub struct StreamableHttpSession {
     pub last_activity: Arc<RwLock<SystemTime>>,
     pub event_sender: mpsc::UnboundedSender<McpEvent>,
     pub cleanup_handle: Option<tokio::task::JoinHandle<()>>,
+    pub client_fingerprint: Arc<RwLock<Option<ClientFingerprint>>>,
+    pub state: Arc<RwLock<SessionState>>,
+    notifications: broadcast::Sender<McpEvent>,
+    closed: watch::Sender<bool>,
+    clock: Clock,
 }
 
 impl StreamableHttpSession {
     pub fn new(session_id: String) -> (Self, mpsc::UnboundedReceiver<McpEvent>) {
+        Self::with_clock(session_id, Clock::default())
+    }
+
+    /// Create a session whose activity is timed on `clock`
+    pub fn with_clock(
+        session_id: String,
+        clock: Clock,
+    ) -> (Self, mpsc::UnboundedReceiver<McpEvent>) {
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let now = clock.system_time();
         let session = Self {
             session_id,
-            created_at: SystemTime::now(),
-            last_activity: Arc::new(RwLock::new(SystemTime::now())),
+            created_at: now,
+            last_activity: Arc::new(RwLock::new(now)),
             event_sender,
             cleanup_handle: None,
+            client_fingerprint: Arc::new(RwLock::new(None)),
+            state: Arc::new(RwLock::new(SessionState::default())),
+            notifications: broadcast::channel(SESSION_NOTIFICATION_CAPACITY).0,
+            closed: watch::channel(false).0,
+            clock,
         };
         (session, event_receiver)
     }
 
+    /// Resolves once the session has been terminated
+    ///
+    /// In-flight work for the session races against this to be cancelled.
+    pub fn closed(&self) -> impl std::future::Future<Output = ()> + Send + 'static {
+        let mut closed = self.closed.subscribe();
+        async move {
+            // An error means the session was dropped, which also ends it
+            let _ = closed.wait_for(|closed| *closed).await;
+        }
+    }
+
+    /// Whether the session has been terminated
+    pub fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Whether the session has been idle for longer than `timeout`
+    pub async fn is_expired(&self, timeout: Duration) -> bool {
+        let last_activity = *self.last_activity.read().await;
+        self.clock
+            .system_time()
+            .duration_since(last_activity)
+            .unwrap_or_default()
+            > timeout
+    }
+
     pub async fn update_activity(&self) {
-        *self.last_activity.write().await = SystemTime::now();
+        *self.last_activity.write().await = self.clock.system_time();
     }
 
     pub async fn send_event(&self, event: McpEvent) -> McpResult<()> {
@@ -198,8 +290,14 @@ impl StreamableHttpSession {
 pub struct SessionManager {
     sessions: Arc<RwLock<HashMap<String, Arc<StreamableHttpSession>>>>,
     event_store: Arc<dyn EventStore>,
+    backend: Option<Arc<dyn DistributedStateBackend>>,
+    node_id: Option<String>,
     session_timeout: Duration,
     cleanup_interval: Duration,
+    pending: PendingRequests,
+    clock: Clock,
+    wire_tap: Option<WireTap>,
+    ids: Ids,
 }
 
 impl SessionManager {
@@ -211,51 +309,308 @@ impl SessionManager {
         Self {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             event_store,
+            backend: None,
+            node_id: None,
             session_timeout,
             cleanup_interval,
+            pending: PendingRequests::new(),
+            clock: Clock::default(),
+            wire_tap: None,
+            ids: Ids::default(),
         }
     }
 
+    /// Time session activity and cleanup on `clock` instead of real time
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Share session state with other replicas through `backend`
+    ///
+    /// Session records are written through on every change, sessions created
+    /// elsewhere are loaded on first use, and events reach streams on every
+    /// replica sharing the backend. Records expire after the session timeout
+    /// without activity.
+    pub fn with_backend(mut self, backend: Arc<dyn DistributedStateBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Mirror events sent to tapped sessions and their clients' responses
+    /// to `wire_tap`, usually [`McpServer::wire_tap`](crate::server::McpServer::wire_tap)
+    pub fn with_wire_tap(mut self, wire_tap: WireTap) -> Self {
+        self.wire_tap = Some(wire_tap);
+        self
+    }
+
+    /// Generate session IDs and the IDs of requests to clients with `ids`,
+    /// usually [`McpServer::ids`](crate::server::McpServer::ids)
+    pub fn with_ids(mut self, ids: Ids) -> Self {
+        self.pending = self.pending.with_ids(ids.clone());
+        self.ids = ids;
+        self
+    }
+
+    /// Issue session IDs of the form `{node_id}.{id}`
+    ///
+    /// Load balancers and other nodes can then tell which node owns a
+    /// session; see [`crate::server::affinity`].
+    pub fn with_node_id(mut self, node_id: impl Into<String>) -> Self {
+        self.node_id = Some(node_id.into());
+        self
+    }
+
+    /// ID of the node this manager issues sessions for
+    pub fn node_id(&self) -> Option<&str> {
+        self.node_id.as_deref()
+    }
+
     /// Create a new session
     pub async fn create_session(&self) -> McpResult<String> {
-        let session_id = Uuid::new_v4().to_string();
-        let (session, _event_receiver) = StreamableHttpSession::new(session_id.clone());
+        let session_id = match &self.node_id {
+            Some(node_id) => format!("{}.{}", node_id, self.ids.generate(IdKind::Session)),
+            None => self.ids.generate(IdKind::Session),
+        };
+        let (session, _event_receiver) =
+            StreamableHttpSession::with_clock(session_id.clone(), self.clock.clone());
         let session = Arc::new(session);
 
         {
             let mut sessions = self.sessions.write().await;
             sessions.insert(session_id.clone(), session);
         }
+        if let Some(backend) = &self.backend {
+            backend
+                .save_session(&session_id, &SessionRecord::default(), self.session_timeout)
+                .await?;
+        }
 
         info!("Created new streamable HTTP session: {}", session_id);
         Ok(session_id)
     }
 
     /// Get an existing session
+    ///
+    /// With a backend, sessions created by other replicas are loaded from it.
     pub async fn get_session(&self, session_id: &str) -> Option<Arc<StreamableHttpSession>> {
-        let sessions = self.sessions.read().await;
-        sessions.get(session_id).cloned()
+        if let Some(session) = self.sessions.read().await.get(session_id).cloned() {
+            return Some(session);
+        }
+
+        let record = self.load_record(session_id).await?;
+        let (session, _event_receiver) =
+            StreamableHttpSession::with_clock(session_id.to_string(), self.clock.clone());
+        *session.state.write().await = record.state;
+        *session.client_fingerprint.write().await = record.fingerprint;
+        let session = Arc::new(session);
+        debug!("Loaded session {} from the shared backend", session_id);
+        Some(
+            self.sessions
+                .write()
+                .await
+                .entry(session_id.to_string())
+                .or_insert(session)
+                .clone(),
+        )
     }
 
     /// Remove a session  
     pub async fn remove_session(&self, session_id: &str) -> McpResult<()> {
-        {
-            let mut sessions = self.sessions.write().await;
-            sessions.remove(session_id);
-        }
+        let session = self.sessions.write().await.remove(session_id);
 
         // Clean up event store
         self.event_store.remove_session(session_id).await?;
 
+        if let Some(backend) = &self.backend {
+            let subscriptions = match &session {
+                Some(session) => session.state.read().await.subscriptions.clone(),
+                None => BTreeSet::new(),
+            };
+            for uri in &subscriptions {
+                backend.remove_subscription(uri, session_id).await?;
+            }
+            backend.remove_session(session_id).await?;
+        }
+
         info!("Removed streamable HTTP session: {}", session_id);
         Ok(())
     }
 
-    /// Store an event for a session
+    /// Terminate a session at the client's request
+    ///
+    /// Sends a [`SESSION_CLOSED_EVENT`] to connected streams, cancels work
+    /// still running for the session, drops its subscriptions and removes it.
+    /// Returns the state the session had when it was terminated.
+    pub async fn terminate_session(&self, session_id: &str) -> McpResult<SessionState> {
+        let session = self
+            .get_session(session_id)
+            .await
+            .ok_or_else(|| session_not_found(session_id))?;
+
+        let state = std::mem::take(&mut *session.state.write().await);
+        let closed_event = McpEvent::new(
+            session_id.to_string(),
+            SESSION_CLOSED_EVENT.to_string(),
+            serde_json::json!({ "sessionId": session_id }),
+        );
+        match &self.backend {
+            Some(backend) => {
+                for uri in &state.subscriptions {
+                    backend.remove_subscription(uri, session_id).await?;
+                }
+                backend.publish_event(&closed_event).await?;
+            }
+            None => {
+                let _ = session.notifications.send(closed_event);
+            }
+        }
+        session.closed.send_replace(true);
+        self.pending.cancel_session(session_id);
+        self.remove_session(session_id).await?;
+
+        info!(
+            "Terminated session {} ({} subscriptions dropped, {} requests cancelled)",
+            session_id,
+            state.subscriptions.len(),
+            state.pending_requests.len()
+        );
+        Ok(state)
+    }
+
+    /// Resume a session after a client reconnects with its `Mcp-Session-Id`
+    ///
+    /// Returns the session's negotiated state. Unknown or expired sessions
+    /// produce a [`McpError::Session`] error; expired sessions are removed so
+    /// the client has to initialize a new one.
+    pub async fn resume_session(&self, session_id: &str) -> McpResult<SessionState> {
+        let session = match self.get_session(session_id).await {
+            Some(session) if !session.is_expired(self.session_timeout).await => session,
+            Some(_) => {
+                self.remove_session(session_id).await?;
+                return Err(session_not_found(session_id));
+            }
+            None => return Err(session_not_found(session_id)),
+        };
+
+        session.update_activity().await;
+        let state = match (&self.backend, self.load_record(session_id).await) {
+            (None, _) => session.state.read().await.clone(),
+            (Some(_), Some(record)) => {
+                // Saving again keeps the shared record from expiring
+                self.save_record(session_id, &record).await?;
+                *session.state.write().await = record.state.clone();
+                record.state
+            }
+            (Some(_), None) => {
+                // Terminated or expired on another replica
+                self.sessions.write().await.remove(session_id);
+                return Err(session_not_found(session_id));
+            }
+        };
+        debug!(
+            "Resumed session {} ({} subscriptions, {} pending requests)",
+            session_id,
+            state.subscriptions.len(),
+            state.pending_requests.len()
+        );
+        Ok(state)
+    }
+
+    /// Get the negotiated state of a session
+    pub async fn session_state(&self, session_id: &str) -> Option<SessionState> {
+        if self.backend.is_some() {
+            return self
+                .load_record(session_id)
+                .await
+                .map(|record| record.state);
+        }
+        let session = self.get_session(session_id).await?;
+        let state = session.state.read().await.clone();
+        Some(state)
+    }
+
+    /// Update the negotiated state of a session
+    pub async fn update_session_state(
+        &self,
+        session_id: &str,
+        update: impl FnOnce(&mut SessionState),
+    ) -> McpResult<()> {
+        let session = self
+            .get_session(session_id)
+            .await
+            .ok_or_else(|| session_not_found(session_id))?;
+        let Some(backend) = &self.backend else {
+            update(&mut *session.state.write().await);
+            return Ok(());
+        };
+
+        // The shared record may have been changed by another replica
+        let mut record = self.load_record(session_id).await.unwrap_or_default();
+        let previous = record.state.subscriptions.clone();
+        update(&mut record.state);
+        for uri in record.state.subscriptions.difference(&previous) {
+            backend.add_subscription(uri, session_id).await?;
+        }
+        for uri in previous.difference(&record.state.subscriptions) {
+            backend.remove_subscription(uri, session_id).await?;
+        }
+        self.save_record(session_id, &record).await?;
+        *session.state.write().await = record.state;
+        Ok(())
+    }
+
+    /// Record the client fingerprint captured during `initialize`
+    pub async fn set_client_fingerprint(
+        &self,
+        session_id: &str,
+        fingerprint: ClientFingerprint,
+    ) -> McpResult<()> {
+        let session = self
+            .get_session(session_id)
+            .await
+            .ok_or_else(|| session_not_found(session_id))?;
+        *session.client_fingerprint.write().await = Some(fingerprint.clone());
+        if self.backend.is_some() {
+            let mut record = self.load_record(session_id).await.unwrap_or_default();
+            record.fingerprint = Some(fingerprint);
+            self.save_record(session_id, &record).await?;
+        }
+        Ok(())
+    }
+
+    /// Get the client fingerprint recorded for a session
+    pub async fn client_fingerprint(&self, session_id: &str) -> Option<ClientFingerprint> {
+        if self.backend.is_some() {
+            return self.load_record(session_id).await?.fingerprint;
+        }
+        let session = self.get_session(session_id).await?;
+        let fingerprint = session.client_fingerprint.read().await.clone();
+        fingerprint
+    }
+
+    /// Store an event for a session and deliver it to connected streams
+    ///
+    /// Notifications the session's [`NotificationFilter`] holds back are
+    /// dropped.
     pub async fn store_event(&self, event: &McpEvent) -> McpResult<()> {
+        if !self.wanted(event).await {
+            return Ok(());
+        }
+        self.tap(event);
         self.event_store
             .store_event(&event.session_id, event.clone())
-            .await
+            .await?;
+
+        if let Some(backend) = &self.backend {
+            return backend.publish_event(event).await;
+        }
+        if let Some(session) = self.get_session(&event.session_id).await {
+            // No receivers just means the client is not connected right now;
+            // it will pick the event up from the store when it resumes
+            let _ = session.notifications.send(event.clone());
+        }
+        Ok(())
     }
 
     /// Get events since a specific event ID
@@ -269,32 +624,169 @@ impl SessionManager {
             .await
     }
 
-    /// Subscribe to session events (returns a stream)
+    /// Subscribe to events stored for a session from now on
+    ///
+    /// The stream is empty for unknown sessions and ends when the session is
+    /// removed. With a backend, it carries events stored on any replica.
     pub async fn subscribe_to_session(
         &self,
-        _session_id: &str,
+        session_id: &str,
     ) -> impl futures_util::Stream<Item = McpEvent> {
-        use futures_util::stream;
+        use futures_util::StreamExt;
+        use tokio_stream::wrappers::BroadcastStream;
 
-        // For now, return an empty stream as a placeholder
-        // In a real implementation, this would use a broadcast channel or similar
-        stream::empty()
+        let receiver = match self.get_session(session_id).await {
+            Some(session) => session.notifications.subscribe(),
+            None => broadcast::channel(1).1,
+        };
+        let local = BroadcastStream::new(receiver)
+            .filter_map(|event| async move { event.ok() })
+            .boxed();
+
+        // Events delivered on this replica arrive locally, the rest through
+        // the backend
+        if let Some(backend) = &self.backend {
+            match backend.subscribe_events(session_id).await {
+                Ok(shared) => return futures_util::stream::select(shared, local).boxed(),
+                Err(e) => warn!("Failed to subscribe to session {}: {}", session_id, e),
+            }
+        }
+        local
+    }
+
+    /// Deliver an event to a session's streams open on this replica
+    ///
+    /// Unlike [`store_event`](Self::store_event), the event is not shared with
+    /// other replicas; it is used for events every replica receives anyway,
+    /// such as broker notifications. With a backend, the event is only stored
+    /// for replay when a stream on this replica received it. Sessions unknown
+    /// to this replica are skipped.
+    pub async fn deliver_local(&self, event: &McpEvent) -> McpResult<()> {
+        let Some(session) = self.sessions.read().await.get(&event.session_id).cloned() else {
+            return Ok(());
+        };
+        if self.backend.is_none() {
+            return self.store_event(event).await;
+        }
+        if !self.wanted(event).await {
+            return Ok(());
+        }
+        if session.notifications.send(event.clone()).is_ok() {
+            self.tap(event);
+            self.event_store
+                .store_event(&event.session_id, event.clone())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Handle for sending requests to the client of a session
+    pub fn client(&self, session_id: &str) -> ClientHandle {
+        ClientHandle::new(session_id.to_string(), self.clone())
+    }
+
+    /// Requests sent to clients that await a response
+    pub fn pending_requests(&self) -> &PendingRequests {
+        &self.pending
+    }
+
+    /// Route a client's response to the server request waiting for it
+    ///
+    /// Returns `false` if no request to `session_id` with that ID is pending.
+    pub fn complete_client_request(&self, session_id: &str, response: JsonRpcResponse) -> bool {
+        if let Some(wire_tap) = &self.wire_tap {
+            let frame = serde_json::to_value(&response).unwrap_or_default();
+            wire_tap.record(Some(session_id), FrameDirection::Inbound, &frame);
+        }
+        self.pending.complete(session_id, response)
+    }
+
+    /// Whether the client of the event's session wants it
+    async fn wanted(&self, event: &McpEvent) -> bool {
+        if event.event_type != NOTIFICATION_EVENT {
+            return true;
+        }
+        self.session_state(&event.session_id)
+            .await
+            .is_none_or(|state| state.notification_filter.allows_message(&event.data))
+    }
+
+    /// Mirror an event sent to a session, if the session is tapped
+    fn tap(&self, event: &McpEvent) {
+        // Responses are tapped by the server as it answers
+        if let Some(wire_tap) = &self.wire_tap {
+            if event.event_type != "response" {
+                wire_tap.record(
+                    Some(&event.session_id),
+                    FrameDirection::Outbound,
+                    &event.data,
+                );
+            }
+        }
+    }
+
+    /// IDs of the sessions held by this replica
+    pub async fn session_ids(&self) -> Vec<String> {
+        let mut session_ids: Vec<String> = self.sessions.read().await.keys().cloned().collect();
+        session_ids.sort();
+        session_ids
+    }
+
+    /// Sessions subscribed to a resource URI
+    ///
+    /// With a backend, this includes sessions served by other replicas.
+    pub async fn subscribers(&self, uri: &str) -> McpResult<Vec<String>> {
+        if let Some(backend) = &self.backend {
+            return backend.subscribers(uri).await;
+        }
+        let mut subscribers = Vec::new();
+        for (session_id, session) in self.sessions.read().await.iter() {
+            if session.state.read().await.subscriptions.contains(uri) {
+                subscribers.push(session_id.clone());
+            }
+        }
+        subscribers.sort();
+        Ok(subscribers)
+    }
+
+    async fn load_record(&self, session_id: &str) -> Option<SessionRecord> {
+        let backend = self.backend.as_ref()?;
+        match backend.load_session(session_id).await {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("Failed to load session {} from backend: {}", session_id, e);
+                None
+            }
+        }
+    }
+
+    async fn save_record(&self, session_id: &str, record: &SessionRecord) -> McpResult<()> {
+        match &self.backend {
+            Some(backend) => {
+                backend
+                    .save_session(session_id, record, self.session_timeout)
+                    .await
+            }
+            None => Ok(()),
+        }
     }
 
     /// Start background cleanup task
     pub fn start_cleanup_task(&self) -> tokio::task::JoinHandle<()> {
         let sessions = Arc::clone(&self.sessions);
         let event_store = Arc::clone(&self.event_store);
+        // Shared sessions may still be served by other replicas, so only the
+        // local copy is dropped; the shared record and events expire by TTL
+        let shared = self.backend.is_some();
         let session_timeout = self.session_timeout;
         let cleanup_interval = self.cleanup_interval;
+        let clock = self.clock.clone();
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(cleanup_interval);
             loop {
-                interval.tick().await;
-
                 // Clean up expired sessions
-                let cutoff = SystemTime::now()
+                let cutoff = clock
+                    .system_time()
                     .checked_sub(session_timeout)
                     .unwrap_or(UNIX_EPOCH);
 
@@ -315,6 +807,10 @@ impl SessionManager {
                     let mut sessions_guard = sessions.write().await;
                     for session_id in expired_sessions {
                         sessions_guard.remove(&session_id);
+                        if shared {
+                            debug!("Dropped local copy of idle session: {}", session_id);
+                            continue;
+                        }
                         if let Err(e) = event_store.remove_session(&session_id).await {
                             warn!(
                                 "Failed to clean up event store for session {}: {}",
@@ -330,18 +826,28 @@ impl SessionManager {
                 if let Err(e) = event_store.cleanup_expired().await {
                     warn!("Failed to clean up expired events: {}", e);
                 }
+
+                clock.sleep(cleanup_interval).await;
             }
         })
     }
 }
 
+fn session_not_found(session_id: &str) -> McpError {
+    McpError::Session {
+        message: format!("Session not found or expired: {}", session_id),
+    }
+}
+
 /// Streamable HTTP transport implementation
+#[cfg(feature = "axum")]
 pub struct StreamableHttpTransport {
     session_manager: Arc<SessionManager>,
     current_session_id: Option<String>,
     health: Arc<RwLock<TransportHealth>>,
 }
 
+#[cfg(feature = "axum")]
 impl StreamableHttpTransport {
     pub fn new(session_manager: Arc<SessionManager>) -> Self {
         Self {
@@ -419,7 +925,14 @@ impl StreamableHttpTransport {
         let session_id = headers.get("mcp-session-id").and_then(|h| h.to_str().ok());
 
         if let Some(session_id) = session_id {
-            self.session_manager.remove_session(session_id).await?;
+            if self
+                .session_manager
+                .terminate_session(session_id)
+                .await
+                .is_err()
+            {
+                return self.error_response(StatusCode::NOT_FOUND, -32000, "Session not found");
+            }
             Ok(Json(serde_json::json!({
                 "jsonrpc": "2.0",
                 "result": { "status": "terminated" }
@@ -682,6 +1195,7 @@ impl StreamableHttpTransport {
     }
 }
 
+#[cfg(feature = "axum")]
 #[async_trait]
 impl McpTransport for StreamableHttpTransport {
     async fn connect(&mut self) -> McpResult<()> {
@@ -784,4 +1298,130 @@ mod tests {
         manager.remove_session(&session_id).await.unwrap();
         assert!(manager.get_session(&session_id).await.is_none());
     }
+
+    #[tokio::test]
+    async fn test_session_ids_come_from_generator() {
+        struct Counter(std::sync::atomic::AtomicUsize);
+        impl crate::ids::IdGenerator for Counter {
+            fn generate(&self, kind: IdKind) -> String {
+                let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                format!("{:?}-{}", kind, n).to_lowercase()
+            }
+        }
+
+        let event_store = Arc::new(InMemoryEventStore::new(100, Duration::from_secs(3600)));
+        let manager = SessionManager::new(
+            event_store,
+            Duration::from_secs(300),
+            Duration::from_secs(60),
+        )
+        .with_node_id("node-a")
+        .with_ids(Ids::new(Counter(Default::default())));
+
+        assert_eq!(manager.create_session().await.unwrap(), "node-a.session-0");
+        assert_eq!(manager.create_session().await.unwrap(), "node-a.session-1");
+    }
+
+    #[tokio::test]
+    async fn test_session_resumption() {
+        use futures_util::StreamExt;
+
+        let event_store = Arc::new(InMemoryEventStore::new(2, Duration::from_secs(3600)));
+        let manager = SessionManager::new(
+            event_store,
+            Duration::from_secs(300),
+            Duration::from_secs(60),
+        );
+        let session_id = manager.create_session().await.unwrap();
+
+        manager
+            .update_session_state(&session_id, |state| {
+                state.protocol_version = Some("2025-03-26".to_string());
+                state.subscriptions.insert("file:///log".to_string());
+            })
+            .await
+            .unwrap();
+
+        let mut live = Box::pin(manager.subscribe_to_session(&session_id).await);
+        let events: Vec<_> = (0..3)
+            .map(|i| {
+                McpEvent::new(
+                    session_id.clone(),
+                    "message".to_string(),
+                    serde_json::json!({ "n": i }),
+                )
+            })
+            .collect();
+        for event in &events {
+            manager.store_event(event).await.unwrap();
+        }
+        assert_eq!(live.next().await.unwrap().id, events[0].id);
+
+        let state = manager.resume_session(&session_id).await.unwrap();
+        assert_eq!(state.protocol_version.as_deref(), Some("2025-03-26"));
+        assert!(state.subscriptions.contains("file:///log"));
+
+        // Only the bounded buffer survives for replay
+        let missed = manager
+            .get_events_since(&session_id, Some(&events[1].id))
+            .await
+            .unwrap();
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].id, events[2].id);
+
+        assert!(matches!(
+            manager.resume_session("unknown").await,
+            Err(McpError::Session { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expired_session_is_not_resumed() {
+        let event_store = Arc::new(InMemoryEventStore::new(10, Duration::from_secs(3600)));
+        let clock = Clock::manual();
+        let manager = SessionManager::new(event_store, Duration::from_secs(60), Duration::MAX)
+            .with_clock(clock.clone());
+        let session_id = manager.create_session().await.unwrap();
+        assert!(manager.resume_session(&session_id).await.is_ok());
+
+        clock.advance(Duration::from_secs(61));
+        assert!(manager.resume_session(&session_id).await.is_err());
+        assert!(manager.get_session(&session_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_terminate_session() {
+        use futures_util::StreamExt;
+
+        let event_store = Arc::new(InMemoryEventStore::new(10, Duration::from_secs(3600)));
+        let manager = SessionManager::new(
+            event_store,
+            Duration::from_secs(300),
+            Duration::from_secs(60),
+        );
+        let session_id = manager.create_session().await.unwrap();
+        manager
+            .update_session_state(&session_id, |state| {
+                state.subscriptions.insert("file:///log".to_string());
+                state
+                    .pending_requests
+                    .insert("1".to_string(), "tools/call".to_string());
+            })
+            .await
+            .unwrap();
+
+        let session = manager.get_session(&session_id).await.unwrap();
+        let closed = tokio::spawn(session.closed());
+        let mut live = Box::pin(manager.subscribe_to_session(&session_id).await);
+
+        let state = manager.terminate_session(&session_id).await.unwrap();
+        assert_eq!(state.subscriptions.len(), 1);
+        assert_eq!(state.pending_requests.len(), 1);
+
+        assert_eq!(live.next().await.unwrap().event_type, SESSION_CLOSED_EVENT);
+        closed.await.unwrap();
+        assert!(session.is_closed());
+        assert!(manager.get_session(&session_id).await.is_none());
+        assert!(manager.terminate_session(&session_id).await.is_err());
+    }
 }
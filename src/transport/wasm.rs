@@ -0,0 +1,385 @@
+//! Browser transport for wasm32 clients
+//!
+//! [`WasmTransport`] speaks StreamableHTTP to an axum-mcp server from a
+//! browser: requests are POSTed with `fetch` and answered in the response
+//! body, either as JSON or as an SSE stream that ends with the response.
+//! Once the server assigns a session, an `EventSource` on the session stream
+//! delivers server-initiated notifications and requests, read with
+//! [`WasmTransport::next_server_message`] and answered with
+//! [`WasmTransport::respond`].
+//!
+//! Browsers only let scripts read the `Mcp-Session-Id` response header if the
+//! server lists it in `Access-Control-Expose-Headers`. `EventSource` cannot
+//! send custom headers, so the session stream is opened with the
+//! `session_id` query parameter and without the configured authentication.
+
+use async_trait::async_trait;
+use base64::Engine;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{EventSource, Headers, MessageEvent, Request, RequestInit, Response};
+
+use super::{McpTransport, SseAuth, TransportHealth};
+use crate::protocol::{JsonRpcRequest, JsonRpcResponse};
+use crate::{McpError, McpResult};
+
+/// Header carrying the StreamableHTTP session identifier
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Session stream events that carry server-initiated messages
+const SERVER_MESSAGE_EVENTS: [&str; 2] = ["notification", "request"];
+
+/// Open `EventSource` on a session stream and its listeners
+struct SessionStream {
+    source: EventSource,
+    _listeners: Vec<Closure<dyn FnMut(MessageEvent)>>,
+}
+
+impl Drop for SessionStream {
+    fn drop(&mut self) {
+        self.source.close();
+    }
+}
+
+/// fetch/EventSource transport for MCP clients running in a browser
+pub struct WasmTransport {
+    /// MCP endpoint URL
+    url: String,
+
+    /// HTTP headers sent with every request
+    headers: HashMap<String, String>,
+
+    /// Authentication configuration
+    auth: Option<SseAuth>,
+
+    /// Session assigned by the server
+    session_id: Option<String>,
+
+    /// Responses read from POST bodies, in arrival order
+    responses: VecDeque<JsonRpcResponse>,
+
+    /// Server-initiated messages from POST bodies and the session stream
+    server_messages: (
+        mpsc::UnboundedSender<JsonRpcRequest>,
+        mpsc::UnboundedReceiver<JsonRpcRequest>,
+    ),
+
+    /// Session stream, once a session is assigned
+    stream: Option<SessionStream>,
+
+    /// Transport health tracking
+    health: TransportHealth,
+
+    /// Whether the transport is connected
+    connected: bool,
+}
+
+impl std::fmt::Debug for WasmTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmTransport")
+            .field("url", &self.url)
+            .field("session_id", &self.session_id)
+            .field("connected", &self.connected)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmTransport {
+    /// Create a transport for the MCP endpoint at `url`
+    pub fn new(url: impl Into<String>) -> McpResult<Self> {
+        let url = url.into();
+        if let Err(e) = url::Url::parse(&url) {
+            return Err(McpError::Configuration {
+                message: format!("Invalid MCP endpoint URL: {}", e),
+            });
+        }
+
+        Ok(Self {
+            url,
+            headers: HashMap::new(),
+            auth: None,
+            session_id: None,
+            responses: VecDeque::new(),
+            server_messages: mpsc::unbounded_channel(),
+            stream: None,
+            health: TransportHealth::unhealthy("Not connected"),
+            connected: false,
+        })
+    }
+
+    /// Send `name: value` with every request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Authenticate requests with `auth`
+    pub fn with_auth(mut self, auth: SseAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Session assigned by the server, if any
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Wait for the next notification or request sent by the server
+    ///
+    /// Returns `None` once the transport is closed.
+    pub async fn next_server_message(&mut self) -> Option<JsonRpcRequest> {
+        if !self.connected {
+            return None;
+        }
+        self.server_messages.1.recv().await
+    }
+
+    /// Answer a request the server sent to this client
+    pub async fn respond(&mut self, response: JsonRpcResponse) -> McpResult<()> {
+        self.post(serde_json::to_string(&response)?).await.map(drop)
+    }
+
+    /// POST a JSON-RPC message, returning the response to read
+    async fn post(&mut self, body: String) -> McpResult<Response> {
+        if !self.connected {
+            return Err(McpError::Transport {
+                message: "Transport not connected".to_string(),
+            });
+        }
+
+        let headers = Headers::new().map_err(js_error)?;
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(js_error)?;
+        headers
+            .set("Accept", "application/json, text/event-stream")
+            .map_err(js_error)?;
+        for (name, value) in &self.headers {
+            headers.set(name, value).map_err(js_error)?;
+        }
+        match &self.auth {
+            Some(SseAuth::Bearer { token }) => headers
+                .set("Authorization", &format!("Bearer {}", token))
+                .map_err(js_error)?,
+            Some(SseAuth::Basic { username, password }) => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, password));
+                headers
+                    .set("Authorization", &format!("Basic {}", credentials))
+                    .map_err(js_error)?
+            }
+            Some(SseAuth::ApiKey { header, key }) => headers.set(header, key).map_err(js_error)?,
+            None => {}
+        }
+        if let Some(session_id) = &self.session_id {
+            headers.set(SESSION_HEADER, session_id).map_err(js_error)?;
+        }
+
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_headers(&headers);
+        init.set_body(&JsValue::from_str(&body));
+        let request = Request::new_with_str_and_init(&self.url, &init).map_err(js_error)?;
+        let window = web_sys::window().ok_or_else(|| McpError::Transport {
+            message: "WasmTransport requires a browser window".to_string(),
+        })?;
+
+        let response: Response = JsFuture::from(window.fetch_with_request(&request))
+            .await
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        if !response.ok() {
+            return Err(McpError::Network {
+                message: format!("HTTP request failed with status: {}", response.status()),
+            });
+        }
+
+        if let Some(session_id) = response.headers().get(SESSION_HEADER).map_err(js_error)? {
+            if self.session_id.as_deref() != Some(&session_id) {
+                self.open_stream(&session_id)?;
+                self.session_id = Some(session_id);
+            }
+        }
+        Ok(response)
+    }
+
+    /// Read the JSON-RPC messages in a POST response body
+    async fn read_body(&mut self, response: Response) -> McpResult<()> {
+        let is_stream = response
+            .headers()
+            .get("Content-Type")
+            .map_err(js_error)?
+            .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+        let text = JsFuture::from(response.text().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .as_string()
+            .unwrap_or_default();
+
+        if is_stream {
+            for data in sse_data(&text) {
+                self.accept(serde_json::from_str(&data)?);
+            }
+        } else if !text.trim().is_empty() {
+            match serde_json::from_str(&text)? {
+                Value::Array(messages) => messages.into_iter().for_each(|m| self.accept(m)),
+                message => self.accept(message),
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue a message as a response or as a server-initiated message
+    fn accept(&mut self, message: Value) {
+        if message.get("method").is_some() {
+            if let Ok(request) = serde_json::from_value(message) {
+                let _ = self.server_messages.0.send(request);
+            }
+        } else if let Ok(response) = serde_json::from_value(message) {
+            self.responses.push_back(response);
+        }
+    }
+
+    /// Subscribe to the session stream of `session_id`
+    fn open_stream(&mut self, session_id: &str) -> McpResult<()> {
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let url = format!(
+            "{}{}session_id={}",
+            self.url,
+            separator,
+            js_sys::encode_uri_component(session_id)
+        );
+        let source = EventSource::new(&url).map_err(js_error)?;
+
+        let mut listeners = Vec::new();
+        for event in SERVER_MESSAGE_EVENTS {
+            let sender = self.server_messages.0.clone();
+            let listener = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let message = event
+                    .data()
+                    .as_string()
+                    .and_then(|data| serde_json::from_str(&data).ok());
+                if let Some(message) = message {
+                    let _ = sender.send(message);
+                }
+            });
+            source
+                .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+                .map_err(js_error)?;
+            listeners.push(listener);
+        }
+
+        self.stream = Some(SessionStream {
+            source,
+            _listeners: listeners,
+        });
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl McpTransport for WasmTransport {
+    async fn connect(&mut self) -> McpResult<()> {
+        self.connected = true;
+        self.health.mark_success(None);
+        self.health
+            .metadata
+            .insert("url".to_string(), Value::String(self.url.clone()));
+        Ok(())
+    }
+
+    async fn send(&mut self, message: JsonRpcRequest) -> McpResult<()> {
+        let started = js_sys::Date::now();
+        let result = match self.post(serde_json::to_string(&message)?).await {
+            Ok(response) => self.read_body(response).await,
+            Err(e) => Err(e),
+        };
+
+        match &result {
+            Ok(()) => {
+                let latency = Duration::from_secs_f64((js_sys::Date::now() - started) / 1000.0);
+                self.health.mark_success(Some(latency));
+            }
+            Err(e) => self.health.mark_failure(e.to_string()),
+        }
+        result
+    }
+
+    async fn receive(&mut self) -> McpResult<JsonRpcResponse> {
+        // Responses arrive in the body of the POST that sent the request
+        self.responses
+            .pop_front()
+            .ok_or_else(|| McpError::Transport {
+                message: "No response received".to_string(),
+            })
+    }
+
+    async fn send_and_receive(
+        &mut self,
+        request: JsonRpcRequest,
+        timeout_duration: Duration,
+    ) -> McpResult<JsonRpcResponse> {
+        let id = request.id.clone();
+        super::with_timeout(timeout_duration, self.send(request))
+            .await
+            .ok_or(McpError::ServerTimeout {
+                timeout: timeout_duration,
+            })??;
+
+        match self.responses.iter().position(|r| r.id == id) {
+            Some(index) => Ok(self.responses.remove(index).expect("index in bounds")),
+            None => Err(McpError::Protocol {
+                message: "Server did not answer the request".to_string(),
+            }),
+        }
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn health(&self) -> TransportHealth {
+        self.health.clone()
+    }
+
+    async fn close(&mut self) -> McpResult<()> {
+        self.connected = false;
+        self.stream = None;
+        self.session_id = None;
+        self.responses.clear();
+        self.server_messages = mpsc::unbounded_channel();
+        self.health.connected = false;
+        self.health.metadata.insert(
+            "disconnected_at".to_string(),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+        );
+        Ok(())
+    }
+}
+
+/// Data of each event in an SSE body
+fn sse_data(body: &str) -> Vec<String> {
+    body.split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            (!data.is_empty()).then(|| data.join("\n"))
+        })
+        .collect()
+}
+
+fn js_error(error: JsValue) -> McpError {
+    McpError::Network {
+        message: format!("Browser request failed: {:?}", error),
+    }
+}